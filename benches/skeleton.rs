@@ -0,0 +1,89 @@
+//! Skeleton generation over generated trees of 1k, 10k and 50k entries.
+//!
+//! Run with `cargo bench --bench skeleton`. Criterion prints the change from
+//! the previous run; keep a named baseline with `-- --save-baseline before`
+//! and compare against it with `-- --baseline before`. The fixture trees are
+//! built once under the system temp dir and reused by later runs.
+//!
+//! Rendering is quadratic in the number of entries, so the 50k cases take
+//! minutes; `-- '/(1000|10000)$'` skips them.
+
+use agent_rusty::skeleton;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+use std::path::{Path, PathBuf};
+
+/// Entries (files plus directories) in each fixture tree
+const SIZES: [usize; 3] = [1_000, 10_000, 50_000];
+
+/// A tree of about `entries` entries, ten files per directory and two levels
+/// of directories, like a mid-sized workspace
+fn fixture_tree(entries: usize) -> PathBuf {
+    let base = std::env::temp_dir().join("agent-rusty-bench");
+    let root = base.join(format!("tree-{}", entries));
+    // The marker lives outside the tree so the walk doesn't count it
+    let complete = base.join(format!("tree-{}.complete", entries));
+    if complete.exists() {
+        return root;
+    }
+    let _ = std::fs::remove_dir_all(&root);
+
+    let mut made = 0;
+    for file in 0.. {
+        if made >= entries {
+            break;
+        }
+        let dir = root
+            .join(format!("crate{}", file / 100))
+            .join(format!("module{}", (file / 10) % 10));
+        if !dir.exists() {
+            made += usize::from(!dir.parent().unwrap().exists()) + 1;
+            std::fs::create_dir_all(&dir).unwrap();
+        }
+        std::fs::write(dir.join(format!("file{}.rs", file % 10)), "").unwrap();
+        made += 1;
+    }
+    std::fs::write(&complete, "").unwrap();
+    root
+}
+
+fn root_str(root: &Path) -> &str {
+    root.to_str().expect("temp dir is valid UTF-8")
+}
+
+fn generate(c: &mut Criterion) {
+    let mut group = c.benchmark_group("skeleton/generate");
+    // Larger trees take seconds per run; ten samples is criterion's minimum
+    group.sample_size(10);
+    for entries in SIZES {
+        let root = fixture_tree(entries);
+        group.throughput(Throughput::Elements(entries as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(entries), &root, |b, root| {
+            b.iter(|| skeleton::generate_skeleton_blocking(black_box(root_str(root))).unwrap())
+        });
+    }
+    group.finish();
+}
+
+fn render(c: &mut Criterion) {
+    let mut group = c.benchmark_group("skeleton/render");
+    group.sample_size(10);
+    for entries in SIZES {
+        let listing = skeleton::walk(root_str(&fixture_tree(entries))).unwrap();
+        println!(
+            "fixture tree-{}: {} entries walked",
+            entries,
+            listing.entries.len()
+        );
+        group.throughput(Throughput::Elements(listing.entries.len() as u64));
+        group.bench_with_input(
+            BenchmarkId::from_parameter(entries),
+            &listing,
+            |b, listing| b.iter(|| skeleton::render_tree(black_box(listing))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group!(benches, generate, render);
+criterion_main!(benches);
@@ -0,0 +1,36 @@
+//! `StateInferenceEngine::analyze` over small, medium and large pane captures.
+//!
+//! Run with `cargo bench --bench status`. The captures live in
+//! `benches/fixtures/captures`; criterion prints the change from the previous
+//! run, and `-- --save-baseline <name>` keeps a baseline to compare against.
+
+use agent_rusty::tmux::StateInferenceEngine;
+use criterion::{BenchmarkId, Criterion, Throughput, criterion_group, criterion_main};
+use std::hint::black_box;
+
+const CAPTURES: [(&str, &str); 3] = [
+    ("small", include_str!("fixtures/captures/small.txt")),
+    ("medium", include_str!("fixtures/captures/medium.txt")),
+    ("large", include_str!("fixtures/captures/large.txt")),
+];
+
+fn analyze(c: &mut Criterion) {
+    let mut group = c.benchmark_group("status/analyze");
+    for (name, capture) in CAPTURES {
+        println!(
+            "fixture {}: {} lines, {} bytes, detected {:?}",
+            name,
+            capture.lines().count(),
+            capture.len(),
+            StateInferenceEngine::analyze(capture)
+        );
+        group.throughput(Throughput::Bytes(capture.len() as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(name), capture, |b, capture| {
+            b.iter(|| StateInferenceEngine::analyze(black_box(capture)))
+        });
+    }
+    group.finish();
+}
+
+criterion_group!(benches, analyze);
+criterion_main!(benches);
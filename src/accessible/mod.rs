@@ -0,0 +1,607 @@
+//! Plain-line frontend for screen readers.
+//!
+//! Instead of drawing the dashboard, [`Narrator`] compares the app with what
+//! it last said and prints what changed as lines of words: where the
+//! selection moved, status changes as they happen, dialogs opening, text being
+//! typed and messages. The app's state and key handling are the ones the TUI
+//! uses; only the presentation differs.
+
+use std::collections::BTreeMap;
+use std::io::Write;
+
+use crate::app::{App, InputMode, NoteKind, help_lines};
+use crate::session_list::ListRow;
+use crate::text;
+use crate::tmux::AgentStatus;
+
+/// Most new pane lines read out at once
+const PANE_LINES: usize = 10;
+
+/// A status as it is read out
+pub fn status_words(status: AgentStatus) -> &'static str {
+    match status {
+        AgentStatus::Busy => "busy",
+        AgentStatus::Idle => "idle",
+        AgentStatus::WaitingForInput => "waiting for input",
+        AgentStatus::Error => "showing an error",
+        AgentStatus::Unknown => "in an unknown state",
+    }
+}
+
+/// Print `lines` one per line; raw mode needs the carriage returns
+pub fn write_lines(out: &mut impl Write, lines: &[String]) -> std::io::Result<()> {
+    for line in lines {
+        write!(out, "{}\r\n", line)?;
+    }
+    out.flush()
+}
+
+/// What the narrator last said about the app
+#[derive(Debug)]
+pub struct Narrator {
+    greeted: bool,
+    /// Whether the first poll has been summed up
+    listed: bool,
+    /// Names and statuses by session id, as last announced
+    statuses: BTreeMap<String, (String, AgentStatus)>,
+    selection: Option<String>,
+    mode: InputMode,
+    /// Highlighted entry of the open menu
+    menu_entry: Option<String>,
+    input: String,
+    message: Option<String>,
+    /// Pane lines already read out
+    pane: Vec<String>,
+}
+
+impl Default for Narrator {
+    fn default() -> Self {
+        Self {
+            greeted: false,
+            listed: false,
+            statuses: BTreeMap::new(),
+            selection: None,
+            mode: InputMode::Normal,
+            menu_entry: None,
+            input: String::new(),
+            message: None,
+            pane: Vec::new(),
+        }
+    }
+}
+
+impl Narrator {
+    /// Lines telling what changed in `app` since the last call
+    pub fn narrate(&mut self, app: &App, now: u64) -> Vec<String> {
+        let mut lines = Vec::new();
+        if !self.greeted {
+            self.greeted = true;
+            lines.push(
+                "agent-rusty, accessible mode. j and k move between sessions, Enter attaches, \
+                 o previews, q quits."
+                    .to_string(),
+            );
+        }
+        self.narrate_sessions(app, &mut lines);
+        self.narrate_mode(app, &mut lines);
+
+        let selection = describe_selection(app, now);
+        if self.mode == InputMode::Normal && selection != self.selection {
+            lines.extend(selection.clone());
+        }
+        self.selection = selection;
+
+        self.narrate_pane(app, &mut lines);
+        let message = app.message().map(str::to_string);
+        if message != self.message {
+            lines.extend(message.clone());
+            self.message = message;
+        }
+        lines
+    }
+
+    /// Sessions appearing, closing and changing status
+    fn narrate_sessions(&mut self, app: &App, lines: &mut Vec<String>) {
+        if app.last_update.is_none() {
+            return;
+        }
+        let current: BTreeMap<String, (String, AgentStatus)> = app
+            .sessions
+            .iter()
+            .map(|s| (s.id.clone(), (s.name.clone(), s.status)))
+            .collect();
+        if !self.listed {
+            self.listed = true;
+            let attention = app
+                .sessions
+                .iter()
+                .filter(|s| matches!(s.status, AgentStatus::WaitingForInput | AgentStatus::Error))
+                .count();
+            lines.push(match current.len() {
+                0 => "No sessions.".to_string(),
+                1 => format!("1 session, {} needing attention.", attention),
+                n => format!("{} sessions, {} needing attention.", n, attention),
+            });
+        } else {
+            for (id, (name, status)) in &current {
+                match self.statuses.get(id) {
+                    None => lines.push(format!("{} started, {}.", name, status_words(*status))),
+                    Some((_, before)) if before != status => {
+                        lines.push(format!("{} is now {}.", name, status_words(*status)));
+                    }
+                    Some(_) => {}
+                }
+            }
+            for (id, (name, _)) in &self.statuses {
+                if !current.contains_key(id) {
+                    lines.push(format!("{} closed.", name));
+                }
+            }
+        }
+        self.statuses = current;
+    }
+
+    /// Dialogs opening and closing, their highlighted entries and typed text
+    fn narrate_mode(&mut self, app: &App, lines: &mut Vec<String>) {
+        if app.input_mode != self.mode {
+            self.mode = app.input_mode;
+            self.input = app.input_buffer.to_string();
+            self.menu_entry = None;
+            self.pane.clear();
+            lines.push(mode_prompt(app));
+            if app.input_mode == InputMode::Help {
+                lines.extend(
+                    help_lines()
+                        .into_iter()
+                        .map(|(_, line)| line.trim().to_string()),
+                );
+            }
+        } else if app.input_buffer.as_str() != self.input && takes_text(app.input_mode) {
+            self.input = app.input_buffer.to_string();
+            lines.push(if self.input.is_empty() {
+                "Text empty.".to_string()
+            } else {
+                format!("Text: {}", self.input)
+            });
+        }
+        let entry = menu_entry(app);
+        if entry != self.menu_entry {
+            lines.extend(entry.clone());
+            self.menu_entry = entry;
+        }
+    }
+
+    /// Pane lines the preview or dashboard shows for the first time
+    fn narrate_pane(&mut self, app: &App, lines: &mut Vec<String>) {
+        if !matches!(app.input_mode, InputMode::Preview | InputMode::Dashboard) {
+            return;
+        }
+        let Some(preview) = &app.preview else {
+            return;
+        };
+        let pane: Vec<String> = preview
+            .viewport
+            .lines()
+            .iter()
+            .map(|line| text::strip_ansi(line).trim().to_string())
+            .filter(|line| !line.is_empty())
+            .collect();
+        let new: Vec<&String> = pane
+            .iter()
+            .filter(|line| !self.pane.contains(line))
+            .collect();
+        let skip = new.len().saturating_sub(PANE_LINES);
+        lines.extend(new.into_iter().skip(skip).cloned());
+        self.pane = pane;
+    }
+}
+
+/// `Session 3 of 12: payments-fix, waiting for input for 4 minutes`
+pub fn describe_selection(app: &App, now: u64) -> Option<String> {
+    let selected = app.list_state.selected()?;
+    match app.rows.get(selected)? {
+        ListRow::Window(..) => {
+            let session = app.selected_session()?;
+            let window = app.selected_window()?;
+            Some(format!(
+                "Window {} of {}: {}, running {}{}",
+                window.index,
+                session.name,
+                window.name,
+                window.command,
+                if window.active { ", shown" } else { "" }
+            ))
+        }
+        ListRow::Session(index) => {
+            let session = app.sessions.get(*index)?;
+            let sessions = app
+                .rows
+                .iter()
+                .filter(|row| matches!(row, ListRow::Session(_)));
+            let position = app.rows[..=selected]
+                .iter()
+                .filter(|row| matches!(row, ListRow::Session(_)))
+                .count();
+            let mut line = format!(
+                "Session {} of {}: {}, {}",
+                position,
+                sessions.count(),
+                session.name,
+                status_words(session.status)
+            );
+            if let Some(change) = app.status_timeline.changes(&session.id).next() {
+                line.push_str(" for ");
+                line.push_str(&duration_words(now.saturating_sub(change.at)));
+            }
+            if app.marked.contains(&session.id) {
+                line.push_str(", marked");
+            }
+            Some(line)
+        }
+        ListRow::Header {
+            group,
+            count,
+            collapsed,
+        } => Some(format!(
+            "Group {}, {} {}{}",
+            group.label(),
+            count,
+            if *count == 1 { "session" } else { "sessions" },
+            if *collapsed { ", collapsed" } else { "" }
+        )),
+        ListRow::Server(_) => None,
+    }
+}
+
+/// `45 seconds`, `1 minute`, `4 minutes`, `2 hours`, `3 days`
+pub fn duration_words(secs: u64) -> String {
+    let (count, unit) = match secs {
+        0..60 => (secs, "second"),
+        60..3600 => (secs / 60, "minute"),
+        3600..86400 => (secs / 3600, "hour"),
+        _ => (secs / 86400, "day"),
+    };
+    format!("{} {}{}", count, unit, if count == 1 { "" } else { "s" })
+}
+
+/// Whether `mode` is typing into the input buffer
+fn takes_text(mode: InputMode) -> bool {
+    matches!(
+        mode,
+        InputMode::Creating
+            | InputMode::CreatingDir
+            | InputMode::CreatingCommand
+            | InputMode::TemplateDir
+            | InputMode::EditingNote
+            | InputMode::EditingTags
+            | InputMode::Broadcasting
+            | InputMode::SchedulingTime
+            | InputMode::SchedulingPrompt
+            | InputMode::AddingRule
+            | InputMode::CreatingWorktree
+            | InputMode::DashboardPrompt
+            | InputMode::Filtering
+            | InputMode::Renaming
+    )
+}
+
+/// Highlighted entry of the menu `app` shows, if any
+fn menu_entry(app: &App) -> Option<String> {
+    match app.input_mode {
+        InputMode::ChoosingLauncher => app
+            .launchers
+            .get(app.launcher_selected)
+            .map(|launcher| launcher.label()),
+        InputMode::ChoosingCommand => app
+            .custom_commands
+            .get(app.command_selected)
+            .map(|command| command.name.clone()),
+        InputMode::ChoosingTag => match app.tag_selected.checked_sub(1) {
+            Some(index) => app
+                .listed_tags()
+                .keys()
+                .nth(index)
+                .map(|tag| format!("Tag {}", tag)),
+            None => Some("All sessions".to_string()),
+        },
+        InputMode::ChoosingTemplate => app
+            .templates
+            .get(app.template_selected)
+            .map(|template| template.name.clone()),
+        InputMode::PickingDir => {
+            let (_, picker) = app.dir_picker.as_ref()?;
+            Some(match picker.selected_entry() {
+                Some(entry) => match &entry.error {
+                    Some(error) => format!("{}, can't be opened: {}", entry.name, error),
+                    None => entry.name.clone(),
+                },
+                None => format!("Use {}", picker.dir().display()),
+            })
+        }
+        InputMode::ChoosingClient => {
+            let (_, clients) = app.clients.as_ref()?;
+            clients.get(app.client_selected).map(|client| {
+                format!(
+                    "{}, {} by {}, {}",
+                    client.name, client.width, client.height, client.termname
+                )
+            })
+        }
+        InputMode::ChoosingWindow => {
+            let (_, windows) = app.window_choice.as_ref()?;
+            windows
+                .get(app.window_selected)
+                .map(|window| format!("Window {}: {}", window.index, window.name))
+        }
+        _ => None,
+    }
+}
+
+/// What a mode is for and the keys it takes
+fn mode_prompt(app: &App) -> String {
+    let session = app
+        .selected_session()
+        .map_or("the session", |s| s.name.as_str());
+    match app.input_mode {
+        InputMode::Normal => "Session list.".to_string(),
+        InputMode::Creating => {
+            "New session. Type its name, then Enter; Escape cancels.".to_string()
+        }
+        InputMode::ChoosingLauncher => {
+            "Choose what the session runs. j and k move, Enter chooses, Escape cancels.".to_string()
+        }
+        InputMode::CreatingCommand => {
+            "Type the command the session runs, blank for a shell, then Enter; Escape goes back."
+                .to_string()
+        }
+        InputMode::CreatingDir => {
+            "Type the session's directory, then Enter; Escape goes back.".to_string()
+        }
+        InputMode::Confirming
+            if app
+                .selected_session()
+                .is_some_and(|s| app.state.note(&s.name).is_some()) =>
+        {
+            format!(
+                "Delete {}? y deletes it and keeps its note, x deletes both, n keeps it.",
+                session
+            )
+        }
+        InputMode::Confirming => format!("Delete {}? y deletes, n keeps it.", session),
+        InputMode::Preview => format!(
+            "Preview of {}. j and k scroll, Enter attaches, Escape goes back.",
+            session
+        ),
+        InputMode::EditingNote if app.note_kind == NoteKind::Description => {
+            format!(
+                "Editing the description of {}. Enter saves, Escape cancels.",
+                session
+            )
+        }
+        InputMode::EditingNote => format!(
+            "Editing the note of {}. Enter starts a new line, Control S or Escape saves.",
+            session
+        ),
+        InputMode::EditingTags => format!(
+            "Tags of {}. Type them separated by spaces, then Enter; Escape cancels.",
+            session
+        ),
+        InputMode::ChoosingTag => {
+            "Filter by tag. j and k move, Enter filters, Escape cancels.".to_string()
+        }
+        InputMode::Broadcasting => format!(
+            "Broadcast to {} marked sessions. Type the prompt, then Enter; Escape cancels.",
+            app.marked.len()
+        ),
+        InputMode::ConfirmingBroadcast => "Send the broadcast? y sends, n cancels.".to_string(),
+        InputMode::BroadcastResults => "Broadcast done. Any key continues.".to_string(),
+        InputMode::ConfirmingRestart => {
+            format!("Restart the agent in {}? y restarts, n cancels.", session)
+        }
+        InputMode::ConfirmingQuit => format!(
+            "Agents still mid-task: {}. They keep running after quitting. q or y quits, Escape stays.",
+            app.working_sessions().len()
+        ),
+        InputMode::ErrorDetails => "Error details. y copies them, Escape closes.".to_string(),
+        InputMode::ConfirmingRestore => {
+            "Sessions are missing since the last snapshot. y recreates them, n skips.".to_string()
+        }
+        InputMode::SchedulingTime => format!(
+            "Schedule a prompt for {}. Type when, like 14:30 or in 20m, then Enter.",
+            session
+        ),
+        InputMode::SchedulingPrompt => "Type the prompt to schedule, then Enter.".to_string(),
+        InputMode::Schedules => "Scheduled prompts. Escape closes.".to_string(),
+        InputMode::ConfirmingCleanup => {
+            "Stale sessions found. y archives and closes them, n keeps them.".to_string()
+        }
+        InputMode::AddingRule => format!(
+            "New rule for {}. Type it, then Enter; Escape cancels.",
+            session
+        ),
+        InputMode::Rules => "Rules. Escape closes.".to_string(),
+        InputMode::Usage => "Token usage. Escape closes.".to_string(),
+        InputMode::CreatingWorktree => {
+            "New worktree. Type its branch, then Enter; Escape cancels.".to_string()
+        }
+        InputMode::ConfirmingWorktreeRemoval => {
+            "Remove the deleted session's worktree too? y removes it, n keeps it.".to_string()
+        }
+        InputMode::StartupDigest => {
+            "Sessions needing attention at startup. Any key continues.".to_string()
+        }
+        InputMode::ChoosingCommand => {
+            "Commands. j and k move, Enter runs, Escape cancels.".to_string()
+        }
+        InputMode::ChoosingTemplate => {
+            "Templates. j and k move, Enter chooses, Escape cancels.".to_string()
+        }
+        InputMode::TemplateDir => {
+            "Type the project's directory, then Enter creates its sessions; Escape goes back."
+                .to_string()
+        }
+        InputMode::ChoosingWindow => {
+            "Choose a window. j and k move, Enter attaches, Escape cancels.".to_string()
+        }
+        InputMode::ChoosingClient => {
+            "Choose a terminal to detach. j and k move, Enter detaches, Escape cancels."
+                .to_string()
+        }
+        InputMode::Dashboard => format!(
+            "Dashboard of {}. Left and right change session, p sends a prompt, i interrupts, \
+             Escape goes back.",
+            session
+        ),
+        InputMode::DashboardPrompt => {
+            format!(
+                "Prompt for {}. Type it, then Enter; Escape cancels.",
+                session
+            )
+        }
+        InputMode::Logs => "Log. v changes the level, y copies, Escape closes.".to_string(),
+        InputMode::Help => "Keys. Escape closes.".to_string(),
+        InputMode::Renaming => format!(
+            "Rename {}. Type the new name, then Enter; Escape cancels.",
+            session
+        ),
+        InputMode::Filtering => {
+            "Filter sessions. Type part of a name; Enter keeps the filter, Escape clears it."
+                .to_string()
+        }
+        InputMode::PickingDir => "Choose a directory. j and k move, l opens, h goes up, \
+                                  slash filters, Enter on the first entry uses it, Escape goes back."
+            .to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::actions::Action;
+    use crate::config::Config;
+    use crate::notification::Level;
+    use crate::state::PersistedState;
+    use crate::tmux::TmuxSession;
+    use crossterm::event::{KeyCode, KeyEvent};
+
+    fn session(name: &str, status: AgentStatus) -> TmuxSession {
+        let mut s = TmuxSession::new(format!("${}", name), name.to_string());
+        s.status = status;
+        s
+    }
+
+    fn update(app: &mut App, sessions: Vec<TmuxSession>) {
+        app.handle_action(Action::SessionsUpdated(sessions))
+            .unwrap();
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_action(Action::KeyPress(KeyEvent::from(code)))
+            .unwrap();
+    }
+
+    #[test]
+    fn test_narrates_the_list() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut narrator = Narrator::default();
+        assert_eq!(narrator.narrate(&app, 0).len(), 1);
+
+        update(
+            &mut app,
+            vec![
+                session("api", AgentStatus::WaitingForInput),
+                session("web", AgentStatus::Busy),
+            ],
+        );
+        assert_eq!(
+            narrator.narrate(&app, 0),
+            [
+                "2 sessions, 1 needing attention.",
+                "Session 1 of 2: api, waiting for input"
+            ]
+        );
+        assert!(narrator.narrate(&app, 0).is_empty());
+
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(narrator.narrate(&app, 0), ["Session 2 of 2: web, busy"]);
+
+        // Status changes are announced, and timed from then on
+        app.handle_action(Action::StatusChanged(vec![crate::poller::Transition {
+            session_id: "$web".to_string(),
+            session: "web".to_string(),
+            previous: AgentStatus::Busy,
+            status: AgentStatus::Idle,
+        }]))
+        .unwrap();
+        update(
+            &mut app,
+            vec![
+                session("api", AgentStatus::WaitingForInput),
+                session("web", AgentStatus::Idle),
+                session("ops", AgentStatus::Busy),
+            ],
+        );
+        let lines = narrator.narrate(&app, text::unix_now() + 240);
+        assert!(
+            lines.contains(&"web is now idle.".to_string()),
+            "{:?}",
+            lines
+        );
+        assert!(
+            lines.contains(&"ops started, busy.".to_string()),
+            "{:?}",
+            lines
+        );
+        let selection = describe_selection(&app, text::unix_now() + 240).unwrap();
+        assert!(
+            selection.ends_with("web, idle for 4 minutes"),
+            "{}",
+            selection
+        );
+
+        update(&mut app, vec![session("api", AgentStatus::WaitingForInput)]);
+        let lines = narrator.narrate(&app, 0);
+        assert!(lines.contains(&"web closed.".to_string()), "{:?}", lines);
+        assert!(lines.contains(&"ops closed.".to_string()), "{:?}", lines);
+    }
+
+    #[test]
+    fn test_narrates_dialogs_and_typing() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        update(&mut app, vec![session("api", AgentStatus::Idle)]);
+        let mut narrator = Narrator::default();
+        narrator.narrate(&app, 0);
+
+        press(&mut app, KeyCode::Char('n'));
+        assert_eq!(
+            narrator.narrate(&app, 0),
+            ["New session. Type its name, then Enter; Escape cancels."]
+        );
+        press(&mut app, KeyCode::Char('x'));
+        assert_eq!(narrator.narrate(&app, 0), ["Text: x"]);
+        press(&mut app, KeyCode::Backspace);
+        assert_eq!(narrator.narrate(&app, 0), ["Text empty."]);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(narrator.narrate(&app, 0), ["Session list."]);
+
+        app.notify(Level::Info, "Bell muted");
+        assert_eq!(narrator.narrate(&app, 0), ["Bell muted"]);
+    }
+
+    #[test]
+    fn test_duration_words() {
+        assert_eq!(duration_words(1), "1 second");
+        assert_eq!(duration_words(45), "45 seconds");
+        assert_eq!(duration_words(60), "1 minute");
+        assert_eq!(duration_words(4 * 60 + 59), "4 minutes");
+        assert_eq!(duration_words(2 * 3600), "2 hours");
+        assert_eq!(duration_words(3 * 86400), "3 days");
+    }
+
+    #[test]
+    fn test_write_lines() {
+        let mut out = Vec::new();
+        write_lines(&mut out, &["a".to_string(), "b".to_string()]).unwrap();
+        assert_eq!(out, b"a\r\nb\r\n");
+    }
+}
@@ -1,26 +1,297 @@
-use crossterm::event::KeyEvent;
+use crossterm::event::{Event, KeyEvent, KeyEventKind, MouseEvent};
 
-use crate::tmux::TmuxSession;
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use crate::alerts::Alert;
+use crate::broadcast::{BroadcastResult, Recipient};
+use crate::custom_commands::{CommandContext, CustomCommand};
+use crate::dir_picker::DirEntry;
+use crate::launcher::{Launcher, NewSession};
+use crate::mcp::ServerStatus;
+use crate::notification::Level;
+use crate::policy::Verdict;
+use crate::poller::Transition;
+use crate::rules::Firing;
+use crate::schedule::ScheduledPrompt;
+use crate::snapshot::SessionSnapshot;
+use crate::tags::Tags;
+use crate::tmux::{AgentKind, AttachedClient, ServerInfo, TmuxSession, TmuxWindow};
+use crate::usage::Reading;
+use crate::worktree::{Worktree, WorktreeRequest};
+
+/// Outcome of a background operation; the error is shared so actions stay `Clone`
+pub type TaskResult<T> = Result<T, Arc<anyhow::Error>>;
 
 /// Actions that can be dispatched through the application
 #[derive(Debug, Clone)]
 pub enum Action {
     /// A key was pressed
     KeyPress(KeyEvent),
+    /// Mouse input (only reported when the terminal captures the mouse)
+    Mouse(MouseEvent),
+    /// Text pasted into the terminal
+    Paste(String),
+    /// The terminal window gained (`true`) or lost focus
+    FocusChanged(bool),
+    /// The terminal was resized (columns, rows)
+    Resize(u16, u16),
     /// Sessions were updated from tmux
     SessionsUpdated(Vec<TmuxSession>),
+    /// Statuses changed since the previous poll
+    StatusChanged(Vec<Transition>),
+    /// Write the bell sequence to the terminal
+    RingBell,
+    /// A poll found the sessions unchanged
+    Heartbeat,
     /// An error occurred
     Error(String),
     /// Request to quit the application
     Quit,
-    /// Attach to a session
-    AttachSession(String),
+    /// Attach to a session (session id, read-only so keys can't reach its panes)
+    AttachSession(String, bool),
+    /// Attach to a session in a new terminal window, keeping the TUI open
+    AttachSessionInWindow(String),
+    /// Fetch a session's windows to pick one before attaching
+    ListWindows(String),
+    /// A session's windows arrived (session id, windows)
+    WindowsListed(String, TaskResult<Vec<TmuxWindow>>),
+    /// Fetch the windows of a session expanded in the list
+    ListWindowTree(String),
+    /// An expanded session's windows arrived (session id, windows)
+    WindowTreeListed(String, TaskResult<Vec<TmuxWindow>>),
+    /// Create a copy of a session in the directory it is in now (source session id, copy)
+    DuplicateSession(String, NewSession),
+    /// A duplicate was created, or failed to be (operation id, source name,
+    /// copy's name, result)
+    SessionDuplicated(u64, String, String, TaskResult<TmuxSession>),
+    /// Fetch the terminals attached to a session, for the detail pane
+    ListClients(String),
+    /// A session's attached terminals arrived (session id, clients)
+    ClientsListed(String, TaskResult<Vec<AttachedClient>>),
+    /// Detach a terminal from a session (session id, client name)
+    DetachClient(String, String),
+    /// A terminal was detached, or failed to be (session id, client name, result)
+    ClientDetached(String, String, TaskResult<()>),
+    /// List a directory's subdirectories for the directory picker
+    ReadDir(PathBuf),
+    /// A directory listing arrived (directory, subdirectories)
+    DirRead(PathBuf, TaskResult<Vec<DirEntry>>),
+    /// Make a window (`session:index`) current, ahead of attaching to its session
+    SelectWindow(String),
+    /// Run a `[[commands]]` entry against a session
+    RunCommand(CustomCommand, CommandContext),
+    /// A background command finished (operation id, command name, output)
+    CommandFinished(u64, String, TaskResult<String>),
+    /// Rename a session (session id, new name)
+    RenameSession(String, String),
     /// Create a new session
-    CreateSession(String),
+    CreateSession(NewSession),
+    /// Create a template's sessions one after another (template name, sessions)
+    CreateSessions(String, Vec<NewSession>),
+    /// A template's sessions were created, or not (operation id, template name,
+    /// each session's name and result)
+    TemplateCreated(u64, String, Vec<(String, TaskResult<TmuxSession>)>),
+    /// Add a git worktree, then create a session in it running the launcher's command
+    CreateWorktree(WorktreeRequest, Option<Launcher>),
+    /// A background worktree and session creation finished (operation id, result)
+    WorktreeCreated(u64, TaskResult<(TmuxSession, Worktree)>),
+    /// Remove a worktree whose session was deleted
+    RemoveWorktree(Worktree),
+    /// A background worktree removal finished (operation id, removed worktree)
+    WorktreeRemoved(u64, TaskResult<Worktree>),
     /// Delete a session
     DeleteSession(String),
-    /// Toggle MCP mode
+    /// A background create finished (operation id, new session)
+    SessionCreated(u64, TaskResult<TmuxSession>),
+    /// A background delete finished (operation id, deleted session id)
+    SessionDeleted(u64, TaskResult<String>),
+    /// A background rename finished (operation id, (session id, new name))
+    SessionRenamed(u64, TaskResult<(String, String)>),
+    /// A background skeleton copy finished (operation id, where it went)
+    SkeletonCopied(u64, TaskResult<String>),
+    /// Show or hide the MCP servers' status in the footer
     ToggleMcpMode,
-    /// Copy skeleton map to clipboard
-    CopySkeleton,
+    /// tmux's version and how its servers are doing, read on startup and
+    /// every little while
+    ServerInfoUpdated(TaskResult<ServerInfo>),
+    /// Read which `--mcp` servers are running
+    LoadMcpStatus,
+    /// Running `--mcp` servers, oldest first
+    McpStatusLoaded(Vec<ServerStatus>),
+    /// Copy the skeleton map of a directory to the clipboard
+    CopySkeleton(PathBuf),
+    /// Capture a session's pane with scrollback for the preview
+    CapturePane(String),
+    /// Pane content captured for a session (session_id, content)
+    PaneCaptured(String, String),
+    /// Copy arbitrary text to the clipboard
+    CopyText(String),
+    /// Copy a session's pane content to the clipboard
+    CopyPane(String),
+    /// Open a session's directory in the user's editor (session_id, dir)
+    OpenEditor(String, String),
+    /// Interrupt a session's agent and start it again
+    RestartAgent(String),
+    /// Send Ctrl-C to a session's active pane
+    Interrupt(String),
+    /// Message for the footer
+    Notify(Level, String),
+    /// Once a second; expires old footer messages
+    Tick,
+    /// Send the same prompt to several sessions
+    Broadcast(Vec<Recipient>, String),
+    /// Per-session outcome of a broadcast
+    BroadcastFinished(Vec<BroadcastResult>),
+    /// Select a session by name or ID
+    FocusSession(String),
+    /// Poll tmux now
+    Refresh,
+    /// Suspend to the shell like Ctrl-Z in any other program
+    Suspend,
+    /// Save a snapshot of the sessions for restoring later
+    TakeSnapshot,
+    /// A background snapshot finished (operation id, sessions saved)
+    SnapshotSaved(u64, TaskResult<usize>),
+    /// Write every session to the export file (notes by session name, and tags)
+    ExportSessions(BTreeMap<String, String>, Tags),
+    /// A background export finished (operation id, sessions written)
+    SessionsExported(u64, TaskResult<usize>),
+    /// Type a scheduled prompt into its session (the session's ID, if it is running)
+    SendScheduled(ScheduledPrompt, Option<String>),
+    /// A scheduled prompt was sent, or failed to be
+    ScheduledSent(ScheduledPrompt, Result<(), String>),
+    /// Carry out a fired rule (history entry, rule, the `send_to` session's ID if it is running)
+    FireRule(u64, Firing, Option<String>),
+    /// A fired rule finished (history entry, outcome)
+    RuleFinished(u64, Result<(), String>),
+    /// Notify or run the hook of an alert that fired
+    FireAlert(Alert),
+    /// Read the usage totals shown in these sessions' panes
+    SampleUsage(Vec<TmuxSession>),
+    /// Totals read from panes (session name, agent, reading)
+    UsageSampled(Vec<(String, AgentKind, Reading)>),
+    /// Read the line each of these sessions' panes is waiting on, for the startup digest
+    ReadQuestions(Vec<String>),
+    /// Lines the panes are waiting on (session id, line)
+    QuestionsRead(Vec<(String, String)>),
+    /// Archive an idle session's scrollback, then close it
+    ArchiveAndKill(Verdict),
+    /// A background archive finished (operation id, session, archive file)
+    SessionArchived(u64, Verdict, TaskResult<PathBuf>),
+    /// Recreate sessions from the snapshot
+    RestoreSessions(Vec<SessionSnapshot>),
+    /// A background restore finished (operation id, sessions restored)
+    SessionsRestored(u64, TaskResult<usize>),
+}
+
+impl Action {
+    /// Translate a terminal event; key releases are dropped
+    pub fn from_event(event: Event) -> Option<Self> {
+        match event {
+            Event::Key(key) => match key.kind {
+                KeyEventKind::Press | KeyEventKind::Repeat => Some(Action::KeyPress(key)),
+                KeyEventKind::Release => None,
+            },
+            Event::Mouse(mouse) => Some(Action::Mouse(mouse)),
+            Event::Paste(text) => Some(Action::Paste(text)),
+            Event::FocusGained => Some(Action::FocusChanged(true)),
+            Event::FocusLost => Some(Action::FocusChanged(false)),
+            Event::Resize(width, height) => Some(Action::Resize(width, height)),
+        }
+    }
+
+    /// Attach to a session the usual way
+    pub fn attach(session_id: String) -> Self {
+        Action::AttachSession(session_id, false)
+    }
+
+    /// Attach to a session read-only, to watch it without typing into it
+    pub fn watch(session_id: String) -> Self {
+        Action::AttachSession(session_id, true)
+    }
+
+    /// Short name for logs (payloads can be large)
+    pub fn label(&self) -> &'static str {
+        match self {
+            Action::KeyPress(_) => "key_press",
+            Action::Mouse(_) => "mouse",
+            Action::Paste(_) => "paste",
+            Action::FocusChanged(_) => "focus_changed",
+            Action::Resize(..) => "resize",
+            Action::SessionsUpdated(_) => "sessions_updated",
+            Action::StatusChanged(_) => "status_changed",
+            Action::RingBell => "ring_bell",
+            Action::Heartbeat => "heartbeat",
+            Action::Error(_) => "error",
+            Action::Quit => "quit",
+            Action::AttachSession(..) => "attach_session",
+            Action::AttachSessionInWindow(_) => "attach_session_in_window",
+            Action::ListWindows(_) => "list_windows",
+            Action::WindowsListed(..) => "windows_listed",
+            Action::ListWindowTree(_) => "list_window_tree",
+            Action::WindowTreeListed(..) => "window_tree_listed",
+            Action::DuplicateSession(..) => "duplicate_session",
+            Action::SessionDuplicated(..) => "session_duplicated",
+            Action::ListClients(_) => "list_clients",
+            Action::ClientsListed(..) => "clients_listed",
+            Action::DetachClient(..) => "detach_client",
+            Action::ClientDetached(..) => "client_detached",
+            Action::ReadDir(_) => "read_dir",
+            Action::DirRead(..) => "dir_read",
+            Action::SelectWindow(_) => "select_window",
+            Action::RunCommand(..) => "run_command",
+            Action::CommandFinished(..) => "command_finished",
+            Action::RenameSession(..) => "rename_session",
+            Action::CreateSession(..) => "create_session",
+            Action::CreateSessions(..) => "create_sessions",
+            Action::TemplateCreated(..) => "template_created",
+            Action::CreateWorktree(..) => "create_worktree",
+            Action::WorktreeCreated(..) => "worktree_created",
+            Action::RemoveWorktree(_) => "remove_worktree",
+            Action::WorktreeRemoved(..) => "worktree_removed",
+            Action::DeleteSession(_) => "delete_session",
+            Action::SessionCreated(..) => "session_created",
+            Action::SessionDeleted(..) => "session_deleted",
+            Action::SessionRenamed(..) => "session_renamed",
+            Action::SkeletonCopied(..) => "skeleton_copied",
+            Action::ToggleMcpMode => "toggle_mcp_mode",
+            Action::ServerInfoUpdated(_) => "server_info_updated",
+            Action::LoadMcpStatus => "load_mcp_status",
+            Action::McpStatusLoaded(_) => "mcp_status_loaded",
+            Action::CopySkeleton(_) => "copy_skeleton",
+            Action::CapturePane(_) => "capture_pane",
+            Action::PaneCaptured(..) => "pane_captured",
+            Action::CopyText(_) => "copy_text",
+            Action::CopyPane(_) => "copy_pane",
+            Action::OpenEditor(..) => "open_editor",
+            Action::RestartAgent(_) => "restart_agent",
+            Action::Interrupt(_) => "interrupt",
+            Action::Notify(..) => "notify",
+            Action::Tick => "tick",
+            Action::Broadcast(..) => "broadcast",
+            Action::BroadcastFinished(_) => "broadcast_finished",
+            Action::FocusSession(_) => "focus_session",
+            Action::Refresh => "refresh",
+            Action::Suspend => "suspend",
+            Action::TakeSnapshot => "take_snapshot",
+            Action::SnapshotSaved(..) => "snapshot_saved",
+            Action::ExportSessions(..) => "export_sessions",
+            Action::SessionsExported(..) => "sessions_exported",
+            Action::SendScheduled(..) => "send_scheduled",
+            Action::ScheduledSent(..) => "scheduled_sent",
+            Action::FireRule(..) => "fire_rule",
+            Action::RuleFinished(..) => "rule_finished",
+            Action::FireAlert(_) => "fire_alert",
+            Action::SampleUsage(_) => "sample_usage",
+            Action::UsageSampled(_) => "usage_sampled",
+            Action::ReadQuestions(_) => "read_questions",
+            Action::QuestionsRead(_) => "questions_read",
+            Action::ArchiveAndKill(_) => "archive_and_kill",
+            Action::SessionArchived(..) => "session_archived",
+            Action::RestoreSessions(_) => "restore_sessions",
+            Action::SessionsRestored(..) => "sessions_restored",
+        }
+    }
 }
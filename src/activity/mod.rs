@@ -0,0 +1,234 @@
+//! Output activity over the last few minutes, drawn as sparklines.
+//!
+//! Each time a pane is captured for its status, the lines that weren't on
+//! screen at the previous capture are counted; a session with no new activity
+//! isn't captured and counts as zero. The counts are kept per session for the
+//! configured window and summed into fixed buckets for drawing.
+
+use std::collections::hash_map::DefaultHasher;
+use std::collections::{HashMap, VecDeque};
+use std::hash::{Hash, Hasher};
+use std::time::{Duration, Instant};
+
+/// Buckets a session's activity window is summed into, oldest first
+pub const ACTIVITY_BUCKETS: usize = 30;
+/// Sparkline cells in a list row
+pub const LIST_CELLS: usize = 10;
+/// Samples kept per session, whatever the window and poll interval
+const MAX_SAMPLES: usize = 512;
+
+const LEVELS: [char; 8] = ['▁', '▂', '▃', '▄', '▅', '▆', '▇', '█'];
+
+/// Hashes of a capture's non-blank lines, sorted for `count_new`
+pub fn line_digests(content: &str) -> Vec<u64> {
+    let mut digests: Vec<u64> = content
+        .lines()
+        .map(str::trim_end)
+        .filter(|line| !line.is_empty())
+        .map(|line| {
+            let mut hasher = DefaultHasher::new();
+            line.hash(&mut hasher);
+            hasher.finish()
+        })
+        .collect();
+    digests.sort_unstable();
+    digests
+}
+
+/// Lines of `current` that weren't in `previous`; scrolling by three lines counts three
+pub fn count_new(previous: &[u64], current: &[u64]) -> u32 {
+    current
+        .iter()
+        .filter(|digest| previous.binary_search(digest).is_err())
+        .count() as u32
+}
+
+/// Sum `samples` (age, count) into `count` buckets spanning `window`, oldest first
+pub fn buckets(
+    samples: impl IntoIterator<Item = (Duration, u32)>,
+    window: Duration,
+    count: usize,
+) -> Vec<u32> {
+    let mut buckets = vec![0; count];
+    if count == 0 || window.is_zero() {
+        return buckets;
+    }
+    for (age, value) in samples {
+        if age >= window {
+            continue;
+        }
+        let back = (age.as_secs_f64() * count as f64 / window.as_secs_f64()) as usize;
+        buckets[count - 1 - back.min(count - 1)] += value;
+    }
+    buckets
+}
+
+/// Squeeze `values` into `cells`, summing the buckets each cell covers
+pub fn resample(values: &[u32], cells: usize) -> Vec<u32> {
+    if values.len() <= cells {
+        return values.to_vec();
+    }
+    let mut resampled = vec![0; cells];
+    for (index, value) in values.iter().enumerate() {
+        resampled[index * cells / values.len()] += value;
+    }
+    resampled
+}
+
+/// Block characters for `values` scaled to `ceiling`; zero is blank, anything else at least `▁`
+pub fn sparkline(values: &[u32], ceiling: u32) -> String {
+    values
+        .iter()
+        .map(|&value| match value {
+            0 => ' ',
+            _ => {
+                let ceiling = ceiling.max(value) as u64;
+                let level = (value as u64 * LEVELS.len() as u64).div_ceil(ceiling);
+                LEVELS[(level as usize).clamp(1, LEVELS.len()) - 1]
+            }
+        })
+        .collect()
+}
+
+/// Per-session activity samples over a sliding window
+#[derive(Debug)]
+pub struct ActivityTracker {
+    window: Duration,
+    /// When each non-zero sample was taken, oldest first
+    samples: HashMap<String, VecDeque<(Instant, u32)>>,
+}
+
+impl ActivityTracker {
+    pub fn new(window: Duration) -> Self {
+        Self {
+            window,
+            samples: HashMap::new(),
+        }
+    }
+
+    /// Record new lines per session id at `now`
+    pub fn record(&mut self, changes: &HashMap<String, u32>, now: Instant) {
+        for (id, &count) in changes.iter().filter(|(_, count)| **count > 0) {
+            let samples = self.samples.entry(id.clone()).or_default();
+            if samples.len() == MAX_SAMPLES {
+                samples.pop_front();
+            }
+            samples.push_back((now, count));
+        }
+        let window = self.window;
+        for samples in self.samples.values_mut() {
+            while samples
+                .front()
+                .is_some_and(|(at, _)| now.duration_since(*at) >= window)
+            {
+                samples.pop_front();
+            }
+        }
+        self.samples.retain(|_, samples| !samples.is_empty());
+    }
+
+    /// A session's activity in `ACTIVITY_BUCKETS` buckets ending at `now`
+    pub fn series(&self, id: &str, now: Instant) -> Vec<u32> {
+        let samples = self.samples.get(id).into_iter().flatten();
+        buckets(
+            samples.map(|&(at, count)| (now.duration_since(at), count)),
+            self.window,
+            ACTIVITY_BUCKETS,
+        )
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.samples.retain(|id, _| keep(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_count_new() {
+        let before = line_digests("$ cargo test\n   Compiling api\n\n");
+        let after = line_digests("   Compiling api\n   Finished\ntest result: ok\n\n\n");
+        assert_eq!(count_new(&before, &after), 2);
+        assert_eq!(count_new(&after, &after), 0);
+        // Trailing spaces from a redraw aren't new output
+        assert_eq!(count_new(&after, &line_digests("   Finished   \n")), 0);
+        assert_eq!(count_new(&[], &after), 3);
+    }
+
+    #[test]
+    fn test_buckets() {
+        let window = Duration::from_secs(300);
+        let secs = Duration::from_secs;
+        let values = buckets(
+            [
+                (secs(0), 1),
+                (secs(9), 2),
+                (secs(10), 4),
+                (secs(299), 8),
+                (secs(300), 100),
+                (secs(1000), 100),
+            ],
+            window,
+            30,
+        );
+        assert_eq!(values.len(), 30);
+        // The newest bucket covers the last ten seconds
+        assert_eq!(values[29], 3);
+        assert_eq!(values[28], 4);
+        assert_eq!(values[0], 8);
+        assert_eq!(values.iter().sum::<u32>(), 15);
+
+        assert_eq!(buckets([(secs(0), 1)], Duration::ZERO, 3), [0, 0, 0]);
+        assert!(buckets([(secs(0), 1)], window, 0).is_empty());
+    }
+
+    #[test]
+    fn test_resample() {
+        let values: Vec<u32> = (1..=30).collect();
+        let cells = resample(&values, 10);
+        assert_eq!(cells.len(), 10);
+        assert_eq!(cells[0], 1 + 2 + 3);
+        assert_eq!(cells[9], 28 + 29 + 30);
+        assert_eq!(resample(&[1, 2, 3, 4, 5], 2), [1 + 2 + 3, 4 + 5]);
+        assert_eq!(resample(&[1, 2], 10), [1, 2]);
+    }
+
+    #[test]
+    fn test_sparkline_scaling() {
+        assert_eq!(sparkline(&[0, 1, 4, 8], 8), " ▁▄█");
+        // Anything above zero shows, however small against the ceiling
+        assert_eq!(sparkline(&[1, 1000], 1000), "▁█");
+        // A value over the ceiling is capped rather than overflowing
+        assert_eq!(sparkline(&[20], 8), "█");
+        assert_eq!(sparkline(&[3], 0), "█");
+        assert_eq!(sparkline(&[], 8), "");
+    }
+
+    #[test]
+    fn test_tracker_window() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let changes = |count| HashMap::from([("$1".to_string(), count), ("$2".to_string(), 0)]);
+        let mut tracker = ActivityTracker::new(Duration::from_secs(300));
+        tracker.record(&changes(5), at(0));
+        tracker.record(&changes(7), at(100));
+        let series = tracker.series("$1", at(100));
+        assert_eq!(series.iter().sum::<u32>(), 12);
+        assert_eq!(series[29], 7);
+        assert!(tracker.series("$2", at(100)).iter().all(|&v| v == 0));
+
+        // Samples fall out of the window
+        tracker.record(&HashMap::new(), at(301));
+        assert_eq!(tracker.series("$1", at(301)).iter().sum::<u32>(), 7);
+        tracker.retain(|id| id != "$1");
+        assert_eq!(tracker.series("$1", at(301)).iter().sum::<u32>(), 0);
+
+        // Bounded however fast samples arrive
+        for i in 0..2 * MAX_SAMPLES as u64 {
+            tracker.record(&changes(1), start + Duration::from_millis(i));
+        }
+        assert_eq!(tracker.samples["$1"].len(), MAX_SAMPLES);
+    }
+}
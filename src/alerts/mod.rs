@@ -0,0 +1,350 @@
+//! Alerts for sessions stuck in one status: an error that outlasts a retry,
+//! an agent busy far longer than it should be, a question left unanswered.
+//!
+//! Each `[[alerts]]` rule names a status and how long a session has to stay
+//! in it. The tracker times each session's current status (an episode) from
+//! the poll that first saw it, fires a rule once per episode when it has
+//! lasted long enough, and forgets the episode when the status changes, so
+//! the next episode can fire again. A rule that fired stays active until its
+//! episode ends.
+
+use serde::{Deserialize, Deserializer};
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::rules::glob_match;
+use crate::tmux::{AgentStatus, TmuxSession};
+
+/// What an alert does when it fires
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum AlertAction {
+    /// Desktop notification
+    Notify,
+    /// Terminal bell, unless muted
+    Bell,
+    /// Run the rule's `run` command
+    Hook,
+    /// Only mark the session in the list
+    Highlight,
+}
+
+/// `[[alerts]]` entry of the config
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct AlertRule {
+    /// Session names to watch; `*` and `?` are wildcards
+    #[serde(default = "any_session")]
+    pub when: String,
+    /// Status that has to persist (`error`, `busy`, `waiting-for-input`, ...)
+    #[serde(deserialize_with = "parse_status")]
+    pub status: AgentStatus,
+    /// Seconds the status has to last before the alert fires
+    pub for_secs: u64,
+    pub action: AlertAction,
+    /// Shell command for `hook`; `{session}` and `{status}` are replaced as for hooks
+    #[serde(default)]
+    pub run: Option<String>,
+}
+
+fn any_session() -> String {
+    "*".to_string()
+}
+
+fn parse_status<'de, D: Deserializer<'de>>(deserializer: D) -> Result<AgentStatus, D::Error> {
+    String::deserialize(deserializer)?
+        .parse()
+        .map_err(serde::de::Error::custom)
+}
+
+impl AlertRule {
+    /// Why the rule can't work, if it can't
+    pub fn validate(&self) -> Result<(), String> {
+        match (self.action, &self.run) {
+            (AlertAction::Hook, None) => Err("`hook` needs `run`".to_string()),
+            (AlertAction::Hook, Some(_)) | (_, None) => Ok(()),
+            (_, Some(_)) => Err("`run` only applies to `hook`".to_string()),
+        }
+    }
+
+    fn threshold(&self) -> Duration {
+        Duration::from_secs(self.for_secs)
+    }
+}
+
+/// A rule firing, or still active, for one session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Alert {
+    pub session_id: String,
+    pub session: String,
+    pub rule: AlertRule,
+    /// When the episode began
+    pub since: Instant,
+}
+
+/// A session's current status and the rules it has fired
+#[derive(Debug)]
+struct Episode {
+    session: String,
+    status: AgentStatus,
+    created_at: u64,
+    since: Instant,
+    /// Indexes of the rules fired during this episode
+    fired: Vec<usize>,
+}
+
+impl Episode {
+    fn start(session: &TmuxSession, now: Instant) -> Self {
+        Self {
+            session: session.name.clone(),
+            status: session.status,
+            created_at: session.created_at,
+            since: now,
+            fired: Vec::new(),
+        }
+    }
+}
+
+/// Times each session's status and decides which alerts fire
+#[derive(Debug, Default)]
+pub struct AlertTracker {
+    rules: Vec<AlertRule>,
+    /// Episodes by session id
+    episodes: HashMap<String, Episode>,
+}
+
+impl AlertTracker {
+    pub fn new(rules: Vec<AlertRule>) -> Self {
+        Self {
+            rules,
+            episodes: HashMap::new(),
+        }
+    }
+
+    /// Alerts that fire with this poll, taken at `now`
+    ///
+    /// A session created again under a reused id starts a new episode, as it
+    /// would with a different status.
+    pub fn observe(&mut self, sessions: &[TmuxSession], now: Instant) -> Vec<Alert> {
+        if self.rules.is_empty() {
+            return Vec::new();
+        }
+        self.episodes
+            .retain(|id, _| sessions.iter().any(|s| &s.id == id));
+        let mut fired = Vec::new();
+        for session in sessions {
+            let episode = self
+                .episodes
+                .entry(session.id.clone())
+                .and_modify(|episode| {
+                    if episode.status != session.status || episode.created_at != session.created_at
+                    {
+                        *episode = Episode::start(session, now);
+                    }
+                })
+                .or_insert_with(|| Episode::start(session, now));
+            episode.session.clone_from(&session.name);
+            let lasted = now.saturating_duration_since(episode.since);
+            for (index, rule) in self.rules.iter().enumerate() {
+                if rule.status == session.status
+                    && lasted >= rule.threshold()
+                    && !episode.fired.contains(&index)
+                    && glob_match(&rule.when, &session.name)
+                {
+                    episode.fired.push(index);
+                    fired.push(Alert {
+                        session_id: session.id.clone(),
+                        session: session.name.clone(),
+                        rule: rule.clone(),
+                        since: episode.since,
+                    });
+                }
+            }
+        }
+        fired
+    }
+
+    /// Alerts whose episodes are still going, by session name
+    pub fn active(&self) -> Vec<Alert> {
+        let mut active: Vec<Alert> = self
+            .episodes
+            .iter()
+            .flat_map(|(id, episode)| {
+                episode.fired.iter().map(|&index| Alert {
+                    session_id: id.clone(),
+                    session: episode.session.clone(),
+                    rule: self.rules[index].clone(),
+                    since: episode.since,
+                })
+            })
+            .collect();
+        active.sort_by(|a, b| a.session.cmp(&b.session).then(a.since.cmp(&b.since)));
+        active
+    }
+
+    /// Whether an active `highlight` alert marks the session
+    pub fn highlights(&self, session_id: &str) -> bool {
+        self.episodes.get(session_id).is_some_and(|episode| {
+            episode
+                .fired
+                .iter()
+                .any(|&index| self.rules[index].action == AlertAction::Highlight)
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn rule(status: AgentStatus, for_secs: u64, action: AlertAction) -> AlertRule {
+        AlertRule {
+            when: "*".to_string(),
+            status,
+            for_secs,
+            action,
+            run: None,
+        }
+    }
+
+    fn session(name: &str, status: AgentStatus) -> TmuxSession {
+        let mut s = TmuxSession::new(format!("${}", name), name.to_string());
+        s.status = status;
+        s
+    }
+
+    /// Feed one poll per `(seconds, status)` step and collect the seconds alerts fired at
+    fn timeline(tracker: &mut AlertTracker, steps: &[(u64, AgentStatus)]) -> Vec<u64> {
+        let start = Instant::now();
+        let mut fired_at = Vec::new();
+        for &(secs, status) in steps {
+            let now = start + Duration::from_secs(secs);
+            let fired = tracker.observe(&[session("api", status)], now);
+            fired_at.extend(fired.iter().map(|_| secs));
+        }
+        fired_at
+    }
+
+    #[test]
+    fn test_fires_once_per_episode() {
+        use AgentStatus::*;
+        let mut tracker = AlertTracker::new(vec![rule(Error, 120, AlertAction::Notify)]);
+        let steps = [
+            // A brief error during a retry stays quiet
+            (0, Error),
+            (60, Error),
+            (90, Busy),
+            // A persistent one fires once it has lasted long enough, and only once
+            (100, Error),
+            (200, Error),
+            (220, Error),
+            (500, Error),
+            // A new episode can fire again
+            (510, Idle),
+            (520, Error),
+            (640, Error),
+        ];
+        assert_eq!(timeline(&mut tracker, &steps), [220, 640]);
+    }
+
+    #[test]
+    fn test_active_until_status_changes() {
+        use AgentStatus::*;
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let mut tracker = AlertTracker::new(vec![
+            rule(Busy, 2700, AlertAction::Highlight),
+            rule(Busy, 3600, AlertAction::Bell),
+        ]);
+        let sessions = [session("web", Busy), session("api", Busy)];
+
+        tracker.observe(&sessions, at(0));
+        assert!(tracker.active().is_empty());
+        assert_eq!(tracker.observe(&sessions, at(2700)).len(), 2);
+        assert_eq!(tracker.observe(&sessions, at(3600)).len(), 2);
+        let names: Vec<String> = tracker.active().into_iter().map(|a| a.session).collect();
+        assert_eq!(names, ["api", "api", "web", "web"]);
+        assert!(tracker.highlights("$web"));
+
+        // Going idle ends the episode and its alerts; a vanished session takes its own along
+        tracker.observe(&[session("web", Idle)], at(3700));
+        assert!(tracker.active().is_empty());
+        assert!(!tracker.highlights("$web"));
+        assert!(
+            tracker
+                .observe(&[session("web", Busy)], at(3800))
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_recreated_session_starts_over() {
+        let mut tracker = AlertTracker::new(vec![rule(
+            AgentStatus::WaitingForInput,
+            600,
+            AlertAction::Bell,
+        )]);
+        let start = Instant::now();
+        let mut old = session("api", AgentStatus::WaitingForInput);
+        old.created_at = 100;
+        tracker.observe(std::slice::from_ref(&old), start);
+
+        // Same id and status, but tmux reused the id for a new session
+        let mut new = old.clone();
+        new.created_at = 500;
+        let later = start + Duration::from_secs(600);
+        assert!(
+            tracker
+                .observe(std::slice::from_ref(&new), later)
+                .is_empty()
+        );
+        assert_eq!(
+            tracker
+                .observe(&[new], later + Duration::from_secs(600))
+                .len(),
+            1
+        );
+    }
+
+    #[test]
+    fn test_session_patterns() {
+        let mut tracker = AlertTracker::new(vec![AlertRule {
+            when: "batch-*".to_string(),
+            ..rule(AgentStatus::Error, 0, AlertAction::Notify)
+        }]);
+        let now = Instant::now();
+        let fired = tracker.observe(
+            &[
+                session("api", AgentStatus::Error),
+                session("batch-nightly", AgentStatus::Error),
+            ],
+            now,
+        );
+        let names: Vec<&str> = fired.iter().map(|a| a.session.as_str()).collect();
+        assert_eq!(names, ["batch-nightly"]);
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(
+            rule(AgentStatus::Error, 60, AlertAction::Notify)
+                .validate()
+                .is_ok()
+        );
+        assert!(
+            rule(AgentStatus::Error, 60, AlertAction::Hook)
+                .validate()
+                .is_err()
+        );
+        let hook = AlertRule {
+            run: Some("page-oncall {session}".to_string()),
+            ..rule(AgentStatus::Error, 60, AlertAction::Hook)
+        };
+        assert!(hook.validate().is_ok());
+        let bell = AlertRule {
+            action: AlertAction::Bell,
+            ..hook
+        };
+        assert!(bell.validate().is_err());
+    }
+}
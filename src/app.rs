@@ -1,534 +1,10655 @@
 use anyhow::Result;
-use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use crossterm::event::{
+    KeyCode, KeyEvent, KeyEventKind, KeyModifiers, MouseButton, MouseEvent, MouseEventKind,
+};
 use ratatui::{
-    layout::{Constraint, Direction, Layout, Rect},
+    Frame,
+    layout::{Constraint, Direction, Layout, Margin, Position, Rect},
     style::{Color, Modifier, Style},
     text::{Line, Span},
-    widgets::{Block, Borders, List, ListItem, ListState, Paragraph, Clear},
-    Frame,
+    widgets::{
+        Block, Borders, Cell, Clear, List, ListItem, ListState, Paragraph, Row, Table, TableState,
+        Wrap,
+    },
 };
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
 
 use crate::actions::Action;
-use crate::tmux::{AgentStatus, TmuxSession};
-
-/// Theme colors inspired by Claude Code
-pub struct Theme {
-    pub bg: Color,
-    pub fg: Color,
-    pub accent: Color,
-    pub dim: Color,
-    pub success: Color,
-    pub warning: Color,
-    pub error: Color,
+use crate::activity;
+use crate::alerts::{AlertAction, AlertTracker};
+use crate::bell::Bell;
+use crate::broadcast::{self, BroadcastResult, Recipient};
+use crate::config::Config;
+use crate::custom_commands::{self, CommandContext, CustomCommand};
+use crate::description;
+use crate::digest::{self, DigestEntry};
+use crate::dir_picker::{DirPicker, PickerEvent};
+use crate::error::AgentRustyError;
+use crate::input::{self, RepeatLimiter};
+use crate::jump_list::JumpList;
+use crate::launcher::{self, Launcher, NewSession};
+use crate::logging::{self, LogBuffer, LogLine};
+use crate::mcp::ServerStatus;
+use crate::metrics;
+use crate::notification::{Level, Notifications};
+use crate::policy::{self, IdlePolicy, PolicyAction, Verdict};
+use crate::poller::{PollRates, Transition};
+use crate::preview::PanePreview;
+use crate::rules::{self, RuleAction, RuleBook, RuleRun};
+use crate::schedule;
+use crate::session_list::{self, Column, ListDisplay, ListLayout, ListRow, StatusGroup};
+use crate::snapshot::{SessionSnapshot, Snapshot};
+use crate::state::PersistedState;
+use crate::tags;
+use crate::template::{self, Template};
+use crate::text;
+use crate::text_input::TextInput;
+use crate::theme::{ColorSupport, PALETTES, Theme, ThemeConfig};
+use crate::timeline::StatusTimeline;
+use crate::tmux::{
+    self, AgentKind, AgentStatus, AttachedClient, ServerInfo, TmuxServer, TmuxSession, TmuxWindow,
+};
+use crate::usage::{self, RollupRow};
+use crate::worktree::{self, Worktree, WorktreeConfig, WorktreeRequest};
+use unicode_width::UnicodeWidthStr;
+
+/// What the directory picker chooses
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DirPurpose {
+    /// Where the session being created starts
+    SessionDir,
+    /// Root of the skeleton `y` copies
+    SkeletonRoot,
 }
 
-impl Default for Theme {
-    fn default() -> Self {
-        Self {
-            bg: Color::Rgb(30, 30, 30),
-            fg: Color::Rgb(220, 220, 220),
-            accent: Color::Rgb(217, 119, 87), // Claude orange
-            dim: Color::Rgb(100, 100, 100),
-            success: Color::Rgb(80, 200, 120),
-            warning: Color::Rgb(255, 193, 7),
-            error: Color::Rgb(220, 53, 69),
-        }
-    }
+/// What the note dialog edits
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NoteKind {
+    /// Free-form notes, up to `NOTE_MAX_LINES` lines
+    Note,
+    /// The one-line description shown under the name
+    Description,
 }
 
 /// Input mode for the application
-#[derive(Debug, Clone, PartialEq, Eq)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum InputMode {
     Normal,
     Creating,
+    /// Picking what the new session runs
+    ChoosingLauncher,
+    /// Typing the new session's directory
+    CreatingDir,
+    /// Typing the command the new session runs
+    CreatingCommand,
     Confirming,
+    Preview,
+    EditingNote,
+    /// Typing the selected session's tags
+    EditingTags,
+    /// Picking a tag to filter the list by
+    ChoosingTag,
+    /// Typing the prompt to broadcast to marked sessions
+    Broadcasting,
+    /// Reviewing broadcast recipients before sending
+    ConfirmingBroadcast,
+    /// Showing per-session broadcast outcomes
+    BroadcastResults,
+    /// Confirming an agent restart in the selected session
+    ConfirmingRestart,
+    /// Reading the full cause chain of the footer error
+    ErrorDetails,
+    /// Offering to recreate sessions missing since the last snapshot
+    ConfirmingRestore,
+    /// Typing when a scheduled prompt should be sent
+    SchedulingTime,
+    /// Typing the scheduled prompt
+    SchedulingPrompt,
+    /// Listing pending scheduled prompts
+    Schedules,
+    /// Suggesting to archive and close stale sessions
+    ConfirmingCleanup,
+    /// Typing a rule chained to the selected session
+    AddingRule,
+    /// Listing rules and what they have fired
+    Rules,
+    /// Token and cost rollup across sessions
+    Usage,
+    /// Typing the branch of a new worktree and its session
+    CreatingWorktree,
+    /// Offering to remove a deleted session's worktree
+    ConfirmingWorktreeRemoval,
+    /// Sessions that needed attention when the dashboard started
+    StartupDigest,
+    /// Picking a `[[commands]]` entry to run against the selected session
+    ChoosingCommand,
+    /// Picking a `[[template]]` to create sessions from
+    ChoosingTemplate,
+    /// Typing the project directory for the chosen template
+    TemplateDir,
+    /// Asking before quitting while agents are mid-task
+    ConfirmingQuit,
+    /// Picking the window to attach to
+    ChoosingWindow,
+    /// Picking a terminal to detach from the session
+    ChoosingClient,
+    /// One session full-screen: its pane, status timeline, usage and windows
+    Dashboard,
+    /// Typing a prompt for the dashboard's session
+    DashboardPrompt,
+    /// Recent lines of agent-rusty's own log
+    Logs,
+    /// Browsing for a directory
+    PickingDir,
+    /// Typing the filter of the session list
+    Filtering,
+    /// Every key of the session list
+    Help,
+    /// Typing the selected session's new name
+    Renaming,
+}
+
+/// Data older than this is shown as stale
+const STALE_AFTER: Duration = Duration::from_secs(5);
+/// Data older than this suggests the poller is stuck
+const WEDGED_AFTER: Duration = Duration::from_secs(15);
+
+/// Frames of the footer spinner shown while operations run
+const SPINNER: [&str; 4] = ["◐", "◓", "◑", "◒"];
+/// How long each spinner frame stays up
+const SPINNER_STEP: Duration = Duration::from_millis(250);
+
+/// How often the idle policy is evaluated
+const POLICY_INTERVAL: Duration = Duration::from_secs(300);
+
+/// How often agents' usage totals are read from their panes
+const USAGE_INTERVAL: Duration = Duration::from_secs(60);
+
+/// Maximum number of lines in a session note
+const NOTE_MAX_LINES: usize = 5;
+
+/// A session whose command ends this soon after it was created gets a warning
+const EARLY_EXIT: Duration = Duration::from_secs(10);
+
+/// A poll this soon after creating a session may have started before it existed
+const LAUNCH_GRACE: Duration = Duration::from_secs(1);
+
+/// Characters allowed in the name of a session made or renamed here
+fn is_session_name_char(c: char) -> bool {
+    c.is_alphanumeric() || c == '-' || c == '_'
+}
+
+/// A second click this soon after one that attached is part of a double-click
+const DOUBLE_CLICK: Duration = Duration::from_millis(500);
+
+/// Keys of the session list by category, for the help overlay
+pub const HELP: &[(&str, &[(&str, &str)])] = &[
+    (
+        "Navigation",
+        &[
+            ("j/k ↑/↓", "Move the selection"),
+            ("Click / wheel", "Select; click again to attach"),
+            (
+                "←/→ h",
+                "Collapse or expand the group, or the session's windows (l is the log)",
+            ),
+            ("^O / Tab", "Jump back or forward"),
+            ("/", "Filter sessions by name"),
+            ("v", "Grouped or flat list"),
+            ("t", "List or table layout"),
+            ("s", "Change the sort column"),
+        ],
+    ),
+    (
+        "Sessions",
+        &[
+            ("Enter", "Attach, choosing a window"),
+            ("1-9", "Attach to the numbered session"),
+            ("`", "Attach to the last attached session again"),
+            ("Shift-Enter", "Attach to the current window"),
+            ("Alt-Enter", "Attach in a new terminal window"),
+            ("V", "Attach read-only, to watch without typing into it"),
+            ("n", "New session"),
+            ("F", "Duplicate the session: same command, same directory"),
+            ("G", "New worktree and session"),
+            ("d", "Delete session"),
+            ("r", "Rename session"),
+            ("←/→ ^W ^U", "Move, delete a word, clear (in names)"),
+            ("R", "Restart the agent"),
+            ("o", "Preview the pane"),
+            ("O", "Dashboard of the session"),
+            ("E", "Open the directory in the editor"),
+            ("J / K", "Move the session down or up the list"),
+            ("N / D", "Edit the note or description"),
+            ("C", "Detach one of the session's terminals"),
+            ("#", "Edit the session's tags"),
+            ("f", "Filter by tag"),
+            ("Space", "Mark for broadcast"),
+            ("b", "Broadcast a prompt to marked sessions"),
+            ("Esc", "Dismiss a failure, else clear marks and the filter"),
+            ("Y", "Copy the pane"),
+            ("T / P", "Schedule a prompt, list scheduled ones"),
+            ("w / W", "Add a rule, list rules"),
+            (":", "Run a configured command"),
+            ("A", "Create sessions from a template"),
+        ],
+    ),
+    (
+        "Skeleton",
+        &[("y", "Copy the skeleton"), ("B", "Pick the skeleton root")],
+    ),
+    ("MCP", &[("M", "Show whether `--mcp` servers are running")]),
+    (
+        "Other",
+        &[
+            ("S / X", "Snapshot or export sessions"),
+            ("U", "Usage"),
+            ("l", "agent-rusty's log"),
+            ("e", "Details of the footer error"),
+            ("m", "Mute the bell"),
+            ("c", "Next color theme"),
+            ("p / F5", "Poll rate (formerly r), poll now"),
+            ("^Z", "Suspend"),
+            ("?", "This help"),
+            ("q", "Quit"),
+        ],
+    ),
+];
+
+/// The help overlay's text, one line per key and a heading per category
+pub fn help_lines() -> Vec<(bool, String)> {
+    let mut lines = Vec::new();
+    for (i, (category, keys)) in HELP.iter().enumerate() {
+        if i > 0 {
+            lines.push((false, String::new()));
+        }
+        lines.push((true, category.to_string()));
+        lines.extend(
+            keys.iter()
+                .map(|(key, what)| (false, format!("  {:<12} {}", key, what))),
+        );
+    }
+    lines
+}
+
+/// Cause chain attached to a footer message
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ErrorDetails {
+    /// Footer message these details belong to
+    pub summary: String,
+    /// Each error in the chain, outermost first
+    pub chain: Vec<String>,
+}
+
+/// Identity of a list row that survives session refreshes
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum RowKey {
+    Session(String),
+    /// A window of an expanded session, by session ID and window index
+    Window(String, u32),
+    Header(StatusGroup),
 }
 
 /// Main application state
 pub struct App {
     /// List of tmux sessions
     pub sessions: Vec<TmuxSession>,
-    /// Currently selected session index
+    /// Rows of the session list (headers and sessions) in display order
+    pub rows: Vec<ListRow>,
+    /// Currently selected row index
     pub list_state: ListState,
-    /// Current message to display (info or error)
-    pub error_message: Option<String>,
-    /// Whether MCP mode is active
+    /// Messages for the footer; the newest is shown
+    pub notifications: Notifications,
+    /// Full cause chain for the footer message it was reported with
+    pub error_details: Option<ErrorDetails>,
+    /// Scroll position in the error details popup
+    pub error_details_scroll: usize,
+    /// Scroll position in the help overlay
+    pub help_scroll: usize,
+    /// Session being renamed, by id
+    pub rename_target: Option<String>,
+    /// Whether the footer shows the MCP servers' status
     pub mcp_mode: bool,
+    /// `--mcp` servers running as last read, oldest first
+    pub mcp_servers: Vec<ServerStatus>,
+    /// tmux version and servers for the header; `None` until read, or if
+    /// tmux couldn't be asked
+    pub server_info: Option<ServerInfo>,
     /// Theme
     pub theme: Theme,
+    /// `[theme]` colors, applied over whichever palette is shown
+    theme_config: ThemeConfig,
+    /// Palette shown, from the config until `c` cycles it
+    pub theme_name: String,
     /// Current input mode
     pub input_mode: InputMode,
     /// Text input buffer
-    pub input_buffer: String,
+    pub input_buffer: TextInput,
     /// Pending action queue
     pub pending_actions: Vec<Action>,
+    /// Full-screen pane preview, when open
+    pub preview: Option<PanePreview>,
+    /// UI state persisted between runs
+    pub state: PersistedState,
+    /// Renderer used for the session list
+    pub list_layout: ListLayout,
+    /// Columns shown by the last list render (sorting cycles through these)
+    pub visible_columns: Vec<Column>,
+    /// Where the last render put the list's rows, and each row's height, for clicks
+    pub list_area: Rect,
+    pub row_heights: Vec<u16>,
+    /// Last click that attached, so the second click of a double-click doesn't attach again
+    last_attach_click: Option<(usize, Instant)>,
+    /// History of deliberately visited sessions for Ctrl-o / Ctrl-i
+    pub jump_list: JumpList,
+    /// Show git branches after session names in the list
+    pub show_branch_in_list: bool,
+    /// Sessions created longer ago than this show their age in the list; 0 never
+    list_old_after_secs: u64,
+    /// Launchers offered for new sessions, in menu order
+    pub launchers: Vec<Launcher>,
+    /// Highlighted launcher in the menu
+    pub launcher_selected: usize,
+    /// Name of the session being created, once typed
+    pub create_name: Option<String>,
+    /// Command typed for the session being created, replacing its launcher's
+    create_command: Option<String>,
+    /// Launchers of sessions still being created, by name, recorded once they exist
+    creating: BTreeMap<String, Launcher>,
+    /// Sessions just created running a command: name and command by id, and when
+    launched: BTreeMap<String, (String, String, Instant)>,
+    /// Where new worktrees go and the repository to fall back on
+    worktree_config: WorktreeConfig,
+    /// Repository the worktree being created branches from, and its branch once typed
+    pub worktree_target: Option<(PathBuf, Option<String>)>,
+    /// Deleted session and the worktree the removal prompt offers to remove
+    pub worktree_removal: Option<(String, Worktree)>,
+    /// Scripts from the config run against the selected session
+    pub custom_commands: Vec<CustomCommand>,
+    /// Highlighted row of the commands menu
+    pub command_selected: usize,
+    /// `[[template]]` entries of the config
+    pub templates: Vec<Template>,
+    /// Highlighted template in the templates menu
+    pub template_selected: usize,
+    /// Ask which window to attach to when a session has several
+    pick_window: bool,
+    /// Session whose windows the picker offers, and those windows
+    pub window_choice: Option<(String, Vec<TmuxWindow>)>,
+    /// Highlighted row of the window picker
+    pub window_selected: usize,
+    /// Terminals attached to the selected session, by its ID
+    pub clients: Option<(String, Vec<AttachedClient>)>,
+    /// Highlighted row of the client picker
+    pub client_selected: usize,
+    /// Frame of the busy spinner, advanced by [`Action::Tick`]
+    pub spinner_frame: u64,
+    /// Drops held keys that delete, restart or confirm
+    repeats: RepeatLimiter,
+    /// Recent status changes by session, for the dashboard
+    pub status_timeline: StatusTimeline,
+    /// The single-session dashboard is open, possibly under a dialog it opened
+    pub dashboard_open: bool,
+    /// Windows of the session the dashboard shows
+    pub dashboard_windows: Vec<TmuxWindow>,
+    /// Open directory picker, and what it's choosing
+    pub dir_picker: Option<(DirPurpose, DirPicker)>,
+    /// Directory `y` copies the skeleton of
+    pub skeleton_root: PathBuf,
+    /// Recent log lines, filled by the tracing layer
+    pub logs: LogBuffer,
+    /// Least severe level the log viewer shows
+    pub log_filter: tracing::Level,
+    /// First line the log viewer shows, or `None` to follow the newest
+    pub log_top: Option<usize>,
+    /// Name of the session whose note is being edited
+    pub note_target: Option<String>,
+    /// Whether the note dialog edits the note or the description
+    pub note_kind: NoteKind,
+    /// Name of the session whose tags are being edited
+    pub tag_target: Option<String>,
+    /// Highlighted row of the tag filter menu; 0 is every session
+    pub tag_selected: usize,
+    /// Session name and, once typed, Unix time of the prompt being scheduled
+    pub schedule_target: Option<(String, Option<u64>)>,
+    /// Highlighted row of the schedules popup
+    pub schedule_selected: usize,
+    /// Rules from the config and those added here
+    pub rules: RuleBook,
+    /// Rules fired this run, oldest first
+    pub rule_history: Vec<RuleRun>,
+    /// Id for the next history entry
+    next_rule_run: u64,
+    /// Session a rule is being added for
+    pub rule_target: Option<String>,
+    /// Highlighted row of the rules popup
+    pub rules_selected: usize,
+    /// When the last session poll arrived
+    pub last_update: Option<Instant>,
+    /// Session ids marked for multi-session actions
+    pub marked: BTreeSet<String>,
+    /// Only sessions whose names contain this, ignoring case, are listed
+    pub session_filter: String,
+    /// Include Error and agentless sessions in the broadcast
+    pub broadcast_force: bool,
+    /// Outcomes of the last broadcast
+    pub broadcast_results: Vec<BroadcastResult>,
+    /// Background operations still running, by id, with a description
+    pub operations: BTreeMap<u64, String>,
+    /// Id for the next background operation
+    next_operation: u64,
+    /// Operation shown while a manual refresh waits for its poll
+    refresh_op: Option<u64>,
+    /// Another instance owns this tmux server; don't change sessions
+    pub read_only: bool,
+    /// Snapshot to compare with the first poll, offering to restore what's missing
+    restore_offer: Option<Snapshot>,
+    /// Snapshotted sessions the restore prompt offers to recreate
+    pub missing_sessions: Vec<SessionSnapshot>,
+    /// Summarise the sessions needing attention once the first poll arrives
+    digest_offer: bool,
+    /// Sessions the startup digest lists
+    pub digest: Vec<DigestEntry>,
+    /// Idle policy from the config
+    pub policy: IdlePolicy,
+    /// Seconds the activity sparklines cover
+    activity_window_secs: u64,
+    /// Poll rate of each session, for the detail pane and `p`
+    poll_rates: PollRates,
+    /// Rings for sessions that need attention; `m` mutes it
+    pub bell: Bell,
+    /// Sessions that have stayed in a status too long
+    pub alerts: AlertTracker,
+    /// When the idle policy is next evaluated; `None` until the first poll
+    next_policy_check: Option<Instant>,
+    /// Sessions the idle policy flagged, by id, with the activity they were flagged at
+    pub stale: BTreeMap<String, u64>,
+    /// Stale sessions the cleanup prompt offers to close
+    pub cleanup_candidates: Vec<Verdict>,
+    /// Sessions being archived and closed, so they aren't queued twice
+    enforcing: BTreeSet<String>,
+    /// When usage is next sampled
+    next_usage_sample: Option<Instant>,
+    /// Terminal area of the last frame or resize
+    pub area: Rect,
+    /// Session the next poll selects, if `returned_from` couldn't find it
+    return_to: Option<String>,
+    /// Ask before quitting while agents are busy or waiting
+    confirm_quit: bool,
+    /// Mode to go back to if quitting is cancelled
+    quit_from: InputMode,
+    /// Id and name of the session last attached to, which ` returns to
+    pub last_attached: Option<(String, String)>,
+    /// Terminal focus as last reported; `None` until the terminal reports it
+    pub focused: Option<bool>,
+    /// Something visible changed since the last `take_dirty`
+    dirty: bool,
 }
 
 impl App {
-    pub fn new() -> Self {
+    pub fn new(config: &Config, state: PersistedState) -> Self {
+        let rules = RuleBook::new(&config.rules, &state.rules);
         let mut list_state = ListState::default();
         list_state.select(Some(0));
 
         Self {
             sessions: Vec::new(),
+            rows: Vec::new(),
             list_state,
-            error_message: None,
+            notifications: Notifications::default(),
+            error_details: None,
+            error_details_scroll: 0,
+            help_scroll: 0,
+            rename_target: None,
             mcp_mode: false,
-            theme: Theme::default(),
+            mcp_servers: Vec::new(),
+            server_info: None,
+            theme: Theme::with_palette(
+                &config.theme.palette(&config.theme.name),
+                config.colors.unwrap_or_else(ColorSupport::detect),
+                config.status_icons.clone(),
+            ),
+            theme_config: config.theme.clone(),
+            theme_name: config.theme.name.clone(),
             input_mode: InputMode::Normal,
-            input_buffer: String::new(),
+            input_buffer: TextInput::default(),
             pending_actions: Vec::new(),
+            preview: None,
+            state,
+            list_layout: config.list_layout,
+            visible_columns: Column::ALL.to_vec(),
+            list_area: Rect::default(),
+            row_heights: Vec::new(),
+            last_attach_click: None,
+            jump_list: JumpList::default(),
+            show_branch_in_list: config.list_show_branch,
+            list_old_after_secs: config.list_old_after_secs,
+            launchers: launcher::menu(&config.launchers),
+            launcher_selected: 0,
+            create_name: None,
+            create_command: None,
+            launched: BTreeMap::new(),
+            creating: BTreeMap::new(),
+            worktree_config: config.worktrees.clone(),
+            worktree_target: None,
+            worktree_removal: None,
+            custom_commands: config.commands.clone(),
+            command_selected: 0,
+            templates: config.templates.clone(),
+            template_selected: 0,
+            pick_window: config.attach.pick_window,
+            window_choice: None,
+            window_selected: 0,
+            clients: None,
+            client_selected: 0,
+            spinner_frame: 0,
+            repeats: RepeatLimiter::default(),
+            status_timeline: StatusTimeline::default(),
+            dashboard_open: false,
+            dashboard_windows: Vec::new(),
+            dir_picker: None,
+            skeleton_root: PathBuf::from("."),
+            logs: LogBuffer::default(),
+            log_filter: tracing::Level::TRACE,
+            log_top: None,
+            note_target: None,
+            tag_target: None,
+            tag_selected: 0,
+            note_kind: NoteKind::Note,
+            schedule_target: None,
+            schedule_selected: 0,
+            rules,
+            rule_history: Vec::new(),
+            next_rule_run: 0,
+            rule_target: None,
+            rules_selected: 0,
+            last_update: None,
+            marked: BTreeSet::new(),
+            session_filter: String::new(),
+            broadcast_force: false,
+            broadcast_results: Vec::new(),
+            operations: BTreeMap::new(),
+            next_operation: 0,
+            refresh_op: None,
+            read_only: false,
+            restore_offer: None,
+            missing_sessions: Vec::new(),
+            digest_offer: false,
+            digest: Vec::new(),
+            policy: config.idle_policy.clone(),
+            bell: Bell::new(config.bell.clone()),
+            alerts: AlertTracker::new(config.alerts.clone()),
+            activity_window_secs: config.activity_window_secs,
+            poll_rates: PollRates::new(
+                Duration::from_millis(config.poll_fast_interval_ms),
+                Duration::from_millis(config.poll_slow_interval_ms),
+                config.poll_rates.clone(),
+            ),
+            next_policy_check: None,
+            stale: BTreeMap::new(),
+            cleanup_candidates: Vec::new(),
+            enforcing: BTreeSet::new(),
+            next_usage_sample: None,
+            area: Rect::default(),
+            return_to: None,
+            confirm_quit: config.confirm_quit,
+            quit_from: InputMode::Normal,
+            last_attached: None,
+            focused: None,
+            dirty: true,
         }
     }
 
-    /// Get the currently selected session
-    pub fn selected_session(&self) -> Option<&TmuxSession> {
-        self.list_state
-            .selected()
-            .and_then(|i| self.sessions.get(i))
+    /// Offer to restore sessions from `snapshot` that the first poll doesn't find
+    pub fn offer_restore(&mut self, snapshot: Snapshot) {
+        if !self.read_only {
+            self.restore_offer = Some(snapshot);
+        }
     }
 
-    /// Take pending actions (drains the queue)
-    pub fn take_pending_actions(&mut self) -> Vec<Action> {
-        std::mem::take(&mut self.pending_actions)
+    /// List the sessions needing attention once the first poll arrives
+    pub fn offer_digest(&mut self) {
+        self.digest_offer = true;
     }
 
-    /// Handle an action and return whether to quit
-    pub fn handle_action(&mut self, action: Action) -> Result<bool> {
-        match action {
-            Action::KeyPress(key) => self.handle_key(key),
-            Action::SessionsUpdated(sessions) => {
-                self.sessions = sessions;
-                // Ensure selection is valid
-                if let Some(selected) = self.list_state.selected() {
-                    if selected >= self.sessions.len() && !self.sessions.is_empty() {
-                        self.list_state.select(Some(self.sessions.len() - 1));
-                    }
-                }
-                Ok(false)
-            }
-            Action::Error(msg) => {
-                self.error_message = Some(msg);
-                Ok(false)
+    /// Periodic snapshots wait until a restore offer is settled so they can't
+    /// overwrite the sessions being offered
+    pub fn can_snapshot(&self) -> bool {
+        !self.read_only
+            && self.restore_offer.is_none()
+            && self.input_mode != InputMode::ConfirmingRestore
+    }
+
+    /// Get the currently selected session
+    /// The selected session, or the session of the selected window
+    pub fn selected_session(&self) -> Option<&TmuxSession> {
+        match self.list_state.selected().and_then(|i| self.rows.get(i)) {
+            Some(ListRow::Session(index) | ListRow::Window(index, _)) => self.sessions.get(*index),
+            _ => None,
+        }
+    }
+
+    fn selected_session_mut(&mut self) -> Option<&mut TmuxSession> {
+        match self.list_state.selected().and_then(|i| self.rows.get(i)) {
+            Some(ListRow::Session(index) | ListRow::Window(index, _)) => {
+                self.sessions.get_mut(*index)
             }
-            Action::Quit => Ok(true),
-            _ => Ok(false),
+            _ => None,
         }
     }
 
-    fn handle_key(&mut self, key: KeyEvent) -> Result<bool> {
-        // Clear error message on any key press
-        if self.error_message.is_some() && self.input_mode == InputMode::Normal {
-            self.error_message = None;
+    /// The selected window row's window
+    pub fn selected_window(&self) -> Option<&TmuxWindow> {
+        match self.list_state.selected().and_then(|i| self.rows.get(i))? {
+            ListRow::Window(index, window) => self.window_row(*index, *window),
+            _ => None,
         }
+    }
 
-        match self.input_mode {
-            InputMode::Normal => self.handle_normal_key(key),
-            InputMode::Creating => self.handle_creating_key(key),
-            InputMode::Confirming => self.handle_confirming_key(key),
+    fn window_row(&self, index: usize, window: usize) -> Option<&TmuxWindow> {
+        self.sessions.get(index)?.window_tree.as_ref()?.get(window)
+    }
+
+    fn selected_key(&self) -> Option<RowKey> {
+        match self.list_state.selected().and_then(|i| self.rows.get(i))? {
+            ListRow::Session(index) => Some(RowKey::Session(self.sessions.get(*index)?.id.clone())),
+            ListRow::Window(index, window) => Some(RowKey::Window(
+                self.sessions.get(*index)?.id.clone(),
+                self.window_row(*index, *window)?.index,
+            )),
+            ListRow::Header { group, .. } => Some(RowKey::Header(*group)),
+            ListRow::Server(_) => None,
         }
     }
 
-    fn handle_normal_key(&mut self, key: KeyEvent) -> Result<bool> {
-        match key.code {
-            KeyCode::Char('q') => return Ok(true),
-            KeyCode::Char('j') | KeyCode::Down => self.next_session(),
-            KeyCode::Char('k') | KeyCode::Up => self.previous_session(),
-            KeyCode::Char('M') => self.mcp_mode = !self.mcp_mode,
-            KeyCode::Enter => {
-                if let Some(session) = self.selected_session() {
-                    self.pending_actions
-                        .push(Action::AttachSession(session.id.clone()));
-                }
-            }
-            KeyCode::Char('n') => {
-                self.input_mode = InputMode::Creating;
-                self.input_buffer.clear();
-            }
-            KeyCode::Char('d') => {
-                if self.selected_session().is_some() {
-                    self.input_mode = InputMode::Confirming;
+    /// Rebuild the list rows, keeping the selection on `keep` when it is still shown
+    fn rebuild_rows(&mut self, keep: Option<RowKey>) {
+        let fallback = self.list_state.selected().unwrap_or(0);
+        let rows = session_list::build_rows(
+            &self.sessions,
+            &self.session_filter,
+            self.state.list_display,
+            &self.state.collapsed_groups,
+            self.state.sort_by,
+            &self.state.attach_history,
+            &self.state.session_order,
+        );
+        self.rows = session_list::with_windows(rows, |i| {
+            self.sessions[i].window_tree.as_ref().map_or(0, Vec::len)
+        });
+
+        let found = keep.and_then(|key| {
+            self.rows.iter().position(|row| match (row, &key) {
+                (ListRow::Session(i), RowKey::Session(id)) => &self.sessions[*i].id == id,
+                (ListRow::Window(i, w), RowKey::Window(id, index)) => {
+                    &self.sessions[*i].id == id
+                        && self.window_row(*i, *w).map(|w| w.index) == Some(*index)
                 }
+                (ListRow::Header { group, .. }, RowKey::Header(g)) => group == g,
+                _ => false,
+            })
+        });
+        let selected = session_list::nearest_selectable(&self.rows, found.unwrap_or(fallback));
+        self.list_state.select(selected);
+    }
+
+    /// Select a session by id, expanding its group if it is collapsed
+    pub fn select_session_id(&mut self, id: &str) -> bool {
+        let Some(session) = self.sessions.iter().find(|s| s.id == id) else {
+            return false;
+        };
+        if self.state.list_display == ListDisplay::Grouped {
+            let group = StatusGroup::of(session.status);
+            if self.state.collapsed_groups.remove(&group) {
+                self.save_state();
             }
-            KeyCode::Char('y') => {
-                self.pending_actions.push(Action::CopySkeleton);
-            }
-            KeyCode::Char('c') if key.modifiers.contains(KeyModifiers::CONTROL) => {
-                return Ok(true);
-            }
-            _ => {}
         }
-        Ok(false)
+        self.rebuild_rows(Some(RowKey::Session(id.to_string())));
+        true
     }
 
-    fn handle_creating_key(&mut self, key: KeyEvent) -> Result<bool> {
-        match key.code {
-            KeyCode::Enter => {
-                if !self.input_buffer.is_empty() {
-                    let name = self.input_buffer.clone();
-                    self.pending_actions.push(Action::CreateSession(name));
-                    self.input_buffer.clear();
-                }
-                self.input_mode = InputMode::Normal;
-            }
-            KeyCode::Esc => {
-                self.input_buffer.clear();
-                self.input_mode = InputMode::Normal;
-            }
-            KeyCode::Char(c) => {
-                // Only allow valid session name characters
-                if c.is_alphanumeric() || c == '-' || c == '_' {
-                    self.input_buffer.push(c);
-                }
-            }
-            KeyCode::Backspace => {
-                self.input_buffer.pop();
-            }
-            _ => {}
+    /// Poll now, with a spinner until the result arrives; repeats meanwhile are ignored
+    fn request_refresh(&mut self) {
+        if self.refresh_op.is_none() {
+            self.refresh_op = Some(self.start_operation("Refreshing"));
+            self.pending_actions.push(Action::Refresh);
         }
-        Ok(false)
     }
 
-    fn handle_confirming_key(&mut self, key: KeyEvent) -> Result<bool> {
-        match key.code {
-            KeyCode::Char('y') | KeyCode::Char('Y') => {
-                if let Some(session) = self.selected_session() {
-                    self.pending_actions
-                        .push(Action::DeleteSession(session.id.clone()));
-                }
-                self.input_mode = InputMode::Normal;
-            }
-            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
-                self.input_mode = InputMode::Normal;
-            }
-            _ => {}
+    fn finish_refresh(&mut self) {
+        if let Some(op) = self.refresh_op.take() {
+            self.operations.remove(&op);
         }
-        Ok(false)
     }
 
-    fn next_session(&mut self) {
-        if self.sessions.is_empty() {
-            return;
+    /// Capture the previewed pane again if the preview follows its output
+    fn refresh_preview(&mut self) {
+        if let Some(preview) = &self.preview
+            && preview.viewport.is_following()
+        {
+            self.pending_actions
+                .push(Action::CapturePane(preview.session_id.clone()));
         }
-        let i = match self.list_state.selected() {
-            Some(i) => {
-                if i >= self.sessions.len() - 1 {
-                    0
-                } else {
-                    i + 1
-                }
-            }
-            None => 0,
+    }
+
+    /// Something on screen moves by itself, so every frame tick should redraw
+    pub fn is_animating(&self) -> bool {
+        !self.operations.is_empty() && !self.in_background()
+    }
+
+    /// Busy sessions show a spinner in the list, so ticks should advance it
+    pub fn is_spinning(&self) -> bool {
+        self.theme.icons.spinner
+            && !self.in_background()
+            && self.sessions.iter().any(|s| s.status == AgentStatus::Busy)
+    }
+
+    /// The terminal reported losing focus; `false` for terminals that never report it
+    pub fn in_background(&self) -> bool {
+        self.focused == Some(false)
+    }
+
+    /// Put the list back on `session_id` after attaching to it or editing in it
+    ///
+    /// What the footer said before is stale by now, sticky failures aside. If
+    /// the session isn't listed yet, the next poll gets one more chance to
+    /// select it.
+    pub fn returned_from(&mut self, session_id: &str) {
+        self.notifications.clear_transient();
+        if self.current_error_details().is_none() {
+            self.error_details = None;
+        }
+        self.jump_list.push(session_id);
+        self.return_to = (!self.select_session_id(session_id)).then(|| session_id.to_string());
+    }
+
+    /// Note `session_id` as the session ` attaches to again
+    pub fn attached(&mut self, session_id: &str) {
+        let name = self
+            .sessions
+            .iter()
+            .find(|s| s.id == session_id)
+            .map_or_else(|| session_id.to_string(), |s| s.name.clone());
+        self.last_attached = Some((session_id.to_string(), name));
+    }
+
+    /// Attach to the session last attached to; if it's gone, say so and go
+    /// to the top of the list
+    fn attach_last(&mut self) {
+        let Some((id, name)) = self.last_attached.clone() else {
+            self.notify(Level::Info, "No session attached yet");
+            return;
         };
-        self.list_state.select(Some(i));
+        if !self.sessions.iter().any(|s| s.id == id) {
+            self.notify(Level::Warning, format!("'{}' no longer exists", name));
+            let top = self
+                .rows
+                .iter()
+                .position(|row| matches!(row, ListRow::Session(_)));
+            self.list_state.select(top.or(Some(0)));
+            return;
+        }
+        self.select_session_id(&id);
+        if self.selected_session().is_none_or(|s| s.id != id) {
+            // Hidden by the filter
+            self.session_filter.clear();
+            self.select_session_id(&id);
+        }
+        self.attach_selected(Action::attach);
     }
 
-    fn previous_session(&mut self) {
-        if self.sessions.is_empty() {
+    /// Attach to the selected session, noting when for the recently-used sort
+    fn attach_selected(&mut self, attach: fn(String) -> Action) {
+        let Some(session) = self.selected_session() else {
             return;
+        };
+        let (id, name) = (session.id.clone(), session.name.clone());
+        self.pending_actions.push(attach(id.clone()));
+        self.record_jump();
+        self.state.attach_history.record(&name, text::unix_now());
+        self.save_state();
+        if self.state.sort_by == Some(Column::Used) {
+            self.rebuild_rows(Some(RowKey::Session(id)));
         }
-        let i = match self.list_state.selected() {
-            Some(i) => {
-                if i == 0 {
-                    self.sessions.len() - 1
-                } else {
-                    i - 1
-                }
-            }
-            None => 0,
+    }
+
+    /// Attach to the `number`th session listed, counting from 1 as the list
+    /// shows them; nothing if there are fewer
+    fn attach_number(&mut self, number: usize) {
+        let Some(index) = self
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| matches!(row, ListRow::Session(_)))
+            .nth(number.wrapping_sub(1))
+            .map(|(i, _)| i)
+        else {
+            return;
         };
-        self.list_state.select(Some(i));
+        self.list_state.select(Some(index));
+        self.attach_selected(Action::attach);
     }
 
-    pub fn render(&mut self, frame: &mut Frame) {
-        let chunks = Layout::default()
-            .direction(Direction::Vertical)
-            .constraints([
-                Constraint::Length(3), // Header
-                Constraint::Min(0),    // Main content
-                Constraint::Length(3), // Footer/status
-            ])
-            .split(frame.area());
+    /// Attach to the selected window row's window, or else to the selected session
+    fn attach_row(&mut self) {
+        match self.selected_key() {
+            Some(RowKey::Window(id, index)) => {
+                self.pending_actions
+                    .push(Action::SelectWindow(tmux::window_target(&id, index)));
+                self.attach_selected(Action::attach);
+            }
+            _ => self.attach_or_pick_window(),
+        }
+    }
 
-        self.render_header(frame, chunks[0]);
-        self.render_main(frame, chunks[1]);
-        self.render_footer(frame, chunks[2]);
+    /// Show the selected session's windows under it
+    fn expand_windows(&mut self) {
+        if let Some(session) = self.selected_session_mut()
+            && session.window_tree.is_none()
+        {
+            session.window_tree = Some(Vec::new());
+            let id = session.id.clone();
+            self.pending_actions.push(Action::ListWindowTree(id));
+        }
+    }
 
-        // Render modal dialogs on top
-        match self.input_mode {
-            InputMode::Creating => self.render_create_dialog(frame),
-            InputMode::Confirming => self.render_confirm_dialog(frame),
-            InputMode::Normal => {}
+    /// Hide the selected session's windows, selecting the session, or else
+    /// collapse its group
+    fn collapse_selected(&mut self) {
+        if let Some(session) = self.selected_session_mut()
+            && session.window_tree.take().is_some()
+        {
+            let id = session.id.clone();
+            self.rebuild_rows(Some(RowKey::Session(id)));
+        } else {
+            self.set_selected_group_collapsed(true);
         }
     }
 
-    fn render_header(&self, frame: &mut Frame, area: Rect) {
-        let title = Paragraph::new(Line::from(vec![
-            Span::styled(
-                " AgentRusty ",
-                Style::default()
-                    .fg(self.theme.accent)
-                    .add_modifier(Modifier::BOLD),
-            ),
-            Span::styled(
-                "│ Mission Control for AI Agents",
-                Style::default().fg(self.theme.dim),
-            ),
-        ]))
-        .block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(self.theme.dim)),
-        );
-        frame.render_widget(title, area);
+    /// Attach to the selected session, asking which window first if it has several
+    fn attach_or_pick_window(&mut self) {
+        match self.selected_session() {
+            Some(session) if self.pick_window && session.windows > 1 => {
+                let id = session.id.clone();
+                self.pending_actions.push(Action::ListWindows(id));
+            }
+            _ => self.attach_selected(Action::attach),
+        }
     }
 
-    fn render_main(&mut self, frame: &mut Frame, area: Rect) {
-        let chunks = Layout::default()
-            .direction(Direction::Horizontal)
-            .constraints([
-                Constraint::Percentage(40), // Session list
-                Constraint::Percentage(60), // Detail pane
-            ])
-            .split(area);
+    /// Move the selected session to the next poll rate, dropping the
+    /// override once it's back to what the config gives
+    fn cycle_poll_rate(&mut self) {
+        let Some(name) = self.selected_session().map(|s| s.name.clone()) else {
+            return;
+        };
+        let rate = self.poll_rates.rate(&name, &self.state.poll_rates).next();
+        if rate == self.poll_rates.rate(&name, &BTreeMap::new()) {
+            self.state.poll_rates.remove(&name);
+        } else {
+            self.state.poll_rates.insert(name.clone(), rate);
+        }
+        self.save_state();
+        self.notify(Level::Info, format!("Polling {} {}", name, rate.label()));
+    }
 
-        self.render_session_list(frame, chunks[0]);
-        self.render_detail_pane(frame, chunks[1]);
+    /// Whether a session was created longer ago than the list's threshold
+    fn is_old(&self, session: &TmuxSession) -> bool {
+        self.list_old_after_secs > 0
+            && session.created_at > 0
+            && text::unix_now().saturating_sub(session.created_at) > self.list_old_after_secs
     }
 
-    fn render_session_list(&mut self, frame: &mut Frame, area: Rect) {
-        let items: Vec<ListItem> = if self.sessions.is_empty() {
-            vec![ListItem::new(Line::from(Span::styled(
-                "  No sessions found. Press 'n' to create one.",
-                Style::default().fg(self.theme.dim),
-            )))]
-        } else {
-            self.sessions
-                .iter()
-                .map(|session| {
-                    let status_icon = match session.status {
-                        AgentStatus::Busy => {
-                            Span::styled("● ", Style::default().fg(self.theme.warning))
-                        }
-                        AgentStatus::Idle => {
-                            Span::styled("● ", Style::default().fg(self.theme.success))
-                        }
-                        AgentStatus::WaitingForInput => {
-                            Span::styled("? ", Style::default().fg(self.theme.accent))
-                        }
-                        AgentStatus::Error => {
-                            Span::styled("✗ ", Style::default().fg(self.theme.error))
-                        }
-                        AgentStatus::Unknown => {
-                            Span::styled("○ ", Style::default().fg(self.theme.dim))
-                        }
-                    };
+    /// Show the next built-in palette, keeping the config's colors on top
+    fn cycle_theme(&mut self) {
+        let next = PALETTES
+            .iter()
+            .position(|&name| name == self.theme_name)
+            .map_or(0, |i| (i + 1) % PALETTES.len());
+        self.theme_name = PALETTES[next].to_string();
+        self.theme = Theme::with_palette(
+            &self.theme_config.palette(&self.theme_name),
+            self.theme.support(),
+            self.theme.icons.clone(),
+        );
+        self.notify(Level::Info, format!("Theme: {}", self.theme_name));
+    }
 
-                    let name = Span::styled(&session.name, Style::default().fg(self.theme.fg));
+    /// Record the selected session in the jump list
+    fn record_jump(&mut self) {
+        if let Some(id) = self.selected_session().map(|s| s.id.clone()) {
+            self.jump_list.push(&id);
+        }
+    }
 
-                    ListItem::new(Line::from(vec![status_icon, name]))
-                })
-                .collect()
-        };
+    fn jump_back(&mut self) {
+        let selected = self.selected_session().map(|s| s.id.clone());
+        let sessions = &self.sessions;
+        let target = self
+            .jump_list
+            .back(selected.as_deref(), |id| {
+                sessions.iter().any(|s| s.id == id)
+            })
+            .map(str::to_string);
+        if let Some(id) = target {
+            self.select_session_id(&id);
+        }
+    }
 
-        let list = List::new(items)
-            .block(
-                Block::default()
-                    .title(" Sessions ")
-                    .borders(Borders::ALL)
-                    .border_style(Style::default().fg(self.theme.dim)),
-            )
-            .highlight_style(
-                Style::default()
-                    .bg(Color::Rgb(50, 50, 50))
-                    .add_modifier(Modifier::BOLD),
-            )
-            .highlight_symbol("▶ ");
+    fn jump_forward(&mut self) {
+        let sessions = &self.sessions;
+        let target = self
+            .jump_list
+            .forward(|id| sessions.iter().any(|s| s.id == id))
+            .map(str::to_string);
+        if let Some(id) = target {
+            self.select_session_id(&id);
+        }
+    }
 
-        frame.render_stateful_widget(list, area, &mut self.list_state);
+    /// Change the schedules on disk, which the CLI may have changed since we last looked
+    fn update_schedules<R>(&mut self, f: impl FnOnce(&mut PersistedState) -> R) -> R {
+        self.state.reload_schedules();
+        let result = f(&mut self.state);
+        self.save_state();
+        result
     }
 
-    fn render_detail_pane(&self, frame: &mut Frame, area: Rect) {
-        let content = if let Some(session) = self.selected_session() {
-            vec![
-                Line::from(vec![
-                    Span::styled("Name: ", Style::default().fg(self.theme.dim)),
-                    Span::styled(&session.name, Style::default().fg(self.theme.fg)),
+    /// Queue sending the scheduled prompts due at `now`
+    ///
+    /// They are removed from the state file first, so each fires at most once.
+    /// A read-only instance leaves them to the instance that owns the server,
+    /// and nothing is sent before the first poll says which sessions exist.
+    pub fn queue_due_schedules(&mut self, now: u64) {
+        if self.read_only || self.last_update.is_none() {
+            self.state.reload_schedules();
+            return;
+        }
+        let due = self.update_schedules(|state| state.take_due(now));
+        for scheduled in due {
+            let id = self
+                .sessions
+                .iter()
+                .find(|s| s.name == scheduled.session)
+                .map(|s| s.id.clone());
+            self.pending_actions
+                .push(Action::SendScheduled(scheduled, id));
+        }
+    }
+
+    /// Fire the rules matching `transitions`
+    ///
+    /// A read-only instance leaves rules to the instance that owns the server.
+    fn apply_rules(&mut self, transitions: &[Transition]) {
+        if self.read_only {
+            return;
+        }
+        let spent = self.state.rule_flags.spent.len();
+        let firings = self.rules.evaluate(transitions, &mut self.state.rule_flags);
+        if self.state.rule_flags.spent.len() != spent {
+            self.save_state();
+        }
+        for firing in firings {
+            let id = self.next_rule_run;
+            self.next_rule_run += 1;
+            let action = firing.action.describe();
+            tracing::info!(
+                "Rule '{}' fired by {} going {:?}: {}",
+                firing.rule,
+                firing.transition.session,
+                firing.transition.status,
+                action
+            );
+            self.rule_history.push(RuleRun {
+                id,
+                at: text::unix_now(),
+                rule: firing.rule.clone(),
+                session: firing.transition.session.clone(),
+                action,
+                outcome: None,
+            });
+            let target = match &firing.action {
+                RuleAction::Send { session, .. } => self
+                    .sessions
+                    .iter()
+                    .find(|s| &s.name == session)
+                    .map(|s| s.id.clone()),
+                RuleAction::Notify(text) => {
+                    self.notify(
+                        Level::Info,
+                        format!("{}: {}", firing.transition.session, text),
+                    );
+                    None
+                }
+                RuleAction::Run(_) => None,
+            };
+            self.pending_actions
+                .push(Action::FireRule(id, firing, target));
+        }
+        let excess = self.rule_history.len().saturating_sub(rules::HISTORY_LEN);
+        self.rule_history.drain(..excess);
+    }
+
+    /// Save the dashboard's rules after adding or deleting one
+    fn save_rules(&mut self) {
+        self.state.rules = self.rules.local_specs();
+        self.save_state();
+    }
+
+    /// Apply the idle policy to the current sessions
+    ///
+    /// Flagged sessions get a stale badge, and on the first evaluation a
+    /// prompt offers to close them. Sessions the policy kills are archived
+    /// first; a read-only instance only flags them.
+    pub fn enforce_policy(&mut self, now: u64, first: bool) {
+        let verdicts = policy::evaluate(&self.policy, &self.sessions, now);
+        let activity: BTreeMap<&str, u64> = self
+            .sessions
+            .iter()
+            .map(|s| (s.id.as_str(), s.activity_at))
+            .collect();
+        let mut stale = BTreeMap::new();
+        for verdict in verdicts {
+            let kill = verdict.action == PolicyAction::Kill && !self.read_only;
+            if kill {
+                if self.enforcing.insert(verdict.session_id.clone()) {
+                    tracing::info!(
+                        "Idle policy: archiving and closing '{}', idle for {}",
+                        verdict.name,
+                        text::format_duration_long(verdict.idle_secs)
+                    );
+                    self.pending_actions.push(Action::ArchiveAndKill(verdict));
+                }
+                continue;
+            }
+            if !self.stale.contains_key(&verdict.session_id) {
+                tracing::info!(
+                    "Idle policy: flagged '{}' as stale, idle for {}",
+                    verdict.name,
+                    text::format_duration_long(verdict.idle_secs)
+                );
+            }
+            let at = activity.get(verdict.session_id.as_str()).copied();
+            stale.insert(verdict.session_id.clone(), at.unwrap_or(0));
+            if first && !self.read_only {
+                self.cleanup_candidates.push(verdict);
+            }
+        }
+        self.stale = stale;
+        if !self.cleanup_candidates.is_empty() && self.input_mode == InputMode::Normal {
+            self.input_mode = InputMode::ConfirmingCleanup;
+        } else {
+            self.cleanup_candidates.clear();
+        }
+    }
+
+    /// Evaluate the idle policy if it's due; stale flags clear as soon as a session is active
+    fn check_policy(&mut self) {
+        let sessions = &self.sessions;
+        self.stale.retain(|id, at| {
+            sessions
+                .iter()
+                .any(|s| &s.id == id && s.activity_at == *at && policy::is_dormant(s))
+        });
+        if !self.policy.enabled() || self.next_policy_check.is_some_and(|at| Instant::now() < at) {
+            return;
+        }
+        let first = self.next_policy_check.is_none();
+        self.next_policy_check = Some(Instant::now() + POLICY_INTERVAL);
+        self.enforce_policy(text::unix_now(), first);
+    }
+
+    /// Ask for the agents' usage totals if a sample is due
+    ///
+    /// A read-only instance leaves sampling to the one that owns the server.
+    /// Carry out the alerts for sessions that have now been in a status too long
+    ///
+    /// A read-only instance only shows them; the other instance notifies.
+    fn check_alerts(&mut self) {
+        let fired = self.alerts.observe(&self.sessions, Instant::now());
+        if fired.is_empty() {
+            return;
+        }
+        self.dirty = true;
+        let mut ring = false;
+        for alert in fired {
+            tracing::info!(
+                "Alert: {} {:?} for {}s",
+                alert.session,
+                alert.rule.status,
+                alert.rule.for_secs
+            );
+            match alert.rule.action {
+                AlertAction::Notify | AlertAction::Hook if !self.read_only => {
+                    self.pending_actions.push(Action::FireAlert(alert));
+                }
+                AlertAction::Bell => ring = true,
+                AlertAction::Notify | AlertAction::Hook | AlertAction::Highlight => {}
+            }
+        }
+        if ring && !self.bell.muted {
+            self.pending_actions.push(Action::RingBell);
+        }
+    }
+
+    /// Warn about sessions that vanished right after being created with a command
+    fn check_launched(&mut self) {
+        let now = Instant::now();
+        let mut exited = Vec::new();
+        self.launched.retain(|id, (name, command, at)| {
+            let age = now.duration_since(*at);
+            if self.sessions.iter().any(|s| &s.id == id) {
+                age < EARLY_EXIT
+            } else if age < LAUNCH_GRACE {
+                true
+            } else {
+                exited.push((std::mem::take(name), std::mem::take(command)));
+                false
+            }
+        });
+        for (name, command) in exited {
+            self.warn_exited(&name, Some(&command));
+        }
+    }
+
+    /// Record a session just created and show it selected
+    fn add_created(&mut self, session: TmuxSession) {
+        if let Some(launcher) = self.creating.remove(&session.name) {
+            // A description left by an earlier session of that name no longer applies
+            let described = launcher.command.as_deref().map(|command| {
+                let dir = Some(session.start_path.as_str()).filter(|p| !p.is_empty());
+                description::describe(command, dir.map(Path::new), dirs::home_dir().as_deref())
+            });
+            self.state.descriptions.set(
+                &session.name,
+                described.as_deref().unwrap_or_default(),
+                text::unix_now(),
+            );
+            self.state.launchers.insert(session.name.clone(), launcher);
+            self.save_state();
+        }
+        let id = session.id.clone();
+        if let Some(command) = self
+            .state
+            .launcher(&session.name)
+            .and_then(|l| l.command.clone())
+        {
+            self.launched
+                .insert(id.clone(), (session.name.clone(), command, Instant::now()));
+        }
+        // Show it right away instead of waiting for the next poll
+        if !self.sessions.iter().any(|s| s.id == id) {
+            self.sessions.push(session);
+        }
+        self.rebuild_rows(self.selected_key());
+        self.select_session_id(&id);
+    }
+
+    fn warn_exited(&mut self, name: &str, command: Option<&str>) {
+        let text = match command {
+            Some(command) => format!("Session '{}' ended right away: '{}' exited", name, command),
+            None => format!("Session '{}' ended right away", name),
+        };
+        self.notify(Level::Warning, text);
+    }
+
+    fn check_usage(&mut self) {
+        if self.read_only || self.next_usage_sample.is_some_and(|at| Instant::now() < at) {
+            return;
+        }
+        self.next_usage_sample = Some(Instant::now() + USAGE_INTERVAL);
+        let agents: Vec<TmuxSession> = self
+            .sessions
+            .iter()
+            .filter(|s| !matches!(s.agent_kind(), AgentKind::Shell | AgentKind::Other))
+            .cloned()
+            .collect();
+        if !agents.is_empty() {
+            self.pending_actions.push(Action::SampleUsage(agents));
+        }
+    }
+
+    fn save_state(&mut self) {
+        if let Err(e) = self.state.save() {
+            self.notify(Level::Error, format!("Failed to save state: {}", e));
+        }
+    }
+
+    /// Cycle between the flat and grouped list
+    fn cycle_list_display(&mut self) {
+        let key = self.selected_key();
+        self.state.list_display = self.state.list_display.next();
+        self.rebuild_rows(key);
+        self.save_state();
+    }
+
+    /// Sort by the next visible column, ending back at tmux's order
+    fn cycle_sort(&mut self) {
+        let key = self.selected_key();
+        self.state.sort_by = session_list::next_sort(self.state.sort_by, &self.visible_columns);
+        self.rebuild_rows(key);
+        self.save_state();
+    }
+
+    /// Start a copy of the selected session, named `<name>-2` or the next free
+    /// number, running the same command in the directory it is in now
+    fn duplicate_selected(&mut self) {
+        let Some(session) = self.selected_session() else {
+            return;
+        };
+        let name = launcher::duplicate_name(&session.name, |candidate| {
+            self.sessions.iter().any(|s| s.name == candidate)
+                || self.creating.contains_key(candidate)
+        });
+        let launcher = launcher::duplicate_launcher(
+            self.state.launcher(&session.name),
+            &session.start_command,
+        );
+        let source = session.id.clone();
+        self.creating.insert(name.clone(), launcher.clone());
+        self.pending_actions.push(Action::DuplicateSession(
+            source,
+            NewSession {
+                name,
+                launcher: Some(launcher),
+                cwd: None,
+            },
+        ));
+    }
+
+    /// Move the selected session past its neighbour in the list, down or up,
+    /// into the order kept by hand
+    fn move_selected(&mut self, down: bool) {
+        let Some(index) = self.list_state.selected() else {
+            return;
+        };
+        let Some(ListRow::Session(moved)) = self.rows.get(index).copied() else {
+            return;
+        };
+        if let Some(column) = self.state.sort_by {
+            self.notify(
+                Level::Info,
+                format!(
+                    "Sorted by {}; press s until tmux's order to arrange by hand",
+                    column.label()
+                ),
+            );
+            return;
+        }
+        // The next session in the same section; headers and other servers end it
+        let is_window = |row: &&ListRow| matches!(row, ListRow::Window(..));
+        let neighbour = if down {
+            self.rows[index + 1..].iter().find(|row| !is_window(row))
+        } else {
+            self.rows[..index].iter().rev().find(|row| !is_window(row))
+        };
+        let Some(&ListRow::Session(neighbour)) = neighbour else {
+            return;
+        };
+        let key = self.selected_key();
+        let shown = self
+            .rows
+            .iter()
+            .filter_map(|row| match row {
+                ListRow::Session(i) => Some(*i),
+                _ => None,
+            })
+            .chain(0..self.sessions.len())
+            .map(|i| self.sessions[i].name.as_str());
+        self.state.session_order.place(
+            shown,
+            &self.sessions[moved].name,
+            &self.sessions[neighbour].name,
+            down,
+        );
+        self.rebuild_rows(key);
+        self.save_state();
+    }
+
+    /// Collapse or expand the status group containing the selected row
+    fn set_selected_group_collapsed(&mut self, collapse: bool) {
+        let Some(index) = self.list_state.selected() else {
+            return;
+        };
+        let Some(group) = session_list::group_of_row(&self.rows, index) else {
+            return;
+        };
+        let changed = if collapse {
+            self.state.collapsed_groups.insert(group)
+        } else {
+            self.state.collapsed_groups.remove(&group)
+        };
+        if changed {
+            // Expanding lands on the group's first session via the header position
+            self.rebuild_rows(Some(RowKey::Header(group)));
+            self.save_state();
+        }
+    }
+
+    /// Show `error` in the footer, keeping its full cause chain for the details popup
+    pub fn report_error(&mut self, error: &anyhow::Error) {
+        tracing::warn!("{:#}", error);
+        let summary = error.to_string();
+        let chain: Vec<String> = error.chain().map(ToString::to_string).collect();
+        if chain.len() > 1 {
+            self.error_details = Some(ErrorDetails {
+                summary: summary.clone(),
+                chain,
+            });
+        }
+        self.notify(Level::Error, summary);
+    }
+
+    /// Report a failure that leaves sessions other than asked for, such as a
+    /// failed create or delete; it stays in the footer until Esc
+    pub fn report_sticky_error(&mut self, error: &anyhow::Error) {
+        self.report_error(error);
+        self.notifications.pin_latest();
+    }
+
+    /// Details for the message currently in the footer, if it has any
+    pub fn current_error_details(&self) -> Option<&ErrorDetails> {
+        self.error_details
+            .as_ref()
+            .filter(|d| self.message() == Some(d.summary.as_str()))
+    }
+
+    /// Show `text` in the footer
+    pub fn notify(&mut self, level: Level, text: impl Into<String>) {
+        self.notifications.push(level, text);
+    }
+
+    /// Text of the notification in the footer, if any
+    pub fn message(&self) -> Option<&str> {
+        self.notifications.latest().map(|n| n.text.as_str())
+    }
+
+    /// Record a background operation so the footer can show it; returns its id
+    pub fn start_operation(&mut self, description: impl Into<String>) -> u64 {
+        let id = self.next_operation;
+        self.next_operation += 1;
+        self.operations.insert(id, description.into());
+        id
+    }
+
+    /// Take pending actions (drains the queue)
+    pub fn take_pending_actions(&mut self) -> Vec<Action> {
+        std::mem::take(&mut self.pending_actions)
+    }
+
+    /// Handle an action and return whether to quit
+    pub fn handle_action(&mut self, action: Action) -> Result<bool> {
+        if self.affects_view(&action) {
+            self.dirty = true;
+        }
+        self.apply(action)
+    }
+
+    /// Whether anything on screen changed since the last call
+    pub fn take_dirty(&mut self) -> bool {
+        std::mem::take(&mut self.dirty)
+    }
+
+    /// Whether `action` may change what's on screen
+    ///
+    /// Clocks in the UI are redrawn on a timer, so a poll that found nothing
+    /// new only counts once the data had been shown as stale.
+    fn affects_view(&self, action: &Action) -> bool {
+        let stale = self
+            .last_update
+            .is_none_or(|at| at.elapsed() >= STALE_AFTER);
+        match action {
+            Action::SessionsUpdated(sessions) => {
+                *sessions != self.sessions
+                    || stale
+                    || self.refresh_op.is_some()
+                    || self.restore_offer.is_some()
+                    || self.digest_offer
+                    || self.return_to.is_some()
+            }
+            Action::Heartbeat => stale || self.refresh_op.is_some(),
+            // Only counts once something expired
+            Action::FocusChanged(_) | Action::Tick => false,
+            Action::Mouse(mouse) => matches!(
+                mouse.kind,
+                MouseEventKind::ScrollDown
+                    | MouseEventKind::ScrollUp
+                    | MouseEventKind::Down(MouseButton::Left)
+            ),
+            _ => true,
+        }
+    }
+
+    fn apply(&mut self, action: Action) -> Result<bool> {
+        match action {
+            Action::KeyPress(key) => self.handle_key(key),
+            Action::Paste(text) => {
+                self.handle_paste(&text);
+                Ok(false)
+            }
+            Action::Mouse(mouse) => {
+                self.handle_mouse(mouse);
+                Ok(false)
+            }
+            Action::FocusChanged(focused) => {
+                self.focused = Some(focused);
+                // Catch up on what changed while in the background
+                if focused {
+                    self.pending_actions.push(Action::Refresh);
+                    self.refresh_preview();
+                }
+                Ok(false)
+            }
+            Action::SessionsUpdated(sessions) => {
+                self.finish_refresh();
+                let key = self.selected_key();
+                let mut expanded: BTreeMap<String, Vec<TmuxWindow>> = self
+                    .sessions
+                    .drain(..)
+                    .filter_map(|s| Some((s.id, s.window_tree?)))
+                    .collect();
+                self.sessions = sessions;
+                self.marked
+                    .retain(|id| self.sessions.iter().any(|s| &s.id == id));
+                // Windows open, close and run other commands between polls
+                for session in &mut self.sessions {
+                    if let Some(windows) = expanded.remove(&session.id) {
+                        session.window_tree = Some(windows);
+                        self.pending_actions
+                            .push(Action::ListWindowTree(session.id.clone()));
+                    }
+                }
+                if self.mcp_mode {
+                    self.pending_actions.push(Action::LoadMcpStatus);
+                }
+                let now = text::unix_now();
+                let listed = self.sessions.iter().map(|s| s.name.as_str());
+                let seen = self.state.attach_history.refresh(listed, now);
+                // Edits made since the poll started are already here
+                self.state.tags.apply(&mut self.sessions);
+                let tagged = self
+                    .state
+                    .tags
+                    .refresh(self.sessions.iter().map(|s| s.name.as_str()), now);
+                let reordered = self
+                    .state
+                    .session_order
+                    .refresh(self.sessions.iter().map(|s| s.name.as_str()));
+                if self.state.descriptions.refresh(&self.sessions, now)
+                    || seen
+                    || tagged
+                    || reordered
+                {
+                    self.save_state();
+                }
+                self.last_update = Some(Instant::now());
+                // Keep the selection on the same row as sessions move around
+                self.rebuild_rows(key);
+                if let Some(id) = self.return_to.take() {
+                    self.select_session_id(&id);
+                }
+                if let Some(snapshot) = self.restore_offer.take() {
+                    self.missing_sessions = snapshot.missing(&self.sessions);
+                    if !self.missing_sessions.is_empty() && self.input_mode == InputMode::Normal {
+                        self.input_mode = InputMode::ConfirmingRestore;
+                    }
+                }
+                // Once per launch; a restore prompt on the same poll takes precedence
+                if std::mem::take(&mut self.digest_offer) {
+                    let entries = digest::entries(&self.sessions, &self.state.attach_history);
+                    if !entries.is_empty() && self.input_mode == InputMode::Normal {
+                        let ids = entries.iter().map(|e| e.session_id.clone()).collect();
+                        self.pending_actions.push(Action::ReadQuestions(ids));
+                        self.digest = entries;
+                        self.input_mode = InputMode::StartupDigest;
+                    }
+                }
+                self.check_alerts();
+                self.check_policy();
+                self.check_usage();
+                self.check_launched();
+                // Keep a pinned preview in sync with the pane
+                if let Some(preview) = &self.preview
+                    && !self.sessions.iter().any(|s| s.id == preview.session_id)
+                {
+                    self.notify(
+                        Level::Warning,
+                        format!("Session '{}' is gone", preview.session_name),
+                    );
+                    self.preview = None;
+                    self.dashboard_open = false;
+                    self.input_mode = InputMode::Normal;
+                }
+                self.status_timeline
+                    .retain(|id| self.sessions.iter().any(|s| s.id == id));
+                // A window opened or closed since the dashboard listed them
+                if self.dashboard_open
+                    && let Some(session) = self.selected_session()
+                    && session.windows != 0
+                    && session.windows != self.dashboard_windows.len()
+                {
+                    self.pending_actions
+                        .push(Action::ListWindows(session.id.clone()));
+                }
+                // Who is attached, and at what size, for the detail pane
+                match self.selected_session() {
+                    Some(session) if session.attached_clients > 0 => {
+                        self.pending_actions
+                            .push(Action::ListClients(session.id.clone()));
+                    }
+                    _ => self.clients = None,
+                }
+                self.refresh_preview();
+                Ok(false)
+            }
+            Action::StatusChanged(transitions) => {
+                self.status_timeline.record(&transitions, text::unix_now());
+                self.apply_rules(&transitions);
+                if self.bell.due(&transitions, self.focused, Instant::now()) {
+                    self.pending_actions.push(Action::RingBell);
+                }
+                Ok(false)
+            }
+            Action::RuleFinished(id, outcome) => {
+                if let Some(run) = self.rule_history.iter_mut().find(|r| r.id == id) {
+                    if let Err(e) = &outcome {
+                        self.notifications
+                            .push(Level::Error, format!("Rule '{}' failed: {}", run.rule, e));
+                    }
+                    run.outcome = Some(outcome);
+                }
+                Ok(false)
+            }
+            Action::UsageSampled(readings) => {
+                let now = text::unix_now();
+                let mut grew = false;
+                for (session, kind, reading) in readings {
+                    grew |= self.state.usage.record(&session, kind, reading, now);
+                }
+                self.state.usage.prune(now);
+                if grew {
+                    self.save_state();
+                }
+                Ok(false)
+            }
+            Action::CommandFinished(op, name, result) => {
+                self.operations.remove(&op);
+                match result {
+                    Ok(output) => {
+                        self.notify(
+                            Level::Success,
+                            match custom_commands::last_line(&output) {
+                                Some(line) => format!("'{}' done: {}", name, line),
+                                None => format!("'{}' done", name),
+                            },
+                        );
+                    }
+                    Err(e) => self.report_error(&e),
+                }
+                Ok(false)
+            }
+            Action::DirRead(dir, result) => {
+                if let Some((_, picker)) = &mut self.dir_picker {
+                    picker.loaded(&dir, result.map_err(|e| format!("{:#}", e)));
+                }
+                Ok(false)
+            }
+            Action::ClientsListed(session_id, result) => {
+                match result {
+                    Ok(clients) => {
+                        self.client_selected =
+                            self.client_selected.min(clients.len().saturating_sub(1));
+                        self.clients = Some((session_id, clients));
+                    }
+                    Err(e) => {
+                        tracing::warn!("Detail pane without clients: {:#}", e);
+                        self.clients = None;
+                    }
+                }
+                if self.input_mode == InputMode::ChoosingClient
+                    && self.clients.as_ref().is_none_or(|(_, c)| c.is_empty())
+                {
+                    self.input_mode = InputMode::Normal;
+                }
+                Ok(false)
+            }
+            Action::ClientDetached(session_id, name, result) => {
+                match result {
+                    Ok(()) => {
+                        self.notify(Level::Success, format!("Detached {}", name));
+                        self.pending_actions.push(Action::ListClients(session_id));
+                        self.pending_actions.push(Action::Refresh);
+                    }
+                    Err(e) => self.report_error(&e),
+                }
+                Ok(false)
+            }
+            Action::WindowTreeListed(session_id, result) => {
+                if let Some(session) = self
+                    .sessions
+                    .iter_mut()
+                    .find(|s| s.id == session_id && s.window_tree.is_some())
+                {
+                    match result {
+                        Ok(listed) => session.window_tree = Some(listed),
+                        Err(e) => {
+                            session.window_tree = None;
+                            self.report_error(&e);
+                        }
+                    }
+                    self.rebuild_rows(self.selected_key());
+                }
+                Ok(false)
+            }
+            Action::WindowsListed(session_id, result) if self.dashboard_open => {
+                if self.preview.as_ref().map(|p| p.session_id.as_str()) == Some(session_id.as_str())
+                {
+                    match result {
+                        Ok(windows) => self.dashboard_windows = windows,
+                        Err(e) => tracing::warn!("Dashboard without windows: {:#}", e),
+                    }
+                }
+                Ok(false)
+            }
+            Action::WindowsListed(session_id, result) => {
+                // Moving on, or opening something else, while they loaded cancels the attach
+                if self.input_mode != InputMode::Normal
+                    || self.selected_session().map(|s| s.id.as_str()) != Some(session_id.as_str())
+                {
+                    return Ok(false);
+                }
+                match result {
+                    Ok(windows) if windows.len() > 1 => {
+                        self.window_selected = windows.iter().position(|w| w.active).unwrap_or(0);
+                        self.window_choice = Some((session_id, windows));
+                        self.input_mode = InputMode::ChoosingWindow;
+                    }
+                    Ok(_) => self.attach_selected(Action::attach),
+                    Err(e) => {
+                        tracing::warn!("Attaching without picking a window: {:#}", e);
+                        self.attach_selected(Action::attach);
+                    }
+                }
+                Ok(false)
+            }
+            Action::QuestionsRead(questions) => {
+                for (id, question) in questions {
+                    if let Some(entry) = self.digest.iter_mut().find(|e| e.session_id == id) {
+                        entry.question = Some(question);
+                    }
+                }
+                Ok(false)
+            }
+            Action::Heartbeat => {
+                self.finish_refresh();
+                self.last_update = Some(Instant::now());
+                // Nothing changed, so a session missing on return isn't coming back
+                self.return_to = None;
+                self.check_alerts();
+                self.refresh_preview();
+                Ok(false)
+            }
+            Action::PaneCaptured(session_id, content) => {
+                if let Some(preview) = &mut self.preview
+                    && preview.session_id == session_id
+                {
+                    preview.set_content(&content);
+                }
+                Ok(false)
+            }
+            Action::SessionCreated(op, result) => {
+                self.operations.remove(&op);
+                match result {
+                    Ok(session) => {
+                        self.notify(
+                            Level::Success,
+                            format!("Session '{}' created", session.name),
+                        );
+                        self.add_created(session);
+                        self.pending_actions.push(Action::Refresh);
+                    }
+                    // Its command ended before the session could be listed
+                    Err(e) => match e.downcast_ref::<AgentRustyError>() {
+                        Some(AgentRustyError::SessionNotFound(name)) => {
+                            let command = self.creating.remove(name).and_then(|l| l.command);
+                            self.warn_exited(&name.clone(), command.as_deref());
+                        }
+                        _ => self.report_sticky_error(&e),
+                    },
+                }
+                Ok(false)
+            }
+            Action::SessionDuplicated(op, source, _, Ok(session)) => {
+                self.operations.remove(&op);
+                self.notify(
+                    Level::Success,
+                    format!("Duplicated '{}' as '{}'", source, session.name),
+                );
+                self.add_created(session);
+                self.pending_actions.push(Action::Refresh);
+                Ok(false)
+            }
+            // Fails the way creating any session does
+            Action::SessionDuplicated(op, _, name, Err(e)) => {
+                let quit = self.handle_action(Action::SessionCreated(op, Err(e)));
+                self.creating.remove(&name);
+                quit
+            }
+            Action::TemplateCreated(op, template, results) => {
+                self.operations.remove(&op);
+                let total = results.len();
+                let mut failures = Vec::new();
+                for (name, result) in results {
+                    match result {
+                        Ok(session) => self.add_created(session),
+                        Err(e) => {
+                            tracing::warn!("Template '{}': {}: {:#}", template, name, e);
+                            let command = self.creating.remove(&name).and_then(|l| l.command);
+                            failures.push(match e.downcast_ref::<AgentRustyError>() {
+                                Some(AgentRustyError::DuplicateSession(_)) => {
+                                    format!("{} already exists", name)
+                                }
+                                Some(AgentRustyError::SessionNotFound(_)) => format!(
+                                    "{} ended right away: '{}' exited",
+                                    name,
+                                    command.unwrap_or_default()
+                                ),
+                                _ => format!("{}: {:#}", name, e),
+                            });
+                        }
+                    }
+                }
+                if failures.is_empty() {
+                    self.notify(
+                        Level::Success,
+                        format!("Created {} sessions from '{}'", total, template),
+                    );
+                } else {
+                    self.notify(
+                        Level::Warning,
+                        format!(
+                            "Created {} of {} sessions from '{}'; {}",
+                            total - failures.len(),
+                            total,
+                            template,
+                            failures.join("; ")
+                        ),
+                    );
+                    self.notifications.pin_latest();
+                }
+                self.pending_actions.push(Action::Refresh);
+                Ok(false)
+            }
+            Action::WorktreeCreated(op, result) => match result {
+                Ok((session, worktree)) => {
+                    self.state.worktrees.insert(session.name.clone(), worktree);
+                    self.save_state();
+                    self.handle_action(Action::SessionCreated(op, Ok(session)))
+                }
+                Err(e) => self.handle_action(Action::SessionCreated(op, Err(e))),
+            },
+            Action::WorktreeRemoved(op, result) => {
+                self.operations.remove(&op);
+                match result {
+                    Ok(worktree) => {
+                        self.notify(
+                            Level::Success,
+                            format!("Removed worktree {}", worktree.path.display()),
+                        );
+                    }
+                    Err(e) => self.report_error(&e),
+                }
+                Ok(false)
+            }
+            Action::SessionDeleted(op, result) => {
+                self.operations.remove(&op);
+                match result {
+                    Ok(id) => {
+                        self.notify(Level::Success, "Session deleted");
+                        let key = self.selected_key();
+                        self.sessions.retain(|s| s.id != id);
+                        self.rebuild_rows(key);
+                        self.pending_actions.push(Action::Refresh);
+                    }
+                    Err(e) => self.report_sticky_error(&e),
+                }
+                Ok(false)
+            }
+            Action::SessionRenamed(op, result) => {
+                self.operations.remove(&op);
+                match result {
+                    Ok((id, name)) => {
+                        if let Some(session) = self.sessions.iter_mut().find(|s| s.id == id) {
+                            let old = std::mem::replace(&mut session.name, name.clone());
+                            self.state.rename_session(&old, &name);
+                            self.save_state();
+                            self.notify(
+                                Level::Success,
+                                format!("Session '{}' renamed to '{}'", old, name),
+                            );
+                        }
+                        self.rebuild_rows(self.selected_key());
+                        self.pending_actions.push(Action::Refresh);
+                    }
+                    // tmux's reason, such as the name being taken, belongs in the footer
+                    Err(e) => {
+                        tracing::warn!("{:#}", e);
+                        self.notify(Level::Error, format!("{:#}", e));
+                    }
+                }
+                Ok(false)
+            }
+            Action::SkeletonCopied(op, result) => {
+                self.operations.remove(&op);
+                match result {
+                    Ok(backend) => {
+                        self.notify(Level::Success, format!("Skeleton copied to {}!", backend));
+                    }
+                    Err(e) => self.report_error(&e),
+                }
+                Ok(false)
+            }
+            Action::SnapshotSaved(op, result) => {
+                self.operations.remove(&op);
+                match result {
+                    Ok(count) => {
+                        self.notify(
+                            Level::Success,
+                            format!("Snapshot saved ({} sessions)", count),
+                        );
+                    }
+                    Err(e) => self.report_error(&e),
+                }
+                Ok(false)
+            }
+            Action::SessionsExported(op, result) => {
+                self.operations.remove(&op);
+                match result {
+                    Ok(count) => {
+                        self.notify(Level::Success, format!("Exported {} sessions", count));
+                    }
+                    Err(e) => self.report_error(&e),
+                }
+                Ok(false)
+            }
+            Action::SessionArchived(op, verdict, result) => {
+                self.operations.remove(&op);
+                match result {
+                    Ok(path) => {
+                        self.notify(
+                            Level::Success,
+                            format!(
+                                "Archived '{}' to {} and closed it",
+                                verdict.name,
+                                path.display()
+                            ),
+                        );
+                    }
+                    Err(e) => {
+                        // Not retried this run; flag it for the user instead
+                        let at = self
+                            .sessions
+                            .iter()
+                            .find(|s| s.id == verdict.session_id)
+                            .map_or(0, |s| s.activity_at);
+                        self.stale.insert(verdict.session_id, at);
+                        self.report_error(&e);
+                    }
+                }
+                Ok(false)
+            }
+            Action::ScheduledSent(scheduled, result) => {
+                match result {
+                    Ok(()) => self.notify(
+                        Level::Success,
+                        format!("Sent scheduled prompt to {}", scheduled.session),
+                    ),
+                    Err(e) => self.notify(
+                        Level::Error,
+                        format!("Scheduled prompt for {} failed: {}", scheduled.session, e),
+                    ),
+                }
+                Ok(false)
+            }
+            Action::SessionsRestored(op, result) => {
+                self.operations.remove(&op);
+                match result {
+                    Ok(count) => {
+                        self.notify(Level::Success, format!("Restored {} sessions", count));
+                    }
+                    Err(e) => self.report_error(&e),
+                }
+                Ok(false)
+            }
+            // A prompt from the dashboard went to its one session
+            Action::BroadcastFinished(results) if self.dashboard_open => {
+                if let Some(r) = results.into_iter().next() {
+                    match r.outcome {
+                        Ok(()) => {
+                            self.notify(Level::Success, format!("Prompt sent to '{}'", r.name))
+                        }
+                        Err(e) => self.notify(
+                            Level::Error,
+                            format!("Failed to send to '{}': {}", r.name, e),
+                        ),
+                    }
+                }
+                Ok(false)
+            }
+            Action::BroadcastFinished(results) => {
+                self.broadcast_results = results;
+                self.input_mode = InputMode::BroadcastResults;
+                Ok(false)
+            }
+            Action::Error(msg) => {
+                self.finish_refresh();
+                tracing::warn!("{}", msg);
+                self.notify(Level::Error, msg);
+                Ok(false)
+            }
+            Action::Notify(level, msg) => {
+                self.notify(level, msg);
+                Ok(false)
+            }
+            Action::Tick => {
+                if self.notifications.expire(Instant::now()) {
+                    self.dirty = true;
+                }
+                if self.is_spinning() {
+                    self.spinner_frame = self.spinner_frame.wrapping_add(1);
+                    self.dirty = true;
+                }
+                Ok(false)
+            }
+            Action::ToggleMcpMode => {
+                self.mcp_mode = !self.mcp_mode;
+                if self.mcp_mode {
+                    self.pending_actions.push(Action::LoadMcpStatus);
+                }
+                Ok(false)
+            }
+            Action::McpStatusLoaded(servers) => {
+                self.mcp_servers = servers;
+                Ok(false)
+            }
+            Action::ServerInfoUpdated(result) => {
+                match result {
+                    Ok(info) => self.server_info = Some(info),
+                    Err(e) => {
+                        tracing::warn!("Failed to read tmux server info: {:#}", e);
+                        self.server_info = None;
+                    }
+                }
+                Ok(false)
+            }
+            Action::FocusSession(name) => {
+                let id = self
+                    .sessions
+                    .iter()
+                    .find(|s| s.name == name)
+                    .or_else(|| self.sessions.iter().find(|s| s.id == name))
+                    .map(|s| s.id.clone());
+                match id {
+                    Some(id) => {
+                        self.select_session_id(&id);
+                    }
+                    None => self.notify(Level::Warning, format!("No session named {}", name)),
+                }
+                Ok(false)
+            }
+            Action::Refresh => {
+                self.pending_actions.push(Action::Refresh);
+                Ok(false)
+            }
+            Action::Resize(width, height) => {
+                self.resize(Rect::new(0, 0, width, height));
+                Ok(false)
+            }
+            Action::Quit => Ok(true),
+            _ => Ok(false),
+        }
+    }
+
+    /// Insert pasted text into whichever input is focused
+    fn handle_paste(&mut self, text: &str) {
+        let text = text.replace("\r\n", "\n");
+        match self.input_mode {
+            InputMode::Creating | InputMode::Renaming => {
+                for c in text.chars().filter(|&c| is_session_name_char(c)) {
+                    self.input_buffer.insert(c);
+                }
+            }
+            InputMode::EditingNote if self.note_kind == NoteKind::Description => {
+                self.input_buffer.insert_str(&text.replace('\n', " "))
+            }
+            InputMode::EditingNote => {
+                for c in text.chars() {
+                    if c != '\n' || self.input_buffer.as_str().split('\n').count() < NOTE_MAX_LINES
+                    {
+                        self.input_buffer.insert(c);
+                    }
+                }
+            }
+            // A newline would submit the prompt part-way through
+            InputMode::Broadcasting | InputMode::DashboardPrompt => {
+                self.input_buffer.insert_str(&text.replace('\n', " "))
+            }
+            InputMode::CreatingDir
+            | InputMode::CreatingCommand
+            | InputMode::TemplateDir
+            | InputMode::EditingTags => self
+                .input_buffer
+                .insert_str(text.lines().next().unwrap_or("")),
+            InputMode::Filtering => {
+                self.input_buffer
+                    .insert_str(text.lines().next().unwrap_or(""));
+                self.set_session_filter(self.input_buffer.to_string());
+            }
+            InputMode::Preview => {
+                if let Some(preview) = &mut self.preview
+                    && preview.search.editing
+                {
+                    preview
+                        .search
+                        .query
+                        .push_str(text.lines().next().unwrap_or(""));
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Whether keys go to a text field, where every letter is typed
+    pub fn takes_text(&self) -> bool {
+        match self.input_mode {
+            InputMode::Creating
+            | InputMode::CreatingDir
+            | InputMode::CreatingCommand
+            | InputMode::EditingNote
+            | InputMode::EditingTags
+            | InputMode::Broadcasting
+            | InputMode::SchedulingTime
+            | InputMode::SchedulingPrompt
+            | InputMode::AddingRule
+            | InputMode::CreatingWorktree
+            | InputMode::TemplateDir
+            | InputMode::DashboardPrompt
+            | InputMode::Filtering
+            | InputMode::Renaming => true,
+            InputMode::Preview => self.preview.as_ref().is_some_and(|p| p.search.editing),
+            InputMode::PickingDir => self
+                .dir_picker
+                .as_ref()
+                .is_some_and(|(_, picker)| picker.filtering),
+            _ => false,
+        }
+    }
+
+    fn handle_mouse(&mut self, mouse: MouseEvent) {
+        if mouse.kind == MouseEventKind::Down(MouseButton::Left) {
+            if self.input_mode == InputMode::Normal {
+                self.click_list(mouse.column, mouse.row);
+            }
+            return;
+        }
+        let down = match mouse.kind {
+            MouseEventKind::ScrollDown => true,
+            MouseEventKind::ScrollUp => false,
+            _ => return,
+        };
+        match self.input_mode {
+            InputMode::Normal if down => self.next_session(),
+            InputMode::Normal => self.previous_session(),
+            InputMode::Preview | InputMode::Dashboard => {
+                if let Some(preview) = &mut self.preview {
+                    if down {
+                        preview.viewport.scroll_down(3);
+                    } else {
+                        preview.viewport.scroll_up(3);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Row of the list drawn at a screen position
+    fn row_at(&self, column: u16, row: u16) -> Option<usize> {
+        let area = self.list_area;
+        if column < area.x || column >= area.right() || row < area.y || row >= area.bottom() {
+            return None;
+        }
+        let mut top = area.y;
+        for index in self.list_state.offset()..self.rows.len() {
+            let height = self.row_heights.get(index).copied().unwrap_or(1);
+            if row < top + height {
+                return Some(index);
+            }
+            top += height;
+        }
+        None
+    }
+
+    /// Select the clicked session, or attach to it if it is already selected;
+    /// a clicked header opens or closes its group
+    fn click_list(&mut self, column: u16, row: u16) {
+        let Some(index) = self.row_at(column, row) else {
+            return;
+        };
+        match self.rows[index] {
+            ListRow::Header { collapsed, .. } => {
+                self.list_state.select(Some(index));
+                self.set_selected_group_collapsed(!collapsed);
+            }
+            ListRow::Server(_) => {}
+            ListRow::Session(_) | ListRow::Window(..)
+                if self.list_state.selected() == Some(index) =>
+            {
+                let now = Instant::now();
+                let repeated = self
+                    .last_attach_click
+                    .is_some_and(|(last, at)| last == index && now - at < DOUBLE_CLICK);
+                if !repeated {
+                    self.last_attach_click = Some((index, now));
+                    match self.rows[index] {
+                        ListRow::Window(..) => self.attach_row(),
+                        _ => self.attach_selected(Action::attach),
+                    }
+                }
+            }
+            ListRow::Session(_) | ListRow::Window(..) => {
+                self.list_state.select(Some(index));
+                self.record_jump();
+            }
+        }
+    }
+
+    fn handle_key(&mut self, key: KeyEvent) -> Result<bool> {
+        // Held keys that delete, restart or confirm don't repeat, unless typed
+        if !self.takes_text() {
+            let admitted = self.repeats.admit(&key, Instant::now());
+            if !admitted || key.kind == KeyEventKind::Repeat && input::is_destructive(&key) {
+                return Ok(false);
+            }
+        }
+
+        if self.input_mode == InputMode::Normal
+            && key.code == KeyCode::Char('e')
+            && self.current_error_details().is_some()
+        {
+            self.error_details_scroll = 0;
+            self.input_mode = InputMode::ErrorDetails;
+            return Ok(false);
+        }
+
+        // Raw mode turns Ctrl-Z into a key press, so suspend by hand from any mode
+        if key.code == KeyCode::Char('z') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            self.pending_actions.push(Action::Suspend);
+            return Ok(false);
+        }
+        // Likewise Ctrl-C, which must quit even with a dialog open
+        if key.code == KeyCode::Char('c') && key.modifiers.contains(KeyModifiers::CONTROL) {
+            return self.request_quit();
+        }
+
+        // Any key press dismisses the footer's messages; sticky ones wait for Esc
+        if self.notifications.latest().is_some()
+            && matches!(self.input_mode, InputMode::Normal | InputMode::Dashboard)
+        {
+            if key.code == KeyCode::Esc && self.notifications.latest().is_some_and(|n| n.sticky) {
+                self.notifications.clear();
+                self.error_details = None;
+                return Ok(false);
+            }
+            self.notifications.clear_transient();
+            if self.current_error_details().is_none() {
+                self.error_details = None;
+            }
+        }
+
+        match self.input_mode {
+            InputMode::Normal => self.handle_normal_key(key),
+            InputMode::Creating => self.handle_creating_key(key),
+            InputMode::ChoosingLauncher => self.handle_launcher_key(key),
+            InputMode::ChoosingWindow => self.handle_window_key(key),
+            InputMode::ChoosingClient => self.handle_client_key(key),
+            InputMode::ChoosingCommand => self.handle_command_key(key),
+            InputMode::ChoosingTemplate => self.handle_template_key(key),
+            InputMode::TemplateDir => self.handle_template_dir_key(key),
+            InputMode::Dashboard => self.handle_dashboard_key(key),
+            InputMode::DashboardPrompt => self.handle_dashboard_prompt_key(key),
+            InputMode::CreatingDir => self.handle_create_dir_key(key),
+            InputMode::CreatingCommand => self.handle_create_command_key(key),
+            InputMode::Confirming => self.handle_confirming_key(key),
+            InputMode::Preview => self.handle_preview_key(key),
+            InputMode::EditingNote => self.handle_note_key(key),
+            InputMode::EditingTags => self.handle_tags_key(key),
+            InputMode::ChoosingTag => self.handle_tag_filter_key(key),
+            InputMode::Broadcasting => self.handle_broadcast_key(key),
+            InputMode::ConfirmingBroadcast => self.handle_confirming_broadcast_key(key),
+            InputMode::ConfirmingRestart => self.handle_confirming_restart_key(key),
+            InputMode::ConfirmingQuit => self.handle_confirming_quit_key(key),
+            InputMode::ConfirmingRestore => self.handle_confirming_restore_key(key),
+            InputMode::StartupDigest => self.handle_digest_key(key),
+            InputMode::ConfirmingCleanup => self.handle_confirming_cleanup_key(key),
+            InputMode::ErrorDetails => self.handle_error_details_key(key),
+            InputMode::SchedulingTime => self.handle_schedule_time_key(key),
+            InputMode::SchedulingPrompt => self.handle_schedule_prompt_key(key),
+            InputMode::Schedules => self.handle_schedules_key(key),
+            InputMode::AddingRule => self.handle_adding_rule_key(key),
+            InputMode::Rules => self.handle_rules_key(key),
+            InputMode::CreatingWorktree => self.handle_creating_worktree_key(key),
+            InputMode::ConfirmingWorktreeRemoval => self.handle_worktree_removal_key(key),
+            InputMode::Logs => self.handle_logs_key(key),
+            InputMode::PickingDir => self.handle_dir_picker_key(key),
+            InputMode::Filtering => self.handle_filter_key(key),
+            InputMode::Help => self.handle_help_key(key),
+            InputMode::Renaming => self.handle_renaming_key(key),
+            InputMode::Usage => {
+                if matches!(
+                    key.code,
+                    KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('U')
+                ) {
+                    self.input_mode = InputMode::Normal;
+                }
+                Ok(false)
+            }
+            InputMode::BroadcastResults => {
+                self.broadcast_results.clear();
+                self.input_mode = InputMode::Normal;
+                Ok(false)
+            }
+        }
+    }
+
+    /// Lines of the log viewer's popup
+    fn log_view_height(&self) -> usize {
+        centered_rect(80, 80, self.area).height.saturating_sub(2) as usize
+    }
+
+    /// Log lines passing the filter, and the first of them on screen
+    fn log_window(&self) -> (Vec<LogLine>, usize) {
+        let lines = self.logs.lines(self.log_filter);
+        let last_top = lines.len().saturating_sub(self.log_view_height());
+        let top = self.log_top.map_or(last_top, |top| top.min(last_top));
+        (lines, top)
+    }
+
+    /// Log lines the viewer shows right now
+    fn visible_logs(&self) -> Vec<LogLine> {
+        let (mut lines, top) = self.log_window();
+        lines.drain(..top);
+        lines.truncate(self.log_view_height());
+        lines
+    }
+
+    fn handle_logs_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let (lines, top) = self.log_window();
+        let page = self.log_view_height().max(1);
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('l') => {
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char('j') | KeyCode::Down => self.log_top = Some(top + 1),
+            KeyCode::Char('k') | KeyCode::Up => self.log_top = Some(top.saturating_sub(1)),
+            KeyCode::PageDown => self.log_top = Some(top + page),
+            KeyCode::PageUp => self.log_top = Some(top.saturating_sub(page)),
+            KeyCode::Char('g') | KeyCode::Home => self.log_top = Some(0),
+            KeyCode::Char('G') | KeyCode::End => self.log_top = None,
+            KeyCode::Char('f') => {
+                self.log_top = match self.log_top {
+                    Some(_) => None,
+                    None => Some(top),
+                };
+            }
+            KeyCode::Char('v') => {
+                self.log_filter = logging::next_filter(self.log_filter);
+                self.log_top = None;
+            }
+            KeyCode::Char('y') if !lines.is_empty() => {
+                let text: Vec<String> = self.visible_logs().iter().map(LogLine::text).collect();
+                self.pending_actions.push(Action::CopyText(text.join("\n")));
+            }
+            _ => {}
+        }
+        // Keep a paused view in range, so scrolling back up starts at once
+        if let Some(top) = self.log_top {
+            let last_top = lines.len().saturating_sub(self.log_view_height());
+            self.log_top = Some(top.min(last_top));
+        }
+        Ok(false)
+    }
+
+    fn handle_normal_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let changes = match key.code {
+            KeyCode::Char('n' | 'd' | 'R' | 'r' | 'b' | 'S' | 'G' | 'A' | ':' | 'F' | 'C') => true,
+            // Custom commands can do anything
+            KeyCode::Char(c) => {
+                !custom_commands::RESERVED_KEYS.contains(c)
+                    && self.custom_commands.iter().any(|cmd| cmd.key == Some(c))
+            }
+            _ => false,
+        };
+        if self.read_only && changes {
+            self.notify(Level::Warning, "Read-only: another instance is running");
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Char('q') => return self.request_quit(),
+            KeyCode::Char('j') | KeyCode::Down => self.next_session(),
+            KeyCode::Char('k') | KeyCode::Up => self.previous_session(),
+            KeyCode::Char('M') => return self.handle_action(Action::ToggleMcpMode),
+            KeyCode::Char('C') => self.choose_client(),
+            KeyCode::F(5) => self.request_refresh(),
+            KeyCode::Enter if key.modifiers.contains(KeyModifiers::ALT) => {
+                self.attach_selected(Action::AttachSessionInWindow)
+            }
+            KeyCode::Enter => match self.list_state.selected().and_then(|i| self.rows.get(i)) {
+                Some(ListRow::Header { collapsed, .. }) => {
+                    let collapse = !*collapsed;
+                    self.set_selected_group_collapsed(collapse);
+                }
+                // Shift goes straight to the window the session shows
+                Some(ListRow::Session(_)) if key.modifiers.contains(KeyModifiers::SHIFT) => {
+                    self.attach_selected(Action::attach)
+                }
+                _ => self.attach_row(),
+            },
+            KeyCode::Char('o') if key.modifiers.contains(KeyModifiers::CONTROL) => self.jump_back(),
+            // Terminals report Ctrl-i as Tab
+            KeyCode::Tab => self.jump_forward(),
+            KeyCode::Char('i') if key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.jump_forward()
+            }
+            KeyCode::Left | KeyCode::Char('h') => self.collapse_selected(),
+            KeyCode::Right => match self.list_state.selected().and_then(|i| self.rows.get(i)) {
+                Some(ListRow::Session(_)) => self.expand_windows(),
+                _ => self.set_selected_group_collapsed(false),
+            },
+            KeyCode::Char('v') => self.cycle_list_display(),
+            KeyCode::Char('t') => self.list_layout = self.list_layout.toggle(),
+            KeyCode::Char('s') => self.cycle_sort(),
+            KeyCode::Char('n') => {
+                self.input_mode = InputMode::Creating;
+                self.input_buffer.clear();
+            }
+            KeyCode::Char('G') => {
+                self.worktree_target = Some((self.worktree_repo(), None));
+                self.input_mode = InputMode::CreatingWorktree;
+                self.input_buffer.clear();
+            }
+            KeyCode::Char('d') if self.selected_session().is_some() => {
+                self.input_mode = InputMode::Confirming;
+            }
+            KeyCode::Char('E') => match self.selected_session() {
+                Some(session) if !session.current_path.is_empty() => {
+                    self.pending_actions.push(Action::OpenEditor(
+                        session.id.clone(),
+                        session.current_path.clone(),
+                    ));
+                }
+                Some(_) => {
+                    self.notify(Level::Warning, "Session directory is unknown");
+                }
+                None => {}
+            },
+            KeyCode::Char('R') if self.selected_session().is_some() => {
+                self.input_mode = InputMode::ConfirmingRestart;
+            }
+            KeyCode::Char('y') => {
+                self.pending_actions
+                    .push(Action::CopySkeleton(self.skeleton_root.clone()));
+            }
+            KeyCode::Char('B') => self.open_dir_picker(DirPurpose::SkeletonRoot),
+            KeyCode::Char('F') => self.duplicate_selected(),
+            KeyCode::Char('V') => self.attach_selected(Action::watch),
+            KeyCode::Char('J') => self.move_selected(true),
+            KeyCode::Char('K') => self.move_selected(false),
+            KeyCode::Char('S') => {
+                self.pending_actions.push(Action::TakeSnapshot);
+            }
+            KeyCode::Char('T') => {
+                if let Some(name) = self.selected_session().map(|s| s.name.clone()) {
+                    self.input_buffer.clear();
+                    self.schedule_target = Some((name, None));
+                    self.input_mode = InputMode::SchedulingTime;
+                }
+            }
+            KeyCode::Char('P') => {
+                self.state.reload_schedules();
+                self.schedule_selected = 0;
+                self.input_mode = InputMode::Schedules;
+            }
+            KeyCode::Char('X') => {
+                self.pending_actions.push(Action::ExportSessions(
+                    self.state.notes.clone(),
+                    self.state.tags.clone(),
+                ));
+            }
+            KeyCode::Char('w') => {
+                if let Some(name) = self.selected_session().map(|s| s.name.clone()) {
+                    self.input_buffer.clear();
+                    self.rule_target = Some(name);
+                    self.input_mode = InputMode::AddingRule;
+                }
+            }
+            KeyCode::Char('W') => {
+                self.rules_selected = 0;
+                self.input_mode = InputMode::Rules;
+            }
+            KeyCode::Char('U') => self.input_mode = InputMode::Usage,
+            KeyCode::Char('l') => {
+                self.log_top = None;
+                self.input_mode = InputMode::Logs;
+            }
+            KeyCode::Char('m') => {
+                self.bell.muted = !self.bell.muted;
+                self.notify(
+                    Level::Info,
+                    if self.bell.muted {
+                        "Bell muted"
+                    } else {
+                        "Bell unmuted"
+                    }
+                    .to_string(),
+                );
+            }
+            KeyCode::Char('p') => self.cycle_poll_rate(),
+            KeyCode::Char('c') => self.cycle_theme(),
+            KeyCode::Char('r') => {
+                if let Some((id, name)) = self
+                    .selected_session()
+                    .map(|s| (s.id.clone(), s.name.clone()))
+                {
+                    self.input_buffer.set(name);
+                    self.rename_target = Some(id);
+                    self.input_mode = InputMode::Renaming;
+                }
+            }
+            KeyCode::Char('Y') => {
+                if let Some(session) = self.selected_session() {
+                    self.pending_actions
+                        .push(Action::CopyPane(session.id.clone()));
+                }
+            }
+            KeyCode::Char('o') => self.open_preview(),
+            KeyCode::Char('O') => self.open_dashboard(),
+            KeyCode::Char(' ') => {
+                if let Some(id) = self.selected_session().map(|s| s.id.clone())
+                    && !self.marked.remove(&id)
+                {
+                    self.marked.insert(id);
+                }
+            }
+            KeyCode::Esc => {
+                self.marked.clear();
+                self.set_session_filter(String::new());
+            }
+            KeyCode::Char('/') => {
+                self.input_buffer.set(self.session_filter.clone());
+                self.input_mode = InputMode::Filtering;
+            }
+            KeyCode::Char('?') => {
+                self.help_scroll = 0;
+                self.input_mode = InputMode::Help;
+            }
+            KeyCode::Char('b') => {
+                if self.marked.is_empty() {
+                    self.notify(Level::Warning, "Mark sessions with Space first");
+                } else {
+                    self.input_buffer.clear();
+                    self.broadcast_force = false;
+                    self.input_mode = InputMode::Broadcasting;
+                }
+            }
+            KeyCode::Char('N') => {
+                if let Some(name) = self.selected_session().map(|s| s.name.clone()) {
+                    self.input_buffer
+                        .set(self.state.note(&name).unwrap_or_default());
+                    self.note_target = Some(name);
+                    self.note_kind = NoteKind::Note;
+                    self.input_mode = InputMode::EditingNote;
+                }
+            }
+            KeyCode::Char('D') => {
+                if let Some(name) = self.selected_session().map(|s| s.name.clone()) {
+                    self.input_buffer
+                        .set(self.state.descriptions.get(&name).unwrap_or_default());
+                    self.note_target = Some(name);
+                    self.note_kind = NoteKind::Description;
+                    self.input_mode = InputMode::EditingNote;
+                }
+            }
+            KeyCode::Char('#') => {
+                if let Some(name) = self.selected_session().map(|s| s.name.clone()) {
+                    self.input_buffer.set(self.state.tags.get(&name).join(" "));
+                    self.tag_target = Some(name);
+                    self.input_mode = InputMode::EditingTags;
+                }
+            }
+            KeyCode::Char('f') => {
+                let tags = self.listed_tags();
+                if tags.is_empty() {
+                    self.notify(Level::Warning, "No tagged sessions: press # to tag one");
+                } else {
+                    // On the tag filtered by, if any
+                    let current = self.session_filter.strip_prefix('#');
+                    self.tag_selected = tags
+                        .keys()
+                        .position(|tag| Some(*tag) == current)
+                        .map_or(0, |i| i + 1);
+                    self.input_mode = InputMode::ChoosingTag;
+                }
+            }
+            KeyCode::Char('A') => {
+                if self.templates.is_empty() {
+                    self.notify(Level::Warning, "No [[template]] in the config");
+                } else {
+                    self.template_selected = 0;
+                    self.input_mode = InputMode::ChoosingTemplate;
+                }
+            }
+            KeyCode::Char(':') => {
+                if self.custom_commands.is_empty() {
+                    self.notify(Level::Warning, "No [[commands]] in the config");
+                } else if self.selected_session().is_some() {
+                    self.command_selected = 0;
+                    self.input_mode = InputMode::ChoosingCommand;
+                }
+            }
+            KeyCode::Char('`') => self.attach_last(),
+            KeyCode::Char(c @ '1'..='9')
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                self.attach_number(c.to_digit(10).unwrap_or_default() as usize);
+            }
+            // Keys bound to custom commands, where agent-rusty doesn't use them
+            KeyCode::Char(c)
+                if !key
+                    .modifiers
+                    .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT) =>
+            {
+                if let Some(index) = self
+                    .custom_commands
+                    .iter()
+                    .position(|cmd| cmd.key == Some(c))
+                {
+                    self.run_custom_command(index);
+                }
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// List only sessions matching `filter`, keeping the selected one when it matches
+    fn set_session_filter(&mut self, filter: String) {
+        if filter != self.session_filter {
+            self.session_filter = filter;
+            self.rebuild_rows(self.selected_key());
+        }
+    }
+
+    fn handle_filter_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter => self.input_mode = InputMode::Normal,
+            KeyCode::Esc => {
+                self.input_buffer.clear();
+                self.set_session_filter(String::new());
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Down => self.next_session(),
+            KeyCode::Up => self.previous_session(),
+            KeyCode::Char(c) => {
+                self.input_buffer.insert(c);
+                self.set_session_filter(self.input_buffer.to_string());
+            }
+            KeyCode::Backspace => {
+                self.input_buffer.backspace();
+                self.set_session_filter(self.input_buffer.to_string());
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Run a `[[commands]]` entry against the selected session
+    fn run_custom_command(&mut self, index: usize) {
+        let (Some(command), Some(session)) =
+            (self.custom_commands.get(index), self.selected_session())
+        else {
+            return;
+        };
+        let context = CommandContext {
+            session_id: session.id.clone(),
+            session_name: session.name.clone(),
+            cwd: session.current_path.clone(),
+        };
+        self.pending_actions
+            .push(Action::RunCommand(command.clone(), context));
+    }
+
+    fn handle_creating_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter => {
+                if self.input_buffer.is_empty() {
+                    self.input_mode = InputMode::Normal;
+                } else {
+                    self.create_name = Some(self.input_buffer.take());
+                    self.launcher_selected = 0;
+                    self.input_mode = InputMode::ChoosingLauncher;
+                }
+            }
+            KeyCode::Esc => {
+                self.input_buffer.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char(c)
+                if is_session_name_char(c) && !key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.input_buffer.insert(c)
+            }
+            _ => {
+                self.input_buffer.edit(&key);
+            }
+        }
+        Ok(false)
+    }
+
+    fn handle_renaming_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter => {
+                let name = self.input_buffer.take();
+                if let Some(id) = self.rename_target.take()
+                    && !name.is_empty()
+                    && self.sessions.iter().any(|s| s.id == id && s.name != name)
+                {
+                    self.pending_actions.push(Action::RenameSession(id, name));
+                }
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => {
+                self.input_buffer.clear();
+                self.rename_target = None;
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char(c)
+                if is_session_name_char(c) && !key.modifiers.contains(KeyModifiers::CONTROL) =>
+            {
+                self.input_buffer.insert(c)
+            }
+            _ => {
+                self.input_buffer.edit(&key);
+            }
+        }
+        Ok(false)
+    }
+
+    fn handle_command_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down
+                if self.command_selected + 1 < self.custom_commands.len() =>
+            {
+                self.command_selected += 1;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.command_selected = self.command_selected.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                self.input_mode = InputMode::Normal;
+                self.run_custom_command(self.command_selected);
+            }
+            KeyCode::Esc => self.input_mode = InputMode::Normal,
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_template_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down
+                if self.template_selected + 1 < self.templates.len() =>
+            {
+                self.template_selected += 1;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.template_selected = self.template_selected.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                let cwd = std::env::current_dir().unwrap_or_default();
+                self.input_buffer.set(cwd.display().to_string());
+                self.input_mode = InputMode::TemplateDir;
+            }
+            KeyCode::Esc => self.input_mode = InputMode::Normal,
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_template_dir_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter => match launcher::parse_dir(self.input_buffer.as_str()) {
+                Ok(dir) => {
+                    self.input_buffer.clear();
+                    self.input_mode = InputMode::Normal;
+                    let project = dir
+                        .or_else(|| std::env::current_dir().ok())
+                        .unwrap_or_default();
+                    let Some(template) = self.templates.get(self.template_selected) else {
+                        return Ok(false);
+                    };
+                    let sessions = template::plan(template, &project);
+                    let name = template.name.clone();
+                    for session in &sessions {
+                        if let Some(launcher) = &session.launcher {
+                            self.creating.insert(session.name.clone(), launcher.clone());
+                        }
+                    }
+                    self.pending_actions
+                        .push(Action::CreateSessions(name, sessions));
+                }
+                Err(e) => self.notify(Level::Error, e),
+            },
+            KeyCode::Esc => {
+                self.input_buffer.clear();
+                self.input_mode = InputMode::ChoosingTemplate;
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.input_buffer.insert(c)
+            }
+            _ => {
+                self.input_buffer.edit(&key);
+            }
+        }
+        Ok(false)
+    }
+
+    fn handle_window_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let count = self.window_choice.as_ref().map_or(0, |(_, w)| w.len());
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down if self.window_selected + 1 < count => {
+                self.window_selected += 1;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.window_selected = self.window_selected.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                if let Some((id, windows)) = self.window_choice.take()
+                    && let Some(window) = windows.get(self.window_selected)
+                {
+                    let target = tmux::window_target(&id, window.index);
+                    self.pending_actions.push(Action::SelectWindow(target));
+                }
+                self.input_mode = InputMode::Normal;
+                self.attach_selected(Action::attach);
+            }
+            // Attach to whichever window the session shows
+            KeyCode::Esc => {
+                self.window_choice = None;
+                self.input_mode = InputMode::Normal;
+                self.attach_selected(Action::attach);
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Offer the selected session's attached terminals for detaching
+    fn choose_client(&mut self) {
+        let Some(session) = self.selected_session() else {
+            return;
+        };
+        if self
+            .clients
+            .as_ref()
+            .is_some_and(|(id, clients)| *id == session.id && !clients.is_empty())
+        {
+            self.client_selected = 0;
+            self.input_mode = InputMode::ChoosingClient;
+        } else {
+            let message = format!("Nothing is attached to '{}'", session.name);
+            self.notify(Level::Info, message);
+        }
+    }
+
+    fn handle_client_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let count = self.clients.as_ref().map_or(0, |(_, c)| c.len());
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down if self.client_selected + 1 < count => {
+                self.client_selected += 1;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.client_selected = self.client_selected.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                if let Some((id, clients)) = &self.clients
+                    && let Some(client) = clients.get(self.client_selected)
+                {
+                    self.pending_actions
+                        .push(Action::DetachClient(id.clone(), client.name.clone()));
+                }
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => self.input_mode = InputMode::Normal,
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_launcher_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down
+                if self.launcher_selected + 1 < self.launchers.len() =>
+            {
+                self.launcher_selected += 1;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.launcher_selected = self.launcher_selected.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                self.create_command = None;
+                match self.launchers.get(self.launcher_selected) {
+                    Some(launcher) if launcher.ask_command => {
+                        self.input_buffer
+                            .set(launcher.command.as_deref().unwrap_or_default());
+                        self.input_mode = InputMode::CreatingCommand;
+                    }
+                    _ => self.ask_create_dir(),
+                }
+            }
+            KeyCode::Esc => {
+                self.create_name = None;
+                self.worktree_target = None;
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Ask where the new session starts, unless that's a new worktree
+    fn ask_create_dir(&mut self) {
+        match self.launchers.get(self.launcher_selected) {
+            _ if self.worktree_target.is_some() => self.create_session(None),
+            Some(launcher) if launcher.ask_dir => {
+                self.open_dir_picker(DirPurpose::SessionDir);
+            }
+            // Typed, starting from the current directory
+            _ => {
+                let cwd = std::env::current_dir().unwrap_or_default();
+                self.input_buffer.set(cwd.display().to_string());
+                self.input_mode = InputMode::CreatingDir;
+            }
+        }
+    }
+
+    fn handle_create_command_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter => {
+                self.create_command = Some(self.input_buffer.take().trim().to_string());
+                self.ask_create_dir();
+            }
+            KeyCode::Esc => {
+                self.input_buffer.clear();
+                self.input_mode = InputMode::ChoosingLauncher;
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.input_buffer.insert(c)
+            }
+            _ => {
+                self.input_buffer.edit(&key);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Repository a new worktree branches from: the selected session's if it's
+    /// in one, else the configured one, else the current directory
+    fn worktree_repo(&self) -> PathBuf {
+        self.selected_session()
+            .filter(|s| s.git.is_some() && !s.current_path.is_empty())
+            .map(|s| PathBuf::from(&s.current_path))
+            .or_else(|| {
+                self.worktree_config
+                    .repo
+                    .as_deref()
+                    .map(worktree::expand_home)
+            })
+            .or_else(|| std::env::current_dir().ok())
+            .unwrap_or_default()
+    }
+
+    fn handle_creating_worktree_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter => {
+                let branch = self.input_buffer.as_str().trim().to_string();
+                let name = worktree::session_name(&branch);
+                if branch.is_empty() {
+                    self.worktree_target = None;
+                    self.input_mode = InputMode::Normal;
+                } else if let Some((_, target)) = &mut self.worktree_target {
+                    *target = Some(branch);
+                    self.input_buffer.clear();
+                    self.create_name = Some(name);
+                    self.launcher_selected = 0;
+                    self.input_mode = InputMode::ChoosingLauncher;
+                }
+            }
+            KeyCode::Esc => {
+                self.input_buffer.clear();
+                self.worktree_target = None;
+                self.input_mode = InputMode::Normal;
+            }
+            // Git rejects the rest of the oddities with a message
+            KeyCode::Char(c) if !c.is_whitespace() => self.input_buffer.insert(c),
+            KeyCode::Backspace => {
+                self.input_buffer.backspace();
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_create_dir_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter => match launcher::parse_dir(self.input_buffer.as_str()) {
+                Ok(dir) => {
+                    self.input_buffer.clear();
+                    self.notifications.clear();
+                    self.create_session(dir);
+                }
+                Err(e) => self.notify(Level::Error, e),
+            },
+            KeyCode::Esc => {
+                self.input_buffer.clear();
+                self.notifications.clear();
+                self.input_mode = InputMode::ChoosingLauncher;
+            }
+            // Back to browsing, from the typed directory if there is one
+            KeyCode::Tab => {
+                let typed = launcher::parse_dir(self.input_buffer.as_str())
+                    .ok()
+                    .flatten();
+                self.input_buffer.clear();
+                self.notifications.clear();
+                self.show_dir_picker(DirPurpose::SessionDir, typed);
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.input_buffer.insert(c)
+            }
+            _ => {
+                self.input_buffer.edit(&key);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Browse for a directory, from the last one chosen or else home
+    fn open_dir_picker(&mut self, purpose: DirPurpose) {
+        self.show_dir_picker(purpose, None);
+    }
+
+    fn show_dir_picker(&mut self, purpose: DirPurpose, start: Option<PathBuf>) {
+        let start = start
+            .or_else(|| self.state.last_dir.clone().filter(|dir| dir.is_dir()))
+            .or_else(dirs::home_dir)
+            .unwrap_or_else(|| PathBuf::from("/"));
+        self.pending_actions.push(Action::ReadDir(start.clone()));
+        self.dir_picker = Some((purpose, DirPicker::new(start)));
+        self.input_mode = InputMode::PickingDir;
+    }
+
+    fn handle_dir_picker_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let Some((purpose, picker)) = &mut self.dir_picker else {
+            self.input_mode = InputMode::Normal;
+            return Ok(false);
+        };
+        let purpose = *purpose;
+        // Typing the path instead, starting from where the picker is
+        if key.code == KeyCode::Tab && purpose == DirPurpose::SessionDir && !picker.filtering {
+            self.input_buffer.set(picker.dir().display().to_string());
+            self.dir_picker = None;
+            self.input_mode = InputMode::CreatingDir;
+            return Ok(false);
+        }
+        match picker.handle_key(key) {
+            PickerEvent::None => {}
+            PickerEvent::Load(dir) => self.pending_actions.push(Action::ReadDir(dir)),
+            PickerEvent::Chosen(dir) => {
+                self.dir_picker = None;
+                self.state.last_dir = Some(dir.clone());
+                self.save_state();
+                match purpose {
+                    DirPurpose::SessionDir => self.create_session(Some(dir)),
+                    DirPurpose::SkeletonRoot => {
+                        self.input_mode = InputMode::Normal;
+                        self.skeleton_root = dir.clone();
+                        self.pending_actions.push(Action::CopySkeleton(dir));
+                    }
+                }
+            }
+            PickerEvent::Cancelled => {
+                self.dir_picker = None;
+                self.input_mode = match purpose {
+                    DirPurpose::SessionDir => InputMode::ChoosingLauncher,
+                    DirPurpose::SkeletonRoot => InputMode::Normal,
+                };
+            }
+        }
+        Ok(false)
+    }
+
+    /// Create the named session with the highlighted launcher
+    fn create_session(&mut self, dir: Option<PathBuf>) {
+        self.input_mode = InputMode::Normal;
+        let Some(name) = self.create_name.take() else {
+            return;
+        };
+        let mut launcher = self.launchers.get(self.launcher_selected).cloned();
+        if let (Some(launcher), Some(command)) = (&mut launcher, self.create_command.take()) {
+            launcher.command = Some(command).filter(|c| !c.is_empty());
+        }
+        if let Some(launcher) = &launcher {
+            self.creating.insert(name.clone(), launcher.clone());
+        }
+        if let Some((repo, Some(branch))) = self.worktree_target.take() {
+            let request = WorktreeRequest {
+                branch,
+                repo,
+                base: self.worktree_config.base.clone(),
+                session: name,
+            };
+            self.pending_actions
+                .push(Action::CreateWorktree(request, launcher));
+            return;
+        }
+        self.pending_actions.push(Action::CreateSession(NewSession {
+            name,
+            launcher,
+            cwd: dir,
+        }));
+    }
+
+    fn handle_note_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let ctrl = key.modifiers.contains(KeyModifiers::CONTROL);
+        let description = self.note_kind == NoteKind::Description;
+        match key.code {
+            KeyCode::Char('s') if ctrl => self.save_note(),
+            KeyCode::Enter if description => self.save_note(),
+            KeyCode::Char('d') if ctrl => {
+                // A deleted description is derived again on the next poll
+                self.input_buffer.clear();
+                self.save_note();
+            }
+            // Closing a note keeps what was typed; a description edit is abandoned
+            KeyCode::Esc if !description => self.save_note(),
+            KeyCode::Esc => {
+                self.note_target = None;
+                self.input_buffer.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Enter
+                if !description
+                    && self.input_buffer.as_str().split('\n').count() < NOTE_MAX_LINES =>
+            {
+                self.input_buffer.insert('\n');
+            }
+            KeyCode::Char(c) if !ctrl => self.input_buffer.insert(c),
+            KeyCode::Backspace => {
+                self.input_buffer.backspace();
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_tags_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter => match tags::parse(self.input_buffer.as_str()) {
+                Ok(tags) => {
+                    if let Some(name) = self.tag_target.take() {
+                        for session in self.sessions.iter_mut().filter(|s| s.name == name) {
+                            session.tags.clone_from(&tags);
+                        }
+                        self.state.tags.set(&name, tags, text::unix_now());
+                        self.save_state();
+                        self.rebuild_rows(self.selected_key());
+                    }
+                    self.input_buffer.clear();
+                    self.input_mode = InputMode::Normal;
+                }
+                Err(e) => self.notify(Level::Error, e),
+            },
+            KeyCode::Esc => {
+                self.tag_target = None;
+                self.input_buffer.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char(c) if !key.modifiers.contains(KeyModifiers::CONTROL) => {
+                self.input_buffer.insert(c)
+            }
+            _ => {
+                self.input_buffer.edit(&key);
+            }
+        }
+        Ok(false)
+    }
+
+    /// Tags of the listed sessions, with how many have each
+    pub fn listed_tags(&self) -> BTreeMap<&str, usize> {
+        let mut tags = BTreeMap::new();
+        for tag in self.sessions.iter().flat_map(|s| &s.tags) {
+            *tags.entry(tag.as_str()).or_default() += 1;
+        }
+        tags
+    }
+
+    fn handle_tag_filter_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let count = self.listed_tags().len();
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down if self.tag_selected < count => {
+                self.tag_selected += 1;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.tag_selected = self.tag_selected.saturating_sub(1);
+            }
+            KeyCode::Enter => {
+                let filter = match self.tag_selected.checked_sub(1) {
+                    Some(index) => self
+                        .listed_tags()
+                        .keys()
+                        .nth(index)
+                        .map(|tag| format!("#{}", tag))
+                        .unwrap_or_default(),
+                    None => String::new(),
+                };
+                self.set_session_filter(filter);
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => self.input_mode = InputMode::Normal,
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Store the note dialog's text as the note or description it edits
+    fn save_note(&mut self) {
+        if let Some(name) = self.note_target.take() {
+            match self.note_kind {
+                NoteKind::Note => self.state.set_note(&name, self.input_buffer.as_str()),
+                NoteKind::Description => {
+                    self.state
+                        .descriptions
+                        .set(&name, self.input_buffer.as_str(), text::unix_now())
+                }
+            }
+            self.save_state();
+        }
+        self.input_buffer.clear();
+        self.input_mode = InputMode::Normal;
+    }
+
+    fn handle_broadcast_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter if !self.input_buffer.as_str().trim().is_empty() => {
+                self.input_mode = InputMode::ConfirmingBroadcast;
+            }
+            KeyCode::Esc => {
+                self.input_buffer.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char(c) => self.input_buffer.insert(c),
+            KeyCode::Backspace => {
+                self.input_buffer.backspace();
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_schedule_time_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter => match schedule::parse_when_local(self.input_buffer.as_str()) {
+                Ok(at) => {
+                    if let Some((_, when)) = &mut self.schedule_target {
+                        *when = Some(at);
+                    }
+                    self.notifications.clear();
+                    self.input_buffer.clear();
+                    self.input_mode = InputMode::SchedulingPrompt;
+                }
+                Err(e) => self.notify(Level::Error, e),
+            },
+            KeyCode::Esc => {
+                self.schedule_target = None;
+                self.input_buffer.clear();
+                self.notifications.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char(c) => self.input_buffer.insert(c),
+            KeyCode::Backspace => {
+                self.input_buffer.backspace();
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_schedule_prompt_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter if !self.input_buffer.as_str().trim().is_empty() => {
+                if let Some((name, Some(at))) = self.schedule_target.take() {
+                    let prompt = self.input_buffer.take();
+                    self.update_schedules(|state| state.add_schedule(&name, &prompt, at));
+                    self.notify(
+                        Level::Success,
+                        format!(
+                            "Scheduled for {} at {}",
+                            name,
+                            schedule::format_at(at, text::unix_now())
+                        ),
+                    );
+                }
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc => {
+                self.schedule_target = None;
+                self.input_buffer.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char(c) => self.input_buffer.insert(c),
+            KeyCode::Backspace => {
+                self.input_buffer.backspace();
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_adding_rule_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter => {
+                let Some(session) = self.rule_target.clone() else {
+                    self.input_mode = InputMode::Normal;
+                    return Ok(false);
+                };
+                match rules::parse_line(&session, self.input_buffer.as_str()) {
+                    Ok(mut spec) => {
+                        let name = self.rules.unique_name(&format!("{} {}", session, spec.on));
+                        spec.name = Some(name.clone());
+                        let mut local = self.rules.local_specs();
+                        local.push(spec);
+                        // Toggles and spent flags of an old rule by this name don't carry over
+                        self.state.rule_flags.forget(&name);
+                        self.rules = RuleBook::new(&self.rules_config(), &local);
+                        self.save_rules();
+                        self.notify(Level::Success, format!("Added rule '{}'", name));
+                        self.rule_target = None;
+                        self.input_buffer.clear();
+                        self.input_mode = InputMode::Normal;
+                    }
+                    Err(e) => self.notify(Level::Error, e),
+                }
+            }
+            KeyCode::Esc => {
+                self.rule_target = None;
+                self.input_buffer.clear();
+                self.notifications.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char(c) => self.input_buffer.insert(c),
+            KeyCode::Backspace => {
+                self.input_buffer.backspace();
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// The rules that came from the config
+    fn rules_config(&self) -> Vec<rules::RuleSpec> {
+        self.rules
+            .rules
+            .iter()
+            .filter(|r| !r.local)
+            .map(|r| r.spec.clone())
+            .collect()
+    }
+
+    fn handle_rules_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let count = self.rules.rules.len();
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down if self.rules_selected + 1 < count => {
+                self.rules_selected += 1;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.rules_selected = self.rules_selected.saturating_sub(1);
+            }
+            KeyCode::Char(' ') | KeyCode::Char('e') => {
+                if let Some(rule) = self.rules.rules.get(self.rules_selected) {
+                    let enabled = self.state.rule_flags.toggle(rule);
+                    self.notify(
+                        Level::Info,
+                        format!(
+                            "Rule '{}' {}",
+                            rule.key,
+                            if enabled { "enabled" } else { "disabled" }
+                        ),
+                    );
+                    self.save_state();
+                }
+            }
+            KeyCode::Char('d') | KeyCode::Char('x') | KeyCode::Delete => {
+                match self.rules.rules.get(self.rules_selected) {
+                    Some(rule) if rule.local => {
+                        let key = rule.key.clone();
+                        self.rules.rules.remove(self.rules_selected);
+                        self.state.rule_flags.forget(&key);
+                        self.save_rules();
+                        self.notify(Level::Success, format!("Deleted rule '{}'", key));
+                        let last = self.rules.rules.len().saturating_sub(1);
+                        self.rules_selected = self.rules_selected.min(last);
+                    }
+                    Some(_) => {
+                        self.notify(
+                            Level::Warning,
+                            "Rules from the config can only be disabled here",
+                        );
+                    }
+                    None => {}
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('W') => {
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_schedules_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let count = self.state.schedules.len();
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down if self.schedule_selected + 1 < count => {
+                self.schedule_selected += 1;
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.schedule_selected = self.schedule_selected.saturating_sub(1);
+            }
+            KeyCode::Char('d') | KeyCode::Char('x') | KeyCode::Delete => {
+                if let Some(id) = self
+                    .state
+                    .schedules
+                    .get(self.schedule_selected)
+                    .map(|s| s.id)
+                {
+                    let cancelled = self.update_schedules(|state| state.cancel_schedule(id));
+                    if let Some(cancelled) = cancelled {
+                        self.notify(
+                            Level::Info,
+                            format!("Cancelled scheduled prompt for {}", cancelled.session),
+                        );
+                    }
+                    let last = self.state.schedules.len().saturating_sub(1);
+                    self.schedule_selected = self.schedule_selected.min(last);
+                }
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('P') => {
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_confirming_broadcast_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                let plan = broadcast::plan(&self.sessions, &self.marked, self.broadcast_force);
+                if plan.recipients.is_empty() {
+                    self.notify(Level::Warning, "No sessions to broadcast to");
+                } else {
+                    let text = self.input_buffer.take();
+                    self.pending_actions
+                        .push(Action::Broadcast(plan.recipients, text));
+                }
+                self.input_buffer.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char('f') => self.broadcast_force = !self.broadcast_force,
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.input_buffer.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_error_details_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let lines = self.current_error_details().map_or(0, |d| d.chain.len());
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.error_details_scroll =
+                    (self.error_details_scroll + 1).min(lines.saturating_sub(1));
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.error_details_scroll = self.error_details_scroll.saturating_sub(1);
+            }
+            KeyCode::Char('y') => {
+                if let Some(details) = self.current_error_details() {
+                    let text = details.chain.join("\n");
+                    self.pending_actions.push(Action::CopyText(text));
+                }
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc | KeyCode::Char('q') | KeyCode::Char('e') => {
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Lines the help overlay shows at once
+    fn help_view_height(&self) -> usize {
+        centered_rect(60, 80, self.area).height.saturating_sub(2) as usize
+    }
+
+    fn handle_help_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let last_top = help_lines().len().saturating_sub(self.help_view_height());
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => {
+                self.help_scroll = (self.help_scroll + 1).min(last_top);
+            }
+            KeyCode::Char('k') | KeyCode::Up => {
+                self.help_scroll = self.help_scroll.saturating_sub(1);
+            }
+            KeyCode::Esc | KeyCode::Char('?') | KeyCode::Char('q') => {
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    /// Sessions whose agent would be left mid-task by quitting
+    pub fn working_sessions(&self) -> Vec<&TmuxSession> {
+        self.sessions
+            .iter()
+            .filter(|s| matches!(s.status, AgentStatus::Busy | AgentStatus::WaitingForInput))
+            .collect()
+    }
+
+    /// Quit, asking first if agents are mid-task; asking again quits
+    fn request_quit(&mut self) -> Result<bool> {
+        if self.confirm_quit
+            && self.input_mode != InputMode::ConfirmingQuit
+            && !self.working_sessions().is_empty()
+        {
+            self.quit_from = self.input_mode;
+            self.input_mode = InputMode::ConfirmingQuit;
+            return Ok(false);
+        }
+        self.handle_action(Action::Quit)
+    }
+
+    fn handle_confirming_quit_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Char('y') | KeyCode::Char('Y') => {
+                return self.handle_action(Action::Quit);
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.input_mode = self.quit_from;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_confirming_restart_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                if let Some(session) = self.selected_session() {
+                    self.pending_actions
+                        .push(Action::RestartAgent(session.id.clone()));
+                }
+                self.input_mode = self.base_mode();
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.input_mode = self.base_mode();
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_confirming_restore_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                let sessions = std::mem::take(&mut self.missing_sessions);
+                self.pending_actions.push(Action::RestoreSessions(sessions));
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.missing_sessions.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_digest_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter => {
+                if let Some(first) = self.digest.first() {
+                    let id = first.session_id.clone();
+                    if self.select_session_id(&id) {
+                        self.record_jump();
+                    }
+                }
+                self.digest.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Esc | KeyCode::Char('q') => {
+                self.digest.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_confirming_cleanup_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => {
+                for verdict in std::mem::take(&mut self.cleanup_candidates) {
+                    if self.enforcing.insert(verdict.session_id.clone()) {
+                        tracing::info!("Cleanup: archiving and closing '{}'", verdict.name);
+                        self.pending_actions.push(Action::ArchiveAndKill(verdict));
+                    }
+                }
+                self.input_mode = InputMode::Normal;
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.cleanup_candidates.clear();
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_confirming_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Char(c @ ('y' | 'Y' | 'x' | 'X')) => {
+                self.input_mode = InputMode::Normal;
+                if let Some(session) = self.selected_session() {
+                    let name = session.name.clone();
+                    self.pending_actions
+                        .push(Action::DeleteSession(session.id.clone()));
+                    // 'y' keeps the note for a later session of the same name
+                    if c.eq_ignore_ascii_case(&'x') && self.state.note(&name).is_some() {
+                        self.state.set_note(&name, "");
+                        self.save_state();
+                    }
+                    // Removing the worktree is asked separately; it may hold uncommitted work
+                    if let Some(worktree) = self.state.worktrees.get(&name) {
+                        self.worktree_removal = Some((name, worktree.clone()));
+                        self.input_mode = InputMode::ConfirmingWorktreeRemoval;
+                    }
+                }
+            }
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => {
+                self.input_mode = InputMode::Normal;
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn handle_worktree_removal_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let remove = match key.code {
+            KeyCode::Char('y') | KeyCode::Char('Y') => true,
+            KeyCode::Char('n') | KeyCode::Char('N') | KeyCode::Esc => false,
+            _ => return Ok(false),
+        };
+        self.input_mode = InputMode::Normal;
+        let Some((name, worktree)) = self.worktree_removal.take() else {
+            return Ok(false);
+        };
+        // Either way the session is gone, so its worktree is no longer ours to offer
+        self.state.worktrees.remove(&name);
+        self.save_state();
+        if remove {
+            self.pending_actions.push(Action::RemoveWorktree(worktree));
+        } else {
+            self.notify(
+                Level::Info,
+                format!("Kept worktree {}", worktree.path.display()),
+            );
+        }
+        Ok(false)
+    }
+
+    fn handle_preview_key(&mut self, key: KeyEvent) -> Result<bool> {
+        let Some(preview) = self.preview.as_mut() else {
+            self.input_mode = InputMode::Normal;
+            return Ok(false);
+        };
+
+        if preview.search.editing {
+            match key.code {
+                KeyCode::Enter => preview.commit_search(),
+                KeyCode::Esc => preview.search.clear(),
+                KeyCode::Char(c) => preview.search.query.push(c),
+                KeyCode::Backspace => {
+                    preview.search.query.pop();
+                }
+                _ => {}
+            }
+            return Ok(false);
+        }
+
+        let was_following = preview.viewport.is_following();
+        match key.code {
+            KeyCode::Char('q') | KeyCode::Esc => {
+                self.preview = None;
+                self.input_mode = InputMode::Normal;
+                return Ok(false);
+            }
+            KeyCode::Char('j') | KeyCode::Down => preview.viewport.scroll_down(1),
+            KeyCode::Char('k') | KeyCode::Up => preview.viewport.scroll_up(1),
+            KeyCode::PageDown | KeyCode::Char(' ') => preview.viewport.page_down(),
+            KeyCode::PageUp => preview.viewport.page_up(),
+            KeyCode::Char('g') | KeyCode::Home => preview.viewport.scroll_to_top(),
+            KeyCode::Char('G') | KeyCode::End => preview.viewport.scroll_to_bottom(),
+            KeyCode::Char('/') => preview.search.begin(),
+            KeyCode::Char('n') => preview.next_match(),
+            KeyCode::Char('N') => preview.prev_match(),
+            KeyCode::Char('s') => preview.toggle_smart_case(),
+            _ => {}
+        }
+
+        // Re-pinning to the bottom picks up output missed while frozen
+        if !was_following && preview.viewport.is_following() {
+            self.pending_actions
+                .push(Action::CapturePane(preview.session_id.clone()));
+        }
+        Ok(false)
+    }
+
+    fn handle_dashboard_key(&mut self, key: KeyEvent) -> Result<bool> {
+        if self.read_only && matches!(key.code, KeyCode::Char('i' | 'p' | 'R')) {
+            self.notify(Level::Warning, "Read-only: another instance is running");
+            return Ok(false);
+        }
+        match key.code {
+            KeyCode::Esc | KeyCode::Char('q') => self.close_dashboard(),
+            KeyCode::Left | KeyCode::Char('h') => self.cycle_dashboard(false),
+            KeyCode::Right | KeyCode::Char('l') => self.cycle_dashboard(true),
+            KeyCode::Char('i') => {
+                if let Some(session) = self.selected_session() {
+                    self.pending_actions
+                        .push(Action::Interrupt(session.id.clone()));
+                }
+            }
+            KeyCode::Char('p') => {
+                self.input_buffer.clear();
+                self.input_mode = InputMode::DashboardPrompt;
+            }
+            KeyCode::Char('R') => self.input_mode = InputMode::ConfirmingRestart,
+            KeyCode::Enter => self.attach_selected(Action::attach),
+            KeyCode::Char(c @ '0'..='9') => {
+                let index = c.to_digit(10).unwrap_or_default();
+                if let Some(session) = self.selected_session()
+                    && self.dashboard_windows.iter().any(|w| w.index == index)
+                {
+                    self.pending_actions
+                        .push(Action::SelectWindow(tmux::window_target(
+                            &session.id,
+                            index,
+                        )));
+                    self.attach_selected(Action::attach);
+                }
+            }
+            _ => {
+                if let Some(preview) = &mut self.preview {
+                    let was_following = preview.viewport.is_following();
+                    match key.code {
+                        KeyCode::Char('j') | KeyCode::Down => preview.viewport.scroll_down(1),
+                        KeyCode::Char('k') | KeyCode::Up => preview.viewport.scroll_up(1),
+                        KeyCode::PageDown => preview.viewport.page_down(),
+                        KeyCode::PageUp => preview.viewport.page_up(),
+                        KeyCode::Char('G') | KeyCode::End => preview.viewport.scroll_to_bottom(),
+                        _ => {}
+                    }
+                    if !was_following && preview.viewport.is_following() {
+                        self.pending_actions
+                            .push(Action::CapturePane(preview.session_id.clone()));
+                    }
+                }
+            }
+        }
+        Ok(false)
+    }
+
+    fn handle_dashboard_prompt_key(&mut self, key: KeyEvent) -> Result<bool> {
+        match key.code {
+            KeyCode::Enter if !self.input_buffer.as_str().trim().is_empty() => {
+                if let Some(session) = self.selected_session() {
+                    let recipient = Recipient {
+                        id: session.id.clone(),
+                        name: session.name.clone(),
+                    };
+                    let text = self.input_buffer.take();
+                    self.pending_actions
+                        .push(Action::Broadcast(vec![recipient], text));
+                }
+                self.input_mode = InputMode::Dashboard;
+            }
+            KeyCode::Esc => {
+                self.input_buffer.clear();
+                self.input_mode = InputMode::Dashboard;
+            }
+            KeyCode::Char(c) => self.input_buffer.insert(c),
+            KeyCode::Backspace => {
+                self.input_buffer.backspace();
+            }
+            _ => {}
+        }
+        Ok(false)
+    }
+
+    fn open_preview(&mut self) {
+        if let Some(session) = self.selected_session() {
+            let preview = PanePreview::new(session.id.clone(), session.name.clone());
+            self.pending_actions
+                .push(Action::CapturePane(session.id.clone()));
+            self.preview = Some(preview);
+            self.input_mode = InputMode::Preview;
+        }
+    }
+
+    fn open_dashboard(&mut self) {
+        if self.selected_session().is_some() {
+            self.dashboard_open = true;
+            self.input_mode = InputMode::Dashboard;
+            self.show_in_dashboard();
+        }
+    }
+
+    /// Point the dashboard's pane and windows at the selected session
+    fn show_in_dashboard(&mut self) {
+        let Some(session) = self.selected_session() else {
+            return;
+        };
+        let id = session.id.clone();
+        self.preview = Some(PanePreview::new(id.clone(), session.name.clone()));
+        self.dashboard_windows.clear();
+        self.pending_actions.push(Action::CapturePane(id.clone()));
+        self.pending_actions.push(Action::ListWindows(id));
+    }
+
+    /// Move the dashboard to the adjacent session, past collapsed group headers
+    fn cycle_dashboard(&mut self, forward: bool) {
+        let mut at = self.list_state.selected();
+        for _ in 0..self.rows.len() {
+            at = session_list::step_selection(&self.rows, at, forward);
+            if let Some(i) = at
+                && matches!(self.rows[i], ListRow::Session(_))
+            {
+                self.list_state.select(at);
+                self.show_in_dashboard();
+                return;
+            }
+        }
+    }
+
+    fn close_dashboard(&mut self) {
+        self.dashboard_open = false;
+        self.dashboard_windows.clear();
+        self.preview = None;
+        self.input_mode = InputMode::Normal;
+    }
+
+    /// Mode a dialog returns to: the dashboard if it opened the dialog
+    fn base_mode(&self) -> InputMode {
+        if self.dashboard_open {
+            InputMode::Dashboard
+        } else {
+            InputMode::Normal
+        }
+    }
+
+    fn next_session(&mut self) {
+        let next = session_list::step_selection(&self.rows, self.list_state.selected(), true);
+        if next.is_some() {
+            self.list_state.select(next);
+        }
+    }
+
+    fn previous_session(&mut self) {
+        let previous = session_list::step_selection(&self.rows, self.list_state.selected(), false);
+        if previous.is_some() {
+            self.list_state.select(previous);
+        }
+    }
+
+    pub fn render(&mut self, frame: &mut Frame) {
+        self.render_screen(frame);
+        self.theme.finish(frame.buffer_mut());
+    }
+
+    fn render_screen(&mut self, frame: &mut Frame) {
+        if frame.area() != self.area {
+            self.resize(frame.area());
+        }
+        if self.input_mode == InputMode::Preview {
+            self.render_preview(frame, frame.area());
+            return;
+        }
+        if self.dashboard_open {
+            self.render_dashboard(frame, frame.area());
+            if self.input_mode == InputMode::ConfirmingRestart {
+                self.render_restart_dialog(frame);
+            }
+            return;
+        }
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(3), // Header
+                Constraint::Min(0),    // Main content
+                Constraint::Length(3), // Footer/status
+            ])
+            .split(frame.area());
+
+        self.render_header(frame, chunks[0]);
+        self.render_main(frame, chunks[1]);
+        self.render_footer(frame, chunks[2]);
+
+        // Render modal dialogs on top
+        match self.input_mode {
+            InputMode::Creating => self.render_input_dialog(
+                frame,
+                " Create New Session ",
+                "Enter session name:",
+                "Press Enter to choose what to run, Esc to cancel",
+            ),
+            InputMode::Renaming => self.render_input_dialog(
+                frame,
+                " Rename Session ",
+                "Enter session name:",
+                "Press Enter to rename, Esc to cancel",
+            ),
+            InputMode::CreatingCommand => self.render_input_dialog(
+                frame,
+                &format!(
+                    " Command for '{}' ",
+                    self.create_name.as_deref().unwrap_or_default()
+                ),
+                "Run in the new session (blank for a shell):",
+                "Press Enter to choose the directory, Esc to go back",
+            ),
+            InputMode::ChoosingLauncher => self.render_launcher_menu(frame),
+            InputMode::ChoosingWindow => self.render_window_picker(frame),
+            InputMode::ChoosingClient => self.render_client_picker(frame),
+            InputMode::ChoosingCommand => self.render_command_menu(frame),
+            InputMode::ChoosingTemplate => self.render_template_menu(frame),
+            InputMode::TemplateDir => self.render_input_dialog(
+                frame,
+                &format!(
+                    " Template '{}' ",
+                    self.templates
+                        .get(self.template_selected)
+                        .map(|t| t.name.as_str())
+                        .unwrap_or_default()
+                ),
+                "Project directory:",
+                "Press Enter to create, Esc to go back",
+            ),
+            InputMode::CreatingDir => self.render_create_dir_dialog(frame),
+            InputMode::Confirming => self.render_confirm_dialog(frame),
+            InputMode::EditingNote => self.render_note_dialog(frame),
+            InputMode::EditingTags => self.render_input_dialog(
+                frame,
+                &format!(
+                    " Tags of '{}' ",
+                    self.tag_target.as_deref().unwrap_or_default()
+                ),
+                "Tags, separated by spaces:",
+                "Press Enter to save, Esc to cancel",
+            ),
+            InputMode::ChoosingTag => self.render_tag_menu(frame),
+            InputMode::Broadcasting => self.render_broadcast_dialog(frame),
+            InputMode::ConfirmingBroadcast => self.render_broadcast_confirm_dialog(frame),
+            InputMode::BroadcastResults => self.render_broadcast_results(frame),
+            InputMode::ConfirmingRestart => self.render_restart_dialog(frame),
+            InputMode::ConfirmingQuit => self.render_quit_dialog(frame),
+            InputMode::ConfirmingRestore => self.render_restore_dialog(frame),
+            InputMode::StartupDigest => self.render_digest(frame),
+            InputMode::ConfirmingCleanup => self.render_cleanup_dialog(frame),
+            InputMode::ErrorDetails => self.render_error_details(frame),
+            InputMode::SchedulingTime | InputMode::SchedulingPrompt => {
+                self.render_schedule_dialog(frame)
+            }
+            InputMode::Schedules => self.render_schedules(frame),
+            InputMode::AddingRule => self.render_rule_dialog(frame),
+            InputMode::Rules => self.render_rules(frame),
+            InputMode::Usage => self.render_usage(frame),
+            InputMode::Logs => self.render_logs(frame),
+            InputMode::PickingDir => self.render_dir_picker(frame),
+            InputMode::Help => self.render_help(frame),
+            InputMode::CreatingWorktree => self.render_worktree_dialog(frame),
+            InputMode::ConfirmingWorktreeRemoval => self.render_worktree_removal_dialog(frame),
+            InputMode::Normal
+            | InputMode::Filtering
+            | InputMode::Preview
+            | InputMode::Dashboard
+            | InputMode::DashboardPrompt => {}
+        }
+    }
+
+    /// Fit size-dependent state to a new terminal size before the next frame
+    fn resize(&mut self, area: Rect) {
+        self.area = area;
+        if let Some(preview) = &mut self.preview {
+            preview.viewport.set_height(preview_viewport_height(area));
+        }
+        if let Some(details) = self.current_error_details() {
+            let last = details.chain.len().saturating_sub(1);
+            self.error_details_scroll = self.error_details_scroll.min(last);
+        }
+    }
+
+    /// Color for data of the given age: normal, stale, then wedged
+    fn freshness_color(&self, age: Duration) -> Color {
+        if age >= WEDGED_AFTER {
+            self.theme.error
+        } else if age >= STALE_AFTER {
+            self.theme.warning
+        } else {
+            self.theme.dim
+        }
+    }
+
+    fn level_color(&self, level: Level) -> Color {
+        match level {
+            Level::Info => self.theme.fg,
+            Level::Success => self.theme.success,
+            Level::Warning => self.theme.warning,
+            Level::Error => self.theme.error,
+        }
+    }
+
+    fn render_header(&self, frame: &mut Frame, area: Rect) {
+        let freshness = match self.last_update {
+            Some(at) => {
+                let age = at.elapsed();
+                Span::styled(
+                    format!(
+                        "updated {} ago ",
+                        text::format_duration_short(age.as_secs())
+                    ),
+                    Style::default().fg(self.freshness_color(age)),
+                )
+            }
+            None => Span::styled("waiting for tmux… ", Style::default().fg(self.theme.dim)),
+        };
+
+        let mut block = Block::default()
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.dim));
+        if let Some(server) = self.server_summary() {
+            block = block.title_top(Line::from(server).right_aligned());
+        }
+        let title = Paragraph::new(Line::from(vec![
+            Span::styled(
+                " AgentRusty ",
+                Style::default()
+                    .fg(self.theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            ),
+            Span::styled(
+                "│ Mission Control for AI Agents",
+                Style::default().fg(self.theme.dim),
+            ),
+            Span::styled(
+                if self.read_only { " [read-only]" } else { "" },
+                Style::default().fg(self.theme.warning),
+            ),
+            Span::styled(
+                if self.bell.muted { " [bell muted]" } else { "" },
+                Style::default().fg(self.theme.dim),
+            ),
+            Span::styled(self.alert_summary(), Style::default().fg(self.theme.error)),
+        ]))
+        .block(block);
+        frame.render_widget(title, area);
+
+        // Sessions per status, most urgent first
+        let mut status_line: Vec<Span> = [
+            AgentStatus::WaitingForInput,
+            AgentStatus::Error,
+            AgentStatus::Busy,
+            AgentStatus::Idle,
+            AgentStatus::Unknown,
+        ]
+        .into_iter()
+        .filter_map(|status| {
+            let count = self.sessions.iter().filter(|s| s.status == status).count();
+            (count > 0).then(|| {
+                Span::styled(
+                    format!("{} {}  ", self.theme.icons.get(status), count),
+                    Style::default().fg(self.theme.status_color(status)),
+                )
+            })
+        })
+        .collect();
+        status_line.push(freshness);
+
+        let inner = Block::default().borders(Borders::ALL).inner(area);
+        frame.render_widget(
+            Paragraph::new(Line::from(status_line).right_aligned()),
+            inner,
+        );
+    }
+
+    /// ` tmux 3.4 · /tmp/tmux-1000/default · 3 sessions, 7 windows ` for the
+    /// header's top border, with servers that aren't running in the warning color
+    fn server_summary(&self) -> Option<Vec<Span<'static>>> {
+        let info = self.server_info.as_ref()?;
+        let (running, down): (Vec<&TmuxServer>, Vec<&TmuxServer>) =
+            info.servers.iter().partition(|s| s.running);
+        let warning = Style::default().fg(self.theme.warning);
+        let dim = Style::default().fg(self.theme.dim);
+        if running.is_empty() {
+            let paths: Vec<&str> = down
+                .iter()
+                .map(|s| s.socket_path.as_str())
+                .filter(|p| !p.is_empty())
+                .collect();
+            let text = if paths.is_empty() {
+                format!(" {}: no server running ", info.version)
+            } else {
+                format!(
+                    " {}: no server running on {} ",
+                    info.version,
+                    paths.join(", ")
+                )
+            };
+            return Some(vec![Span::styled(text, warning)]);
+        }
+        let place = match running.as_slice() {
+            [server] if !server.socket_path.is_empty() => server.socket_path.clone(),
+            [_] => "running".to_string(),
+            servers => format!("{} servers", servers.len()),
+        };
+        let mut spans = vec![Span::styled(
+            format!(
+                " {} · {} · {} sessions, {} windows ",
+                info.version,
+                place,
+                running.iter().map(|s| s.sessions).sum::<usize>(),
+                running.iter().map(|s| s.windows).sum::<usize>()
+            ),
+            dim,
+        )];
+        for server in down {
+            let name = Path::new(&server.socket_path)
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| "a server".to_string());
+            spans.push(Span::styled(format!("· {} not running ", name), warning));
+        }
+        Some(spans)
+    }
+
+    /// ` ⚠ api error 5m, web busy 1h` for the alerts currently firing
+    fn alert_summary(&self) -> String {
+        let active = self.alerts.active();
+        if active.is_empty() {
+            return String::new();
+        }
+        let mut seen = BTreeSet::new();
+        let alerts: Vec<String> = active
+            .iter()
+            // A session that fired several rules is listed once
+            .filter(|alert| seen.insert(alert.session_id.as_str()))
+            .map(|alert| {
+                format!(
+                    "{} {} {}",
+                    alert.session,
+                    metrics::label(alert.rule.status),
+                    text::format_duration_short(alert.since.elapsed().as_secs())
+                )
+            })
+            .collect();
+        format!(" ⚠ {}", alerts.join(", "))
+    }
+
+    fn render_main(&mut self, frame: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([
+                Constraint::Percentage(40), // Session list
+                Constraint::Percentage(60), // Detail pane
+            ])
+            .split(area);
+
+        self.render_session_list(frame, chunks[0]);
+        self.render_detail_pane(frame, chunks[1]);
+    }
+
+    fn session_list_title(&self) -> String {
+        match self.state.sort_by {
+            Some(column) => format!(" Sessions · by {} ", column.label()),
+            None => " Sessions ".to_string(),
+        }
+    }
+
+    /// `work (3)`: the server of the session at `first`, and how many sessions it has
+    fn server_heading(&self, first: usize) -> String {
+        let socket = &self.sessions[first].socket;
+        let count = self.sessions.iter().filter(|s| &s.socket == socket).count();
+        format!("{} ({})", socket, count)
+    }
+
+    fn render_session_list(&mut self, frame: &mut Frame, area: Rect) {
+        if self.list_layout == ListLayout::Table {
+            self.render_session_table(frame, area);
+            return;
+        }
+        self.visible_columns = vec![Column::Status, Column::Name];
+
+        let items: Vec<ListItem> = if self.sessions.is_empty() {
+            vec![ListItem::new(Line::from(Span::styled(
+                "  No sessions found. Press 'n' to create one.",
+                Style::default().fg(self.theme.dim),
+            )))]
+        } else {
+            let indent = match self.state.list_display {
+                ListDisplay::Flat => "",
+                ListDisplay::Grouped => "  ",
+            };
+            // Borders, highlight symbol, indent, status icon and number come before the name
+            let mut name_width = (area.width as usize).saturating_sub(8 + indent.len());
+            // The sparkline goes at the end of the row when the name keeps room
+            let ceiling = self.output_ceiling();
+            let show_output = ceiling > 0 && name_width > 2 * activity::LIST_CELLS;
+            if show_output {
+                name_width -= activity::LIST_CELLS + 1;
+            }
+            let mut number = 0;
+            self.rows
+                .iter()
+                .map(|row| match *row {
+                    ListRow::Header {
+                        group,
+                        count,
+                        collapsed,
+                    } => {
+                        let marker = if collapsed { "▸" } else { "▾" };
+                        ListItem::new(Line::from(Span::styled(
+                            format!("{} {} ({})", marker, group.label(), count),
+                            Style::default()
+                                .fg(self.theme.accent)
+                                .add_modifier(Modifier::BOLD),
+                        )))
+                    }
+                    ListRow::Server(first) => ListItem::new(Line::from(Span::styled(
+                        self.server_heading(first),
+                        Style::default()
+                            .fg(self.theme.fg)
+                            .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                    ))),
+                    ListRow::Window(index, window) => {
+                        let Some(w) = self.window_row(index, window) else {
+                            return ListItem::new("");
+                        };
+                        let (branch, label) = self.window_label(index, window, w);
+                        let style = if w.active {
+                            Style::default()
+                                .fg(self.theme.fg)
+                                .add_modifier(Modifier::BOLD)
+                        } else {
+                            Style::default().fg(self.theme.fg)
+                        };
+                        let panes = if w.panes > 1 {
+                            format!(" ({} panes)", w.panes)
+                        } else {
+                            String::new()
+                        };
+                        ListItem::new(Line::from(vec![
+                            Span::raw(indent),
+                            Span::styled(branch, Style::default().fg(self.theme.dim)),
+                            Span::styled(label, style),
+                            Span::styled(
+                                format!("  {}{}", w.command, panes),
+                                Style::default().fg(self.theme.dim),
+                            ),
+                        ]))
+                    }
+                    ListRow::Session(index) => {
+                        let session = &self.sessions[index];
+                        let status_icon = self
+                            .theme
+                            .animated_status_icon(session.status, self.spinner_frame);
+                        let branch = session
+                            .git
+                            .as_ref()
+                            .filter(|_| self.show_branch_in_list)
+                            .map(|git| format!(" {}", git.label()))
+                            .unwrap_or_default();
+                        let note = if self.state.note(&session.name).is_some() {
+                            " ✎"
+                        } else {
+                            ""
+                        };
+                        let tags: String = session
+                            .tags
+                            .iter()
+                            .map(|tag| format!(" #{}", tag))
+                            .collect();
+                        let stale = if self.stale.contains_key(&session.id) {
+                            " stale"
+                        } else {
+                            ""
+                        };
+                        let age = if self.is_old(session) {
+                            format!(
+                                " {}",
+                                text::format_duration_short(
+                                    text::unix_now().saturating_sub(session.created_at)
+                                )
+                            )
+                        } else {
+                            String::new()
+                        };
+                        // What to press to attach, for the first nine on screen
+                        number += 1;
+                        let number = if number <= 9 {
+                            format!("{} ", number)
+                        } else {
+                            "  ".to_string()
+                        };
+                        let marked = self.marked.contains(&session.id);
+                        let mark = if marked { "+ " } else { "" };
+                        let name = Span::styled(
+                            format!(
+                                "{}{}",
+                                mark,
+                                text::truncate_middle(
+                                    &session.name,
+                                    name_width.saturating_sub(
+                                        branch.width()
+                                            + note.width()
+                                            + tags.width()
+                                            + stale.len()
+                                            + age.len()
+                                            + mark.len()
+                                    ),
+                                )
+                            ),
+                            if marked {
+                                Style::default()
+                                    .fg(self.theme.accent)
+                                    .add_modifier(Modifier::BOLD)
+                            } else if self.alerts.highlights(&session.id) {
+                                Style::default()
+                                    .fg(self.theme.error)
+                                    .add_modifier(Modifier::BOLD)
+                            } else {
+                                Style::default().fg(self.theme.fg)
+                            },
+                        );
+                        let branch = Span::styled(branch, Style::default().fg(self.theme.dim));
+                        let note = Span::styled(note, Style::default().fg(self.theme.accent));
+                        let tags = Span::styled(tags, Style::default().fg(self.theme.dim));
+                        let stale = Span::styled(stale, Style::default().fg(self.theme.warning));
+                        let age = Span::styled(age, Style::default().fg(self.theme.warning));
+
+                        let mut spans = vec![
+                            Span::raw(indent),
+                            status_icon,
+                            Span::styled(number, Style::default().fg(self.theme.dim)),
+                            name,
+                            note,
+                            tags,
+                            stale,
+                            age,
+                            branch,
+                        ];
+                        if show_output && !session.activity_trend.is_empty() {
+                            let used: usize = spans.iter().map(|s| s.width()).sum();
+                            let pad = (indent.len() + 4 + name_width + 1).saturating_sub(used);
+                            spans.push(Span::raw(" ".repeat(pad)));
+                            spans.push(self.output_sparkline(session, ceiling));
+                        }
+                        let mut lines = vec![Line::from(spans)];
+                        if let Some(description) = self.state.descriptions.get(&session.name) {
+                            // Under the name, past the status icon
+                            lines.push(Line::from(Span::styled(
+                                format!("{}  {}", indent, description),
+                                Style::default().fg(self.theme.dim),
+                            )));
+                        }
+                        ListItem::new(lines)
+                    }
+                })
+                .collect()
+        };
+
+        self.row_heights = items.iter().map(|item| item.height() as u16).collect();
+        let list = List::new(items)
+            .block(
+                Block::default()
+                    .title(self.session_list_title())
+                    .borders(Borders::ALL)
+                    .border_style(Style::default().fg(self.theme.dim)),
+            )
+            .highlight_style(
+                Style::default()
+                    .bg(self.theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("▶ ");
+
+        frame.render_stateful_widget(list, area, &mut self.list_state);
+        self.list_area = area.inner(Margin::new(1, 1));
+    }
+
+    /// Tree branch and `index: name` of a window row; `*` marks the window
+    /// the session shows
+    fn window_label(&self, index: usize, window: usize, w: &TmuxWindow) -> (&'static str, String) {
+        let last = self.sessions[index]
+            .window_tree
+            .as_ref()
+            .is_none_or(|windows| window + 1 == windows.len());
+        let branch = if last { " └ " } else { " ├ " };
+        let active = if w.active { "*" } else { "" };
+        (branch, format!("{}: {}{}", w.index, w.name, active))
+    }
+
+    fn render_session_table(&mut self, frame: &mut Frame, area: Rect) {
+        let block = Block::default()
+            .title(self.session_list_title())
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.dim));
+
+        // The highlight symbol takes two cells in front of every row
+        let available = block.inner(area).width.saturating_sub(2);
+        let columns = session_list::visible_columns(available);
+        let name_width = session_list::name_width(&columns, available) as usize;
+        let now = text::unix_now();
+        let ceiling = self.output_ceiling();
+
+        let header = Row::new(columns.iter().map(|&column| {
+            let mut title = column.title().to_string();
+            if self.state.sort_by == Some(column) {
+                title.push('↓');
+            }
+            Cell::from(title)
+        }))
+        .style(
+            Style::default()
+                .fg(self.theme.dim)
+                .add_modifier(Modifier::BOLD),
+        );
+
+        let rows: Vec<Row> = if self.sessions.is_empty() {
+            vec![
+                Row::new(columns.iter().map(|&column| match column {
+                    Column::Name => Cell::from("No sessions found. Press 'n' to create one."),
+                    _ => Cell::from(""),
+                }))
+                .style(Style::default().fg(self.theme.dim)),
+            ]
+        } else {
+            let mut number = 0;
+            self.rows
+                .iter()
+                .map(|row| match *row {
+                    ListRow::Header {
+                        group,
+                        count,
+                        collapsed,
+                    } => Row::new(columns.iter().map(|&column| match column {
+                        Column::Status => Cell::from(if collapsed { "▸" } else { "▾" }),
+                        Column::Name => Cell::from(format!("{} ({})", group.label(), count)),
+                        _ => Cell::from(""),
+                    }))
+                    .style(
+                        Style::default()
+                            .fg(self.theme.accent)
+                            .add_modifier(Modifier::BOLD),
+                    ),
+                    ListRow::Server(first) => {
+                        Row::new(columns.iter().map(|&column| match column {
+                            Column::Name => Cell::from(self.server_heading(first)),
+                            _ => Cell::from(""),
+                        }))
+                        .style(
+                            Style::default()
+                                .fg(self.theme.fg)
+                                .add_modifier(Modifier::BOLD | Modifier::UNDERLINED),
+                        )
+                    }
+                    ListRow::Window(index, window) => {
+                        let Some(w) = self.window_row(index, window) else {
+                            return Row::new(Vec::<Cell>::new());
+                        };
+                        let (branch, label) = self.window_label(index, window, w);
+                        Row::new(columns.iter().map(|&column| match column {
+                            Column::Name => Cell::from(format!("{}{}", branch, label)),
+                            Column::Agent => Cell::from(w.command.clone()),
+                            _ => Cell::from(""),
+                        }))
+                        .style(Style::default().fg(self.theme.fg))
+                    }
+                    ListRow::Session(index) => {
+                        let session = &self.sessions[index];
+                        // What to press to attach, as the list renderer shows it
+                        number += 1;
+                        let number = Span::styled(
+                            if number <= 9 {
+                                format!("{} ", number)
+                            } else {
+                                "  ".to_string()
+                            },
+                            Style::default().fg(self.theme.dim),
+                        );
+                        let name_width = name_width.saturating_sub(number.width());
+                        Row::new(columns.iter().map(|&column| {
+                            match column {
+                                Column::Status => Cell::from(
+                                    self.theme
+                                        .animated_status_icon(session.status, self.spinner_frame),
+                                ),
+                                Column::Name if self.marked.contains(&session.id) => {
+                                    Cell::from(Line::from(vec![
+                                        number.clone(),
+                                        Span::styled(
+                                            format!(
+                                                "+ {}",
+                                                text::truncate_middle(
+                                                    &session.name,
+                                                    name_width.saturating_sub(2)
+                                                )
+                                            ),
+                                            Style::default()
+                                                .fg(self.theme.accent)
+                                                .add_modifier(Modifier::BOLD),
+                                        ),
+                                    ]))
+                                }
+                                Column::Name if self.stale.contains_key(&session.id) => {
+                                    Cell::from(Line::from(vec![
+                                        number.clone(),
+                                        Span::raw(text::truncate_middle(
+                                            &session.name,
+                                            name_width.saturating_sub(6),
+                                        )),
+                                        Span::styled(
+                                            " stale",
+                                            Style::default().fg(self.theme.warning),
+                                        ),
+                                    ]))
+                                }
+                                Column::Name => Cell::from(Line::from(vec![
+                                    number.clone(),
+                                    Span::raw(text::truncate_middle(&session.name, name_width)),
+                                ])),
+                                Column::Agent => Cell::from(session.agent_kind().label()),
+                                Column::Age if self.is_old(session) => Cell::from(Span::styled(
+                                    text::format_duration_short(
+                                        now.saturating_sub(session.created_at),
+                                    ),
+                                    Style::default().fg(self.theme.warning),
+                                )),
+                                Column::Age => Cell::from(text::format_duration_short(
+                                    now.saturating_sub(session.created_at),
+                                )),
+                                Column::Activity => Cell::from(text::format_duration_short(
+                                    now.saturating_sub(session.activity_at),
+                                )),
+                                Column::Output => {
+                                    Cell::from(self.output_sparkline(session, ceiling))
+                                }
+                                Column::Clients => Cell::from(session.attached_clients.to_string()),
+                                Column::Used => Cell::from(
+                                    self.state
+                                        .attach_history
+                                        .last_attached(&session.name)
+                                        .map_or_else(
+                                            || "-".to_string(),
+                                            |at| {
+                                                text::format_duration_short(now.saturating_sub(at))
+                                            },
+                                        ),
+                                ),
+                            }
+                        }))
+                        .style(if self.alerts.highlights(&session.id) {
+                            Style::default()
+                                .fg(self.theme.error)
+                                .add_modifier(Modifier::BOLD)
+                        } else {
+                            Style::default().fg(self.theme.fg)
+                        })
+                    }
+                })
+                .collect()
+        };
+
+        let widths: Vec<Constraint> = columns
+            .iter()
+            .map(|&column| match column {
+                Column::Name => Constraint::Fill(1),
+                other => Constraint::Length(other.min_width()),
+            })
+            .collect();
+
+        let table = Table::new(rows, widths)
+            .header(header)
+            .block(block)
+            .row_highlight_style(
+                Style::default()
+                    .bg(self.theme.highlight)
+                    .add_modifier(Modifier::BOLD),
+            )
+            .highlight_symbol("▶ ");
+
+        // Share selection and scroll position with the list renderer
+        let mut table_state = TableState::default()
+            .with_offset(self.list_state.offset())
+            .with_selected(self.list_state.selected());
+        frame.render_stateful_widget(table, area, &mut table_state);
+        *self.list_state.offset_mut() = table_state.offset();
+        // Rows start under the header, one line each
+        let inner = area.inner(Margin::new(1, 1));
+        self.list_area = Rect {
+            y: inner.y + 1,
+            height: inner.height.saturating_sub(1),
+            ..inner
+        };
+        self.row_heights = vec![1; self.rows.len()];
+        self.visible_columns = columns;
+    }
+
+    /// Largest list sparkline cell across sessions, so rows compare with each other
+    fn output_ceiling(&self) -> u32 {
+        self.sessions
+            .iter()
+            .flat_map(|s| activity::resample(&s.activity_trend, activity::LIST_CELLS))
+            .max()
+            .unwrap_or(0)
+    }
+
+    fn output_sparkline(&self, session: &TmuxSession, ceiling: u32) -> Span<'static> {
+        Span::styled(
+            activity::sparkline(
+                &activity::resample(&session.activity_trend, activity::LIST_CELLS),
+                ceiling,
+            ),
+            Style::default().fg(self.theme.accent),
+        )
+    }
+
+    fn render_detail_pane(&self, frame: &mut Frame, area: Rect) {
+        let content = if let Some(session) = self.selected_session() {
+            let mut content = vec![
+                Line::from(vec![
+                    Span::styled("Name: ", Style::default().fg(self.theme.dim)),
+                    Span::styled(&session.name, Style::default().fg(self.theme.fg)),
                 ]),
                 Line::from(vec![
                     Span::styled("ID: ", Style::default().fg(self.theme.dim)),
                     Span::styled(&session.id, Style::default().fg(self.theme.fg)),
                 ]),
                 Line::from(vec![
-                    Span::styled("Status: ", Style::default().fg(self.theme.dim)),
+                    Span::styled("Status: ", Style::default().fg(self.theme.dim)),
+                    self.theme.status_icon(session.status),
+                    Span::styled(
+                        format!("{:?}", session.status),
+                        Style::default().fg(self.theme.status_color(session.status)),
+                    ),
+                ]),
+            ];
+            // Under the status line, wrapped so nothing of it is cut off
+            if let Some(note) = self.state.note(&session.name) {
+                let width = usize::from(area.width.saturating_sub(4));
+                let note_lines = note.lines().flat_map(|line| text::wrap(line, width));
+                content.push(Line::from(Span::styled(
+                    "Note:",
+                    Style::default().fg(self.theme.dim),
+                )));
+                content.extend(note_lines.map(|line| {
+                    Line::from(Span::styled(
+                        format!("  {}", line),
+                        Style::default().fg(self.theme.fg),
+                    ))
+                }));
+            }
+            content.extend([
+                Line::from(vec![
+                    Span::styled("Created: ", Style::default().fg(self.theme.dim)),
+                    Span::styled(
+                        if session.created_at == 0 {
+                            "unknown".to_string()
+                        } else {
+                            format!(
+                                "{} ({})",
+                                text::format_local_time(session.created_at),
+                                text::format_ago(session.created_at, text::unix_now())
+                            )
+                        },
+                        Style::default().fg(if self.is_old(session) {
+                            self.theme.warning
+                        } else {
+                            self.theme.fg
+                        }),
+                    ),
+                ]),
+                Line::from(vec![
+                    Span::styled("Clients: ", Style::default().fg(self.theme.dim)),
+                    Span::styled(
+                        session.attached_clients.to_string(),
+                        Style::default().fg(self.theme.fg),
+                    ),
+                ]),
+            ]);
+            // Each attached terminal under the count; the smallest one sets the pane's size
+            if let Some((id, clients)) = &self.clients
+                && *id == session.id
+            {
+                let size = |c: &AttachedClient| u32::from(c.width) * u32::from(c.height);
+                let smallest = clients.iter().map(size).min();
+                let differ = clients.iter().any(|c| Some(size(c)) != smallest);
+                content.extend(clients.iter().map(|client| {
+                    let clamps = differ && Some(size(client)) == smallest;
+                    Line::from(vec![
+                        Span::styled(
+                            format!("  {} ", client.name),
+                            Style::default().fg(self.theme.fg),
+                        ),
+                        Span::styled(
+                            format!("{}x{}", client.width, client.height),
+                            Style::default().fg(if clamps {
+                                self.theme.warning
+                            } else {
+                                self.theme.fg
+                            }),
+                        ),
+                        Span::styled(
+                            format!(" {}", client.termname),
+                            Style::default().fg(self.theme.dim),
+                        ),
+                    ])
+                }));
+            }
+            content.extend([
+                Line::from(vec![
+                    Span::styled("Last attached: ", Style::default().fg(self.theme.dim)),
+                    Span::styled(
+                        self.state
+                            .attach_history
+                            .last_attached(&session.name)
+                            .map_or_else(
+                                || "never".to_string(),
+                                |at| {
+                                    format!(
+                                        "{} ago",
+                                        text::format_duration_short(
+                                            text::unix_now().saturating_sub(at)
+                                        )
+                                    )
+                                },
+                            ),
+                        Style::default().fg(self.theme.fg),
+                    ),
+                    Span::styled(
+                        if self
+                            .last_attached
+                            .as_ref()
+                            .is_some_and(|(id, _)| *id == session.id)
+                        {
+                            " (latest, ` returns)"
+                        } else {
+                            ""
+                        },
+                        Style::default().fg(self.theme.accent),
+                    ),
+                ]),
+                Line::from(vec![
+                    Span::styled("Polling: ", Style::default().fg(self.theme.dim)),
+                    Span::styled(
+                        self.poll_rates
+                            .rate(&session.name, &self.state.poll_rates)
+                            .label(),
+                        Style::default().fg(self.theme.fg),
+                    ),
+                    Span::styled(" (p cycles)", Style::default().fg(self.theme.dim)),
+                ]),
+                Line::from(vec![
+                    Span::styled("Dir: ", Style::default().fg(self.theme.dim)),
+                    Span::styled(
+                        session.current_path.as_str(),
+                        Style::default().fg(self.theme.fg),
+                    ),
+                    Span::styled(
+                        if session.start_path.is_empty()
+                            || session.start_path == session.current_path
+                        {
+                            String::new()
+                        } else {
+                            format!(" (started in {})", session.start_path)
+                        },
+                        Style::default().fg(self.theme.dim),
+                    ),
+                ]),
+                Line::from(vec![
+                    Span::styled("Branch: ", Style::default().fg(self.theme.dim)),
+                    match &session.git {
+                        Some(git) => Span::styled(
+                            git.describe(),
+                            Style::default().fg(if git.dirty {
+                                self.theme.warning
+                            } else {
+                                self.theme.fg
+                            }),
+                        ),
+                        None => Span::styled(
+                            "not a git repository",
+                            Style::default().fg(self.theme.dim),
+                        ),
+                    },
+                ]),
+            ]);
+            if let Some(description) = self.state.descriptions.get(&session.name) {
+                content.insert(
+                    1,
+                    Line::from(vec![
+                        Span::styled("Description: ", Style::default().fg(self.theme.dim)),
+                        Span::styled(description, Style::default().fg(self.theme.dim)),
+                    ]),
+                );
+            }
+            if !session.pane_title.is_empty() {
+                content.push(Line::from(vec![
+                    Span::styled("Title: ", Style::default().fg(self.theme.dim)),
+                    Span::styled(
+                        session.pane_title.as_str(),
+                        Style::default().fg(self.theme.fg),
+                    ),
+                ]));
+            }
+            if !session.activity_trend.is_empty() {
+                let total: u32 = session.activity_trend.iter().sum();
+                let ceiling = session.activity_trend.iter().copied().max().unwrap_or(0);
+                content.push(Line::from(vec![
+                    Span::styled("Output: ", Style::default().fg(self.theme.dim)),
+                    Span::styled(
+                        activity::sparkline(&session.activity_trend, ceiling),
+                        Style::default().fg(self.theme.accent),
+                    ),
+                    Span::styled(
+                        format!(
+                            " {} lines in {}",
+                            total,
+                            text::format_duration_short(self.activity_window_secs)
+                        ),
+                        Style::default().fg(self.theme.dim),
+                    ),
+                ]));
+            }
+            if let Some(meta) = &session.claude
+                && session.agent_kind() == AgentKind::Claude
+            {
+                let started = meta.started_at.map_or_else(
+                    || "unknown".to_string(),
+                    |at| {
+                        format!(
+                            "{} ago",
+                            text::format_duration_long(text::unix_now().saturating_sub(at))
+                        )
+                    },
+                );
+                let fields = [
+                    ("Conversation: ", meta.session_id.clone()),
+                    (
+                        "Model: ",
+                        meta.model.clone().unwrap_or_else(|| "unknown".to_string()),
+                    ),
+                    ("Started: ", started),
+                ];
+                content.extend(fields.into_iter().map(|(label, value)| {
+                    Line::from(vec![
+                        Span::styled(label, Style::default().fg(self.theme.dim)),
+                        Span::styled(value, Style::default().fg(self.theme.fg)),
+                    ])
+                }));
+            }
+            if let Some(aider) = &session.aider
+                && session.agent_kind() == AgentKind::Aider
+            {
+                let fields = [("Model: ", &aider.model), ("Spent: ", &aider.cost)];
+                content.extend(fields.into_iter().map(|(label, value)| {
+                    Line::from(vec![
+                        Span::styled(label, Style::default().fg(self.theme.dim)),
+                        Span::styled(
+                            value.as_deref().unwrap_or("not announced yet").to_string(),
+                            Style::default().fg(self.theme.fg),
+                        ),
+                    ])
+                }));
+            }
+            content.push(Line::from(""));
+            content.push(Line::from(Span::styled(
+                "Press Enter to attach, 'V' to watch read-only, 'd' to delete, 'w' to chain a rule",
+                Style::default().fg(self.theme.dim),
+            )));
+            let now = text::unix_now();
+            let mut scheduled = self.state.schedules_for(&session.name).peekable();
+            if scheduled.peek().is_some() {
+                content.push(Line::from(""));
+                content.push(Line::from(Span::styled(
+                    "Scheduled",
+                    Style::default()
+                        .fg(self.theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                )));
+                content.extend(scheduled.map(|s| {
+                    Line::from(vec![
+                        Span::styled(
+                            format!("  {}  ", schedule::format_at(s.at, now)),
+                            Style::default().fg(self.theme.dim),
+                        ),
+                        Span::styled(s.prompt.as_str(), Style::default().fg(self.theme.fg)),
+                    ])
+                }));
+            }
+            let mut watching = self.rules.watching(&session.name).peekable();
+            if watching.peek().is_some() {
+                content.push(Line::from(""));
+                content.push(Line::from(Span::styled(
+                    "Rules",
+                    Style::default()
+                        .fg(self.theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                )));
+                let flags = &self.state.rule_flags;
+                content.extend(watching.map(|rule| {
+                    let on = flags.is_enabled(rule) && !flags.is_spent(rule);
+                    Line::from(Span::styled(
+                        format!("  {}", rule.describe()),
+                        Style::default().fg(if on { self.theme.fg } else { self.theme.dim }),
+                    ))
+                }));
+            }
+            if let Some(breadcrumb) = self.jump_breadcrumb() {
+                content.push(Line::from(""));
+                content.push(breadcrumb);
+            }
+            content
+        } else {
+            vec![
+                Line::from(Span::styled(
+                    "No session selected",
+                    Style::default().fg(self.theme.dim),
+                )),
+                Line::from(""),
+                Line::from(Span::styled(
+                    "Press 'n' to create a new session",
+                    Style::default().fg(self.theme.dim),
+                )),
+            ]
+        };
+
+        let detail = Paragraph::new(content).block(
+            Block::default()
+                .title(" Details ")
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(self.theme.dim)),
+        );
+        frame.render_widget(detail, area);
+    }
+
+    /// The last couple of jump list entries, e.g. `Jumps: alpha › beta`
+    fn jump_breadcrumb(&self) -> Option<Line<'_>> {
+        let names: Vec<&str> = self
+            .jump_list
+            .recent(2)
+            .filter_map(|id| self.sessions.iter().find(|s| s.id == id))
+            .map(|s| s.name.as_str())
+            .collect();
+        if names.is_empty() {
+            return None;
+        }
+        Some(Line::from(vec![
+            Span::styled("Jumps: ", Style::default().fg(self.theme.dim)),
+            Span::styled(names.join(" › "), Style::default().fg(self.theme.dim)),
+            Span::styled("  (Ctrl-o back)", Style::default().fg(self.theme.dim)),
+        ]))
+    }
+
+    /// Footer line while `M` shows the MCP servers
+    fn mcp_status_text(&self) -> String {
+        let Some(oldest) = self.mcp_servers.first() else {
+            return " MCP: no server running │ Start one with `agent-rusty --mcp` from your agent's MCP config │ M: Close ".to_string();
+        };
+        let pids: Vec<String> = self.mcp_servers.iter().map(|s| s.pid.to_string()).collect();
+        format!(
+            " MCP: serving (pid {}) │ {} tool calls │ up {} │ M: Close ",
+            pids.join(", "),
+            self.mcp_servers.iter().map(|s| s.calls).sum::<u64>(),
+            text::format_duration_short(text::unix_now().saturating_sub(oldest.started_at))
+        )
+    }
+
+    fn render_footer(&self, frame: &mut Frame, area: Rect) {
+        let mcp_text;
+        let help_text = if self.mcp_mode {
+            mcp_text = self.mcp_status_text();
+            mcp_text.as_str()
+        } else {
+            " q: Quit │ j/k: Navigate │ Enter: Attach │ n: New │ d: Delete │ /: Filter │ ?: Help "
+        };
+
+        let content = if self.input_mode == InputMode::Filtering {
+            Line::from(vec![
+                Span::styled(
+                    format!(" /{}_ ", self.session_filter),
+                    Style::default().fg(self.theme.accent),
+                ),
+                Span::styled(
+                    "│ Enter: Keep │ Esc: Clear │ ↑/↓: Navigate ",
+                    Style::default().fg(self.theme.dim),
+                ),
+            ])
+        } else if let Some(notification) = self.notifications.latest() {
+            let style = Style::default().fg(self.level_color(notification.level));
+            let hint = match (self.current_error_details().is_some(), notification.sticky) {
+                (true, true) => "(press e for details, Esc to dismiss) ",
+                (true, false) => "(press e for details) ",
+                (false, true) => "(press Esc to dismiss) ",
+                (false, false) => "",
+            };
+            Line::from(vec![
+                Span::styled(format!(" {} ", notification.text), style),
+                Span::styled(hint, Style::default().fg(self.theme.dim)),
+            ])
+        } else if let Some(description) = self.operations.values().next() {
+            let step = SystemTime::now()
+                .duration_since(UNIX_EPOCH)
+                .map_or(0, |d| d.as_millis() / SPINNER_STEP.as_millis());
+            let frame_index = step as usize % SPINNER.len();
+            let more = match self.operations.len() {
+                1 => String::new(),
+                n => format!(" (+{} more)", n - 1),
+            };
+            Line::from(Span::styled(
+                format!(" {} {}…{} ", SPINNER[frame_index], description, more),
+                Style::default().fg(self.theme.accent),
+            ))
+        } else if !self.session_filter.is_empty() {
+            Line::from(vec![
+                Span::styled(
+                    format!(" Filter: /{} ", self.session_filter),
+                    Style::default().fg(self.theme.accent),
+                ),
+                Span::styled(
+                    "│ /: Edit │ Esc: Clear │",
+                    Style::default().fg(self.theme.dim),
+                ),
+                Span::styled(help_text, Style::default().fg(self.theme.dim)),
+            ])
+        } else {
+            Line::from(Span::styled(help_text, Style::default().fg(self.theme.dim)))
+        };
+
+        let footer = Paragraph::new(content).block(
+            Block::default()
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(self.theme.dim)),
+        );
+        frame.render_widget(footer, area);
+    }
+
+    fn render_preview(&mut self, frame: &mut Frame, area: Rect) {
+        let Some(preview) = self.preview.as_mut() else {
+            return;
+        };
+
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(area);
+
+        let follow_label = if preview.viewport.is_following() {
+            "following"
+        } else {
+            "paused"
+        };
+        let match_label = match (
+            preview.search.matches().len(),
+            preview.search.current_index(),
+        ) {
+            (0, _) if !preview.search.query.is_empty() && !preview.search.editing => {
+                " [no matches]".to_string()
+            }
+            (0, _) => String::new(),
+            (total, Some(i)) => format!(" [{}/{}]", i + 1, total),
+            (total, None) => format!(" [{} matches]", total),
+        };
+        let block = Block::default()
+            .title(format!(" {}{} ", preview.session_name, match_label))
+            .title(
+                Line::from(format!(
+                    " {}/{} │ {} ",
+                    (preview.viewport.offset() + 1).min(preview.viewport.lines().len()),
+                    preview.viewport.lines().len(),
+                    follow_label
+                ))
+                .right_aligned(),
+            )
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(chunks[0]);
+        preview.viewport.set_height(inner.height as usize);
+
+        let text_style = Style::default().fg(self.theme.fg);
+        let match_style = Style::default().fg(self.theme.bg).bg(self.theme.warning);
+        let current_style = Style::default()
+            .fg(self.theme.bg)
+            .bg(self.theme.accent)
+            .add_modifier(Modifier::BOLD);
+        let current = preview.search.current();
+
+        // Only the visible slice of the buffer is turned into widgets
+        let offset = preview.viewport.offset();
+        let lines: Vec<Line> = preview
+            .viewport
+            .visible_lines()
+            .iter()
+            .enumerate()
+            .map(|(i, line)| {
+                let matches = preview.search.matches_on_line(offset + i);
+                let mut spans = Vec::new();
+                let mut last = 0;
+                for m in matches {
+                    if m.start > last {
+                        spans.push(Span::styled(&line[last..m.start], text_style));
+                    }
+                    let style = if current == Some(*m) {
+                        current_style
+                    } else {
+                        match_style
+                    };
+                    spans.push(Span::styled(&line[m.start..m.end], style));
+                    last = m.end;
+                }
+                if last < line.len() {
+                    spans.push(Span::styled(&line[last..], text_style));
+                }
+                Line::from(spans)
+            })
+            .collect();
+
+        frame.render_widget(Paragraph::new(lines).block(block), chunks[0]);
+
+        let case_label = if preview.search.smart_case {
+            "smart-case"
+        } else {
+            "ignore-case"
+        };
+        let status = if preview.search.editing {
+            Line::from(vec![
+                Span::styled(
+                    format!(" /{}_", preview.search.query),
+                    Style::default().fg(self.theme.accent),
+                ),
+                Span::styled(
+                    format!("  ({})", case_label),
+                    Style::default().fg(self.theme.dim),
+                ),
+            ])
+        } else {
+            Line::from(Span::styled(
+                format!(
+                    " q/Esc: Back │ j/k: Scroll │ PgUp/PgDn: Page │ g/G: Top/Bottom │ /: Search │ n/N: Next/Prev │ s: {} ",
+                    case_label
+                ),
+                Style::default().fg(self.theme.dim),
+            ))
+        };
+        frame.render_widget(Paragraph::new(status), chunks[1]);
+    }
+
+    fn render_dashboard(&mut self, frame: &mut Frame, area: Rect) {
+        let chunks = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([Constraint::Min(0), Constraint::Length(1)])
+            .split(area);
+        let side = (area.width / 3).clamp(24, 40);
+        let columns = Layout::default()
+            .direction(Direction::Horizontal)
+            .constraints([Constraint::Min(0), Constraint::Length(side)])
+            .split(chunks[0]);
+
+        let positions: Vec<usize> = self
+            .rows
+            .iter()
+            .enumerate()
+            .filter(|(_, row)| matches!(row, ListRow::Session(_)))
+            .map(|(i, _)| i)
+            .collect();
+        let position = self
+            .list_state
+            .selected()
+            .and_then(|selected| positions.iter().position(|&i| i == selected))
+            .map_or(0, |p| p + 1);
+        let session = self.selected_session().cloned();
+
+        if let Some(preview) = self.preview.as_mut() {
+            let block = Block::default()
+                .title(format!(" {} ", preview.session_name))
+                .title(Line::from(format!(" {}/{} ", position, positions.len())).right_aligned())
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(self.theme.accent));
+            let inner = block.inner(columns[0]);
+            preview.viewport.set_height(inner.height as usize);
+            let lines: Vec<Line> = preview
+                .viewport
+                .visible_lines()
+                .iter()
+                .map(|line| {
+                    Line::from(Span::styled(
+                        line.as_str(),
+                        Style::default().fg(self.theme.fg),
+                    ))
+                })
+                .collect();
+            frame.render_widget(Paragraph::new(lines).block(block), columns[0]);
+        }
+
+        let windows_height = (self.dashboard_windows.len().max(1) as u16 + 2).min(8);
+        let panels = Layout::default()
+            .direction(Direction::Vertical)
+            .constraints([
+                Constraint::Length(4),
+                Constraint::Length(windows_height),
+                Constraint::Min(0),
+            ])
+            .split(columns[1]);
+        let panel = |title: &'static str| {
+            Block::default()
+                .title(title)
+                .borders(Borders::ALL)
+                .border_style(Style::default().fg(self.theme.dim))
+        };
+        let dim = Style::default().fg(self.theme.dim);
+        let plain = Style::default().fg(self.theme.fg);
+
+        let (day_start, week_start) = usage::period_starts(&chrono::Local::now());
+        let rollup = self.state.usage.rollup(day_start, week_start);
+        let spent = session
+            .as_ref()
+            .and_then(|s| rollup.sessions.iter().find(|r| r.label == s.name));
+        let usage_lines = match spent {
+            Some(row) => [("Today", row.today), ("Week", row.week)]
+                .into_iter()
+                .map(|(label, totals)| {
+                    Line::from(vec![
+                        Span::styled(format!("{:<7}", label), dim),
+                        Span::styled(
+                            format!(
+                                "{} {}",
+                                usage::format_cost(totals.cost),
+                                usage::format_tokens(totals.tokens)
+                            ),
+                            plain,
+                        ),
+                    ])
+                })
+                .collect(),
+            None => vec![Line::from(Span::styled("No usage this week", dim))],
+        };
+        frame.render_widget(
+            Paragraph::new(usage_lines).block(panel(" Usage ")),
+            panels[0],
+        );
+
+        let window_lines: Vec<Line> = if self.dashboard_windows.is_empty() {
+            vec![Line::from(Span::styled("Loading...", dim))]
+        } else {
+            self.dashboard_windows
+                .iter()
+                .map(|w| {
+                    let style = if w.active {
+                        plain.add_modifier(Modifier::BOLD)
+                    } else {
+                        plain
+                    };
+                    Line::from(vec![
+                        Span::styled(if w.active { "▶ " } else { "  " }, style),
+                        Span::styled(format!("{}: {}", w.index, w.name), style),
+                        Span::styled(
+                            if w.panes > 1 {
+                                format!(" ({} panes)", w.panes)
+                            } else {
+                                String::new()
+                            },
+                            dim,
+                        ),
+                    ])
+                })
+                .collect()
+        };
+        frame.render_widget(
+            Paragraph::new(window_lines).block(panel(" Windows ")),
+            panels[1],
+        );
+
+        let mut timeline = Vec::new();
+        if let Some(session) = &session {
+            timeline.push(Line::from(vec![
+                Span::styled("Now: ", dim),
+                self.theme.status_icon(session.status),
+                Span::styled(
+                    format!("{:?}", session.status),
+                    Style::default().fg(self.theme.status_color(session.status)),
+                ),
+            ]));
+            let now = text::unix_now();
+            let mut changes = self.status_timeline.changes(&session.id).peekable();
+            if changes.peek().is_none() {
+                timeline.push(Line::from(Span::styled("No changes seen yet", dim)));
+            }
+            for change in changes {
+                timeline.push(Line::from(vec![
+                    Span::styled(
+                        format!(
+                            "{:>3} ago ",
+                            text::format_duration_short(now.saturating_sub(change.at))
+                        ),
+                        dim,
+                    ),
+                    self.theme.status_icon(change.status),
+                    Span::styled(
+                        format!("{:?}", change.status),
+                        Style::default().fg(self.theme.status_color(change.status)),
+                    ),
+                ]));
+            }
+        }
+        frame.render_widget(Paragraph::new(timeline).block(panel(" Status ")), panels[2]);
+
+        let status = if self.input_mode == InputMode::DashboardPrompt {
+            Line::from(Span::styled(
+                format!(" Prompt ▶ {}_", self.input_buffer),
+                Style::default().fg(self.theme.accent),
+            ))
+        } else if let Some(notification) = self.notifications.latest() {
+            Line::from(Span::styled(
+                format!(" {} ", notification.text),
+                Style::default().fg(self.level_color(notification.level)),
+            ))
+        } else {
+            Line::from(Span::styled(
+                " Esc: Back │ ←/→: Session │ i: Interrupt │ p: Prompt │ R: Restart │ Enter: Attach │ 0-9: Window │ j/k: Scroll ",
+                dim,
+            ))
+        };
+        frame.render_widget(Paragraph::new(status), chunks[1]);
+    }
+
+    /// Dialog for typing a session name, creating one or renaming one
+    /// Shows the terminal's cursor at the input's cursor
+    fn render_input_dialog(&self, frame: &mut Frame, title: &str, prompt: &str, hint: &str) {
+        let area = centered_rect(50, 20, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(title)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled(prompt, Style::default().fg(self.theme.fg))),
+            Line::from(""),
+            self.input_line(),
+            Line::from(""),
+            Line::from(Span::styled(hint, Style::default().fg(self.theme.dim))),
+        ];
+
+        let paragraph = Paragraph::new(text);
+        frame.render_widget(paragraph, inner);
+        self.show_input_cursor(frame, inner, 3);
+    }
+
+    /// `input_buffer` as a dialog shows it
+    fn input_line(&self) -> Line<'_> {
+        Line::from(Span::styled(
+            format!("▶ {}", self.input_buffer),
+            Style::default()
+                .fg(self.theme.accent)
+                .add_modifier(Modifier::BOLD),
+        ))
+    }
+
+    /// Put the terminal's cursor at the input's, on line `row` of `inner`
+    fn show_input_cursor(&self, frame: &mut Frame, inner: Rect, row: u16) {
+        let column = inner.x as usize + "▶ ".width() + self.input_buffer.cursor_column();
+        frame.set_cursor_position(Position::new(
+            (column as u16).min(inner.right().saturating_sub(1)),
+            inner.y + row,
+        ));
+    }
+
+    fn render_worktree_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(60, 25, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" New Worktree ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let repo = self
+            .worktree_target
+            .as_ref()
+            .map(|(repo, _)| repo.display().to_string())
+            .unwrap_or_default();
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("Branch from {}:", repo),
+                Style::default().fg(self.theme.fg),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("▶ {}_", self.input_buffer),
+                Style::default()
+                    .fg(self.theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Press Enter to choose what to run, Esc to cancel",
+                Style::default().fg(self.theme.dim),
+            )),
+        ];
+
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), inner);
+    }
+
+    fn render_launcher_menu(&self, frame: &mut Frame) {
+        let area = centered_rect(50, 40, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!(
+                " Run in '{}' ",
+                self.create_name.as_deref().unwrap_or_default()
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut text = vec![Line::from("")];
+        text.extend(self.launchers.iter().enumerate().map(|(index, launcher)| {
+            if index == self.launcher_selected {
+                Line::from(Span::styled(
+                    format!("▶ {}", launcher.label()),
+                    Style::default()
+                        .fg(self.theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                ))
+            } else {
+                Line::from(Span::styled(
+                    format!("  {}", launcher.label()),
+                    Style::default().fg(self.theme.fg),
+                ))
+            }
+        }));
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "j/k: Move │ Enter: Choose │ Esc: Cancel",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_command_menu(&self, frame: &mut Frame) {
+        let area = centered_rect(50, 40, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!(
+                " Run on '{}' ",
+                self.selected_session()
+                    .map(|s| s.name.as_str())
+                    .unwrap_or_default()
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut text = vec![Line::from("")];
+        text.extend(
+            self.custom_commands
+                .iter()
+                .enumerate()
+                .map(|(index, command)| {
+                    let (marker, style) = if index == self.command_selected {
+                        (
+                            "▶ ",
+                            Style::default()
+                                .fg(self.theme.accent)
+                                .add_modifier(Modifier::BOLD),
+                        )
+                    } else {
+                        ("  ", Style::default().fg(self.theme.fg))
+                    };
+                    let key = command
+                        .key
+                        .map(|key| format!(" [{}]", key))
+                        .unwrap_or_default();
+                    Line::from(vec![
+                        Span::styled(format!("{}{}", marker, command.name), style),
+                        Span::styled(
+                            format!("  {}{}", command.mode.label(), key),
+                            Style::default().fg(self.theme.dim),
+                        ),
+                    ])
+                }),
+        );
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "j/k: Move │ Enter: Run │ Esc: Cancel",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_tag_menu(&self, frame: &mut Frame) {
+        let area = centered_rect(50, 40, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Filter by Tag ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let choices = std::iter::once(("All sessions".to_string(), self.sessions.len())).chain(
+            self.listed_tags()
+                .into_iter()
+                .map(|(tag, count)| (format!("#{}", tag), count)),
+        );
+        let mut text = vec![Line::from("")];
+        text.extend(choices.enumerate().map(|(index, (label, count))| {
+            let (marker, style) = if index == self.tag_selected {
+                (
+                    "▶ ",
+                    Style::default()
+                        .fg(self.theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                ("  ", Style::default().fg(self.theme.fg))
+            };
+            Line::from(vec![
+                Span::styled(format!("{}{}", marker, label), style),
+                Span::styled(format!("  {}", count), Style::default().fg(self.theme.dim)),
+            ])
+        }));
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "j/k: Move │ Enter: Filter │ Esc: Cancel",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_template_menu(&self, frame: &mut Frame) {
+        let area = centered_rect(50, 40, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Create from Template ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut text = vec![Line::from("")];
+        text.extend(self.templates.iter().enumerate().map(|(index, template)| {
+            let (marker, style) = if index == self.template_selected {
+                (
+                    "▶ ",
+                    Style::default()
+                        .fg(self.theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                ("  ", Style::default().fg(self.theme.fg))
+            };
+            let sessions: Vec<&str> = template.sessions.iter().map(|s| s.name.as_str()).collect();
+            Line::from(vec![
+                Span::styled(format!("{}{}", marker, template.name), style),
+                Span::styled(
+                    format!("  {}", sessions.join(", ")),
+                    Style::default().fg(self.theme.dim),
+                ),
+            ])
+        }));
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "j/k: Move │ Enter: Choose │ Esc: Cancel",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_window_picker(&self, frame: &mut Frame) {
+        let Some((id, windows)) = &self.window_choice else {
+            return;
+        };
+        let area = centered_rect(50, 40, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let name = self
+            .sessions
+            .iter()
+            .find(|s| &s.id == id)
+            .map_or(id.as_str(), |s| s.name.as_str());
+        let block = Block::default()
+            .title(format!(" Attach to '{}' ", name))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut text = vec![Line::from("")];
+        text.extend(windows.iter().enumerate().map(|(row, window)| {
+            // `*` marks the current window, as in tmux's status line
+            let label = format!(
+                "{}: {}{}",
+                window.index,
+                window.name,
+                if window.active { "*" } else { "" }
+            );
+            let panes = if window.panes > 1 {
+                format!("  {} panes", window.panes)
+            } else {
+                String::new()
+            };
+            let (marker, style) = if row == self.window_selected {
+                (
+                    "▶ ",
+                    Style::default()
+                        .fg(self.theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                ("  ", Style::default().fg(self.theme.fg))
+            };
+            Line::from(vec![
+                Span::styled(format!("{}{}", marker, label), style),
+                Span::styled(panes, Style::default().fg(self.theme.dim)),
+            ])
+        }));
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "j/k: Move │ Enter: Attach │ Esc: Current window",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_client_picker(&self, frame: &mut Frame) {
+        let Some((id, clients)) = &self.clients else {
+            return;
+        };
+        let area = centered_rect(50, 40, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let name = self
+            .sessions
+            .iter()
+            .find(|s| &s.id == id)
+            .map_or(id.as_str(), |s| s.name.as_str());
+        let block = Block::default()
+            .title(format!(" Detach from '{}' ", name))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut text = vec![Line::from("")];
+        text.extend(clients.iter().enumerate().map(|(row, client)| {
+            let (marker, style) = if row == self.client_selected {
+                (
+                    "▶ ",
+                    Style::default()
+                        .fg(self.theme.accent)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                ("  ", Style::default().fg(self.theme.fg))
+            };
+            Line::from(vec![
+                Span::styled(format!("{}{}", marker, client.name), style),
+                Span::styled(
+                    format!("  {}x{}  {}", client.width, client.height, client.termname),
+                    Style::default().fg(self.theme.dim),
+                ),
+            ])
+        }));
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "j/k: Move │ Enter: Detach │ Esc: Cancel",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_create_dir_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(60, 20, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let launcher = self
+            .launchers
+            .get(self.launcher_selected)
+            .map(|l| l.name.as_str())
+            .unwrap_or_default();
+        let block = Block::default()
+            .title(format!(
+                " {} in '{}' ",
+                launcher,
+                self.create_name.as_deref().unwrap_or_default()
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                "Start in directory:",
+                Style::default().fg(self.theme.fg),
+            )),
+            Line::from(""),
+            self.input_line(),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Press Enter to create, Tab to browse, Esc to go back",
+                Style::default().fg(self.theme.dim),
+            )),
+        ];
+
+        frame.render_widget(Paragraph::new(text), inner);
+        self.show_input_cursor(frame, inner, 3);
+    }
+
+    fn render_dir_picker(&self, frame: &mut Frame) {
+        let Some((purpose, picker)) = &self.dir_picker else {
+            return;
+        };
+        let area = centered_rect(60, 60, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let title = match purpose {
+            DirPurpose::SessionDir => format!(
+                " Start '{}' in ",
+                self.create_name.as_deref().unwrap_or_default()
+            ),
+            DirPurpose::SkeletonRoot => " Skeleton root ".to_string(),
+        };
+        let hints = if picker.filtering {
+            " Enter: Done │ Esc: Clear filter "
+        } else if *purpose == DirPurpose::SessionDir {
+            " j/k: Move │ l: Open │ h: Up │ .: Hidden │ /: Filter │ Tab: Type path │ Esc: Back "
+        } else {
+            " j/k: Move │ l: Open │ h: Up │ .: Hidden │ /: Filter │ Esc: Back "
+        };
+        let block = Block::default()
+            .title(title)
+            .title_bottom(hints)
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut text = vec![Line::from(Span::styled(
+            text::truncate_middle(&picker.dir().display().to_string(), inner.width as usize)
+                .into_owned(),
+            Style::default()
+                .fg(self.theme.fg)
+                .add_modifier(Modifier::BOLD),
+        ))];
+        if picker.filtering || !picker.filter.is_empty() {
+            text.push(Line::from(Span::styled(
+                format!(
+                    "/{}{}",
+                    picker.filter,
+                    if picker.filtering { "_" } else { "" }
+                ),
+                Style::default().fg(self.theme.accent),
+            )));
+        }
+        if let Some(error) = picker.error() {
+            text.push(Line::from(Span::styled(
+                error.to_string(),
+                Style::default().fg(self.theme.error),
+            )));
+        } else if picker.is_loading() {
+            text.push(Line::from(Span::styled(
+                "Loading…",
+                Style::default().fg(self.theme.dim),
+            )));
+        }
+
+        let row = |selected: bool, label: String, color| {
+            let (marker, style) = if selected {
+                (
+                    "▶ ",
+                    Style::default().fg(color).add_modifier(Modifier::BOLD),
+                )
+            } else {
+                ("  ", Style::default().fg(color))
+            };
+            Line::from(Span::styled(format!("{}{}", marker, label), style))
+        };
+        let shown = picker.shown();
+        let mut rows = vec![row(
+            picker.selected == 0,
+            "[use this directory]".to_string(),
+            self.theme.accent,
+        )];
+        rows.extend(
+            shown
+                .iter()
+                .enumerate()
+                .map(|(i, entry)| match &entry.error {
+                    Some(error) => row(
+                        picker.selected == i + 1,
+                        format!("{}/ ({})", entry.name, error),
+                        self.theme.error,
+                    ),
+                    None => row(
+                        picker.selected == i + 1,
+                        format!("{}/", entry.name),
+                        self.theme.fg,
+                    ),
+                }),
+        );
+        // Keep the highlight in view
+        let height = (inner.height as usize).saturating_sub(text.len()).max(1);
+        let skip = (picker.selected + 1).saturating_sub(height);
+        text.extend(rows.into_iter().skip(skip));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_note_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(60, 40, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let kind = match self.note_kind {
+            NoteKind::Note => "Note",
+            NoteKind::Description => "Description",
+        };
+        let block = Block::default()
+            .title(format!(
+                " {} for '{}' ",
+                kind,
+                self.note_target.as_deref().unwrap_or("")
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut text: Vec<Line> = vec![Line::from("")];
+        let line_count = self.input_buffer.as_str().split('\n').count();
+        for (i, line) in self.input_buffer.as_str().split('\n').enumerate() {
+            let cursor = if i + 1 == line_count { "_" } else { "" };
+            text.push(Line::from(Span::styled(
+                format!("  {}{}", line, cursor),
+                Style::default().fg(self.theme.fg),
+            )));
+        }
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            match self.note_kind {
+                NoteKind::Note => format!(
+                    "Ctrl-S/Esc: Save │ Enter: New line (max {}) │ Ctrl-D: Delete",
+                    NOTE_MAX_LINES
+                ),
+                NoteKind::Description => {
+                    "Enter: Save │ Ctrl-D: Derive again │ Esc: Cancel".to_string()
+                }
+            },
+            Style::default().fg(self.theme.dim),
+        )));
+
+        let paragraph = Paragraph::new(text);
+        frame.render_widget(paragraph, inner);
+    }
+
+    fn render_broadcast_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(60, 20, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!(" Broadcast to {} marked ", self.marked.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                "Prompt to send:",
+                Style::default().fg(self.theme.fg),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("▶ {}_", self.input_buffer),
+                Style::default()
+                    .fg(self.theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Press Enter to review recipients, Esc to cancel",
+                Style::default().fg(self.theme.dim),
+            )),
+        ];
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_schedule_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(60, 20, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let (name, at) = match &self.schedule_target {
+            Some((name, at)) => (name.as_str(), *at),
+            None => ("", None),
+        };
+        let block = Block::default()
+            .title(format!(" Schedule a prompt for {} ", name))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let (label, hint) = match at {
+            None => (
+                "Send at (+30m, 14:00 or 2026-10-17 14:00):".to_string(),
+                "Press Enter to continue, Esc to cancel",
+            ),
+            Some(at) => (
+                format!(
+                    "Prompt to send at {}:",
+                    schedule::format_at(at, text::unix_now())
+                ),
+                "Press Enter to schedule, Esc to cancel",
+            ),
+        };
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled(label, Style::default().fg(self.theme.fg))),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("▶ {}_", self.input_buffer),
+                Style::default()
+                    .fg(self.theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(hint, Style::default().fg(self.theme.dim))),
+        ];
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_rule_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(70, 25, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!(
+                " When {} ... ",
+                self.rule_target.as_deref().unwrap_or_default()
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                "[once] <status or from->to> then send <session> <prompt>, run <command> or notify <text>",
+                Style::default().fg(self.theme.fg),
+            )),
+            Line::from(Span::styled(
+                "e.g. busy->idle send tests run the test suite",
+                Style::default().fg(self.theme.dim),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("▶ {}_", self.input_buffer),
+                Style::default()
+                    .fg(self.theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Press Enter to add, Esc to cancel",
+                Style::default().fg(self.theme.dim),
+            )),
+        ];
+
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), inner);
+    }
+
+    fn render_rules(&self, frame: &mut Frame) {
+        let area = centered_rect(80, 70, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!(" Rules ({}) ", self.rules.rules.len()))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let flags = &self.state.rule_flags;
+        let mut text: Vec<Line> = self
+            .rules
+            .rules
+            .iter()
+            .enumerate()
+            .map(|(index, rule)| {
+                let state = if !flags.is_enabled(rule) {
+                    "off"
+                } else if flags.is_spent(rule) {
+                    "spent"
+                } else {
+                    "on"
+                };
+                let style = if index == self.rules_selected {
+                    Style::default()
+                        .fg(self.theme.accent)
+                        .add_modifier(Modifier::BOLD)
+                } else if state == "on" {
+                    Style::default().fg(self.theme.fg)
+                } else {
+                    Style::default().fg(self.theme.dim)
+                };
+                Line::from(Span::styled(
+                    format!(
+                        "  [{:<5}] {}{}: {}",
+                        state,
+                        rule.key,
+                        if rule.local { "" } else { " (config)" },
+                        rule.describe()
+                    ),
+                    style,
+                ))
+            })
+            .collect();
+        if text.is_empty() {
+            text.push(Line::from(Span::styled(
+                "  No rules; press w on a session or add [[rules]] to the config",
+                Style::default().fg(self.theme.dim),
+            )));
+        }
+
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "Fired",
+            Style::default()
+                .fg(self.theme.accent)
+                .add_modifier(Modifier::BOLD),
+        )));
+        if self.rule_history.is_empty() {
+            text.push(Line::from(Span::styled(
+                "  Nothing yet",
+                Style::default().fg(self.theme.dim),
+            )));
+        }
+        let now = text::unix_now();
+        text.extend(self.rule_history.iter().rev().map(|run| {
+            let (outcome, color) = match &run.outcome {
+                None => ("running".to_string(), self.theme.dim),
+                Some(Ok(())) => ("done".to_string(), self.theme.success),
+                Some(Err(e)) => (format!("failed: {}", e), self.theme.error),
+            };
+            Line::from(vec![
+                Span::styled(
+                    format!(
+                        "  {} ago  {} by {}: {} ",
+                        text::format_duration_short(now.saturating_sub(run.at)),
+                        run.rule,
+                        run.session,
+                        run.action
+                    ),
+                    Style::default().fg(self.theme.fg),
+                ),
+                Span::styled(outcome, Style::default().fg(color)),
+            ])
+        }));
+
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "j/k: Move │ Space: Enable/disable │ d: Delete │ Esc: Close",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), inner);
+    }
+
+    fn render_logs(&self, frame: &mut Frame) {
+        let area = centered_rect(80, 80, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let filter = match self.log_filter {
+            tracing::Level::TRACE => "all".to_string(),
+            tracing::Level::ERROR => "error".to_string(),
+            level => format!("{}+", level.as_str().to_lowercase()),
+        };
+        let block = Block::default()
+            .title(format!(" Log ({}) ", filter))
+            .title(
+                Line::from(if self.log_top.is_none() {
+                    " following "
+                } else {
+                    " paused "
+                })
+                .right_aligned(),
+            )
+            .title_bottom(" j/k: Scroll │ f: Follow │ v: Level │ y: Copy │ Esc: Close ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let lines = self.visible_logs();
+        if lines.is_empty() {
+            frame.render_widget(
+                Paragraph::new(Span::styled(
+                    "Nothing logged at this level yet",
+                    Style::default().fg(self.theme.dim),
+                )),
+                inner,
+            );
+            return;
+        }
+        let text: Vec<Line> = lines
+            .iter()
+            .map(|line| {
+                let color = match line.level {
+                    tracing::Level::ERROR => self.theme.error,
+                    tracing::Level::WARN => self.theme.warning,
+                    tracing::Level::INFO => self.theme.fg,
+                    _ => self.theme.dim,
+                };
+                Line::from(vec![
+                    Span::styled(format!("{} ", line.at), Style::default().fg(self.theme.dim)),
                     Span::styled(
-                        format!("{:?}", session.status),
-                        Style::default().fg(match session.status {
-                            AgentStatus::Busy => self.theme.warning,
-                            AgentStatus::Idle => self.theme.success,
-                            AgentStatus::WaitingForInput => self.theme.accent,
-                            AgentStatus::Error => self.theme.error,
-                            AgentStatus::Unknown => self.theme.dim,
-                        }),
+                        format!("{:>5} ", line.level),
+                        Style::default().fg(color).add_modifier(Modifier::BOLD),
                     ),
-                ]),
-                Line::from(vec![
-                    Span::styled("Clients: ", Style::default().fg(self.theme.dim)),
                     Span::styled(
-                        session.attached_clients.to_string(),
-                        Style::default().fg(self.theme.fg),
+                        format!("{}: ", line.target),
+                        Style::default().fg(self.theme.dim),
+                    ),
+                    Span::styled(line.message.clone(), Style::default().fg(color)),
+                ])
+            })
+            .collect();
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_usage(&self, frame: &mut Frame) {
+        let area = centered_rect(70, 70, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Usage ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let (day_start, week_start) = usage::period_starts(&chrono::Local::now());
+        let rollup = self.state.usage.rollup(day_start, week_start);
+        let heading = |title: &str| {
+            Line::from(Span::styled(
+                format!("{:<24}{:>18}{:>18}", title, "Today", "This week"),
+                Style::default()
+                    .fg(self.theme.accent)
+                    .add_modifier(Modifier::BOLD),
+            ))
+        };
+        let figures = |totals: usage::Totals| {
+            format!(
+                "{} {}",
+                usage::format_cost(totals.cost),
+                usage::format_tokens(totals.tokens)
+            )
+        };
+        let row = |row: &RollupRow, style: Style| {
+            Line::from(Span::styled(
+                format!(
+                    "  {:<22}{:>18}{:>18}",
+                    text::truncate_middle(&row.label, 22),
+                    figures(row.today),
+                    figures(row.week)
+                ),
+                style,
+            ))
+        };
+        let plain = Style::default().fg(self.theme.fg);
+
+        let mut text = vec![
+            heading("Total"),
+            row(
+                &RollupRow {
+                    label: "All sessions".to_string(),
+                    today: rollup.today,
+                    week: rollup.week,
+                },
+                plain.add_modifier(Modifier::BOLD),
+            ),
+            Line::from(""),
+            heading("By session"),
+        ];
+        if rollup.sessions.is_empty() {
+            text.push(Line::from(Span::styled(
+                "  Nothing recorded this week; agents' totals are read from their panes (/cost in Claude Code)",
+                Style::default().fg(self.theme.dim),
+            )));
+        }
+        let biggest = rollup.biggest_spender();
+        text.extend(rollup.sessions.iter().map(|r| {
+            if Some(r.label.as_str()) == biggest {
+                row(
+                    r,
+                    Style::default()
+                        .fg(self.theme.warning)
+                        .add_modifier(Modifier::BOLD),
+                )
+            } else {
+                row(r, plain)
+            }
+        }));
+        text.push(Line::from(""));
+        text.push(heading("By agent"));
+        text.extend(rollup.kinds.iter().map(|r| row(r, plain)));
+
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "Costs are the agents' own estimates │ Esc: Close",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_schedules(&self, frame: &mut Frame) {
+        let area = centered_rect(70, 50, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(format!(
+                " Scheduled prompts ({}) ",
+                self.state.schedules.len()
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let now = text::unix_now();
+        let mut text: Vec<Line> = self
+            .state
+            .schedules
+            .iter()
+            .enumerate()
+            .map(|(index, s)| {
+                let style = if index == self.schedule_selected {
+                    Style::default()
+                        .fg(self.theme.accent)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(self.theme.fg)
+                };
+                Line::from(Span::styled(
+                    format!(
+                        "  #{} {} → {}: {}",
+                        s.id,
+                        schedule::format_at(s.at, now),
+                        s.session,
+                        s.prompt
                     ),
-                ]),
-                Line::from(""),
+                    style,
+                ))
+            })
+            .collect();
+        if text.is_empty() {
+            text.push(Line::from(Span::styled(
+                "  Nothing scheduled; press T on a session to add a prompt",
+                Style::default().fg(self.theme.dim),
+            )));
+        }
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "j/k: Move │ d: Cancel │ Esc: Close",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: false }), inner);
+    }
+
+    fn render_broadcast_confirm_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(60, 50, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Confirm Broadcast ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.warning));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let plan = broadcast::plan(&self.sessions, &self.marked, self.broadcast_force);
+        let mut text = vec![
+            Line::from(Span::styled(
+                format!("Send \"{}\" to:", self.input_buffer),
+                Style::default().fg(self.theme.fg),
+            )),
+            Line::from(""),
+        ];
+        for recipient in &plan.recipients {
+            text.push(Line::from(Span::styled(
+                format!("  • {}", recipient.name),
+                Style::default().fg(self.theme.fg),
+            )));
+        }
+        for (name, reason) in &plan.skipped {
+            text.push(Line::from(Span::styled(
+                format!("  – {} (skipped: {})", name, reason),
+                Style::default().fg(self.theme.dim),
+            )));
+        }
+        text.push(Line::from(""));
+        let force = if self.broadcast_force {
+            "f: Skip unready"
+        } else {
+            "f: Force all"
+        };
+        text.push(Line::from(Span::styled(
+            format!(
+                "y: Send to {} │ {} │ n/Esc: Cancel",
+                plan.recipients.len(),
+                force
+            ),
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_broadcast_results(&self, frame: &mut Frame) {
+        let area = centered_rect(60, 50, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let failed = self
+            .broadcast_results
+            .iter()
+            .filter(|r| r.outcome.is_err())
+            .count();
+        let block = Block::default()
+            .title(format!(
+                " Broadcast: {} sent, {} failed ",
+                self.broadcast_results.len() - failed,
+                failed
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(if failed > 0 {
+                self.theme.error
+            } else {
+                self.theme.success
+            }));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut text: Vec<Line> = self
+            .broadcast_results
+            .iter()
+            .map(|result| match &result.outcome {
+                Ok(()) => Line::from(Span::styled(
+                    format!("  ✓ {}", result.name),
+                    Style::default().fg(self.theme.success),
+                )),
+                Err(e) => Line::from(Span::styled(
+                    format!("  ✗ {}: {}", result.name, e),
+                    Style::default().fg(self.theme.error),
+                )),
+            })
+            .collect();
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "Press any key to close",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_error_details(&self, frame: &mut Frame) {
+        let Some(details) = self.current_error_details() else {
+            return;
+        };
+        let area = centered_rect(70, 50, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Error Details ")
+            .title_bottom(" j/k: Scroll │ y: Copy │ Esc: Close ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.error));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let text: Vec<Line> = details
+            .chain
+            .iter()
+            .enumerate()
+            .map(|(i, cause)| {
+                let (prefix, color) = if i == 0 {
+                    ("", self.theme.error)
+                } else {
+                    ("caused by: ", self.theme.fg)
+                };
                 Line::from(Span::styled(
-                    "Press Enter to attach, 'd' to delete",
+                    format!("{}{}", prefix, cause),
+                    Style::default().fg(color),
+                ))
+            })
+            .collect();
+
+        frame.render_widget(
+            Paragraph::new(text)
+                .wrap(Wrap { trim: false })
+                .scroll((self.error_details_scroll as u16, 0)),
+            inner,
+        );
+    }
+
+    fn render_help(&self, frame: &mut Frame) {
+        let area = centered_rect(60, 80, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Keys ")
+            .title_bottom(" j/k: Scroll │ Esc: Close ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let text: Vec<Line> = help_lines()
+            .into_iter()
+            .map(|(heading, line)| {
+                let style = if heading {
+                    Style::default()
+                        .fg(self.theme.accent)
+                        .add_modifier(Modifier::BOLD)
+                } else {
+                    Style::default().fg(self.theme.fg)
+                };
+                Line::from(Span::styled(line, style))
+            })
+            .collect();
+
+        frame.render_widget(
+            Paragraph::new(text)
+                .block(block)
+                .scroll((self.help_scroll as u16, 0)),
+            area,
+        );
+    }
+
+    fn render_restart_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(50, 20, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Restart Agent ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.warning));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let session_name = self
+            .selected_session()
+            .map(|s| s.name.as_str())
+            .unwrap_or("unknown");
+
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("Interrupt and restart the agent in '{}'?", session_name),
+                Style::default().fg(self.theme.fg),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Any work in progress will be interrupted.",
+                Style::default().fg(self.theme.warning),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Press 'y' to confirm, 'n' or Esc to cancel",
+                Style::default().fg(self.theme.dim),
+            )),
+        ];
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_quit_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(60, 50, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Quit ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.warning));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let working = self.working_sessions();
+        let mut text = vec![Line::from(Span::styled(
+            format!("Agents still mid-task ({}):", working.len()),
+            Style::default().fg(self.theme.fg),
+        ))];
+        for session in working {
+            text.push(Line::from(vec![
+                Span::raw("  "),
+                self.theme.status_icon(session.status),
+                Span::styled(&session.name, Style::default().fg(self.theme.fg)),
+            ]));
+        }
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "They keep running in tmux; only this dashboard closes.",
+            Style::default().fg(self.theme.fg),
+        )));
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "q/y: Quit │ n/Esc: Stay",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_restore_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(50, 30, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Restore Sessions ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.accent));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let count = self.missing_sessions.len();
+        let names: Vec<&str> = self
+            .missing_sessions
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                format!(
+                    "{} {} from the last snapshot {} missing — restore {}?",
+                    count,
+                    if count == 1 { "session" } else { "sessions" },
+                    if count == 1 { "is" } else { "are" },
+                    if count == 1 { "it" } else { "them" },
+                ),
+                Style::default().fg(self.theme.fg),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                names.join(", "),
+                Style::default().fg(self.theme.accent),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Press 'y' to restore, 'n' or Esc to skip",
+                Style::default().fg(self.theme.dim),
+            )),
+        ];
+
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), inner);
+    }
+
+    fn render_digest(&self, frame: &mut Frame) {
+        let area = centered_rect(60, 50, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let count = self.digest.len();
+        let block = Block::default()
+            .title(format!(
+                " {} {} Need Attention ",
+                count,
+                if count == 1 { "Session" } else { "Sessions" }
+            ))
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.warning));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let now = text::unix_now();
+        let mut text = vec![Line::from("")];
+        for entry in &self.digest {
+            let waiting = entry.waiting_secs(now).map_or_else(
+                || "for a while".to_string(),
+                |secs| format!("for {}", text::format_duration_short(secs)),
+            );
+            text.push(Line::from(vec![
+                self.theme.status_icon(entry.status),
+                Span::styled(entry.name.as_str(), Style::default().fg(self.theme.fg)),
+                Span::styled(
+                    format!("  {:?} {}", entry.status, waiting),
+                    Style::default().fg(self.theme.status_color(entry.status)),
+                ),
+            ]));
+            if let Some(question) = &entry.question {
+                text.push(Line::from(Span::styled(
+                    format!("  {}", question),
                     Style::default().fg(self.theme.dim),
-                )),
+                )));
+            }
+        }
+        text.push(Line::from(""));
+        text.push(Line::from(Span::styled(
+            "Press Enter to jump to the first, Esc to dismiss",
+            Style::default().fg(self.theme.dim),
+        )));
+
+        frame.render_widget(Paragraph::new(text), inner);
+    }
+
+    fn render_cleanup_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(50, 30, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Stale Sessions ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.warning));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let count = self.cleanup_candidates.len();
+        let names: Vec<String> = self
+            .cleanup_candidates
+            .iter()
+            .map(|v| format!("{} ({})", v.name, text::format_duration_short(v.idle_secs)))
+            .collect();
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                format!(
+                    "{} {} idle for over {}h — archive the scrollback and close {}?",
+                    count,
+                    if count == 1 {
+                        "session has been"
+                    } else {
+                        "sessions have been"
+                    },
+                    self.policy.after_hours,
+                    if count == 1 { "it" } else { "them" },
+                ),
+                Style::default().fg(self.theme.fg),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                names.join(", "),
+                Style::default().fg(self.theme.warning),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Press 'y' to clean up, 'n' or Esc to keep them",
+                Style::default().fg(self.theme.dim),
+            )),
+        ];
+
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), inner);
+    }
+
+    fn render_confirm_dialog(&self, frame: &mut Frame) {
+        let session_name = self
+            .selected_session()
+            .map(|s| s.name.as_str())
+            .unwrap_or("unknown");
+        let has_note = self.state.note(session_name).is_some();
+        let area = centered_rect(50, if has_note { 30 } else { 20 }, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Confirm Delete ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.error));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let mut text = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("Delete session '{}'?", session_name),
+                Style::default().fg(self.theme.fg),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "This action cannot be undone.",
+                Style::default().fg(self.theme.warning),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Press 'y' to confirm, 'n' or Esc to cancel",
+                Style::default().fg(self.theme.dim),
+            )),
+        ];
+        if has_note {
+            text.push(Line::from(""));
+            text.push(Line::from(Span::styled(
+                "It has a note: 'x' deletes it too, 'y' keeps it",
+                Style::default().fg(self.theme.dim),
+            )));
+        }
+
+        let paragraph = Paragraph::new(text);
+        frame.render_widget(paragraph, inner);
+    }
+
+    fn render_worktree_removal_dialog(&self, frame: &mut Frame) {
+        let area = centered_rect(60, 25, frame.area());
+
+        frame.render_widget(Clear, area);
+
+        let block = Block::default()
+            .title(" Remove Worktree ")
+            .borders(Borders::ALL)
+            .border_style(Style::default().fg(self.theme.warning));
+
+        let inner = block.inner(area);
+        frame.render_widget(block, area);
+
+        let Some((name, worktree)) = &self.worktree_removal else {
+            return;
+        };
+        let text = vec![
+            Line::from(""),
+            Line::from(Span::styled(
+                format!("Also remove the worktree of '{}'?", name),
+                Style::default().fg(self.theme.fg),
+            )),
+            Line::from(Span::styled(
+                worktree.path.display().to_string(),
+                Style::default().fg(self.theme.accent),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                format!(
+                    "Branch '{}' is kept; git refuses if there are uncommitted changes.",
+                    worktree.branch
+                ),
+                Style::default().fg(self.theme.warning),
+            )),
+            Line::from(""),
+            Line::from(Span::styled(
+                "Press 'y' to remove it, 'n' or Esc to keep it",
+                Style::default().fg(self.theme.dim),
+            )),
+        ];
+
+        frame.render_widget(Paragraph::new(text).wrap(Wrap { trim: true }), inner);
+    }
+}
+
+/// Helper function to create a centered rectangle
+/// Lines of pane content the full-screen preview shows in `area`
+fn preview_viewport_height(area: Rect) -> usize {
+    // Bordered content block above a one-line status bar
+    area.height.saturating_sub(3) as usize
+}
+
+fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
+    let popup_layout = Layout::default()
+        .direction(Direction::Vertical)
+        .constraints([
+            Constraint::Percentage((100 - percent_y) / 2),
+            Constraint::Percentage(percent_y),
+            Constraint::Percentage((100 - percent_y) / 2),
+        ])
+        .split(r);
+
+    Layout::default()
+        .direction(Direction::Horizontal)
+        .constraints([
+            Constraint::Percentage((100 - percent_x) / 2),
+            Constraint::Percentage(percent_x),
+            Constraint::Percentage((100 - percent_x) / 2),
+        ])
+        .split(popup_layout[1])[1]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::ClaudeMeta;
+    use crate::poller::PollRate;
+    use crate::theme::StatusIcons;
+    use crate::tmux::AiderInfo;
+    use crossterm::event::KeyEvent;
+    use ratatui::{Terminal, backend::TestBackend};
+    use std::sync::Arc;
+
+    fn session(name: &str, status: AgentStatus) -> TmuxSession {
+        let mut s = TmuxSession::new(format!("${}", name), name.to_string());
+        s.status = status;
+        s
+    }
+
+    fn grouped_app() -> App {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.state.list_display = ListDisplay::Grouped;
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("zeta", AgentStatus::Idle),
+            session("beta", AgentStatus::Busy),
+            session("alpha", AgentStatus::Idle),
+            session("gamma", AgentStatus::WaitingForInput),
+            session("delta", AgentStatus::Unknown),
+        ]))
+        .unwrap();
+        app
+    }
+
+    fn press(app: &mut App, code: KeyCode) {
+        app.handle_action(Action::KeyPress(KeyEvent::from(code)))
+            .unwrap();
+    }
+
+    fn render_list(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|f| app.render_session_list(f, f.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_grouped_rendering_snapshot() {
+        let mut app = grouped_app();
+        assert_eq!(
+            render_list(&mut app, 30, 13),
+            vec![
+                "┌ Sessions ──────────────────┐",
+                "│  ▾ Needs attention (1)     │",
+                "│▶   ? 1 gamma               │",
+                "│  ▾ Working (1)             │",
+                "│    ⠋ 2 beta                │",
+                "│  ▾ Idle (2)                │",
+                "│    ● 3 alpha               │",
+                "│    ● 4 zeta                │",
+                "│  ▾ Other (1)               │",
+                "│    ○ 5 delta               │",
+                "│                            │",
+                "│                            │",
+                "└────────────────────────────┘",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_output_sparklines() {
+        let mut app = grouped_app();
+        let mut trend = vec![0; activity::ACTIVITY_BUCKETS];
+        trend[27..].copy_from_slice(&[1, 2, 5]);
+        let gamma = app.sessions.iter_mut().find(|s| s.name == "gamma").unwrap();
+        gamma.activity_trend = trend;
+        app.sessions
+            .iter_mut()
+            .find(|s| s.name == "beta")
+            .unwrap()
+            .activity_trend = vec![0; activity::ACTIVITY_BUCKETS];
+
+        // Scaled across sessions; a session with nothing recent shows a blank line
+        let rows = render_list(&mut app, 32, 6);
+        assert_eq!(rows[2], "│▶   ? 1 gamma                █│");
+        assert_eq!(rows[4], "│    ⠋ 2 beta                  │");
+        // Too narrow to share with the name
+        assert_eq!(
+            render_list(&mut app, 28, 6)[2],
+            "│▶   ? 1 gamma             │"
+        );
+
+        let lines = render_detail(&app, 60, 20).join("\n");
+        assert!(
+            lines.contains(&format!("Output: {}▂▄█ 8 lines in 5m", " ".repeat(27))),
+            "{}",
+            lines
+        );
+    }
+
+    #[test]
+    fn test_detail_shows_pane_title() {
+        let mut app = grouped_app();
+        let lines = render_detail(&app, 60, 20).join("\n");
+        assert!(!lines.contains("Title: "), "{}", lines);
+
+        let gamma = app.sessions.iter_mut().find(|s| s.name == "gamma").unwrap();
+        gamma.pane_title = "✳ Fix login bug".to_string();
+        let lines = render_detail(&app, 60, 20).join("\n");
+        assert!(lines.contains("Title: ✳ Fix login bug"), "{}", lines);
+    }
+
+    #[test]
+    fn test_sort_by_recently_used() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("zeta", AgentStatus::Idle),
+            session("beta", AgentStatus::Busy),
+            session("alpha", AgentStatus::Idle),
+            session("gamma", AgentStatus::WaitingForInput),
+        ]))
+        .unwrap();
+        let now = text::unix_now();
+        app.state.attach_history.record("zeta", now - 3600);
+        app.state.attach_history.record("beta", now - 60);
+
+        app.select_session_id("$gamma");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(
+            app.pending_actions.as_slice(),
+            [Action::AttachSession(id, false)] if id == "$gamma"
+        ));
+        assert!(app.state.attach_history.last_attached("gamma").unwrap() >= now);
+
+        // Most recent first, never-attached after and alphabetical
+        while app.state.sort_by != Some(Column::Used) {
+            press(&mut app, KeyCode::Char('s'));
+        }
+        let names: Vec<&str> = app
+            .rows
+            .iter()
+            .filter_map(|row| match row {
+                ListRow::Session(index) => Some(app.sessions[*index].name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["gamma", "beta", "zeta", "alpha"]);
+
+        app.select_session_id("$zeta");
+        let lines = render_detail(&app, 60, 20).join("\n");
+        assert!(lines.contains("Last attached: 1h ago"), "{}", lines);
+        app.select_session_id("$alpha");
+        let lines = render_detail(&app, 60, 20).join("\n");
+        assert!(lines.contains("Last attached: never"), "{}", lines);
+    }
+
+    #[test]
+    fn test_detail_shows_start_dir() {
+        let mut app = grouped_app();
+        for session in &mut app.sessions {
+            session.start_path = "/work/api".to_string();
+            session.current_path = match session.name.as_str() {
+                "beta" => "/work/api/server".to_string(),
+                _ => "/work/api".to_string(),
+            };
+        }
+        app.select_session_id("$beta");
+        let lines = render_detail(&app, 80, 20).join("\n");
+        assert!(
+            lines.contains("Dir: /work/api/server (started in /work/api)"),
+            "{}",
+            lines
+        );
+        app.select_session_id("$alpha");
+        let lines = render_detail(&app, 80, 20).join("\n");
+        assert!(!lines.contains("started in"), "{}", lines);
+    }
+
+    #[test]
+    fn test_session_age() {
+        let mut app = grouped_app();
+        let now = text::unix_now();
+        for session in &mut app.sessions {
+            session.created_at = match session.name.as_str() {
+                "beta" => now - (2 * 86400 + 3 * 3600 + 60),
+                "zeta" => now - 3600,
+                _ => 0,
+            };
+        }
+
+        app.select_session_id("$beta");
+        let lines = render_detail(&app, 60, 20).join("\n");
+        assert!(lines.contains("(2d 3h ago)"), "{}", lines);
+        assert!(lines.contains(&text::format_local_time(now - (2 * 86400 + 3 * 3600 + 60))));
+        app.select_session_id("$alpha");
+        let lines = render_detail(&app, 60, 20).join("\n");
+        assert!(lines.contains("Created: unknown"), "{}", lines);
+
+        // Only sessions past the threshold show their age in the list
+        let list = render_list(&mut app, 40, 15);
+        assert!(
+            list.iter().any(|line| line.contains("beta 2d")),
+            "{:?}",
+            list
+        );
+        assert!(list.iter().any(|line| line.contains("zeta ")));
+        assert!(!list.iter().any(|line| line.contains("zeta 1h")));
+        app.list_old_after_secs = 0;
+        let list = render_list(&mut app, 40, 15);
+        assert!(!list.iter().any(|line| line.contains("beta 2d")));
+    }
+
+    #[test]
+    fn test_monochrome_rendering_snapshot() {
+        let mut app = grouped_app();
+        app.theme = Theme::new(ColorSupport::Monochrome, StatusIcons::default());
+        let mut terminal = Terminal::new(TestBackend::new(40, 12)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let lines: Vec<String> = (0..12)
+            .map(|y| (0..40).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect();
+        assert_eq!(
+            lines[3..9],
+            [
+                "┌ Sessions ────┐┌ Details ─────────────┐",
+                "│  ▾ Needs atte││Name: gamma           │",
+                "│▶   ? 1 gamma ││ID: $gamma            │",
+                "│  ▾ Working (1││Status: ? WaitingForIn│",
+                "│    ⠋ 2 beta  ││Created: unknown      │",
+                "└──────────────┘└──────────────────────┘",
+            ]
+        );
+
+        // No colors reach the terminal; roles show as modifiers instead
+        assert!(
+            buffer
+                .content
+                .iter()
+                .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset)
+        );
+        let modifier = |x, y| buffer[(x, y)].modifier;
+        // The selected row is reversed, its waiting icon bold
+        assert!(modifier(8, 5).contains(Modifier::REVERSED | Modifier::BOLD));
+        assert!(modifier(12, 5).contains(Modifier::REVERSED));
+        assert!(!modifier(12, 7).contains(Modifier::REVERSED));
+        // Borders are dim, a busy icon bold
+        assert!(modifier(0, 4).contains(Modifier::DIM));
+        assert!(modifier(5, 7).contains(Modifier::BOLD));
+    }
+
+    #[test]
+    fn test_custom_status_icons() {
+        let config = Config {
+            status_icons: StatusIcons {
+                waiting_for_input: "!".to_string(),
+                idle: "-".to_string(),
+                ..StatusIcons::default()
+            },
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(grouped_app().sessions))
+            .unwrap();
+        app.select_session_id("$gamma");
+        let mut terminal = Terminal::new(TestBackend::new(80, 14)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let lines: Vec<String> = (0..14)
+            .map(|y| (0..80).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect();
+        // Header counts, list and detail pane all use the configured glyphs
+        assert!(
+            lines[1].contains("! 1  ● 1  - 2  ○ 1  updated"),
+            "{}",
+            lines[1]
+        );
+        assert!(lines[4].contains("- 1 zeta"), "{}", lines[4]);
+        assert!(lines[7].contains("! 4 gamma"), "{}", lines[7]);
+        assert!(
+            lines[6].contains("Status: ! WaitingForInput"),
+            "{}",
+            lines[6]
+        );
+    }
+
+    #[test]
+    fn test_busy_spinner() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Idle,
+        )]))
+        .unwrap();
+        // Nothing to animate: ticks don't redraw
+        assert!(!app.is_spinning());
+        app.take_dirty();
+        app.handle_action(Action::Tick).unwrap();
+        assert!(!app.take_dirty());
+
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Busy,
+        )]))
+        .unwrap();
+        assert!(app.is_spinning());
+        assert_eq!(render_list(&mut app, 20, 3)[1], "│▶ ⠋ 1 api         │");
+        app.take_dirty();
+        app.handle_action(Action::Tick).unwrap();
+        assert!(app.take_dirty());
+        assert_eq!(render_list(&mut app, 20, 3)[1], "│▶ ⠙ 1 api         │");
+
+        // Turned off, busy sessions show their static icon
+        app.theme.icons.spinner = false;
+        app.theme.icons.busy = "*".to_string();
+        assert!(!app.is_spinning());
+        assert_eq!(render_list(&mut app, 20, 3)[1], "│▶ * 1 api         │");
+    }
+
+    #[test]
+    fn test_duplicate_session() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut api = session("api", AgentStatus::Busy);
+        api.start_command = "claude".to_string();
+        app.handle_action(Action::SessionsUpdated(vec![
+            api,
+            session("api-2", AgentStatus::Idle),
+        ]))
+        .unwrap();
+        app.take_pending_actions();
+
+        press(&mut app, KeyCode::Char('F'));
+        let actions = app.take_pending_actions();
+        let [Action::DuplicateSession(source, copy)] = actions.as_slice() else {
+            panic!("{:?}", actions);
+        };
+        assert_eq!(source, "$api");
+        assert_eq!(copy.name, "api-3");
+        let command = copy.launcher.as_ref().and_then(|l| l.command.as_deref());
+        assert_eq!(command, Some("claude"));
+
+        let op = app.start_operation("Duplicating 'api'");
+        app.handle_action(Action::SessionDuplicated(
+            op,
+            "api".to_string(),
+            "api-3".to_string(),
+            Ok(session("api-3", AgentStatus::Unknown)),
+        ))
+        .unwrap();
+        assert_eq!(app.message(), Some("Duplicated 'api' as 'api-3'"));
+        assert_eq!(app.selected_session().unwrap().name, "api-3");
+        // Restartable like any session created here
+        assert_eq!(
+            app.state
+                .launcher("api-3")
+                .and_then(|l| l.command.as_deref()),
+            Some("claude")
+        );
+
+        // A failed copy doesn't hold on to its name
+        app.select_session_id("$api");
+        press(&mut app, KeyCode::Char('F'));
+        assert!(app.creating.contains_key("api-4"));
+        let op = app.start_operation("Duplicating 'api'");
+        let error = anyhow::anyhow!("can't find session: $api");
+        app.handle_action(Action::SessionDuplicated(
+            op,
+            "api".to_string(),
+            "api-4".to_string(),
+            Err(Arc::new(error)),
+        ))
+        .unwrap();
+        assert!(app.creating.is_empty());
+
+        // Read-only instances don't create any
+        app.read_only = true;
+        app.take_pending_actions();
+        press(&mut app, KeyCode::Char('F'));
+        assert!(app.take_pending_actions().is_empty());
+    }
+
+    #[test]
+    fn test_reorder_sessions_by_hand() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let listed = |names: &[&str]| {
+            Action::SessionsUpdated(
+                names
+                    .iter()
+                    .map(|name| session(name, AgentStatus::Idle))
+                    .collect(),
+            )
+        };
+        app.handle_action(listed(&["api", "web", "db"])).unwrap();
+
+        // The selection follows the moved session
+        press(&mut app, KeyCode::Char('J'));
+        assert_eq!(app.selected_session().unwrap().name, "api");
+        press(&mut app, KeyCode::Char('J'));
+        press(&mut app, KeyCode::Char('J'));
+        let names = |app: &mut App| -> Vec<String> {
+            render_list(app, 16, 6)[1..5]
+                .iter()
+                .map(|l| l.trim_matches(|c| c == '│' || c == ' ').to_string())
+                .collect()
+        };
+        assert_eq!(names(&mut app), ["● 1 web", "● 2 db", "▶ ● 3 api", ""]);
+
+        // Kept on every poll; new sessions go last, gone ones are dropped
+        app.handle_action(listed(&["new", "api", "db"])).unwrap();
+        assert_eq!(names(&mut app), ["● 1 db", "▶ ● 2 api", "● 3 new", ""]);
+        press(&mut app, KeyCode::Char('K'));
+        press(&mut app, KeyCode::Char('K'));
+        assert_eq!(names(&mut app), ["▶ ● 1 api", "● 2 db", "● 3 new", ""]);
+
+        // A column sort overrides it
+        app.state.sort_by = Some(Column::Name);
+        press(&mut app, KeyCode::Char('J'));
+        assert!(app.message().unwrap().starts_with("Sorted by name"));
+    }
+
+    #[test]
+    fn test_cycle_poll_rate() {
+        let config = Config {
+            poll_rates: vec![crate::poller::PollRateRule {
+                when: "batch-*".to_string(),
+                rate: PollRate::Slow,
+            }],
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("batch-nightly", AgentStatus::Busy),
+            session("api", AgentStatus::Idle),
+        ]))
+        .unwrap();
+        let polling = |app: &App| {
+            render_detail(app, 60, 20)
+                .into_iter()
+                .find(|line| line.contains("Polling: "))
+                .unwrap()
+        };
+        assert!(polling(&app).contains("Polling: slow (p cycles)"));
+
+        press(&mut app, KeyCode::Char('p'));
+        assert_eq!(
+            app.state.poll_rates,
+            BTreeMap::from([("batch-nightly".to_string(), PollRate::Normal)])
+        );
+        assert_eq!(app.message(), Some("Polling batch-nightly normal"));
+        press(&mut app, KeyCode::Char('p'));
+        assert!(polling(&app).contains("Polling: fast"));
+        // Back to what the config says: nothing left to persist
+        press(&mut app, KeyCode::Char('p'));
+        assert!(app.state.poll_rates.is_empty());
+        assert!(polling(&app).contains("Polling: slow"));
+
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('p'));
+        assert_eq!(
+            app.state.poll_rates,
+            BTreeMap::from([("api".to_string(), PollRate::Fast)])
+        );
+    }
+
+    #[test]
+    fn test_collapse_and_expand_group() {
+        let mut app = grouped_app();
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected_session().unwrap().name, "alpha");
+
+        press(&mut app, KeyCode::Left);
+        assert!(app.state.collapsed_groups.contains(&StatusGroup::Idle));
+        assert!(app.selected_session().is_none());
+        assert_eq!(
+            render_list(&mut app, 30, 9)[5],
+            "│▶ ▸ Idle (2)                │"
+        );
+
+        // Collapsed headers stay reachable; expanding lands on the first member
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Up);
+        press(&mut app, KeyCode::Enter);
+        assert!(app.state.collapsed_groups.is_empty());
+        assert_eq!(app.selected_session().unwrap().name, "alpha");
+    }
+
+    #[test]
+    fn test_selection_follows_session_across_groups() {
+        let mut app = grouped_app();
+        press(&mut app, KeyCode::Down);
+        assert_eq!(app.selected_session().unwrap().name, "beta");
+
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("beta", AgentStatus::Idle),
+            session("gamma", AgentStatus::WaitingForInput),
+        ]))
+        .unwrap();
+        assert_eq!(app.selected_session().unwrap().name, "beta");
+
+        press(&mut app, KeyCode::Char('v'));
+        assert_eq!(app.state.list_display, ListDisplay::Flat);
+        assert_eq!(app.selected_session().unwrap().name, "beta");
+    }
+
+    #[test]
+    fn test_table_drops_columns_as_width_shrinks() {
+        let mut app = grouped_app();
+        app.list_layout = ListLayout::Table;
+        app.state.list_display = ListDisplay::Flat;
+        app.rebuild_rows(None);
+        let now = text::unix_now();
+        for session in &mut app.sessions {
+            session.created_at = now - 7200;
+            session.activity_at = now - 90;
+            session.current_command = "claude".to_string();
+            session.activity_trend = vec![0; activity::ACTIVITY_BUCKETS];
+        }
+        let beta = app.sessions.iter_mut().find(|s| s.name == "beta").unwrap();
+        beta.activity_trend[0] = 2;
+        beta.activity_trend[29] = 8;
+
+        let wide = render_list(&mut app, 56, 4);
+        assert_eq!(
+            wide[1],
+            "│    Name         Agent  Age  Active Output     Clients│"
+        );
+        assert_eq!(
+            wide[2],
+            "│▶ ⠋ 2 beta       claude 2h   1m     ▂        █ 0      │"
+        );
+        assert!(!app.visible_columns.contains(&Column::Used));
+
+        let widest = render_list(&mut app, 62, 4);
+        assert_eq!(
+            widest[1],
+            "│    Name         Agent  Age  Active Output     Clients Used │"
+        );
+        assert_eq!(
+            widest[2],
+            "│▶ ⠋ 2 beta       claude 2h   1m     ▂        █ 0       -    │"
+        );
+        assert_eq!(app.visible_columns, Column::ALL.to_vec());
+
+        let wide = render_list(&mut app, 45, 4);
+        assert_eq!(wide[1], "│    Name                 Agent  Age  Active│");
+        assert!(!app.visible_columns.contains(&Column::Output));
+
+        let medium = render_list(&mut app, 34, 4);
+        assert_eq!(medium[1], "│    Name             Agent  Age │");
+        assert_eq!(medium[2], "│▶ ⠋ 2 beta           claude 2h  │");
+        assert_eq!(
+            app.visible_columns,
+            vec![Column::Status, Column::Name, Column::Agent, Column::Age]
+        );
+
+        let narrow = render_list(&mut app, 20, 4);
+        assert_eq!(narrow[1], "│    Name          │");
+        assert_eq!(narrow[2], "│▶ ⠋ 2 beta        │");
+        assert_eq!(app.visible_columns, vec![Column::Status, Column::Name]);
+
+        // Sorting only cycles through what is on screen, and recently used
+        press(&mut app, KeyCode::Char('s'));
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.state.sort_by, Some(Column::Name));
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.state.sort_by, Some(Column::Used));
+        press(&mut app, KeyCode::Char('s'));
+        assert_eq!(app.state.sort_by, None);
+    }
+
+    #[test]
+    fn test_long_names_are_truncated_in_the_middle() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "agent-payments-refactor-attempt-3-with-new-prompt",
+            AgentStatus::Idle,
+        )]))
+        .unwrap();
+
+        assert_eq!(
+            render_list(&mut app, 27, 3)[1],
+            "│▶ ● 1 agent-pay…ew-prompt│"
+        );
+
+        app.list_layout = ListLayout::Table;
+        assert_eq!(render_list(&mut app, 24, 4)[2], "│▶ ● 1 agen…ompt -     │");
+    }
+
+    #[test]
+    fn test_ctrl_o_returns_to_previously_attached_session() {
+        let mut app = grouped_app();
+        app.state.list_display = ListDisplay::Flat;
+        app.rebuild_rows(None);
+        app.list_state.select(Some(0));
+
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Down);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.selected_session().unwrap().name, "alpha");
+
+        let ctrl_o = KeyEvent::new(KeyCode::Char('o'), KeyModifiers::CONTROL);
+        app.handle_action(Action::KeyPress(ctrl_o)).unwrap();
+        assert_eq!(app.selected_session().unwrap().name, "zeta");
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.selected_session().unwrap().name, "alpha");
+    }
+
+    #[test]
+    fn test_returning_from_attach_restores_selection() {
+        let mut app = grouped_app();
+        app.state.list_display = ListDisplay::Flat;
+        app.rebuild_rows(None);
+        app.list_state.select(Some(0));
+        press(&mut app, KeyCode::Enter);
+        let attached = app.selected_session().unwrap().id.clone();
+        app.notify(Level::Success, "Text copied to tmux");
+
+        // A poll while attached moved the sessions and the selection with them
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("alpha", AgentStatus::Idle),
+            session("beta", AgentStatus::Busy),
+        ]))
+        .unwrap();
+        assert_ne!(app.selected_session().unwrap().id, attached);
+
+        // Detaching puts the selection back once the session is listed again
+        app.returned_from(&attached);
+        assert!(app.message().is_none());
+        assert_eq!(app.jump_list.current(), Some(attached.as_str()));
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("alpha", AgentStatus::Idle),
+            session("beta", AgentStatus::Busy),
+            session("zeta", AgentStatus::Idle),
+        ]))
+        .unwrap();
+        assert_eq!(app.selected_session().unwrap().id, attached);
+
+        // Only the first poll after returning is affected
+        press(&mut app, KeyCode::Up);
+        app.handle_action(Action::SessionsUpdated(app.sessions.clone()))
+            .unwrap();
+        assert_eq!(app.selected_session().unwrap().name, "beta");
+    }
+
+    #[test]
+    fn test_refresh_key_and_refresh_after_mutations() {
+        let mut app = grouped_app();
+        press(&mut app, KeyCode::F(5));
+        press(&mut app, KeyCode::F(5));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::Refresh]
+        ));
+        assert_eq!(app.operations.values().collect::<Vec<_>>(), ["Refreshing"]);
+
+        // The next poll result ends the refresh, whether or not anything changed
+        app.handle_action(Action::Heartbeat).unwrap();
+        assert!(app.operations.is_empty());
+        press(&mut app, KeyCode::F(5));
+        assert_eq!(app.take_pending_actions().len(), 1);
+        app.handle_action(Action::Error("Tmux: server exited".to_string()))
+            .unwrap();
+        assert!(app.operations.is_empty());
+
+        let op = app.start_operation("Deleting");
+        app.handle_action(Action::SessionDeleted(op, Ok("$beta".to_string())))
+            .unwrap();
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::Refresh]
+        ));
+        let op = app.start_operation("Creating");
+        let created = session("omega", AgentStatus::Unknown);
+        app.handle_action(Action::SessionCreated(op, Ok(created)))
+            .unwrap();
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::Refresh]
+        ));
+        assert_eq!(app.selected_session().unwrap().name, "omega");
+    }
+
+    #[test]
+    fn test_focus_throttles_background_work() {
+        let mut app = grouped_app();
+        app.start_operation("Saving snapshot");
+        // No focus reports yet: behave as if focused
+        assert!(!app.in_background());
+        assert!(app.is_animating());
+
+        app.handle_action(Action::FocusChanged(false)).unwrap();
+        assert!(app.in_background());
+        assert!(!app.is_animating());
+        assert!(app.take_pending_actions().is_empty());
+
+        app.handle_action(Action::FocusChanged(true)).unwrap();
+        assert!(app.is_animating());
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::Refresh]
+        ));
+    }
+
+    #[test]
+    fn test_bell_rings_when_unfocused() {
+        let config = Config {
+            bell: crate::bell::BellConfig {
+                waiting_for_input: true,
+                debounce_secs: 0,
+                min_interval_secs: 0,
+                ..Default::default()
+            },
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        let waiting = || {
+            Action::StatusChanged(vec![Transition {
+                session_id: "$api".to_string(),
+                session: "api".to_string(),
+                previous: AgentStatus::Busy,
+                status: AgentStatus::WaitingForInput,
+            }])
+        };
+
+        app.handle_action(waiting()).unwrap();
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::RingBell]
+        ));
+
+        // Looking at the dashboard already
+        app.handle_action(Action::FocusChanged(true)).unwrap();
+        app.take_pending_actions();
+        app.handle_action(waiting()).unwrap();
+        assert!(app.take_pending_actions().is_empty());
+
+        app.handle_action(Action::FocusChanged(false)).unwrap();
+        press(&mut app, KeyCode::Char('m'));
+        assert!(app.bell.muted);
+        assert_eq!(app.message(), Some("Bell muted"));
+        app.handle_action(waiting()).unwrap();
+        assert!(app.take_pending_actions().is_empty());
+
+        press(&mut app, KeyCode::Char('m'));
+        app.handle_action(waiting()).unwrap();
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::RingBell]
+        ));
+    }
+
+    #[test]
+    fn test_alerts_fire_and_show_in_header() {
+        let alert = |action| crate::alerts::AlertRule {
+            when: "*".to_string(),
+            status: AgentStatus::Error,
+            for_secs: 0,
+            action,
+            run: None,
+        };
+        let config = Config {
+            alerts: vec![
+                alert(AlertAction::Notify),
+                alert(AlertAction::Bell),
+                alert(AlertAction::Highlight),
+            ],
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        let header = |app: &App| {
+            let mut terminal = Terminal::new(TestBackend::new(120, 3)).unwrap();
+            terminal.draw(|f| app.render_header(f, f.area())).unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..120)
+                .map(|x| buffer[(x, 1)].symbol())
+                .collect::<String>()
+        };
+
+        let sessions = vec![
+            session("api", AgentStatus::Error),
+            session("web", AgentStatus::Busy),
+        ];
+        app.handle_action(Action::SessionsUpdated(sessions.clone()))
+            .unwrap();
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::FireAlert(alert), Action::RingBell] if alert.session == "api"
+        ));
+        assert!(app.alerts.highlights("$api"));
+        assert!(!app.alerts.highlights("$web"));
+        assert!(
+            header(&app).contains(" ⚠ api error 0s "),
+            "{}",
+            header(&app)
+        );
+
+        // Still firing, but only once per episode
+        app.handle_action(Action::Heartbeat).unwrap();
+        app.handle_action(Action::SessionsUpdated(sessions))
+            .unwrap();
+        assert!(app.take_pending_actions().is_empty());
+
+        // Recovering clears it
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Busy,
+        )]))
+        .unwrap();
+        assert!(!app.alerts.highlights("$api"));
+        assert!(!header(&app).contains('⚠'));
+
+        // A read-only instance leaves notifying to the other one
+        let mut app = App::new(&config, PersistedState::default());
+        app.read_only = true;
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Error,
+        )]))
+        .unwrap();
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::RingBell]
+        ));
+    }
+
+    #[test]
+    fn test_dirty_tracking() {
+        let mut app = grouped_app();
+        assert!(app.take_dirty());
+        assert!(!app.take_dirty());
+
+        // Polls that found nothing new leave the screen alone
+        let sessions = app.sessions.clone();
+        app.handle_action(Action::SessionsUpdated(sessions.clone()))
+            .unwrap();
+        app.handle_action(Action::Heartbeat).unwrap();
+        app.handle_action(Action::FocusChanged(false)).unwrap();
+        app.handle_action(Action::Mouse(MouseEvent {
+            kind: MouseEventKind::Moved,
+            column: 3,
+            row: 4,
+            modifiers: KeyModifiers::NONE,
+        }))
+        .unwrap();
+        assert!(!app.take_dirty());
+
+        // Any real change is drawn
+        let mut changed = sessions.clone();
+        changed[0].status = AgentStatus::Error;
+        app.handle_action(Action::SessionsUpdated(changed)).unwrap();
+        assert!(app.take_dirty());
+        press(&mut app, KeyCode::Char('j'));
+        assert!(app.take_dirty());
+        app.handle_action(Action::Resize(80, 24)).unwrap();
+        assert!(app.take_dirty());
+
+        // So is fresh data replacing data shown as stale
+        app.last_update = Some(Instant::now() - STALE_AFTER);
+        app.handle_action(Action::Heartbeat).unwrap();
+        assert!(app.take_dirty());
+        app.handle_action(Action::Heartbeat).unwrap();
+        assert!(!app.take_dirty());
+
+        // And the end of a manual refresh
+        press(&mut app, KeyCode::F(5));
+        assert!(app.take_dirty());
+        app.handle_action(Action::Heartbeat).unwrap();
+        assert!(app.take_dirty());
+    }
+
+    #[test]
+    fn test_branch_suffix_in_list() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut with_git = session("agent-payments-refactor", AgentStatus::Idle);
+        with_git.git = Some(crate::git::GitInfo {
+            head: crate::git::GitHead::Branch("main".to_string()),
+            dirty: true,
+        });
+        app.handle_action(Action::SessionsUpdated(vec![with_git]))
+            .unwrap();
+
+        assert_eq!(render_list(&mut app, 24, 3)[1], "│▶ ● 1 agent-pa…efactor│");
+
+        // The branch suffix takes its room from the name
+        app.show_branch_in_list = true;
+        assert_eq!(render_list(&mut app, 24, 3)[1], "│▶ ● 1 agent…ctor main*│");
+    }
+
+    #[test]
+    fn test_broadcast_requires_confirmation() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut shell = session("shell", AgentStatus::Idle);
+        shell.current_command = "zsh".to_string();
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("a", AgentStatus::Idle),
+            session("b", AgentStatus::Error),
+            shell,
+        ]))
+        .unwrap();
+
+        // Nothing marked: no prompt
+        press(&mut app, KeyCode::Char('b'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Char(' '));
+            press(&mut app, KeyCode::Char('j'));
+        }
+        assert_eq!(app.marked.len(), 3);
+        assert!(render_list(&mut app, 30, 6)[1].contains("+ a"));
+
+        press(&mut app, KeyCode::Char('b'));
+        for c in "hi".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::ConfirmingBroadcast);
+        assert!(app.pending_actions.is_empty());
+
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        match app.take_pending_actions().as_slice() {
+            [Action::Broadcast(recipients, text)] => {
+                assert_eq!(text, "hi");
+                let names: Vec<_> = recipients.iter().map(|r| r.name.as_str()).collect();
+                assert_eq!(names, vec!["a"]);
+            }
+            other => panic!("unexpected actions: {:?}", other),
+        }
+
+        // Forcing includes the skipped sessions
+        press(&mut app, KeyCode::Char('b'));
+        press(&mut app, KeyCode::Char('x'));
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('f'));
+        press(&mut app, KeyCode::Char('y'));
+        match app.take_pending_actions().as_slice() {
+            [Action::Broadcast(recipients, _)] => assert_eq!(recipients.len(), 3),
+            other => panic!("unexpected actions: {:?}", other),
+        }
+
+        app.handle_action(Action::BroadcastFinished(vec![BroadcastResult {
+            name: "a".to_string(),
+            outcome: Err("can't find pane".to_string()),
+        }]))
+        .unwrap();
+        assert_eq!(app.input_mode, InputMode::BroadcastResults);
+        press(&mut app, KeyCode::Char('q'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn test_restart_requires_confirmation() {
+        let mut app = grouped_app();
+        press(&mut app, KeyCode::Char('j'));
+        let id = app.selected_session().unwrap().id.clone();
+
+        press(&mut app, KeyCode::Char('R'));
+        assert_eq!(app.input_mode, InputMode::ConfirmingRestart);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.pending_actions.is_empty());
+
+        press(&mut app, KeyCode::Char('R'));
+        press(&mut app, KeyCode::Char('y'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::RestartAgent(target)] if *target == id
+        ));
+    }
+
+    #[test]
+    fn test_alt_enter_attaches_in_window() {
+        let mut app = grouped_app();
+        press(&mut app, KeyCode::Char('j'));
+        let id = app.selected_session().unwrap().id.clone();
+        app.handle_action(Action::KeyPress(KeyEvent::new(
+            KeyCode::Enter,
+            KeyModifiers::ALT,
+        )))
+        .unwrap();
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::AttachSessionInWindow(target)] if *target == id
+        ));
+    }
+
+    #[test]
+    fn test_error_details_popup() {
+        let mut app = grouped_app();
+        let error = anyhow::anyhow!("can't find session: $9")
+            .context("tmux new-session exited with 1")
+            .context("Failed to create session 'api'");
+        app.report_error(&error);
+        assert_eq!(app.message(), Some("Failed to create session 'api'"));
+
+        press(&mut app, KeyCode::Char('e'));
+        assert_eq!(app.input_mode, InputMode::ErrorDetails);
+        press(&mut app, KeyCode::Char('y'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::CopyText(text)] if text.lines().count() == 3
+        ));
+
+        // A newer plain message has no details, and `e` just dismisses it
+        app.handle_action(Action::Error("Tmux: server exited".to_string()))
+            .unwrap();
+        assert!(app.current_error_details().is_none());
+        press(&mut app, KeyCode::Char('e'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.message().is_none());
+    }
+
+    #[test]
+    fn test_paste_into_inputs() {
+        let mut app = grouped_app();
+        press(&mut app, KeyCode::Char('n'));
+        app.handle_action(Action::Paste("my api/v2".to_string()))
+            .unwrap();
+        assert_eq!(app.input_buffer.as_str(), "myapiv2");
+        press(&mut app, KeyCode::Esc);
+
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('N'));
+        app.handle_action(Action::Paste("a\r\nb\nc\nd\ne\nf".to_string()))
+            .unwrap();
+        assert_eq!(app.input_buffer.as_str(), "a\nb\nc\nd\nef");
+
+        // Outside of an input, pastes are ignored
+        press(&mut app, KeyCode::Esc);
+        app.handle_action(Action::Paste("q".to_string())).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn test_background_operations() {
+        let mut app = grouped_app();
+        let create = app.start_operation("Creating 'new'");
+        let delete = app.start_operation("Deleting beta");
+        assert_eq!(app.operations.len(), 2);
+        assert!(render_footer(&mut app).contains("Creating 'new'… (+1 more)"));
+
+        app.handle_action(Action::SessionCreated(
+            create,
+            Ok(session("new", AgentStatus::Unknown)),
+        ))
+        .unwrap();
+        assert_eq!(app.selected_session().unwrap().name, "new");
+        assert_eq!(app.operations.len(), 1);
+
+        let error = anyhow::anyhow!("can't find session").context("Failed to delete beta");
+        app.handle_action(Action::SessionDeleted(delete, Err(Arc::new(error))))
+            .unwrap();
+        assert!(app.operations.is_empty());
+        assert_eq!(app.message(), Some("Failed to delete beta"));
+        assert!(app.current_error_details().is_some());
+
+        let op = app.start_operation("Deleting new");
+        app.handle_action(Action::SessionDeleted(op, Ok("$new".to_string())))
+            .unwrap();
+        assert!(app.sessions.iter().all(|s| s.name != "new"));
+        assert!(app.selected_session().is_some());
+    }
+
+    #[test]
+    fn test_sticky_failures_wait_for_esc() {
+        let mut app = grouped_app();
+        let op = app.start_operation("Deleting beta");
+        let error = anyhow::anyhow!("can't find session").context("Failed to delete beta");
+        app.handle_action(Action::SessionDeleted(op, Err(Arc::new(error))))
+            .unwrap();
+        app.notify(Level::Success, "Text copied");
+        press(&mut app, KeyCode::Char(' '));
+
+        // Other keys and the clock leave it, with its details
+        press(&mut app, KeyCode::Char('j'));
+        app.handle_action(Action::Tick).unwrap();
+        assert_eq!(app.message(), Some("Failed to delete beta"));
+        assert!(
+            render_footer(&mut app).contains("(press e for details, Esc to dismiss)"),
+            "{}",
+            render_footer(&mut app)
+        );
+        app.returned_from("$beta");
+        assert!(app.current_error_details().is_some());
+
+        // The first Esc only dismisses it
+        press(&mut app, KeyCode::Esc);
+        assert!(app.message().is_none());
+        assert_eq!(app.marked.len(), 1);
+        press(&mut app, KeyCode::Esc);
+        assert!(app.marked.is_empty());
+
+        // Transient messages still go with any key, Esc included
+        app.notify(Level::Warning, "Mark sessions with Space first");
+        press(&mut app, KeyCode::Esc);
+        assert!(app.message().is_none());
+
+        let op = app.start_operation("Creating 'api'");
+        let error = anyhow::anyhow!("exit 1").context("Failed to create api");
+        app.handle_action(Action::SessionCreated(op, Err(Arc::new(error))))
+            .unwrap();
+        assert!(render_footer(&mut app).contains("Esc to dismiss"));
+    }
+
+    #[test]
+    fn test_server_info_in_header() {
+        let mut app = grouped_app();
+        let server = |path: &str, running, sessions, windows| TmuxServer {
+            socket_path: path.to_string(),
+            running,
+            sessions,
+            windows,
+        };
+        let header = |app: &mut App, info: ServerInfo| {
+            app.handle_action(Action::ServerInfoUpdated(Ok(info)))
+                .unwrap();
+            let mut terminal = Terminal::new(TestBackend::new(100, 10)).unwrap();
+            terminal.draw(|f| app.render(f)).unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..100)
+                .map(|x| buffer[(x, 0)].symbol())
+                .collect::<String>()
+        };
+        let info = |servers| ServerInfo {
+            version: "tmux 3.4".to_string(),
+            servers,
+        };
+
+        let line = header(
+            &mut app,
+            info(vec![server("/tmp/tmux-1000/default", true, 5, 12)]),
+        );
+        assert!(
+            line.ends_with("─ tmux 3.4 · /tmp/tmux-1000/default · 5 sessions, 12 windows ┐"),
+            "{}",
+            line
+        );
+        let line = header(
+            &mut app,
+            info(vec![
+                server("/tmp/tmux-1000/work", true, 5, 12),
+                server("/tmp/tmux-1000/personal", true, 1, 1),
+                server("/tmp/tmux-1000/ci", false, 0, 0),
+            ]),
+        );
+        assert!(
+            line.contains(" tmux 3.4 · 2 servers · 6 sessions, 13 windows · ci not running "),
+            "{}",
+            line
+        );
+        let line = header(
+            &mut app,
+            info(vec![server("/tmp/tmux-1000/default", false, 0, 0)]),
+        );
+        assert!(
+            line.contains(" tmux 3.4: no server running on /tmp/tmux-1000/default "),
+            "{}",
+            line
+        );
+
+        // Nothing to say if tmux couldn't be asked
+        app.handle_action(Action::ServerInfoUpdated(Err(Arc::new(anyhow::anyhow!(
+            "tmux not found"
+        )))))
+        .unwrap();
+        assert!(app.server_info.is_none());
+    }
+
+    fn render_footer(app: &mut App) -> String {
+        let backend = TestBackend::new(120, 10);
+        let mut terminal = Terminal::new(backend).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..buffer.area.width)
+            .map(|x| buffer[(x, 8)].symbol())
+            .collect()
+    }
+
+    #[test]
+    fn test_notifications() {
+        let mut app = grouped_app();
+        app.handle_action(Action::Error("Tmux: no server running".to_string()))
+            .unwrap();
+        // A later message shows instead, without replacing the error
+        app.notifications.push_at(
+            Level::Success,
+            "Text copied to tmux",
+            Instant::now() - Level::Success.ttl(),
+        );
+        assert_eq!(app.message(), Some("Text copied to tmux"));
+        // Otherwise busy beta's spinner redraws on every tick
+        app.theme.icons.spinner = false;
+        app.take_dirty();
+
+        app.handle_action(Action::Tick).unwrap();
+        assert!(app.take_dirty());
+        assert_eq!(app.message(), Some("Tmux: no server running"));
+        app.handle_action(Action::Tick).unwrap();
+        assert!(!app.take_dirty());
+
+        // Colored by level, not by wording
+        let mut terminal = Terminal::new(TestBackend::new(120, 10)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert!(render_footer(&mut app).starts_with("│ Tmux: no server running "));
+        assert_eq!(terminal.backend().buffer()[(2, 8)].fg, app.theme.error);
+        app.handle_action(Action::Notify(Level::Success, "success!".to_string()))
+            .unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        assert_eq!(terminal.backend().buffer()[(2, 8)].fg, app.theme.success);
+
+        // A key dismisses them all
+        press(&mut app, KeyCode::Char('j'));
+        assert!(app.message().is_none());
+    }
+
+    #[test]
+    fn test_watch_attaches_read_only() {
+        let mut app = grouped_app();
+        // Several windows would ask which one first; Enter still attaches normally
+        app.pick_window = false;
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::AttachSession(id, false)] if id == "$gamma"
+        ));
+        press(&mut app, KeyCode::Char('V'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::AttachSession(id, true)] if id == "$gamma"
+        ));
+        let detail = render_detail(&app, 100, 20).join("\n");
+        assert!(detail.contains("Press Enter to attach, 'V' to watch read-only"));
+    }
+
+    #[test]
+    fn test_number_keys_attach() {
+        // Numbers follow the list as shown, not the order tmux reported
+        let mut app = grouped_app();
+        press(&mut app, KeyCode::Char('3'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::AttachSession(id, false)] if id == "$alpha"
+        ));
+        assert_eq!(app.selected_session().unwrap().name, "alpha");
+
+        // and count only what the filter leaves
+        press(&mut app, KeyCode::Char('/'));
+        for c in "eta".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('2'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::AttachSession(id, false)] if id == "$zeta"
+        ));
+        press(&mut app, KeyCode::Char('3'));
+        assert!(app.take_pending_actions().is_empty());
+    }
+
+    #[test]
+    fn test_attach_last_session() {
+        let mut app = grouped_app();
+        press(&mut app, KeyCode::Char('`'));
+        assert!(app.take_pending_actions().is_empty());
+
+        app.select_session_id("$beta");
+        press(&mut app, KeyCode::Enter);
+        app.take_pending_actions();
+        app.attached("$beta");
+        app.returned_from("$beta");
+        let detail = render_detail(&app, 60, 12).join("\n");
+        assert!(detail.contains("(latest, ` returns)"), "{}", detail);
+
+        // Even from elsewhere, and through a filter that hides it
+        app.select_session_id("$delta");
+        assert!(!render_detail(&app, 60, 12).join("\n").contains("latest"));
+        press(&mut app, KeyCode::Char('/'));
+        for c in "alp".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Char('`'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::AttachSession(id, false)] if id == "$beta"
+        ));
+        assert!(app.session_filter.is_empty());
+
+        // Once it's gone, the top of the list instead
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("alpha", AgentStatus::Idle),
+            session("zeta", AgentStatus::Idle),
+        ]))
+        .unwrap();
+        press(&mut app, KeyCode::Char('`'));
+        assert!(app.take_pending_actions().is_empty());
+        assert_eq!(app.message().unwrap(), "'beta' no longer exists");
+        assert_eq!(app.selected_session().unwrap().name, "alpha");
+    }
+
+    #[test]
+    fn test_mcp_status_in_footer() {
+        let mut app = grouped_app();
+        press(&mut app, KeyCode::Char('M'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::LoadMcpStatus]
+        ));
+        let footer = |app: &mut App| {
+            let mut terminal = Terminal::new(TestBackend::new(140, 24)).unwrap();
+            terminal.draw(|f| app.render(f)).unwrap();
+            let buffer = terminal.backend().buffer();
+            (0..140)
+                .map(|x| buffer[(x, 22)].symbol())
+                .collect::<String>()
+        };
+        let screen = footer(&mut app);
+        assert!(screen.contains("MCP: no server running"), "{}", screen);
+
+        let now = text::unix_now();
+        let server = |pid, calls| ServerStatus {
+            pid,
+            started_at: now - 300,
+            calls,
+        };
+        app.handle_action(Action::McpStatusLoaded(vec![server(41, 3), server(57, 14)]))
+            .unwrap();
+        let screen = footer(&mut app);
+        assert!(
+            screen.contains("MCP: serving (pid 41, 57) │ 17 tool calls │ up 5m"),
+            "{}",
+            screen
+        );
+
+        // Kept current while shown
+        app.handle_action(Action::SessionsUpdated(app.sessions.clone()))
+            .unwrap();
+        let loads = |app: &mut App| {
+            app.take_pending_actions()
+                .iter()
+                .any(|a| matches!(a, Action::LoadMcpStatus))
+        };
+        assert!(loads(&mut app));
+        press(&mut app, KeyCode::Char('M'));
+        app.handle_action(Action::SessionsUpdated(app.sessions.clone()))
+            .unwrap();
+        assert!(!loads(&mut app));
+    }
+
+    #[test]
+    fn test_filter_sessions() {
+        let mut app = grouped_app();
+        app.select_session_id("$beta");
+        press(&mut app, KeyCode::Char('/'));
+        assert_eq!(app.input_mode, InputMode::Filtering);
+        for c in "ETA".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        // The selection stays on its session as the list narrows
+        let names: Vec<&str> = app
+            .rows
+            .iter()
+            .filter_map(|row| match row {
+                ListRow::Session(i) => Some(app.sessions[*i].name.as_str()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(names, ["beta", "zeta"]);
+        assert_eq!(app.selected_session().unwrap().name, "beta");
+        assert!(render_footer(&mut app).contains("/ETA_"));
+
+        // Enter keeps the filter, and new polls are filtered too
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let mut sessions = app.sessions.clone();
+        sessions.push(session("theta", AgentStatus::Idle));
+        sessions.push(session("omega", AgentStatus::Idle));
+        app.handle_action(Action::SessionsUpdated(sessions))
+            .unwrap();
+        assert_eq!(
+            app.rows
+                .iter()
+                .filter(|row| matches!(row, ListRow::Session(_)))
+                .count(),
+            3
+        );
+        assert_eq!(app.selected_session().unwrap().name, "beta");
+        assert!(render_footer(&mut app).contains("Filter: /ETA"));
+
+        // Filtering the selected session out moves the selection to one still shown
+        press(&mut app, KeyCode::Char('/'));
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Backspace);
+        }
+        assert!(app.session_filter.is_empty());
+        press(&mut app, KeyCode::Char('z'));
+        assert_eq!(app.selected_session().unwrap().name, "zeta");
+
+        // Esc clears it
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.session_filter.is_empty());
+        assert_eq!(app.selected_session().unwrap().name, "zeta");
+        assert_eq!(
+            app.rows
+                .iter()
+                .filter(|row| matches!(row, ListRow::Session(_)))
+                .count(),
+            7
+        );
+    }
+
+    #[test]
+    fn test_mouse_selects_and_attaches() {
+        let mut app = grouped_app();
+        let mouse = |app: &mut App, kind, row| {
+            app.handle_action(Action::Mouse(MouseEvent {
+                kind,
+                column: 5,
+                row,
+                modifiers: KeyModifiers::NONE,
+            }))
+            .unwrap();
+        };
+        let click = |app: &mut App, row| mouse(app, MouseEventKind::Down(MouseButton::Left), row);
+        let line_of = |app: &mut App, text: &str| {
+            render_list(app, 40, 15)
+                .iter()
+                .position(|line| line.contains(text))
+                .unwrap() as u16
+        };
+
+        let alpha = line_of(&mut app, "alpha");
+        click(&mut app, alpha);
+        assert_eq!(app.selected_session().unwrap().name, "alpha");
+        assert!(app.take_dirty());
+        assert!(app.pending_actions.is_empty());
+
+        // Clicking the selected session attaches, once per double-click
+        click(&mut app, alpha);
+        click(&mut app, alpha);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::AttachSession(id, false)] if id == "$alpha"
+        ));
+
+        // Headers open and close their group; borders and blank lines do nothing
+        let header = line_of(&mut app, "Idle");
+        click(&mut app, header);
+        assert!(app.state.collapsed_groups.contains(&StatusGroup::Idle));
+        let selected = app.list_state.selected();
+        click(&mut app, 0);
+        click(&mut app, 14);
+        assert_eq!(app.list_state.selected(), selected);
+
+        // The wheel moves the selection
+        let beta = line_of(&mut app, "beta");
+        click(&mut app, beta);
+        let selected = app.list_state.selected();
+        mouse(&mut app, MouseEventKind::ScrollDown, 0);
+        assert_ne!(app.list_state.selected(), selected);
+        mouse(&mut app, MouseEventKind::ScrollUp, 0);
+        assert_eq!(app.selected_session().unwrap().name, "beta");
+    }
+
+    #[test]
+    fn test_cycle_theme() {
+        let config = Config {
+            colors: Some(ColorSupport::TrueColor),
+            theme: ThemeConfig {
+                accent: Some("#0000ff".to_string()),
+                ..ThemeConfig::default()
+            },
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        assert_eq!(app.theme_name, "dark");
+        assert_eq!(app.theme.accent, Color::Rgb(0, 0, 255));
+        let dark_fg = app.theme.fg;
+
+        press(&mut app, KeyCode::Char('c'));
+        assert_eq!(app.theme_name, "light");
+        assert_eq!(app.message(), Some("Theme: light"));
+        assert_ne!(app.theme.fg, dark_fg);
+        assert_eq!(app.theme.accent, Color::Rgb(0, 0, 255));
+
+        press(&mut app, KeyCode::Char('c'));
+        assert_eq!(app.theme_name, "dark");
+        assert_eq!(app.theme.fg, dark_fg);
+    }
+
+    #[test]
+    fn test_held_keys_repeat_only_in_text() {
+        let mut app = grouped_app();
+        let repeat = |code| KeyEvent::new_with_kind(code, KeyModifiers::NONE, KeyEventKind::Repeat);
+
+        // Doubled letters typed quickly all reach the filter
+        press(&mut app, KeyCode::Char('/'));
+        for c in "add".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        app.handle_action(Action::KeyPress(repeat(KeyCode::Char('d'))))
+            .unwrap();
+        assert_eq!(app.session_filter, "addd");
+        press(&mut app, KeyCode::Esc);
+
+        // Outside text, a held `d` confirms nothing
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.input_mode, InputMode::Confirming);
+        app.handle_action(Action::KeyPress(repeat(KeyCode::Char('y'))))
+            .unwrap();
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.input_mode, InputMode::Confirming);
+        assert!(app.take_pending_actions().is_empty());
+    }
+
+    #[test]
+    fn test_read_only_blocks_changes() {
+        let mut app = grouped_app();
+        app.read_only = true;
+        press(&mut app, KeyCode::Char('j'));
+        for key in ['n', 'd', 'R', 'b'] {
+            press(&mut app, KeyCode::Char(key));
+            assert_eq!(app.input_mode, InputMode::Normal);
+        }
+        assert!(app.pending_actions.is_empty());
+
+        // Looking around still works
+        press(&mut app, KeyCode::Char('o'));
+        assert_eq!(app.input_mode, InputMode::Preview);
+    }
+
+    #[test]
+    fn test_focus_session_from_control_socket() {
+        let mut app = grouped_app();
+        app.handle_action(Action::FocusSession("gamma".to_string()))
+            .unwrap();
+        assert_eq!(app.selected_session().unwrap().name, "gamma");
+
+        // IDs work too, and collapsed groups open up
+        app.state.collapsed_groups.insert(StatusGroup::Idle);
+        app.handle_action(Action::FocusSession("$zeta".to_string()))
+            .unwrap();
+        assert_eq!(app.selected_session().unwrap().name, "zeta");
+
+        app.handle_action(Action::FocusSession("omega".to_string()))
+            .unwrap();
+        assert_eq!(app.selected_session().unwrap().name, "zeta");
+        assert_eq!(app.message(), Some("No session named omega"));
+
+        app.handle_action(Action::Refresh).unwrap();
+        assert!(matches!(app.pending_actions.as_slice(), [Action::Refresh]));
+    }
+
+    #[test]
+    fn test_restore_prompt_for_missing_sessions() {
+        let snapshot = |names: &[&str]| Snapshot {
+            version: crate::snapshot::SNAPSHOT_VERSION,
+            taken_at: 0,
+            sessions: names
+                .iter()
+                .map(|name| SessionSnapshot {
+                    name: name.to_string(),
+                    socket: String::new(),
+                    windows: Vec::new(),
+                })
+                .collect(),
+        };
+
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.offer_restore(snapshot(&["alpha", "payments", "search"]));
+        assert!(!app.can_snapshot());
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "alpha",
+            AgentStatus::Idle,
+        )]))
+        .unwrap();
+        assert_eq!(app.input_mode, InputMode::ConfirmingRestore);
+        assert!(!app.can_snapshot());
+        let names: Vec<&str> = app
+            .missing_sessions
+            .iter()
+            .map(|s| s.name.as_str())
+            .collect();
+        assert_eq!(names, ["payments", "search"]);
+
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.can_snapshot());
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::RestoreSessions(sessions)] if sessions.len() == 2
+        ));
+
+        // Declining, or having nothing missing, just carries on
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.offer_restore(snapshot(&["payments"]));
+        app.handle_action(Action::SessionsUpdated(Vec::new()))
+            .unwrap();
+        press(&mut app, KeyCode::Esc);
+        assert!(app.pending_actions.is_empty());
+        assert!(app.missing_sessions.is_empty());
+
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.offer_restore(snapshot(&["alpha"]));
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "alpha",
+            AgentStatus::Idle,
+        )]))
+        .unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+
+        // A read-only instance never offers to change sessions
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.read_only = true;
+        app.offer_restore(snapshot(&["payments"]));
+        app.handle_action(Action::SessionsUpdated(Vec::new()))
+            .unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(!app.can_snapshot());
+    }
+
+    fn digest_app() -> App {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.offer_digest();
+        let mut web = session("web", AgentStatus::Error);
+        web.activity_at = text::unix_now() - 2 * 3600;
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("api", AgentStatus::Busy),
+            web,
+            session("docs", AgentStatus::WaitingForInput),
+        ]))
+        .unwrap();
+        app
+    }
+
+    #[test]
+    fn test_startup_digest_shows_once() {
+        let mut app = digest_app();
+        assert_eq!(app.input_mode, InputMode::StartupDigest);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::ReadQuestions(ids)] if ids == &["$docs", "$web"]
+        ));
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.digest.is_empty());
+        // Later polls never bring it back
+        app.handle_action(Action::SessionsUpdated(app.sessions.clone()))
+            .unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.take_pending_actions().is_empty());
+
+        // Enter jumps to the first listed session
+        let mut app = digest_app();
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.selected_session().unwrap().name, "docs");
+
+        // Nothing to report, or not offered (disabled in the config), shows nothing
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.offer_digest();
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Idle,
+        )]))
+        .unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Error,
+        )]))
+        .unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(grouped_app().digest.is_empty());
+    }
+
+    #[test]
+    fn test_startup_digest_rendering() {
+        let mut app = digest_app();
+        app.handle_action(Action::QuestionsRead(vec![(
+            "$docs".to_string(),
+            "Do you want to make this edit to lib.rs?".to_string(),
+        )]))
+        .unwrap();
+
+        let mut terminal = Terminal::new(TestBackend::new(100, 20)).unwrap();
+        terminal.draw(|f| app.render_digest(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let lines: Vec<String> = (0..20)
+            .map(|y| (0..100).map(|x| buffer[(x, y)].symbol()).collect())
+            .collect();
+        let text = lines.join("\n");
+        assert!(text.contains("2 Sessions Need Attention"), "{}", text);
+        assert!(
+            text.contains("? docs  WaitingForInput for a while"),
+            "{}",
+            text
+        );
+        assert!(
+            text.contains("  Do you want to make this edit to lib.rs?"),
+            "{}",
+            text
+        );
+        assert!(text.contains("✗ web  Error for 2h"), "{}", text);
+        assert!(
+            text.contains("Press Enter to jump to the first"),
+            "{}",
+            text
+        );
+    }
+
+    fn idle_app(action: PolicyAction) -> App {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.policy = IdlePolicy {
+            after_hours: 1.0,
+            action,
+            exempt: vec!["pinned".to_string()],
+            ..IdlePolicy::default()
+        };
+        app
+    }
+
+    fn quiet(name: &str, status: AgentStatus, secs: u64) -> TmuxSession {
+        let mut s = session(name, status);
+        s.current_command = "claude".to_string();
+        s.activity_at = text::unix_now() - secs;
+        s
+    }
+
+    #[test]
+    fn test_idle_policy_flags_and_suggests_cleanup() {
+        let mut app = idle_app(PolicyAction::Flag);
+        let sessions = vec![
+            quiet("old", AgentStatus::Idle, 7200),
+            quiet("pinned", AgentStatus::Idle, 7200),
+            quiet("fresh", AgentStatus::Idle, 60),
+        ];
+        app.handle_action(Action::SessionsUpdated(sessions.clone()))
+            .unwrap();
+        assert_eq!(app.stale.keys().collect::<Vec<_>>(), ["$old"]);
+        assert_eq!(app.input_mode, InputMode::ConfirmingCleanup);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::SampleUsage(_)]
+        ));
+
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::ArchiveAndKill(v)] if v.name == "old"
+        ));
+
+        // The prompt is for startup only; later evaluations just flag
+        app.enforce_policy(text::unix_now(), false);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.pending_actions.is_empty());
+
+        // Output clears the flag at the next poll, without waiting for an evaluation
+        let mut active = sessions;
+        active[0].activity_at = text::unix_now();
+        app.handle_action(Action::SessionsUpdated(active)).unwrap();
+        assert!(app.stale.is_empty());
+    }
+
+    #[test]
+    fn test_idle_policy_kills_once_after_archiving() {
+        let mut app = idle_app(PolicyAction::Kill);
+        app.handle_action(Action::SessionsUpdated(vec![quiet(
+            "old",
+            AgentStatus::Idle,
+            7200,
+        )]))
+        .unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let actions = app.take_pending_actions();
+        let [Action::ArchiveAndKill(verdict), Action::SampleUsage(_)] = actions.as_slice() else {
+            panic!("{:?}", actions);
+        };
+        assert!(app.stale.is_empty());
+
+        // Already being enforced, so not queued again
+        app.enforce_policy(text::unix_now(), false);
+        assert!(app.pending_actions.is_empty());
+
+        // A failed archive leaves the session open and flagged
+        let op = app.start_operation("Archiving");
+        let error = Arc::new(anyhow::anyhow!("disk full"));
+        app.handle_action(Action::SessionArchived(op, verdict.clone(), Err(error)))
+            .unwrap();
+        assert!(app.operations.is_empty());
+        assert!(app.stale.contains_key("$old"));
+        assert_eq!(app.message(), Some("disk full"));
+
+        // A read-only instance only flags
+        let mut app = idle_app(PolicyAction::Kill);
+        app.read_only = true;
+        app.handle_action(Action::SessionsUpdated(vec![quiet(
+            "old",
+            AgentStatus::Idle,
+            7200,
+        )]))
+        .unwrap();
+        assert!(app.pending_actions.is_empty());
+        assert!(app.stale.contains_key("$old"));
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    fn type_name(app: &mut App, name: &str) {
+        press(app, KeyCode::Char('n'));
+        for c in name.chars() {
+            press(app, KeyCode::Char(c));
+        }
+        press(app, KeyCode::Enter);
+    }
+
+    fn worktree(branch: &str) -> Worktree {
+        Worktree {
+            repo: PathBuf::from("/src/api"),
+            path: PathBuf::from("/src/api-worktrees").join(branch),
+            branch: branch.to_string(),
+            new_branch: true,
+        }
+    }
+
+    #[test]
+    fn test_worktree_from_selected_repo() {
+        let config = Config {
+            worktrees: WorktreeConfig {
+                base: Some(PathBuf::from("/wt")),
+                repo: Some(PathBuf::from("/src/fallback")),
+            },
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        let mut api = session("api", AgentStatus::Idle);
+        api.current_path = "/src/api/server".to_string();
+        api.git = Some(crate::git::GitInfo {
+            head: crate::git::GitHead::Branch("main".to_string()),
+            dirty: false,
+        });
+        app.handle_action(Action::SessionsUpdated(vec![api]))
+            .unwrap();
+        app.take_pending_actions();
+
+        press(&mut app, KeyCode::Char('G'));
+        assert_eq!(app.input_mode, InputMode::CreatingWorktree);
+        for c in "feat/login x".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.input_buffer.as_str(), "feat/loginx");
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Enter);
+        // The session is named after the branch; the launcher doesn't ask for a directory
+        assert_eq!(app.input_mode, InputMode::ChoosingLauncher);
+        assert_eq!(app.create_name.as_deref(), Some("feat-login"));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let request = WorktreeRequest {
+            branch: "feat/login".to_string(),
+            repo: PathBuf::from("/src/api/server"),
+            base: Some(PathBuf::from("/wt")),
+            session: "feat-login".to_string(),
+        };
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::CreateWorktree(r, Some(launcher))]
+                if *r == request && launcher.name == "Resume Claude"
+        ));
+
+        // Only a created session gets its worktree recorded
+        let op = app.start_operation("Creating worktree");
+        let error = anyhow::anyhow!("'feat/login' isn't a valid branch name");
+        app.handle_action(Action::WorktreeCreated(op, Err(Arc::new(error))))
+            .unwrap();
+        assert!(app.operations.is_empty());
+        assert!(app.state.worktrees.is_empty());
+        assert!(app.message().unwrap().contains("valid branch name"));
+
+        let op = app.start_operation("Creating worktree");
+        let created = session("feat-login", AgentStatus::Unknown);
+        app.handle_action(Action::WorktreeCreated(
+            op,
+            Ok((created, worktree("feat/login"))),
+        ))
+        .unwrap();
+        assert!(app.operations.is_empty());
+        assert_eq!(app.state.worktrees["feat-login"], worktree("feat/login"));
+        assert_eq!(app.selected_session().unwrap().name, "feat-login");
+        assert_eq!(
+            app.state.launcher("feat-login").unwrap().name,
+            "Resume Claude"
+        );
+
+        // Outside a repository the configured one is used; Esc abandons it
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "notes",
+            AgentStatus::Idle,
+        )]))
+        .unwrap();
+        app.take_pending_actions();
+        press(&mut app, KeyCode::Char('G'));
+        assert_eq!(
+            app.worktree_target,
+            Some((PathBuf::from("/src/fallback"), None))
+        );
+        press(&mut app, KeyCode::Char('x'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.worktree_target, None);
+        assert!(app.pending_actions.is_empty());
+    }
+
+    #[test]
+    fn test_deleting_worktree_session_offers_removal() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.state
+            .worktrees
+            .insert("feat-login".to_string(), worktree("feat/login"));
+        app.state
+            .worktrees
+            .insert("fix-typo".to_string(), worktree("fix/typo"));
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("feat-login", AgentStatus::Idle),
+            session("fix-typo", AgentStatus::Idle),
+            session("plain", AgentStatus::Idle),
+        ]))
+        .unwrap();
+        app.take_pending_actions();
+        app.select_session_id("$feat-login");
+
+        // The session goes first; the worktree is a separate question
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.input_mode, InputMode::ConfirmingWorktreeRemoval);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::DeleteSession(id)] if id == "$feat-login"
+        ));
+        // Pressed again after a while, not held
+        app.repeats = RepeatLimiter::default();
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::RemoveWorktree(w)] if *w == worktree("feat/login")
+        ));
+        assert!(!app.state.worktrees.contains_key("feat-login"));
+
+        // Declining keeps it on disk and forgets it
+        app.select_session_id("$fix-typo");
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('y'));
+        app.take_pending_actions();
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.pending_actions.is_empty());
+        assert!(app.state.worktrees.is_empty());
+        assert!(app.message().unwrap().starts_with("Kept worktree"));
+
+        // Sessions without a worktree are deleted as before
+        app.select_session_id("$plain");
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.worktree_removal, None);
+    }
+
+    #[test]
+    fn test_custom_commands() {
+        use crate::custom_commands::RunMode;
+        let command = |name: &str, key| CustomCommand {
+            name: name.to_string(),
+            run: "cargo test".to_string(),
+            mode: RunMode::Window,
+            key,
+        };
+        let config = Config {
+            commands: vec![command("tests", Some('L')), command("log", Some('j'))],
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        let mut api = session("api", AgentStatus::Idle);
+        api.current_path = "/work/api".to_string();
+        app.handle_action(Action::SessionsUpdated(vec![
+            api,
+            session("web", AgentStatus::Idle),
+        ]))
+        .unwrap();
+        let ran = |app: &mut App| match app.take_pending_actions().as_slice() {
+            [Action::RunCommand(command, context)] => {
+                Some((command.name.clone(), context.session_name.clone()))
+            }
+            _ => None,
+        };
+
+        // Picked from the menu, with the selected session as context
+        press(&mut app, KeyCode::Char(':'));
+        assert_eq!(app.input_mode, InputMode::ChoosingCommand);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(ran(&mut app), None);
+        press(&mut app, KeyCode::Char(':'));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(ran(&mut app), Some(("log".to_string(), "api".to_string())));
+
+        // Bound keys run them directly, unless agent-rusty uses the key itself
+        press(&mut app, KeyCode::Char('L'));
+        assert_eq!(
+            ran(&mut app),
+            Some(("tests".to_string(), "api".to_string()))
+        );
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(ran(&mut app), None);
+        assert_eq!(app.selected_session().unwrap().name, "web");
+
+        // Read-only instances run none, from the menu or by key
+        app.read_only = true;
+        press(&mut app, KeyCode::Char(':'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        press(&mut app, KeyCode::Char('L'));
+        assert_eq!(ran(&mut app), None);
+        app.read_only = false;
+
+        // Background outcomes end up in the footer, failures with their output
+        let op = app.start_operation("Running 'tests'");
+        let output = "running 3 tests\ntest result: ok. 3 passed\n".to_string();
+        app.handle_action(Action::CommandFinished(op, "tests".to_string(), Ok(output)))
+            .unwrap();
+        assert_eq!(
+            app.message(),
+            Some("'tests' done: test result: ok. 3 passed")
+        );
+        assert!(app.operations.is_empty());
+        let error = anyhow::anyhow!("error: 1 test failed")
+            .context("exited with exit status: 101")
+            .context("'tests' failed in 'api'");
+        app.handle_action(Action::CommandFinished(
+            op,
+            "tests".to_string(),
+            Err(Arc::new(error)),
+        ))
+        .unwrap();
+        assert_eq!(app.message(), Some("'tests' failed in 'api'"));
+        assert_eq!(
+            app.current_error_details().unwrap().chain.last().unwrap(),
+            "error: 1 test failed"
+        );
+
+        // Without any, the menu says why it doesn't open
+        let mut app = grouped_app();
+        press(&mut app, KeyCode::Char(':'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(app.message().is_some());
+    }
+
+    #[test]
+    fn test_attached_clients() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut api = session("api", AgentStatus::Idle);
+        api.attached_clients = 2;
+        app.handle_action(Action::SessionsUpdated(vec![api]))
+            .unwrap();
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::ListClients(id)] if id == "$api"
+        ));
+
+        let client = |name: &str, width, height| AttachedClient {
+            name: name.to_string(),
+            width,
+            height,
+            termname: "xterm".to_string(),
+        };
+        let clients = vec![client("/dev/pts/3", 212, 50), client("/dev/pts/7", 80, 24)];
+        app.handle_action(Action::ClientsListed("$api".to_string(), Ok(clients)))
+            .unwrap();
+        let detail = render_detail(&app, 40, 20);
+        let at = detail
+            .iter()
+            .position(|l| l.contains("Clients: 2"))
+            .unwrap();
+        assert_eq!(detail[at + 1], "│  /dev/pts/3 212x50 xterm             │");
+        assert_eq!(detail[at + 2], "│  /dev/pts/7 80x24 xterm              │");
+
+        // Still under the count when a note pushes it down
+        app.state.set_note("api", "waiting on PR");
+        let detail = render_detail(&app, 40, 20);
+        let at = detail
+            .iter()
+            .position(|l| l.contains("Clients: 2"))
+            .unwrap();
+        assert_eq!(detail[at + 1], "│  /dev/pts/3 212x50 xterm             │");
+        assert!(detail[at + 3].contains("Last attached:"), "{:?}", detail);
+
+        // C picks one to detach, except in read-only instances
+        app.read_only = true;
+        press(&mut app, KeyCode::Char('C'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        app.read_only = false;
+        press(&mut app, KeyCode::Char('C'));
+        assert_eq!(app.input_mode, InputMode::ChoosingClient);
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::DetachClient(id, name)] if id == "$api" && name == "/dev/pts/7"
+        ));
+        app.handle_action(Action::ClientDetached(
+            "$api".to_string(),
+            "/dev/pts/7".to_string(),
+            Ok(()),
+        ))
+        .unwrap();
+        assert_eq!(
+            app.notifications.latest().unwrap().text,
+            "Detached /dev/pts/7"
+        );
+
+        // Nobody attached: nothing to list or pick
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Idle,
+        )]))
+        .unwrap();
+        app.take_pending_actions();
+        assert!(app.clients.is_none());
+        press(&mut app, KeyCode::Char('C'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(
+            app.notifications.latest().unwrap().text,
+            "Nothing is attached to 'api'"
+        );
+    }
+
+    #[test]
+    fn test_window_tree() {
+        let window = |index, name: &str, active, command: &str| TmuxWindow {
+            index,
+            name: name.to_string(),
+            active,
+            panes: 1,
+            command: command.to_string(),
+        };
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("api", AgentStatus::Idle),
+            session("web", AgentStatus::Idle),
+        ]))
+        .unwrap();
+
+        // Right lists the selected session's windows under it
+        press(&mut app, KeyCode::Right);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::ListWindowTree(id)] if id == "$api"
+        ));
+        let windows = vec![
+            window(0, "claude", false, "node"),
+            window(2, "logs", true, "tail"),
+        ];
+        app.handle_action(Action::WindowTreeListed("$api".to_string(), Ok(windows)))
+            .unwrap();
+        assert_eq!(
+            render_list(&mut app, 30, 6)[1..5],
+            [
+                "│▶ ● 1 api                   │",
+                "│   ├ 0: claude  node        │",
+                "│   └ 2: logs*  tail         │",
+                "│  ● 2 web                   │",
             ]
-        } else {
-            vec![
-                Line::from(Span::styled(
-                    "No session selected",
-                    Style::default().fg(self.theme.dim),
-                )),
-                Line::from(""),
-                Line::from(Span::styled(
-                    "Press 'n' to create a new session",
-                    Style::default().fg(self.theme.dim),
-                )),
+        );
+
+        // A window row attaches to that window, and stays selected across polls
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.selected_window().unwrap().name, "logs");
+        assert_eq!(app.selected_session().unwrap().name, "api");
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("api", AgentStatus::Idle),
+            session("web", AgentStatus::Idle),
+        ]))
+        .unwrap();
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::ListWindowTree(id)] if id == "$api"
+        ));
+        assert_eq!(app.selected_window().unwrap().name, "logs");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::SelectWindow(target), Action::AttachSession(id, false)]
+                if target == "$api:2" && id == "$api"
+        ));
+
+        // h collapses back onto the session
+        press(&mut app, KeyCode::Char('h'));
+        assert!(app.sessions.iter().all(|s| s.window_tree.is_none()));
+        assert_eq!(app.rows.len(), 2);
+        assert_eq!(app.selected_session().unwrap().name, "api");
+        assert!(app.selected_window().is_none());
+    }
+
+    #[test]
+    fn test_window_picker() {
+        let window = |index, name: &str, active| TmuxWindow {
+            index,
+            name: name.to_string(),
+            active,
+            panes: 1,
+            command: String::new(),
+        };
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut api = session("api", AgentStatus::Idle);
+        api.windows = 3;
+        app.handle_action(Action::SessionsUpdated(vec![
+            api,
+            session("web", AgentStatus::Idle),
+        ]))
+        .unwrap();
+
+        // Windows are fetched first, opening on the current one
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::ListWindows(id)] if id == "$api"
+        ));
+        let windows = vec![
+            window(0, "claude", false),
+            window(1, "logs", true),
+            window(3, "tests", false),
+        ];
+        app.handle_action(Action::WindowsListed("$api".to_string(), Ok(windows)))
+            .unwrap();
+        assert_eq!(app.input_mode, InputMode::ChoosingWindow);
+        assert_eq!(app.window_selected, 1);
+        let mut terminal = Terminal::new(TestBackend::new(60, 20)).unwrap();
+        terminal.draw(|f| app.render_window_picker(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..20)
+            .flat_map(|y| (0..60).map(move |x| (x, y)))
+            .map(|pos| buffer[pos].symbol())
+            .collect();
+        assert!(screen.contains("Attach to 'api'"), "{}", screen);
+        assert!(screen.contains("▶ 1: logs*"), "{}", screen);
+
+        // The window is selected before attaching
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::SelectWindow(target), Action::AttachSession(id, false)]
+                if target == "$api:3" && id == "$api"
+        ));
+
+        // Esc attaches to whatever window the session shows
+        press(&mut app, KeyCode::Enter);
+        let windows = vec![window(0, "claude", true), window(1, "logs", false)];
+        app.handle_action(Action::WindowsListed("$api".to_string(), Ok(windows)))
+            .unwrap();
+        press(&mut app, KeyCode::Esc);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::ListWindows(_), Action::AttachSession(id, false)] if id == "$api"
+        ));
+
+        // Shift-Enter skips the picker, as do sessions with one window
+        let shift_enter = KeyEvent::new(KeyCode::Enter, KeyModifiers::SHIFT);
+        app.handle_action(Action::KeyPress(shift_enter)).unwrap();
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::AttachSession(api, false), Action::AttachSession(web, false)]
+                if api == "$api" && web == "$web"
+        ));
+
+        // Unless turned off in the config
+        let config = Config {
+            attach: crate::config::AttachConfig {
+                pick_window: false,
+                ..Default::default()
+            },
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        let mut api = session("api", AgentStatus::Idle);
+        api.windows = 3;
+        app.handle_action(Action::SessionsUpdated(vec![api]))
+            .unwrap();
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::AttachSession(_, false)]
+        ));
+    }
+
+    #[test]
+    fn test_launcher_menu() {
+        let config = Config {
+            launchers: vec![Launcher {
+                name: "Aider".to_string(),
+                command: Some("aider --model sonnet".to_string()),
+                ask_dir: true,
+                ask_command: false,
+                resume: false,
+            }],
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        let names: Vec<&str> = app.launchers.iter().map(|l| l.name.as_str()).collect();
+        assert_eq!(
+            names,
+            [
+                "Aider",
+                "Claude Code",
+                "Resume Claude",
+                "Custom command",
+                "Shell"
             ]
+        );
+
+        // The name comes first, then the menu
+        type_name(&mut app, "api");
+        assert_eq!(app.input_mode, InputMode::ChoosingLauncher);
+        assert_eq!(app.create_name.as_deref(), Some("api"));
+        press(&mut app, KeyCode::Char('k'));
+        assert_eq!(app.launcher_selected, 0);
+        for _ in 0..6 {
+            press(&mut app, KeyCode::Char('j'));
+        }
+        assert_eq!(app.launcher_selected, 4);
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Char('k'));
+        }
+        press(&mut app, KeyCode::Enter);
+
+        // Then the directory, the current one unless changed
+        assert_eq!(app.input_mode, InputMode::CreatingDir);
+        let cwd = std::env::current_dir().unwrap();
+        assert_eq!(app.input_buffer.as_str(), cwd.display().to_string());
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::CreateSession(NewSession { name, launcher: Some(launcher), cwd: Some(dir) })]
+                if name == "api" && launcher.command.as_deref() == Some("claude") && *dir == cwd
+        ));
+
+        // Recorded once the session exists, for restarting it later
+        assert!(app.state.launcher("api").is_none());
+        let op = app.start_operation("Creating");
+        app.handle_action(Action::SessionCreated(
+            op,
+            Ok(session("api", AgentStatus::Unknown)),
+        ))
+        .unwrap();
+        assert_eq!(app.state.launcher("api").unwrap().name, "Claude Code");
+        assert_eq!(app.state.descriptions.get("api"), Some("claude"));
+        app.take_pending_actions();
+
+        // Esc in the menu abandons the session
+        type_name(&mut app, "web");
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.create_name, None);
+        assert!(app.pending_actions.is_empty());
+    }
+
+    #[test]
+    fn test_custom_command() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        type_name(&mut app, "api");
+        while !app.launchers[app.launcher_selected].ask_command {
+            press(&mut app, KeyCode::Char('j'));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::CreatingCommand);
+        assert!(app.input_buffer.is_empty());
+        for c in "aider --yes".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::CreatingDir);
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::CreateSession(NewSession { name, launcher: Some(launcher), .. })]
+                if name == "api" && launcher.command.as_deref() == Some("aider --yes")
+        ));
+
+        // Its command exiting takes the session with it, which is worth a warning
+        app.handle_action(Action::SessionCreated(
+            0,
+            Ok(session("api", AgentStatus::Unknown)),
+        ))
+        .unwrap();
+        let sessions: Vec<TmuxSession> = app
+            .sessions
+            .iter()
+            .filter(|s| s.name != "api")
+            .cloned()
+            .collect();
+        app.handle_action(Action::SessionsUpdated(sessions.clone()))
+            .unwrap();
+        assert_eq!(app.message(), Some("Session 'api' created"));
+        for (_, _, at) in app.launched.values_mut() {
+            *at -= LAUNCH_GRACE;
+        }
+        app.handle_action(Action::SessionsUpdated(sessions))
+            .unwrap();
+        assert_eq!(
+            app.message(),
+            Some("Session 'api' ended right away: 'aider --yes' exited")
+        );
+        assert!(app.launched.is_empty());
+
+        // Or before it could even be listed
+        app.creating.insert(
+            "web".to_string(),
+            Launcher {
+                command: Some("false".to_string()),
+                ..app.launchers[0].clone()
+            },
+        );
+        let error = anyhow::Error::from(AgentRustyError::SessionNotFound("web".to_string()))
+            .context("Failed to create session 'web'");
+        app.handle_action(Action::SessionCreated(1, Err(Arc::new(error))))
+            .unwrap();
+        assert_eq!(
+            app.message(),
+            Some("Session 'web' ended right away: 'false' exited")
+        );
+    }
+
+    #[test]
+    fn test_template() {
+        use crate::template::TemplateSession;
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        press(&mut app, KeyCode::Char('A'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.message(), Some("No [[template]] in the config"));
+
+        let config = Config {
+            templates: vec![Template {
+                name: "web".to_string(),
+                sessions: ["{project}-agent", "{project}-server", "{project}-tests"]
+                    .iter()
+                    .map(|name| TemplateSession {
+                        name: name.to_string(),
+                        dir: None,
+                        command: Some("npm test".to_string()),
+                    })
+                    .collect(),
+            }],
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        press(&mut app, KeyCode::Char('A'));
+        assert_eq!(app.input_mode, InputMode::ChoosingTemplate);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::TemplateDir);
+        let root =
+            std::env::temp_dir().join(format!("agent-rusty-app-template-{}", std::process::id()));
+        let project = root.join("shop");
+        std::fs::create_dir_all(&project).unwrap();
+        app.input_buffer.set(project.display().to_string());
+        press(&mut app, KeyCode::Enter);
+        std::fs::remove_dir_all(&root).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let Some(Action::CreateSessions(template, sessions)) =
+            app.take_pending_actions().into_iter().next()
+        else {
+            panic!("no sessions to create");
+        };
+        assert_eq!(template, "web");
+        let names: Vec<&str> = sessions.iter().map(|s| s.name.as_str()).collect();
+        assert_eq!(names, ["shop-agent", "shop-server", "shop-tests"]);
+
+        // Each is reported, and the rest are still created
+        let taken =
+            anyhow::Error::from(AgentRustyError::DuplicateSession("shop-server".to_string()));
+        let missing = anyhow::anyhow!("'/work/shop/tests' is not a directory");
+        app.handle_action(Action::TemplateCreated(
+            0,
+            template,
+            vec![
+                (
+                    "shop-agent".to_string(),
+                    Ok(session("shop-agent", AgentStatus::Unknown)),
+                ),
+                ("shop-server".to_string(), Err(Arc::new(taken))),
+                ("shop-tests".to_string(), Err(Arc::new(missing))),
+            ],
+        ))
+        .unwrap();
+        assert_eq!(
+            app.message(),
+            Some(
+                "Created 1 of 3 sessions from 'web'; shop-server already exists; \
+                 shop-tests: '/work/shop/tests' is not a directory"
+            )
+        );
+        assert_eq!(app.notifications.latest().unwrap().level, Level::Warning);
+        assert_eq!(app.selected_session().unwrap().name, "shop-agent");
+        assert!(app.creating.is_empty());
+        assert_eq!(
+            app.state.launcher("shop-agent").unwrap().command.as_deref(),
+            Some("npm test")
+        );
+    }
+
+    #[test]
+    fn test_launcher_asks_for_a_directory() {
+        let config = Config {
+            launchers: vec![Launcher {
+                name: "Aider".to_string(),
+                command: Some("aider".to_string()),
+                ask_dir: true,
+                ask_command: false,
+                resume: false,
+            }],
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        type_name(&mut app, "api");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::PickingDir);
+        // Tab types the path instead, starting from where the picker is
+        press(&mut app, KeyCode::Tab);
+        assert_eq!(app.input_mode, InputMode::CreatingDir);
+        assert!(!app.input_buffer.is_empty());
+
+        app.input_buffer.set("/no/such/dir");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::CreatingDir);
+        assert!(app.message().unwrap().contains("not a directory"));
+
+        // Esc goes back to the menu with the name kept
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::ChoosingLauncher);
+        press(&mut app, KeyCode::Enter);
+        press(&mut app, KeyCode::Tab);
+        app.input_buffer.clear();
+        app.take_pending_actions();
+        app.handle_action(Action::Paste(
+            std::env::temp_dir().display().to_string() + "\nignored",
+        ))
+        .unwrap();
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        let tmp = std::env::temp_dir();
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::CreateSession(NewSession { name, cwd: Some(dir), .. })]
+                if name == "api" && *dir == tmp
+        ));
+    }
+
+    #[test]
+    fn test_directory_picker() {
+        let root =
+            std::env::temp_dir().join(format!("agent-rusty-app-picker-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("api")).unwrap();
+        let config = Config {
+            launchers: vec![Launcher {
+                name: "Aider".to_string(),
+                command: Some("aider".to_string()),
+                ask_dir: true,
+                ask_command: false,
+                resume: false,
+            }],
+            ..Config::default()
+        };
+        let mut state = PersistedState::default();
+        state.last_dir = Some(root.clone());
+        let mut app = App::new(&config, state);
+        // Listings are read by the main loop; hand them over like it does
+        let serve = |app: &mut App| {
+            let reads: Vec<PathBuf> = app
+                .take_pending_actions()
+                .into_iter()
+                .filter_map(|action| match action {
+                    Action::ReadDir(dir) => Some(dir),
+                    _ => None,
+                })
+                .collect();
+            for dir in reads {
+                let entries = tokio::runtime::Runtime::new()
+                    .unwrap()
+                    .block_on(crate::dir_picker::read_dir(&dir))
+                    .map_err(|e| Arc::new(anyhow::Error::from(e)));
+                app.handle_action(Action::DirRead(dir, entries)).unwrap();
+            }
         };
 
-        let detail = Paragraph::new(content).block(
-            Block::default()
-                .title(" Details ")
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(self.theme.dim)),
+        // Opens where a directory was last chosen
+        type_name(&mut app, "api");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::PickingDir);
+        serve(&mut app);
+        let screen = render_screen(&mut app, 80, 24);
+        assert!(
+            screen
+                .iter()
+                .any(|line| line.contains("▶ [use this directory]"))
+        );
+        assert!(screen.iter().any(|line| line.contains("  api/")));
+
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+        serve(&mut app);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::CreateSession(NewSession { name, cwd: Some(dir), .. })]
+                if name == "api" && *dir == root.join("api")
+        ));
+        assert_eq!(app.state.last_dir, Some(root.join("api")));
+
+        // The skeleton root is picked the same way, and copied at once
+        press(&mut app, KeyCode::Char('B'));
+        serve(&mut app);
+        press(&mut app, KeyCode::Char('h'));
+        serve(&mut app);
+        press(&mut app, KeyCode::Char('k'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.skeleton_root, root);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::CopySkeleton(dir)] if *dir == root
+        ));
+        press(&mut app, KeyCode::Char('B'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[test]
+    fn test_ctrl_c_quits_from_any_mode() {
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        for mode in [
+            InputMode::Normal,
+            InputMode::Creating,
+            InputMode::ChoosingLauncher,
+            InputMode::ChoosingWindow,
+            InputMode::ChoosingCommand,
+            InputMode::ChoosingTemplate,
+            InputMode::TemplateDir,
+            InputMode::CreatingDir,
+            InputMode::CreatingWorktree,
+            InputMode::Confirming,
+            InputMode::ConfirmingWorktreeRemoval,
+            InputMode::ConfirmingCleanup,
+            InputMode::EditingNote,
+            InputMode::ErrorDetails,
+        ] {
+            let mut app = grouped_app();
+            app.input_mode = mode;
+            // Agents are busy, so the first one asks
+            assert!(!app.handle_action(Action::KeyPress(ctrl_c)).unwrap());
+            assert_eq!(app.input_mode, InputMode::ConfirmingQuit);
+            assert!(app.handle_action(Action::KeyPress(ctrl_c)).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_quit_asks_while_agents_work() {
+        let mut app = grouped_app();
+        assert!(
+            !app.handle_action(Action::KeyPress(KeyEvent::from(KeyCode::Char('q'))))
+                .unwrap()
+        );
+        assert_eq!(app.input_mode, InputMode::ConfirmingQuit);
+        let screen = render_screen(&mut app, 80, 24).join("\n");
+        assert!(screen.contains("Agents still mid-task (2):"), "{}", screen);
+        assert!(screen.contains("? gamma"), "{}", screen);
+        assert!(screen.contains("keep running in tmux"), "{}", screen);
+
+        // Esc goes back to where quitting started
+        app.input_mode = InputMode::EditingNote;
+        let ctrl_c = KeyEvent::new(KeyCode::Char('c'), KeyModifiers::CONTROL);
+        app.handle_action(Action::KeyPress(ctrl_c)).unwrap();
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::EditingNote);
+        app.input_mode = InputMode::Normal;
+
+        press(&mut app, KeyCode::Char('q'));
+        assert!(
+            app.handle_action(Action::KeyPress(KeyEvent::from(KeyCode::Char('y'))))
+                .unwrap()
+        );
+
+        // Nothing to lose, or asked not to: quit at once
+        let mut app = grouped_app();
+        app.confirm_quit = false;
+        assert!(
+            app.handle_action(Action::KeyPress(KeyEvent::from(KeyCode::Char('q'))))
+                .unwrap()
+        );
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Idle,
+        )]))
+        .unwrap();
+        assert!(
+            app.handle_action(Action::KeyPress(KeyEvent::from(KeyCode::Char('q'))))
+                .unwrap()
         );
-        frame.render_widget(detail, area);
     }
 
-    fn render_footer(&self, frame: &mut Frame, area: Rect) {
-        let help_text = if self.mcp_mode {
-            " MCP Mode │ Space: Toggle │ Esc: Exit "
-        } else {
-            " q: Quit │ j/k: Navigate │ Enter: Attach │ n: New │ d: Delete │ y: Copy skeleton │ M: MCP "
+    #[test]
+    fn test_ctrl_z_suspends_from_any_mode() {
+        let mut app = grouped_app();
+        let ctrl_z = KeyEvent::new(KeyCode::Char('z'), KeyModifiers::CONTROL);
+        for mode in [InputMode::Normal, InputMode::Creating] {
+            app.input_mode = mode;
+            app.input_buffer.set("api");
+            app.handle_action(Action::KeyPress(ctrl_z)).unwrap();
+            assert!(matches!(
+                app.take_pending_actions().as_slice(),
+                [Action::Suspend]
+            ));
+            // Nothing typed so far is lost
+            assert_eq!(app.input_mode, mode);
+            assert_eq!(app.input_buffer.as_str(), "api");
+        }
+    }
+
+    fn resize(app: &mut App, terminal: &mut Terminal<TestBackend>, width: u16, height: u16) {
+        terminal.backend_mut().resize(width, height);
+        app.handle_action(Action::Resize(width, height)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+    }
+
+    #[test]
+    fn test_render_survives_shrinking_and_growing() {
+        let mut terminal = Terminal::new(TestBackend::new(120, 40)).unwrap();
+        for display in [ListDisplay::Grouped, ListDisplay::Flat] {
+            let mut app = grouped_app();
+            app.state.list_display = display;
+            terminal.draw(|f| app.render(f)).unwrap();
+            app.report_error(&anyhow::anyhow!("exit 1").context("Failed to attach"));
+            press(&mut app, KeyCode::Char('e'));
+            assert_eq!(app.input_mode, InputMode::ErrorDetails);
+            for (width, height) in [(40, 12), (10, 3), (1, 1), (0, 0), (200, 60), (80, 24)] {
+                resize(&mut app, &mut terminal, width, height);
+                assert_eq!(app.area, Rect::new(0, 0, width, height));
+            }
+            // The selection survives the round trip
+            assert!(app.selected_session().is_some());
+
+            press(&mut app, KeyCode::Esc);
+            press(&mut app, KeyCode::Char('n'));
+            assert_eq!(app.input_mode, InputMode::Creating);
+            for (width, height) in [(12, 5), (2, 2), (120, 40)] {
+                resize(&mut app, &mut terminal, width, height);
+            }
+            press(&mut app, KeyCode::Esc);
+        }
+    }
+
+    #[test]
+    fn test_resize_clamps_preview_viewport() {
+        let mut app = grouped_app();
+        let mut terminal = Terminal::new(TestBackend::new(80, 24)).unwrap();
+        press(&mut app, KeyCode::Char('o'));
+        let id = app.preview.as_ref().unwrap().session_id.clone();
+        let content: Vec<String> = (0..100).map(|i| format!("line {}", i)).collect();
+        app.handle_action(Action::PaneCaptured(id, content.join("\n")))
+            .unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+
+        // Scrolled to the top, so shrinking must not move the view
+        press(&mut app, KeyCode::Char('g'));
+        resize(&mut app, &mut terminal, 80, 10);
+        let viewport = &app.preview.as_ref().unwrap().viewport;
+        assert_eq!(viewport.offset(), 0);
+
+        // Following keeps the last line at the bottom at any height
+        press(&mut app, KeyCode::Char('G'));
+        for height in [10, 50, 4, 2, 24] {
+            resize(&mut app, &mut terminal, 80, height);
+            let viewport = &app.preview.as_ref().unwrap().viewport;
+            assert!(viewport.is_following());
+            assert_eq!(
+                viewport.offset(),
+                100usize.saturating_sub(preview_viewport_height(app.area))
+            );
+        }
+
+        // Growing past the content pulls a scrolled view back to the top
+        press(&mut app, KeyCode::Char('k'));
+        resize(&mut app, &mut terminal, 80, 200);
+        assert_eq!(app.preview.as_ref().unwrap().viewport.offset(), 0);
+    }
+
+    fn render_detail(app: &App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal
+            .draw(|f| app.render_detail_pane(f, f.area()))
+            .unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_detail_shows_claude_conversation() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut api = session("api", AgentStatus::Idle);
+        api.current_command = "claude".to_string();
+        api.claude = Some(ClaudeMeta {
+            session_id: "3f2c9a1e".to_string(),
+            model: Some("claude-sonnet-4-5".to_string()),
+            started_at: Some(text::unix_now() - 7200),
+        });
+        // The directory's last conversation, but no Claude running now
+        let mut shell = api.clone();
+        shell.id = "$shell".to_string();
+        shell.name = "shell".to_string();
+        shell.current_command = "zsh".to_string();
+        app.handle_action(Action::SessionsUpdated(vec![api, shell]))
+            .unwrap();
+
+        let lines = render_detail(&app, 60, 20).join("\n");
+        assert!(lines.contains("Conversation: 3f2c9a1e"), "{}", lines);
+        assert!(lines.contains("Model: claude-sonnet-4-5"));
+        assert!(lines.contains("Started: 2h 0m ago"));
+
+        press(&mut app, KeyCode::Char('j'));
+        let lines = render_detail(&app, 60, 20).join("\n");
+        assert!(lines.contains("Name: shell"));
+        assert!(!lines.contains("Conversation:"), "{}", lines);
+    }
+
+    #[test]
+    fn test_detail_shows_aider_model_and_cost() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut aider = session("aider", AgentStatus::Idle);
+        aider.current_command = "aider".to_string();
+        aider.aider = Some(AiderInfo {
+            model: Some("gpt-4.1".to_string()),
+            cost: None,
+        });
+        app.handle_action(Action::SessionsUpdated(vec![aider]))
+            .unwrap();
+        let lines = render_detail(&app, 60, 20).join("\n");
+        assert!(lines.contains("Model: gpt-4.1"), "{}", lines);
+        assert!(lines.contains("Spent: not announced yet"), "{}", lines);
+    }
+
+    #[test]
+    fn test_note_editing_and_rendering() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Idle,
+        )]))
+        .unwrap();
+
+        press(&mut app, KeyCode::Char('N'));
+        assert_eq!(app.input_mode, InputMode::EditingNote);
+        for c in "waiting on PR".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        for c in "resume after".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        let save = KeyEvent::new(KeyCode::Char('s'), KeyModifiers::CONTROL);
+        app.handle_action(Action::KeyPress(save)).unwrap();
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.state.note("api"), Some("waiting on PR\nresume after"));
+
+        // Right under the status line, wrapped to the pane
+        let detail = render_detail(&app, 16, 17);
+        assert!(detail[3].contains("Status:"), "{:?}", detail);
+        assert_eq!(detail[4], "│Note:         │");
+        assert_eq!(detail[5], "│  waiting on  │");
+        assert_eq!(detail[6], "│  PR          │");
+        assert_eq!(detail[7], "│  resume after│");
+        assert!(detail[8].contains("Created:"), "{:?}", detail);
+        assert_eq!(render_list(&mut app, 20, 3)[1], "│▶ ● 1 api ✎       │");
+
+        // Esc saves too
+        press(&mut app, KeyCode::Char('N'));
+        assert_eq!(app.input_buffer.as_str(), "waiting on PR\nresume after");
+        press(&mut app, KeyCode::Char('!'));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.state.note("api"), Some("waiting on PR\nresume after!"));
+
+        // Reopening starts from the saved text; Ctrl-D deletes it
+        press(&mut app, KeyCode::Char('N'));
+        let delete = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        app.handle_action(Action::KeyPress(delete)).unwrap();
+        assert_eq!(app.state.note("api"), None);
+    }
+
+    #[test]
+    fn test_delete_offers_to_drop_the_note() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("api", AgentStatus::Idle),
+            session("web", AgentStatus::Idle),
+        ]))
+        .unwrap();
+        app.state.set_note("api", "keep me");
+        app.state.set_note("web", "drop me");
+
+        // 'y' deletes the session but keeps its note
+        press(&mut app, KeyCode::Char('d'));
+        assert!(
+            render_screen(&mut app, 100, 40)
+                .iter()
+                .any(|l| l.contains("It has a note: 'x' deletes it too"))
+        );
+        press(&mut app, KeyCode::Char('y'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::DeleteSession(id)] if id == "$api"
+        ));
+        assert_eq!(app.state.note("api"), Some("keep me"));
+
+        // 'x' deletes both
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('d'));
+        press(&mut app, KeyCode::Char('x'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::DeleteSession(id)] if id == "$web"
+        ));
+        assert_eq!(app.state.note("web"), None);
+    }
+
+    #[test]
+    fn test_descriptions() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut api = session("api", AgentStatus::Idle);
+        api.start_command = "claude".to_string();
+        api.start_path = "/work/api".to_string();
+        app.handle_action(Action::SessionsUpdated(vec![
+            api,
+            session("web", AgentStatus::Idle),
+        ]))
+        .unwrap();
+
+        // Derived when first seen, and shown under the name
+        assert_eq!(
+            app.state.descriptions.get("api"),
+            Some("claude in /work/api")
+        );
+        let list = render_list(&mut app, 30, 5);
+        assert_eq!(list[1], "│▶ ● 1 api                   │");
+        assert_eq!(list[2], "│    claude in /work/api     │");
+        assert_eq!(list[3], "│  ● 2 web                   │");
+        let detail = render_detail(&app, 40, 6);
+        assert_eq!(detail[2], "│Description: claude in /work/api      │");
+
+        // Edited like a note, on one line
+        press(&mut app, KeyCode::Char('D'));
+        assert_eq!(app.input_mode, InputMode::EditingNote);
+        assert_eq!(app.input_buffer.as_str(), "claude in /work/api");
+        app.input_buffer.clear();
+        for c in "payments".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.state.descriptions.get("api"), Some("payments"));
+        assert_eq!(app.state.note("api"), None);
+
+        // Deleting it derives it again on the next poll
+        press(&mut app, KeyCode::Char('D'));
+        let delete = KeyEvent::new(KeyCode::Char('d'), KeyModifiers::CONTROL);
+        app.handle_action(Action::KeyPress(delete)).unwrap();
+        assert_eq!(app.state.descriptions.get("api"), None);
+        let sessions = app.sessions.clone();
+        app.handle_action(Action::SessionsUpdated(sessions))
+            .unwrap();
+        assert_eq!(
+            app.state.descriptions.get("api"),
+            Some("claude in /work/api")
+        );
+    }
+
+    #[test]
+    fn test_rules_fire_on_status_changes() {
+        let config = Config {
+            rules: vec![
+                rules::parse_line("refactor", "once busy->idle send tests run the suite").unwrap(),
+                rules::parse_line("*", "error notify something broke").unwrap(),
+            ],
+            ..Config::default()
         };
+        let mut app = App::new(&config, PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("refactor", AgentStatus::Idle),
+            session("tests", AgentStatus::Idle),
+        ]))
+        .unwrap();
 
-        let content = if let Some(ref msg) = self.error_message {
-            let style = if msg.contains("copied") || msg.contains("success") {
-                Style::default().fg(self.theme.success)
-            } else {
-                Style::default().fg(self.theme.error)
-            };
-            Line::from(Span::styled(format!(" {} ", msg), style))
-        } else {
-            Line::from(Span::styled(help_text, Style::default().fg(self.theme.dim)))
+        let idle = Transition {
+            session_id: "$refactor".to_string(),
+            session: "refactor".to_string(),
+            previous: AgentStatus::Busy,
+            status: AgentStatus::Idle,
+        };
+        app.handle_action(Action::StatusChanged(vec![idle.clone()]))
+            .unwrap();
+        let actions = app.take_pending_actions();
+        let [Action::FireRule(id, firing, target)] = actions.as_slice() else {
+            panic!("{:?}", actions);
         };
+        assert_eq!(firing.rule, "rules[0]");
+        // The prompt goes to whichever session is called `tests` now
+        assert_eq!(target.as_deref(), Some("$tests"));
+        assert!(app.state.rule_flags.spent.contains("rules[0]"));
+        assert_eq!(app.rule_history.len(), 1);
+        assert_eq!(app.rule_history[0].outcome, None);
 
-        let footer = Paragraph::new(content).block(
-            Block::default()
-                .borders(Borders::ALL)
-                .border_style(Style::default().fg(self.theme.dim)),
+        app.handle_action(Action::RuleFinished(*id, Err("no such pane".to_string())))
+            .unwrap();
+        assert_eq!(
+            app.rule_history[0].outcome,
+            Some(Err("no such pane".to_string()))
         );
-        frame.render_widget(footer, area);
+        assert!(app.message().unwrap().contains("failed"));
+
+        // Spent, so the same transition again does nothing
+        app.handle_action(Action::StatusChanged(vec![idle]))
+            .unwrap();
+        assert!(app.pending_actions.is_empty());
+
+        // Notifications show in the footer too
+        app.handle_action(Action::StatusChanged(vec![Transition {
+            session_id: "$tests".to_string(),
+            session: "tests".to_string(),
+            previous: AgentStatus::Busy,
+            status: AgentStatus::Error,
+        }]))
+        .unwrap();
+        assert_eq!(app.message(), Some("tests: something broke"));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::FireRule(_, _, None)]
+        ));
+
+        // A read-only instance leaves rules to the one that owns the server
+        app.read_only = true;
+        app.handle_action(Action::StatusChanged(vec![Transition {
+            session_id: "$tests".to_string(),
+            session: "tests".to_string(),
+            previous: AgentStatus::Idle,
+            status: AgentStatus::Error,
+        }]))
+        .unwrap();
+        assert!(app.pending_actions.is_empty());
     }
 
-    fn render_create_dialog(&self, frame: &mut Frame) {
-        let area = centered_rect(50, 20, frame.area());
+    #[test]
+    fn test_usage_sampling_and_popup() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut api = session("api", AgentStatus::Idle);
+        api.current_command = "claude".to_string();
+        let mut shell = session("shell", AgentStatus::Unknown);
+        shell.current_command = "zsh".to_string();
+        let sessions = vec![api, shell];
+        app.handle_action(Action::SessionsUpdated(sessions.clone()))
+            .unwrap();
+        // Only agents are read, and not again until the interval passes
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::SampleUsage(agents)] if agents.len() == 1 && agents[0].name == "api"
+        ));
+        app.handle_action(Action::SessionsUpdated(sessions))
+            .unwrap();
+        assert!(app.take_pending_actions().is_empty());
 
-        frame.render_widget(Clear, area);
+        let reading = |tokens, cost| usage::Reading {
+            tokens: Some(tokens),
+            cost: Some(cost),
+        };
+        for (tokens, cost) in [(1_000, 0.10), (3_000, 0.40), (500, 0.05)] {
+            app.handle_action(Action::UsageSampled(vec![(
+                "api".to_string(),
+                AgentKind::Claude,
+                reading(tokens, cost),
+            )]))
+            .unwrap();
+        }
+        let rollup = app.state.usage.rollup(0, 0);
+        assert_eq!(rollup.week.tokens, 3_500);
+        assert_eq!(rollup.biggest_spender(), Some("api"));
 
-        let block = Block::default()
-            .title(" Create New Session ")
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(self.theme.accent));
+        press(&mut app, KeyCode::Char('U'));
+        assert_eq!(app.input_mode, InputMode::Usage);
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|f| app.render_usage(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        let screen: String = (0..30)
+            .flat_map(|y| (0..100).map(move |x| (x, y)))
+            .map(|pos| buffer[pos].symbol())
+            .collect();
+        assert!(screen.contains("$0.45 3.5k"), "{}", screen);
+        assert!(screen.contains("claude"));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
 
-        let inner = block.inner(area);
-        frame.render_widget(block, area);
+    #[test]
+    fn test_read_only_does_not_sample_usage() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.read_only = true;
+        let mut api = session("api", AgentStatus::Idle);
+        api.current_command = "aider".to_string();
+        app.handle_action(Action::SessionsUpdated(vec![api]))
+            .unwrap();
+        assert!(app.take_pending_actions().is_empty());
+    }
 
-        let text = vec![
-            Line::from(""),
-            Line::from(Span::styled(
-                "Enter session name:",
-                Style::default().fg(self.theme.fg),
-            )),
-            Line::from(""),
-            Line::from(Span::styled(
-                format!("▶ {}_", self.input_buffer),
-                Style::default()
-                    .fg(self.theme.accent)
-                    .add_modifier(Modifier::BOLD),
-            )),
-            Line::from(""),
-            Line::from(Span::styled(
-                "Press Enter to create, Esc to cancel",
-                Style::default().fg(self.theme.dim),
-            )),
-        ];
+    #[test]
+    fn test_adding_toggling_and_deleting_rules() {
+        let config = Config {
+            rules: vec![rules::parse_line("*", "error notify broke").unwrap()],
+            ..Config::default()
+        };
+        let mut app = App::new(&config, PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "refactor",
+            AgentStatus::Busy,
+        )]))
+        .unwrap();
 
-        let paragraph = Paragraph::new(text);
-        frame.render_widget(paragraph, inner);
+        press(&mut app, KeyCode::Char('w'));
+        assert_eq!(app.input_mode, InputMode::AddingRule);
+        for c in "idle email tests".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::AddingRule);
+        assert!(app.message().unwrap().contains("expected"));
+
+        app.input_buffer.set("idle send tests go");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.state.rules.len(), 1);
+        assert_eq!(app.state.rules[0].name.as_deref(), Some("refactor idle"));
+        assert_eq!(app.state.rules[0].when, "refactor");
+
+        // The same rule again gets a name of its own
+        press(&mut app, KeyCode::Char('w'));
+        app.input_buffer.set("idle send tests go");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.rules.rules[2].key, "refactor idle 2");
+
+        let detail = render_detail(&app, 80, 30).join("\n");
+        assert!(detail.contains("Rules"), "{}", detail);
+        assert!(detail.contains("send 'go' to tests"), "{}", detail);
+
+        // Config rules can be switched off but not deleted
+        press(&mut app, KeyCode::Char('W'));
+        assert_eq!(app.input_mode, InputMode::Rules);
+        press(&mut app, KeyCode::Char(' '));
+        assert_eq!(app.state.rule_flags.toggles.get("rules[0]"), Some(&false));
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.rules.rules.len(), 3);
+
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char(' '));
+        press(&mut app, KeyCode::Char('d'));
+        assert_eq!(app.rules.rules.len(), 2);
+        assert_eq!(app.state.rules.len(), 1);
+        // A deleted rule's flags go with it
+        assert!(!app.state.rule_flags.toggles.contains_key("refactor idle"));
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
     }
 
-    fn render_confirm_dialog(&self, frame: &mut Frame) {
-        let area = centered_rect(50, 20, frame.area());
+    #[test]
+    fn test_scheduling_a_prompt() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Busy,
+        )]))
+        .unwrap();
 
-        frame.render_widget(Clear, area);
+        press(&mut app, KeyCode::Char('T'));
+        assert_eq!(app.input_mode, InputMode::SchedulingTime);
+        for c in "soon".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        // A bad time keeps the dialog open with the reason in the footer
+        assert_eq!(app.input_mode, InputMode::SchedulingTime);
+        assert!(app.message().unwrap().contains("isn't a time"));
 
-        let block = Block::default()
-            .title(" Confirm Delete ")
-            .borders(Borders::ALL)
-            .border_style(Style::default().fg(self.theme.error));
+        app.input_buffer.set("+30m");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::SchedulingPrompt);
+        for c in "summarize".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.state.schedules.len(), 1);
+        let scheduled = &app.state.schedules[0];
+        assert_eq!(scheduled.session, "api");
+        assert_eq!(scheduled.prompt, "summarize");
+        assert!(scheduled.at.abs_diff(text::unix_now() + 1800) <= 1);
 
-        let inner = block.inner(area);
-        frame.render_widget(block, area);
+        let detail = render_detail(&app, 60, 20).join("\n");
+        assert!(detail.contains("Scheduled"), "{}", detail);
+        assert!(detail.contains("summarize"), "{}", detail);
 
-        let session_name = self
-            .selected_session()
-            .map(|s| s.name.as_str())
-            .unwrap_or("unknown");
+        // Cancel from the popup
+        press(&mut app, KeyCode::Char('P'));
+        assert_eq!(app.input_mode, InputMode::Schedules);
+        press(&mut app, KeyCode::Char('d'));
+        assert!(app.state.schedules.is_empty());
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
 
-        let text = vec![
-            Line::from(""),
-            Line::from(Span::styled(
-                format!("Delete session '{}'?", session_name),
-                Style::default().fg(self.theme.fg),
-            )),
-            Line::from(""),
-            Line::from(Span::styled(
-                "This action cannot be undone.",
-                Style::default().fg(self.theme.warning),
-            )),
-            Line::from(""),
-            Line::from(Span::styled(
-                "Press 'y' to confirm, 'n' or Esc to cancel",
-                Style::default().fg(self.theme.dim),
-            )),
+    #[test]
+    fn test_due_schedules_are_queued_once() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.state.add_schedule("api", "summarize", 100);
+        app.state.add_schedule("gone", "hello?", 100);
+        app.state.add_schedule("api", "later", 1_000);
+
+        // Nothing goes out before the first poll
+        app.queue_due_schedules(500);
+        assert!(app.take_pending_actions().is_empty());
+
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Idle,
+        )]))
+        .unwrap();
+        app.queue_due_schedules(500);
+        let actions = app.take_pending_actions();
+        let targets: Vec<(&str, Option<&str>)> = actions
+            .iter()
+            .map(|action| match action {
+                Action::SendScheduled(s, id) => (s.prompt.as_str(), id.as_deref()),
+                other => panic!("{:?}", other),
+            })
+            .collect();
+        assert_eq!(targets, [("summarize", Some("$api")), ("hello?", None)]);
+        assert_eq!(app.state.schedules.len(), 1);
+
+        app.queue_due_schedules(500);
+        assert!(app.take_pending_actions().is_empty());
+
+        // A read-only instance leaves sending to the other one
+        app.read_only = true;
+        app.queue_due_schedules(1_000);
+        assert!(app.take_pending_actions().is_empty());
+        assert_eq!(app.state.schedules.len(), 1);
+    }
+
+    #[test]
+    fn test_export_carries_notes() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.state.set_note("api", "handing over");
+        press(&mut app, KeyCode::Char('X'));
+        let actions = app.take_pending_actions();
+        let [Action::ExportSessions(notes, _)] = actions.as_slice() else {
+            panic!("{:?}", actions);
+        };
+        assert_eq!(notes.get("api").map(String::as_str), Some("handing over"));
+
+        let op = app.start_operation("Exporting sessions");
+        app.handle_action(Action::SessionsExported(op, Ok(2)))
+            .unwrap();
+        assert!(app.operations.is_empty());
+        assert_eq!(app.message(), Some("Exported 2 sessions"));
+    }
+
+    #[test]
+    fn test_tags() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        press(&mut app, KeyCode::Char('f'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(
+            app.message(),
+            Some("No tagged sessions: press # to tag one")
+        );
+        app.handle_action(Action::SessionsUpdated(vec![
+            session("api", AgentStatus::Idle),
+            session("web", AgentStatus::Idle),
+        ]))
+        .unwrap();
+
+        press(&mut app, KeyCode::Char('#'));
+        assert_eq!(app.input_mode, InputMode::EditingTags);
+        for c in "acme, back end!".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::EditingTags);
+        assert_eq!(
+            app.message(),
+            Some("'end!' isn't a tag: use letters, digits and - _ / .")
+        );
+        press(&mut app, KeyCode::Backspace);
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert_eq!(app.state.tags.get("api"), ["acme", "back", "end"]);
+        assert_eq!(
+            render_list(&mut app, 30, 4)[1],
+            "│▶ ● 1 api #acme #back #end  │"
+        );
+
+        // Reopening starts from the saved tags
+        press(&mut app, KeyCode::Char('#'));
+        assert_eq!(app.input_buffer.as_str(), "acme back end");
+        press(&mut app, KeyCode::Esc);
+
+        // Choosing a tag filters by it, and "All sessions" clears the filter
+        press(&mut app, KeyCode::Char('f'));
+        assert_eq!(app.input_mode, InputMode::ChoosingTag);
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Char('j'));
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.session_filter, "#back");
+        assert_eq!(app.rows, [ListRow::Session(0)]);
+        press(&mut app, KeyCode::Char('f'));
+        assert_eq!(app.tag_selected, 2);
+        press(&mut app, KeyCode::Char('k'));
+        press(&mut app, KeyCode::Char('k'));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.session_filter.is_empty());
+        assert_eq!(app.rows.len(), 2);
+    }
+
+    #[test]
+    fn test_note_line_limit() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        app.handle_action(Action::SessionsUpdated(vec![session(
+            "api",
+            AgentStatus::Idle,
+        )]))
+        .unwrap();
+        press(&mut app, KeyCode::Char('N'));
+        for _ in 0..10 {
+            press(&mut app, KeyCode::Char('x'));
+            press(&mut app, KeyCode::Enter);
+        }
+        assert_eq!(
+            app.input_buffer.as_str().split('\n').count(),
+            NOTE_MAX_LINES
+        );
+    }
+
+    #[test]
+    fn test_freshness_thresholds() {
+        let app = App::new(&Config::default(), PersistedState::default());
+        assert_eq!(app.freshness_color(Duration::from_secs(1)), app.theme.dim);
+        assert_eq!(
+            app.freshness_color(Duration::from_secs(5)),
+            app.theme.warning
+        );
+        assert_eq!(
+            app.freshness_color(Duration::from_secs(14)),
+            app.theme.warning
+        );
+        assert_eq!(
+            app.freshness_color(Duration::from_secs(15)),
+            app.theme.error
+        );
+    }
+
+    fn dashboard_app() -> App {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut api = session("api", AgentStatus::WaitingForInput);
+        api.windows = 2;
+        app.handle_action(Action::SessionsUpdated(vec![
+            api,
+            session("web", AgentStatus::Busy),
+        ]))
+        .unwrap();
+        press(&mut app, KeyCode::Char('O'));
+        let windows = vec![
+            TmuxWindow {
+                index: 0,
+                name: "claude".to_string(),
+                active: true,
+                panes: 1,
+                command: "node".to_string(),
+            },
+            TmuxWindow {
+                index: 1,
+                name: "tests".to_string(),
+                active: false,
+                panes: 2,
+                command: "cargo".to_string(),
+            },
         ];
+        app.handle_action(Action::WindowsListed("$api".to_string(), Ok(windows)))
+            .unwrap();
+        app.handle_action(Action::PaneCaptured(
+            "$api".to_string(),
+            "$ claude\n> Run the tests? (y/n)".to_string(),
+        ))
+        .unwrap();
+        let ago = text::unix_now() - 120;
+        app.status_timeline.record(
+            &[Transition {
+                session_id: "$api".to_string(),
+                session: "api".to_string(),
+                previous: AgentStatus::Busy,
+                status: AgentStatus::WaitingForInput,
+            }],
+            ago,
+        );
+        app
+    }
 
-        let paragraph = Paragraph::new(text);
-        frame.render_widget(paragraph, inner);
+    fn render_screen(app: &mut App, width: u16, height: u16) -> Vec<String> {
+        let mut terminal = Terminal::new(TestBackend::new(width, height)).unwrap();
+        terminal.draw(|f| app.render_screen(f)).unwrap();
+        let buffer = terminal.backend().buffer();
+        (0..height)
+            .map(|y| {
+                (0..width)
+                    .map(|x| buffer[(x, y)].symbol())
+                    .collect::<String>()
+            })
+            .collect()
     }
-}
 
-/// Helper function to create a centered rectangle
-fn centered_rect(percent_x: u16, percent_y: u16, r: Rect) -> Rect {
-    let popup_layout = Layout::default()
-        .direction(Direction::Vertical)
-        .constraints([
-            Constraint::Percentage((100 - percent_y) / 2),
-            Constraint::Percentage(percent_y),
-            Constraint::Percentage((100 - percent_y) / 2),
-        ])
-        .split(r);
+    #[test]
+    fn test_help_overlay() {
+        let mut app = grouped_app();
+        let selected = app.list_state.selected();
+        press(&mut app, KeyCode::Char('?'));
+        assert_eq!(app.input_mode, InputMode::Help);
 
-    Layout::default()
-        .direction(Direction::Horizontal)
-        .constraints([
-            Constraint::Percentage((100 - percent_x) / 2),
-            Constraint::Percentage(percent_x),
-            Constraint::Percentage((100 - percent_x) / 2),
-        ])
-        .split(popup_layout[1])[1]
+        // Keys scroll the overlay, not the list, and stop at the last line
+        let screen = render_screen(&mut app, 80, 20).join("\n");
+        assert!(screen.contains("Navigation"));
+        assert!(screen.contains("Filter sessions by name"));
+        for _ in 0..200 {
+            press(&mut app, KeyCode::Char('j'));
+        }
+        assert_eq!(app.list_state.selected(), selected);
+        assert_eq!(app.help_scroll, help_lines().len() - app.help_view_height());
+        let screen = render_screen(&mut app, 80, 20).join("\n");
+        assert!(screen.contains("Quit"));
+        assert!(!screen.contains("Navigation"));
+
+        press(&mut app, KeyCode::Char('?'));
+        assert_eq!(app.input_mode, InputMode::Normal);
+        press(&mut app, KeyCode::Char('?'));
+        assert_eq!(app.help_scroll, 0);
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn test_name_dialog_cursor() {
+        let mut app = grouped_app();
+        app.select_session_id("$beta");
+        press(&mut app, KeyCode::Char('r'));
+        press(&mut app, KeyCode::Home);
+        for c in "naïve_".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        press(&mut app, KeyCode::Delete);
+        assert_eq!(app.input_buffer.as_str(), "naïve_eta");
+
+        // Drawn where the next character goes, not at the end
+        let mut terminal = Terminal::new(TestBackend::new(100, 30)).unwrap();
+        terminal.draw(|f| app.render(f)).unwrap();
+        let inner = centered_rect(50, 20, Rect::new(0, 0, 100, 30)).inner(Margin::new(1, 1));
+        let line: String = (inner.x..inner.right())
+            .map(|x| {
+                terminal.backend().buffer()[(x, inner.y + 3)]
+                    .symbol()
+                    .to_string()
+            })
+            .collect();
+        assert_eq!(line.trim_end(), "▶ naïve_eta");
+        terminal
+            .backend_mut()
+            .assert_cursor_position((inner.x + 8, inner.y + 3));
+
+        press(&mut app, KeyCode::End);
+        app.handle_key(KeyEvent::new(KeyCode::Char('w'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert_eq!(app.input_buffer.as_str(), "naïve_");
+        app.handle_key(KeyEvent::new(KeyCode::Char('u'), KeyModifiers::CONTROL))
+            .unwrap();
+        assert!(app.input_buffer.is_empty());
+        assert_eq!(app.input_mode, InputMode::Renaming);
+    }
+
+    #[test]
+    fn test_rename_session() {
+        let mut app = grouped_app();
+        app.select_session_id("$beta");
+        app.state.set_note("beta", "flaky");
+        press(&mut app, KeyCode::Char('r'));
+        assert_eq!(app.input_mode, InputMode::Renaming);
+        assert_eq!(app.input_buffer.as_str(), "beta");
+        for c in "-2 ok".chars() {
+            press(&mut app, KeyCode::Char(c));
+        }
+        assert_eq!(app.input_buffer.as_str(), "beta-2ok");
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::RenameSession(id, name)] if id == "$beta" && name == "beta-2ok"
+        ));
+
+        // Keeping the name renames nothing
+        press(&mut app, KeyCode::Char('r'));
+        press(&mut app, KeyCode::Enter);
+        assert!(app.pending_actions.is_empty());
+
+        app.handle_action(Action::SessionRenamed(
+            0,
+            Ok(("$beta".to_string(), "beta-2ok".to_string())),
+        ))
+        .unwrap();
+        let session = app.selected_session().unwrap();
+        assert_eq!(
+            (session.id.as_str(), session.name.as_str()),
+            ("$beta", "beta-2ok")
+        );
+        assert_eq!(app.state.note("beta-2ok"), Some("flaky"));
+
+        // tmux's reason for refusing shows in the footer
+        let error = anyhow::Error::from(crate::error::AgentRustyError::DuplicateSession(
+            "alpha".to_string(),
+        ))
+        .context("Failed to rename session to 'alpha'");
+        app.handle_action(Action::SessionRenamed(1, Err(Arc::new(error))))
+            .unwrap();
+        assert_eq!(
+            app.message(),
+            Some("Failed to rename session to 'alpha': duplicate session: alpha")
+        );
+    }
+
+    fn log_line(at: &str, level: tracing::Level, message: &str) -> LogLine {
+        LogLine {
+            at: at.to_string(),
+            level,
+            target: "agent_rusty".to_string(),
+            message: message.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_log_viewer() {
+        let mut app = grouped_app();
+        app.area = Rect::new(0, 0, 60, 15);
+        for i in 0..20 {
+            let level = match i % 4 {
+                0 => tracing::Level::WARN,
+                1 => tracing::Level::DEBUG,
+                _ => tracing::Level::INFO,
+            };
+            app.logs.push(log_line(
+                &format!("12:00:{:02}", i),
+                level,
+                &format!("event {}", i),
+            ));
+        }
+        press(&mut app, KeyCode::Char('l'));
+        assert_eq!(app.input_mode, InputMode::Logs);
+        let messages = |app: &App| -> Vec<String> {
+            app.visible_logs().into_iter().map(|l| l.message).collect()
+        };
+
+        // Following: the newest lines, staying at the bottom as more arrive
+        let shown = messages(&app);
+        assert_eq!(shown.len(), app.log_view_height());
+        assert_eq!(shown.last().unwrap(), "event 19");
+        app.logs
+            .push(log_line("12:00:20", tracing::Level::ERROR, "event 20"));
+        assert_eq!(messages(&app).last().unwrap(), "event 20");
+
+        // Scrolling up pauses, so new lines don't move the view
+        press(&mut app, KeyCode::Char('k'));
+        let paused = messages(&app);
+        assert_eq!(paused.last().unwrap(), "event 19");
+        app.logs
+            .push(log_line("12:00:21", tracing::Level::INFO, "event 21"));
+        assert_eq!(messages(&app), paused);
+        press(&mut app, KeyCode::Char('f'));
+        assert_eq!(messages(&app).last().unwrap(), "event 21");
+        press(&mut app, KeyCode::Char('f'));
+        press(&mut app, KeyCode::Char('g'));
+        assert_eq!(messages(&app)[0], "event 0");
+
+        // The filter goes from everything to errors only, one level at a time
+        for _ in 0..3 {
+            press(&mut app, KeyCode::Char('v'));
+        }
+        assert_eq!(app.log_filter, tracing::Level::WARN);
+        assert_eq!(
+            messages(&app),
+            [
+                "event 0", "event 4", "event 8", "event 12", "event 16", "event 20"
+            ]
+        );
+        let screen = render_screen(&mut app, 60, 15);
+        assert!(screen[2].contains(" Log (warn+) ") && screen[2].contains(" following "));
+        assert!(screen[3].contains("12:00:00  WARN agent_rusty: event 0"));
+
+        // Copying takes what is on screen
+        press(&mut app, KeyCode::Char('v'));
+        press(&mut app, KeyCode::Char('y'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::CopyText(text)] if text == "12:00:20 ERROR agent_rusty: event 20"
+        ));
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+    }
+
+    #[test]
+    fn test_dashboard_snapshot() {
+        let mut app = dashboard_app();
+        assert_eq!(app.input_mode, InputMode::Dashboard);
+        assert_eq!(
+            render_screen(&mut app, 80, 16),
+            vec![
+                "┌ api ────────────────────────────────────────── 1/2 ┐┌ Usage ─────────────────┐",
+                "│$ claude                                            ││No usage this week      │",
+                "│> Run the tests? (y/n)                              ││                        │",
+                "│                                                    │└────────────────────────┘",
+                "│                                                    │┌ Windows ───────────────┐",
+                "│                                                    ││▶ 0: claude             │",
+                "│                                                    ││  1: tests (2 panes)    │",
+                "│                                                    │└────────────────────────┘",
+                "│                                                    │┌ Status ────────────────┐",
+                "│                                                    ││Now: ? WaitingForInput  │",
+                "│                                                    ││ 2m ago ? WaitingForInpu│",
+                "│                                                    ││                        │",
+                "│                                                    ││                        │",
+                "│                                                    ││                        │",
+                "└────────────────────────────────────────────────────┘└────────────────────────┘",
+                " Esc: Back │ ←/→: Session │ i: Interrupt │ p: Prompt │ R: Restart │ Enter: Attac",
+            ]
+        );
+        // The side column keeps its minimum width; the pane gives way
+        assert_eq!(
+            render_screen(&mut app, 60, 12),
+            vec![
+                "┌ api ──────────────────────── 1/2 ┐┌ Usage ───────────────┐",
+                "│$ claude                          ││No usage this week    │",
+                "│> Run the tests? (y/n)            ││                      │",
+                "│                                  │└──────────────────────┘",
+                "│                                  │┌ Windows ─────────────┐",
+                "│                                  ││▶ 0: claude           │",
+                "│                                  ││  1: tests (2 panes)  │",
+                "│                                  │└──────────────────────┘",
+                "│                                  │┌ Status ──────────────┐",
+                "│                                  ││Now: ? WaitingForInput│",
+                "└──────────────────────────────────┘└──────────────────────┘",
+                " Esc: Back │ ←/→: Session │ i: Interrupt │ p: Prompt │ R: Re",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_dashboard_keys() {
+        let mut app = dashboard_app();
+        app.take_pending_actions();
+
+        // Cycling moves the selection and follows it with the pane and windows
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.selected_session().unwrap().name, "web");
+        assert!(app.dashboard_windows.is_empty());
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::CapturePane(a), Action::ListWindows(b)] if a == "$web" && b == "$web"
+        ));
+        // Windows of the session left behind arrive too late to show
+        app.handle_action(Action::WindowsListed("$api".to_string(), Ok(Vec::new())))
+            .unwrap();
+        assert_eq!(app.input_mode, InputMode::Dashboard);
+        press(&mut app, KeyCode::Right);
+        assert_eq!(app.selected_session().unwrap().name, "api");
+        app.take_pending_actions();
+
+        press(&mut app, KeyCode::Char('i'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::Interrupt(id)] if id == "$api"
+        ));
+
+        press(&mut app, KeyCode::Char('p'));
+        assert_eq!(app.input_mode, InputMode::DashboardPrompt);
+        app.handle_action(Action::Paste("yes".to_string())).unwrap();
+        press(&mut app, KeyCode::Enter);
+        assert_eq!(app.input_mode, InputMode::Dashboard);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::Broadcast(to, text)] if to[0].id == "$api" && text == "yes"
+        ));
+        app.handle_action(Action::BroadcastFinished(vec![BroadcastResult {
+            name: "api".to_string(),
+            outcome: Ok(()),
+        }]))
+        .unwrap();
+        assert_eq!(app.input_mode, InputMode::Dashboard);
+        assert_eq!(app.message(), Some("Prompt sent to 'api'"));
+
+        // Restarting comes back to the dashboard
+        press(&mut app, KeyCode::Char('R'));
+        assert_eq!(app.input_mode, InputMode::ConfirmingRestart);
+        press(&mut app, KeyCode::Char('y'));
+        assert_eq!(app.input_mode, InputMode::Dashboard);
+        app.take_pending_actions();
+
+        app.handle_action(Action::WindowsListed(
+            "$api".to_string(),
+            Ok(vec![TmuxWindow {
+                index: 1,
+                name: "tests".to_string(),
+                active: false,
+                panes: 1,
+                command: "cargo".to_string(),
+            }]),
+        ))
+        .unwrap();
+        press(&mut app, KeyCode::Char('1'));
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::SelectWindow(target), Action::AttachSession(id, false)]
+                if target == "$api:1" && id == "$api"
+        ));
+        press(&mut app, KeyCode::Char('2'));
+        assert!(app.take_pending_actions().is_empty());
+
+        press(&mut app, KeyCode::Esc);
+        assert_eq!(app.input_mode, InputMode::Normal);
+        assert!(!app.dashboard_open && app.preview.is_none());
+        assert_eq!(app.selected_session().unwrap().name, "api");
+    }
+
+    #[test]
+    fn test_sessions_of_several_servers() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let on = |socket: &str, status| {
+            let mut s = session("api", status);
+            s.id = format!("{}/$0", socket);
+            s.socket = socket.to_string();
+            s
+        };
+        app.handle_action(Action::SessionsUpdated(vec![
+            on("work", AgentStatus::Busy),
+            on("personal", AgentStatus::Idle),
+        ]))
+        .unwrap();
+        assert_eq!(
+            render_list(&mut app, 24, 6),
+            vec![
+                "┌ Sessions ────────────┐",
+                "│  work (1)            │",
+                "│▶ ⠋ 1 api             │",
+                "│  personal (1)        │",
+                "│  ● 2 api             │",
+                "└──────────────────────┘",
+            ]
+        );
+
+        // The same name on another server is a different session
+        press(&mut app, KeyCode::Char('j'));
+        assert_eq!(app.selected_session().unwrap().id, "personal/$0");
+        app.handle_action(Action::SessionsUpdated(vec![
+            on("personal", AgentStatus::Idle),
+            on("work", AgentStatus::Idle),
+        ]))
+        .unwrap();
+        assert_eq!(app.selected_session().unwrap().id, "personal/$0");
+        press(&mut app, KeyCode::Enter);
+        assert!(matches!(
+            app.take_pending_actions().as_slice(),
+            [Action::AttachSession(id, false)] if id == "personal/$0"
+        ));
+    }
 }
@@ -0,0 +1,209 @@
+//! Terminal bell for sessions that need attention.
+//!
+//! Desktop notifications don't reach a full-screen terminal, but the bell
+//! does: the terminal or tmux flags the window. Which statuses ring, focus
+//! gating and rate limiting are decided here; writing the sequence is left to
+//! the caller.
+
+use serde::Deserialize;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+
+use crate::poller::Transition;
+use crate::tmux::AgentStatus;
+
+/// `[bell]` section of the config; nothing rings until a status is enabled
+#[derive(Debug, Clone, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BellConfig {
+    /// Ring when a session starts waiting for input
+    pub waiting_for_input: bool,
+    /// Ring when a session hits an error
+    pub error: bool,
+    /// Ring when a session goes idle
+    pub idle: bool,
+    /// Written to the terminal; BEL by default, e.g. `"\u001b[?5h\u001b[?5l"` to flash instead
+    pub sequence: String,
+    /// Stay quiet while the terminal has focus; terminals that don't report focus always ring
+    pub only_unfocused: bool,
+    /// Minimum seconds between rings for the same session
+    pub debounce_secs: u64,
+    /// Minimum seconds between any two rings
+    pub min_interval_secs: u64,
+}
+
+impl Default for BellConfig {
+    fn default() -> Self {
+        Self {
+            waiting_for_input: false,
+            error: false,
+            idle: false,
+            sequence: "\u{7}".to_string(),
+            only_unfocused: true,
+            debounce_secs: 30,
+            min_interval_secs: 5,
+        }
+    }
+}
+
+impl BellConfig {
+    pub fn rings_for(&self, status: AgentStatus) -> bool {
+        match status {
+            AgentStatus::WaitingForInput => self.waiting_for_input,
+            AgentStatus::Error => self.error,
+            AgentStatus::Idle => self.idle,
+            AgentStatus::Busy | AgentStatus::Unknown => false,
+        }
+    }
+}
+
+/// Decides when status changes ring the bell
+#[derive(Debug)]
+pub struct Bell {
+    config: BellConfig,
+    /// Silenced from the dashboard
+    pub muted: bool,
+    last_rung: Option<Instant>,
+    /// Last ring per session id, dropped once outside the debounce window
+    rung_for: HashMap<String, Instant>,
+}
+
+impl Bell {
+    pub fn new(config: BellConfig) -> Self {
+        Self {
+            config,
+            muted: false,
+            last_rung: None,
+            rung_for: HashMap::new(),
+        }
+    }
+
+    pub fn sequence(&self) -> &str {
+        &self.config.sequence
+    }
+
+    /// Whether `transitions` ring the bell at `now`, recording the ring if so
+    ///
+    /// `focused` is the terminal's last focus report, `None` if it never sent one.
+    pub fn due(&mut self, transitions: &[Transition], focused: Option<bool>, now: Instant) -> bool {
+        if self.muted || (self.config.only_unfocused && focused == Some(true)) {
+            return false;
+        }
+        let debounce = Duration::from_secs(self.config.debounce_secs);
+        self.rung_for
+            .retain(|_, at| now.duration_since(*at) < debounce);
+        let sessions: Vec<&str> = transitions
+            .iter()
+            .filter(|t| {
+                self.config.rings_for(t.status) && !self.rung_for.contains_key(&t.session_id)
+            })
+            .map(|t| t.session_id.as_str())
+            .collect();
+        if sessions.is_empty() {
+            return false;
+        }
+        let min_interval = Duration::from_secs(self.config.min_interval_secs);
+        if self
+            .last_rung
+            .is_some_and(|at| now.duration_since(at) < min_interval)
+        {
+            tracing::debug!(sessions = sessions.len(), "Bell rate limited");
+            return false;
+        }
+        for session in sessions {
+            self.rung_for.insert(session.to_string(), now);
+        }
+        self.last_rung = Some(now);
+        true
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transition(session: &str, status: AgentStatus) -> Transition {
+        Transition {
+            session_id: format!("${}", session),
+            session: session.to_string(),
+            previous: AgentStatus::Busy,
+            status,
+        }
+    }
+
+    fn ringing() -> Bell {
+        Bell::new(BellConfig {
+            waiting_for_input: true,
+            error: true,
+            ..BellConfig::default()
+        })
+    }
+
+    #[test]
+    fn test_statuses() {
+        let start = Instant::now();
+        assert!(!Bell::new(BellConfig::default()).due(
+            &[transition("api", AgentStatus::WaitingForInput)],
+            None,
+            start
+        ));
+
+        let mut bell = ringing();
+        assert!(!bell.due(&[transition("api", AgentStatus::Idle)], None, start));
+        assert!(!bell.due(&[transition("api", AgentStatus::Busy)], None, start));
+        assert!(bell.due(&[transition("api", AgentStatus::Error)], None, start));
+    }
+
+    #[test]
+    fn test_focus_and_mute() {
+        let start = Instant::now();
+        let waiting = [transition("api", AgentStatus::WaitingForInput)];
+        let mut bell = ringing();
+        assert!(!bell.due(&waiting, Some(true), start));
+        bell.muted = true;
+        assert!(!bell.due(&waiting, Some(false), start));
+        bell.muted = false;
+        assert!(bell.due(&waiting, Some(false), start));
+
+        // Terminals that don't report focus always ring
+        let mut bell = ringing();
+        assert!(bell.due(&waiting, None, start));
+
+        let mut always = Bell::new(BellConfig {
+            waiting_for_input: true,
+            only_unfocused: false,
+            ..BellConfig::default()
+        });
+        assert!(always.due(&waiting, Some(true), start));
+    }
+
+    #[test]
+    fn test_rate_limits() {
+        let start = Instant::now();
+        let at = |secs| start + Duration::from_secs(secs);
+        let waiting = |session| [transition(session, AgentStatus::WaitingForInput)];
+        let mut bell = ringing();
+        assert!(bell.due(&waiting("api"), None, at(0)));
+
+        // A flapping session rings once per debounce window
+        assert!(!bell.due(&waiting("api"), None, at(10)));
+        assert!(!bell.due(&waiting("api"), None, at(29)));
+        assert!(bell.due(&waiting("api"), None, at(30)));
+
+        // Other sessions wait for the global interval
+        assert!(!bell.due(&waiting("web"), None, at(32)));
+        assert!(bell.due(&waiting("web"), None, at(35)));
+
+        // Several sessions in one poll ring once and all count as rung
+        assert!(bell.due(
+            &[
+                transition("db", AgentStatus::WaitingForInput),
+                transition("docs", AgentStatus::Error),
+            ],
+            None,
+            at(40)
+        ));
+        assert!(!bell.due(&waiting("db"), None, at(50)));
+        assert!(!bell.due(&waiting("docs"), None, at(50)));
+    }
+}
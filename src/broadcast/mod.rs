@@ -0,0 +1,93 @@
+use std::collections::BTreeSet;
+
+use crate::tmux::{AgentKind, AgentStatus, TmuxSession};
+
+/// A session that will receive the broadcast
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Recipient {
+    pub id: String,
+    pub name: String,
+}
+
+/// Recipients of a broadcast after filtering the marked sessions
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct BroadcastPlan {
+    pub recipients: Vec<Recipient>,
+    /// Marked sessions left out, with the reason
+    pub skipped: Vec<(String, &'static str)>,
+}
+
+/// Why a session should not receive a prompt unless forced
+fn skip_reason(session: &TmuxSession) -> Option<&'static str> {
+    if session.status == AgentStatus::Error {
+        Some("in error state")
+    } else if session.agent_kind() == AgentKind::Shell {
+        Some("no agent running")
+    } else {
+        None
+    }
+}
+
+/// Work out who receives a broadcast to the `marked` sessions, in list order
+pub fn plan(sessions: &[TmuxSession], marked: &BTreeSet<String>, force: bool) -> BroadcastPlan {
+    let mut plan = BroadcastPlan::default();
+    for session in sessions.iter().filter(|s| marked.contains(&s.id)) {
+        match skip_reason(session) {
+            Some(reason) if !force => plan.skipped.push((session.name.clone(), reason)),
+            _ => plan.recipients.push(Recipient {
+                id: session.id.clone(),
+                name: session.name.clone(),
+            }),
+        }
+    }
+    plan
+}
+
+/// Outcome of sending to one recipient
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct BroadcastResult {
+    pub name: String,
+    pub outcome: Result<(), String>,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(name: &str, status: AgentStatus, command: &str) -> TmuxSession {
+        let mut s = TmuxSession::new(format!("${}", name), name.to_string());
+        s.status = status;
+        s.current_command = command.to_string();
+        s
+    }
+
+    #[test]
+    fn test_plan_skips_error_and_agentless_sessions() {
+        let sessions = vec![
+            session("a", AgentStatus::Idle, "claude"),
+            session("b", AgentStatus::Error, "claude"),
+            session("c", AgentStatus::Idle, "zsh"),
+            session("d", AgentStatus::Busy, "aider"),
+            session("unmarked", AgentStatus::Idle, "claude"),
+        ];
+        let marked: BTreeSet<String> = ["$a", "$b", "$c", "$d"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect();
+
+        let p = plan(&sessions, &marked, false);
+        let names: Vec<_> = p.recipients.iter().map(|r| r.name.as_str()).collect();
+        assert_eq!(names, vec!["a", "d"]);
+        assert_eq!(
+            p.skipped,
+            vec![
+                ("b".to_string(), "in error state"),
+                ("c".to_string(), "no agent running")
+            ]
+        );
+
+        let forced = plan(&sessions, &marked, true);
+        assert_eq!(forced.recipients.len(), 4);
+        assert!(forced.skipped.is_empty());
+    }
+}
@@ -0,0 +1,302 @@
+//! Claude Code's own record of its conversations, read from its data
+//! directory (`~/.claude`, or `$CLAUDE_CONFIG_DIR`).
+//!
+//! Claude Code keeps one JSON Lines transcript per conversation under
+//! `projects/<cwd with every non-alphanumeric character as ->/`. The latest
+//! transcript for a session's working directory gives its conversation id,
+//! model and start time. The format isn't documented, so every field is
+//! optional, unknown fields are ignored and unreadable lines are skipped.
+
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::fs::File;
+use std::io::{BufRead, BufReader, Read, Seek, SeekFrom};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant, SystemTime};
+
+/// How long a directory's lookup is reused before checking for a newer transcript
+pub const CLAUDE_CACHE_TTL: Duration = Duration::from_secs(30);
+/// Lines read from the start of a transcript for its id and start time
+const HEAD_LINES: usize = 20;
+/// Bytes read from the end of a transcript for the model in use
+const TAIL_BYTES: u64 = 64 * 1024;
+
+/// The Claude Code conversation a session's directory last had
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ClaudeMeta {
+    /// Conversation id, as taken by `claude --resume`
+    pub session_id: String,
+    /// Model of the latest reply
+    pub model: Option<String>,
+    /// Unix time of the first message
+    pub started_at: Option<u64>,
+}
+
+/// The parts of a transcript line we use
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, rename_all = "camelCase")]
+struct Entry {
+    session_id: Option<String>,
+    cwd: Option<String>,
+    timestamp: Option<String>,
+    message: Option<Message>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(default)]
+struct Message {
+    model: Option<String>,
+}
+
+fn parse_entry(line: &str) -> Option<Entry> {
+    serde_json::from_str(line).ok()
+}
+
+/// Claude Code's data directory, if there is one
+pub fn data_dir() -> Option<PathBuf> {
+    let dir = std::env::var_os("CLAUDE_CONFIG_DIR")
+        .map(PathBuf::from)
+        .or_else(|| dirs::home_dir().map(|home| home.join(".claude")))?;
+    dir.is_dir().then_some(dir)
+}
+
+/// Directory name Claude Code files a working directory's conversations under
+pub fn project_dir_name(cwd: &str) -> String {
+    cwd.chars()
+        .map(|c| if c.is_ascii_alphanumeric() { c } else { '-' })
+        .collect()
+}
+
+/// The most recently written transcript in `project`
+fn latest_transcript(project: &Path) -> Option<(PathBuf, SystemTime)> {
+    std::fs::read_dir(project)
+        .ok()?
+        .filter_map(|entry| entry.ok())
+        .map(|entry| entry.path())
+        .filter(|path| path.extension().is_some_and(|ext| ext == "jsonl"))
+        .filter_map(|path| {
+            let modified = path.metadata().and_then(|m| m.modified()).ok()?;
+            Some((path, modified))
+        })
+        .max_by(|a, b| a.1.cmp(&b.1).then_with(|| a.0.cmp(&b.0)))
+}
+
+/// Read a transcript's id, start time and latest model
+///
+/// `None` if it has no conversation id or belongs to another directory whose
+/// name maps to the same project.
+pub fn read_transcript(path: &Path, cwd: &str) -> Option<ClaudeMeta> {
+    let mut file = File::open(path).ok()?;
+    let mut session_id = None;
+    let mut started_at = None;
+    for line in BufReader::new(&mut file)
+        .lines()
+        .take(HEAD_LINES)
+        .map_while(Result::ok)
+    {
+        let Some(entry) = parse_entry(&line) else {
+            continue;
+        };
+        if entry.cwd.as_deref().is_some_and(|c| c != cwd) {
+            return None;
+        }
+        session_id = session_id.or(entry.session_id);
+        started_at = started_at.or_else(|| {
+            let at = chrono::DateTime::parse_from_rfc3339(entry.timestamp.as_deref()?).ok()?;
+            Some(at.timestamp().max(0) as u64)
+        });
+    }
+
+    // The tail is enough for the latest reply; its first line is likely cut short
+    let len = file.metadata().ok()?.len();
+    file.seek(SeekFrom::Start(len.saturating_sub(TAIL_BYTES)))
+        .ok()?;
+    let mut tail = Vec::new();
+    file.read_to_end(&mut tail).ok()?;
+    let entries: Vec<Entry> = String::from_utf8_lossy(&tail)
+        .lines()
+        .filter_map(parse_entry)
+        .collect();
+    // Replies Claude Code makes up itself have a `<synthetic>` model
+    let model = entries.iter().rev().find_map(|e| {
+        let model = e.message.as_ref()?.model.as_ref()?;
+        (!model.starts_with('<')).then(|| model.clone())
+    });
+    let session_id = session_id.or_else(|| entries.iter().find_map(|e| e.session_id.clone()))?;
+
+    Some(ClaudeMeta {
+        session_id,
+        model,
+        started_at,
+    })
+}
+
+/// The latest conversation Claude Code had in `cwd`, under `data_dir`
+pub fn lookup_in(data_dir: &Path, cwd: &str) -> Option<ClaudeMeta> {
+    let project = data_dir.join("projects").join(project_dir_name(cwd));
+    let (path, _) = latest_transcript(&project)?;
+    read_transcript(&path, cwd)
+}
+
+/// The latest conversation Claude Code had in `cwd`, read off the runtime's threads
+pub async fn lookup(cwd: &str) -> Option<ClaudeMeta> {
+    let cwd = cwd.to_string();
+    tokio::task::spawn_blocking(move || lookup_in(&data_dir()?, &cwd))
+        .await
+        .ok()
+        .flatten()
+}
+
+/// Per-directory cache of lookups; a transcript is only read again once it changes
+pub struct ClaudeCache {
+    data_dir: Option<PathBuf>,
+    entries: HashMap<String, CacheEntry>,
+    ttl: Duration,
+}
+
+struct CacheEntry {
+    checked: Instant,
+    transcript: Option<(PathBuf, SystemTime)>,
+    meta: Option<ClaudeMeta>,
+}
+
+impl ClaudeCache {
+    pub fn new(data_dir: Option<PathBuf>, ttl: Duration) -> Self {
+        Self {
+            data_dir,
+            entries: HashMap::new(),
+            ttl,
+        }
+    }
+
+    /// Conversation for `cwd`, checked again for a newer transcript once the TTL passes
+    ///
+    /// The data directory is read on a blocking thread.
+    pub async fn get(&mut self, cwd: &str) -> Option<ClaudeMeta> {
+        let data_dir = self.data_dir.clone()?;
+        let known = match self.entries.get(cwd) {
+            Some(entry) if entry.checked.elapsed() < self.ttl => return entry.meta.clone(),
+            Some(entry) => Some((entry.transcript.clone(), entry.meta.clone())),
+            None => None,
+        };
+        let owned = cwd.to_string();
+        let (transcript, meta) = tokio::task::spawn_blocking(move || {
+            let project = data_dir.join("projects").join(project_dir_name(&owned));
+            let transcript = latest_transcript(&project);
+            let meta = match known {
+                Some((read, meta)) if read == transcript => meta,
+                _ => transcript
+                    .as_ref()
+                    .and_then(|(path, _)| read_transcript(path, &owned)),
+            };
+            (transcript, meta)
+        })
+        .await
+        .ok()?;
+        self.entries.insert(
+            cwd.to_string(),
+            CacheEntry {
+                checked: Instant::now(),
+                transcript,
+                meta: meta.clone(),
+            },
+        );
+        meta
+    }
+
+    /// Forget directories no session is in any more
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.entries.retain(|cwd, _| keep(cwd));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn fixtures() -> PathBuf {
+        Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/claude")
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-rusty-claude-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        dir
+    }
+
+    #[test]
+    fn test_project_dir_name() {
+        assert_eq!(project_dir_name("/home/dev/api"), "-home-dev-api");
+        assert_eq!(
+            project_dir_name("/home/dev/my.app_v2"),
+            "-home-dev-my-app-v2"
+        );
+    }
+
+    #[test]
+    fn test_lookup_fixture() {
+        let meta = lookup_in(&fixtures(), "/home/dev/api").unwrap();
+        assert_eq!(meta.session_id, "3f2c9a1e-7d4b-4c1a-9e2f-5b8d6a0c1e77");
+        // The latest reply's model, not the first
+        assert_eq!(meta.model.as_deref(), Some("claude-opus-4-1"));
+        let started = chrono::DateTime::parse_from_rfc3339("2026-10-14T09:12:03.512Z").unwrap();
+        assert_eq!(meta.started_at, Some(started.timestamp() as u64));
+
+        assert_eq!(lookup_in(&fixtures(), "/home/dev/nothing-here"), None);
+    }
+
+    #[test]
+    fn test_format_drift() {
+        // Unknown fields, garbage lines, no timestamps and no model
+        let meta = lookup_in(&fixtures(), "/home/dev/drift").unwrap();
+        assert_eq!(meta.session_id, "9b1d7c55-0000-4000-8000-000000000001");
+        assert_eq!(meta.model, None);
+        assert_eq!(meta.started_at, None);
+
+        // Another directory mapping to the same project name isn't ours
+        assert_eq!(lookup_in(&fixtures(), "/home/dev.api"), None);
+    }
+
+    #[tokio::test]
+    async fn test_latest_transcript_wins() {
+        let dir = temp_dir("latest");
+        let project = dir.join("projects").join("-work");
+        std::fs::create_dir_all(&project).unwrap();
+        let line = |id: &str| format!("{{\"sessionId\":\"{}\",\"cwd\":\"/work\"}}\n", id);
+        std::fs::write(project.join("old.jsonl"), line("old")).unwrap();
+        std::fs::write(project.join("new.jsonl"), line("new")).unwrap();
+        std::fs::write(project.join("notes.txt"), "not a transcript").unwrap();
+        let earlier = SystemTime::now() - Duration::from_secs(3600);
+        File::options()
+            .write(true)
+            .open(project.join("old.jsonl"))
+            .unwrap()
+            .set_modified(earlier)
+            .unwrap();
+
+        let mut cache = ClaudeCache::new(Some(dir.clone()), Duration::ZERO);
+        assert_eq!(cache.get("/work").await.unwrap().session_id, "new");
+
+        // A newer conversation takes over
+        std::fs::write(project.join("old.jsonl"), line("resumed")).unwrap();
+        File::options()
+            .write(true)
+            .open(project.join("old.jsonl"))
+            .unwrap()
+            .set_modified(SystemTime::now() + Duration::from_secs(60))
+            .unwrap();
+        assert_eq!(cache.get("/work").await.unwrap().session_id, "resumed");
+
+        std::fs::remove_dir_all(&dir).unwrap();
+        assert_eq!(cache.get("/work").await, None);
+        assert_eq!(
+            ClaudeCache::new(None, Duration::ZERO).get("/work").await,
+            None
+        );
+    }
+}
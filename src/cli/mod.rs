@@ -0,0 +1,425 @@
+use clap::{Parser, Subcommand, ValueEnum};
+use clap_complete::{ArgValueCompleter, Shell};
+use std::path::PathBuf;
+
+use crate::completions;
+use crate::tmux::AgentStatus;
+
+/// Shortest poll interval accepted from the command line or config
+pub const MIN_POLL_INTERVAL_MS: u64 = 100;
+
+/// Terminal dashboard for AI agent sessions running in tmux
+#[derive(Debug, Parser)]
+#[command(version, about)]
+pub struct Cli {
+    /// tmux binary to run
+    #[arg(long, value_name = "PATH", default_value = "tmux")]
+    pub tmux: String,
+
+    /// tmux server socket name (passed to tmux as `-L`)
+    #[arg(short = 'L', long, value_name = "NAME")]
+    pub socket_name: Option<String>,
+
+    /// Run tmux inside this WSL distribution (implies WSL mode)
+    #[arg(long, value_name = "NAME")]
+    pub wsl_distro: Option<String>,
+
+    /// Milliseconds between session polls (overrides `poll_interval_ms` in the config)
+    #[arg(
+        long,
+        value_name = "MS",
+        value_parser = clap::value_parser!(u64).range(MIN_POLL_INTERVAL_MS..=60_000)
+    )]
+    pub poll_interval: Option<u64>,
+
+    /// Directory used as the skeleton root
+    #[arg(long, value_name = "PATH", default_value = ".", value_parser = existing_dir)]
+    pub dir: PathBuf,
+
+    /// Write logs to this file instead of ~/.agent-deck/logs/agent-rusty.log
+    #[arg(long, value_name = "PATH")]
+    pub log_file: Option<PathBuf>,
+
+    /// Rewrite this file with Prometheus metrics after every poll (overrides `metrics_file`)
+    #[arg(long, value_name = "PATH")]
+    pub metrics_file: Option<PathBuf>,
+
+    /// Config file to use instead of the default location
+    #[arg(long, value_name = "PATH")]
+    pub config: Option<PathBuf>,
+
+    /// Print plain lines for screen readers instead of drawing the dashboard
+    /// (also `accessible` in the config)
+    #[arg(long)]
+    pub accessible: bool,
+
+    /// Serve the Model Context Protocol on stdin/stdout instead of drawing the
+    /// dashboard (the same as `mcp-serve`)
+    #[arg(long)]
+    pub mcp: bool,
+
+    /// Print the man page (roff) and exit
+    #[arg(long, hide = true)]
+    pub generate_man: bool,
+
+    /// Run a single operation instead of the dashboard
+    #[command(subcommand)]
+    pub command: Option<Command>,
+}
+
+/// Headless operations for scripts and CI
+#[derive(Debug, Subcommand)]
+pub enum Command {
+    /// List sessions
+    List {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Show one session; exits 1 if it doesn't exist and 3 if it is in Error
+    Status {
+        /// Session name or ID
+        #[arg(add = ArgValueCompleter::new(completions::session_names))]
+        name: String,
+        /// Print JSON instead of text
+        #[arg(long)]
+        json: bool,
+    },
+    /// Create a detached session
+    Create {
+        /// Session name
+        name: String,
+        /// Starting directory
+        #[arg(long, value_name = "PATH", value_parser = existing_dir)]
+        dir: Option<PathBuf>,
+        /// Command to run in the session instead of a shell
+        #[arg(long, value_name = "COMMAND")]
+        cmd: Option<String>,
+        /// Print the new session as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Wait until a session reaches a status; exits 1 if it disappears and 124 on timeout
+    Wait {
+        /// Session name or ID
+        #[arg(add = ArgValueCompleter::new(completions::session_names))]
+        name: String,
+        /// Statuses to wait for, comma separated (busy, idle, waiting-for-input, error, unknown)
+        #[arg(
+            long = "for",
+            value_name = "STATUSES",
+            value_delimiter = ',',
+            default_value = "idle,waiting-for-input"
+        )]
+        statuses: Vec<AgentStatus>,
+        /// Give up after this many seconds
+        #[arg(long, value_name = "SECS")]
+        timeout: Option<u64>,
+        /// Milliseconds between checks
+        #[arg(
+            long,
+            value_name = "MS",
+            default_value_t = 1000,
+            value_parser = clap::value_parser!(u64).range(MIN_POLL_INTERVAL_MS..=60_000)
+        )]
+        interval: u64,
+        /// Print the session as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Kill a session
+    Kill {
+        /// Session name or ID
+        #[arg(add = ArgValueCompleter::new(completions::session_names))]
+        name: String,
+    },
+    /// Serve the Model Context Protocol on stdin/stdout
+    McpServe,
+    /// Recreate sessions from ~/.agent-deck/snapshot.json that aren't running
+    Restore {
+        /// List the missing sessions without creating them
+        #[arg(long)]
+        dry_run: bool,
+    },
+    /// Write every session, with its agent, start command and note, as JSON
+    Export {
+        /// Write to this file instead of stdout
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Recreate the sessions in an export that aren't running; exits 1 if any fail
+    Import {
+        /// File written by `export`
+        path: PathBuf,
+    },
+    /// Queue prompts for the dashboard to send to sessions at a set time
+    Schedule {
+        #[command(subcommand)]
+        command: ScheduleCommand,
+    },
+    /// Send a command to the running dashboard (needs `control_socket = true`)
+    Ctl {
+        #[command(subcommand)]
+        command: CtlCommand,
+    },
+    /// Print the project skeleton of `--dir`
+    Skeleton {
+        #[arg(long, value_enum, default_value_t = SkeletonFormat::Text)]
+        format: SkeletonFormat,
+        /// Write to this file instead of stdout
+        #[arg(long, value_name = "PATH")]
+        out: Option<PathBuf>,
+    },
+    /// Check tmux, the clipboard, notifications, the config and our directories; exits 1 if a check fails
+    Doctor,
+    /// Print a script enabling shell completion, e.g. `source <(agent-rusty completions bash)`
+    Completions {
+        #[arg(value_enum)]
+        shell: Shell,
+    },
+}
+
+/// Scheduled prompts, kept in ~/.agent-deck/state.json and sent by the dashboard
+#[derive(Debug, Subcommand)]
+pub enum ScheduleCommand {
+    /// Schedule a prompt; one that falls due while no dashboard runs is sent when one starts
+    Add {
+        /// Session name
+        #[arg(add = ArgValueCompleter::new(completions::session_names))]
+        session: String,
+        /// When to send: +30m, +1h30m, 14:00 or "2026-10-17 14:00"
+        #[arg(value_parser = parse_when)]
+        when: u64,
+        /// Text typed into the session, followed by Enter
+        prompt: String,
+    },
+    /// List pending prompts, soonest first
+    List {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Cancel a pending prompt
+    Cancel {
+        /// ID shown by `schedule list`
+        id: u64,
+    },
+}
+
+/// Commands for the running dashboard's control socket
+#[derive(Debug, Subcommand)]
+pub enum CtlCommand {
+    /// List sessions as the dashboard currently sees them
+    List {
+        /// Print JSON instead of a table
+        #[arg(long)]
+        json: bool,
+    },
+    /// Poll tmux now
+    Refresh,
+    /// Select a session in the list
+    Focus {
+        /// Session name or ID
+        #[arg(add = ArgValueCompleter::new(completions::session_names))]
+        name: String,
+    },
+    /// Show a message in the footer
+    Toast { message: String },
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, ValueEnum)]
+pub enum SkeletonFormat {
+    /// Plain tree
+    Text,
+    /// Tree in a fenced Markdown code block
+    Md,
+}
+
+fn parse_when(value: &str) -> Result<u64, String> {
+    crate::schedule::parse_when_local(value)
+}
+
+fn existing_dir(value: &str) -> Result<PathBuf, String> {
+    let path = PathBuf::from(value);
+    if path.is_dir() {
+        Ok(path)
+    } else {
+        Err(format!("'{}' is not a directory", value))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use clap::CommandFactory;
+
+    #[test]
+    fn test_cli_definition() {
+        Cli::command().debug_assert();
+    }
+
+    #[test]
+    fn test_parse_args() {
+        let cli = Cli::try_parse_from(["agent-rusty"]).unwrap();
+        assert_eq!(cli.tmux, "tmux");
+        assert_eq!(cli.poll_interval, None);
+        assert_eq!(cli.dir, PathBuf::from("."));
+        assert_eq!(cli.wsl_distro, None);
+
+        let cli = Cli::try_parse_from([
+            "agent-rusty",
+            "-L",
+            "agents",
+            "--poll-interval",
+            "250",
+            "--dir",
+            "src",
+            "--wsl-distro",
+            "Ubuntu",
+        ])
+        .unwrap();
+        assert_eq!(cli.socket_name.as_deref(), Some("agents"));
+        assert_eq!(cli.wsl_distro.as_deref(), Some("Ubuntu"));
+        assert_eq!(cli.poll_interval, Some(250));
+
+        assert!(Cli::try_parse_from(["agent-rusty", "--poll-interval", "10"]).is_err());
+        assert!(Cli::try_parse_from(["agent-rusty", "--poll-interval", "soon"]).is_err());
+        assert!(Cli::try_parse_from(["agent-rusty", "--dir", "/no/such/dir"]).is_err());
+    }
+
+    #[test]
+    fn test_parse_subcommands() {
+        let cli = Cli::try_parse_from(["agent-rusty"]).unwrap();
+        assert!(cli.command.is_none());
+
+        let cli = Cli::try_parse_from(["agent-rusty", "-L", "agents", "list", "--json"]).unwrap();
+        assert_eq!(cli.socket_name.as_deref(), Some("agents"));
+        assert!(matches!(cli.command, Some(Command::List { json: true })));
+
+        let cli = Cli::try_parse_from([
+            "agent-rusty",
+            "create",
+            "api",
+            "--dir",
+            "src",
+            "--cmd",
+            "claude",
+        ])
+        .unwrap();
+        match cli.command {
+            Some(Command::Create {
+                name,
+                dir,
+                cmd,
+                json,
+            }) => {
+                assert_eq!(name, "api");
+                assert_eq!(dir, Some(PathBuf::from("src")));
+                assert_eq!(cmd.as_deref(), Some("claude"));
+                assert!(!json);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+
+        let cli = Cli::try_parse_from(["agent-rusty", "skeleton", "--format", "md"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Skeleton {
+                format: SkeletonFormat::Md,
+                out: None
+            })
+        ));
+
+        let cli = Cli::try_parse_from(["agent-rusty", "ctl", "focus", "api"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Ctl { command: CtlCommand::Focus { name } }) if name == "api"
+        ));
+        assert!(Cli::try_parse_from(["agent-rusty", "ctl"]).is_err());
+
+        let cli = Cli::try_parse_from(["agent-rusty", "wait", "api"]).unwrap();
+        match cli.command {
+            Some(Command::Wait {
+                statuses,
+                timeout,
+                interval,
+                ..
+            }) => {
+                assert_eq!(statuses, [AgentStatus::Idle, AgentStatus::WaitingForInput]);
+                assert_eq!(timeout, None);
+                assert_eq!(interval, 1000);
+            }
+            other => panic!("unexpected command: {:?}", other),
+        }
+        let cli = Cli::try_parse_from([
+            "agent-rusty",
+            "wait",
+            "api",
+            "--for",
+            "waiting-for-input,Error",
+            "--timeout",
+            "1800",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Wait { statuses, timeout: Some(1800), .. })
+                if statuses == [AgentStatus::WaitingForInput, AgentStatus::Error]
+        ));
+        assert!(Cli::try_parse_from(["agent-rusty", "wait", "api", "--for", "asleep"]).is_err());
+        assert!(Cli::try_parse_from(["agent-rusty", "wait", "api", "--interval", "5"]).is_err());
+
+        let cli = Cli::try_parse_from(["agent-rusty", "restore", "--dry-run"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Restore { dry_run: true })
+        ));
+
+        let cli = Cli::try_parse_from(["agent-rusty", "mcp-serve"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::McpServe)));
+        let cli = Cli::try_parse_from(["agent-rusty", "-L", "work", "--mcp"]).unwrap();
+        assert!(cli.mcp && cli.command.is_none());
+
+        let cli = Cli::try_parse_from(["agent-rusty", "export", "--out", "s.json"]).unwrap();
+        assert!(
+            matches!(cli.command, Some(Command::Export { out: Some(p) }) if p.as_os_str() == "s.json")
+        );
+        let cli = Cli::try_parse_from(["agent-rusty", "import", "s.json"]).unwrap();
+        assert!(
+            matches!(cli.command, Some(Command::Import { path }) if path.as_os_str() == "s.json")
+        );
+
+        let cli = Cli::try_parse_from([
+            "agent-rusty",
+            "schedule",
+            "add",
+            "payments-fix",
+            "+30m",
+            "write up a summary",
+        ])
+        .unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Schedule { command: ScheduleCommand::Add { session, when, prompt } })
+                if session == "payments-fix"
+                    && when > crate::text::unix_now()
+                    && prompt == "write up a summary"
+        ));
+        assert!(
+            Cli::try_parse_from(["agent-rusty", "schedule", "add", "api", "soon", "x"]).is_err()
+        );
+        let cli = Cli::try_parse_from(["agent-rusty", "schedule", "cancel", "3"]).unwrap();
+        assert!(matches!(
+            cli.command,
+            Some(Command::Schedule {
+                command: ScheduleCommand::Cancel { id: 3 }
+            })
+        ));
+
+        let cli = Cli::try_parse_from(["agent-rusty", "doctor"]).unwrap();
+        assert!(matches!(cli.command, Some(Command::Doctor)));
+
+        assert!(Cli::try_parse_from(["agent-rusty", "status"]).is_err());
+        assert!(Cli::try_parse_from(["agent-rusty", "skeleton", "--format", "html"]).is_err());
+    }
+}
@@ -0,0 +1,473 @@
+//! Copying text out of the dashboard.
+//!
+//! No one clipboard works everywhere: arboard fails on Wayland without a
+//! portal, on headless boxes and in some containers, OSC 52 needs a terminal
+//! that allows it, and a tmux buffer only helps inside tmux. Copies go
+//! through a chain of [`ClipboardBackend`]s in the configured order; the first
+//! to take the text wins, and if none does, every failure is kept in the
+//! error's cause chain for the error details popup.
+
+use futures_util::future::BoxFuture;
+use serde::Deserialize;
+use std::borrow::Cow;
+use std::io::{IsTerminal, Write};
+use std::process::Stdio;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::error::{AgentRustyError, Result};
+use crate::text;
+use crate::tmux::TmuxClient;
+
+/// A clipboard command still running after this long is killed
+const COMMAND_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// One way of putting text on a clipboard
+pub trait ClipboardBackend: Send + Sync {
+    /// Where the text ends up, for `Text copied to …`
+    fn label(&self) -> String;
+    fn copy<'a>(&'a self, text: &'a str) -> BoxFuture<'a, std::result::Result<(), String>>;
+}
+
+/// Backends that can be named in `clipboard.order`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BackendKind {
+    /// `clipboard.command`, given the text on stdin
+    Command,
+    /// The system clipboard via arboard
+    System,
+    /// The terminal's clipboard via an OSC 52 escape
+    Osc52,
+    /// tmux's paste buffer
+    Tmux,
+}
+
+/// `[clipboard]` section of the config
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ClipboardConfig {
+    /// Backends to try, first to last; `command` (if set), `system`, `osc52`, `tmux` when unset
+    pub order: Option<Vec<BackendKind>>,
+    /// Shell command that reads the text on stdin (`wl-copy`, `xclip -selection clipboard`, ...)
+    pub command: Option<String>,
+}
+
+impl ClipboardConfig {
+    /// Backends in the order they are tried
+    pub fn order(&self) -> Vec<BackendKind> {
+        match &self.order {
+            Some(order) => order.clone(),
+            None => self
+                .command
+                .iter()
+                .map(|_| BackendKind::Command)
+                .chain([BackendKind::System, BackendKind::Osc52, BackendKind::Tmux])
+                .collect(),
+        }
+    }
+
+    /// Why the section can't work, if it can't
+    pub fn validate(&self) -> std::result::Result<(), String> {
+        match &self.order {
+            Some(order) if order.is_empty() => Err("`order` is empty".to_string()),
+            Some(order) if order.contains(&BackendKind::Command) && self.command.is_none() => {
+                Err("`order` lists `command` but no `command` is set".to_string())
+            }
+            _ => Ok(()),
+        }
+    }
+}
+
+/// The system clipboard via arboard
+pub struct System;
+
+impl ClipboardBackend for System {
+    fn label(&self) -> String {
+        "clipboard".to_string()
+    }
+
+    fn copy<'a>(&'a self, text: &'a str) -> BoxFuture<'a, std::result::Result<(), String>> {
+        Box::pin(async move {
+            arboard::Clipboard::new()
+                .and_then(|mut c| c.set_text(text))
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// The terminal's clipboard via an OSC 52 escape
+pub struct Osc52;
+
+impl ClipboardBackend for Osc52 {
+    fn label(&self) -> String {
+        "terminal clipboard".to_string()
+    }
+
+    fn copy<'a>(&'a self, text: &'a str) -> BoxFuture<'a, std::result::Result<(), String>> {
+        Box::pin(async move {
+            let mut stdout = std::io::stdout();
+            if !stdout.is_terminal() {
+                return Err("output is not a terminal".to_string());
+            }
+            stdout
+                .write_all(osc52_sequence(text).as_bytes())
+                .and_then(|_| stdout.flush())
+                .map_err(|e| e.to_string())
+        })
+    }
+}
+
+/// tmux's paste buffer
+pub struct TmuxBuffer(pub TmuxClient);
+
+impl ClipboardBackend for TmuxBuffer {
+    fn label(&self) -> String {
+        "tmux buffer".to_string()
+    }
+
+    fn copy<'a>(&'a self, text: &'a str) -> BoxFuture<'a, std::result::Result<(), String>> {
+        Box::pin(async move { self.0.set_buffer(text).await.map_err(|e| e.to_string()) })
+    }
+}
+
+/// A shell command given the text on stdin
+pub struct External(pub String);
+
+impl ClipboardBackend for External {
+    fn label(&self) -> String {
+        format!("`{}`", self.0)
+    }
+
+    fn copy<'a>(&'a self, text: &'a str) -> BoxFuture<'a, std::result::Result<(), String>> {
+        Box::pin(async move {
+            let mut child = Command::new("sh")
+                .arg("-c")
+                .arg(&self.0)
+                .stdin(Stdio::piped())
+                .stdout(Stdio::null())
+                .stderr(Stdio::piped())
+                .kill_on_drop(true)
+                .spawn()
+                .map_err(|e| e.to_string())?;
+            // A command that never reads would block the write once the pipe fills
+            let run = async {
+                if let Some(mut stdin) = child.stdin.take() {
+                    stdin.write_all(text.as_bytes()).await?;
+                }
+                child.wait_with_output().await
+            };
+            let output = tokio::time::timeout(COMMAND_TIMEOUT, run)
+                .await
+                .map_err(|_| format!("timed out after {}s", COMMAND_TIMEOUT.as_secs()))?
+                .map_err(|e| e.to_string())?;
+            if output.status.success() {
+                Ok(())
+            } else {
+                Err(format!(
+                    "{}: {}",
+                    output.status,
+                    String::from_utf8_lossy(&output.stderr).trim()
+                ))
+            }
+        })
+    }
+}
+
+/// A backend's failure, linked to the next one's so the error chain lists them all
+#[derive(Debug)]
+pub struct BackendFailure {
+    pub backend: String,
+    pub message: String,
+    next: Option<Box<BackendFailure>>,
+}
+
+impl std::fmt::Display for BackendFailure {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}: {}", self.backend, self.message)
+    }
+}
+
+impl std::error::Error for BackendFailure {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        self.next
+            .as_deref()
+            .map(|next| next as &(dyn std::error::Error + 'static))
+    }
+}
+
+/// Backends tried in order until one takes the text
+pub struct Clipboard {
+    backends: Vec<Box<dyn ClipboardBackend>>,
+}
+
+impl Clipboard {
+    /// The chain `config` asks for, with tmux buffers going to `tmux`'s server
+    pub fn new(config: &ClipboardConfig, tmux: &TmuxClient) -> Self {
+        let backends = config
+            .order()
+            .into_iter()
+            .filter_map(|kind| -> Option<Box<dyn ClipboardBackend>> {
+                Some(match kind {
+                    BackendKind::Command => Box::new(External(config.command.clone()?)),
+                    BackendKind::System => Box::new(System),
+                    BackendKind::Osc52 => Box::new(Osc52),
+                    BackendKind::Tmux => Box::new(TmuxBuffer(tmux.clone())),
+                })
+            })
+            .collect();
+        Self::with_backends(backends)
+    }
+
+    pub fn with_backends(backends: Vec<Box<dyn ClipboardBackend>>) -> Self {
+        Self { backends }
+    }
+
+    /// Copy `text` with the first backend that takes it; returns that backend's label
+    pub async fn copy(&self, text: &str) -> Result<String> {
+        let mut failures = Vec::new();
+        for backend in &self.backends {
+            match backend.copy(text).await {
+                Ok(()) => return Ok(backend.label()),
+                Err(message) => {
+                    tracing::debug!("Clipboard {} failed: {}", backend.label(), message);
+                    failures.push((backend.label(), message));
+                }
+            }
+        }
+        let failure = failures.into_iter().rev().fold(
+            None,
+            |next: Option<BackendFailure>, (backend, message)| {
+                Some(BackendFailure {
+                    backend,
+                    message,
+                    next: next.map(Box::new),
+                })
+            },
+        );
+        Err(AgentRustyError::ClipboardUnavailable(failure.unwrap_or(
+            BackendFailure {
+                backend: "clipboard".to_string(),
+                message: "no backends configured".to_string(),
+                next: None,
+            },
+        )))
+    }
+}
+
+/// Escape sequence asking the terminal to put `text` on its clipboard
+pub fn osc52_sequence(text: &str) -> String {
+    format!("\x1b]52;c;{}\x07", base64(text.as_bytes()))
+}
+
+fn base64(bytes: &[u8]) -> String {
+    const ALPHABET: &[u8; 64] = b"ABCDEFGHIJKLMNOPQRSTUVWXYZabcdefghijklmnopqrstuvwxyz0123456789+/";
+    let mut out = String::with_capacity(bytes.len().div_ceil(3) * 4);
+    for chunk in bytes.chunks(3) {
+        let n = chunk
+            .iter()
+            .enumerate()
+            .fold(0u32, |n, (i, &b)| n | (b as u32) << (16 - 8 * i));
+        for i in 0..4 {
+            if i <= chunk.len() {
+                out.push(ALPHABET[(n >> (18 - 6 * i)) as usize & 63] as char);
+            } else {
+                out.push('=');
+            }
+        }
+    }
+    out
+}
+
+/// A pane capture as it is copied: without escapes if `strip_ansi`, and
+/// without the blank lines tmux pads the screen with below the cursor, which
+/// may still hold escapes when those are kept
+pub fn pane_text(captured: &str, strip_ansi: bool) -> String {
+    let captured = if strip_ansi {
+        text::strip_ansi(captured)
+    } else {
+        Cow::Borrowed(captured)
+    };
+    let mut lines: Vec<&str> = captured.lines().collect();
+    while lines
+        .last()
+        .is_some_and(|line| text::strip_ansi(line).trim().is_empty())
+    {
+        lines.pop();
+    }
+    lines.join("\n").trim_end().to_string()
+}
+
+/// Footer message for copied pane content
+pub fn describe_pane_copy(content: &str, backend: &str) -> String {
+    let lines = content.lines().count();
+    format!(
+        "Pane copied to {}: {} line{}, {}",
+        backend,
+        lines,
+        if lines == 1 { "" } else { "s" },
+        text::format_bytes(content.len())
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    /// Records what it was asked to copy, failing with `error` if set
+    struct Mock {
+        name: &'static str,
+        error: Option<&'static str>,
+        copied: Arc<Mutex<Vec<String>>>,
+    }
+
+    impl ClipboardBackend for Mock {
+        fn label(&self) -> String {
+            self.name.to_string()
+        }
+
+        fn copy<'a>(&'a self, text: &'a str) -> BoxFuture<'a, std::result::Result<(), String>> {
+            self.copied
+                .lock()
+                .unwrap()
+                .push(format!("{}:{}", self.name, text));
+            let result = self.error.map_or(Ok(()), |e| Err(e.to_string()));
+            Box::pin(async move { result })
+        }
+    }
+
+    fn chain(
+        backends: &[(&'static str, Option<&'static str>)],
+    ) -> (Clipboard, Arc<Mutex<Vec<String>>>) {
+        let copied = Arc::new(Mutex::new(Vec::new()));
+        let backends = backends
+            .iter()
+            .map(|&(name, error)| -> Box<dyn ClipboardBackend> {
+                Box::new(Mock {
+                    name,
+                    error,
+                    copied: copied.clone(),
+                })
+            })
+            .collect();
+        (Clipboard::with_backends(backends), copied)
+    }
+
+    #[tokio::test]
+    async fn test_first_success_wins() {
+        let (clipboard, copied) = chain(&[
+            ("wl-copy", Some("not found")),
+            ("clipboard", None),
+            ("tmux buffer", None),
+        ]);
+        assert_eq!(clipboard.copy("hi").await.unwrap(), "clipboard");
+        assert_eq!(*copied.lock().unwrap(), ["wl-copy:hi", "clipboard:hi"]);
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_chained() {
+        let (clipboard, _) = chain(&[
+            ("clipboard", Some("no display")),
+            ("terminal clipboard", Some("output is not a terminal")),
+        ]);
+        let error = anyhow::Error::from(clipboard.copy("hi").await.unwrap_err());
+        let causes: Vec<String> = error.chain().map(ToString::to_string).collect();
+        assert_eq!(
+            causes,
+            [
+                "clipboard unavailable",
+                "clipboard: no display",
+                "terminal clipboard: output is not a terminal",
+            ]
+        );
+
+        let (clipboard, _) = chain(&[]);
+        assert!(clipboard.copy("hi").await.is_err());
+    }
+
+    #[cfg(unix)]
+    #[tokio::test]
+    async fn test_external_command() {
+        let dir = std::env::temp_dir().join(format!("agent-rusty-clip-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let file = dir.join("copied");
+        let external = External(format!("cat > '{}'", file.display()));
+        external.copy("hello\n").await.unwrap();
+        assert_eq!(std::fs::read_to_string(&file).unwrap(), "hello\n");
+        let _ = std::fs::remove_dir_all(&dir);
+
+        let error = External("echo nope >&2; exit 3".to_string())
+            .copy("hello")
+            .await
+            .unwrap_err();
+        assert!(error.ends_with(": nope"), "{}", error);
+
+        // More than a pipe holds, to a command that doesn't read it
+        let error = External("sleep 30".to_string())
+            .copy(&"x".repeat(1 << 20))
+            .await
+            .unwrap_err();
+        assert_eq!(error, "timed out after 5s");
+    }
+
+    #[test]
+    fn test_config_order() {
+        let config = ClipboardConfig::default();
+        assert_eq!(
+            config.order(),
+            [BackendKind::System, BackendKind::Osc52, BackendKind::Tmux]
+        );
+        let config = ClipboardConfig {
+            command: Some("wl-copy".to_string()),
+            ..ClipboardConfig::default()
+        };
+        assert_eq!(config.order()[0], BackendKind::Command);
+        assert!(config.validate().is_ok());
+
+        let config = ClipboardConfig {
+            order: Some(vec![BackendKind::Tmux, BackendKind::Command]),
+            command: None,
+        };
+        assert!(config.validate().is_err());
+        let config = ClipboardConfig {
+            order: Some(Vec::new()),
+            command: None,
+        };
+        assert!(config.validate().is_err());
+    }
+
+    #[test]
+    fn test_base64() {
+        assert_eq!(base64(b""), "");
+        assert_eq!(base64(b"f"), "Zg==");
+        assert_eq!(base64(b"fo"), "Zm8=");
+        assert_eq!(base64(b"foo"), "Zm9v");
+        assert_eq!(base64(b"foobar"), "Zm9vYmFy");
+        assert_eq!(osc52_sequence("hi"), "\x1b]52;c;aGk=\x07");
+    }
+
+    #[test]
+    fn test_pane_text() {
+        // A pane with less scrollback than asked for is just shorter
+        let captured = "$ claude\n\x1b[1mDone.\x1b[0m  \n\n\x1b[0m\n   \n";
+        assert_eq!(pane_text(captured, true), "$ claude\nDone.");
+        assert_eq!(pane_text(captured, false), "$ claude\n\x1b[1mDone.\x1b[0m");
+        assert_eq!(pane_text("a\n\nb\n\n", true), "a\n\nb");
+        assert_eq!(pane_text("\n\n", true), "");
+    }
+
+    #[test]
+    fn test_describe_pane_copy() {
+        assert_eq!(
+            describe_pane_copy("one line", "clipboard"),
+            "Pane copied to clipboard: 1 line, 8 B"
+        );
+        let content = "x".repeat(2047) + "\n" + &"y".repeat(2000);
+        assert_eq!(
+            describe_pane_copy(&content, "tmux buffer"),
+            "Pane copied to tmux buffer: 2 lines, 4.0 KB"
+        );
+    }
+}
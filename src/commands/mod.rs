@@ -0,0 +1,631 @@
+//! Headless subcommands.
+//!
+//! With `--json`, sessions are printed as serialized [`TmuxSession`] values
+//! (`list` prints an array of them). The schema is stable:
+//!
+//! ```json
+//! {
+//!   "id": "$0",
+//!   "name": "api",
+//!   "created_at": 1700000000,
+//!   "attached_clients": 0,
+//!   "activity_at": 1700000100,
+//!   "current_command": "claude",
+//!   "current_path": "/home/me/api",
+//!   "git": { "head": { "Branch": "main" }, "dirty": true },
+//!   "status": "Idle"
+//! }
+//! ```
+//!
+//! `git` is `null` outside a repository, and `head` is `{ "Detached": "<short hash>" }`
+//! on a detached HEAD. `status` is one of `Busy`, `Idle`, `WaitingForInput`, `Error`
+//! or `Unknown`. Timestamps are Unix seconds. Sessions given tags in the dashboard
+//! also have `"tags": ["backend"]`, and sessions in a directory Claude Code has
+//! a conversation in have `"claude": { "session_id": "…", "model": "…", "started_at": … }`.
+
+use anyhow::{Context, Result};
+use std::io::Write;
+use std::path::Path;
+use std::time::Duration;
+
+use crate::cli::{Command, CtlCommand, ScheduleCommand, SkeletonFormat};
+use crate::completions;
+use crate::control::{self, Request};
+use crate::doctor;
+use crate::git;
+use crate::inventory::{self, Inventory, Outcome};
+use crate::mcp;
+use crate::schedule::{self, ScheduledPrompt};
+use crate::skeleton;
+use crate::snapshot::{self, Snapshot};
+use crate::state::PersistedState;
+use crate::tmux::{AgentStatus, Sessions, TmuxClient, TmuxSession};
+use crate::wait::{self, WaitOutcome};
+
+/// Exit code when the named session doesn't exist
+pub const EXIT_NOT_FOUND: i32 = 1;
+/// Exit code from `status` when the session is in Error
+pub const EXIT_SESSION_ERROR: i32 = 3;
+/// Exit code from `wait` when the timeout expires (as with coreutils `timeout`)
+pub const EXIT_TIMEOUT: i32 = 124;
+
+/// Run `command` and return the process exit code
+///
+/// `doctor_paths` are the files `doctor` checks, which only the binary knows.
+pub async fn run(
+    command: Command,
+    client: &TmuxClient,
+    skeleton_root: &Path,
+    control_path: &Path,
+    doctor_paths: &doctor::Paths,
+) -> Result<i32> {
+    let mut out = std::io::stdout();
+    match command {
+        Command::List { json } => {
+            let sessions = list_sessions(client).await?;
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&sessions)?)?;
+            } else {
+                write!(out, "{}", format_table(&sessions))?;
+            }
+            Ok(0)
+        }
+        Command::Status { name, json } => {
+            let sessions = list_sessions(client).await?;
+            let Some(session) = find_session(&sessions, &name) else {
+                eprintln!("No session named {}", name);
+                return Ok(EXIT_NOT_FOUND);
+            };
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(session)?)?;
+            } else {
+                write!(out, "{}", format_status(session))?;
+            }
+            Ok(status_exit_code(session.status))
+        }
+        Command::Create {
+            name,
+            dir,
+            cmd,
+            json,
+        } => {
+            let session = client
+                .create_session_with(&name, dir.as_deref(), cmd.as_deref())
+                .await?;
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&session)?)?;
+            } else {
+                writeln!(out, "Created {} ({})", session.name, session.id)?;
+            }
+            Ok(0)
+        }
+        Command::Wait {
+            name,
+            statuses,
+            timeout,
+            interval,
+            json,
+        } => {
+            let timeout = timeout.map(Duration::from_secs);
+            let interval = Duration::from_millis(interval);
+            run_wait(client, &name, &statuses, timeout, interval, json, &mut out).await
+        }
+        Command::Kill { name } => {
+            let sessions = client.list_sessions().await?;
+            let Some(session) = find_session(&sessions, &name) else {
+                eprintln!("No session named {}", name);
+                return Ok(EXIT_NOT_FOUND);
+            };
+            client.kill_session(&session.id).await?;
+            writeln!(out, "Killed {}", session.name)?;
+            Ok(0)
+        }
+        Command::Schedule { command } => {
+            let mut state = PersistedState::load();
+            // Only for a warning, so a missing server isn't an error here
+            let running = client.session_names().await.unwrap_or_default();
+            let code = run_schedule(command, &mut state, &running, &mut out)?;
+            state.save()?;
+            Ok(code)
+        }
+        Command::Ctl { command } => {
+            let (request, json) = match command {
+                CtlCommand::List { json } => (Request::List, json),
+                CtlCommand::Refresh => (Request::Refresh, false),
+                CtlCommand::Focus { name } => (Request::Focus { session: name }, false),
+                CtlCommand::Toast { message } => (Request::Toast { message }, false),
+            };
+            let response = control::send(control_path, &request).await?;
+            if let Some(error) = response.error {
+                eprintln!("{}", error);
+                return Ok(EXIT_NOT_FOUND);
+            }
+            if let Some(sessions) = response.sessions {
+                if json {
+                    writeln!(out, "{}", serde_json::to_string_pretty(&sessions)?)?;
+                } else {
+                    write!(out, "{}", format_table(&sessions))?;
+                }
+            }
+            Ok(0)
+        }
+        Command::Restore { dry_run } => {
+            let path = Snapshot::default_path();
+            let Some(saved) = Snapshot::load_from(&path)? else {
+                eprintln!("No snapshot at {}", path.display());
+                return Ok(EXIT_NOT_FOUND);
+            };
+            let missing = saved.missing(&client.list_sessions().await?);
+            if missing.is_empty() {
+                writeln!(
+                    out,
+                    "All {} snapshotted sessions are running",
+                    saved.sessions.len()
+                )?;
+                return Ok(0);
+            }
+            let mut failed = false;
+            for session in &missing {
+                if dry_run {
+                    writeln!(out, "Would restore {}", session.name)?;
+                    continue;
+                }
+                match snapshot::restore(client, session).await {
+                    Ok(()) => writeln!(out, "Restored {}", session.name)?,
+                    Err(e) => {
+                        eprintln!("Failed to restore {}: {:#}", session.name, e);
+                        failed = true;
+                    }
+                }
+            }
+            Ok(if failed { 1 } else { 0 })
+        }
+        Command::Export { out: path } => {
+            let state = PersistedState::load();
+            let inventory = inventory::capture(client, &state.notes, &state.tags).await?;
+            match path {
+                Some(path) => {
+                    inventory.save_to(&path)?;
+                    eprintln!(
+                        "Exported {} sessions to {}",
+                        inventory.sessions.len(),
+                        path.display()
+                    );
+                }
+                None => writeln!(out, "{}", inventory.to_json()?)?,
+            }
+            Ok(0)
+        }
+        Command::Import { path } => {
+            let inventory = Inventory::load_from(&path)?;
+            let mut state = PersistedState::load();
+            let outcomes = inventory::import(client, &inventory, &mut state).await?;
+            if let Err(e) = state.save() {
+                eprintln!("Failed to save notes and tags: {:#}", e);
+            }
+            let mut failed = false;
+            for (name, outcome) in outcomes {
+                match outcome {
+                    Outcome::Created => writeln!(out, "Created {}", name)?,
+                    Outcome::Skipped => writeln!(out, "Skipped {} (already running)", name)?,
+                    Outcome::Failed(e) => {
+                        eprintln!("Failed to create {}: {}", name, e);
+                        failed = true;
+                    }
+                }
+            }
+            Ok(if failed { 1 } else { 0 })
+        }
+        Command::McpServe => {
+            mcp::serve(client.clone()).await?;
+            Ok(0)
+        }
+        Command::Skeleton { format, out: path } => {
+            let tree = skeleton::generate_skeleton(&skeleton_root.to_string_lossy()).await?;
+            let text = match format {
+                SkeletonFormat::Text => tree,
+                SkeletonFormat::Md => format_markdown(&tree),
+            };
+            match path {
+                Some(path) => std::fs::write(&path, text)
+                    .with_context(|| format!("Failed to write {}", path.display()))?,
+                None => write!(out, "{}", text)?,
+            }
+            Ok(0)
+        }
+        Command::Completions { shell } => {
+            completions::write_registration(shell, &mut out)?;
+            Ok(0)
+        }
+        Command::Doctor => doctor::run(client, doctor_paths, &mut out).await,
+    }
+}
+
+/// `wait`: report how the wait ended and map it to an exit code
+async fn run_wait<S: Sessions>(
+    sessions: &S,
+    name: &str,
+    statuses: &[AgentStatus],
+    timeout: Option<Duration>,
+    interval: Duration,
+    json: bool,
+    out: &mut impl Write,
+) -> Result<i32> {
+    let outcome = wait::wait_for_status(sessions, name, statuses, timeout, interval).await?;
+    match &outcome {
+        WaitOutcome::Reached(session) | WaitOutcome::TimedOut(session) => {
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(session)?)?;
+            } else if matches!(outcome, WaitOutcome::Reached(_)) {
+                writeln!(out, "{:?}", session.status)?;
+            }
+            if let (WaitOutcome::TimedOut(_), Some(timeout)) = (&outcome, timeout) {
+                eprintln!(
+                    "Timed out after {}s; {} is {:?}",
+                    timeout.as_secs(),
+                    session.name,
+                    session.status
+                );
+            }
+        }
+        WaitOutcome::Gone => eprintln!("No session named {}", name),
+    }
+    Ok(wait_exit_code(&outcome))
+}
+
+/// `schedule`: edit the schedules in `state`, which the caller saves
+fn run_schedule(
+    command: ScheduleCommand,
+    state: &mut PersistedState,
+    running: &[String],
+    out: &mut impl Write,
+) -> Result<i32> {
+    let now = crate::text::unix_now();
+    match command {
+        ScheduleCommand::Add {
+            session,
+            when,
+            prompt,
+        } => {
+            if !running.contains(&session) {
+                eprintln!("Warning: no session named {} is running yet", session);
+            }
+            let id = state.add_schedule(&session, &prompt, when);
+            writeln!(
+                out,
+                "Scheduled #{} for {} at {}",
+                id,
+                session,
+                schedule::format_at(when, now)
+            )?;
+            Ok(0)
+        }
+        ScheduleCommand::List { json } => {
+            if json {
+                writeln!(out, "{}", serde_json::to_string_pretty(&state.schedules)?)?;
+            } else {
+                write!(out, "{}", format_schedules(&state.schedules, now))?;
+            }
+            Ok(0)
+        }
+        ScheduleCommand::Cancel { id } => match state.cancel_schedule(id) {
+            Some(cancelled) => {
+                writeln!(out, "Cancelled #{} for {}", id, cancelled.session)?;
+                Ok(0)
+            }
+            None => {
+                eprintln!("No scheduled prompt #{}", id);
+                Ok(EXIT_NOT_FOUND)
+            }
+        },
+    }
+}
+
+fn format_schedules(schedules: &[ScheduledPrompt], now: u64) -> String {
+    let width = schedules
+        .iter()
+        .map(|s| s.session.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("SESSION".len());
+    let mut table = format!("{:<4}  {:<width$}  {:<32}  PROMPT\n", "ID", "SESSION", "AT");
+    for s in schedules {
+        table.push_str(&format!(
+            "{:<4}  {:<width$}  {:<32}  {}\n",
+            s.id,
+            s.session,
+            schedule::format_at(s.at, now),
+            s.prompt
+        ));
+    }
+    table
+}
+
+fn wait_exit_code(outcome: &WaitOutcome) -> i32 {
+    match outcome {
+        WaitOutcome::Reached(_) => 0,
+        WaitOutcome::TimedOut(_) => EXIT_TIMEOUT,
+        WaitOutcome::Gone => EXIT_NOT_FOUND,
+    }
+}
+
+/// Sessions with git state filled in, as the dashboard shows them
+async fn list_sessions(client: &TmuxClient) -> Result<Vec<TmuxSession>> {
+    let mut sessions = client.list_sessions().await?;
+    for session in &mut sessions {
+        if !session.current_path.is_empty() {
+            session.git = git::lookup(Path::new(&session.current_path)).await;
+        }
+    }
+    PersistedState::load().tags.apply(&mut sessions);
+    Ok(sessions)
+}
+
+/// Match by exact name first, then by session ID
+pub fn find_session<'a>(sessions: &'a [TmuxSession], name: &str) -> Option<&'a TmuxSession> {
+    sessions
+        .iter()
+        .find(|s| s.name == name)
+        .or_else(|| sessions.iter().find(|s| s.id == name))
+}
+
+fn status_exit_code(status: AgentStatus) -> i32 {
+    match status {
+        AgentStatus::Error => EXIT_SESSION_ERROR,
+        _ => 0,
+    }
+}
+
+fn format_table(sessions: &[TmuxSession]) -> String {
+    let width = sessions
+        .iter()
+        .map(|s| s.name.chars().count())
+        .max()
+        .unwrap_or(0)
+        .max("NAME".len());
+    let mut table = format!(
+        "{:<width$}  {:<15}  {:<7}  {:<12}  PATH\n",
+        "NAME", "STATUS", "AGENT", "BRANCH"
+    );
+    for session in sessions {
+        table.push_str(&format!(
+            "{:<width$}  {:<15}  {:<7}  {:<12}  {}\n",
+            session.name,
+            format!("{:?}", session.status),
+            session.agent_kind().label(),
+            session
+                .git
+                .as_ref()
+                .map(|g| g.label())
+                .unwrap_or_else(|| "-".to_string()),
+            session.current_path,
+        ));
+    }
+    table
+}
+
+fn format_status(session: &TmuxSession) -> String {
+    let mut text = format!("{} ({})\n", session.name, session.id);
+    text.push_str(&format!("  Status:   {:?}\n", session.status));
+    text.push_str(&format!("  Agent:    {}\n", session.agent_kind().label()));
+    text.push_str(&format!("  Command:  {}\n", session.current_command));
+    text.push_str(&format!("  Path:     {}\n", session.current_path));
+    if let Some(git) = &session.git {
+        text.push_str(&format!("  Git:      {}\n", git.describe()));
+    }
+    text.push_str(&format!("  Attached: {}\n", session.attached_clients));
+    text
+}
+
+fn format_markdown(tree: &str) -> String {
+    format!("# Project structure\n\n```text\n{}```\n", tree)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::claude::ClaudeMeta;
+    use crate::git::{GitHead, GitInfo};
+    use crate::tmux::fake::FakeSessions;
+
+    fn session(name: &str, status: AgentStatus) -> TmuxSession {
+        TmuxSession {
+            id: "$0".to_string(),
+            name: name.to_string(),
+            created_at: 1_700_000_000,
+            attached_clients: 0,
+            activity_at: 1_700_000_100,
+            current_command: "claude".to_string(),
+            current_path: "/home/me/api".to_string(),
+            pane_title: String::new(),
+            windows: 0,
+            start_command: String::new(),
+            start_path: String::new(),
+            socket: String::new(),
+            git: Some(GitInfo {
+                head: GitHead::Branch("main".to_string()),
+                dirty: true,
+            }),
+            claude: None,
+            aider: None,
+            activity_trend: Vec::new(),
+            tags: Vec::new(),
+            window_tree: None,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_session_json_schema() {
+        let value = serde_json::to_value(session("api", AgentStatus::Idle)).unwrap();
+        assert_eq!(
+            value,
+            serde_json::json!({
+                "id": "$0",
+                "name": "api",
+                "created_at": 1_700_000_000u64,
+                "attached_clients": 0,
+                "activity_at": 1_700_000_100u64,
+                "current_command": "claude",
+                "current_path": "/home/me/api",
+                "git": { "head": { "Branch": "main" }, "dirty": true },
+                "status": "Idle"
+            })
+        );
+
+        let mut detached = session("api", AgentStatus::WaitingForInput);
+        detached.git = Some(GitInfo {
+            head: GitHead::Detached("a1b2c3d".to_string()),
+            dirty: false,
+        });
+        let value = serde_json::to_value(&detached).unwrap();
+        assert_eq!(
+            value["git"]["head"],
+            serde_json::json!({ "Detached": "a1b2c3d" })
+        );
+        assert_eq!(value["status"], "WaitingForInput");
+
+        detached.claude = Some(ClaudeMeta {
+            session_id: "3f2c9a1e".to_string(),
+            model: Some("claude-sonnet-4-5".to_string()),
+            started_at: Some(1_700_000_000),
+        });
+        assert_eq!(
+            serde_json::to_value(&detached).unwrap()["claude"],
+            serde_json::json!({
+                "session_id": "3f2c9a1e",
+                "model": "claude-sonnet-4-5",
+                "started_at": 1_700_000_000u64
+            })
+        );
+
+        detached.git = None;
+        assert!(serde_json::to_value(&detached).unwrap()["git"].is_null());
+    }
+
+    #[test]
+    fn test_session_json_round_trip() {
+        let json = serde_json::to_string(&session("api", AgentStatus::Error)).unwrap();
+        let parsed: TmuxSession = serde_json::from_str(&json).unwrap();
+        assert_eq!(parsed.name, "api");
+        assert_eq!(parsed.status, AgentStatus::Error);
+    }
+
+    #[test]
+    fn test_find_session_by_name_or_id() {
+        let mut other = session("other", AgentStatus::Idle);
+        other.id = "$1".to_string();
+        let sessions = vec![session("api", AgentStatus::Idle), other];
+        assert_eq!(find_session(&sessions, "api").unwrap().id, "$0");
+        assert_eq!(find_session(&sessions, "$1").unwrap().name, "other");
+        assert!(find_session(&sessions, "missing").is_none());
+    }
+
+    #[test]
+    fn test_status_exit_code() {
+        assert_eq!(status_exit_code(AgentStatus::Idle), 0);
+        assert_eq!(status_exit_code(AgentStatus::Busy), 0);
+        assert_eq!(status_exit_code(AgentStatus::Error), EXIT_SESSION_ERROR);
+    }
+
+    #[test]
+    fn test_format_table() {
+        let table = format_table(&[session("api", AgentStatus::Idle)]);
+        let lines: Vec<&str> = table.lines().collect();
+        assert!(lines[0].starts_with("NAME  STATUS"));
+        assert!(lines[1].starts_with("api   Idle"));
+        assert!(lines[1].contains("claude"));
+        assert!(lines[1].contains("main*"));
+        assert!(lines[1].ends_with("/home/me/api"));
+    }
+
+    #[test]
+    fn test_schedule_commands() {
+        let mut state = PersistedState::default();
+        let at = crate::text::unix_now() + 1800;
+        let mut out = Vec::new();
+        let add = ScheduleCommand::Add {
+            session: "payments-fix".to_string(),
+            when: at,
+            prompt: "write up a summary".to_string(),
+        };
+        let running = ["payments-fix".to_string()];
+        assert_eq!(
+            run_schedule(add, &mut state, &running, &mut out).unwrap(),
+            0
+        );
+        let text = String::from_utf8(std::mem::take(&mut out)).unwrap();
+        assert!(
+            text.starts_with("Scheduled #1 for payments-fix at "),
+            "{}",
+            text
+        );
+        assert_eq!(state.schedules.len(), 1);
+
+        let list = ScheduleCommand::List { json: false };
+        run_schedule(list, &mut state, &running, &mut out).unwrap();
+        let text = String::from_utf8(std::mem::take(&mut out)).unwrap();
+        let lines: Vec<&str> = text.lines().collect();
+        assert!(lines[0].starts_with("ID    SESSION       AT"), "{}", text);
+        assert!(lines[1].starts_with("1     payments-fix  "), "{}", text);
+        assert!(lines[1].ends_with("write up a summary"));
+
+        let cancel = |id| ScheduleCommand::Cancel { id };
+        assert_eq!(
+            run_schedule(cancel(1), &mut state, &running, &mut out).unwrap(),
+            0
+        );
+        assert!(state.schedules.is_empty());
+        assert_eq!(
+            run_schedule(cancel(1), &mut state, &running, &mut out).unwrap(),
+            EXIT_NOT_FOUND
+        );
+    }
+
+    #[tokio::test]
+    async fn test_wait_exit_codes() {
+        let fake = FakeSessions::with(&["api"]);
+        *fake.statuses.lock().unwrap() = vec![AgentStatus::Busy, AgentStatus::WaitingForInput];
+        let targets = [AgentStatus::WaitingForInput, AgentStatus::Error];
+        let tick = Duration::from_millis(1);
+
+        let mut out = Vec::new();
+        let code = run_wait(&fake, "api", &targets, None, tick, false, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(code, 0);
+        assert_eq!(String::from_utf8(out).unwrap(), "WaitingForInput\n");
+
+        let mut out = Vec::new();
+        let timeout = Some(Duration::from_millis(20));
+        let code = run_wait(
+            &fake,
+            "api",
+            &[AgentStatus::Idle],
+            timeout,
+            tick,
+            true,
+            &mut out,
+        )
+        .await
+        .unwrap();
+        assert_eq!(code, EXIT_TIMEOUT);
+        let session: serde_json::Value = serde_json::from_slice(&out).unwrap();
+        assert_eq!(session["status"], "WaitingForInput");
+
+        let mut out = Vec::new();
+        let code = run_wait(&fake, "web", &targets, None, tick, false, &mut out)
+            .await
+            .unwrap();
+        assert_eq!(code, EXIT_NOT_FOUND);
+        assert!(out.is_empty());
+    }
+
+    #[test]
+    fn test_format_markdown() {
+        assert_eq!(
+            format_markdown("root/\n└── a.rs\n"),
+            "# Project structure\n\n```text\nroot/\n└── a.rs\n```\n"
+        );
+    }
+}
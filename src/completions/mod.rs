@@ -0,0 +1,135 @@
+//! Shell completions and the man page.
+//!
+//! Completion is dynamic: the script printed by `agent-rusty completions <shell>`
+//! calls back into `agent-rusty` with `COMPLETE=<shell>` set, so session names
+//! come from the tmux server at the moment Tab is pressed.
+
+use clap::CommandFactory;
+use clap_complete::engine::CompletionCandidate;
+use clap_complete::env::Shells;
+use clap_complete::{CompleteEnv, Shell};
+use std::ffi::OsStr;
+use std::io::Write;
+use std::time::Duration;
+
+use crate::cli::Cli;
+use crate::tmux::TmuxClient;
+
+/// Environment variable that turns a run of the binary into a completion request
+pub const COMPLETE_VAR: &str = "COMPLETE";
+
+/// Name of the binary the scripts complete and call back into
+const BIN: &str = "agent-rusty";
+
+/// Tab has to feel instant; a slower tmux just offers no names
+const LOOKUP_TIMEOUT: Duration = Duration::from_millis(500);
+
+/// Answer the shell and exit if this run is a completion request
+///
+/// Must run before anything writes to stdout, and outside a tokio runtime.
+pub fn complete_from_env() {
+    CompleteEnv::with_factory(Cli::command)
+        .var(COMPLETE_VAR)
+        .bin(BIN)
+        .complete();
+}
+
+/// Write the script that registers completions for `shell`
+pub fn write_registration(shell: Shell, out: &mut dyn Write) -> std::io::Result<()> {
+    let shells = Shells::builtins();
+    let completer = shells
+        .completer(&shell.to_string())
+        .ok_or_else(|| std::io::Error::other(format!("no completions for {}", shell)))?;
+    completer.write_registration(COMPLETE_VAR, BIN, BIN, BIN, out)
+}
+
+/// Write the man page for the command line, in roff
+pub fn write_man(out: &mut dyn Write) -> std::io::Result<()> {
+    clap_mangen::Man::new(Cli::command()).render(out)
+}
+
+/// Candidates for an argument naming a session
+///
+/// Asks the default tmux server for names only, without capturing panes.
+/// Any failure, including tmux being absent, yields no candidates and no output.
+pub fn session_names(current: &OsStr) -> Vec<CompletionCandidate> {
+    let Some(prefix) = current.to_str() else {
+        return Vec::new();
+    };
+    let Ok(runtime) = tokio::runtime::Builder::new_current_thread()
+        .enable_all()
+        .build()
+    else {
+        return Vec::new();
+    };
+    let client = TmuxClient::new().with_timeout(LOOKUP_TIMEOUT);
+    let names = runtime.block_on(client.session_names()).unwrap_or_default();
+    matching(names, prefix)
+}
+
+fn matching(names: Vec<String>, prefix: &str) -> Vec<CompletionCandidate> {
+    names
+        .into_iter()
+        .filter(|name| name.starts_with(prefix))
+        .map(CompletionCandidate::new)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn registration(shell: Shell) -> String {
+        let mut out = Vec::new();
+        write_registration(shell, &mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_registration_scripts() {
+        let bash = registration(Shell::Bash);
+        assert!(bash.contains("COMPLETE=\"bash\""), "{}", bash);
+        assert!(
+            bash.lines()
+                .map(str::trim)
+                .any(|l| l.starts_with("complete ") && l.ends_with(" agent-rusty")),
+            "{}",
+            bash
+        );
+
+        let zsh = registration(Shell::Zsh);
+        assert!(zsh.starts_with("#compdef agent-rusty"), "{}", zsh);
+        assert!(zsh.contains("COMPLETE=\"zsh\""), "{}", zsh);
+
+        let fish = registration(Shell::Fish);
+        assert!(
+            fish.starts_with("complete --keep-order --exclusive --command agent-rusty"),
+            "{}",
+            fish
+        );
+        assert!(fish.contains("COMPLETE=fish"), "{}", fish);
+    }
+
+    #[test]
+    fn test_matching_names() {
+        let names = vec!["api".to_string(), "app".to_string(), "web".to_string()];
+        let matched: Vec<_> = matching(names.clone(), "ap")
+            .iter()
+            .map(|c| c.get_value().to_string_lossy().into_owned())
+            .collect();
+        assert_eq!(matched, ["api", "app"]);
+        assert_eq!(matching(names, "").len(), 3);
+    }
+
+    #[test]
+    fn test_man_page() {
+        let mut out = Vec::new();
+        write_man(&mut out).unwrap();
+        let man = String::from_utf8(out).unwrap();
+        assert!(man.starts_with(".ie"), "{}", &man[..80.min(man.len())]);
+        assert!(man.contains(".TH agent-rusty 1"));
+        assert!(man.contains("completions"));
+        // Hidden flags stay out of the manual
+        assert!(!man.contains("generate\\-man"));
+    }
+}
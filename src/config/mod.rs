@@ -0,0 +1,617 @@
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::alerts::AlertRule;
+use crate::bell::BellConfig;
+use crate::clipboard::ClipboardConfig;
+use crate::custom_commands::{self, CustomCommand};
+use crate::error::AgentRustyError;
+use crate::hooks::HookConfig;
+use crate::launcher::Launcher;
+use crate::policy::IdlePolicy;
+use crate::poller::PollRateRule;
+use crate::rules::RuleSpec;
+use crate::session_list::ListLayout;
+use crate::template::{self, Template};
+use crate::theme::{ColorSupport, StatusIcons, ThemeConfig};
+use crate::tmux::{AgentKind, Socket, TitleRule};
+use crate::worktree::WorktreeConfig;
+
+/// User configuration read from `~/.config/agent-rusty/config.toml`
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct Config {
+    /// Initial layout of the session list (`compact` or `table`)
+    pub list_layout: ListLayout,
+    /// Show each session's git branch after its name in the list
+    pub list_show_branch: bool,
+    /// Show the age of sessions created longer ago than this after their
+    /// names in the list; 0 never does
+    pub list_old_after_secs: u64,
+    /// Colors the terminal can show (`truecolor`, `256`, `16` or `none`);
+    /// detected from `NO_COLOR`, `COLORTERM` and `TERM` when unset
+    pub colors: Option<ColorSupport>,
+    /// Glyph shown for each status in the list, detail pane, header and notifications
+    pub status_icons: StatusIcons,
+    /// Palette (`dark` or `light`) and `#rrggbb` colors replacing some of its roles
+    pub theme: ThemeConfig,
+    /// Command that restarts each agent, keyed by agent name (`claude`, `aider`, ...)
+    pub restart_commands: BTreeMap<String, String>,
+    /// Command for opening a session's directory (`{dir}` is replaced);
+    /// defaults to `$VISUAL` or `$EDITOR`
+    pub editor_command: Option<String>,
+    /// The editor opens its own window, so launch it without suspending the TUI
+    pub editor_gui: bool,
+    /// Scrollback lines included when copying a pane with `Y`
+    pub copy_history_lines: usize,
+    /// Remove color and other escape sequences from copied pane content
+    pub copy_strip_ansi: bool,
+    /// Which clipboards copies go to, and in what order
+    pub clipboard: ClipboardConfig,
+    /// How sessions are attached
+    pub attach: AttachConfig,
+    /// Running tmux inside WSL from Windows
+    pub wsl: WslConfig,
+    /// tmux servers to watch together, by socket name (`-L`) or path (`-S`);
+    /// new sessions go to the first. `-L` on the command line watches just that one.
+    pub sockets: Vec<String>,
+    /// Milliseconds between session polls
+    pub poll_interval_ms: u64,
+    /// Slow down to `poll_idle_interval_ms` while no agent is busy
+    pub poll_adaptive: bool,
+    /// Poll interval once sessions have gone quiet (adaptive mode only)
+    pub poll_idle_interval_ms: u64,
+    /// Poll interval while the terminal is unfocused, if it reports focus
+    pub poll_unfocused_interval_ms: u64,
+    /// Capture interval of sessions polled fast, and of the selected session
+    pub poll_fast_interval_ms: u64,
+    /// Capture interval of sessions polled slowly
+    pub poll_slow_interval_ms: u64,
+    /// Poll rate (`slow`, `normal` or `fast`) by session name (`[[poll_rates]]` tables);
+    /// the first match wins and `p` overrides it per session
+    pub poll_rates: Vec<PollRateRule>,
+    /// Seconds of output the activity sparklines cover
+    pub activity_window_secs: u64,
+    /// Status by pane title regex (`[[title_rules]]` tables); a title the matching
+    /// rules agree on skips capturing that pane
+    pub title_rules: Vec<TitleRule>,
+    /// Log filter (e.g. `debug` or `agent_rusty=trace`); `RUST_LOG` takes precedence
+    pub log_level: String,
+    /// Accept `agent-rusty ctl` commands on `$XDG_RUNTIME_DIR/agent-rusty.sock`
+    pub control_socket: bool,
+    /// Commands or URLs triggered by status changes (`[[hooks]]` tables)
+    pub hooks: Vec<HookConfig>,
+    /// Terminal bell for sessions that need attention
+    pub bell: BellConfig,
+    /// Actions for sessions that stay in a status too long (`[[alerts]]` tables)
+    pub alerts: Vec<AlertRule>,
+    /// Seconds between automatic session snapshots; 0 disables them
+    pub snapshot_interval_secs: u64,
+    /// After the first poll, list the sessions waiting for input or stuck on an error
+    pub startup_digest: bool,
+    /// Ask before quitting while any agent is busy or waiting for input
+    pub confirm_quit: bool,
+    /// Print plain lines for screen readers instead of drawing the dashboard
+    pub accessible: bool,
+    /// Rewrite this file with Prometheus metrics after every poll
+    pub metrics_file: Option<PathBuf>,
+    /// Most sessions given their own `session` label in the metrics
+    pub metrics_max_sessions: usize,
+    /// Flag or close sessions that have sat idle for too long
+    pub idle_policy: IdlePolicy,
+    /// Actions chained to other sessions' status changes (`[[rules]]` tables)
+    pub rules: Vec<RuleSpec>,
+    /// Offered when creating a session, ahead of the built-in ones (`[[launchers]]` tables)
+    pub launchers: Vec<Launcher>,
+    /// Where `G` puts new worktrees and which repository it branches from
+    pub worktrees: WorktreeConfig,
+    /// Scripts run against the selected session from `:` or their own key
+    /// (`[[commands]]` tables)
+    pub commands: Vec<CustomCommand>,
+    /// Sets of sessions `A` creates together for a project (`[[template]]` tables)
+    #[serde(rename = "template")]
+    pub templates: Vec<Template>,
+}
+
+/// `[attach]` section of the config
+#[derive(Debug, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct AttachConfig {
+    /// Command that opens a new terminal window running `{cmd}`; detected
+    /// from the environment when unset
+    pub terminal_command: Option<String>,
+    /// Ask which window to attach to when a session has more than one
+    pub pick_window: bool,
+}
+
+impl Default for AttachConfig {
+    fn default() -> Self {
+        Self {
+            terminal_command: None,
+            pick_window: true,
+        }
+    }
+}
+
+/// `[wsl]` section of the config
+#[derive(Debug, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WslConfig {
+    /// Run tmux through `wsl.exe`; on by default on Windows when `wsl.exe` is found
+    pub enabled: Option<bool>,
+    /// Distribution to run tmux in; WSL's default distribution when unset
+    pub distro: Option<String>,
+}
+
+impl Default for Config {
+    fn default() -> Self {
+        Self {
+            list_layout: ListLayout::default(),
+            list_show_branch: false,
+            list_old_after_secs: 86400,
+            colors: None,
+            status_icons: StatusIcons::default(),
+            theme: ThemeConfig::default(),
+            restart_commands: BTreeMap::new(),
+            editor_command: None,
+            editor_gui: false,
+            copy_history_lines: 2000,
+            copy_strip_ansi: true,
+            clipboard: ClipboardConfig::default(),
+            attach: AttachConfig::default(),
+            wsl: WslConfig::default(),
+            sockets: Vec::new(),
+            log_level: "info".to_string(),
+            poll_interval_ms: 1000,
+            poll_adaptive: false,
+            poll_idle_interval_ms: 5000,
+            poll_unfocused_interval_ms: 10000,
+            poll_fast_interval_ms: 250,
+            poll_slow_interval_ms: 30000,
+            poll_rates: Vec::new(),
+            activity_window_secs: 300,
+            title_rules: Vec::new(),
+            control_socket: false,
+            hooks: Vec::new(),
+            bell: BellConfig::default(),
+            alerts: Vec::new(),
+            snapshot_interval_secs: 300,
+            startup_digest: true,
+            confirm_quit: true,
+            accessible: false,
+            metrics_file: None,
+            metrics_max_sessions: 50,
+            idle_policy: IdlePolicy::default(),
+            rules: Vec::new(),
+            launchers: Vec::new(),
+            worktrees: WorktreeConfig::default(),
+            commands: Vec::new(),
+            templates: Vec::new(),
+        }
+    }
+}
+
+impl Config {
+    /// Default location of the config file
+    pub fn default_path() -> PathBuf {
+        dirs::config_dir()
+            .unwrap_or_default()
+            .join("agent-rusty")
+            .join("config.toml")
+    }
+
+    /// Load the config from the default location; a missing file yields defaults
+    pub fn load() -> crate::error::Result<Self> {
+        Self::load_from(&Self::default_path())
+    }
+
+    pub fn load_from(path: &Path) -> crate::error::Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        std::fs::read_to_string(path)
+            .context("Failed to read it")
+            .and_then(|text| Self::parse(&text))
+            .map_err(|e| AgentRustyError::ConfigInvalid {
+                path: path.to_path_buf(),
+                message: format!("{:#}", e),
+            })
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let config: Self = toml::from_str(text)?;
+        for (index, hook) in config.hooks.iter().enumerate() {
+            if hook.run.is_none() && hook.url.is_none() {
+                anyhow::bail!("hooks[{}] needs `run` or `url`", index);
+            }
+        }
+        for (index, rule) in config.rules.iter().enumerate() {
+            if let Err(e) = rule.action() {
+                anyhow::bail!("rules[{}] {}", index, e);
+            }
+        }
+        if let Err(e) = config.clipboard.validate() {
+            anyhow::bail!("clipboard {}", e);
+        }
+        for (index, alert) in config.alerts.iter().enumerate() {
+            if let Err(e) = alert.validate() {
+                anyhow::bail!("alerts[{}] {}", index, e);
+            }
+        }
+        if let Err(e) = custom_commands::validate(&config.commands) {
+            anyhow::bail!("commands{}", e);
+        }
+        if let Err(e) = template::validate(&config.templates) {
+            anyhow::bail!("template{}", e);
+        }
+        let labels: Vec<String> = config.sockets().iter().map(Socket::label).collect();
+        for (index, label) in labels.iter().enumerate() {
+            if labels[..index].contains(label) {
+                anyhow::bail!("sockets[{}] is another socket named '{}'", index, label);
+            }
+        }
+        Ok(config)
+    }
+
+    /// Servers from `sockets`
+    pub fn sockets(&self) -> Vec<Socket> {
+        self.sockets.iter().map(|s| Socket::parse(s)).collect()
+    }
+
+    /// Settings that load but won't look right
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = self.status_icons.warnings();
+        warnings.extend(self.theme.warnings());
+        warnings
+    }
+
+    /// Command that restarts `agent` after it was interrupted
+    pub fn restart_command(&self, agent: AgentKind) -> Option<&str> {
+        if let Some(command) = self.restart_commands.get(agent.label()) {
+            return Some(command);
+        }
+        match agent {
+            AgentKind::Claude => Some("claude --continue"),
+            AgentKind::Aider => Some("aider"),
+            AgentKind::Codex => Some("codex resume --last"),
+            AgentKind::Gemini => Some("gemini"),
+            AgentKind::Shell | AgentKind::Other => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::alerts::AlertAction;
+    use crate::custom_commands::RunMode;
+    use crate::policy::PolicyAction;
+    use crate::poller::PollRate;
+    use crate::tmux::AgentStatus;
+
+    #[test]
+    fn test_parse_config() {
+        let config = Config::parse("list_layout = \"table\"").unwrap();
+        assert_eq!(config.list_layout, ListLayout::Table);
+
+        let config = Config::parse("").unwrap();
+        assert_eq!(config.list_layout, ListLayout::Compact);
+        assert!(!config.list_show_branch);
+        assert_eq!(config.list_old_after_secs, 86400);
+        assert!(config.copy_strip_ansi);
+        assert_eq!(config.copy_history_lines, 2000);
+        assert_eq!(config.metrics_file, None);
+        assert_eq!(config.metrics_max_sessions, 50);
+        assert!(config.startup_digest);
+        assert!(config.confirm_quit);
+        assert!(!Config::parse("confirm_quit = false").unwrap().confirm_quit);
+        assert!(
+            !Config::parse("startup_digest = false")
+                .unwrap()
+                .startup_digest
+        );
+
+        let config =
+            Config::parse("metrics_file = \"/var/lib/node_exporter/agents.prom\"").unwrap();
+        assert_eq!(
+            config.metrics_file.as_deref(),
+            Some(Path::new("/var/lib/node_exporter/agents.prom"))
+        );
+
+        assert!(Config::parse("list_layout = \"grid\"").is_err());
+        assert!(Config::parse("unknown_key = 1").is_err());
+
+        let config = Config::parse("[wsl]\nenabled = true\ndistro = \"Ubuntu\"").unwrap();
+        assert_eq!(config.wsl.enabled, Some(true));
+        assert_eq!(config.wsl.distro.as_deref(), Some("Ubuntu"));
+        assert!(Config::parse("[wsl]\ndistribution = \"Ubuntu\"").is_err());
+
+        let config = Config::parse("[clipboard]\ncommand = \"wl-copy\"").unwrap();
+        assert_eq!(config.clipboard.command.as_deref(), Some("wl-copy"));
+        let config = Config::parse("[clipboard]\norder = [\"osc52\", \"tmux\"]").unwrap();
+        assert_eq!(config.clipboard.order().len(), 2);
+        assert!(Config::parse("[clipboard]\norder = [\"command\"]").is_err());
+        assert!(Config::parse("[clipboard]\norder = [\"xclip\"]").is_err());
+
+        let config = Config::parse("[attach]\nterminal_command = \"kitty {cmd}\"").unwrap();
+        assert_eq!(
+            config.attach.terminal_command.as_deref(),
+            Some("kitty {cmd}")
+        );
+        assert!(config.attach.pick_window);
+        let config = Config::parse("[attach]\npick_window = false").unwrap();
+        assert!(!config.attach.pick_window);
+    }
+
+    #[test]
+    fn test_parse_idle_policy() {
+        let config = Config::parse("").unwrap();
+        assert!(!config.idle_policy.enabled());
+
+        let config = Config::parse(
+            "[idle_policy]\nafter_hours = 24\naction = \"kill\"\nexempt = [\"notes\"]",
+        )
+        .unwrap();
+        assert_eq!(config.idle_policy.threshold_secs(), 24 * 3600);
+        assert_eq!(config.idle_policy.action, PolicyAction::Kill);
+        assert_eq!(config.idle_policy.exempt, ["notes"]);
+
+        let config = Config::parse("[idle_policy]\nafter_hours = 0.5").unwrap();
+        assert_eq!(config.idle_policy.threshold_secs(), 1800);
+        assert_eq!(config.idle_policy.action, PolicyAction::Flag);
+
+        assert!(Config::parse("[idle_policy]\naction = \"delete\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_rules() {
+        let config = Config::parse(
+            "[[rules]]\nname = \"tests after refactor\"\nwhen = \"refactor*\"\non = \"busy->idle\"\nsend_to = \"tests\"\nprompt = \"run the suite\"\n\n[[rules]]\non = \"error\"\nnotify = \"{session} failed\"\nonce = true",
+        )
+        .unwrap();
+        assert_eq!(config.rules.len(), 2);
+        assert_eq!(config.rules[0].when, "refactor*");
+        assert_eq!(config.rules[1].when, "*");
+        assert!(config.rules[1].once && config.rules[1].enabled);
+
+        let error = Config::parse("[[rules]]\non = \"idle\"\nrun = \"make\"\nnotify = \"done\"")
+            .unwrap_err();
+        assert!(error.to_string().contains("rules[0]"), "{}", error);
+        assert!(Config::parse("[[rules]]\non = \"idle\"\nsend_to = \"tests\"").is_err());
+        assert!(Config::parse("[[rules]]\non = \"asleep\"\nrun = \"make\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_launchers() {
+        let config = Config::parse(
+            "[[launchers]]\nname = \"Claude Code\"\ncommand = \"claude\"\nask_dir = true\n\n[[launchers]]\nname = \"Scratch shell\"\nask_command = true",
+        )
+        .unwrap();
+        assert_eq!(config.launchers.len(), 2);
+        assert_eq!(config.launchers[0].command.as_deref(), Some("claude"));
+        assert!(config.launchers[0].ask_dir);
+        assert_eq!(config.launchers[1].command, None);
+        assert!(!config.launchers[1].ask_dir);
+        assert!(config.launchers[1].ask_command);
+
+        assert!(Config::parse("[[launchers]]\ncommand = \"claude\"").is_err());
+        assert!(Config::parse("[[launchers]]\nname = \"x\"\ndir = \"/tmp\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_commands() {
+        let config = Config::parse(
+            "[[commands]]\nname = \"tests\"\nrun = \"cargo test\"\nmode = \"window\"\nkey = \"L\"\n\n\
+             [[commands]]\nname = \"pr\"\nrun = \"gh pr view --web\"",
+        )
+        .unwrap();
+        assert_eq!(config.commands.len(), 2);
+        assert_eq!(config.commands[0].mode, RunMode::Window);
+        assert_eq!(config.commands[0].key, Some('L'));
+        assert_eq!(config.commands[1].mode, RunMode::Background);
+        assert_eq!(config.commands[1].key, None);
+
+        assert!(Config::parse("[[commands]]\nname = \"x\"\nrun = \"x\"\nkey = \"ab\"").is_err());
+        assert!(Config::parse("[[commands]]\nname = \"x\"\nrun = \"x\"\nmode = \"tab\"").is_err());
+        let error = Config::parse(
+            "[[commands]]\nname = \"x\"\nrun = \"a\"\n\n[[commands]]\nname = \"x\"\nrun = \"b\"",
+        )
+        .unwrap_err();
+        assert_eq!(error.to_string(), "commands[1] repeats the name 'x'");
+        let error =
+            Config::parse("[[commands]]\nname = \"x\"\nrun = \"x\"\nkey = \"d\"").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "commands[0] key 'd' is used by agent-rusty"
+        );
+    }
+
+    #[test]
+    fn test_parse_templates() {
+        let config = Config::parse(
+            "[[template]]\nname = \"web\"\n\n\
+             [[template.session]]\nname = \"{project}-agent\"\ncommand = \"claude\"\n\n\
+             [[template.session]]\nname = \"{project}-server\"\ndir = \"frontend\"",
+        )
+        .unwrap();
+        assert_eq!(config.templates.len(), 1);
+        let sessions = &config.templates[0].sessions;
+        assert_eq!(sessions[0].command.as_deref(), Some("claude"));
+        assert_eq!(sessions[1].dir.as_deref(), Some("frontend"));
+
+        let error = Config::parse("[[template]]\nname = \"web\"\nsession = []").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "template[0] needs a [[template.session]]"
+        );
+    }
+
+    #[test]
+    fn test_parse_sockets() {
+        let config = Config::parse("sockets = [\"work\", \"/tmp/tmux-1000/personal\"]").unwrap();
+        assert_eq!(
+            config.sockets(),
+            [
+                Socket::Name("work".to_string()),
+                Socket::Path(PathBuf::from("/tmp/tmux-1000/personal"))
+            ]
+        );
+        // Labels prefix session ids, so they must tell the servers apart
+        let error = Config::parse("sockets = [\"work\", \"/run/work\"]").unwrap_err();
+        assert_eq!(
+            error.to_string(),
+            "sockets[1] is another socket named 'work'"
+        );
+    }
+
+    #[test]
+    fn test_parse_hooks() {
+        let config = Config::parse(
+            "[[hooks]]\non = [\"waiting-for-input\", \"busy->idle\"]\nrun = \"notify-send {session}\"\n\n[[hooks]]\non = [\"error\"]\nurl = \"https://example.com/hook\"\ndebounce_secs = 60",
+        )
+        .unwrap();
+        assert_eq!(config.hooks.len(), 2);
+        assert_eq!(config.hooks[0].debounce_secs, 10);
+        assert_eq!(config.hooks[1].debounce_secs, 60);
+
+        assert!(Config::parse("[[hooks]]\non = [\"error\"]").is_err());
+        assert!(Config::parse("[[hooks]]\non = [\"asleep\"]\nrun = \"true\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_bell() {
+        let config = Config::parse(
+            "[bell]\nwaiting_for_input = true\nsequence = \"\\u001b[?5h\\u001b[?5l\"\nmin_interval_secs = 2",
+        )
+        .unwrap();
+        assert!(config.bell.waiting_for_input);
+        assert!(!config.bell.error);
+        assert_eq!(config.bell.sequence, "\x1b[?5h\x1b[?5l");
+        assert_eq!(config.bell.min_interval_secs, 2);
+        assert_eq!(config.bell.debounce_secs, 30);
+        assert_eq!(Config::default().bell.sequence, "\u{7}");
+
+        assert!(Config::parse("[bell]\npermission_prompt = true").is_err());
+    }
+
+    #[test]
+    fn test_parse_colors() {
+        assert_eq!(Config::default().colors, None);
+        let config = Config::parse("colors = \"256\"").unwrap();
+        assert_eq!(config.colors, Some(ColorSupport::Ansi256));
+        let config = Config::parse("colors = \"none\"").unwrap();
+        assert_eq!(config.colors, Some(ColorSupport::Monochrome));
+        assert!(Config::parse("colors = \"8\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_poll_rates() {
+        let config = Config::parse(
+            "poll_fast_interval_ms = 200\n\n[[poll_rates]]\nwhen = \"batch-*\"\nrate = \"slow\"",
+        )
+        .unwrap();
+        assert_eq!(config.poll_fast_interval_ms, 200);
+        assert_eq!(config.poll_slow_interval_ms, 30000);
+        assert_eq!(config.poll_rates.len(), 1);
+        assert_eq!(config.poll_rates[0].when, "batch-*");
+        assert_eq!(config.poll_rates[0].rate, PollRate::Slow);
+        assert!(Config::parse("[[poll_rates]]\nwhen = \"*\"\nrate = \"turbo\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_status_icons() {
+        let config =
+            Config::parse("[status_icons]\nwaiting_for_input = \"\u{f128}\"\nerror = \"E\"")
+                .unwrap();
+        assert_eq!(config.status_icons.waiting_for_input, "\u{f128}");
+        assert_eq!(config.status_icons.error, "E");
+        assert_eq!(config.status_icons.busy, "●");
+        assert!(config.status_icons.spinner);
+        assert!(config.warnings().is_empty());
+
+        let config = Config::parse("[status_icons]\nspinner = false\nbusy = \"*\"").unwrap();
+        assert!(!config.status_icons.spinner);
+        assert_eq!(config.status_icons.busy, "*");
+
+        let config = Config::parse("[status_icons]\nbusy = \"🔥\"").unwrap();
+        assert_eq!(config.warnings().len(), 1);
+        assert!(Config::parse("[status_icons]\npermission = \"!\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_theme() {
+        let config = Config::parse("[theme]\nname = \"light\"\naccent = \"#d97757\"").unwrap();
+        assert_eq!(config.theme.name, "light");
+        assert_eq!(config.theme.accent.as_deref(), Some("#d97757"));
+        assert!(config.warnings().is_empty());
+
+        // A bad color loads, with a warning for the footer
+        let config = Config::parse("[theme]\nfg = \"#12345\"").unwrap();
+        assert_eq!(config.theme.name, "dark");
+        assert_eq!(config.warnings(), ["theme.fg \"#12345\" is not #rrggbb"]);
+        assert!(Config::parse("[theme]\nborder = \"#000000\"").is_err());
+    }
+
+    #[test]
+    fn test_parse_alerts() {
+        let config = Config::parse(
+            "[[alerts]]\nstatus = \"error\"\nfor_secs = 120\naction = \"notify\"\n\n\
+             [[alerts]]\nwhen = \"batch-*\"\nstatus = \"busy\"\nfor_secs = 2700\n\
+             action = \"hook\"\nrun = \"page-oncall {session}\"",
+        )
+        .unwrap();
+        assert_eq!(config.alerts.len(), 2);
+        assert_eq!(config.alerts[0].status, AgentStatus::Error);
+        assert_eq!(config.alerts[0].when, "*");
+        assert_eq!(config.alerts[1].action, AlertAction::Hook);
+
+        let error =
+            Config::parse("[[alerts]]\nstatus = \"error\"\nfor_secs = 120\naction = \"hook\"")
+                .unwrap_err();
+        assert_eq!(error.to_string(), "alerts[0] `hook` needs `run`");
+        assert!(
+            Config::parse("[[alerts]]\nstatus = \"stuck\"\nfor_secs = 1\naction = \"bell\"")
+                .is_err()
+        );
+        assert!(
+            Config::parse("[[alerts]]\nstatus = \"idle\"\nfor_secs = 1\naction = \"page\"")
+                .is_err()
+        );
+    }
+
+    #[test]
+    fn test_parse_title_rules() {
+        let config = Config::parse(
+            "[[title_rules]]\ntitle = \"^✳\"\nstatus = \"idle\"\n\n\
+             [[title_rules]]\ntitle = \"^[⠁-⣿]\"\nstatus = \"busy\"",
+        )
+        .unwrap();
+        assert_eq!(config.title_rules.len(), 2);
+        assert_eq!(config.title_rules[1].status, AgentStatus::Busy);
+        assert!(config.title_rules[0].pattern.is_match("✳ Fix login bug"));
+
+        assert!(Config::parse("[[title_rules]]\ntitle = \"(\"\nstatus = \"idle\"").is_err());
+        assert!(Config::parse("[[title_rules]]\ntitle = \"x\"\nstatus = \"asleep\"").is_err());
+        assert!(Config::parse("[[title_rules]]\ntitle = \"x\"").is_err());
+    }
+
+    #[test]
+    fn test_restart_command_overrides() {
+        let config = Config::parse("[restart_commands]\naider = \"aider --restore-chat-history\"\nshell = \"claude --continue\"").unwrap();
+        assert_eq!(
+            config.restart_command(AgentKind::Aider),
+            Some("aider --restore-chat-history")
+        );
+        assert_eq!(
+            config.restart_command(AgentKind::Claude),
+            Some("claude --continue")
+        );
+        assert_eq!(
+            config.restart_command(AgentKind::Shell),
+            Some("claude --continue")
+        );
+        assert_eq!(Config::default().restart_command(AgentKind::Other), None);
+    }
+}
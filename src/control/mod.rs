@@ -0,0 +1,302 @@
+//! Control socket for the running dashboard.
+//!
+//! Clients send one JSON request per line, e.g. `{"command":"focus","session":"api"}`,
+//! and get one JSON response line back. Mutations become [`Action`]s on the app's
+//! channel; `list` is answered from the poller's latest snapshot.
+
+use serde::{Deserialize, Serialize};
+use std::io::ErrorKind;
+use std::os::unix::fs::PermissionsExt;
+use std::path::{Path, PathBuf};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{UnixListener, UnixStream};
+use tokio::sync::{mpsc, watch};
+
+use crate::actions::Action;
+use crate::commands::find_session;
+use crate::instance;
+use crate::notification::Level;
+use crate::tmux::TmuxSession;
+
+/// Control socket location; a tmux socket name gets its own control socket
+pub fn socket_path(socket_name: Option<&str>) -> PathBuf {
+    let file = match socket_name {
+        Some(name) => format!("agent-rusty-{}.sock", name),
+        None => "agent-rusty.sock".to_string(),
+    };
+    instance::runtime_dir().join(file)
+}
+
+/// A command sent to the running instance
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "command", rename_all = "snake_case")]
+pub enum Request {
+    /// Return the current session list
+    List,
+    /// Poll tmux now instead of waiting for the next interval
+    Refresh,
+    /// Select a session (by name or ID) in the list
+    Focus { session: String },
+    /// Show a message in the footer
+    Toast { message: String },
+}
+
+/// Reply to a [`Request`]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Response {
+    pub ok: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub sessions: Option<Vec<TmuxSession>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+impl Response {
+    fn ok() -> Self {
+        Self {
+            ok: true,
+            ..Default::default()
+        }
+    }
+
+    fn error(message: impl Into<String>) -> Self {
+        Self {
+            ok: false,
+            error: Some(message.into()),
+            ..Default::default()
+        }
+    }
+}
+
+/// The bound control socket; the socket file is removed on drop
+pub struct ControlServer {
+    path: PathBuf,
+    listener: UnixListener,
+}
+
+impl ControlServer {
+    /// Bind `path` readable and writable by the owner only, replacing a stale socket
+    pub async fn bind(path: &Path) -> std::io::Result<Self> {
+        if let Some(parent) = path.parent() {
+            instance::create_private_dir(parent)?;
+        }
+        let listener = match UnixListener::bind(path) {
+            Err(e) if e.kind() == ErrorKind::AddrInUse => {
+                if UnixStream::connect(path).await.is_ok() {
+                    return Err(std::io::Error::new(
+                        ErrorKind::AddrInUse,
+                        format!("{} is served by another process", path.display()),
+                    ));
+                }
+                std::fs::remove_file(path)?;
+                UnixListener::bind(path)?
+            }
+            result => result?,
+        };
+        std::fs::set_permissions(path, std::fs::Permissions::from_mode(0o600))?;
+        Ok(Self {
+            path: path.to_path_buf(),
+            listener,
+        })
+    }
+
+    /// Accept clients until the app's channel closes
+    pub async fn serve(
+        self,
+        actions: mpsc::UnboundedSender<Action>,
+        sessions: watch::Receiver<Vec<TmuxSession>>,
+    ) {
+        loop {
+            let stream = tokio::select! {
+                accepted = self.listener.accept() => match accepted {
+                    Ok((stream, _)) => stream,
+                    Err(e) => {
+                        tracing::warn!("Control socket accept failed: {}", e);
+                        return;
+                    }
+                },
+                _ = actions.closed() => return,
+            };
+            tokio::spawn(serve_client(stream, actions.clone(), sessions.clone()));
+        }
+    }
+}
+
+impl Drop for ControlServer {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+async fn serve_client(
+    stream: UnixStream,
+    actions: mpsc::UnboundedSender<Action>,
+    sessions: watch::Receiver<Vec<TmuxSession>>,
+) {
+    let (reader, mut writer) = stream.into_split();
+    let mut lines = BufReader::new(reader).lines();
+    while let Ok(Some(line)) = lines.next_line().await {
+        if line.trim().is_empty() {
+            continue;
+        }
+        let response = match serde_json::from_str::<Request>(&line) {
+            Ok(request) => {
+                tracing::debug!(?request, "Control request");
+                handle(request, &actions, &sessions)
+            }
+            Err(e) => Response::error(format!("Invalid request: {}", e)),
+        };
+        let Ok(mut reply) = serde_json::to_string(&response) else {
+            return;
+        };
+        reply.push('\n');
+        if writer.write_all(reply.as_bytes()).await.is_err() {
+            return;
+        }
+    }
+}
+
+fn handle(
+    request: Request,
+    actions: &mpsc::UnboundedSender<Action>,
+    sessions: &watch::Receiver<Vec<TmuxSession>>,
+) -> Response {
+    let action = match request {
+        Request::List => {
+            return Response {
+                ok: true,
+                sessions: Some(sessions.borrow().clone()),
+                error: None,
+            };
+        }
+        Request::Refresh => Action::Refresh,
+        Request::Focus { session } => {
+            if find_session(&sessions.borrow(), &session).is_none() {
+                return Response::error(format!("No session named {}", session));
+            }
+            Action::FocusSession(session)
+        }
+        Request::Toast { message } => Action::Notify(Level::Info, message),
+    };
+    match actions.send(action) {
+        Ok(()) => Response::ok(),
+        Err(_) => Response::error("The dashboard is shutting down"),
+    }
+}
+
+/// Send `request` to the instance listening on `path`
+pub async fn send(path: &Path, request: &Request) -> anyhow::Result<Response> {
+    use anyhow::Context;
+
+    let stream = UnixStream::connect(path)
+        .await
+        .with_context(|| format!("No dashboard is listening on {}", path.display()))?;
+    let (reader, mut writer) = stream.into_split();
+    let mut line = serde_json::to_string(request)?;
+    line.push('\n');
+    writer.write_all(line.as_bytes()).await?;
+    let reply = BufReader::new(reader)
+        .lines()
+        .next_line()
+        .await?
+        .context("The dashboard closed the connection")?;
+    Ok(serde_json::from_str(&reply)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tmux::AgentStatus;
+
+    fn socket(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("agent-rusty-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("agent-rusty.sock")
+    }
+
+    #[test]
+    fn test_request_format() {
+        let request: Request =
+            serde_json::from_str(r#"{"command":"focus","session":"api"}"#).unwrap();
+        assert_eq!(
+            request,
+            Request::Focus {
+                session: "api".to_string()
+            }
+        );
+        assert_eq!(
+            serde_json::to_string(&Request::Refresh).unwrap(),
+            r#"{"command":"refresh"}"#
+        );
+        assert!(serde_json::from_str::<Request>(r#"{"command":"explode"}"#).is_err());
+        assert_eq!(
+            serde_json::to_string(&Response::error("nope")).unwrap(),
+            r#"{"ok":false,"error":"nope"}"#
+        );
+    }
+
+    #[tokio::test]
+    async fn test_round_trip() {
+        let path = socket("control");
+        let server = ControlServer::bind(&path).await.unwrap();
+        let mode = std::fs::metadata(&path).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o600);
+
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        let mut api = TmuxSession::new("$0".to_string(), "api".to_string());
+        api.status = AgentStatus::Busy;
+        let (_sessions_tx, sessions_rx) = watch::channel(vec![api]);
+        tokio::spawn(server.serve(tx, sessions_rx));
+
+        // Query: answered from the latest poll
+        let response = send(&path, &Request::List).await.unwrap();
+        assert!(response.ok);
+        let sessions = response.sessions.unwrap();
+        assert_eq!(sessions.len(), 1);
+        assert_eq!(sessions[0].name, "api");
+        assert_eq!(sessions[0].status, AgentStatus::Busy);
+
+        // Mutations: become actions on the app's channel
+        let toast = Request::Toast {
+            message: "build finished".to_string(),
+        };
+        assert!(send(&path, &toast).await.unwrap().ok);
+        assert!(
+            matches!(rx.recv().await, Some(Action::Notify(Level::Info, m)) if m == "build finished")
+        );
+
+        let focus = Request::Focus {
+            session: "api".to_string(),
+        };
+        assert!(send(&path, &focus).await.unwrap().ok);
+        assert!(matches!(rx.recv().await, Some(Action::FocusSession(s)) if s == "api"));
+
+        let missing = Request::Focus {
+            session: "web".to_string(),
+        };
+        let response = send(&path, &missing).await.unwrap();
+        assert!(!response.ok);
+        assert_eq!(response.error.as_deref(), Some("No session named web"));
+        assert!(rx.try_recv().is_err());
+
+        // The socket goes away with the app's channel
+        drop(rx);
+        for _ in 0..100 {
+            if !path.exists() {
+                break;
+            }
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        assert!(!path.exists());
+    }
+
+    #[tokio::test]
+    async fn test_stale_socket_is_replaced() {
+        let path = socket("control-stale");
+        drop(std::os::unix::net::UnixListener::bind(&path).unwrap());
+        let server = ControlServer::bind(&path).await.unwrap();
+        assert!(ControlServer::bind(&path).await.is_err());
+        drop(server);
+        assert!(!path.exists());
+    }
+}
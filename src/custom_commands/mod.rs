@@ -0,0 +1,317 @@
+//! Commands from the config run with the selected session as context:
+//! tailing the agent's log, opening its PR, running the tests in a window
+//! next to it.
+//!
+//! `{session_name}`, `{session_id}` and `{cwd}` in the template are replaced
+//! as [`script::expand`] describes, so values are never read as shell syntax.
+
+use anyhow::Context as _;
+use serde::Deserialize;
+use std::path::Path;
+use std::process::Stdio;
+use tokio::process::Command;
+
+use crate::script;
+
+/// Where a custom command runs
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum RunMode {
+    /// Without a terminal; the outcome shows in the footer
+    #[default]
+    Background,
+    /// In a new tmux window of the session
+    Window,
+    /// In this terminal, with the dashboard suspended until it exits
+    Interactive,
+}
+
+impl RunMode {
+    pub fn label(self) -> &'static str {
+        match self {
+            RunMode::Background => "background",
+            RunMode::Window => "window",
+            RunMode::Interactive => "interactive",
+        }
+    }
+}
+
+/// `[[commands]]` entry of the config
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct CustomCommand {
+    /// Shown in the commands menu, and as the window name
+    pub name: String,
+    /// Shell command; `{session_name}`, `{session_id}` and `{cwd}` are replaced
+    pub run: String,
+    #[serde(default)]
+    pub mode: RunMode,
+    /// Key that runs it from the session list; can't be one agent-rusty uses
+    #[serde(default)]
+    pub key: Option<char>,
+}
+
+/// Keys the session list uses itself
+pub const RESERVED_KEYS: &str = " #/:?ABCDEFGJKMNOPRSTUVWXY`bcdefhjklmnopqrstvwy123456789";
+
+/// Why a list of commands can't be used, if it can't
+pub fn validate(commands: &[CustomCommand]) -> Result<(), String> {
+    for (index, command) in commands.iter().enumerate() {
+        if command.name.trim().is_empty() {
+            return Err(format!("[{}] needs a `name`", index));
+        }
+        let earlier = &commands[..index];
+        if earlier.iter().any(|c| c.name == command.name) {
+            return Err(format!("[{}] repeats the name '{}'", index, command.name));
+        }
+        if let Some(key) = command.key {
+            if RESERVED_KEYS.contains(key) {
+                return Err(format!("[{}] key '{}' is used by agent-rusty", index, key));
+            }
+            if earlier.iter().any(|c| c.key == Some(key)) {
+                return Err(format!("[{}] repeats the key '{}'", index, key));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// The session a command runs against
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct CommandContext {
+    pub session_id: String,
+    pub session_name: String,
+    /// Working directory of the session's active pane
+    pub cwd: String,
+}
+
+/// Placeholders, with the shell variable holding each one's value
+const VARIABLES: [(&str, &str); 3] = [
+    ("session_name", "AGENT_RUSTY_SESSION_NAME"),
+    ("session_id", "AGENT_RUSTY_SESSION_ID"),
+    ("cwd", "AGENT_RUSTY_CWD"),
+];
+
+/// Turn `template` into a script that sets `context`'s values in variables
+/// and refers to them where the placeholders were
+pub fn expand(template: &str, context: &CommandContext) -> String {
+    let values = [&context.session_name, &context.session_id, &context.cwd];
+    let placeholders: Vec<_> = VARIABLES
+        .iter()
+        .zip(values)
+        .map(|(&(name, variable), value)| (name, variable, value.as_str()))
+        .collect();
+    script::expand(template, &placeholders)
+}
+
+/// Run `script` with `sh` in `dir`, returning its output
+///
+/// The error carries the exit status, with the output as its cause.
+pub async fn run_background(script: &str, dir: Option<&Path>) -> anyhow::Result<String> {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(script)
+        .stdin(Stdio::null())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped());
+    if let Some(dir) = dir {
+        command.current_dir(dir);
+    }
+    let output = command.output().await.context("Failed to run sh")?;
+    let mut text = String::from_utf8_lossy(&output.stdout).into_owned();
+    text.push_str(&String::from_utf8_lossy(&output.stderr));
+    let text = text.trim_end().to_string();
+    if output.status.success() {
+        Ok(text)
+    } else {
+        Err(anyhow::anyhow!(text).context(format!("exited with {}", output.status)))
+    }
+}
+
+/// Last line of a command's output, for the footer
+pub fn last_line(output: &str) -> Option<&str> {
+    output.lines().map(str::trim).rfind(|line| !line.is_empty())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const NASTY: &str = r#"it's $(touch /tmp/pwned) `id` "q" \ $HOME; rm -rf ~ *"#;
+
+    fn context(session_name: &str) -> CommandContext {
+        CommandContext {
+            session_id: "$3".to_string(),
+            session_name: session_name.to_string(),
+            cwd: "/work/my api".to_string(),
+        }
+    }
+
+    /// Output of the script `template` expands to
+    fn run(template: &str, context: &CommandContext) -> String {
+        let script = expand(template, context);
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&script)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", script);
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    /// Words the shell sees when running `printf '%s|'` with `args`
+    fn words(args: &str, context: &CommandContext) -> String {
+        run(&format!("printf '%s|' {}", args), context)
+    }
+
+    /// The script `template` expands to, without the variables it sets
+    fn body(template: &str) -> String {
+        let script = expand(template, &context("api"));
+        script
+            .lines()
+            .skip(VARIABLES.len())
+            .collect::<Vec<_>>()
+            .join("\n")
+    }
+
+    #[test]
+    fn test_expand_by_quoting() {
+        assert_eq!(
+            expand("cd {cwd}", &context("api")),
+            "AGENT_RUSTY_SESSION_NAME='api'\nAGENT_RUSTY_SESSION_ID='$3'\n\
+             AGENT_RUSTY_CWD='/work/my api'\ncd \"$AGENT_RUSTY_CWD\""
+        );
+        assert_eq!(
+            body("gh pr view --web {session_name}"),
+            r#"gh pr view --web "$AGENT_RUSTY_SESSION_NAME""#
+        );
+        assert_eq!(
+            body("echo '{session_id}: x'"),
+            r#"echo ''"$AGENT_RUSTY_SESSION_ID"': x'"#
+        );
+        assert_eq!(
+            body(r#"echo "{session_id} in {cwd}""#),
+            r#"echo "${AGENT_RUSTY_SESSION_ID} in ${AGENT_RUSTY_CWD}""#
+        );
+        assert_eq!(
+            body(r#"echo "$(basename {cwd})""#),
+            r#"echo "$(basename "$AGENT_RUSTY_CWD")""#
+        );
+
+        // Quotes of the other kind don't end the current one
+        assert_eq!(
+            body(r#"echo "it's {session_name}""#),
+            r#"echo "it's ${AGENT_RUSTY_SESSION_NAME}""#
+        );
+        assert_eq!(
+            body(r#"echo '"{session_name}'"#),
+            r#"echo '"'"$AGENT_RUSTY_SESSION_NAME"''"#
+        );
+    }
+
+    #[test]
+    fn test_expand_leaves_other_braces() {
+        let api = context("api");
+        // Unknown placeholders, stray braces and escaped ones stay as written
+        assert_eq!(
+            body("echo {session} {session_name"),
+            "echo {session} {session_name"
+        );
+        assert_eq!(run("echo '{a: 1}' {session_name}", &api), "{a: 1} api\n");
+        assert_eq!(
+            run(r"echo \{session_name} {session_name}", &api),
+            "{session_name} api\n"
+        );
+        // Single quotes take backslashes literally, so a placeholder after one expands
+        assert_eq!(run(r"echo '\{cwd}'", &api), "\\/work/my api\n");
+
+        // Values are substituted once, never re-expanded
+        assert_eq!(run("echo {session_name}", &context("{cwd}")), "{cwd}\n");
+    }
+
+    #[test]
+    fn test_shell_safety_in_every_quoting() {
+        let nasty = context(NASTY);
+        assert_eq!(words("{session_name}", &nasty), format!("{}|", NASTY));
+        assert_eq!(words("'{session_name}'", &nasty), format!("{}|", NASTY));
+        assert_eq!(words(r#""{session_name}""#, &nasty), format!("{}|", NASTY));
+
+        // Glued to other text in each quoting, the value stays one word
+        assert_eq!(
+            words(
+                r#"pre{session_name}'-{session_name}-'"({session_name})""#,
+                &nasty
+            ),
+            format!("pre{0}-{0}-({0})|", NASTY)
+        );
+        assert_eq!(
+            words("{session_name} {cwd}", &nasty),
+            format!("{}|/work/my api|", NASTY)
+        );
+
+        let quoted = context("'\"'\"");
+        for args in ["{session_name}", "'{session_name}'", r#""{session_name}""#] {
+            assert_eq!(words(args, &quoted), "'\"'\"|");
+        }
+        assert_eq!(words("{session_name}", &context("")), "|");
+        assert_eq!(words("\"a\\\"{session_name}\"", &context("b")), "a\"b|");
+    }
+
+    #[test]
+    fn test_substitution_in_double_quotes_is_not_injected() {
+        let dir = std::env::temp_dir().join(format!("ar-inject-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let template = r#"cd {cwd} && echo "$(basename {session_name})""#;
+        let context = CommandContext {
+            session_id: "$1".to_string(),
+            session_name: "x;touch pwned".to_string(),
+            cwd: dir.display().to_string(),
+        };
+        assert_eq!(run(template, &context), "x;touch pwned\n");
+        assert!(!dir.join("pwned").exists());
+
+        // Nested substitutions and subshells get back to the quoting around them
+        assert_eq!(
+            run(
+                r#"echo "$( (echo "{session_name}") ) [{session_name}]""#,
+                &context
+            ),
+            "x;touch pwned [x;touch pwned]\n"
+        );
+        assert!(!dir.join("pwned").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_validate() {
+        let command = |name: &str, key| CustomCommand {
+            name: name.to_string(),
+            run: "true".to_string(),
+            mode: RunMode::Background,
+            key,
+        };
+        assert!(validate(&[command("tests", Some('L')), command("log", None)]).is_ok());
+        assert!(validate(&[command(" ", None)]).is_err());
+        assert!(validate(&[command("tests", None), command("tests", None)]).is_err());
+        assert!(validate(&[command("a", Some('L')), command("b", Some('L'))]).is_err());
+        // Keys the session list uses can't be bound
+        for key in ['d', 'T', ':', '1'] {
+            assert!(validate(&[command("a", Some(key))]).is_err());
+        }
+    }
+
+    #[tokio::test]
+    async fn test_run_background() {
+        let dir = std::env::temp_dir();
+        let output = run_background("pwd; echo done", Some(&dir)).await.unwrap();
+        assert_eq!(last_line(&output), Some("done"));
+
+        let error = run_background("echo broke >&2; exit 3", None)
+            .await
+            .unwrap_err();
+        let chain: Vec<String> = error.chain().map(ToString::to_string).collect();
+        assert_eq!(chain, ["exited with exit status: 3", "broke"]);
+        assert_eq!(last_line("  \n"), None);
+    }
+}
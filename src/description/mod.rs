@@ -0,0 +1,168 @@
+//! One-line descriptions of what each session is for.
+//!
+//! A session created here is described by the command its launcher ran and
+//! the directory it started in; one created elsewhere by its pane's start
+//! command and the session's start directory, as tmux reports them the first
+//! time it is listed. Descriptions are kept by session name, can be edited by
+//! hand, and are forgotten once the name has been gone for 30 days (see
+//! [`crate::seen`]).
+
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+
+use crate::seen::{SeenByName, Sighted};
+use crate::tmux::TmuxSession;
+
+/// A session's description and when its name was last listed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct DescriptionRecord {
+    pub text: String,
+    /// Unix time the session was last listed, to a day's resolution
+    pub seen_at: u64,
+}
+
+impl Sighted for DescriptionRecord {
+    fn seen_at(&mut self) -> &mut u64 {
+        &mut self.seen_at
+    }
+}
+
+/// Descriptions by session name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Descriptions {
+    entries: SeenByName<DescriptionRecord>,
+}
+
+impl Descriptions {
+    pub fn get(&self, name: &str) -> Option<&str> {
+        self.entries.get(name).map(|r| r.text.as_str())
+    }
+
+    /// Set or, when `text` is blank, remove a session's description
+    ///
+    /// A removed description is derived again on the next poll.
+    pub fn set(&mut self, name: &str, text: &str, now: u64) {
+        let text = text.trim();
+        if text.is_empty() {
+            self.entries.remove(name);
+        } else {
+            self.entries.insert(
+                name,
+                DescriptionRecord {
+                    text: text.to_string(),
+                    seen_at: now,
+                },
+            );
+        }
+    }
+
+    /// Move a session's description to its new name
+    pub fn rename(&mut self, old: &str, new: &str) {
+        self.entries.rename(old, new);
+    }
+
+    /// Describe listed sessions that have no description yet, note which
+    /// names are still listed, and forget those unseen for 30 days
+    ///
+    /// Returns whether anything changed enough to be worth saving.
+    pub fn refresh(&mut self, sessions: &[TmuxSession], now: u64) -> bool {
+        let home = dirs::home_dir();
+        let mut changed = false;
+        for session in sessions {
+            if self.entries.get(&session.name).is_none()
+                && let Some(text) = derive(session, home.as_deref())
+            {
+                self.set(&session.name, &text, now);
+                changed = true;
+            }
+        }
+        let listed = sessions.iter().map(|s| s.name.as_str());
+        self.entries.refresh(listed, now) || changed
+    }
+}
+
+/// Description of a session seen for the first time: its start command,
+/// else whatever runs in it now, and where it started
+pub fn derive(session: &TmuxSession, home: Option<&Path>) -> Option<String> {
+    let command = [&session.start_command, &session.current_command]
+        .into_iter()
+        .map(|c| c.trim())
+        .find(|c| !c.is_empty())?;
+    let dir = [&session.start_path, &session.current_path]
+        .into_iter()
+        .find(|p| !p.is_empty());
+    Some(describe(command, dir.map(Path::new), home))
+}
+
+/// `claude in ~/code/api`, with the home directory shortened to `~`
+pub fn describe(command: &str, dir: Option<&Path>, home: Option<&Path>) -> String {
+    match dir {
+        Some(dir) => {
+            let dir = match home.and_then(|home| dir.strip_prefix(home).ok()) {
+                Some(rest) if rest.as_os_str().is_empty() => "~".to_string(),
+                Some(rest) => format!("~/{}", rest.display()),
+                None => dir.display().to_string(),
+            };
+            format!("{} in {}", command, dir)
+        }
+        None => command.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(name: &str, start_command: &str, start_path: &str) -> TmuxSession {
+        let mut s = TmuxSession::new(format!("${}", name), name.to_string());
+        s.start_command = start_command.to_string();
+        s.start_path = start_path.to_string();
+        s.current_command = "zsh".to_string();
+        s.current_path = "/elsewhere".to_string();
+        s
+    }
+
+    #[test]
+    fn test_derive() {
+        let home = Some(Path::new("/home/dev"));
+        assert_eq!(
+            derive(
+                &session("api", "claude --continue", "/home/dev/code/api"),
+                home
+            )
+            .as_deref(),
+            Some("claude --continue in ~/code/api")
+        );
+        // A plain shell has no start command, so what runs now stands in
+        assert_eq!(
+            derive(&session("web", "", "/home/dev"), home).as_deref(),
+            Some("zsh in ~")
+        );
+        assert_eq!(
+            derive(&session("ops", "htop", "/srv/ops"), home).as_deref(),
+            Some("htop in /srv/ops")
+        );
+        let bare = TmuxSession::new("$9".to_string(), "bare".to_string());
+        assert_eq!(derive(&bare, home), None);
+        assert_eq!(describe("aider", None, home), "aider");
+    }
+
+    #[test]
+    fn test_refresh_keeps_first_description() {
+        let mut descriptions = Descriptions::default();
+        assert!(descriptions.refresh(&[session("api", "claude", "/work/api")], 0));
+        assert_eq!(descriptions.get("api"), Some("claude in /work/api"));
+
+        // Later polls don't overwrite it, nor an edit
+        assert!(!descriptions.refresh(&[session("api", "aider", "/work/api")], 60));
+        descriptions.set("api", "  payments refactor ", 60);
+        descriptions.refresh(&[session("api", "claude", "/work/api")], 120);
+        assert_eq!(descriptions.get("api"), Some("payments refactor"));
+
+        // Clearing it derives it again
+        descriptions.set("api", "", 180);
+        descriptions.refresh(&[session("api", "claude", "/work/api")], 240);
+        assert_eq!(descriptions.get("api"), Some("claude in /work/api"));
+    }
+}
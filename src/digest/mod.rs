@@ -0,0 +1,125 @@
+//! Startup digest of sessions that need attention.
+//!
+//! Opening the dashboard after a while away, the first poll shows which
+//! agents stopped for a question or an error. Instead of scanning the list,
+//! they are summarised once in a popup, in the grouped view's order, with
+//! how long each has been quiet and the line its pane is waiting on.
+
+use crate::jump_list::AttachHistory;
+use crate::session_list::{Column, StatusGroup};
+use crate::text;
+use crate::tmux::{AgentStatus, TmuxSession};
+
+/// Lines from the bottom of the pane searched for the question
+const QUESTION_SEARCH_LINES: usize = 15;
+
+/// A session in the digest
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DigestEntry {
+    pub session_id: String,
+    pub name: String,
+    pub status: AgentStatus,
+    /// Unix time of the pane's last output, the best guess at when it stopped; 0 if unknown
+    pub since: u64,
+    /// Line the pane is waiting on, once captured
+    pub question: Option<String>,
+}
+
+impl DigestEntry {
+    /// How long the session has apparently been in its status, if known
+    pub fn waiting_secs(&self, now: u64) -> Option<u64> {
+        (self.since > 0).then(|| now.saturating_sub(self.since))
+    }
+}
+
+/// Sessions needing attention, in the order the grouped list shows them
+pub fn entries(sessions: &[TmuxSession], attached: &AttachHistory) -> Vec<DigestEntry> {
+    let mut needing: Vec<&TmuxSession> = sessions
+        .iter()
+        .filter(|s| StatusGroup::of(s.status) == StatusGroup::NeedsAttention)
+        .collect();
+    needing.sort_by(|a, b| Column::Status.compare(a, b, attached));
+    needing
+        .into_iter()
+        .map(|s| DigestEntry {
+            session_id: s.id.clone(),
+            name: s.name.clone(),
+            status: s.status,
+            since: s.activity_at,
+            question: None,
+        })
+        .collect()
+}
+
+/// The line a pane is waiting on: its latest question near the bottom,
+/// else its last line of text
+///
+/// Box borders around agent prompts are dropped.
+pub fn pending_question(content: &str) -> Option<String> {
+    let content = text::strip_ansi(content);
+    let lines: Vec<&str> = content
+        .lines()
+        .map(|line| line.trim_matches(|c: char| c.is_whitespace() || "│┃╭╮╰╯─".contains(c)))
+        .filter(|line| !line.is_empty())
+        .collect();
+    let recent = &lines[lines.len().saturating_sub(QUESTION_SEARCH_LINES)..];
+    recent
+        .iter()
+        .rev()
+        .find(|line| line.ends_with('?'))
+        .or(recent.last())
+        .map(|line| line.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(name: &str, status: AgentStatus, activity_at: u64) -> TmuxSession {
+        let mut s = TmuxSession::new(format!("${}", name), name.to_string());
+        s.status = status;
+        s.activity_at = activity_at;
+        s
+    }
+
+    #[test]
+    fn test_entries() {
+        let sessions = [
+            session("web", AgentStatus::Error, 100),
+            session("api", AgentStatus::Busy, 0),
+            session("docs", AgentStatus::WaitingForInput, 0),
+            session("cli", AgentStatus::Idle, 0),
+        ];
+        let entries = entries(&sessions, &AttachHistory::default());
+        let names: Vec<&str> = entries.iter().map(|e| e.name.as_str()).collect();
+        assert_eq!(names, ["docs", "web"]);
+        assert_eq!(entries[1].waiting_secs(160), Some(60));
+        assert_eq!(entries[0].waiting_secs(160), None);
+        assert!(super::entries(&sessions[1..2], &AttachHistory::default()).is_empty());
+    }
+
+    #[test]
+    fn test_pending_question() {
+        let claude = "\
+╭──────────────────────────────────────────╮
+│ Edit file                                │
+│ Do you want to make this edit to lib.rs? │
+│ ❯ 1. Yes                                 │
+│   2. No, and tell Claude what to do      │
+╰──────────────────────────────────────────╯
+";
+        assert_eq!(
+            pending_question(claude).as_deref(),
+            Some("Do you want to make this edit to lib.rs?")
+        );
+
+        let error =
+            "cargo build\nerror: could not compile `api`\n\n\x1b[31mAPI Error: 529\x1b[0m\n\n";
+        assert_eq!(pending_question(error).as_deref(), Some("API Error: 529"));
+
+        // A question scrolled far above the prompt is no longer pending
+        let old = format!("Proceed?\n{}", "line\n".repeat(QUESTION_SEARCH_LINES));
+        assert_eq!(pending_question(&old).as_deref(), Some("line"));
+        assert_eq!(pending_question("\n  \n"), None);
+    }
+}
@@ -0,0 +1,373 @@
+//! Directory browser for choosing where a new session starts, or the
+//! skeleton root.
+//!
+//! Listings are read off the render path: the picker asks for a directory
+//! with [`PickerEvent::Load`], and its entries come back through
+//! [`DirPicker::loaded`]. Until then the current listing stays up. A
+//! directory that can't be opened is marked in the listing, not left.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::path::{Path, PathBuf};
+
+/// A subdirectory in a listing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirEntry {
+    pub name: String,
+    /// Why it can't be opened, once that is known
+    pub error: Option<String>,
+}
+
+impl DirEntry {
+    pub fn is_hidden(&self) -> bool {
+        self.name.starts_with('.')
+    }
+}
+
+/// Subdirectories of `path`, by name; links to directories count
+pub async fn read_dir(path: &Path) -> std::io::Result<Vec<DirEntry>> {
+    let mut dir = tokio::fs::read_dir(path).await?;
+    let mut entries = Vec::new();
+    while let Some(entry) = dir.next_entry().await? {
+        let name = entry.file_name().to_string_lossy().into_owned();
+        match tokio::fs::metadata(entry.path()).await {
+            Ok(meta) if meta.is_dir() => entries.push(DirEntry { name, error: None }),
+            Ok(_) => {}
+            // A directory we may not look into is still listed, marked
+            Err(e) if entry.file_type().await.is_ok_and(|t| t.is_dir()) => {
+                entries.push(DirEntry {
+                    name,
+                    error: Some(e.to_string()),
+                });
+            }
+            // Dangling links and the like
+            Err(_) => {}
+        }
+    }
+    entries.sort_by_cached_key(|e| e.name.to_lowercase());
+    Ok(entries)
+}
+
+/// What the picker needs from whoever shows it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PickerEvent {
+    None,
+    /// List this directory and pass the result to [`DirPicker::loaded`]
+    Load(PathBuf),
+    Chosen(PathBuf),
+    Cancelled,
+}
+
+/// A directory listing to move around in
+#[derive(Debug)]
+pub struct DirPicker {
+    dir: PathBuf,
+    entries: Vec<DirEntry>,
+    /// Directory being listed; the picker moves there once it arrives
+    pending: Option<PathBuf>,
+    /// Why `dir` itself couldn't be listed
+    error: Option<String>,
+    pub show_hidden: bool,
+    /// Only entries containing this (ignoring case) are shown
+    pub filter: String,
+    /// Keys go to the filter line
+    pub filtering: bool,
+    /// Highlighted row: 0 is the directory itself, then the shown entries
+    pub selected: usize,
+}
+
+impl DirPicker {
+    /// A picker in `dir`; its listing is the first thing to load
+    pub fn new(dir: PathBuf) -> Self {
+        Self {
+            pending: Some(dir.clone()),
+            dir,
+            entries: Vec::new(),
+            error: None,
+            show_hidden: false,
+            filter: String::new(),
+            filtering: false,
+            selected: 0,
+        }
+    }
+
+    /// The directory shown
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    pub fn is_loading(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    /// Why the shown directory couldn't be listed
+    pub fn error(&self) -> Option<&str> {
+        self.error.as_deref()
+    }
+
+    /// Entries passing the hidden toggle and the filter
+    pub fn shown(&self) -> Vec<&DirEntry> {
+        let filter = self.filter.to_lowercase();
+        self.entries
+            .iter()
+            .filter(|e| self.show_hidden || !e.is_hidden())
+            .filter(|e| e.name.to_lowercase().contains(&filter))
+            .collect()
+    }
+
+    /// Entry under the highlight; `None` on the directory itself
+    pub fn selected_entry(&self) -> Option<&DirEntry> {
+        self.selected
+            .checked_sub(1)
+            .and_then(|i| self.shown().get(i).copied())
+    }
+
+    /// Take the listing of `path`, if it is the one asked for
+    pub fn loaded(&mut self, path: &Path, listing: Result<Vec<DirEntry>, String>) {
+        if self.pending.as_deref() != Some(path) {
+            return;
+        }
+        self.pending = None;
+        match listing {
+            Ok(entries) => {
+                // Going up keeps the directory we came from highlighted
+                let came_from = self
+                    .dir
+                    .strip_prefix(path)
+                    .ok()
+                    .and_then(|rest| rest.components().next())
+                    .map(|c| c.as_os_str().to_string_lossy().into_owned());
+                self.dir = path.to_path_buf();
+                self.entries = entries;
+                self.error = None;
+                self.filter.clear();
+                self.filtering = false;
+                self.selected = came_from
+                    .and_then(|name| self.shown().iter().position(|e| e.name == name))
+                    .map_or(0, |i| i + 1);
+            }
+            // Opening a subdirectory failed: mark it and stay
+            Err(e) if path.parent() == Some(self.dir.as_path()) && path != self.dir => {
+                let name = path.file_name().map(|n| n.to_string_lossy());
+                if let Some(entry) = self
+                    .entries
+                    .iter_mut()
+                    .find(|entry| Some(entry.name.as_str()) == name.as_deref())
+                {
+                    entry.error = Some(e);
+                }
+            }
+            Err(e) => {
+                self.dir = path.to_path_buf();
+                self.entries.clear();
+                self.selected = 0;
+                self.error = Some(e);
+            }
+        }
+    }
+
+    pub fn handle_key(&mut self, key: KeyEvent) -> PickerEvent {
+        if key
+            .modifiers
+            .intersects(KeyModifiers::CONTROL | KeyModifiers::ALT)
+        {
+            return PickerEvent::None;
+        }
+        if self.filtering {
+            match key.code {
+                KeyCode::Char(c) => {
+                    self.filter.push(c);
+                    self.selected = usize::from(!self.shown().is_empty());
+                }
+                KeyCode::Backspace => {
+                    self.filter.pop();
+                    self.selected = usize::from(!self.shown().is_empty());
+                }
+                KeyCode::Enter => self.filtering = false,
+                KeyCode::Esc => {
+                    self.filter.clear();
+                    self.filtering = false;
+                    self.selected = 0;
+                }
+                KeyCode::Down => self.move_by(1),
+                KeyCode::Up => self.move_by(-1),
+                _ => {}
+            }
+            return PickerEvent::None;
+        }
+        match key.code {
+            KeyCode::Char('j') | KeyCode::Down => self.move_by(1),
+            KeyCode::Char('k') | KeyCode::Up => self.move_by(-1),
+            KeyCode::Enter if self.selected == 0 && self.error.is_none() => {
+                return PickerEvent::Chosen(self.dir.clone());
+            }
+            KeyCode::Enter | KeyCode::Char('l') | KeyCode::Right => {
+                if let Some(entry) = self.selected_entry() {
+                    let child = self.dir.join(&entry.name);
+                    return self.load(child);
+                }
+            }
+            KeyCode::Char('h') | KeyCode::Left | KeyCode::Backspace => {
+                if let Some(parent) = self.dir.parent() {
+                    let parent = parent.to_path_buf();
+                    return self.load(parent);
+                }
+            }
+            KeyCode::Char('~') => {
+                if let Some(home) = dirs::home_dir() {
+                    return self.load(home);
+                }
+            }
+            KeyCode::Char('.') => {
+                self.show_hidden = !self.show_hidden;
+                self.selected = self.selected.min(self.shown().len());
+            }
+            KeyCode::Char('/') => self.filtering = true,
+            KeyCode::Esc if !self.filter.is_empty() => {
+                self.filter.clear();
+                self.selected = 0;
+            }
+            KeyCode::Esc => return PickerEvent::Cancelled,
+            _ => {}
+        }
+        PickerEvent::None
+    }
+
+    fn move_by(&mut self, delta: isize) {
+        self.selected = self
+            .selected
+            .saturating_add_signed(delta)
+            .min(self.shown().len());
+    }
+
+    fn load(&mut self, path: PathBuf) -> PickerEvent {
+        self.pending = Some(path.clone());
+        PickerEvent::Load(path)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// root/{alpha/inner, beta, .git}, plus a file and a dangling link
+    fn tree(label: &str) -> PathBuf {
+        let root = std::env::temp_dir().join(format!(
+            "agent-rusty-picker-{}-{}",
+            label,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&root);
+        for dir in ["alpha/inner", "Beta", ".git"] {
+            std::fs::create_dir_all(root.join(dir)).unwrap();
+        }
+        std::fs::write(root.join("notes.txt"), "").unwrap();
+        #[cfg(unix)]
+        std::os::unix::fs::symlink(root.join("missing"), root.join("dangling")).unwrap();
+        root
+    }
+
+    fn press(picker: &mut DirPicker, code: KeyCode) -> PickerEvent {
+        picker.handle_key(KeyEvent::from(code))
+    }
+
+    async fn follow(picker: &mut DirPicker, event: PickerEvent) {
+        let PickerEvent::Load(path) = event else {
+            panic!("expected a load, got {:?}", event);
+        };
+        let listing = read_dir(&path).await.map_err(|e| e.to_string());
+        picker.loaded(&path, listing);
+    }
+
+    fn names(picker: &DirPicker) -> Vec<&str> {
+        picker.shown().iter().map(|e| e.name.as_str()).collect()
+    }
+
+    #[tokio::test]
+    async fn test_read_dir_lists_directories() {
+        let root = tree("read");
+        let names: Vec<String> = read_dir(&root)
+            .await
+            .unwrap()
+            .into_iter()
+            .map(|e| e.name)
+            .collect();
+        assert_eq!(names, [".git", "alpha", "Beta"]);
+        assert!(read_dir(&root.join("notes.txt")).await.is_err());
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_browse_and_choose() {
+        let root = tree("browse");
+        let mut picker = DirPicker::new(root.clone());
+        assert!(picker.is_loading());
+        follow(&mut picker, PickerEvent::Load(root.clone())).await;
+        assert_eq!(names(&picker), ["alpha", "Beta"]);
+
+        // Into alpha and back up, landing on it again
+        press(&mut picker, KeyCode::Char('j'));
+        let event = press(&mut picker, KeyCode::Char('l'));
+        follow(&mut picker, event).await;
+        assert_eq!(picker.dir(), root.join("alpha"));
+        assert_eq!(names(&picker), ["inner"]);
+        let event = press(&mut picker, KeyCode::Char('h'));
+        follow(&mut picker, event).await;
+        assert_eq!(picker.dir(), root);
+        assert_eq!(picker.selected_entry().unwrap().name, "alpha");
+
+        press(&mut picker, KeyCode::Char('.'));
+        assert_eq!(names(&picker), [".git", "alpha", "Beta"]);
+        press(&mut picker, KeyCode::Char('.'));
+
+        // Filtering takes letters that are otherwise keys
+        press(&mut picker, KeyCode::Char('/'));
+        for c in "bet".chars() {
+            press(&mut picker, KeyCode::Char(c));
+        }
+        assert_eq!(names(&picker), ["Beta"]);
+        assert_eq!(picker.selected, 1);
+        press(&mut picker, KeyCode::Enter);
+        assert!(!picker.filtering);
+        let event = press(&mut picker, KeyCode::Enter);
+        follow(&mut picker, event).await;
+        assert_eq!(picker.dir(), root.join("Beta"));
+        assert!(picker.filter.is_empty());
+
+        assert_eq!(
+            press(&mut picker, KeyCode::Enter),
+            PickerEvent::Chosen(root.join("Beta"))
+        );
+        assert_eq!(press(&mut picker, KeyCode::Esc), PickerEvent::Cancelled);
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_unopenable_entries_are_marked() {
+        let root = tree("denied");
+        let mut picker = DirPicker::new(root.clone());
+        follow(&mut picker, PickerEvent::Load(root.clone())).await;
+
+        press(&mut picker, KeyCode::Char('j'));
+        let event = press(&mut picker, KeyCode::Enter);
+        assert_eq!(event, PickerEvent::Load(root.join("alpha")));
+        picker.loaded(&root.join("alpha"), Err("Permission denied".to_string()));
+        assert_eq!(picker.dir(), root);
+        assert!(!picker.is_loading());
+        assert_eq!(
+            picker.selected_entry().unwrap().error.as_deref(),
+            Some("Permission denied")
+        );
+
+        // Stale listings are ignored; a start directory that can't be read says so
+        picker.loaded(&root.join("Beta"), Ok(Vec::new()));
+        assert_eq!(names(&picker), ["alpha", "Beta"]);
+        let mut picker = DirPicker::new(root.join("gone"));
+        follow(&mut picker, PickerEvent::Load(root.join("gone"))).await;
+        assert!(picker.error().is_some());
+        assert_eq!(
+            press(&mut picker, KeyCode::Char('h')),
+            PickerEvent::Load(root.clone())
+        );
+        std::fs::remove_dir_all(&root).unwrap();
+    }
+}
@@ -0,0 +1,563 @@
+//! `agent-rusty doctor`: diagnose the environment.
+//!
+//! Each check is a function from what it observed (a tmux reply, environment
+//! variables, a path) to a [`Check`], so it can be tested without the real
+//! environment. [`run`] gathers the observations and prints the report.
+
+use anyhow::Result;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{Command, Stdio};
+
+use crate::config::Config;
+use crate::tmux::TmuxClient;
+
+/// Exit code when any check fails
+pub const EXIT_FAILED: i32 = 1;
+
+/// Oldest tmux we expect to work
+const MIN_TMUX_VERSION: (u32, u32) = (3, 0);
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Level {
+    Pass,
+    /// Works, but something is degraded or worth knowing
+    Warn,
+    /// agent-rusty won't work until this is fixed
+    Fail,
+}
+
+impl Level {
+    pub fn label(self) -> &'static str {
+        match self {
+            Level::Pass => "pass",
+            Level::Warn => "warn",
+            Level::Fail => "FAIL",
+        }
+    }
+}
+
+/// Outcome of one check
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Check {
+    pub name: &'static str,
+    pub level: Level,
+    pub detail: String,
+    /// What to do about a warning or failure
+    pub hint: Option<String>,
+}
+
+impl Check {
+    fn pass(name: &'static str, detail: impl Into<String>) -> Self {
+        Self {
+            name,
+            level: Level::Pass,
+            detail: detail.into(),
+            hint: None,
+        }
+    }
+
+    fn warn(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            level: Level::Warn,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+
+    fn fail(name: &'static str, detail: impl Into<String>, hint: impl Into<String>) -> Self {
+        Self {
+            name,
+            level: Level::Fail,
+            detail: detail.into(),
+            hint: Some(hint.into()),
+        }
+    }
+}
+
+/// Files and directories the checks look at
+#[derive(Debug, Clone)]
+pub struct Paths {
+    pub config: PathBuf,
+    /// The config was named with `--config`, so it has to exist
+    pub config_explicit: bool,
+    pub log: PathBuf,
+    /// Holds the state, snapshot and history
+    pub data_dir: PathBuf,
+    pub history_dir: PathBuf,
+}
+
+/// Run every check, print the report to `out`, and return the exit code
+pub async fn run(client: &TmuxClient, paths: &Paths, out: &mut dyn Write) -> Result<i32> {
+    let env = |name: &str| std::env::var(name).ok();
+    let on_path = |program: &str| on_path(program, std::env::var_os("PATH"));
+
+    let mut checks = vec![
+        check_tmux_version(client.version().await),
+        check_server(client.session_names().await),
+        check_nesting(env),
+        check_clipboard(
+            arboard::Clipboard::new()
+                .map(drop)
+                .map_err(|e| e.to_string()),
+            env,
+        ),
+        check_notifications(env, on_path, notification_daemon),
+        check_config(&paths.config, paths.config_explicit),
+    ];
+    for (name, dir) in [
+        ("state dir", paths.data_dir.as_path()),
+        ("history dir", paths.history_dir.as_path()),
+        ("log dir", paths.log.parent().unwrap_or(Path::new("."))),
+    ] {
+        checks.push(check_writable(name, dir));
+    }
+
+    write!(out, "{}", format_report(&checks))?;
+    Ok(exit_code(&checks))
+}
+
+pub fn exit_code(checks: &[Check]) -> i32 {
+    if checks.iter().any(|c| c.level == Level::Fail) {
+        EXIT_FAILED
+    } else {
+        0
+    }
+}
+
+pub fn format_report(checks: &[Check]) -> String {
+    let width = checks.iter().map(|c| c.name.len()).max().unwrap_or(0);
+    let mut report = String::new();
+    for check in checks {
+        report.push_str(&format!(
+            "[{}] {:width$}  {}\n",
+            check.level.label(),
+            check.name,
+            check.detail
+        ));
+        if let Some(hint) = &check.hint {
+            report.push_str(&format!("       {:width$}  hint: {}\n", "", hint));
+        }
+    }
+    let failed = checks.iter().filter(|c| c.level == Level::Fail).count();
+    let warned = checks.iter().filter(|c| c.level == Level::Warn).count();
+    report.push_str(&format!("\n{} failed, {} warnings\n", failed, warned));
+    report
+}
+
+/// tmux is installed, runs, and is recent enough
+pub fn check_tmux_version(version: crate::Result<String>) -> Check {
+    const NAME: &str = "tmux";
+    let version = match version {
+        Ok(version) => version,
+        Err(e) => {
+            return Check::fail(
+                NAME,
+                format!("tmux doesn't run: {}", e),
+                "Install tmux, or point --tmux at its binary",
+            );
+        }
+    };
+    match parse_version(&version) {
+        Some(found) if found < MIN_TMUX_VERSION => Check::warn(
+            NAME,
+            version,
+            format!(
+                "tmux older than {}.{} is untested; upgrade if sessions misbehave",
+                MIN_TMUX_VERSION.0, MIN_TMUX_VERSION.1
+            ),
+        ),
+        _ => Check::pass(NAME, version),
+    }
+}
+
+/// Major and minor version from e.g. `tmux 3.3a` or `tmux next-3.5`
+fn parse_version(version: &str) -> Option<(u32, u32)> {
+    let number = version.split_whitespace().last()?;
+    let number = number.rsplit('-').next()?;
+    let (major, rest) = number.split_once('.')?;
+    let minor: String = rest.chars().take_while(char::is_ascii_digit).collect();
+    Some((major.parse().ok()?, minor.parse().ok()?))
+}
+
+/// The server answers, and how many sessions it has
+pub fn check_server(names: crate::Result<Vec<String>>) -> Check {
+    const NAME: &str = "server";
+    match names {
+        Ok(names) if names.is_empty() => Check::warn(
+            NAME,
+            "No sessions (or no server running yet)",
+            "Create a session with `agent-rusty create <name>` or `n` in the dashboard",
+        ),
+        Ok(names) => Check::pass(NAME, format!("{} sessions visible", names.len())),
+        Err(e) => Check::fail(
+            NAME,
+            format!("Can't list sessions: {}", e),
+            "Check --socket-name matches the server your agents run on",
+        ),
+    }
+}
+
+/// Running inside tmux makes attaching nest a client in a client
+pub fn check_nesting(env: impl Fn(&str) -> Option<String>) -> Check {
+    const NAME: &str = "nesting";
+    match env("TMUX").filter(|v| !v.is_empty()) {
+        Some(_) => Check::warn(
+            NAME,
+            "Running inside tmux; attaching nests sessions",
+            "Run agent-rusty from a plain terminal, or press the prefix twice to reach the inner session",
+        ),
+        None => Check::pass(NAME, "Not inside tmux"),
+    }
+}
+
+/// Which clipboard copies will reach; `system` is the result of opening the system clipboard
+pub fn check_clipboard(
+    system: std::result::Result<(), String>,
+    env: impl Fn(&str) -> Option<String>,
+) -> Check {
+    const NAME: &str = "clipboard";
+    let Err(error) = system else {
+        return Check::pass(NAME, "System clipboard available");
+    };
+    let over_ssh = env("SSH_CONNECTION").is_some() || env("SSH_TTY").is_some();
+    let hint = match (over_ssh, env("TMUX").is_some()) {
+        (_, true) => "Copies fall back to OSC 52; enable it with `set -g set-clipboard on` in tmux",
+        (true, false) => "Over SSH copies fall back to OSC 52; make sure your terminal allows it",
+        (false, false) => "Copies fall back to OSC 52, then to a tmux buffer",
+    };
+    Check::warn(
+        NAME,
+        format!("System clipboard unavailable: {}", error),
+        hint,
+    )
+}
+
+/// Hooks usually notify through `notify-send` on Linux and `osascript` on macOS
+///
+/// `daemon` asks whether a notification daemon owns its D-Bus name; it's
+/// only called when there is a session bus to ask.
+pub fn check_notifications(
+    env: impl Fn(&str) -> Option<String>,
+    on_path: impl Fn(&str) -> bool,
+    daemon: impl FnOnce() -> Option<bool>,
+) -> Check {
+    const NAME: &str = "notifications";
+    if cfg!(target_os = "macos") {
+        return if on_path("osascript") {
+            Check::pass(NAME, "osascript available")
+        } else {
+            Check::warn(
+                NAME,
+                "osascript not found",
+                "Notification hooks need another way to notify",
+            )
+        };
+    }
+    if !on_path("notify-send") {
+        return Check::warn(
+            NAME,
+            "notify-send not found",
+            "Install libnotify (e.g. libnotify-bin) for desktop notifications from hooks",
+        );
+    }
+    if env("DBUS_SESSION_BUS_ADDRESS").is_none() {
+        return Check::warn(
+            NAME,
+            "No D-Bus session bus",
+            "Desktop notifications need a graphical session; use a webhook over SSH",
+        );
+    }
+    match daemon() {
+        Some(false) => Check::warn(
+            NAME,
+            "No notification daemon is running",
+            "Start one (e.g. dunst or mako), or use a webhook",
+        ),
+        Some(true) => Check::pass(NAME, "Notification daemon reachable"),
+        None => Check::pass(NAME, "notify-send available"),
+    }
+}
+
+/// Whether a daemon owns `org.freedesktop.Notifications`; `None` if we can't tell
+fn notification_daemon() -> Option<bool> {
+    let output = Command::new("dbus-send")
+        .args([
+            "--session",
+            "--print-reply",
+            "--dest=org.freedesktop.DBus",
+            "/org/freedesktop/DBus",
+            "org.freedesktop.DBus.NameHasOwner",
+            "string:org.freedesktop.Notifications",
+        ])
+        .stdin(Stdio::null())
+        .stderr(Stdio::null())
+        .output()
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).contains("boolean true"))
+}
+
+fn on_path(program: &str, path: Option<std::ffi::OsString>) -> bool {
+    path.is_some_and(|path| std::env::split_paths(&path).any(|dir| dir.join(program).is_file()))
+}
+
+/// The config file parses; a missing default file just means defaults
+pub fn check_config(path: &Path, explicit: bool) -> Check {
+    const NAME: &str = "config";
+    if !path.exists() {
+        return if explicit {
+            Check::fail(
+                NAME,
+                format!("{} not found", path.display()),
+                "Fix the --config path",
+            )
+        } else {
+            Check::pass(
+                NAME,
+                format!("No file at {}; using defaults", path.display()),
+            )
+        };
+    }
+    match Config::load_from(path) {
+        Ok(config) => match config.warnings().as_slice() {
+            [] => Check::pass(NAME, format!("Loaded {}", path.display())),
+            warnings => Check::warn(
+                NAME,
+                format!("Loaded {}, but {}", path.display(), warnings.join("; ")),
+                "Use icons that take one cell",
+            ),
+        },
+        Err(e) => Check::fail(
+            NAME,
+            e.to_string(),
+            "Fix the file, or move it aside to start from defaults",
+        ),
+    }
+}
+
+/// `dir` exists (or can be created) and we can write files in it
+pub fn check_writable(name: &'static str, dir: &Path) -> Check {
+    let probe = dir.join(format!(".agent-rusty-doctor-{}", std::process::id()));
+    let result = std::fs::create_dir_all(dir)
+        .and_then(|_| std::fs::write(&probe, b""))
+        .and_then(|_| std::fs::remove_file(&probe));
+    match result {
+        Ok(()) => Check::pass(name, format!("{} is writable", dir.display())),
+        Err(e) => Check::fail(
+            name,
+            format!("Can't write to {}: {}", dir.display(), e),
+            format!("Check the ownership and permissions of {}", dir.display()),
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::AgentRustyError;
+    use std::collections::HashMap;
+
+    fn env(vars: &[(&str, &str)]) -> impl Fn(&str) -> Option<String> {
+        let vars: HashMap<String, String> = vars
+            .iter()
+            .map(|(k, v)| (k.to_string(), v.to_string()))
+            .collect();
+        move |name| vars.get(name).cloned()
+    }
+
+    fn temp_dir(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-rusty-doctor-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        dir
+    }
+
+    #[test]
+    fn test_tmux_version() {
+        assert_eq!(check_tmux_version(Ok("tmux 3.4".into())).level, Level::Pass);
+        assert_eq!(
+            check_tmux_version(Ok("tmux next-3.5".into())).level,
+            Level::Pass
+        );
+        let old = check_tmux_version(Ok("tmux 2.9a".into()));
+        assert_eq!(old.level, Level::Warn);
+        assert_eq!(old.detail, "tmux 2.9a");
+        // Unrecognised versions are given the benefit of the doubt
+        assert_eq!(
+            check_tmux_version(Ok("tmux master".into())).level,
+            Level::Pass
+        );
+
+        let missing = check_tmux_version(Err(AgentRustyError::spawn(
+            "tmux",
+            std::io::Error::from(std::io::ErrorKind::NotFound),
+        )));
+        assert_eq!(missing.level, Level::Fail);
+        assert!(missing.hint.unwrap().contains("--tmux"));
+    }
+
+    #[test]
+    fn test_parse_version() {
+        assert_eq!(parse_version("tmux 3.3a"), Some((3, 3)));
+        assert_eq!(parse_version("tmux 3.10"), Some((3, 10)));
+        assert_eq!(parse_version("tmux next-3.5"), Some((3, 5)));
+        assert_eq!(parse_version("tmux openbsd-7.4"), Some((7, 4)));
+        assert_eq!(parse_version("tmux master"), None);
+    }
+
+    #[test]
+    fn test_server() {
+        let check = check_server(Ok(vec!["api".into(), "web".into()]));
+        assert_eq!(check.level, Level::Pass);
+        assert_eq!(check.detail, "2 sessions visible");
+        assert_eq!(check_server(Ok(Vec::new())).level, Level::Warn);
+        let error = check_server(Err(AgentRustyError::tmux(
+            "list-sessions",
+            b"error connecting to /tmp/tmux-1000/x (Permission denied)",
+        )));
+        assert_eq!(error.level, Level::Fail);
+        assert!(
+            error.detail.contains("Permission denied"),
+            "{}",
+            error.detail
+        );
+    }
+
+    #[test]
+    fn test_nesting() {
+        assert_eq!(check_nesting(env(&[])).level, Level::Pass);
+        assert_eq!(check_nesting(env(&[("TMUX", "")])).level, Level::Pass);
+        assert_eq!(
+            check_nesting(env(&[("TMUX", "/tmp/tmux-1000/default,123,0")])).level,
+            Level::Warn
+        );
+    }
+
+    #[test]
+    fn test_clipboard() {
+        assert_eq!(check_clipboard(Ok(()), env(&[])).level, Level::Pass);
+
+        let headless = check_clipboard(Err("no display".into()), env(&[]));
+        assert_eq!(headless.level, Level::Warn);
+        assert!(headless.detail.contains("no display"));
+        assert!(headless.hint.unwrap().contains("tmux buffer"));
+
+        let ssh = check_clipboard(
+            Err("no display".into()),
+            env(&[("SSH_CONNECTION", "10.0.0.1 22 10.0.0.2 22")]),
+        );
+        assert!(ssh.hint.unwrap().contains("SSH"));
+
+        let nested = check_clipboard(
+            Err("no display".into()),
+            env(&[("SSH_TTY", "/dev/pts/1"), ("TMUX", "x")]),
+        );
+        assert!(nested.hint.unwrap().contains("set-clipboard"));
+    }
+
+    #[cfg(not(target_os = "macos"))]
+    #[test]
+    fn test_notifications() {
+        let bus = [("DBUS_SESSION_BUS_ADDRESS", "unix:path=/run/user/1000/bus")];
+        let unreachable = || panic!("daemon probed");
+
+        let missing = check_notifications(env(&bus), |_| false, unreachable);
+        assert_eq!(missing.level, Level::Warn);
+        assert!(missing.detail.contains("notify-send"));
+
+        let no_bus = check_notifications(env(&[]), |_| true, unreachable);
+        assert_eq!(no_bus.level, Level::Warn);
+        assert!(no_bus.detail.contains("D-Bus"));
+
+        assert_eq!(
+            check_notifications(env(&bus), |_| true, || Some(true)).level,
+            Level::Pass
+        );
+        assert_eq!(
+            check_notifications(env(&bus), |_| true, || Some(false)).level,
+            Level::Warn
+        );
+        // Without dbus-send we can't ask, so notify-send being there is enough
+        assert_eq!(
+            check_notifications(env(&bus), |_| true, || None).level,
+            Level::Pass
+        );
+    }
+
+    #[test]
+    fn test_config() {
+        let dir = temp_dir("config");
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("config.toml");
+
+        assert_eq!(check_config(&path, false).level, Level::Pass);
+        assert_eq!(check_config(&path, true).level, Level::Fail);
+
+        std::fs::write(&path, "poll_interval_ms = 500\n").unwrap();
+        assert_eq!(check_config(&path, true).level, Level::Pass);
+
+        std::fs::write(&path, "[status_icons]\nbusy = \"🔥\"\n").unwrap();
+        let wide = check_config(&path, true);
+        assert_eq!(wide.level, Level::Warn);
+        assert!(wide.detail.contains("status_icons.busy"), "{}", wide.detail);
+
+        std::fs::write(&path, "poll_interval_ms = \"fast\"\n").unwrap();
+        let broken = check_config(&path, false);
+        assert_eq!(broken.level, Level::Fail);
+        assert!(
+            broken.detail.contains("poll_interval_ms"),
+            "{}",
+            broken.detail
+        );
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_writable() {
+        let dir = temp_dir("writable");
+        let nested = dir.join("a").join("b");
+        let check = check_writable("state dir", &nested);
+        assert_eq!(check.level, Level::Pass, "{:?}", check);
+        assert!(nested.is_dir());
+        // The probe file is cleaned up
+        assert_eq!(std::fs::read_dir(&nested).unwrap().count(), 0);
+
+        // A file where the directory should be
+        let blocked = dir.join("file");
+        std::fs::write(&blocked, "").unwrap();
+        assert_eq!(check_writable("log dir", &blocked).level, Level::Fail);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_report_and_exit_code() {
+        let mut checks = vec![
+            Check::pass("tmux", "tmux 3.4"),
+            Check::warn("server", "No sessions", "Create one"),
+        ];
+        assert_eq!(exit_code(&checks), 0);
+        assert_eq!(
+            format_report(&checks),
+            concat!(
+                "[pass] tmux    tmux 3.4\n",
+                "[warn] server  No sessions\n",
+                "               hint: Create one\n",
+                "\n",
+                "0 failed, 1 warnings\n",
+            )
+        );
+
+        checks.push(Check::fail("config", "bad", "Fix it"));
+        assert_eq!(exit_code(&checks), EXIT_FAILED);
+        assert!(format_report(&checks).ends_with("1 failed, 1 warnings\n"));
+    }
+}
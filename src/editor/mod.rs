@@ -0,0 +1,48 @@
+/// Editor command template from the config, `$VISUAL`, or `$EDITOR`
+pub fn resolve_command(configured: Option<&str>) -> Option<String> {
+    configured
+        .map(str::to_string)
+        .or_else(|| std::env::var("VISUAL").ok())
+        .or_else(|| std::env::var("EDITOR").ok())
+        .filter(|c| !c.trim().is_empty())
+}
+
+/// Split an editor command into program and arguments, opening `dir`
+///
+/// `{dir}` is replaced in each argument; without a placeholder the directory
+/// is appended. Splitting happens before substitution so paths with spaces
+/// stay one argument.
+pub fn command_argv(template: &str, dir: &str) -> Vec<String> {
+    let mut argv: Vec<String> = template
+        .split_whitespace()
+        .map(|part| part.replace("{dir}", dir))
+        .collect();
+    if !template.contains("{dir}") {
+        argv.push(dir.to_string());
+    }
+    argv
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_command_argv() {
+        assert_eq!(command_argv("nvim", "/src/api"), vec!["nvim", "/src/api"]);
+        assert_eq!(
+            command_argv("code --new-window", "/my src"),
+            vec!["code", "--new-window", "/my src"]
+        );
+        assert_eq!(
+            command_argv("emacsclient -c {dir}/README.md", "/src"),
+            vec!["emacsclient", "-c", "/src/README.md"]
+        );
+    }
+
+    #[test]
+    fn test_configured_command_wins() {
+        assert_eq!(resolve_command(Some("hx")), Some("hx".to_string()));
+        assert_eq!(resolve_command(Some("  ")), None);
+    }
+}
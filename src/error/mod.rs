@@ -0,0 +1,77 @@
+use std::path::PathBuf;
+use std::time::Duration;
+
+/// Errors returned by the library's public API
+#[derive(Debug, thiserror::Error)]
+pub enum AgentRustyError {
+    /// The tmux binary couldn't be started
+    #[error("tmux not found at '{path}'")]
+    TmuxNotFound { path: String },
+    /// tmux ran but reported a failure
+    #[error("tmux {command} failed: {stderr}")]
+    TmuxCommandFailed { command: String, stderr: String },
+    /// tmux didn't finish in time and was killed
+    #[error("tmux {command} timed out after {}s", after.as_secs_f32())]
+    TmuxTimedOut { command: String, after: Duration },
+    /// No session has the given name or ID
+    #[error("no session named '{0}'")]
+    SessionNotFound(String),
+    /// A session with the name already exists
+    #[error("duplicate session: {0}")]
+    DuplicateSession(String),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    /// None of the clipboard backends could take the text; the source lists each failure
+    #[error("clipboard unavailable")]
+    ClipboardUnavailable(#[source] crate::clipboard::BackendFailure),
+    /// The config file couldn't be read or parsed
+    #[error("invalid config {}: {message}", path.display())]
+    ConfigInvalid { path: PathBuf, message: String },
+}
+
+/// Result with [`AgentRustyError`] as the default error
+pub type Result<T, E = AgentRustyError> = std::result::Result<T, E>;
+
+impl AgentRustyError {
+    /// Error for a tmux invocation that failed to start or exited unsuccessfully
+    pub(crate) fn tmux(command: &str, stderr: &[u8]) -> Self {
+        Self::TmuxCommandFailed {
+            command: command.to_string(),
+            stderr: String::from_utf8_lossy(stderr).trim().to_string(),
+        }
+    }
+
+    /// Map a failure to spawn `tmux_path`, singling out a missing binary
+    pub(crate) fn spawn(tmux_path: &str, error: std::io::Error) -> Self {
+        if error.kind() == std::io::ErrorKind::NotFound {
+            Self::TmuxNotFound {
+                path: tmux_path.to_string(),
+            }
+        } else {
+            Self::Io(error)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_messages() {
+        assert_eq!(
+            AgentRustyError::tmux("kill-session", b"can't find session: $9\n").to_string(),
+            "tmux kill-session failed: can't find session: $9"
+        );
+        let missing = std::io::Error::from(std::io::ErrorKind::NotFound);
+        assert!(matches!(
+            AgentRustyError::spawn("/opt/tmux", missing),
+            AgentRustyError::TmuxNotFound { path } if path == "/opt/tmux"
+        ));
+        let denied = std::io::Error::from(std::io::ErrorKind::PermissionDenied);
+        assert!(matches!(
+            AgentRustyError::spawn("tmux", denied),
+            AgentRustyError::Io(_)
+        ));
+    }
+}
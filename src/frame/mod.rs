@@ -0,0 +1,241 @@
+use anyhow::Result;
+use std::time::Duration;
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::actions::Action;
+use crate::input;
+
+/// How often the main loop considers drawing a frame
+pub const FRAME_INTERVAL: Duration = Duration::from_millis(100);
+
+/// Decides on each frame tick whether the screen needs redrawing
+#[derive(Debug)]
+pub struct FramePacer {
+    /// State changed since the last frame
+    dirty: bool,
+    /// Frames drawn so far
+    frames: u64,
+}
+
+impl Default for FramePacer {
+    fn default() -> Self {
+        // Nothing is on screen yet
+        Self {
+            dirty: true,
+            frames: 0,
+        }
+    }
+}
+
+impl FramePacer {
+    /// Something on screen changed; draw it on the next tick
+    pub fn invalidate(&mut self) {
+        self.dirty = true;
+    }
+
+    /// Whether to draw this tick: state changed, or an animation is running
+    pub fn should_draw(&mut self, animating: bool) -> bool {
+        let draw = self.dirty || animating;
+        self.dirty = false;
+        if draw {
+            self.frames += 1;
+        }
+        draw
+    }
+
+    pub fn frames(&self) -> u64 {
+        self.frames
+    }
+}
+
+/// Handle `first` and every action already queued behind it in one go
+///
+/// `first` may hold several actions, such as keys the terminal had queued.
+/// Only the newest session update of the burst is applied, and runs of arrow
+/// keys only by their net movement; of `j` and `k` too when `letters` (no text
+/// field has focus). Returns `true` as soon as an action asks to quit,
+/// skipping the rest.
+pub fn handle_burst(
+    rx: &mut UnboundedReceiver<Action>,
+    first: Vec<Action>,
+    letters: bool,
+    mut handle: impl FnMut(Action) -> Result<bool>,
+) -> Result<bool> {
+    let mut burst = first;
+    while let Ok(action) = rx.try_recv() {
+        burst.push(action);
+    }
+    for action in input::coalesce_navigation(coalesce(burst), letters) {
+        if handle(action)? {
+            return Ok(true);
+        }
+    }
+    Ok(false)
+}
+
+/// Drop session updates and heartbeats superseded later in `actions`
+fn coalesce(actions: Vec<Action>) -> Vec<Action> {
+    let is_update = |a: &Action| matches!(a, Action::SessionsUpdated(_) | Action::Heartbeat);
+    let last_update = actions.iter().rposition(is_update);
+    let last_sessions = actions
+        .iter()
+        .rposition(|a| matches!(a, Action::SessionsUpdated(_)));
+    actions
+        .into_iter()
+        .enumerate()
+        .filter(|(i, action)| match action {
+            // A heartbeat after the newest snapshot still marks the data fresh
+            Action::Heartbeat => Some(*i) == last_update,
+            Action::SessionsUpdated(_) => Some(*i) == last_sessions,
+            _ => true,
+        })
+        .map(|(_, action)| action)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::app::App;
+    use crate::config::Config;
+    use crate::state::PersistedState;
+    use crate::tmux::TmuxSession;
+
+    #[test]
+    fn test_burst_draws_one_frame() {
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let mut pacer = FramePacer::default();
+        assert!(pacer.should_draw(false));
+
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        for i in 0..200 {
+            let session = TmuxSession::new(format!("${}", i), format!("s{}", i));
+            tx.send(Action::SessionsUpdated(vec![session])).unwrap();
+        }
+        let first = rx.try_recv().unwrap();
+        let mut handled = 0;
+        let quit = handle_burst(&mut rx, vec![first], false, |action| {
+            handled += 1;
+            app.handle_action(action)
+        })
+        .unwrap();
+        assert!(!quit);
+        // Only the newest snapshot is applied
+        assert_eq!(handled, 1);
+        pacer.invalidate();
+
+        // Redrawing per action would have drawn 200 frames
+        assert!(pacer.should_draw(false));
+        assert!(!pacer.should_draw(false));
+        assert_eq!(pacer.frames(), 2);
+        assert_eq!(app.sessions[0].name, "s199");
+    }
+
+    #[test]
+    fn test_held_keys_draw_one_frame() {
+        use crossterm::event::{Event, KeyCode, KeyEvent, KeyEventKind, KeyModifiers};
+
+        let mut app = App::new(&Config::default(), PersistedState::default());
+        let sessions = (0..5)
+            .map(|i| TmuxSession::new(format!("${}", i), format!("s{}", i)))
+            .collect();
+        app.handle_action(Action::SessionsUpdated(sessions))
+            .unwrap();
+        let mut pacer = FramePacer::default();
+        pacer.should_draw(false);
+        let selected = |app: &App| app.selected_session().unwrap().name.clone();
+        assert_eq!(selected(&app), "s0");
+
+        // A held arrow as a terminal reports it: a press, repeats and a release
+        let key = |code, kind| {
+            Action::from_event(Event::Key(KeyEvent::new_with_kind(
+                code,
+                KeyModifiers::NONE,
+                kind,
+            )))
+        };
+        let mut burst: Vec<Action> = std::iter::once(key(KeyCode::Down, KeyEventKind::Press))
+            .chain((0..11).map(|_| key(KeyCode::Down, KeyEventKind::Repeat)))
+            .chain([key(KeyCode::Down, KeyEventKind::Release)])
+            .chain([key(KeyCode::Up, KeyEventKind::Press)])
+            .flatten()
+            .collect();
+        assert_eq!(burst.len(), 13);
+        burst.push(Action::KeyPress(KeyEvent::from(KeyCode::Char('j'))));
+
+        let (_tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        let mut handled = 0;
+        handle_burst(&mut rx, burst, false, |action| {
+            handled += 1;
+            app.handle_action(action)
+        })
+        .unwrap();
+        // Down 12 times and up once is 11 steps, then one more for `j`
+        assert_eq!(handled, 12);
+        assert_eq!(selected(&app), "s2");
+        pacer.invalidate();
+        assert!(pacer.should_draw(false));
+        assert!(!pacer.should_draw(false));
+        assert_eq!(pacer.frames(), 2);
+    }
+
+    #[test]
+    fn test_burst_stops_at_quit() {
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        tx.send(Action::Quit).unwrap();
+        tx.send(Action::Refresh).unwrap();
+        let mut handled = Vec::new();
+        let quit = handle_burst(&mut rx, vec![Action::Refresh], false, |action| {
+            handled.push(action.label());
+            Ok(matches!(action, Action::Quit))
+        })
+        .unwrap();
+        assert!(quit);
+        assert_eq!(handled, ["refresh", "quit"]);
+    }
+
+    #[test]
+    fn test_coalesce_keeps_other_actions_in_order() {
+        let sessions = |name: &str| {
+            Action::SessionsUpdated(vec![TmuxSession::new("$1".to_string(), name.to_string())])
+        };
+        let labels = |actions: Vec<Action>| {
+            actions
+                .iter()
+                .map(|a| match a {
+                    Action::SessionsUpdated(s) => s[0].name.clone(),
+                    other => other.label().to_string(),
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            labels(coalesce(vec![
+                sessions("old"),
+                Action::Heartbeat,
+                Action::Refresh,
+                sessions("new"),
+                Action::TakeSnapshot,
+            ])),
+            ["refresh", "new", "take_snapshot"]
+        );
+        assert_eq!(
+            labels(coalesce(vec![
+                sessions("new"),
+                Action::Heartbeat,
+                Action::Heartbeat,
+            ])),
+            ["new", "heartbeat"]
+        );
+    }
+
+    #[test]
+    fn test_animation_keeps_drawing() {
+        let mut pacer = FramePacer::default();
+        pacer.should_draw(false);
+        for _ in 0..5 {
+            assert!(pacer.should_draw(true));
+        }
+        assert!(!pacer.should_draw(false));
+        assert_eq!(pacer.frames(), 6);
+    }
+}
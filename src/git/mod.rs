@@ -0,0 +1,190 @@
+use futures_util::future::join_all;
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeSet, HashMap};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::process::Command;
+
+/// How long a directory's git state is reused before looking it up again
+pub const GIT_CACHE_TTL: Duration = Duration::from_secs(10);
+
+/// What HEAD points at
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub enum GitHead {
+    /// On a named branch
+    Branch(String),
+    /// Detached at a commit (short hash)
+    Detached(String),
+}
+
+/// Git state of a session's working directory
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct GitInfo {
+    pub head: GitHead,
+    /// Tracked files have uncommitted changes
+    pub dirty: bool,
+}
+
+impl GitInfo {
+    /// Short label such as `main*` or `@a1b2c3d`
+    pub fn label(&self) -> String {
+        let head = match &self.head {
+            GitHead::Branch(name) => name.clone(),
+            GitHead::Detached(hash) => format!("@{}", hash),
+        };
+        if self.dirty {
+            format!("{}*", head)
+        } else {
+            head
+        }
+    }
+
+    /// Longer description for the detail pane
+    pub fn describe(&self) -> String {
+        let head = match &self.head {
+            GitHead::Branch(name) => name.clone(),
+            GitHead::Detached(hash) => format!("detached HEAD at {}", hash),
+        };
+        if self.dirty {
+            format!("{} (uncommitted changes)", head)
+        } else {
+            head
+        }
+    }
+}
+
+/// Interpret `git rev-parse --abbrev-ref HEAD` output
+fn parse_head(abbrev_ref: &str, short_hash: impl FnOnce() -> Option<String>) -> Option<GitHead> {
+    match abbrev_ref.trim() {
+        "" => None,
+        "HEAD" => short_hash().map(GitHead::Detached),
+        branch => Some(GitHead::Branch(branch.to_string())),
+    }
+}
+
+async fn git(dir: &Path, args: &[&str]) -> Option<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .await
+        .ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Look up the git state of `dir`; `None` for directories outside a repository
+pub async fn lookup(dir: &Path) -> Option<GitInfo> {
+    let abbrev_ref = git(dir, &["rev-parse", "--abbrev-ref", "HEAD"]).await?;
+    let hash = if abbrev_ref == "HEAD" {
+        git(dir, &["rev-parse", "--short", "HEAD"]).await
+    } else {
+        None
+    };
+    let head = parse_head(&abbrev_ref, || hash)?;
+    let dirty = git(dir, &["status", "--porcelain", "--untracked-files=no"])
+        .await
+        .is_some_and(|status| !status.is_empty());
+    Some(GitInfo { head, dirty })
+}
+
+/// Per-directory cache of git lookups
+pub struct GitCache {
+    entries: HashMap<PathBuf, (Instant, Option<GitInfo>)>,
+    ttl: Duration,
+}
+
+impl GitCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+        }
+    }
+
+    /// Git state of each of `dirs`, in order
+    ///
+    /// Directories whose cached value is older than the TTL are looked up
+    /// again, all at once, so one slow repository doesn't hold up the rest.
+    pub async fn get_all(&mut self, dirs: &[PathBuf]) -> Vec<Option<GitInfo>> {
+        let stale: BTreeSet<&PathBuf> = dirs
+            .iter()
+            .filter(|dir| {
+                self.entries
+                    .get(*dir)
+                    .is_none_or(|(at, _)| at.elapsed() >= self.ttl)
+            })
+            .collect();
+        let found = join_all(stale.iter().map(|dir| lookup(dir))).await;
+        let now = Instant::now();
+        for (dir, info) in stale.into_iter().zip(found) {
+            self.entries.insert(dir.clone(), (now, info));
+        }
+        dirs.iter()
+            .map(|dir| self.entries.get(dir).and_then(|(_, info)| info.clone()))
+            .collect()
+    }
+
+    /// Forget directories no session is in any more
+    pub fn retain(&mut self, mut keep: impl FnMut(&Path) -> bool) {
+        self.entries.retain(|dir, _| keep(dir));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse_head() {
+        assert_eq!(
+            parse_head("main\n", || None),
+            Some(GitHead::Branch("main".to_string()))
+        );
+        assert_eq!(
+            parse_head("HEAD", || Some("a1b2c3d".to_string())),
+            Some(GitHead::Detached("a1b2c3d".to_string()))
+        );
+        assert_eq!(parse_head("", || None), None);
+    }
+
+    #[test]
+    fn test_labels() {
+        let info = GitInfo {
+            head: GitHead::Branch("feature/login".to_string()),
+            dirty: true,
+        };
+        assert_eq!(info.label(), "feature/login*");
+        assert_eq!(info.describe(), "feature/login (uncommitted changes)");
+
+        let info = GitInfo {
+            head: GitHead::Detached("a1b2c3d".to_string()),
+            dirty: false,
+        };
+        assert_eq!(info.label(), "@a1b2c3d");
+        assert_eq!(info.describe(), "detached HEAD at a1b2c3d");
+    }
+
+    #[tokio::test]
+    async fn test_cache_covers_every_dir_and_forgets() {
+        let outside =
+            std::env::temp_dir().join(format!("agent-rusty-no-git-{}", std::process::id()));
+        std::fs::create_dir_all(&outside).unwrap();
+        let repo = PathBuf::from(env!("CARGO_MANIFEST_DIR"));
+        let mut cache = GitCache::new(Duration::from_secs(60));
+
+        let dirs = [outside.clone(), repo.clone(), outside.clone()];
+        let infos = cache.get_all(&dirs).await;
+        assert_eq!(infos.len(), 3);
+        assert_eq!((&infos[0], &infos[2]), (&None, &None));
+        assert_eq!(cache.entries.len(), 2);
+
+        cache.retain(|dir| dir == repo);
+        assert_eq!(cache.entries.len(), 1);
+        assert!(cache.entries.contains_key(&repo));
+        let _ = std::fs::remove_dir_all(&outside);
+    }
+}
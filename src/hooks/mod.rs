@@ -0,0 +1,443 @@
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::process::Stdio;
+use std::time::{Duration, Instant};
+use tokio::io::AsyncWriteExt;
+use tokio::process::Command;
+
+use crate::metrics;
+use crate::poller::Transition;
+use crate::script;
+use crate::tmux::AgentStatus;
+
+/// A hook still running after this long is killed
+const HOOK_TIMEOUT: Duration = Duration::from_secs(30);
+
+/// What a hook reacts to
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(try_from = "String", into = "String")]
+pub enum Trigger {
+    /// Any change into this status, written `error`
+    Enter(AgentStatus),
+    /// One specific change, written `busy->idle`
+    Change(AgentStatus, AgentStatus),
+}
+
+impl TryFrom<String> for Trigger {
+    type Error = String;
+
+    fn try_from(value: String) -> Result<Self, Self::Error> {
+        match value.split_once("->") {
+            Some((from, to)) => Ok(Trigger::Change(from.trim().parse()?, to.trim().parse()?)),
+            None => Ok(Trigger::Enter(value.trim().parse()?)),
+        }
+    }
+}
+
+impl From<Trigger> for String {
+    fn from(trigger: Trigger) -> Self {
+        trigger.to_string()
+    }
+}
+
+impl std::fmt::Display for Trigger {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Trigger::Enter(status) => f.write_str(metrics::label(*status)),
+            Trigger::Change(from, to) => {
+                write!(f, "{}->{}", metrics::label(*from), metrics::label(*to))
+            }
+        }
+    }
+}
+
+impl Trigger {
+    pub fn matches(self, transition: &Transition) -> bool {
+        match self {
+            Trigger::Enter(status) => transition.status == status,
+            Trigger::Change(from, to) => transition.previous == from && transition.status == to,
+        }
+    }
+}
+
+/// `[[hooks]]` entry of the config
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HookConfig {
+    /// Statuses (`error`) or transitions (`busy->idle`) that fire the hook
+    pub on: Vec<Trigger>,
+    /// Shell command; `{session}`, `{status}` and `{previous}` are replaced with their values,
+    /// quoted wherever they appear
+    #[serde(default)]
+    pub run: Option<String>,
+    /// URL that receives a JSON POST (sent with `curl`)
+    #[serde(default)]
+    pub url: Option<String>,
+    /// Minimum seconds between firings for the same session
+    #[serde(default = "default_debounce_secs")]
+    pub debounce_secs: u64,
+    /// Stay quiet while the agent-rusty terminal has focus (for desktop notifications);
+    /// terminals that don't report focus always fire
+    #[serde(default)]
+    pub only_unfocused: bool,
+}
+
+fn default_debounce_secs() -> u64 {
+    10
+}
+
+/// Turn `template` into a script that sets `transition`'s values in
+/// variables and refers to them where the placeholders were (see
+/// [`script::expand`])
+pub fn expand(template: &str, transition: &Transition) -> String {
+    let status = format!("{:?}", transition.status);
+    let previous = format!("{:?}", transition.previous);
+    script::expand(
+        template,
+        &[
+            ("session", "AGENT_RUSTY_SESSION", &transition.session),
+            ("status", "AGENT_RUSTY_STATUS", &status),
+            ("previous", "AGENT_RUSTY_PREVIOUS", &previous),
+        ],
+    )
+}
+
+/// Body POSTed to `url` hooks
+pub fn json_body(transition: &Transition) -> String {
+    serde_json::json!({
+        "session": transition.session,
+        "session_id": transition.session_id,
+        "status": transition.status,
+        "previous": transition.previous,
+    })
+    .to_string()
+}
+
+/// Runs configured hooks for status transitions, at most once per debounce window
+pub struct HookDispatcher {
+    hooks: Vec<HookConfig>,
+    /// Last firing per (hook index, session id)
+    last_fired: HashMap<(usize, String), Instant>,
+    /// The terminal reported that it has focus
+    focused: bool,
+}
+
+impl HookDispatcher {
+    pub fn new(hooks: Vec<HookConfig>) -> Self {
+        Self {
+            hooks,
+            last_fired: HashMap::new(),
+            focused: false,
+        }
+    }
+
+    /// Whether the user is known to be looking at agent-rusty
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+
+    /// Start the hooks due for `transitions` in the background
+    pub fn dispatch(&mut self, transitions: &[Transition]) {
+        for (hook, transition) in self.due(transitions, Instant::now()) {
+            tokio::spawn(run_hook(hook, transition));
+        }
+    }
+
+    /// Hooks matching `transitions` that are outside their debounce window
+    fn due(&mut self, transitions: &[Transition], now: Instant) -> Vec<(HookConfig, Transition)> {
+        let mut due = Vec::new();
+        for transition in transitions {
+            for (index, hook) in self.hooks.iter().enumerate() {
+                if !hook.on.iter().any(|t| t.matches(transition))
+                    || (hook.only_unfocused && self.focused)
+                {
+                    continue;
+                }
+                let key = (index, transition.session_id.clone());
+                let debounce = Duration::from_secs(hook.debounce_secs);
+                if let Some(last) = self.last_fired.get(&key)
+                    && now.duration_since(*last) < debounce
+                {
+                    tracing::debug!(hook = index, session = %transition.session, "Hook debounced");
+                    continue;
+                }
+                self.last_fired.insert(key, now);
+                due.push((hook.clone(), transition.clone()));
+            }
+        }
+        due
+    }
+}
+
+/// Run `template` through `sh` with placeholders and environment set from `transition`
+pub async fn run_shell(template: &str, transition: &Transition) -> Result<(), String> {
+    let mut command = Command::new("sh");
+    command
+        .arg("-c")
+        .arg(expand(template, transition))
+        .env("AGENT_RUSTY_SESSION", &transition.session)
+        .env("AGENT_RUSTY_STATUS", format!("{:?}", transition.status))
+        .env("AGENT_RUSTY_PREVIOUS", format!("{:?}", transition.previous));
+    run(command, None).await
+}
+
+/// Show a desktop notification with `notify-send`, or `osascript` on macOS
+pub async fn notify(summary: &str, body: &str) -> Result<(), String> {
+    let command = if cfg!(target_os = "macos") {
+        let mut command = Command::new("osascript");
+        command.arg("-e").arg(format!(
+            "display notification {:?} with title {:?}",
+            body, summary
+        ));
+        command
+    } else {
+        let mut command = Command::new("notify-send");
+        command.args(["--app-name=agent-rusty", summary, body]);
+        command
+    };
+    run(command, None).await
+}
+
+async fn run_hook(hook: HookConfig, transition: Transition) {
+    if let Some(template) = &hook.run
+        && let Err(e) = run_shell(template, &transition).await
+    {
+        tracing::warn!(
+            "Hook '{}' for {} failed: {}",
+            template,
+            transition.session,
+            e
+        );
+    }
+    if let Some(url) = &hook.url {
+        let mut command = Command::new("curl");
+        command.args([
+            "-fsS",
+            "-X",
+            "POST",
+            "-H",
+            "Content-Type: application/json",
+            "--data-binary",
+            "@-",
+            url,
+        ]);
+        if let Err(e) = run(command, Some(json_body(&transition))).await {
+            tracing::warn!(
+                "Hook POST to {} for {} failed: {}",
+                url,
+                transition.session,
+                e
+            );
+        }
+    }
+}
+
+/// Run `command` with an optional stdin payload, bounded by [`HOOK_TIMEOUT`]
+async fn run(mut command: Command, input: Option<String>) -> Result<(), String> {
+    let mut child = command
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::null())
+        .stderr(Stdio::piped())
+        .kill_on_drop(true)
+        .spawn()
+        .map_err(|e| e.to_string())?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin
+            .write_all(input.as_bytes())
+            .await
+            .map_err(|e| e.to_string())?;
+    }
+    let output = tokio::time::timeout(HOOK_TIMEOUT, child.wait_with_output())
+        .await
+        .map_err(|_| format!("timed out after {}s", HOOK_TIMEOUT.as_secs()))?
+        .map_err(|e| e.to_string())?;
+    if output.status.success() {
+        Ok(())
+    } else {
+        Err(format!(
+            "{}: {}",
+            output.status,
+            String::from_utf8_lossy(&output.stderr).trim()
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transition(session: &str, previous: AgentStatus, status: AgentStatus) -> Transition {
+        Transition {
+            session_id: format!("${}", session),
+            session: session.to_string(),
+            previous,
+            status,
+        }
+    }
+
+    fn hook(on: &[&str], debounce_secs: u64) -> HookConfig {
+        HookConfig {
+            on: on
+                .iter()
+                .map(|t| Trigger::try_from(t.to_string()).unwrap())
+                .collect(),
+            run: Some("true".to_string()),
+            url: None,
+            debounce_secs,
+            only_unfocused: false,
+        }
+    }
+
+    #[test]
+    fn test_parse_triggers() {
+        assert_eq!(
+            Trigger::try_from("waiting-for-input".to_string()),
+            Ok(Trigger::Enter(AgentStatus::WaitingForInput))
+        );
+        assert_eq!(
+            Trigger::try_from("Busy -> Idle".to_string()),
+            Ok(Trigger::Change(AgentStatus::Busy, AgentStatus::Idle))
+        );
+        assert!(Trigger::try_from("busy->asleep".to_string()).is_err());
+        for text in ["waiting-for-input", "busy->idle"] {
+            let trigger = Trigger::try_from(text.to_string()).unwrap();
+            assert_eq!(trigger.to_string(), text);
+        }
+
+        let t = transition("api", AgentStatus::Busy, AgentStatus::Idle);
+        assert!(Trigger::Enter(AgentStatus::Idle).matches(&t));
+        assert!(Trigger::Change(AgentStatus::Busy, AgentStatus::Idle).matches(&t));
+        assert!(!Trigger::Change(AgentStatus::Error, AgentStatus::Idle).matches(&t));
+    }
+
+    /// Output of the script `template` expands to for `t`
+    fn run_expanded(template: &str, t: &Transition) -> String {
+        let script = expand(template, t);
+        let output = std::process::Command::new("sh")
+            .arg("-c")
+            .arg(&script)
+            .output()
+            .unwrap();
+        assert!(output.status.success(), "{}", script);
+        String::from_utf8(output.stdout).unwrap()
+    }
+
+    #[test]
+    fn test_expand_placeholders() {
+        let t = transition("api", AgentStatus::Busy, AgentStatus::WaitingForInput);
+        assert_eq!(
+            expand("notify-send {session} '{status} <- {previous}'", &t),
+            "AGENT_RUSTY_SESSION='api'\nAGENT_RUSTY_STATUS='WaitingForInput'\n\
+             AGENT_RUSTY_PREVIOUS='Busy'\nnotify-send \"$AGENT_RUSTY_SESSION\" \
+             ''\"$AGENT_RUSTY_STATUS\"' <- '\"$AGENT_RUSTY_PREVIOUS\"''"
+        );
+        // Unknown placeholders and stray braces stay as written
+        assert_eq!(
+            run_expanded("echo {name} {session", &t),
+            "{name} {session\n"
+        );
+        assert_eq!(run_expanded("echo '{a: 1}' {session}", &t), "{a: 1} api\n");
+
+        // Values are substituted once, never re-expanded
+        let t = transition("{status}", AgentStatus::Busy, AgentStatus::Idle);
+        assert_eq!(run_expanded("echo {session}", &t), "{status}\n");
+    }
+
+    #[test]
+    fn test_shell_argument_safety() {
+        let nasty = r#"it's $(echo INJECTED) `id` "q"; rm -rf ~ *"#;
+        let t = transition(nasty, AgentStatus::Busy, AgentStatus::Error);
+        assert_eq!(
+            run_expanded("printf '%s|' {session} {status}", &t),
+            format!("{}|Error|", nasty)
+        );
+        // Inside quotes of either kind the value stays one piece of the word
+        assert_eq!(
+            run_expanded("printf '%s|' '{session} needs you'", &t),
+            format!("{} needs you|", nasty)
+        );
+        assert_eq!(
+            run_expanded(r#"printf '%s|' "{session} is {status}""#, &t),
+            format!("{} is Error|", nasty)
+        );
+        let t = transition("$(echo INJECTED)", AgentStatus::Busy, AgentStatus::Error);
+        assert_eq!(
+            run_expanded("printf '%s|' '{session}' \"'{session}'\"", &t),
+            "$(echo INJECTED)|'$(echo INJECTED)'|"
+        );
+    }
+
+    #[test]
+    fn test_json_body() {
+        let t = transition("api", AgentStatus::Busy, AgentStatus::Error);
+        let body: serde_json::Value = serde_json::from_str(&json_body(&t)).unwrap();
+        assert_eq!(
+            body,
+            serde_json::json!({
+                "session": "api",
+                "session_id": "$api",
+                "status": "Error",
+                "previous": "Busy",
+            })
+        );
+    }
+
+    #[test]
+    fn test_debounce_per_hook_and_session() {
+        let mut dispatcher = HookDispatcher::new(vec![hook(&["error"], 30), hook(&["error"], 0)]);
+        let start = Instant::now();
+        let api = transition("api", AgentStatus::Busy, AgentStatus::Error);
+        let web = transition("web", AgentStatus::Busy, AgentStatus::Error);
+        let idle = transition("api", AgentStatus::Error, AgentStatus::Idle);
+
+        assert_eq!(dispatcher.due(&[api.clone(), idle], start).len(), 2);
+        // Within the window only the undebounced hook fires again; other sessions are separate
+        let due = dispatcher.due(&[api.clone(), web], start + Duration::from_secs(5));
+        let fired: Vec<(u64, &str)> = due
+            .iter()
+            .map(|(h, t)| (h.debounce_secs, t.session.as_str()))
+            .collect();
+        assert_eq!(fired, [(0, "api"), (30, "web"), (0, "web")]);
+
+        assert_eq!(
+            dispatcher
+                .due(&[api], start + Duration::from_secs(31))
+                .len(),
+            2
+        );
+    }
+
+    #[test]
+    fn test_only_unfocused() {
+        let notify = HookConfig {
+            only_unfocused: true,
+            ..hook(&["error"], 0)
+        };
+        let mut dispatcher = HookDispatcher::new(vec![notify, hook(&["error"], 0)]);
+        let api = transition("api", AgentStatus::Busy, AgentStatus::Error);
+        let start = Instant::now();
+
+        // Focus unknown or lost: everything fires
+        assert_eq!(dispatcher.due(std::slice::from_ref(&api), start).len(), 2);
+        dispatcher.set_focused(true);
+        let due = dispatcher.due(std::slice::from_ref(&api), start);
+        assert_eq!(due.len(), 1);
+        assert!(!due[0].0.only_unfocused);
+        dispatcher.set_focused(false);
+        assert_eq!(dispatcher.due(&[api], start).len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_run_reports_failures() {
+        assert!(run(Command::new("true"), None).await.is_ok());
+        let mut command = Command::new("sh");
+        command.args(["-c", "echo broken >&2; exit 3"]);
+        let error = run(command, None).await.unwrap_err();
+        assert!(error.ends_with(": broken"), "{}", error);
+        assert!(run(Command::new("/no/such/hook"), None).await.is_err());
+    }
+}
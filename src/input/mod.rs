@@ -0,0 +1,240 @@
+//! What a held key or a burst of queued keys does.
+//!
+//! Held keys repeat, except the ones that delete, restart or confirm: outside
+//! text fields their repeats are dropped however the terminal reports them, so
+//! holding `d` or `y` can't run through a confirmation. Runs of arrow keys
+//! queued behind one another are reduced to their net movement before the
+//! next frame, and so are `j` and `k` while no text field has focus.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::time::{Duration, Instant};
+
+use crate::actions::Action;
+
+/// A destructive key pressed again this soon after the last time is taken
+/// for auto-repeat and dropped
+pub const REPEAT_GAP: Duration = Duration::from_millis(300);
+
+/// Keys that delete, restart or confirm
+pub fn is_destructive(key: &KeyEvent) -> bool {
+    matches!(key.code, KeyCode::Char('d' | 'R' | 'y' | 'Y'))
+}
+
+/// Drops auto-repeated destructive keys that terminals report as presses
+#[derive(Debug, Default)]
+pub struct RepeatLimiter {
+    /// Last destructive key seen, and when
+    last: Option<(KeyCode, Instant)>,
+}
+
+impl RepeatLimiter {
+    /// Whether a press of `key` at `now` should be handled
+    ///
+    /// Every press counts as seen, so a held key stays blocked until it has
+    /// been released for [`REPEAT_GAP`].
+    pub fn admit(&mut self, key: &KeyEvent, now: Instant) -> bool {
+        if !is_destructive(key) {
+            self.last = None;
+            return true;
+        }
+        let repeated = self
+            .last
+            .is_some_and(|(code, at)| code == key.code && now.duration_since(at) < REPEAT_GAP);
+        self.last = Some((key.code, now));
+        !repeated
+    }
+}
+
+/// Direction and size of a navigation key's step; `j` and `k` only when
+/// `letters`, as they could be typed text otherwise
+fn step(key: &KeyEvent, letters: bool) -> Option<(bool, i64)> {
+    if !key.modifiers.difference(KeyModifiers::SHIFT).is_empty() {
+        return None;
+    }
+    match key.code {
+        KeyCode::Down => Some((false, 1)),
+        KeyCode::Up => Some((false, -1)),
+        KeyCode::Char('j') if letters => Some((false, 1)),
+        KeyCode::Char('k') if letters => Some((false, -1)),
+        KeyCode::PageDown => Some((true, 1)),
+        KeyCode::PageUp => Some((true, -1)),
+        _ => None,
+    }
+}
+
+/// Consecutive movements along one axis
+struct Run {
+    page: bool,
+    net: i64,
+    /// Last key pressed each way
+    forward: Option<KeyEvent>,
+    backward: Option<KeyEvent>,
+}
+
+impl Run {
+    fn presses(self) -> impl Iterator<Item = Action> {
+        let key = if self.net > 0 {
+            self.forward
+        } else {
+            self.backward
+        };
+        let count = self.net.unsigned_abs() as usize;
+        key.into_iter()
+            .flat_map(move |key| std::iter::repeat_n(Action::KeyPress(key), count))
+    }
+}
+
+/// Replace each run of line (or page) movements in `actions` with the
+/// presses of its net movement, so a backlog of arrows that cancel out
+/// moves nothing
+///
+/// With `letters`, `j` and `k` count as movements too, up to the first other
+/// action: any other key could open a text field.
+pub fn coalesce_navigation(actions: Vec<Action>, mut letters: bool) -> Vec<Action> {
+    let mut coalesced = Vec::with_capacity(actions.len());
+    let mut run: Option<Run> = None;
+    for action in actions {
+        let movement = match &action {
+            Action::KeyPress(key) => step(key, letters).map(|(page, delta)| (page, delta, *key)),
+            _ => None,
+        };
+        let Some((page, delta, key)) = movement else {
+            letters = false;
+            coalesced.extend(run.take().into_iter().flat_map(Run::presses));
+            coalesced.push(action);
+            continue;
+        };
+        if run.as_ref().is_some_and(|run| run.page != page) {
+            coalesced.extend(run.take().into_iter().flat_map(Run::presses));
+        }
+        let run = run.get_or_insert(Run {
+            page,
+            net: 0,
+            forward: None,
+            backward: None,
+        });
+        run.net += delta;
+        if delta > 0 {
+            run.forward = Some(key);
+        } else {
+            run.backward = Some(key);
+        }
+    }
+    coalesced.extend(run.into_iter().flat_map(Run::presses));
+    coalesced
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(code: KeyCode) -> Action {
+        Action::KeyPress(KeyEvent::from(code))
+    }
+
+    fn codes(actions: &[Action]) -> Vec<KeyCode> {
+        actions
+            .iter()
+            .filter_map(|a| match a {
+                Action::KeyPress(key) => Some(key.code),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_limiter_blocks_held_destructive_keys() {
+        let mut limiter = RepeatLimiter::default();
+        let start = Instant::now();
+        let d = KeyEvent::from(KeyCode::Char('d'));
+        let j = KeyEvent::from(KeyCode::Char('j'));
+
+        // Held: 30 repeats a second never get through
+        assert!(limiter.admit(&d, start));
+        for i in 1..30 {
+            assert!(!limiter.admit(&d, start + Duration::from_millis(33 * i)));
+        }
+        // Released, then pressed again
+        assert!(limiter.admit(&d, start + Duration::from_millis(33 * 29) + REPEAT_GAP));
+
+        // Other keys repeat freely, and break a destructive key's streak
+        assert!(limiter.admit(&j, start));
+        assert!(limiter.admit(&j, start));
+        assert!(limiter.admit(&d, start));
+        assert!(limiter.admit(&j, start));
+        assert!(limiter.admit(&d, start));
+    }
+
+    #[test]
+    fn test_arrow_runs_net_out() {
+        let mut burst = vec![key(KeyCode::Down); 7];
+        burst.extend(vec![key(KeyCode::Up); 2]);
+        assert_eq!(
+            codes(&coalesce_navigation(burst, false)),
+            vec![KeyCode::Down; 5]
+        );
+
+        let burst = vec![
+            key(KeyCode::Up),
+            key(KeyCode::Down),
+            key(KeyCode::Up),
+            key(KeyCode::Up),
+        ];
+        assert_eq!(
+            codes(&coalesce_navigation(burst, false)),
+            vec![KeyCode::Up; 2]
+        );
+
+        let burst = vec![key(KeyCode::PageDown), key(KeyCode::PageUp)];
+        assert!(coalesce_navigation(burst, false).is_empty());
+    }
+
+    #[test]
+    fn test_other_actions_split_runs() {
+        // Letters may be typed text, so they pass through as they are
+        let burst = vec![
+            key(KeyCode::Down),
+            key(KeyCode::Char('k')),
+            key(KeyCode::Up),
+            key(KeyCode::PageDown),
+            key(KeyCode::Down),
+            Action::Refresh,
+            key(KeyCode::Char('j')),
+            key(KeyCode::Char('j')),
+        ];
+        let coalesced = coalesce_navigation(burst, false);
+        assert_eq!(
+            codes(&coalesced),
+            [
+                KeyCode::Down,
+                KeyCode::Char('k'),
+                KeyCode::Up,
+                KeyCode::PageDown,
+                KeyCode::Down,
+                KeyCode::Char('j'),
+                KeyCode::Char('j'),
+            ]
+        );
+        assert_eq!(coalesced.len(), 8);
+    }
+
+    #[test]
+    fn test_held_j_nets_out_outside_text() {
+        let mut burst = vec![key(KeyCode::Char('j')); 6];
+        burst.push(key(KeyCode::Down));
+        burst.push(key(KeyCode::Char('k')));
+        assert_eq!(
+            codes(&coalesce_navigation(burst, true)),
+            vec![KeyCode::Down; 6]
+        );
+
+        // After a key that could open a text field, letters are left alone
+        let burst = vec![
+            key(KeyCode::Char('j')),
+            key(KeyCode::Char('/')),
+            key(KeyCode::Char('j')),
+            key(KeyCode::Char('k')),
+        ];
+        assert_eq!(coalesce_navigation(burst, true).len(), 4);
+    }
+}
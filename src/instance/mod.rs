@@ -0,0 +1,248 @@
+use std::io::{BufRead, BufReader, ErrorKind, Write};
+use std::os::unix::fs::{DirBuilderExt, MetadataExt};
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt};
+
+/// How long to wait on the other instance during the handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(2);
+
+/// Default lock socket location
+pub fn default_path() -> PathBuf {
+    runtime_dir().join("agent-rusty.lock")
+}
+
+/// Where this user's sockets live: `$XDG_RUNTIME_DIR`, or else a directory of
+/// their own in the temp dir, whose top level every user shares
+pub fn runtime_dir() -> PathBuf {
+    std::env::var_os("XDG_RUNTIME_DIR")
+        .map(PathBuf::from)
+        .unwrap_or_else(|| std::env::temp_dir().join(format!("agent-rusty-{}", uid())))
+}
+
+/// Create `dir` for sockets, only accessible to this user; one that already
+/// exists must belong to them, so nobody else can plant or read sockets there
+pub fn create_private_dir(dir: &Path) -> std::io::Result<()> {
+    std::fs::DirBuilder::new()
+        .recursive(true)
+        .mode(0o700)
+        .create(dir)?;
+    if std::fs::metadata(dir)?.uid() != uid() {
+        return Err(std::io::Error::new(
+            ErrorKind::PermissionDenied,
+            format!("{} belongs to another user", dir.display()),
+        ));
+    }
+    Ok(())
+}
+
+fn uid() -> u32 {
+    // SAFETY: getuid(2) has no preconditions and always succeeds.
+    unsafe { libc::getuid() }
+}
+
+/// Result of trying to become the only running instance
+#[derive(Debug)]
+pub enum Acquire {
+    Acquired(InstanceLock),
+    /// Another live instance holds the lock
+    Held {
+        pid: Option<u32>,
+    },
+}
+
+/// Ownership of the lock socket; the socket file is removed on drop
+#[derive(Debug)]
+pub struct InstanceLock {
+    path: PathBuf,
+    listener: Option<UnixListener>,
+}
+
+impl InstanceLock {
+    /// Take the listener so it can be served on a background task
+    pub fn take_listener(&mut self) -> Option<UnixListener> {
+        self.listener.take()
+    }
+}
+
+impl Drop for InstanceLock {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_file(&self.path);
+    }
+}
+
+/// Bind the lock socket at `path`, reclaiming it if its owner is gone
+pub fn acquire(path: &Path) -> std::io::Result<Acquire> {
+    if let Some(parent) = path.parent() {
+        create_private_dir(parent)?;
+    }
+    match UnixListener::bind(path) {
+        Ok(listener) => Ok(Acquire::Acquired(InstanceLock {
+            path: path.to_path_buf(),
+            listener: Some(listener),
+        })),
+        Err(e) if e.kind() == ErrorKind::AddrInUse => match query(path, "who") {
+            Ok(reply) => Ok(Acquire::Held {
+                pid: reply.strip_prefix("pid ").and_then(|p| p.parse().ok()),
+            }),
+            // Nobody is listening: a crashed instance left the file behind
+            Err(_) => {
+                tracing::info!("Reclaiming stale lock {}", path.display());
+                std::fs::remove_file(path)?;
+                acquire(path)
+            }
+        },
+        Err(e) => Err(e),
+    }
+}
+
+/// Ask the instance holding `path` to quit, then wait for it to let go
+pub fn request_takeover(path: &Path) -> std::io::Result<()> {
+    let reply = query(path, "quit")?;
+    if reply != "ok" {
+        return Err(std::io::Error::other(format!(
+            "unexpected reply '{}'",
+            reply
+        )));
+    }
+    let deadline = Instant::now() + HANDSHAKE_TIMEOUT;
+    while path.exists() {
+        if Instant::now() >= deadline {
+            return Err(std::io::Error::new(
+                ErrorKind::TimedOut,
+                "the other instance did not exit",
+            ));
+        }
+        std::thread::sleep(Duration::from_millis(50));
+    }
+    Ok(())
+}
+
+/// Send one request line and read the one-line reply
+fn query(path: &Path, request: &str) -> std::io::Result<String> {
+    let mut stream = UnixStream::connect(path)?;
+    stream.set_read_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    stream.set_write_timeout(Some(HANDSHAKE_TIMEOUT))?;
+    writeln!(stream, "{}", request)?;
+    let mut reply = String::new();
+    BufReader::new(stream).read_line(&mut reply)?;
+    Ok(reply.trim().to_string())
+}
+
+/// Reply to a request line; the flag says whether to quit afterwards
+fn respond(request: &str, pid: u32) -> (String, bool) {
+    match request.trim() {
+        "who" => (format!("pid {}", pid), false),
+        "quit" => ("ok".to_string(), true),
+        other => (format!("error unknown request '{}'", other), false),
+    }
+}
+
+/// Answer other instances on the lock socket; `on_quit` runs on a takeover
+pub async fn serve(listener: UnixListener, on_quit: impl Fn() + Send + 'static) {
+    let listener = match listener
+        .set_nonblocking(true)
+        .and_then(|_| tokio::net::UnixListener::from_std(listener))
+    {
+        Ok(listener) => listener,
+        Err(e) => {
+            tracing::warn!("Can't serve instance lock: {}", e);
+            return;
+        }
+    };
+    let pid = std::process::id();
+    while let Ok((stream, _)) = listener.accept().await {
+        let (reader, mut writer) = stream.into_split();
+        let mut line = String::new();
+        if tokio::io::BufReader::new(reader)
+            .read_line(&mut line)
+            .await
+            .is_err()
+        {
+            continue;
+        }
+        let (reply, quit) = respond(&line, pid);
+        let _ = writer.write_all(format!("{}\n", reply).as_bytes()).await;
+        if quit {
+            tracing::info!("Another instance is taking over");
+            on_quit();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::Arc;
+    use std::sync::atomic::{AtomicBool, Ordering};
+
+    fn lock_path(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!("agent-rusty-{}-{}", name, std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        dir.join("agent-rusty.lock")
+    }
+
+    #[test]
+    fn test_private_dir_is_created_for_this_user_only() {
+        use std::os::unix::fs::PermissionsExt;
+
+        let dir = std::env::temp_dir()
+            .join(format!("agent-rusty-private-{}", std::process::id()))
+            .join("run");
+        create_private_dir(&dir).unwrap();
+        let mode = std::fs::metadata(&dir).unwrap().permissions().mode();
+        assert_eq!(mode & 0o777, 0o700);
+        // Existing and ours is fine
+        create_private_dir(&dir).unwrap();
+        std::fs::remove_dir_all(dir.parent().unwrap()).unwrap();
+    }
+
+    #[test]
+    fn test_respond() {
+        assert_eq!(respond("who\n", 42), ("pid 42".to_string(), false));
+        assert_eq!(respond("quit\n", 42), ("ok".to_string(), true));
+        assert!(!respond("dance", 42).1);
+    }
+
+    #[test]
+    fn test_stale_lock_is_reclaimed() {
+        let path = lock_path("stale");
+        // A listener that is dropped leaves its socket file behind
+        drop(UnixListener::bind(&path).unwrap());
+        assert!(path.exists());
+
+        let lock = acquire(&path).unwrap();
+        assert!(matches!(lock, Acquire::Acquired(_)));
+        drop(lock);
+        assert!(!path.exists());
+    }
+
+    #[tokio::test(flavor = "multi_thread")]
+    async fn test_held_lock_and_takeover() {
+        let path = lock_path("takeover");
+        let Acquire::Acquired(mut lock) = acquire(&path).unwrap() else {
+            panic!("fresh lock should be acquired");
+        };
+        let quit = Arc::new(AtomicBool::new(false));
+        let listener = lock.take_listener().unwrap();
+        let flag = quit.clone();
+        tokio::spawn(serve(listener, move || flag.store(true, Ordering::SeqCst)));
+
+        let other = path.clone();
+        let held = tokio::task::spawn_blocking(move || acquire(&other).unwrap())
+            .await
+            .unwrap();
+        assert!(matches!(held, Acquire::Held { pid: Some(pid) } if pid == std::process::id()));
+
+        // The owner quits on request; its lock goes away and the new instance binds
+        let other = path.clone();
+        let takeover = tokio::task::spawn_blocking(move || request_takeover(&other));
+        while !quit.load(Ordering::SeqCst) {
+            tokio::time::sleep(Duration::from_millis(10)).await;
+        }
+        drop(lock);
+        takeover.await.unwrap().unwrap();
+        assert!(matches!(acquire(&path).unwrap(), Acquire::Acquired(_)));
+    }
+}
@@ -0,0 +1,292 @@
+//! Session inventory for handoffs: `agent-rusty export` and `import`.
+//!
+//! An export is every session's [`TmuxSession`] data, tags included, flattened
+//! into an entry with its agent kind, start command and note. Importing recreates the
+//! sessions that aren't running; fields this build doesn't know are ignored,
+//! so newer exports still import.
+
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+
+use crate::snapshot;
+use crate::state::PersistedState;
+use crate::tags::Tags;
+use crate::text;
+use crate::tmux::{AgentKind, TmuxClient, TmuxSession};
+
+/// Format version written to new exports
+pub const INVENTORY_VERSION: u32 = 1;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Inventory {
+    pub version: u32,
+    /// Unix timestamp of the export
+    pub exported_at: u64,
+    pub sessions: Vec<InventoryEntry>,
+}
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct InventoryEntry {
+    #[serde(flatten)]
+    pub session: TmuxSession,
+    /// Agent detected in the active pane
+    pub agent: AgentKind,
+    /// What the first pane runs, where tmux knows it; `None` is a plain shell
+    #[serde(default)]
+    pub command: Option<String>,
+    #[serde(default)]
+    pub note: Option<String>,
+}
+
+/// What importing one entry did
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Outcome {
+    Created,
+    /// A session of that name is already running
+    Skipped,
+    Failed(String),
+}
+
+impl Inventory {
+    /// Where the dashboard writes its export
+    pub fn default_path() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_default()
+            .join(".agent-deck")
+            .join("sessions.json")
+    }
+
+    pub fn new(exported_at: u64, sessions: Vec<InventoryEntry>) -> Self {
+        Self {
+            version: INVENTORY_VERSION,
+            exported_at,
+            sessions,
+        }
+    }
+
+    pub fn load_from(path: &Path) -> Result<Self> {
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&text).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    pub fn parse(text: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(text)?;
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+        if version == 0 || version > u64::from(INVENTORY_VERSION) {
+            anyhow::bail!(
+                "unsupported export version {} (this build reads version {})",
+                version,
+                INVENTORY_VERSION
+            );
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    pub fn to_json(&self) -> Result<String> {
+        Ok(serde_json::to_string_pretty(self)?)
+    }
+
+    /// Write the export, replacing the old file only once the new one is complete
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent().filter(|p| !p.as_os_str().is_empty()) {
+            std::fs::create_dir_all(parent)?;
+        }
+        let partial = path.with_extension("json.tmp");
+        std::fs::write(&partial, self.to_json()?)
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        std::fs::rename(&partial, path)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+/// Entries for `sessions`, with start commands by session name
+pub fn entries(
+    sessions: Vec<TmuxSession>,
+    commands: &BTreeMap<String, String>,
+    notes: &BTreeMap<String, String>,
+) -> Vec<InventoryEntry> {
+    sessions
+        .into_iter()
+        .map(|session| InventoryEntry {
+            agent: session.agent_kind(),
+            command: commands.get(&session.name).cloned(),
+            note: notes.get(&session.name).cloned(),
+            session,
+        })
+        .collect()
+}
+
+/// Export every session on the server
+pub async fn capture(
+    client: &TmuxClient,
+    notes: &BTreeMap<String, String>,
+    tags: &Tags,
+) -> Result<Inventory> {
+    let mut sessions = client.list_sessions().await?;
+    tags.apply(&mut sessions);
+    // The first pane's start command, as a snapshot would restore it
+    let commands = snapshot::capture(client)
+        .await?
+        .sessions
+        .into_iter()
+        .filter_map(|s| {
+            let command = s.windows.first()?.panes.first()?.command.clone()?;
+            Some((s.name, command))
+        })
+        .collect();
+    let exported_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(Inventory::new(
+        exported_at,
+        entries(sessions, &commands, notes),
+    ))
+}
+
+/// Export to `path`, returning how many sessions were written
+pub async fn save(
+    client: &TmuxClient,
+    notes: &BTreeMap<String, String>,
+    tags: &Tags,
+    path: &Path,
+) -> Result<usize> {
+    let inventory = capture(client, notes, tags).await?;
+    inventory.save_to(path)?;
+    Ok(inventory.sessions.len())
+}
+
+/// Recreate the entries that aren't running, in order
+///
+/// Sessions start in their exported directory when it still exists, and
+/// notes and tags are restored into `state` unless it already has some for
+/// the name.
+pub async fn import(
+    client: &TmuxClient,
+    inventory: &Inventory,
+    state: &mut PersistedState,
+) -> Result<Vec<(String, Outcome)>> {
+    let running = client.session_names().await?;
+    let mut outcomes = Vec::with_capacity(inventory.sessions.len());
+    for entry in &inventory.sessions {
+        let name = &entry.session.name;
+        if running.contains(name) {
+            outcomes.push((name.clone(), Outcome::Skipped));
+            continue;
+        }
+        let dir = PathBuf::from(client.local_path(&entry.session.current_path));
+        let dir = (!entry.session.current_path.is_empty() && dir.is_dir()).then_some(dir);
+        let outcome = match client
+            .create_session_with(name, dir.as_deref(), entry.command.as_deref())
+            .await
+        {
+            Ok(_) => {
+                if let Some(note) = &entry.note
+                    && state.note(name).is_none()
+                {
+                    state.set_note(name, note);
+                }
+                if state.tags.get(name).is_empty() {
+                    state
+                        .tags
+                        .set(name, entry.session.tags.clone(), text::unix_now());
+                }
+                Outcome::Created
+            }
+            Err(e) => {
+                tracing::warn!("Failed to import {}: {}", name, e);
+                Outcome::Failed(e.to_string())
+            }
+        };
+        outcomes.push((name.clone(), outcome));
+    }
+    Ok(outcomes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tmux::AgentStatus;
+
+    fn sample() -> Inventory {
+        let mut api = TmuxSession::new("$0".to_string(), "api".to_string());
+        api.current_command = "claude".to_string();
+        api.current_path = "/work/api".to_string();
+        api.status = AgentStatus::WaitingForInput;
+        api.tags = vec!["backend".to_string()];
+        let mut web = TmuxSession::new("$1".to_string(), "web".to_string());
+        web.current_command = "zsh".to_string();
+
+        let commands = BTreeMap::from([("api".to_string(), "claude".to_string())]);
+        let notes = BTreeMap::from([("web".to_string(), "waiting on review".to_string())]);
+        Inventory::new(1_700_000_000, entries(vec![api, web], &commands, &notes))
+    }
+
+    #[test]
+    fn test_entries() {
+        let inventory = sample();
+        let api = &inventory.sessions[0];
+        assert_eq!(api.agent, AgentKind::Claude);
+        assert_eq!(api.command.as_deref(), Some("claude"));
+        assert_eq!(api.note, None);
+        let web = &inventory.sessions[1];
+        assert_eq!(web.agent, AgentKind::Shell);
+        assert_eq!(web.command, None);
+        assert_eq!(web.note.as_deref(), Some("waiting on review"));
+    }
+
+    #[test]
+    fn test_schema() {
+        let value: serde_json::Value = serde_json::from_str(&sample().to_json().unwrap()).unwrap();
+        assert_eq!(value["version"], INVENTORY_VERSION);
+        // Session fields sit at the top level of each entry
+        let api = &value["sessions"][0];
+        assert_eq!(api["name"], "api");
+        assert_eq!(api["current_path"], "/work/api");
+        assert_eq!(api["status"], "WaitingForInput");
+        assert_eq!(api["agent"], "Claude");
+        assert_eq!(api["command"], "claude");
+        assert_eq!(api["tags"], serde_json::json!(["backend"]));
+        assert!(value["sessions"][1].get("tags").is_none());
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("agent-rusty-inventory-{}.json", std::process::id()));
+        let inventory = sample();
+        inventory.save_to(&path).unwrap();
+        assert_eq!(Inventory::load_from(&path).unwrap(), inventory);
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_unknown_fields_are_ignored() {
+        let mut value = serde_json::to_value(sample()).unwrap();
+        value["source"] = serde_json::json!("laptop");
+        value["sessions"][0]["origin"] = serde_json::json!("laptop");
+        let inventory = Inventory::parse(&value.to_string()).unwrap();
+        assert_eq!(inventory, sample());
+
+        // Optional fields may be left out
+        let entry = value["sessions"][1].as_object_mut().unwrap();
+        entry.remove("command");
+        entry.remove("note");
+        let inventory = Inventory::parse(&value.to_string()).unwrap();
+        assert_eq!(inventory.sessions[1].note, None);
+    }
+
+    #[test]
+    fn test_version_check() {
+        let mut value = serde_json::to_value(sample()).unwrap();
+        value["version"] = serde_json::json!(INVENTORY_VERSION + 1);
+        let error = Inventory::parse(&value.to_string()).unwrap_err();
+        assert!(error.to_string().contains("unsupported export version"));
+
+        value.as_object_mut().unwrap().remove("version");
+        assert!(Inventory::parse(&value.to_string()).is_err());
+    }
+}
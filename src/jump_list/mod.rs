@@ -0,0 +1,261 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::VecDeque;
+
+use crate::seen::{SeenByName, Sighted};
+
+/// Maximum number of entries kept in the jump list
+const JUMP_LIST_CAPACITY: usize = 50;
+
+/// Back/forward history of deliberately visited sessions, like vim's jump list
+///
+/// Only explicit jumps (attach, filter-jump, number-jump) are recorded;
+/// stepping through the list with j/k is not.
+#[derive(Debug)]
+pub struct JumpList {
+    /// Session ids, oldest first
+    entries: VecDeque<String>,
+    /// Index of the current entry
+    position: Option<usize>,
+    capacity: usize,
+}
+
+impl Default for JumpList {
+    fn default() -> Self {
+        Self::with_capacity(JUMP_LIST_CAPACITY)
+    }
+}
+
+impl JumpList {
+    pub fn with_capacity(capacity: usize) -> Self {
+        Self {
+            entries: VecDeque::new(),
+            position: None,
+            capacity: capacity.max(1),
+        }
+    }
+
+    /// Id at the current position
+    pub fn current(&self) -> Option<&str> {
+        self.position.map(|i| self.entries[i].as_str())
+    }
+
+    /// Record a jump to `id`, discarding any forward history
+    pub fn push(&mut self, id: &str) {
+        if self.current() == Some(id) {
+            return;
+        }
+        if let Some(position) = self.position {
+            self.entries.truncate(position + 1);
+        }
+        self.entries.retain(|entry| entry != id);
+        self.entries.push_back(id.to_string());
+        while self.entries.len() > self.capacity {
+            self.entries.pop_front();
+        }
+        self.position = Some(self.entries.len() - 1);
+    }
+
+    /// Step back to the previous entry that still `exists`
+    ///
+    /// If the selection has wandered away from the current entry, it is
+    /// recorded first so a following forward jump returns to it.
+    pub fn back(&mut self, selected: Option<&str>, exists: impl Fn(&str) -> bool) -> Option<&str> {
+        if let Some(selected) = selected
+            && self.current() != Some(selected)
+        {
+            self.push(selected);
+        }
+        let position = self.position?;
+        let target = (0..position).rev().find(|&i| exists(&self.entries[i]))?;
+        self.position = Some(target);
+        self.current()
+    }
+
+    /// Step forward to the next entry that still `exists`
+    pub fn forward(&mut self, exists: impl Fn(&str) -> bool) -> Option<&str> {
+        let position = self.position?;
+        let target = (position + 1..self.entries.len()).find(|&i| exists(&self.entries[i]))?;
+        self.position = Some(target);
+        self.current()
+    }
+
+    /// Up to `count` entries before the current one, oldest first
+    pub fn recent(&self, count: usize) -> impl Iterator<Item = &str> {
+        let end = self.position.unwrap_or(0);
+        let start = end.saturating_sub(count);
+        self.entries.range(start..end).map(String::as_str)
+    }
+}
+
+/// When a session was last attached to, and last seen at all
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AttachRecord {
+    /// Unix time of the last attach
+    pub attached_at: u64,
+    /// Unix time the session was last listed, to a day's resolution
+    pub seen_at: u64,
+}
+
+impl Sighted for AttachRecord {
+    fn seen_at(&mut self) -> &mut u64 {
+        &mut self.seen_at
+    }
+}
+
+/// Attach times by session name, persisted so recently used sessions can sort first
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct AttachHistory {
+    entries: SeenByName<AttachRecord>,
+}
+
+impl AttachHistory {
+    pub fn record(&mut self, name: &str, now: u64) {
+        self.entries.insert(
+            name,
+            AttachRecord {
+                attached_at: now,
+                seen_at: now,
+            },
+        );
+    }
+
+    /// Move a session's record to its new name
+    pub fn rename(&mut self, old: &str, new: &str) {
+        self.entries.rename(old, new);
+    }
+
+    pub fn last_attached(&self, name: &str) -> Option<u64> {
+        self.entries.get(name).map(|r| r.attached_at)
+    }
+
+    /// Most recently attached first, then sessions never attached; ties by name
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let (a_at, b_at) = (self.last_attached(a), self.last_attached(b));
+        match (a_at, b_at) {
+            (Some(a_at), Some(b_at)) => b_at.cmp(&a_at),
+            (Some(_), None) => Ordering::Less,
+            (None, Some(_)) => Ordering::Greater,
+            (None, None) => Ordering::Equal,
+        }
+        .then_with(|| a.cmp(b))
+    }
+
+    /// Note which names are still listed; see [`SeenByName::refresh`]
+    pub fn refresh<'a>(&mut self, listed: impl IntoIterator<Item = &'a str>, now: u64) -> bool {
+        self.entries.refresh(listed, now)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn all(_: &str) -> bool {
+        true
+    }
+
+    #[test]
+    fn test_back_and_forward() {
+        let mut jumps = JumpList::default();
+        jumps.push("a");
+        jumps.push("b");
+        jumps.push("c");
+
+        assert_eq!(jumps.back(Some("c"), all), Some("b"));
+        assert_eq!(jumps.back(Some("b"), all), Some("a"));
+        assert_eq!(jumps.back(Some("a"), all), None);
+        assert_eq!(jumps.forward(all), Some("b"));
+        assert_eq!(jumps.forward(all), Some("c"));
+        assert_eq!(jumps.forward(all), None);
+    }
+
+    #[test]
+    fn test_push_after_back_truncates_forward_history() {
+        let mut jumps = JumpList::default();
+        jumps.push("a");
+        jumps.push("b");
+        jumps.push("c");
+        jumps.back(Some("c"), all);
+        jumps.back(Some("b"), all);
+
+        // Branching from "a" drops "b" and "c"
+        jumps.push("d");
+        assert_eq!(jumps.forward(all), None);
+        assert_eq!(jumps.back(Some("d"), all), Some("a"));
+        assert_eq!(jumps.forward(all), Some("d"));
+    }
+
+    #[test]
+    fn test_back_records_wandered_selection() {
+        let mut jumps = JumpList::default();
+        jumps.push("a");
+        jumps.push("b");
+
+        // Moved to "x" with j/k, which was not recorded as a jump
+        assert_eq!(jumps.back(Some("x"), all), Some("b"));
+        assert_eq!(jumps.forward(all), Some("x"));
+    }
+
+    #[test]
+    fn test_skips_missing_sessions() {
+        let mut jumps = JumpList::default();
+        jumps.push("a");
+        jumps.push("gone");
+        jumps.push("c");
+
+        let exists = |id: &str| id != "gone";
+        assert_eq!(jumps.back(Some("c"), exists), Some("a"));
+        assert_eq!(jumps.forward(exists), Some("c"));
+    }
+
+    #[test]
+    fn test_duplicates_move_and_capacity_bounds() {
+        let mut jumps = JumpList::with_capacity(3);
+        jumps.push("a");
+        jumps.push("b");
+        jumps.push("a");
+        assert_eq!(jumps.recent(5).collect::<Vec<_>>(), vec!["b"]);
+
+        jumps.push("c");
+        jumps.push("d");
+        assert_eq!(jumps.recent(5).collect::<Vec<_>>(), vec!["a", "c"]);
+        assert_eq!(jumps.current(), Some("d"));
+
+        // Repeated jumps to the current entry are ignored
+        jumps.push("d");
+        assert_eq!(jumps.recent(1).collect::<Vec<_>>(), vec!["c"]);
+    }
+
+    #[test]
+    fn test_attach_order() {
+        let mut history = AttachHistory::default();
+        history.record("web", 100);
+        history.record("api", 300);
+        history.record("db", 300);
+        let mut names = vec!["zeta", "web", "api", "alpha", "db"];
+        names.sort_by(|a, b| history.compare(a, b));
+        // Latest first, ties and the never-attached by name
+        assert_eq!(names, ["api", "db", "web", "alpha", "zeta"]);
+
+        history.record("web", 400);
+        assert_eq!(history.last_attached("web"), Some(400));
+        assert_eq!(history.compare("web", "api"), Ordering::Less);
+    }
+
+    #[test]
+    fn test_attach_history_round_trip() {
+        let mut history = AttachHistory::default();
+        history.record("api", 1_700_000_000);
+        let json = serde_json::to_string(&history).unwrap();
+        assert_eq!(
+            json,
+            r#"{"api":{"attached_at":1700000000,"seen_at":1700000000}}"#
+        );
+        assert_eq!(
+            serde_json::from_str::<AttachHistory>(&json).unwrap(),
+            history
+        );
+    }
+}
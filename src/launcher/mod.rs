@@ -0,0 +1,255 @@
+//! Launchers offered when creating a session: what to run in it, whether to
+//! ask for the command first, and how to ask for its directory.
+
+use serde::{Deserialize, Serialize};
+use std::path::PathBuf;
+
+use crate::claude::ClaudeMeta;
+
+/// `[[launchers]]` entry of the config
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Launcher {
+    /// Shown in the menu
+    pub name: String,
+    /// Command the session runs; a plain shell when unset
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub command: Option<String>,
+    /// Pick the directory to start in from a browser; otherwise it's typed,
+    /// starting from the current directory
+    #[serde(default)]
+    pub ask_dir: bool,
+    /// Ask for the command to run, offering `command` to edit
+    #[serde(default)]
+    pub ask_command: bool,
+    /// Pass `--resume <id>` for the directory's latest Claude Code conversation
+    #[serde(default)]
+    pub resume: bool,
+}
+
+/// A session to create from the new-session dialog
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct NewSession {
+    pub name: String,
+    /// What it runs; a plain shell when unset
+    pub launcher: Option<Launcher>,
+    /// Where it starts; tmux's default when unset
+    pub cwd: Option<PathBuf>,
+}
+
+impl Launcher {
+    fn builtin(name: &str, command: Option<&str>) -> Self {
+        Self {
+            name: name.to_string(),
+            command: command.map(str::to_string),
+            ask_dir: false,
+            ask_command: false,
+            resume: false,
+        }
+    }
+
+    /// `Claude Code (claude)`, or `Shell` for a plain shell
+    pub fn label(&self) -> String {
+        match &self.command {
+            Some(command) if self.resume => format!("{} ({} --resume)", self.name, command),
+            Some(command) => format!("{} ({})", self.name, command),
+            None => self.name.clone(),
+        }
+    }
+
+    /// Command to run, resuming `conversation` if this launcher resumes and one is known
+    pub fn command_for(&self, conversation: Option<&ClaudeMeta>) -> Option<String> {
+        let command = self.command.as_deref()?;
+        Some(match conversation {
+            Some(meta) if self.resume => format!("{} --resume {}", command, meta.session_id),
+            _ => command.to_string(),
+        })
+    }
+}
+
+/// Entries offered without any configuration
+pub fn builtin() -> Vec<Launcher> {
+    vec![
+        Launcher::builtin("Claude Code", Some("claude")),
+        Launcher {
+            ask_dir: true,
+            ask_command: false,
+            resume: true,
+            ..Launcher::builtin("Resume Claude", Some("claude"))
+        },
+        Launcher::builtin("Aider", Some("aider")),
+        Launcher {
+            ask_command: true,
+            ..Launcher::builtin("Custom command", None)
+        },
+        Launcher::builtin("Shell", None),
+    ]
+}
+
+/// The menu: configured launchers in order, then the built-ins they don't replace
+pub fn menu(configured: &[Launcher]) -> Vec<Launcher> {
+    let mut menu = configured.to_vec();
+    menu.extend(
+        builtin()
+            .into_iter()
+            .filter(|b| !configured.iter().any(|c| c.name == b.name)),
+    );
+    menu
+}
+
+/// What a duplicate of a session runs: the launcher it was created with here,
+/// minus resuming its conversation, else the command its pane started with
+pub fn duplicate_launcher(recorded: Option<&Launcher>, start_command: &str) -> Launcher {
+    match recorded {
+        Some(launcher) => Launcher {
+            resume: false,
+            ..launcher.clone()
+        },
+        None => Launcher::builtin("Duplicate", Some(start_command).filter(|c| !c.is_empty())),
+    }
+}
+
+/// First free `<name>-2`, `<name>-3`, ... for a copy of `name`; a copy of a
+/// copy counts on from its number rather than growing `-2-2`
+pub fn duplicate_name(name: &str, taken: impl Fn(&str) -> bool) -> String {
+    let base = match name.rsplit_once('-') {
+        Some((base, n)) if !base.is_empty() && n.parse::<u32>().is_ok() => base,
+        _ => name,
+    };
+    (2..)
+        .map(|n| format!("{}-{}", base, n))
+        .find(|candidate| !taken(candidate))
+        .unwrap_or_default()
+}
+
+/// Directory typed for a launcher that asks; `~/` is the home directory and blank means none
+pub fn parse_dir(input: &str) -> Result<Option<PathBuf>, String> {
+    let input = input.trim();
+    if input.is_empty() {
+        return Ok(None);
+    }
+    let path = match input.strip_prefix("~/") {
+        Some(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        None if input == "~" => dirs::home_dir().unwrap_or_default(),
+        None => PathBuf::from(input),
+    };
+    if path.is_dir() {
+        Ok(Some(path))
+    } else {
+        Err(format!("'{}' is not a directory", input))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_duplicate_name() {
+        let taken = |names: &'static [&'static str]| move |n: &str| names.contains(&n);
+        assert_eq!(duplicate_name("api", taken(&["api"])), "api-2");
+        assert_eq!(duplicate_name("api", taken(&["api", "api-2"])), "api-3");
+        assert_eq!(duplicate_name("api-2", taken(&["api", "api-2"])), "api-3");
+        assert_eq!(
+            duplicate_name("my-agent", taken(&["my-agent"])),
+            "my-agent-2"
+        );
+    }
+
+    #[test]
+    fn test_duplicate_launcher() {
+        let resumed = builtin().remove(1);
+        assert!(resumed.resume);
+        let copy = duplicate_launcher(Some(&resumed), "node");
+        assert_eq!(copy.command.as_deref(), Some("claude"));
+        assert!(!copy.resume);
+        assert_eq!(
+            duplicate_launcher(None, "aider").command.as_deref(),
+            Some("aider")
+        );
+        assert_eq!(duplicate_launcher(None, "").command, None);
+    }
+
+    #[test]
+    fn test_menu() {
+        let names = |menu: &[Launcher]| menu.iter().map(|l| l.name.clone()).collect::<Vec<_>>();
+        assert_eq!(
+            names(&menu(&[])),
+            [
+                "Claude Code",
+                "Resume Claude",
+                "Aider",
+                "Custom command",
+                "Shell"
+            ]
+        );
+
+        let configured = [
+            Launcher {
+                name: "Aider".to_string(),
+                command: Some("aider --model sonnet".to_string()),
+                ask_dir: true,
+                ask_command: false,
+                resume: false,
+            },
+            Launcher {
+                name: "Codex".to_string(),
+                command: Some("codex".to_string()),
+                ask_dir: false,
+                ask_command: false,
+                resume: false,
+            },
+        ];
+        let menu = menu(&configured);
+        assert_eq!(
+            names(&menu),
+            [
+                "Aider",
+                "Codex",
+                "Claude Code",
+                "Resume Claude",
+                "Custom command",
+                "Shell"
+            ]
+        );
+        assert_eq!(menu[0].command.as_deref(), Some("aider --model sonnet"));
+        assert_eq!(menu[0].label(), "Aider (aider --model sonnet)");
+        assert_eq!(menu[3].label(), "Resume Claude (claude --resume)");
+        assert!(menu[4].ask_command);
+        assert_eq!(menu[5].label(), "Shell");
+    }
+
+    #[test]
+    fn test_command_for() {
+        let conversation = ClaudeMeta {
+            session_id: "3f2c9a1e".to_string(),
+            model: None,
+            started_at: None,
+        };
+        let menu = menu(&[]);
+        let (plain, resume, shell) = (&menu[0], &menu[1], &menu[4]);
+        assert_eq!(
+            resume.command_for(Some(&conversation)).as_deref(),
+            Some("claude --resume 3f2c9a1e")
+        );
+        // Nothing to resume starts a new conversation
+        assert_eq!(resume.command_for(None).as_deref(), Some("claude"));
+        assert_eq!(
+            plain.command_for(Some(&conversation)).as_deref(),
+            Some("claude")
+        );
+        assert_eq!(shell.command_for(Some(&conversation)), None);
+    }
+
+    #[test]
+    fn test_parse_dir() {
+        assert_eq!(parse_dir("  "), Ok(None));
+        let tmp = std::env::temp_dir();
+        assert_eq!(parse_dir(tmp.to_str().unwrap()), Ok(Some(tmp)));
+        if let Some(home) = dirs::home_dir().filter(|h| h.is_dir()) {
+            assert_eq!(parse_dir("~"), Ok(Some(home)));
+        }
+        let error = parse_dir("/no/such/dir").unwrap_err();
+        assert!(error.contains("not a directory"), "{}", error);
+    }
+}
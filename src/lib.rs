@@ -0,0 +1,135 @@
+//! Manage AI coding agents running in tmux sessions
+//!
+//! The `agent-rusty` binary is a TUI over this library. The stable API is
+//! the [`tmux`] client and status heuristics, the [`skeleton`] generator,
+//! and the typed [`error`]s they return:
+//!
+//! ```no_run
+//! use agent_rusty::tmux::{AgentStatus, TmuxClient};
+//!
+//! # async fn demo() -> agent_rusty::error::Result<()> {
+//! let client = TmuxClient::new();
+//! for session in client.list_sessions().await? {
+//!     if session.status == AgentStatus::WaitingForInput {
+//!         println!("{} needs you", session.name);
+//!     }
+//! }
+//! # Ok(())
+//! # }
+//! ```
+
+pub mod error;
+pub mod git;
+pub mod skeleton;
+pub mod tmux;
+
+// Internals shared with the binary; not a stable API
+#[doc(hidden)]
+pub mod accessible;
+#[doc(hidden)]
+pub mod actions;
+#[doc(hidden)]
+pub mod activity;
+#[doc(hidden)]
+pub mod alerts;
+#[doc(hidden)]
+pub mod app;
+#[doc(hidden)]
+pub mod bell;
+#[doc(hidden)]
+pub mod broadcast;
+#[doc(hidden)]
+pub mod claude;
+#[doc(hidden)]
+pub mod cli;
+#[doc(hidden)]
+pub mod clipboard;
+#[doc(hidden)]
+pub mod commands;
+#[doc(hidden)]
+pub mod completions;
+#[doc(hidden)]
+pub mod config;
+#[doc(hidden)]
+pub mod control;
+#[doc(hidden)]
+pub mod custom_commands;
+#[doc(hidden)]
+pub mod description;
+#[doc(hidden)]
+pub mod digest;
+#[doc(hidden)]
+pub mod dir_picker;
+#[doc(hidden)]
+pub mod doctor;
+#[doc(hidden)]
+pub mod editor;
+#[doc(hidden)]
+pub mod frame;
+#[doc(hidden)]
+pub mod hooks;
+#[doc(hidden)]
+pub mod input;
+#[doc(hidden)]
+pub mod instance;
+#[doc(hidden)]
+pub mod inventory;
+#[doc(hidden)]
+pub mod jump_list;
+#[doc(hidden)]
+pub mod launcher;
+#[doc(hidden)]
+pub mod logging;
+#[doc(hidden)]
+pub mod mcp;
+#[doc(hidden)]
+pub mod metrics;
+#[doc(hidden)]
+pub mod notification;
+#[doc(hidden)]
+pub mod policy;
+#[doc(hidden)]
+pub mod poller;
+#[doc(hidden)]
+pub mod preview;
+#[doc(hidden)]
+pub mod restart;
+#[doc(hidden)]
+pub mod rules;
+#[doc(hidden)]
+pub mod schedule;
+#[doc(hidden)]
+pub mod script;
+#[doc(hidden)]
+pub mod seen;
+#[doc(hidden)]
+pub mod session_list;
+#[cfg(unix)]
+#[doc(hidden)]
+pub mod signals;
+#[doc(hidden)]
+pub mod snapshot;
+#[doc(hidden)]
+pub mod state;
+#[doc(hidden)]
+pub mod tags;
+#[doc(hidden)]
+pub mod template;
+#[doc(hidden)]
+pub mod terminal;
+#[doc(hidden)]
+pub mod text;
+#[doc(hidden)]
+pub mod text_input;
+#[doc(hidden)]
+pub mod theme;
+#[doc(hidden)]
+pub mod timeline;
+#[doc(hidden)]
+pub mod usage;
+#[doc(hidden)]
+pub mod wait;
+#[doc(hidden)]
+pub mod worktree;
+
+pub use error::{AgentRustyError, Result};
@@ -0,0 +1,290 @@
+use std::collections::VecDeque;
+use std::fmt::Write as _;
+use std::fs::{File, OpenOptions};
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::{Event, Level, Subscriber};
+use tracing_subscriber::EnvFilter;
+use tracing_subscriber::Layer;
+use tracing_subscriber::fmt::format::FmtSpan;
+use tracing_subscriber::layer::{Context, SubscriberExt as _};
+use tracing_subscriber::util::SubscriberInitExt as _;
+
+/// Logs larger than this are moved aside at startup
+const MAX_LOG_BYTES: u64 = 5 * 1024 * 1024;
+
+/// Lines the in-memory log keeps for the log viewer
+pub const LOG_BUFFER_LINES: usize = 1000;
+
+/// An event as the log viewer shows it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LogLine {
+    /// Local time of the event, `HH:MM:SS`
+    pub at: String,
+    pub level: Level,
+    pub target: String,
+    /// The message followed by the event's other fields as `key=value`
+    pub message: String,
+}
+
+impl LogLine {
+    /// The line as the log file would have it, minus the date
+    pub fn text(&self) -> String {
+        format!(
+            "{} {:>5} {}: {}",
+            self.at, self.level, self.target, self.message
+        )
+    }
+}
+
+/// Most recent log lines, oldest first, shared between the tracing layer
+/// and the TUI
+#[derive(Debug, Clone)]
+pub struct LogBuffer {
+    lines: Arc<Mutex<VecDeque<LogLine>>>,
+    capacity: usize,
+}
+
+impl Default for LogBuffer {
+    fn default() -> Self {
+        Self::new(LOG_BUFFER_LINES)
+    }
+}
+
+impl LogBuffer {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: Arc::new(Mutex::new(VecDeque::with_capacity(capacity))),
+            capacity,
+        }
+    }
+
+    pub fn push(&self, line: LogLine) {
+        let mut lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        if lines.len() == self.capacity {
+            lines.pop_front();
+        }
+        lines.push_back(line);
+    }
+
+    /// Lines at least as severe as `min`, oldest first
+    pub fn lines(&self, min: Level) -> Vec<LogLine> {
+        let lines = self.lines.lock().unwrap_or_else(|e| e.into_inner());
+        lines.iter().filter(|l| l.level <= min).cloned().collect()
+    }
+}
+
+/// The log viewer's next level filter: everything, then each level up to
+/// errors only, then everything again
+pub fn next_filter(min: Level) -> Level {
+    match min {
+        Level::TRACE => Level::DEBUG,
+        Level::DEBUG => Level::INFO,
+        Level::INFO => Level::WARN,
+        Level::WARN => Level::ERROR,
+        _ => Level::TRACE,
+    }
+}
+
+/// Tracing layer copying events into a [`LogBuffer`]
+pub struct RingLayer {
+    buffer: LogBuffer,
+}
+
+impl RingLayer {
+    pub fn new(buffer: LogBuffer) -> Self {
+        Self { buffer }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for RingLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let mut fields = FieldText::default();
+        event.record(&mut fields);
+        let metadata = event.metadata();
+        self.buffer.push(LogLine {
+            at: chrono::Local::now().format("%H:%M:%S").to_string(),
+            level: *metadata.level(),
+            target: metadata.target().to_string(),
+            message: fields.message + &fields.rest,
+        });
+    }
+}
+
+/// An event's fields flattened to one line
+#[derive(Default)]
+struct FieldText {
+    message: String,
+    rest: String,
+}
+
+impl Visit for FieldText {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "message" {
+            self.message.push_str(value);
+        } else {
+            let _ = write!(self.rest, " {}={}", field.name(), value);
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "message" {
+            let _ = write!(self.message, "{:?}", value);
+        } else {
+            let _ = write!(self.rest, " {}={:?}", field.name(), value);
+        }
+    }
+}
+
+/// Default log file, next to the persisted state
+pub fn default_path() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_default()
+        .join(".agent-deck")
+        .join("logs")
+        .join("agent-rusty.log")
+}
+
+/// Move an oversized log to `<name>.1`, replacing the previous one
+fn rotate(path: &Path, max_bytes: u64) -> std::io::Result<()> {
+    match std::fs::metadata(path) {
+        Ok(meta) if meta.len() > max_bytes => {
+            let mut rotated = path.as_os_str().to_owned();
+            rotated.push(".1");
+            std::fs::rename(path, rotated)
+        }
+        _ => Ok(()),
+    }
+}
+
+fn open(path: &Path) -> std::io::Result<File> {
+    if let Some(parent) = path.parent() {
+        std::fs::create_dir_all(parent)?;
+    }
+    rotate(path, MAX_LOG_BYTES)?;
+    OpenOptions::new().create(true).append(true).open(path)
+}
+
+/// Install the global subscriber writing to `path`, and copying events into
+/// `buffer` for the log viewer
+///
+/// `RUST_LOG` overrides `level`. Stdout is never used because it belongs to
+/// the TUI; if the file can't be opened logs go to stderr and the returned
+/// message explains why.
+pub fn init(path: &Path, level: &str, buffer: &LogBuffer) -> Option<String> {
+    let filter = EnvFilter::try_from_default_env()
+        .or_else(|_| EnvFilter::try_new(level))
+        .unwrap_or_else(|_| EnvFilter::new("info"));
+    // Span close events carry each tmux call's duration
+    let fmt = tracing_subscriber::fmt::layer().with_span_events(FmtSpan::CLOSE);
+    let subscriber = tracing_subscriber::registry()
+        .with(filter)
+        .with(RingLayer::new(buffer.clone()));
+
+    match open(path) {
+        Ok(file) => {
+            subscriber
+                .with(fmt.with_ansi(false).with_writer(Mutex::new(file)))
+                .init();
+            None
+        }
+        Err(e) => {
+            subscriber.with(fmt.with_writer(std::io::stderr)).init();
+            Some(format!(
+                "Logging to stderr: can't open {}: {}",
+                path.display(),
+                e
+            ))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_rotate_oversized_log() {
+        let dir = std::env::temp_dir().join(format!("agent-rusty-logs-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("agent-rusty.log");
+
+        std::fs::write(&path, "small").unwrap();
+        rotate(&path, 10).unwrap();
+        assert!(path.exists());
+
+        std::fs::write(&path, "much more than ten bytes").unwrap();
+        rotate(&path, 10).unwrap();
+        assert!(!path.exists());
+        assert_eq!(
+            std::fs::read_to_string(dir.join("agent-rusty.log.1")).unwrap(),
+            "much more than ten bytes"
+        );
+
+        // A missing log is fine
+        rotate(&path, 10).unwrap();
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_ring_layer_keeps_recent_events() {
+        let buffer = LogBuffer::new(3);
+        let subscriber = tracing_subscriber::registry().with(RingLayer::new(buffer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "poller", sessions = 2, name = "api", "polled");
+            for i in 0..3 {
+                tracing::debug!("tick {}", i);
+            }
+            tracing::warn!("slow tmux");
+        });
+
+        let messages: Vec<String> = buffer
+            .lines(Level::TRACE)
+            .iter()
+            .map(|l| l.message.clone())
+            .collect();
+        assert_eq!(messages, ["tick 1", "tick 2", "slow tmux"]);
+        let warnings = buffer.lines(Level::WARN);
+        assert_eq!(warnings.len(), 1);
+        assert_eq!(warnings[0].level, Level::WARN);
+        assert_eq!(buffer.lines(Level::ERROR), []);
+    }
+
+    #[test]
+    fn test_event_fields_follow_the_message() {
+        let buffer = LogBuffer::default();
+        let subscriber = tracing_subscriber::registry().with(RingLayer::new(buffer.clone()));
+        tracing::subscriber::with_default(subscriber, || {
+            tracing::info!(target: "poller", sessions = 2, name = "api", "polled");
+        });
+        let line = &buffer.lines(Level::INFO)[0];
+        assert_eq!(line.target, "poller");
+        assert_eq!(line.message, "polled sessions=2 name=api");
+        assert!(
+            line.text()
+                .ends_with("  INFO poller: polled sessions=2 name=api")
+        );
+    }
+
+    #[test]
+    fn test_next_filter_wraps() {
+        let mut min = Level::TRACE;
+        let mut seen = vec![min];
+        for _ in 0..5 {
+            min = next_filter(min);
+            seen.push(min);
+        }
+        assert_eq!(
+            seen,
+            [
+                Level::TRACE,
+                Level::DEBUG,
+                Level::INFO,
+                Level::WARN,
+                Level::ERROR,
+                Level::TRACE
+            ]
+        );
+    }
+}
@@ -1,162 +1,1484 @@
-use anyhow::Result;
-use crossterm::event::{self, Event, KeyEventKind};
-use std::process::Stdio;
-use std::time::Duration;
-use tokio::sync::mpsc;
+use anyhow::{Context, Result};
+use crossterm::event::{
+    DisableBracketedPaste, DisableFocusChange, DisableMouseCapture, EnableBracketedPaste,
+    EnableFocusChange, EnableMouseCapture, Event, EventStream,
+};
+use crossterm::terminal::EnterAlternateScreen;
+use futures_util::{FutureExt, StreamExt};
+use ratatui::{DefaultTerminal, Terminal, backend::CrosstermBackend};
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::process::{ExitStatus, Stdio};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::time::{Duration, Instant};
+use tokio::sync::{mpsc, watch};
 
-mod actions;
-mod app;
-mod skeleton;
-mod tmux;
+#[cfg(unix)]
+use agent_rusty::signals;
+use agent_rusty::{
+    accessible, actions, activity, app, broadcast, claude, cli, clipboard, commands, completions,
+    config, control, custom_commands, digest, dir_picker, doctor, editor, frame, git, hooks,
+    instance, inventory, launcher, logging, mcp, metrics, notification, policy, poller, preview,
+    restart, rules, skeleton, snapshot, state, tags, terminal, text, tmux, usage, worktree,
+};
 
 use actions::Action;
+use activity::ActivityTracker;
 use app::App;
-use tmux::TmuxClient;
+use clap::Parser;
+use claude::ClaudeCache;
+use cli::Cli;
+use config::Config;
+use custom_commands::{CommandContext, RunMode};
+use git::GitCache;
+use hooks::HookDispatcher;
+use launcher::NewSession;
+use notification::Level;
+use poller::{
+    CaptureSchedule, PollRate, PollRates, PollSchedule, PollerControl, TransitionTracker,
+};
+use rules::RuleAction;
+use state::PersistedState;
+use tags::Tags;
+use tmux::{AgentKind, AiderCache, StatusCache, TmuxClient, TmuxSession};
+
+/// Polls slower than this are logged as warnings
+const SLOW_POLL: Duration = Duration::from_secs(2);
+/// How often the header's tmux version and server counts are re-read
+const SERVER_INFO_INTERVAL: Duration = Duration::from_secs(15);
+/// How often busy sessions' spinner moves on
+const SPINNER_INTERVAL: Duration = Duration::from_millis(120);
+
+fn main() -> Result<()> {
+    // Tab completion re-runs us with $COMPLETE set; answer without starting anything
+    completions::complete_from_env();
+    run()
+}
 
 #[tokio::main]
-async fn main() -> Result<()> {
-    // Initialize logging
-    tracing_subscriber::fmt()
-        .with_env_filter(
-            tracing_subscriber::EnvFilter::from_default_env()
-                .add_directive(tracing::Level::INFO.into()),
-        )
-        .init();
-
-    // Create event channel
-    let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+async fn run() -> Result<()> {
+    // Everything that can reject user input runs before raw mode so errors stay readable
+    let mut cli = Cli::parse();
+    if cli.mcp {
+        anyhow::ensure!(
+            cli.command.is_none(),
+            "--mcp can't be combined with a subcommand"
+        );
+        cli.command = Some(cli::Command::McpServe);
+    }
+    if cli.generate_man {
+        completions::write_man(&mut std::io::stdout())?;
+        return Ok(());
+    }
 
-    // Initialize terminal
-    let mut terminal = ratatui::init();
+    // Load configuration, falling back to defaults so a typo can't lock the user out;
+    // an explicitly requested file must load, unless the doctor is here to report on it
+    let doctor = matches!(cli.command, Some(cli::Command::Doctor));
+    let (config, config_error) = match &cli.config {
+        Some(path) if doctor => (Config::load_from(path).unwrap_or_default(), None),
+        Some(path) if !path.exists() => anyhow::bail!("Config file {} not found", path.display()),
+        Some(path) => (Config::load_from(path)?, None),
+        None => match Config::load() {
+            Ok(config) => (config, None),
+            Err(e) => (Config::default(), Some(e.to_string())),
+        },
+    };
 
-    // Spawn input handler
-    let input_tx = tx.clone();
-    tokio::spawn(async move {
-        loop {
-            if event::poll(Duration::from_millis(100)).unwrap_or(false) {
-                if let Ok(evt) = event::read() {
-                    if let Event::Key(key) = evt {
-                        if key.kind == KeyEventKind::Press {
-                            let _ = input_tx.send(Action::KeyPress(key));
-                        }
-                    }
-                }
+    // Initialize logging; stdout belongs to the TUI
+    let log_path = cli.log_file.clone().unwrap_or_else(logging::default_path);
+    let logs = logging::LogBuffer::default();
+    let log_error = logging::init(&log_path, &config.log_level, &logs);
+    tracing::info!(version = env!("CARGO_PKG_VERSION"), "Starting");
+    let config_warnings = config.warnings();
+    for warning in &config_warnings {
+        tracing::warn!("Config: {}", warning);
+    }
+
+    // Subcommands run headless and never touch the terminal
+    if let Some(command) = cli.command {
+        let client = tmux_client(
+            &cli.tmux,
+            cli.socket_name.as_deref(),
+            cli.wsl_distro,
+            &config,
+        );
+        let doctor_paths = doctor::Paths {
+            config_explicit: cli.config.is_some(),
+            config: cli.config.unwrap_or_else(Config::default_path),
+            log: log_path,
+            data_dir: state::PersistedState::default_path()
+                .parent()
+                .map(Path::to_path_buf)
+                .unwrap_or_default(),
+            history_dir: tmux::history_dir(),
+        };
+        let control_path = control::socket_path(cli.socket_name.as_deref());
+        let code = commands::run(command, &client, &cli.dir, &control_path, &doctor_paths).await?;
+        std::process::exit(code);
+    }
+
+    // One instance per tmux server; ask before the TUI takes over the screen
+    let lock_path = match &cli.socket_name {
+        Some(name) => instance::default_path().with_file_name(format!("agent-rusty-{}.lock", name)),
+        None => instance::default_path(),
+    };
+    let (mut instance_lock, read_only) = acquire_instance_lock(&lock_path)?;
+
+    // Create event channel. It stays unbounded: background results must never be
+    // dropped, and the poller only sends changes, pauses while suspended, and
+    // gets coalesced by the main loop, so a backlog stays short.
+    let (tx, mut rx) = mpsc::unbounded_channel::<Action>();
+    if let Some(listener) = instance_lock.as_mut().and_then(|l| l.take_listener()) {
+        let quit_tx = tx.clone();
+        tokio::spawn(instance::serve(listener, move || {
+            let _ = quit_tx.send(Action::Quit);
+        }));
+    }
+
+    // Latest poll results, shared with the control socket; read-only instances don't serve it
+    let (sessions_tx, sessions_rx) = watch::channel(Vec::new());
+    let mut control_error = None;
+    if config.control_socket && instance_lock.is_some() {
+        let path = control::socket_path(cli.socket_name.as_deref());
+        match control::ControlServer::bind(&path).await {
+            Ok(server) => {
+                tracing::info!("Control socket listening on {}", path.display());
+                tokio::spawn(server.serve(tx.clone(), sessions_rx));
+            }
+            Err(e) => {
+                tracing::warn!("Control socket {} unavailable: {}", path.display(), e);
+                control_error = Some(format!("Control socket unavailable: {}", e));
             }
         }
-    });
+    }
+
+    // Initialize terminal
+    terminal::install_panic_hook(restore_terminal);
+    #[cfg(unix)]
+    if let Err(e) = signals::spawn(tx.clone(), signals::SHUTDOWN_DEADLINE, restore_terminal) {
+        tracing::warn!("Failed to install signal handlers: {}", e);
+    }
+    let _guard = TerminalGuard;
+    let mut screen = Screen::init(cli.accessible || config.accessible)?;
+
+    // Terminal input, read without blocking a runtime thread
+    let mut events = Input::new();
 
     // Spawn tmux poller
     let tmux_tx = tx.clone();
+    let tmux_client = tmux_client(
+        &cli.tmux,
+        cli.socket_name.as_deref(),
+        cli.wsl_distro.clone(),
+        &config,
+    );
+    let clipboard = Arc::new(clipboard::Clipboard::new(&config.clipboard, &tmux_client));
+    let client = tmux_client.clone();
+    let poll_interval = cli
+        .poll_interval
+        .unwrap_or(config.poll_interval_ms)
+        .max(cli::MIN_POLL_INTERVAL_MS);
+    let mut schedule = PollSchedule::new(
+        Duration::from_millis(poll_interval),
+        Duration::from_millis(config.poll_idle_interval_ms),
+        config.poll_adaptive,
+    )
+    .with_background(Duration::from_millis(config.poll_unfocused_interval_ms));
+    let (poll_control, mut poll_control_rx) = mpsc::unbounded_channel::<PollerControl>();
+    // The selected session's status is never served from the cache
+    let (selected_tx, selected_rx) = watch::channel(None::<String>);
+    // Poll rates chosen in the dashboard, by session name
+    let (rates_tx, rates_rx) = watch::channel(std::collections::BTreeMap::new());
+    // Tags by session name, copied onto each polled session
+    let (tags_tx, tags_rx) = watch::channel(Tags::default());
+    let rates = PollRates::new(
+        Duration::from_millis(config.poll_fast_interval_ms.max(cli::MIN_POLL_INTERVAL_MS)),
+        Duration::from_millis(config.poll_slow_interval_ms),
+        config.poll_rates.clone(),
+    );
+    // A read-only instance watches alongside another one, which already runs the hooks
+    let mut hooks = HookDispatcher::new(if read_only {
+        Vec::new()
+    } else {
+        config.hooks.clone()
+    });
+    let metrics_path = cli.metrics_file.clone().or(config.metrics_file.clone());
+    let mut metrics = metrics_path
+        .as_ref()
+        .map(|_| metrics::Metrics::new(config.metrics_max_sessions));
+    let activity_window = Duration::from_secs(config.activity_window_secs);
     tokio::spawn(async move {
-        let client = TmuxClient::new();
+        let mut git_cache = GitCache::new(git::GIT_CACHE_TTL);
+        let mut claude_cache = ClaudeCache::new(claude::data_dir(), claude::CLAUDE_CACHE_TTL);
+        let mut aider_cache = AiderCache::default();
+        let mut transitions = TransitionTracker::default();
+        let mut status_cache = StatusCache::new(tmux::STATUS_CACHE_TTL);
+        let mut activity = ActivityTracker::new(activity_window);
+        let mut captures = CaptureSchedule::default();
+        // Last sessions sent to the UI; unchanged polls only send a heartbeat
+        let mut last_sent: Option<Vec<TmuxSession>> = None;
         loop {
-            match client.list_sessions().await {
-                Ok(sessions) => {
-                    let _ = tmux_tx.send(Action::SessionsUpdated(sessions));
+            let started = Instant::now();
+            let selected = selected_rx.borrow().clone();
+            let plan = captures.plan(started);
+            match client
+                .list_sessions_cached(Some(&mut status_cache), &plan)
+                .await
+            {
+                Ok(mut sessions) => {
+                    // The selected session is captured every poll, and at least
+                    // fast unless the terminal is in the background
+                    let normal = schedule
+                        .base_delay()
+                        .unwrap_or(Duration::from_millis(poll_interval));
+                    let overrides = rates_rx.borrow().clone();
+                    captures.update(
+                        &plan,
+                        sessions.iter().map(|session| {
+                            let mut rate = rates.rate(&session.name, &overrides);
+                            let is_selected = selected.as_deref() == Some(session.id.as_str());
+                            if is_selected && !schedule.is_unfocused() {
+                                rate = rate.max(PollRate::Fast);
+                            }
+                            let forced = is_selected || rate == PollRate::Fast;
+                            (session.id.clone(), rates.interval(rate, normal), forced)
+                        }),
+                        started,
+                    );
+                    schedule.set_next_capture(captures.next_due());
+                    let dirs: Vec<PathBuf> = sessions
+                        .iter()
+                        .filter(|session| !session.current_path.is_empty())
+                        .map(|session| PathBuf::from(client.local_path(&session.current_path)))
+                        .collect();
+                    let mut git = git_cache.get_all(&dirs).await.into_iter();
+                    for session in &mut sessions {
+                        if !session.current_path.is_empty() {
+                            session.git = git.next().flatten();
+                            session.claude = claude_cache.get(&session.current_path).await;
+                        }
+                    }
+                    git_cache.retain(|dir| dirs.iter().any(|d| d == dir));
+                    claude_cache.retain(|cwd| sessions.iter().any(|s| s.current_path == cwd));
+                    for session in &mut sessions {
+                        if session.agent_kind() == AgentKind::Aider {
+                            session.aider = aider_cache.lookup(&client, session).await;
+                        }
+                    }
+                    aider_cache.retain(|id| sessions.iter().any(|s| s.id == id));
+                    let now = Instant::now();
+                    activity.record(&status_cache.take_changes(), now);
+                    activity.retain(|id| sessions.iter().any(|s| s.id == id));
+                    for session in &mut sessions {
+                        session.activity_trend = activity.series(&session.id, now);
+                    }
+                    tags_rx.borrow().apply(&mut sessions);
+                    let elapsed = started.elapsed();
+                    tracing::debug!(
+                        sessions = sessions.len(),
+                        elapsed_ms = elapsed.as_millis() as u64,
+                        "Poll finished"
+                    );
+                    if elapsed > SLOW_POLL {
+                        tracing::warn!(elapsed_ms = elapsed.as_millis() as u64, "Slow poll");
+                    }
+                    schedule.observe(&sessions);
+                    hooks.set_focused(schedule.is_focused());
+                    let changes = transitions.observe(&sessions);
+                    hooks.dispatch(&changes);
+                    if let (Some(metrics), Some(path)) = (&mut metrics, &metrics_path) {
+                        metrics.observe_poll(elapsed, &sessions, &changes, Instant::now());
+                        write_metrics(metrics, path);
+                    }
+                    sessions_tx.send_replace(sessions.clone());
+                    let update = if last_sent.as_ref() == Some(&sessions) {
+                        Action::Heartbeat
+                    } else {
+                        last_sent = Some(sessions.clone());
+                        Action::SessionsUpdated(sessions)
+                    };
+                    // After the update, so rules see the sessions the transitions came from
+                    let changed = (!changes.is_empty()).then(|| Action::StatusChanged(changes));
+                    if std::iter::once(update)
+                        .chain(changed)
+                        .any(|action| tmux_tx.send(action).is_err())
+                    {
+                        tracing::info!("Poller stopping: UI is gone");
+                        break;
+                    }
                 }
                 Err(e) => {
+                    if let (Some(metrics), Some(path)) = (&mut metrics, &metrics_path) {
+                        metrics.record_error();
+                        write_metrics(metrics, path);
+                    }
                     let _ = tmux_tx.send(Action::Error(format!("Tmux: {}", e)));
                 }
             }
-            tokio::time::sleep(Duration::from_millis(1000)).await;
+
+            if !poller::wait_for_next_poll(&mut schedule, &mut poll_control_rx).await {
+                break;
+            }
         }
     });
 
-    // Create shared tmux client for actions
-    let tmux_client = TmuxClient::new();
-
     // Create app state
-    let mut app = App::new();
+    let mut app = App::new(&config, PersistedState::load());
+    app.read_only = read_only;
+    app.logs = logs;
+    app.skeleton_root = cli.dir.clone();
+    let snapshot_path = snapshot::Snapshot::default_path();
+    match snapshot::Snapshot::load_from(&snapshot_path) {
+        Ok(Some(saved)) => app.offer_restore(saved),
+        Ok(None) => {}
+        Err(e) => tracing::warn!("Ignoring snapshot: {:#}", e),
+    }
+    if config.startup_digest {
+        app.offer_digest();
+    }
+    if let Some(error) = config_error.or(log_error).or(control_error) {
+        app.notify(Level::Error, error);
+    } else if let Some(warning) = config_warnings.first() {
+        app.notify(Level::Warning, format!("Config: {}", warning));
+    }
 
-    // Main event loop
-    let result = loop {
-        // Render
-        terminal.draw(|f| app.render(f))?;
+    // Draw on frame ticks, and only when something changed or is animating
+    let mut pacer = frame::FramePacer::default();
+    // Focus last passed on to the poller
+    let mut poller_focus = None;
+    let mut frame_ticker = tokio::time::interval(frame::FRAME_INTERVAL);
+    frame_ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
-        // Process any pending actions from the app
-        for pending_action in app.take_pending_actions() {
-            match pending_action {
-                Action::AttachSession(ref session_id) => {
-                    // Suspend TUI and attach to session
-                    ratatui::restore();
+    // Re-render at least once a second so elapsed times and staleness stay current
+    let mut ticker = tokio::time::interval(Duration::from_secs(1));
+
+    // Turns the busy spinner; only armed while a session is busy
+    let mut spinner_ticker = tokio::time::interval(SPINNER_INTERVAL);
+    spinner_ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
-                    let cmd = tmux_client.attach_command(session_id);
-                    let status = std::process::Command::new(&cmd[0])
-                        .args(&cmd[1..])
-                        .stdin(Stdio::inherit())
-                        .stdout(Stdio::inherit())
-                        .stderr(Stdio::inherit())
-                        .status();
+    // tmux's version and servers for the header, first right away
+    let mut server_info_ticker = tokio::time::interval(SERVER_INFO_INTERVAL);
+    server_info_ticker.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
 
-                    // Resume TUI
-                    terminal = ratatui::init();
+    // Periodic snapshots; an interval of zero never fires
+    let snapshot_period = Duration::from_secs(config.snapshot_interval_secs);
+    let mut snapshot_ticker = tokio::time::interval_at(
+        tokio::time::Instant::now() + snapshot_period.max(Duration::from_secs(1)),
+        snapshot_period.max(Duration::from_secs(1)),
+    );
 
-                    if let Err(e) = status {
-                        app.error_message = Some(format!("Failed to attach: {}", e));
+    // Main event loop
+    loop {
+        // Process any pending actions from the app; their outcome shows in the footer
+        let pending_actions = app.take_pending_actions();
+        if !pending_actions.is_empty() {
+            pacer.invalidate();
+        }
+        for pending_action in pending_actions {
+            tracing::debug!(action = pending_action.label(), "Handling action");
+            match pending_action {
+                Action::AttachSession(ref session_id, read_only) => {
+                    let cmd = tmux_client.attach_command(session_id, read_only);
+                    let result = run_suspended(&mut screen, &mut events, &poll_control, &cmd)
+                        .map_err(anyhow::Error::from)
+                        .and_then(check_status);
+                    after_attach(&mut app, &poll_control, session_id);
+                    match result {
+                        Ok(()) => app.attached(session_id),
+                        Err(e) => app.report_error(
+                            &e.context(format!("Failed to attach to {}", session_id)),
+                        ),
                     }
                 }
-                Action::CreateSession(ref name) => {
-                    match tmux_client.create_session(name).await {
-                        Ok(_) => {
-                            app.error_message = Some(format!("Session '{}' created", name));
+                Action::RingBell => {
+                    let mut stdout = std::io::stdout();
+                    if let Err(e) = stdout
+                        .write_all(app.bell.sequence().as_bytes())
+                        .and_then(|_| stdout.flush())
+                    {
+                        tracing::warn!("Failed to ring the bell: {}", e);
+                    }
+                }
+                Action::Suspend => {
+                    if let Err(e) = suspend(&mut screen, &mut events, &poll_control) {
+                        app.report_error(&anyhow::Error::from(e).context("Failed to suspend"));
+                    }
+                }
+                Action::CopyText(ref text) => match clipboard.copy(text).await {
+                    Ok(backend) => {
+                        app.notify(Level::Success, format!("Text copied to {}", backend));
+                    }
+                    Err(e) => app.report_error(&e.into()),
+                },
+                Action::CopyPane(ref session_id) => {
+                    let captured = tmux_client
+                        .capture_pane_with(session_id, config.copy_history_lines, true)
+                        .await;
+                    match captured {
+                        Ok(content) => {
+                            let content = clipboard::pane_text(&content, config.copy_strip_ansi);
+                            match clipboard.copy(&content).await {
+                                Ok(backend) => {
+                                    app.notify(
+                                        Level::Success,
+                                        clipboard::describe_pane_copy(&content, &backend),
+                                    );
+                                }
+                                Err(e) => app.report_error(&e.into()),
+                            }
                         }
                         Err(e) => {
-                            app.error_message = Some(format!("Failed to create: {}", e));
+                            app.notify(Level::Error, format!("Failed to capture: {}", e));
                         }
                     }
                 }
-                Action::DeleteSession(ref session_id) => {
-                    match tmux_client.kill_session(session_id).await {
-                        Ok(_) => {
-                            app.error_message = Some("Session deleted".to_string());
+                Action::AttachSessionInWindow(ref session_id) => {
+                    let cmd = tmux_client.attach_command(session_id, false);
+                    let template = config
+                        .attach
+                        .terminal_command
+                        .as_deref()
+                        .or_else(|| terminal::detect(|key| std::env::var(key).ok()));
+                    let spawned = match template {
+                        Some(template) => {
+                            terminal::spawn_detached(&terminal::command_argv(template, &cmd))
+                                .map_err(|e| format!("Failed to open terminal: {}", e))
                         }
-                        Err(e) => {
-                            app.error_message = Some(format!("Failed to delete: {}", e));
+                        None => {
+                            Err("No terminal configured for attach.terminal_command".to_string())
+                        }
+                    };
+                    if spawned.is_ok() {
+                        after_attach(&mut app, &poll_control, session_id);
+                    }
+                    if let Err(warning) = spawned {
+                        tracing::warn!("{}; attaching in place", warning);
+                        let result = run_suspended(&mut screen, &mut events, &poll_control, &cmd)
+                            .map_err(anyhow::Error::from)
+                            .and_then(check_status);
+                        after_attach(&mut app, &poll_control, session_id);
+                        match result {
+                            Ok(()) => app
+                                .notify(Level::Warning, format!("{}; attached in place", warning)),
+                            Err(e) => app.report_error(
+                                &e.context(warning)
+                                    .context(format!("Failed to attach to {}", session_id)),
+                            ),
                         }
                     }
                 }
-                Action::CopySkeleton => {
-                    match skeleton::generate_skeleton(".").await {
-                        Ok(tree) => match arboard::Clipboard::new() {
-                            Ok(mut clipboard) => {
-                                if let Err(e) = clipboard.set_text(&tree) {
-                                    app.error_message = Some(format!("Clipboard error: {}", e));
+                Action::OpenEditor(ref session_id, ref dir) => {
+                    let Some(template) = editor::resolve_command(config.editor_command.as_deref())
+                    else {
+                        app.notify(
+                            Level::Warning,
+                            "No editor: set $VISUAL, $EDITOR or editor_command",
+                        );
+                        continue;
+                    };
+                    let cmd = editor::command_argv(&template, &tmux_client.local_path(dir));
+                    let result = if config.editor_gui {
+                        terminal::spawn_detached(&cmd)
+                    } else {
+                        run_suspended(&mut screen, &mut events, &poll_control, &cmd).and_then(
+                            |status| {
+                                if status.success() {
+                                    Ok(())
                                 } else {
-                                    app.error_message =
-                                        Some("Skeleton copied to clipboard!".to_string());
+                                    Err(std::io::Error::other(format!("exited with {}", status)))
                                 }
-                            }
+                            },
+                        )
+                    };
+                    after_attach(&mut app, &poll_control, session_id);
+                    if let Err(e) = result {
+                        app.notify(Level::Error, format!("Failed to open {}: {}", cmd[0], e));
+                    }
+                }
+                Action::CreateSession(NewSession {
+                    name,
+                    launcher,
+                    cwd: dir,
+                }) => {
+                    let op = app.start_operation(format!("Creating '{}'", name));
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        // Resume what Claude Code last did in the directory the session starts in
+                        let resume_in = launcher
+                            .as_ref()
+                            .filter(|launcher| launcher.resume)
+                            .and_then(|_| dir.clone().or_else(|| std::env::current_dir().ok()));
+                        let conversation = match resume_in {
+                            Some(dir) => claude::lookup(&dir.to_string_lossy()).await,
+                            None => None,
+                        };
+                        let command = launcher
+                            .and_then(|launcher| launcher.command_for(conversation.as_ref()));
+                        let result = client
+                            .create_session_with(&name, dir.as_deref(), command.as_deref())
+                            .await
+                            .with_context(|| format!("Failed to create session '{}'", name));
+                        let _ = tx.send(Action::SessionCreated(op, result.map_err(Arc::new)));
+                    });
+                }
+                Action::DuplicateSession(source_id, NewSession { name, launcher, .. }) => {
+                    let source = app
+                        .sessions
+                        .iter()
+                        .find(|s| s.id == source_id)
+                        .map_or_else(|| source_id.clone(), |s| s.name.clone());
+                    let op = app.start_operation(format!("Duplicating '{}'", source));
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let command = launcher.and_then(|l| l.command_for(None));
+                        // Where the agent is now, which may not be where it started
+                        let result = async {
+                            let cwd = client.get_session_cwd(&source_id).await?;
+                            let dir = Some(client.local_path(&cwd)).filter(|d| !d.is_empty());
+                            client
+                                .create_session_beside(
+                                    &source_id,
+                                    &name,
+                                    dir.as_deref().map(Path::new),
+                                    command.as_deref(),
+                                )
+                                .await
+                        }
+                        .await
+                        .with_context(|| format!("Failed to duplicate '{}' as '{}'", source, name));
+                        let _ = tx.send(Action::SessionDuplicated(
+                            op,
+                            source,
+                            name,
+                            result.map_err(Arc::new),
+                        ));
+                    });
+                }
+                Action::CreateSessions(template, sessions) => {
+                    let op = app.start_operation(format!("Creating sessions from '{}'", template));
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        // One failing doesn't stop the rest
+                        let mut results = Vec::with_capacity(sessions.len());
+                        for session in sessions {
+                            let result = match &session.cwd {
+                                Some(dir) if !dir.is_dir() => {
+                                    Err(anyhow::anyhow!("'{}' is not a directory", dir.display()))
+                                }
+                                dir => {
+                                    let command =
+                                        session.launcher.and_then(|l| l.command_for(None));
+                                    client
+                                        .create_session_with(
+                                            &session.name,
+                                            dir.as_deref(),
+                                            command.as_deref(),
+                                        )
+                                        .await
+                                        .map_err(anyhow::Error::from)
+                                }
+                            };
+                            results.push((session.name, result.map_err(Arc::new)));
+                        }
+                        let _ = tx.send(Action::TemplateCreated(op, template, results));
+                    });
+                }
+                Action::CreateWorktree(request, launcher) => {
+                    let op = app.start_operation(format!("Creating worktree '{}'", request.branch));
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        // A fresh worktree has no conversation to resume
+                        let command = launcher.and_then(|launcher| launcher.command_for(None));
+                        let result =
+                            worktree::create_session(&client, &request, command.as_deref()).await;
+                        let _ = tx.send(Action::WorktreeCreated(op, result.map_err(Arc::new)));
+                    });
+                }
+                Action::RemoveWorktree(worktree) => {
+                    let op = app
+                        .start_operation(format!("Removing worktree {}", worktree.path.display()));
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let result = worktree::remove(&worktree).await.map(|_| worktree);
+                        let _ = tx.send(Action::WorktreeRemoved(op, result.map_err(Arc::new)));
+                    });
+                }
+                Action::DeleteSession(session_id) => {
+                    let name = app
+                        .sessions
+                        .iter()
+                        .find(|s| s.id == session_id)
+                        .map_or_else(|| session_id.clone(), |s| format!("'{}'", s.name));
+                    let op = app.start_operation(format!("Deleting {}", name));
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let result = client
+                            .kill_session(&session_id)
+                            .await
+                            .map(|_| session_id)
+                            .with_context(|| format!("Failed to delete session {}", name));
+                        let _ = tx.send(Action::SessionDeleted(op, result.map_err(Arc::new)));
+                    });
+                }
+                Action::RenameSession(session_id, name) => {
+                    let op = app.start_operation(format!("Renaming to '{}'", name));
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let result = client
+                            .rename_session(&session_id, &name)
+                            .await
+                            .map(|_| (session_id, name.clone()))
+                            .with_context(|| format!("Failed to rename session to '{}'", name));
+                        let _ = tx.send(Action::SessionRenamed(op, result.map_err(Arc::new)));
+                    });
+                }
+                Action::CopySkeleton(ref root) => {
+                    let op = app.start_operation("Copying skeleton");
+                    let clipboard = clipboard.clone();
+                    let root = root.to_string_lossy().into_owned();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let result = match skeleton::generate_skeleton(&root).await {
+                            Ok(tree) => clipboard
+                                .copy(&tree)
+                                .await
+                                .context("Failed to copy skeleton"),
                             Err(e) => {
-                                app.error_message = Some(format!("Clipboard error: {}", e));
+                                Err(anyhow::Error::from(e).context("Failed to generate skeleton"))
                             }
-                        },
+                        };
+                        let _ = tx.send(Action::SkeletonCopied(op, result.map_err(Arc::new)));
+                    });
+                }
+                Action::CapturePane(ref session_id) => {
+                    match tmux_client
+                        .capture_pane(session_id, preview::PREVIEW_HISTORY_LINES)
+                        .await
+                    {
+                        Ok(content) => {
+                            let _ = tx.send(Action::PaneCaptured(session_id.clone(), content));
+                        }
                         Err(e) => {
-                            app.error_message = Some(format!("Skeleton error: {}", e));
+                            app.notify(Level::Error, format!("Failed to capture: {}", e));
                         }
                     }
                 }
+                Action::RestartAgent(ref session_id) => {
+                    let Some(session) = app.sessions.iter().find(|s| &s.id == session_id) else {
+                        continue;
+                    };
+                    let name = session.name.clone();
+                    // What the session was launched with here, else the agent's usual command
+                    let launched = app
+                        .state
+                        .launcher(&session.name)
+                        .and_then(|l| l.command_for(session.claude.as_ref()));
+                    let Some(command) = launched.or_else(|| {
+                        config
+                            .restart_command(session.agent_kind())
+                            .map(str::to_string)
+                    }) else {
+                        app.notify(
+                            Level::Warning,
+                            format!(
+                                "No restart command for '{}' ({})",
+                                name,
+                                session.agent_kind().label()
+                            ),
+                        );
+                        continue;
+                    };
+                    let client = tmux_client.clone();
+                    let session_id = session_id.clone();
+                    let restart_tx = tx.clone();
+                    app.notify(Level::Info, format!("Restarting agent in '{}'...", name));
+                    // Runs in the background so the UI keeps updating while we wait
+                    tokio::spawn(async move {
+                        let result =
+                            restart::restart_agent(&client, &session_id, &command, |step| {
+                                tracing::info!("Restart '{}': {}", name, step)
+                            })
+                            .await;
+                        let _ = restart_tx.send(match result {
+                            Ok(()) => Action::Notify(
+                                Level::Success,
+                                format!("Agent in '{}' restarted successfully", name),
+                            ),
+                            Err(e) => {
+                                tracing::warn!("Restart '{}' failed: {:#}", name, e);
+                                Action::Error(format!("Restart of '{}' failed: {:#}", name, e))
+                            }
+                        });
+                    });
+                }
+                Action::Broadcast(recipients, text) => {
+                    let mut results = Vec::with_capacity(recipients.len());
+                    for recipient in recipients {
+                        let outcome = tmux_client
+                            .send_keys(&recipient.id, &text)
+                            .await
+                            .map_err(|e| format!("{:#}", e));
+                        match &outcome {
+                            Ok(()) => tracing::info!("Broadcast sent to '{}'", recipient.name),
+                            Err(e) => {
+                                tracing::warn!("Broadcast to '{}' failed: {}", recipient.name, e)
+                            }
+                        }
+                        results.push(broadcast::BroadcastResult {
+                            name: recipient.name,
+                            outcome,
+                        });
+                    }
+                    let _ = tx.send(Action::BroadcastFinished(results));
+                }
+                Action::Refresh => {
+                    let _ = poll_control.send(PollerControl::PollNow);
+                }
+                Action::TakeSnapshot => {
+                    let op = app.start_operation("Saving snapshot");
+                    let client = tmux_client.clone();
+                    let path = snapshot_path.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let result = snapshot::save(&client, &path)
+                            .await
+                            .context("Failed to save snapshot");
+                        let _ = tx.send(Action::SnapshotSaved(op, result.map_err(Arc::new)));
+                    });
+                }
+                Action::ExportSessions(notes, tags) => {
+                    let op = app.start_operation("Exporting sessions");
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let result = inventory::save(
+                            &client,
+                            &notes,
+                            &tags,
+                            &inventory::Inventory::default_path(),
+                        )
+                        .await
+                        .context("Failed to export sessions");
+                        let _ = tx.send(Action::SessionsExported(op, result.map_err(Arc::new)));
+                    });
+                }
+                Action::SendScheduled(scheduled, id) => {
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let result = match &id {
+                            Some(id) => client
+                                .send_keys(id, &scheduled.prompt)
+                                .await
+                                .map_err(|e| format!("{:#}", e)),
+                            None => Err("no session with that name".to_string()),
+                        };
+                        match &result {
+                            Ok(()) => tracing::info!(
+                                "Scheduled prompt #{} sent to '{}'",
+                                scheduled.id,
+                                scheduled.session
+                            ),
+                            Err(e) => tracing::warn!(
+                                "Scheduled prompt #{} for '{}' failed: {}",
+                                scheduled.id,
+                                scheduled.session,
+                                e
+                            ),
+                        }
+                        let _ = tx.send(Action::ScheduledSent(scheduled, result));
+                    });
+                }
+                Action::RestoreSessions(sessions) => {
+                    let op = app.start_operation("Restoring sessions");
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    let poll_control = poll_control.clone();
+                    tokio::spawn(async move {
+                        let result = snapshot::restore_all(&client, &sessions).await;
+                        let _ = poll_control.send(PollerControl::PollNow);
+                        let _ = tx.send(Action::SessionsRestored(op, result.map_err(Arc::new)));
+                    });
+                }
+                Action::FireRule(id, firing, target) => {
+                    let summary = format!(
+                        "{} {}",
+                        app.theme.icons.get(firing.transition.status),
+                        firing.transition.session
+                    );
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let result = match &firing.action {
+                            RuleAction::Send { session, prompt } => match &target {
+                                Some(target) => client
+                                    .send_keys(target, prompt)
+                                    .await
+                                    .map_err(|e| format!("{:#}", e)),
+                                None => Err(format!("no session named '{}'", session)),
+                            },
+                            RuleAction::Run(command) => {
+                                hooks::run_shell(command, &firing.transition).await
+                            }
+                            RuleAction::Notify(text) => hooks::notify(&summary, text).await,
+                        };
+                        match &result {
+                            Ok(()) => tracing::info!("Rule '{}' done", firing.rule),
+                            Err(e) => tracing::warn!("Rule '{}' failed: {}", firing.rule, e),
+                        }
+                        let _ = tx.send(Action::RuleFinished(id, result));
+                    });
+                }
+                Action::FireAlert(alert) => {
+                    let status = alert.rule.status;
+                    let summary = format!("{} {}", app.theme.icons.get(status), alert.session);
+                    let body = format!(
+                        "{} for over {}",
+                        metrics::label(status),
+                        text::format_duration_long(alert.rule.for_secs)
+                    );
+                    let transition = poller::Transition {
+                        session_id: alert.session_id.clone(),
+                        session: alert.session.clone(),
+                        previous: status,
+                        status,
+                    };
+                    tokio::spawn(async move {
+                        let result = match &alert.rule.run {
+                            Some(command) => hooks::run_shell(command, &transition).await,
+                            None => hooks::notify(&summary, &body).await,
+                        };
+                        if let Err(e) = result {
+                            tracing::warn!("Alert for {} failed: {}", alert.session, e);
+                        }
+                    });
+                }
+                Action::SampleUsage(sessions) => {
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let mut readings = Vec::new();
+                        for session in sessions {
+                            let content = match client
+                                .capture_pane(&session.id, usage::USAGE_HISTORY_LINES)
+                                .await
+                            {
+                                Ok(content) => content,
+                                Err(e) => {
+                                    tracing::debug!(
+                                        "Skipping usage of '{}': {:#}",
+                                        session.name,
+                                        e
+                                    );
+                                    continue;
+                                }
+                            };
+                            let reading = usage::parse(&content);
+                            if !reading.is_empty() {
+                                readings.push((
+                                    session.name.clone(),
+                                    session.agent_kind(),
+                                    reading,
+                                ));
+                            }
+                        }
+                        let _ = tx.send(Action::UsageSampled(readings));
+                    });
+                }
+                Action::ReadDir(dir) => {
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let entries = dir_picker::read_dir(&dir)
+                            .await
+                            .map_err(|e| Arc::new(anyhow::Error::from(e)));
+                        let _ = tx.send(Action::DirRead(dir, entries));
+                    });
+                }
+                Action::ListWindows(session_id) => {
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let windows = client
+                            .list_windows(&session_id)
+                            .await
+                            .context("Failed to list windows")
+                            .map_err(Arc::new);
+                        let _ = tx.send(Action::WindowsListed(session_id, windows));
+                    });
+                }
+                Action::ListClients(session_id) => {
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let clients = client
+                            .list_clients(&session_id)
+                            .await
+                            .context("Failed to list clients")
+                            .map_err(Arc::new);
+                        let _ = tx.send(Action::ClientsListed(session_id, clients));
+                    });
+                }
+                Action::DetachClient(session_id, name) => {
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let result = client
+                            .detach_client(&session_id, &name)
+                            .await
+                            .with_context(|| format!("Failed to detach {}", name))
+                            .map_err(Arc::new);
+                        let _ = tx.send(Action::ClientDetached(session_id, name, result));
+                    });
+                }
+                Action::LoadMcpStatus => {
+                    let tx = tx.clone();
+                    tokio::task::spawn_blocking(move || {
+                        let servers = mcp::ServerStatus::running(&mcp::status_dir());
+                        let _ = tx.send(Action::McpStatusLoaded(servers));
+                    });
+                }
+                Action::ListWindowTree(session_id) => {
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let windows = client
+                            .list_windows(&session_id)
+                            .await
+                            .context("Failed to list windows")
+                            .map_err(Arc::new);
+                        let _ = tx.send(Action::WindowTreeListed(session_id, windows));
+                    });
+                }
+                Action::RunCommand(command, context) => match command.mode {
+                    RunMode::Background => {
+                        let op = app.start_operation(format!("Running '{}'", command.name));
+                        let local = CommandContext {
+                            cwd: tmux_client.local_path(&context.cwd),
+                            ..context
+                        };
+                        let tx = tx.clone();
+                        tokio::spawn(async move {
+                            let script = custom_commands::expand(&command.run, &local);
+                            let dir = Some(Path::new(&local.cwd)).filter(|dir| dir.is_dir());
+                            let result = custom_commands::run_background(&script, dir)
+                                .await
+                                .with_context(|| {
+                                    format!("'{}' failed in '{}'", command.name, local.session_name)
+                                })
+                                .map_err(Arc::new);
+                            let _ = tx.send(Action::CommandFinished(op, command.name, result));
+                        });
+                    }
+                    // The window runs on the tmux side, so `{cwd}` stays as tmux reports it
+                    RunMode::Window => {
+                        let script = custom_commands::expand(&command.run, &context);
+                        let opened = tmux_client
+                            .new_window_with(
+                                &context.session_id,
+                                &command.name,
+                                &context.cwd,
+                                &["sh", "-c", &script],
+                            )
+                            .await;
+                        match opened {
+                            Ok(_) => app.notify(
+                                Level::Success,
+                                format!(
+                                    "Opened window '{}' in '{}'",
+                                    command.name, context.session_name
+                                ),
+                            ),
+                            Err(e) => app.report_error(
+                                &anyhow::Error::from(e)
+                                    .context(format!("Failed to open window '{}'", command.name)),
+                            ),
+                        }
+                    }
+                    RunMode::Interactive => {
+                        let local = CommandContext {
+                            cwd: tmux_client.local_path(&context.cwd),
+                            ..context
+                        };
+                        let script = custom_commands::expand(&command.run, &local);
+                        let result =
+                            with_suspended(&mut screen, &mut events, &poll_control, || {
+                                let mut child = std::process::Command::new("sh");
+                                child.arg("-c").arg(&script);
+                                if Path::new(&local.cwd).is_dir() {
+                                    child.current_dir(&local.cwd);
+                                }
+                                child.status()
+                            })
+                            .and_then(|status| status)
+                            .map_err(anyhow::Error::from)
+                            .and_then(check_status);
+                        after_attach(&mut app, &poll_control, &local.session_id);
+                        if let Err(e) = result {
+                            app.report_error(&e.context(format!("'{}' failed", command.name)));
+                        }
+                    }
+                },
+                // Awaited here so the attach queued after it finds the window selected
+                Action::Interrupt(ref session_id) => {
+                    if let Err(e) = tmux_client.send_key_names(session_id, &["C-c"]).await {
+                        app.report_error(
+                            &anyhow::Error::from(e)
+                                .context(format!("Failed to interrupt {}", session_id)),
+                        );
+                    }
+                }
+                Action::SelectWindow(ref target) => {
+                    if let Err(e) = tmux_client.select_window(target).await {
+                        tracing::warn!("Failed to select window {}: {}", target, e);
+                    }
+                }
+                Action::ReadQuestions(session_ids) => {
+                    let client = tmux_client.clone();
+                    let tx = tx.clone();
+                    tokio::spawn(async move {
+                        let mut questions = Vec::new();
+                        for id in session_ids {
+                            match client.capture_pane(&id, 0).await {
+                                Ok(content) => {
+                                    if let Some(line) = digest::pending_question(&content) {
+                                        questions.push((id, line));
+                                    }
+                                }
+                                Err(e) => tracing::debug!("Skipping question of {}: {:#}", id, e),
+                            }
+                        }
+                        let _ = tx.send(Action::QuestionsRead(questions));
+                    });
+                }
+                Action::ArchiveAndKill(verdict) => {
+                    let op = app.start_operation(format!("Archiving '{}'", verdict.name));
+                    let client = tmux_client.clone();
+                    let dir = app.policy.archive_dir();
+                    let tx = tx.clone();
+                    let poll_control = poll_control.clone();
+                    tokio::spawn(async move {
+                        let result = policy::archive_and_kill(
+                            &client,
+                            &dir,
+                            &verdict.session_id,
+                            &verdict.name,
+                            text::unix_now(),
+                        )
+                        .await
+                        .with_context(|| format!("Failed to clean up session '{}'", verdict.name));
+                        match &result {
+                            Ok(path) => tracing::info!(
+                                "Archived '{}' to {} and closed it",
+                                verdict.name,
+                                path.display()
+                            ),
+                            Err(e) => tracing::warn!("{:#}", e),
+                        }
+                        let _ = poll_control.send(PollerControl::PollNow);
+                        let _ = tx.send(Action::SessionArchived(
+                            op,
+                            verdict,
+                            result.map_err(Arc::new),
+                        ));
+                    });
+                }
                 _ => {}
             }
         }
 
-        // Handle events from channel
-        tokio::select! {
-            Some(action) = rx.recv() => {
-                match app.handle_action(action) {
-                    Ok(should_quit) => {
-                        if should_quit {
-                            break Ok(());
-                        }
-                    }
-                    Err(e) => {
-                        break Err(e);
+        // Wait for terminal input, a background update, or the next tick
+        let mut input_at = None;
+        let action = tokio::select! {
+            _ = frame_ticker.tick() => {
+                if pacer.should_draw(app.is_animating()) {
+                    screen.draw(&mut app)?;
+                }
+                continue;
+            }
+            Some(action) = rx.recv() => action,
+            Some(event) = events.next() => match event {
+                Ok(event) => match Action::from_event(event) {
+                    Some(action) => {
+                        input_at = Some(Instant::now());
+                        action
                     }
+                    None => continue,
+                },
+                Err(e) => Action::Error(format!("Input: {}", e)),
+            },
+            // Clocks and staleness can wait while nobody is looking
+            _ = ticker.tick() => {
+                app.queue_due_schedules(text::unix_now());
+                if !app.in_background() {
+                    pacer.invalidate();
                 }
+                Action::Tick
+            }
+            _ = spinner_ticker.tick(), if app.is_spinning() => Action::Tick,
+            _ = server_info_ticker.tick() => {
+                let client = tmux_client.clone();
+                let tx = tx.clone();
+                tokio::spawn(async move {
+                    let info = client.server_info().await.map_err(|e| Arc::new(e.into()));
+                    let _ = tx.send(Action::ServerInfoUpdated(info));
+                });
+                continue;
             }
+            _ = snapshot_ticker.tick(), if !snapshot_period.is_zero() => {
+                if app.can_snapshot() {
+                    let client = tmux_client.clone();
+                    let path = snapshot_path.clone();
+                    tokio::spawn(async move {
+                        match snapshot::save(&client, &path).await {
+                            Ok(count) => tracing::debug!(sessions = count, "Snapshot saved"),
+                            Err(e) => tracing::warn!("Periodic snapshot failed: {:#}", e),
+                        }
+                    });
+                }
+                continue;
+            }
+        };
+
+        // Keys the terminal queued meanwhile, like a held key's repeats, join
+        // the burst so they are all handled before the next frame
+        let mut burst = vec![action];
+        while let Some(Some(event)) = events.next().now_or_never() {
+            match event {
+                Ok(event) => burst.extend(Action::from_event(event)),
+                Err(e) => {
+                    burst.push(Action::Error(format!("Input: {}", e)));
+                    break;
+                }
+            }
+        }
+        // Coalesce a burst of updates into a single frame
+        let letters = !app.takes_text();
+        match frame::handle_burst(&mut rx, burst, letters, |action| app.handle_action(action)) {
+            Ok(should_quit) => {
+                // From reading a key to the app having acted on it
+                if let Some(at) = input_at {
+                    tracing::debug!(
+                        latency_us = at.elapsed().as_micros() as u64,
+                        "Input handled"
+                    );
+                }
+                if should_quit {
+                    tracing::debug!(frames = pacer.frames(), "UI stopping");
+                    break Ok(());
+                }
+            }
+            Err(e) => {
+                break Err(e);
+            }
+        }
+        if app.take_dirty() {
+            pacer.invalidate();
+        }
+        selected_tx.send_replace(app.selected_session().map(|s| s.id.clone()));
+        rates_tx.send_if_modified(|rates| {
+            let changed = *rates != app.state.poll_rates;
+            if changed {
+                rates.clone_from(&app.state.poll_rates);
+            }
+            changed
+        });
+        tags_tx.send_if_modified(|tags| {
+            let changed = *tags != app.state.tags;
+            if changed {
+                tags.clone_from(&app.state.tags);
+            }
+            changed
+        });
+        if app.focused != poller_focus
+            && let Some(focused) = app.focused
+        {
+            let _ = poll_control.send(PollerControl::Focus(focused));
+            poller_focus = app.focused;
+        }
+    }
+}
+
+/// Client for the chosen tmux server, run inside WSL when configured or detected
+fn tmux_client(
+    tmux: &str,
+    socket_name: Option<&str>,
+    wsl_distro: Option<String>,
+    config: &Config,
+) -> TmuxClient {
+    let mut client =
+        TmuxClient::with_options(tmux, socket_name).with_title_rules(config.title_rules.clone());
+    // `-L` picks one server; otherwise watch every configured one
+    if socket_name.is_none() && !config.sockets.is_empty() {
+        client = client.with_sockets(config.sockets());
+    }
+    let wsl = wsl_distro.is_some() || config.wsl.enabled.unwrap_or_else(tmux::Wsl::available);
+    if !wsl {
+        return client;
+    }
+    let distro = wsl_distro
+        .or_else(|| config.wsl.distro.clone())
+        .or_else(tmux::Wsl::default_distro);
+    tracing::info!(
+        distro = distro.as_deref().unwrap_or("default"),
+        "Running tmux in WSL"
+    );
+    client.with_wsl(tmux::Wsl::new(distro))
+}
+
+/// Rewrite the metrics file; a failure is logged and the next poll tries again
+fn write_metrics(metrics: &metrics::Metrics, path: &Path) {
+    if let Err(e) = metrics.write_to(path) {
+        tracing::warn!("Failed to write metrics to {}: {}", path.display(), e);
+    }
+}
+
+/// Take the instance lock, asking the user what to do if another instance holds it
+///
+/// Returns the lock (if we own it) and whether to run read-only.
+fn acquire_instance_lock(path: &Path) -> Result<(Option<instance::InstanceLock>, bool)> {
+    let pid = match instance::acquire(path) {
+        Ok(instance::Acquire::Acquired(lock)) => return Ok((Some(lock), false)),
+        Ok(instance::Acquire::Held { pid }) => pid,
+        Err(e) => {
+            tracing::warn!("Running without instance lock {}: {}", path.display(), e);
+            return Ok((None, false));
         }
     };
 
-    // Restore terminal
-    ratatui::restore();
-    result
+    let holder = pid.map_or_else(|| "unknown pid".to_string(), |p| format!("pid {}", p));
+    eprint!(
+        "agent-rusty is already running ({}).\n[q]uit, [t]ake over, or continue [r]ead-only? [q] ",
+        holder
+    );
+    let mut answer = String::new();
+    std::io::stdin().read_line(&mut answer)?;
+    match answer.trim().to_lowercase().as_str() {
+        "t" | "take over" => {
+            instance::request_takeover(path)
+                .with_context(|| format!("Failed to take over from {}", holder))?;
+            match instance::acquire(path)? {
+                instance::Acquire::Acquired(lock) => Ok((Some(lock), false)),
+                instance::Acquire::Held { .. } => {
+                    anyhow::bail!("Another instance grabbed the lock first")
+                }
+            }
+        }
+        "r" | "read-only" => Ok((None, true)),
+        _ => std::process::exit(0),
+    }
+}
+
+/// How the dashboard reaches the user: drawn full-screen, or narrated as
+/// plain lines for screen readers
+enum Screen {
+    Tui(DefaultTerminal),
+    Plain(accessible::Narrator),
+}
+
+impl Screen {
+    fn init(accessible: bool) -> std::io::Result<Self> {
+        if accessible {
+            init_plain()?;
+            Ok(Screen::Plain(accessible::Narrator::default()))
+        } else {
+            init_terminal().map(Screen::Tui)
+        }
+    }
+
+    fn draw(&mut self, app: &mut App) -> std::io::Result<()> {
+        match self {
+            Screen::Tui(terminal) => terminal.draw(|f| app.render(f)).map(drop),
+            Screen::Plain(narrator) => {
+                let lines = narrator.narrate(app, text::unix_now());
+                accessible::write_lines(&mut std::io::stdout(), &lines)
+            }
+        }
+    }
+
+    /// Take the terminal back after running something in the foreground
+    fn reinit(&mut self) -> std::io::Result<()> {
+        match self {
+            Screen::Tui(terminal) => *terminal = init_terminal()?,
+            Screen::Plain(_) => init_plain()?,
+        }
+        Ok(())
+    }
+}
+
+/// Terminal input as a stream of events
+///
+/// The stream reads on a thread of its own, which would take the keys meant
+/// for a program run in the foreground, so it is dropped while suspended.
+struct Input(Option<EventStream>);
+
+impl Input {
+    fn new() -> Self {
+        Input(Some(EventStream::new()))
+    }
+
+    async fn next(&mut self) -> Option<std::io::Result<Event>> {
+        self.0.as_mut()?.next().await
+    }
+
+    fn pause(&mut self) {
+        self.0 = None;
+    }
+
+    fn resume(&mut self) {
+        self.0 = Some(EventStream::new());
+    }
+}
+
+/// Whether the TUI is on the alternate screen, so restoring must leave it
+static ALTERNATE_SCREEN: AtomicBool = AtomicBool::new(false);
+
+/// Enter the TUI, with bracketed paste, focus reporting and the mouse enabled
+///
+/// Unlike `ratatui::init` this doesn't install a panic hook, so re-entering
+/// after a suspend doesn't stack another one on top of ours.
+fn init_terminal() -> std::io::Result<DefaultTerminal> {
+    crossterm::terminal::enable_raw_mode()?;
+    crossterm::execute!(std::io::stdout(), EnterAlternateScreen)?;
+    ALTERNATE_SCREEN.store(true, Ordering::SeqCst);
+    enable_input_events();
+    // Only the TUI has rows to click; the plain mode leaves the mouse to the terminal
+    if let Err(e) = crossterm::execute!(std::io::stdout(), EnableMouseCapture) {
+        tracing::warn!("Failed to enable the mouse: {}", e);
+    }
+    Terminal::new(CrosstermBackend::new(std::io::stdout()))
+}
+
+/// Read keys one at a time but stay on the normal screen, where printed
+/// lines reach the screen reader
+fn init_plain() -> std::io::Result<()> {
+    crossterm::terminal::enable_raw_mode()?;
+    enable_input_events();
+    Ok(())
+}
+
+fn enable_input_events() {
+    if let Err(e) = crossterm::execute!(std::io::stdout(), EnableBracketedPaste, EnableFocusChange)
+    {
+        tracing::warn!("Failed to enable paste and focus events: {}", e);
+    }
+}
+
+/// Restores the terminal however `main` exits, including early `?` returns
+struct TerminalGuard;
+
+impl Drop for TerminalGuard {
+    fn drop(&mut self) {
+        restore_terminal();
+    }
+}
+
+fn restore_terminal() {
+    // Left on, mouse reporting would reach whatever runs in the terminal next as garbage
+    let _ = crossterm::execute!(
+        std::io::stdout(),
+        DisableMouseCapture,
+        DisableBracketedPaste,
+        DisableFocusChange
+    );
+    if ALTERNATE_SCREEN.swap(false, Ordering::SeqCst) {
+        ratatui::restore();
+    } else {
+        let _ = crossterm::terminal::disable_raw_mode();
+    }
+}
+
+/// Leave the TUI, run `cmd` in the foreground on this terminal, then restore the TUI
+///
+/// Polling pauses meanwhile, and an immediate poll on return refreshes the list.
+fn run_suspended(
+    screen: &mut Screen,
+    input: &mut Input,
+    poller: &mpsc::UnboundedSender<PollerControl>,
+    cmd: &[String],
+) -> std::io::Result<ExitStatus> {
+    with_suspended(screen, input, poller, || {
+        std::process::Command::new(&cmd[0])
+            .args(&cmd[1..])
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .stderr(Stdio::inherit())
+            .status()
+    })?
+}
+
+/// Run `f` with the TUI torn down and input and polling paused, then bring
+/// them back
+///
+/// A re-created terminal starts with empty buffers, so the next draw
+/// repaints the whole screen.
+fn with_suspended<T>(
+    screen: &mut Screen,
+    input: &mut Input,
+    poller: &mpsc::UnboundedSender<PollerControl>,
+    f: impl FnOnce() -> T,
+) -> std::io::Result<T> {
+    let _ = poller.send(PollerControl::Pause);
+    input.pause();
+    restore_terminal();
+
+    let result = f();
+
+    screen.reinit()?;
+    input.resume();
+    let _ = poller.send(PollerControl::Resume);
+    Ok(result)
+}
+
+/// Stop like a shell job on Ctrl-Z, returning once continued with `fg`
+#[cfg(unix)]
+fn suspend(
+    screen: &mut Screen,
+    input: &mut Input,
+    poller: &mpsc::UnboundedSender<PollerControl>,
+) -> std::io::Result<()> {
+    with_suspended(screen, input, poller, || {
+        // The terminal is already restored, so the shell finds it sane.
+        // Stopping the whole group takes any children we spawned along.
+        // SAFETY: kill(2) has no memory-safety preconditions.
+        if unsafe { libc::kill(0, libc::SIGTSTP) } == 0 {
+            Ok(())
+        } else {
+            Err(std::io::Error::last_os_error())
+        }
+    })?
+}
+
+/// Job control is unix-only; elsewhere Ctrl-Z is ignored
+#[cfg(not(unix))]
+fn suspend(
+    _screen: &mut Screen,
+    _input: &mut Input,
+    _poller: &mpsc::UnboundedSender<PollerControl>,
+) -> std::io::Result<()> {
+    Ok(())
+}
+
+/// Catch up after attaching to a session or editing in its directory
+///
+/// Polls right away rather than showing data from before the detach, and
+/// puts the selection back on the session.
+fn after_attach(app: &mut App, poller: &mpsc::UnboundedSender<PollerControl>, session_id: &str) {
+    let _ = poller.send(PollerControl::PollNow);
+    app.returned_from(session_id);
+}
+
+/// Treat a non-zero exit from a foreground command as an error
+fn check_status(status: ExitStatus) -> Result<()> {
+    if status.success() {
+        Ok(())
+    } else {
+        anyhow::bail!("{}", status)
+    }
 }
@@ -0,0 +1,696 @@
+//! `agent-rusty mcp-serve`: a Model Context Protocol server on stdio.
+//!
+//! Messages are newline-delimited JSON-RPC 2.0. The server answers `initialize`,
+//! `ping`, `tools/list` and `tools/call`; notifications get no reply. Tool failures
+//! are reported as results with `isError: true` so the calling agent can see them,
+//! while malformed requests get JSON-RPC errors.
+//!
+//! Each running server keeps a [`ServerStatus`] in `~/.agent-deck/mcp/<pid>.json`
+//! so the dashboard can show that it is up and how much it has been used.
+
+use anyhow::Result;
+use serde::{Deserialize, Serialize};
+use serde_json::{Value, json};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::time::Duration;
+use tokio::io::{AsyncBufReadExt, AsyncWriteExt, BufReader};
+
+use crate::commands::find_session;
+use crate::text;
+use crate::tmux::{AgentStatus, Sessions, TmuxClient, TmuxSession};
+use crate::wait::{self, WaitOutcome};
+
+/// Protocol revision we implement
+pub const PROTOCOL_VERSION: &str = "2024-11-05";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+/// Lines returned by `get_session_output` when no limit is given
+const DEFAULT_OUTPUT_LINES: usize = 100;
+/// Upper bound on `get_session_output` lines
+const MAX_OUTPUT_LINES: usize = 5000;
+/// `wait_for_status` timeout when none is given
+const DEFAULT_WAIT_TIMEOUT_MS: u64 = 60_000;
+/// Upper bound on `wait_for_status` timeouts
+const MAX_WAIT_TIMEOUT_MS: u64 = 600_000;
+/// How often `wait_for_status` re-checks the session
+const WAIT_POLL_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Serve MCP on stdin/stdout until stdin closes
+pub async fn serve(client: TmuxClient) -> Result<()> {
+    let server = Server::new(client);
+    let status_file = status_dir().join(format!("{}.json", std::process::id()));
+    let mut status = ServerStatus {
+        pid: std::process::id(),
+        started_at: text::unix_now(),
+        calls: 0,
+    };
+    if let Err(e) = status.save(&status_file).await {
+        tracing::warn!("Failed to write {}: {}", status_file.display(), e);
+    }
+    let mut lines = BufReader::new(tokio::io::stdin()).lines();
+    let mut stdout = tokio::io::stdout();
+    while let Some(line) = lines.next_line().await? {
+        if let Some(response) = server.handle_message(&line).await {
+            stdout.write_all(response.as_bytes()).await?;
+            stdout.write_all(b"\n").await?;
+            stdout.flush().await?;
+        }
+        if server.calls() != status.calls {
+            status.calls = server.calls();
+            let _ = status.save(&status_file).await;
+        }
+    }
+    tracing::info!("MCP client closed stdin");
+    let _ = tokio::fs::remove_file(&status_file).await;
+    Ok(())
+}
+
+/// Where running servers keep their [`ServerStatus`]
+pub fn status_dir() -> PathBuf {
+    dirs::home_dir()
+        .unwrap_or_default()
+        .join(".agent-deck")
+        .join("mcp")
+}
+
+/// What a running server tells the dashboard about itself
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ServerStatus {
+    pub pid: u32,
+    /// Unix time the server started
+    pub started_at: u64,
+    /// Tool calls answered so far
+    pub calls: u64,
+}
+
+impl ServerStatus {
+    async fn save(&self, path: &Path) -> std::io::Result<()> {
+        if let Some(parent) = path.parent() {
+            tokio::fs::create_dir_all(parent).await?;
+        }
+        tokio::fs::write(path, serde_json::to_string(self)?).await
+    }
+
+    /// Servers with a status in `dir` that are still running, oldest first;
+    /// files left behind by servers that died are removed
+    pub fn running(dir: &Path) -> Vec<Self> {
+        let Ok(entries) = std::fs::read_dir(dir) else {
+            return Vec::new();
+        };
+        let mut running: Vec<Self> = entries
+            .flatten()
+            .filter_map(|entry| {
+                let path = entry.path();
+                let status: Self =
+                    serde_json::from_str(&std::fs::read_to_string(&path).ok()?).ok()?;
+                if process_alive(status.pid) {
+                    Some(status)
+                } else {
+                    let _ = std::fs::remove_file(&path);
+                    None
+                }
+            })
+            .collect();
+        running.sort_by_key(|s| (s.started_at, s.pid));
+        running
+    }
+}
+
+#[cfg(unix)]
+fn process_alive(pid: u32) -> bool {
+    // Signal 0 only checks that the process exists; another user's process
+    // exists too, but refuses the signal
+    // SAFETY: kill(2) has no memory-safety preconditions.
+    let sent = unsafe { libc::kill(pid as libc::pid_t, 0) } == 0;
+    sent || std::io::Error::last_os_error().raw_os_error() == Some(libc::EPERM)
+}
+
+/// Without kill(2) there is no telling, so every server counts as running
+#[cfg(not(unix))]
+fn process_alive(_pid: u32) -> bool {
+    true
+}
+
+/// A JSON-RPC error answer
+struct RpcError {
+    code: i64,
+    message: String,
+}
+
+impl RpcError {
+    fn new(code: i64, message: impl Into<String>) -> Self {
+        Self {
+            code,
+            message: message.into(),
+        }
+    }
+}
+
+/// Protocol layer over a [`Sessions`] backend
+pub struct Server<S> {
+    sessions: S,
+    wait_poll_interval: Duration,
+    calls: AtomicU64,
+}
+
+impl<S: Sessions> Server<S> {
+    pub fn new(sessions: S) -> Self {
+        Self {
+            sessions,
+            wait_poll_interval: WAIT_POLL_INTERVAL,
+            calls: AtomicU64::new(0),
+        }
+    }
+
+    /// Tool calls answered so far, failed ones included
+    pub fn calls(&self) -> u64 {
+        self.calls.load(Ordering::Relaxed)
+    }
+
+    /// Handle one line from the client; `None` when no reply is due
+    pub async fn handle_message(&self, line: &str) -> Option<String> {
+        if line.trim().is_empty() {
+            return None;
+        }
+        let message: Value = match serde_json::from_str(line) {
+            Ok(message) => message,
+            Err(e) => {
+                return Some(error_response(
+                    Value::Null,
+                    RpcError::new(PARSE_ERROR, format!("Parse error: {}", e)),
+                ));
+            }
+        };
+
+        let id = message.get("id").cloned();
+        let Some(method) = message.get("method").and_then(Value::as_str) else {
+            return Some(error_response(
+                id.unwrap_or(Value::Null),
+                RpcError::new(INVALID_REQUEST, "Invalid request: missing method"),
+            ));
+        };
+        let params = message.get("params").cloned().unwrap_or(Value::Null);
+        tracing::debug!(method, "MCP request");
+
+        let result = self.dispatch(method, &params).await;
+        // Notifications carry no id and never get a reply
+        let id = id?;
+        Some(match result {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": id, "result": result }).to_string(),
+            Err(error) => error_response(id, error),
+        })
+    }
+
+    async fn dispatch(&self, method: &str, params: &Value) -> Result<Value, RpcError> {
+        match method {
+            "initialize" => Ok(json!({
+                "protocolVersion": PROTOCOL_VERSION,
+                "capabilities": { "tools": {} },
+                "serverInfo": { "name": "agent-rusty", "version": env!("CARGO_PKG_VERSION") },
+            })),
+            "ping" => Ok(json!({})),
+            "tools/list" => Ok(json!({ "tools": tool_definitions() })),
+            "tools/call" => {
+                let name = params
+                    .get("name")
+                    .and_then(Value::as_str)
+                    .ok_or_else(|| RpcError::new(INVALID_PARAMS, "Missing tool name"))?;
+                let args = params.get("arguments").cloned().unwrap_or(json!({}));
+                let outcome = match name {
+                    "list_sessions" => self.list_sessions().await,
+                    "get_session_output" => self.get_session_output(&args).await,
+                    "send_prompt" => self.send_prompt(&args).await,
+                    "send_text" => self.send_text(&args).await,
+                    "create_session" => self.create_session(&args).await,
+                    "kill_session" => self.kill_session(&args).await,
+                    "wait_for_status" => self.wait_for_status(&args).await,
+                    _ => {
+                        return Err(RpcError::new(
+                            INVALID_PARAMS,
+                            format!("Unknown tool: {}", name),
+                        ));
+                    }
+                };
+                self.calls.fetch_add(1, Ordering::Relaxed);
+                Ok(match outcome {
+                    Ok(text) => tool_result(text, false),
+                    Err(e) => tool_result(format!("{:#}", e), true),
+                })
+            }
+            method if method.starts_with("notifications/") => Ok(Value::Null),
+            _ => Err(RpcError::new(
+                METHOD_NOT_FOUND,
+                format!("Method not found: {}", method),
+            )),
+        }
+    }
+
+    async fn find(&self, args: &Value) -> Result<TmuxSession> {
+        let name = string_arg(args, "name")?;
+        let sessions = self.sessions.list_sessions().await?;
+        find_session(&sessions, name)
+            .cloned()
+            .ok_or_else(|| anyhow::anyhow!("No session named {}", name))
+    }
+
+    async fn list_sessions(&self) -> Result<String> {
+        let sessions = self.sessions.list_sessions().await?;
+        let summaries: Vec<Value> = sessions.iter().map(summary).collect();
+        Ok(serde_json::to_string_pretty(&summaries)?)
+    }
+
+    async fn get_session_output(&self, args: &Value) -> Result<String> {
+        let session = self.find(args).await?;
+        let lines = args
+            .get("lines")
+            .and_then(Value::as_u64)
+            .map(|n| n as usize)
+            .unwrap_or(DEFAULT_OUTPUT_LINES)
+            .min(MAX_OUTPUT_LINES);
+        let content = self.sessions.capture_pane(&session.id, lines).await?;
+        Ok(last_lines(&content, lines))
+    }
+
+    async fn send_prompt(&self, args: &Value) -> Result<String> {
+        let session = self.find(args).await?;
+        let text = string_arg(args, "text")?;
+        self.sessions.send_keys(&session.id, text).await?;
+        Ok(format!("Sent prompt to {}", session.name))
+    }
+
+    async fn send_text(&self, args: &Value) -> Result<String> {
+        let session = self.find(args).await?;
+        let text = string_arg(args, "text")?;
+        if args.get("enter").and_then(Value::as_bool).unwrap_or(false) {
+            self.sessions.send_keys(&session.id, text).await?;
+            return Ok(format!("Typed into {} and pressed Enter", session.name));
+        }
+        self.sessions.send_text(&session.id, text).await?;
+        Ok(format!("Typed into {}", session.name))
+    }
+
+    async fn create_session(&self, args: &Value) -> Result<String> {
+        let name = string_arg(args, "name")?;
+        let dir = args.get("dir").and_then(Value::as_str).map(Path::new);
+        let command = args.get("command").and_then(Value::as_str);
+        let session = self.sessions.create_session(name, dir, command).await?;
+        Ok(serde_json::to_string_pretty(&summary(&session))?)
+    }
+
+    async fn kill_session(&self, args: &Value) -> Result<String> {
+        let session = self.find(args).await?;
+        self.sessions.kill_session(&session.id).await?;
+        Ok(format!("Killed {}", session.name))
+    }
+
+    async fn wait_for_status(&self, args: &Value) -> Result<String> {
+        let targets = match args.get("statuses") {
+            Some(value) => serde_json::from_value::<Vec<AgentStatus>>(value.clone())
+                .map_err(|e| anyhow::anyhow!("Invalid statuses: {}", e))?,
+            None => vec![AgentStatus::Idle, AgentStatus::WaitingForInput],
+        };
+        let timeout = Duration::from_millis(
+            args.get("timeout_ms")
+                .and_then(Value::as_u64)
+                .unwrap_or(DEFAULT_WAIT_TIMEOUT_MS)
+                .min(MAX_WAIT_TIMEOUT_MS),
+        );
+        let name = string_arg(args, "name")?;
+        let outcome = wait::wait_for_status(
+            &self.sessions,
+            name,
+            &targets,
+            Some(timeout),
+            self.wait_poll_interval,
+        )
+        .await?;
+        match outcome {
+            WaitOutcome::Reached(session) => {
+                Ok(format!("{} is {:?}", session.name, session.status))
+            }
+            WaitOutcome::TimedOut(session) => anyhow::bail!(
+                "Timed out after {} ms; {} is {:?}",
+                timeout.as_millis(),
+                session.name,
+                session.status
+            ),
+            WaitOutcome::Gone => anyhow::bail!("No session named {}", name),
+        }
+    }
+}
+
+fn string_arg<'a>(args: &'a Value, key: &str) -> Result<&'a str> {
+    args.get(key)
+        .and_then(Value::as_str)
+        .ok_or_else(|| anyhow::anyhow!("Missing string argument '{}'", key))
+}
+
+/// What `list_sessions` reports per session
+fn summary(session: &TmuxSession) -> Value {
+    json!({
+        "id": session.id,
+        "name": session.name,
+        "status": session.status,
+        "agent": session.agent_kind().label(),
+        "path": session.current_path,
+    })
+}
+
+/// The last `n` lines of `content`, ignoring trailing blank lines
+fn last_lines(content: &str, n: usize) -> String {
+    let lines: Vec<&str> = content.trim_end().lines().collect();
+    lines[lines.len().saturating_sub(n)..].join("\n")
+}
+
+fn tool_result(text: String, is_error: bool) -> Value {
+    json!({
+        "content": [{ "type": "text", "text": text }],
+        "isError": is_error,
+    })
+}
+
+fn error_response(id: Value, error: RpcError) -> String {
+    json!({
+        "jsonrpc": "2.0",
+        "id": id,
+        "error": { "code": error.code, "message": error.message },
+    })
+    .to_string()
+}
+
+fn tool_definitions() -> Value {
+    let name = json!({ "type": "string", "description": "Session name or tmux session ID" });
+    json!([
+        {
+            "name": "list_sessions",
+            "description": "List tmux sessions with their agent status and agent kind",
+            "inputSchema": { "type": "object", "properties": {} },
+        },
+        {
+            "name": "get_session_output",
+            "description": "Capture the recent output of a session's active pane",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": name,
+                    "lines": { "type": "integer", "minimum": 1, "description": "Lines to return (default 100)" },
+                },
+                "required": ["name"],
+            },
+        },
+        {
+            "name": "send_prompt",
+            "description": "Type text into a session and press Enter",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "name": name, "text": { "type": "string" } },
+                "required": ["name", "text"],
+            },
+        },
+        {
+            "name": "send_text",
+            "description": "Type text into a session without pressing Enter, e.g. to answer a prompt a key at a time",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": name,
+                    "text": { "type": "string" },
+                    "enter": { "type": "boolean", "description": "Press Enter afterwards (default false)" },
+                },
+                "required": ["name", "text"],
+            },
+        },
+        {
+            "name": "create_session",
+            "description": "Create a detached session, optionally running a command",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": { "type": "string" },
+                    "dir": { "type": "string", "description": "Starting directory" },
+                    "command": { "type": "string", "description": "Command to run instead of a shell" },
+                },
+                "required": ["name"],
+            },
+        },
+        {
+            "name": "kill_session",
+            "description": "Kill a session",
+            "inputSchema": {
+                "type": "object",
+                "properties": { "name": name },
+                "required": ["name"],
+            },
+        },
+        {
+            "name": "wait_for_status",
+            "description": "Block until a session reaches one of the given statuses (default Idle or WaitingForInput)",
+            "inputSchema": {
+                "type": "object",
+                "properties": {
+                    "name": name,
+                    "statuses": {
+                        "type": "array",
+                        "items": { "enum": ["Busy", "Idle", "WaitingForInput", "Error", "Unknown"] },
+                    },
+                    "timeout_ms": { "type": "integer", "minimum": 0, "description": "Default 60000" },
+                },
+                "required": ["name"],
+            },
+        },
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tmux::fake::FakeSessions;
+
+    /// Replay `>` request lines and compare replies with the following `<` lines
+    async fn replay<S: Sessions>(server: &Server<S>, transcript: &str) {
+        let transcript = transcript.replace("{version}", env!("CARGO_PKG_VERSION"));
+        let mut pending: Option<String> = None;
+        for line in transcript.lines().map(str::trim).filter(|l| !l.is_empty()) {
+            if let Some(request) = line.strip_prefix("> ") {
+                assert!(pending.is_none(), "unexpected reply: {:?}", pending);
+                pending = server.handle_message(request).await;
+            } else if let Some(expected) = line.strip_prefix("< ") {
+                let reply = pending.take().expect("expected a reply");
+                let reply: Value = serde_json::from_str(&reply).unwrap();
+                let expected: Value = serde_json::from_str(expected).unwrap();
+                assert_eq!(reply, expected);
+            } else {
+                panic!("bad transcript line: {}", line);
+            }
+        }
+        assert!(pending.is_none(), "unexpected reply: {:?}", pending);
+    }
+
+    #[tokio::test]
+    async fn test_handshake() {
+        let server = Server::new(FakeSessions::default());
+        replay(
+            &server,
+            r#"
+            > {"jsonrpc":"2.0","id":1,"method":"initialize","params":{"protocolVersion":"2024-11-05","capabilities":{},"clientInfo":{"name":"test","version":"0"}}}
+            < {"jsonrpc":"2.0","id":1,"result":{"protocolVersion":"2024-11-05","capabilities":{"tools":{}},"serverInfo":{"name":"agent-rusty","version":"{version}"}}}
+            > {"jsonrpc":"2.0","method":"notifications/initialized"}
+            > {"jsonrpc":"2.0","id":2,"method":"ping"}
+            < {"jsonrpc":"2.0","id":2,"result":{}}
+            "#,
+        )
+        .await;
+    }
+
+    #[tokio::test]
+    async fn test_tools_list() {
+        let server = Server::new(FakeSessions::default());
+        let reply = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":"a","method":"tools/list"}"#)
+            .await
+            .unwrap();
+        let reply: Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(reply["id"], "a");
+        let names: Vec<&str> = reply["result"]["tools"]
+            .as_array()
+            .unwrap()
+            .iter()
+            .map(|t| t["name"].as_str().unwrap())
+            .collect();
+        assert_eq!(
+            names,
+            [
+                "list_sessions",
+                "get_session_output",
+                "send_prompt",
+                "send_text",
+                "create_session",
+                "kill_session",
+                "wait_for_status"
+            ]
+        );
+        for tool in reply["result"]["tools"].as_array().unwrap() {
+            assert_eq!(tool["inputSchema"]["type"], "object");
+        }
+    }
+
+    #[tokio::test]
+    async fn test_protocol_errors() {
+        let server = Server::new(FakeSessions::default());
+        replay(
+            &server,
+            r#"
+            > {"jsonrpc":"2.0","id":1,"method":"resources/list"}
+            < {"jsonrpc":"2.0","id":1,"error":{"code":-32601,"message":"Method not found: resources/list"}}
+            > {"jsonrpc":"2.0","id":2}
+            < {"jsonrpc":"2.0","id":2,"error":{"code":-32600,"message":"Invalid request: missing method"}}
+            > {"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"format_disk"}}
+            < {"jsonrpc":"2.0","id":3,"error":{"code":-32602,"message":"Unknown tool: format_disk"}}
+            > {"jsonrpc":"2.0","id":4,"method":"tools/call","params":{}}
+            < {"jsonrpc":"2.0","id":4,"error":{"code":-32602,"message":"Missing tool name"}}
+            > {"jsonrpc":"2.0","method":"nonsense"}
+            "#,
+        )
+        .await;
+
+        let reply = server.handle_message("{not json").await.unwrap();
+        let reply: Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(reply["id"], Value::Null);
+        assert_eq!(reply["error"]["code"], PARSE_ERROR);
+        assert_eq!(server.handle_message("   ").await, None);
+    }
+
+    #[tokio::test]
+    async fn test_session_tools() {
+        let server = Server::new(FakeSessions::with(&["api"]));
+        replay(
+            &server,
+            r#"
+            > {"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"list_sessions","arguments":{}}}
+            < {"jsonrpc":"2.0","id":1,"result":{"content":[{"type":"text","text":"[\n  {\n    \"agent\": \"claude\",\n    \"id\": \"$0\",\n    \"name\": \"api\",\n    \"path\": \"/work/api\",\n    \"status\": \"Idle\"\n  }\n]"}],"isError":false}}
+            > {"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"get_session_output","arguments":{"name":"api","lines":2}}}
+            < {"jsonrpc":"2.0","id":2,"result":{"content":[{"type":"text","text":"two\nthree from $0"}],"isError":false}}
+            > {"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"send_prompt","arguments":{"name":"api","text":"run the tests"}}}
+            < {"jsonrpc":"2.0","id":3,"result":{"content":[{"type":"text","text":"Sent prompt to api"}],"isError":false}}
+            > {"jsonrpc":"2.0","id":4,"method":"tools/call","params":{"name":"send_prompt","arguments":{"name":"web","text":"hi"}}}
+            < {"jsonrpc":"2.0","id":4,"result":{"content":[{"type":"text","text":"No session named web"}],"isError":true}}
+            > {"jsonrpc":"2.0","id":5,"method":"tools/call","params":{"name":"send_prompt","arguments":{"name":"api"}}}
+            < {"jsonrpc":"2.0","id":5,"result":{"content":[{"type":"text","text":"Missing string argument 'text'"}],"isError":true}}
+            > {"jsonrpc":"2.0","id":6,"method":"tools/call","params":{"name":"send_text","arguments":{"name":"api","text":"y"}}}
+            < {"jsonrpc":"2.0","id":6,"result":{"content":[{"type":"text","text":"Typed into api"}],"isError":false}}
+            > {"jsonrpc":"2.0","id":7,"method":"tools/call","params":{"name":"send_text","arguments":{"name":"api","text":"/clear","enter":true}}}
+            < {"jsonrpc":"2.0","id":7,"result":{"content":[{"type":"text","text":"Typed into api and pressed Enter"}],"isError":false}}
+            "#,
+        )
+        .await;
+        assert_eq!(
+            *server.sessions.sent.lock().unwrap(),
+            [
+                ("$0".to_string(), "run the tests".to_string()),
+                ("$0".to_string(), "/clear".to_string())
+            ]
+        );
+        assert_eq!(
+            *server.sessions.typed.lock().unwrap(),
+            [("$0".to_string(), "y".to_string())]
+        );
+        // Failed calls count as served; protocol errors don't
+        assert_eq!(server.calls(), 7);
+        server
+            .handle_message(r#"{"jsonrpc":"2.0","id":8,"method":"tools/list"}"#)
+            .await;
+        assert_eq!(server.calls(), 7);
+    }
+
+    #[tokio::test]
+    async fn test_running_servers() {
+        let dir = std::env::temp_dir().join(format!("agent-rusty-mcp-{}", std::process::id()));
+        assert!(ServerStatus::running(&dir).is_empty());
+        let alive = ServerStatus {
+            pid: std::process::id(),
+            started_at: 100,
+            calls: 3,
+        };
+        alive.save(&dir.join("alive.json")).await.unwrap();
+        // Beyond any pid the kernel hands out
+        let dead = ServerStatus {
+            pid: 999_999_999,
+            started_at: 50,
+            calls: 1,
+        };
+        dead.save(&dir.join("dead.json")).await.unwrap();
+        std::fs::write(dir.join("junk.json"), "{").unwrap();
+
+        assert_eq!(ServerStatus::running(&dir), [alive]);
+        assert!(!dir.join("dead.json").exists());
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // init runs as root: alive whether or not we may signal it
+        #[cfg(unix)]
+        assert!(process_alive(1));
+    }
+
+    #[tokio::test]
+    async fn test_create_and_kill() {
+        let server = Server::new(FakeSessions::default());
+        replay(
+            &server,
+            r#"
+            > {"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"create_session","arguments":{"name":"web","dir":"/work/web","command":"aider"}}}
+            < {"jsonrpc":"2.0","id":1,"result":{"content":[{"type":"text","text":"{\n  \"agent\": \"aider\",\n  \"id\": \"$0\",\n  \"name\": \"web\",\n  \"path\": \"/work/web\",\n  \"status\": \"Unknown\"\n}"}],"isError":false}}
+            > {"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"create_session","arguments":{"name":"web"}}}
+            < {"jsonrpc":"2.0","id":2,"result":{"content":[{"type":"text","text":"duplicate session: web"}],"isError":true}}
+            > {"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"kill_session","arguments":{"name":"web"}}}
+            < {"jsonrpc":"2.0","id":3,"result":{"content":[{"type":"text","text":"Killed web"}],"isError":false}}
+            "#,
+        )
+        .await;
+        assert!(server.sessions.sessions.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_wait_for_status() {
+        let mut server = Server::new(FakeSessions::with(&["api"]));
+        server.wait_poll_interval = Duration::from_millis(1);
+        *server.sessions.statuses.lock().unwrap() = vec![
+            AgentStatus::Busy,
+            AgentStatus::Busy,
+            AgentStatus::WaitingForInput,
+        ];
+        replay(
+            &server,
+            r#"
+            > {"jsonrpc":"2.0","id":1,"method":"tools/call","params":{"name":"wait_for_status","arguments":{"name":"api"}}}
+            < {"jsonrpc":"2.0","id":1,"result":{"content":[{"type":"text","text":"api is WaitingForInput"}],"isError":false}}
+            > {"jsonrpc":"2.0","id":2,"method":"tools/call","params":{"name":"wait_for_status","arguments":{"name":"api","statuses":["Error"],"timeout_ms":0}}}
+            < {"jsonrpc":"2.0","id":2,"result":{"content":[{"type":"text","text":"Timed out after 0 ms; api is WaitingForInput"}],"isError":true}}
+            "#,
+        )
+        .await;
+
+        let reply = server
+            .handle_message(r#"{"jsonrpc":"2.0","id":3,"method":"tools/call","params":{"name":"wait_for_status","arguments":{"name":"api","statuses":["Sleeping"]}}}"#)
+            .await
+            .unwrap();
+        let reply: Value = serde_json::from_str(&reply).unwrap();
+        assert_eq!(reply["result"]["isError"], true);
+        assert!(
+            reply["result"]["content"][0]["text"]
+                .as_str()
+                .unwrap()
+                .starts_with("Invalid statuses")
+        );
+    }
+
+    #[test]
+    fn test_last_lines() {
+        assert_eq!(last_lines("a\nb\nc\n\n", 2), "b\nc");
+        assert_eq!(last_lines("a\nb", 10), "a\nb");
+        assert_eq!(last_lines("", 5), "");
+    }
+}
@@ -0,0 +1,378 @@
+//! Poller metrics in the Prometheus text format.
+//!
+//! With `--metrics-file` (or `metrics_file` in the config) the poller rewrites
+//! the file after every poll, which suits node_exporter's textfile collector.
+//! Nothing is recorded otherwise.
+
+use std::collections::{BTreeMap, HashMap};
+use std::fmt::Write as _;
+use std::path::Path;
+use std::time::{Duration, Instant};
+
+use crate::poller::Transition;
+use crate::tmux::{AgentStatus, TmuxSession};
+
+/// Upper bounds of the poll duration histogram, in seconds
+const POLL_BUCKETS: [f64; 10] = [0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0];
+
+const STATUSES: [AgentStatus; 5] = [
+    AgentStatus::Busy,
+    AgentStatus::Idle,
+    AgentStatus::WaitingForInput,
+    AgentStatus::Error,
+    AgentStatus::Unknown,
+];
+
+/// Everything the poller has seen since startup
+#[derive(Debug)]
+pub struct Metrics {
+    /// Most sessions given their own `session` label
+    max_sessions: usize,
+    /// Sessions of the latest poll: name, status, and when that status began
+    sessions: Vec<(String, AgentStatus, Instant)>,
+    /// When each session (by ID) entered its current status
+    status_since: HashMap<String, (AgentStatus, Instant)>,
+    transitions: BTreeMap<(&'static str, &'static str), u64>,
+    /// Per-bucket counts of poll durations, not cumulative
+    poll_buckets: [u64; POLL_BUCKETS.len()],
+    poll_count: u64,
+    poll_sum: f64,
+    tmux_errors: u64,
+}
+
+impl Metrics {
+    pub fn new(max_sessions: usize) -> Self {
+        Self {
+            max_sessions,
+            sessions: Vec::new(),
+            status_since: HashMap::new(),
+            transitions: BTreeMap::new(),
+            poll_buckets: [0; POLL_BUCKETS.len()],
+            poll_count: 0,
+            poll_sum: 0.0,
+            tmux_errors: 0,
+        }
+    }
+
+    /// Record a successful poll that took `elapsed`
+    pub fn observe_poll(
+        &mut self,
+        elapsed: Duration,
+        sessions: &[TmuxSession],
+        transitions: &[Transition],
+        now: Instant,
+    ) {
+        let seconds = elapsed.as_secs_f64();
+        if let Some(bucket) = POLL_BUCKETS.iter().position(|&le| seconds <= le) {
+            self.poll_buckets[bucket] += 1;
+        }
+        self.poll_count += 1;
+        self.poll_sum += seconds;
+
+        for transition in transitions {
+            *self
+                .transitions
+                .entry((label(transition.previous), label(transition.status)))
+                .or_default() += 1;
+        }
+
+        let mut status_since = HashMap::with_capacity(sessions.len());
+        self.sessions.clear();
+        for session in sessions {
+            let since = match self.status_since.get(&session.id) {
+                Some(&(status, since)) if status == session.status => since,
+                _ => now,
+            };
+            status_since.insert(session.id.clone(), (session.status, since));
+            self.sessions
+                .push((session.name.clone(), session.status, since));
+        }
+        self.status_since = status_since;
+    }
+
+    /// Record a poll that failed because tmux did
+    pub fn record_error(&mut self) {
+        self.tmux_errors += 1;
+    }
+
+    /// Render in the Prometheus text exposition format
+    pub fn render(&self, now: Instant) -> String {
+        let mut out = String::new();
+
+        header(
+            &mut out,
+            "agent_rusty_sessions",
+            "gauge",
+            "Sessions by detected status",
+        );
+        for status in STATUSES {
+            let count = self.sessions.iter().filter(|s| s.1 == status).count();
+            let _ = writeln!(
+                out,
+                "agent_rusty_sessions{{status=\"{}\"}} {}",
+                label(status),
+                count
+            );
+        }
+
+        header(
+            &mut out,
+            "agent_rusty_status_transitions_total",
+            "counter",
+            "Status changes seen by the poller",
+        );
+        for ((from, to), count) in &self.transitions {
+            let _ = writeln!(
+                out,
+                "agent_rusty_status_transitions_total{{from=\"{}\",to=\"{}\"}} {}",
+                from, to, count
+            );
+        }
+
+        header(
+            &mut out,
+            "agent_rusty_poll_duration_seconds",
+            "histogram",
+            "Time taken to poll tmux",
+        );
+        let mut cumulative = 0;
+        for (le, count) in POLL_BUCKETS.iter().zip(self.poll_buckets) {
+            cumulative += count;
+            let _ = writeln!(
+                out,
+                "agent_rusty_poll_duration_seconds_bucket{{le=\"{}\"}} {}",
+                le, cumulative
+            );
+        }
+        let _ = writeln!(
+            out,
+            "agent_rusty_poll_duration_seconds_bucket{{le=\"+Inf\"}} {}",
+            self.poll_count
+        );
+        let _ = writeln!(
+            out,
+            "agent_rusty_poll_duration_seconds_sum {}",
+            self.poll_sum
+        );
+        let _ = writeln!(
+            out,
+            "agent_rusty_poll_duration_seconds_count {}",
+            self.poll_count
+        );
+
+        header(
+            &mut out,
+            "agent_rusty_tmux_errors_total",
+            "counter",
+            "Polls that failed because tmux did",
+        );
+        let _ = writeln!(out, "agent_rusty_tmux_errors_total {}", self.tmux_errors);
+
+        header(
+            &mut out,
+            "agent_rusty_session_status_seconds",
+            "gauge",
+            "Time each session has spent in its current status",
+        );
+        let mut sessions: Vec<_> = self.sessions.iter().collect();
+        sessions.sort_by(|a, b| a.0.cmp(&b.0));
+        for (name, status, since) in sessions.into_iter().take(self.max_sessions) {
+            let _ = writeln!(
+                out,
+                "agent_rusty_session_status_seconds{{session=\"{}\",status=\"{}\"}} {:.3}",
+                escape(name),
+                label(*status),
+                now.saturating_duration_since(*since).as_secs_f64()
+            );
+        }
+        out
+    }
+
+    /// Replace `path` with the current metrics, so scrapers never see half a file
+    pub fn write_to(&self, path: &Path) -> std::io::Result<()> {
+        let partial = path.with_extension("prom.tmp");
+        std::fs::write(&partial, self.render(Instant::now()))?;
+        std::fs::rename(&partial, path)
+    }
+}
+
+fn header(out: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(out, "# HELP {} {}", name, help);
+    let _ = writeln!(out, "# TYPE {} {}", name, kind);
+}
+
+/// Status as a label value, spelled as on the command line and in hook triggers
+pub fn label(status: AgentStatus) -> &'static str {
+    match status {
+        AgentStatus::Busy => "busy",
+        AgentStatus::Idle => "idle",
+        AgentStatus::WaitingForInput => "waiting-for-input",
+        AgentStatus::Error => "error",
+        AgentStatus::Unknown => "unknown",
+    }
+}
+
+/// Escape a label value: backslash, double quote and newline
+fn escape(value: &str) -> String {
+    value
+        .replace('\\', "\\\\")
+        .replace('"', "\\\"")
+        .replace('\n', "\\n")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(id: &str, name: &str, status: AgentStatus) -> TmuxSession {
+        let mut session = TmuxSession::new(id.to_string(), name.to_string());
+        session.status = status;
+        session
+    }
+
+    fn transition(previous: AgentStatus, status: AgentStatus) -> Transition {
+        Transition {
+            session_id: "$0".to_string(),
+            session: "api".to_string(),
+            previous,
+            status,
+        }
+    }
+
+    /// Lines of `text` that start with `prefix`
+    fn lines<'a>(text: &'a str, prefix: &str) -> Vec<&'a str> {
+        text.lines().filter(|l| l.starts_with(prefix)).collect()
+    }
+
+    #[test]
+    fn test_empty_exposition() {
+        let text = Metrics::new(10).render(Instant::now());
+        assert!(text.contains(
+            "# HELP agent_rusty_sessions Sessions by detected status\n# TYPE agent_rusty_sessions gauge\n"
+        ));
+        assert!(text.contains("agent_rusty_sessions{status=\"waiting-for-input\"} 0\n"));
+        assert!(text.contains("agent_rusty_poll_duration_seconds_bucket{le=\"+Inf\"} 0\n"));
+        assert!(text.contains("agent_rusty_tmux_errors_total 0\n"));
+        // Every family is declared, even without samples
+        assert_eq!(lines(&text, "# TYPE").len(), 5);
+        assert!(lines(&text, "agent_rusty_session_status_seconds{").is_empty());
+    }
+
+    #[test]
+    fn test_sessions_and_transitions() {
+        let start = Instant::now();
+        let mut metrics = Metrics::new(10);
+        metrics.observe_poll(
+            Duration::from_millis(20),
+            &[
+                session("$0", "api", AgentStatus::Busy),
+                session("$1", "web", AgentStatus::Busy),
+            ],
+            &[],
+            start,
+        );
+        metrics.observe_poll(
+            Duration::from_millis(30),
+            &[
+                session("$0", "api", AgentStatus::WaitingForInput),
+                session("$1", "web", AgentStatus::Busy),
+            ],
+            &[transition(AgentStatus::Busy, AgentStatus::WaitingForInput)],
+            start + Duration::from_secs(5),
+        );
+        metrics.record_error();
+
+        let text = metrics.render(start + Duration::from_secs(8));
+        assert_eq!(
+            lines(&text, "agent_rusty_sessions{"),
+            [
+                "agent_rusty_sessions{status=\"busy\"} 1",
+                "agent_rusty_sessions{status=\"idle\"} 0",
+                "agent_rusty_sessions{status=\"waiting-for-input\"} 1",
+                "agent_rusty_sessions{status=\"error\"} 0",
+                "agent_rusty_sessions{status=\"unknown\"} 0",
+            ]
+        );
+        assert_eq!(
+            lines(&text, "agent_rusty_status_transitions_total{"),
+            ["agent_rusty_status_transitions_total{from=\"busy\",to=\"waiting-for-input\"} 1"]
+        );
+        // web has been busy since the first poll, api waiting since the second
+        assert_eq!(
+            lines(&text, "agent_rusty_session_status_seconds{"),
+            [
+                "agent_rusty_session_status_seconds{session=\"api\",status=\"waiting-for-input\"} 3.000",
+                "agent_rusty_session_status_seconds{session=\"web\",status=\"busy\"} 8.000",
+            ]
+        );
+        assert!(text.contains("agent_rusty_tmux_errors_total 1\n"));
+    }
+
+    #[test]
+    fn test_poll_histogram() {
+        let mut metrics = Metrics::new(10);
+        for ms in [5, 40, 40, 3000, 60_000] {
+            metrics.observe_poll(Duration::from_millis(ms), &[], &[], Instant::now());
+        }
+        let text = metrics.render(Instant::now());
+        let buckets = lines(&text, "agent_rusty_poll_duration_seconds_bucket");
+        assert_eq!(buckets.len(), POLL_BUCKETS.len() + 1);
+        assert_eq!(
+            buckets[0],
+            "agent_rusty_poll_duration_seconds_bucket{le=\"0.01\"} 1"
+        );
+        assert_eq!(
+            buckets[2],
+            "agent_rusty_poll_duration_seconds_bucket{le=\"0.05\"} 3"
+        );
+        assert_eq!(
+            buckets[8],
+            "agent_rusty_poll_duration_seconds_bucket{le=\"5\"} 4"
+        );
+        // A poll slower than every bucket only counts towards +Inf
+        assert_eq!(
+            buckets[9],
+            "agent_rusty_poll_duration_seconds_bucket{le=\"10\"} 4"
+        );
+        assert_eq!(
+            buckets[10],
+            "agent_rusty_poll_duration_seconds_bucket{le=\"+Inf\"} 5"
+        );
+        assert!(text.contains("agent_rusty_poll_duration_seconds_sum 63.085\n"));
+        assert!(text.contains("agent_rusty_poll_duration_seconds_count 5\n"));
+    }
+
+    #[test]
+    fn test_session_label_cap_and_escaping() {
+        let mut metrics = Metrics::new(2);
+        metrics.observe_poll(
+            Duration::ZERO,
+            &[
+                session("$0", "c", AgentStatus::Idle),
+                session("$1", "a \"quoted\"\\name", AgentStatus::Idle),
+                session("$2", "b", AgentStatus::Idle),
+            ],
+            &[],
+            Instant::now(),
+        );
+        let text = metrics.render(Instant::now());
+        let per_session = lines(&text, "agent_rusty_session_status_seconds{");
+        assert_eq!(per_session.len(), 2);
+        assert!(per_session[0].starts_with(
+            "agent_rusty_session_status_seconds{session=\"a \\\"quoted\\\"\\\\name\",status=\"idle\"}"
+        ));
+        assert!(per_session[1].contains("session=\"b\""));
+        // Status totals still count every session
+        assert!(text.contains("agent_rusty_sessions{status=\"idle\"} 3\n"));
+    }
+
+    #[test]
+    fn test_write_to() {
+        let path = std::env::temp_dir().join(format!("agent-rusty-{}.prom", std::process::id()));
+        Metrics::new(10).write_to(&path).unwrap();
+        let text = std::fs::read_to_string(&path).unwrap();
+        assert!(text.starts_with("# HELP agent_rusty_sessions"));
+        assert!(!path.with_extension("prom.tmp").exists());
+        std::fs::remove_file(&path).unwrap();
+    }
+}
@@ -0,0 +1,157 @@
+//! Messages for the footer, each with a level that picks its color and how
+//! long it stays.
+//!
+//! The newest one is shown. Errors outlive the others, so an error followed
+//! by a quick "copied" shows again once that one has expired. Sticky ones,
+//! such as a failed delete, never expire and stay until dismissed.
+
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+/// Notifications kept; older ones drop off
+pub const NOTIFICATIONS_LEN: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Level {
+    Info,
+    Success,
+    Warning,
+    Error,
+}
+
+impl Level {
+    /// How long a notification of this level stays
+    pub fn ttl(self) -> Duration {
+        match self {
+            Level::Info | Level::Success => Duration::from_secs(4),
+            Level::Warning => Duration::from_secs(8),
+            Level::Error => Duration::from_secs(15),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Notification {
+    pub level: Level,
+    pub text: String,
+    pub created: Instant,
+    /// Stays until dismissed instead of expiring or going with the next key
+    pub sticky: bool,
+}
+
+/// Recent notifications, oldest first
+#[derive(Debug, Default)]
+pub struct Notifications {
+    queue: VecDeque<Notification>,
+}
+
+impl Notifications {
+    pub fn push(&mut self, level: Level, text: impl Into<String>) {
+        self.push_at(level, text, Instant::now());
+    }
+
+    /// A repeat of the newest notification only renews it, so an error
+    /// reported on every poll doesn't crowd out the others
+    pub fn push_at(&mut self, level: Level, text: impl Into<String>, now: Instant) {
+        let text = text.into();
+        if let Some(latest) = self.queue.back_mut()
+            && latest.level == level
+            && latest.text == text
+        {
+            latest.created = now;
+            return;
+        }
+        if self.queue.len() == NOTIFICATIONS_LEN {
+            self.queue.pop_front();
+        }
+        self.queue.push_back(Notification {
+            level,
+            text,
+            created: now,
+            sticky: false,
+        });
+    }
+
+    /// Make the newest notification stay until dismissed
+    pub fn pin_latest(&mut self) {
+        if let Some(latest) = self.queue.back_mut() {
+            latest.sticky = true;
+        }
+    }
+
+    /// The notification to show
+    pub fn latest(&self) -> Option<&Notification> {
+        self.queue.back()
+    }
+
+    pub fn clear(&mut self) {
+        self.queue.clear();
+    }
+
+    /// Drop all but the sticky notifications
+    pub fn clear_transient(&mut self) {
+        self.queue.retain(|n| n.sticky);
+    }
+
+    /// Drop notifications that have outlived their level; `true` if any did
+    pub fn expire(&mut self, now: Instant) -> bool {
+        let before = self.queue.len();
+        self.queue
+            .retain(|n| n.sticky || now.saturating_duration_since(n.created) < n.level.ttl());
+        self.queue.len() != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_errors_outlive_later_messages() {
+        let mut notifications = Notifications::default();
+        let start = Instant::now();
+        notifications.push_at(Level::Error, "Tmux: no server", start);
+        notifications.push_at(Level::Success, "Text copied", start);
+        assert_eq!(notifications.latest().unwrap().text, "Text copied");
+
+        assert!(!notifications.expire(start + Duration::from_secs(1)));
+        assert!(notifications.expire(start + Level::Success.ttl()));
+        assert_eq!(notifications.latest().unwrap().level, Level::Error);
+        assert!(notifications.expire(start + Level::Error.ttl()));
+        assert!(notifications.latest().is_none());
+    }
+
+    #[test]
+    fn test_repeats_renew() {
+        let mut notifications = Notifications::default();
+        let start = Instant::now();
+        notifications.push_at(Level::Error, "Tmux: no server", start);
+        let later = start + Duration::from_secs(10);
+        notifications.push_at(Level::Error, "Tmux: no server", later);
+        assert!(!notifications.expire(start + Level::Error.ttl()));
+        assert_eq!(notifications.latest().unwrap().created, later);
+
+        for i in 0..NOTIFICATIONS_LEN + 2 {
+            notifications.push_at(Level::Info, format!("{}", i), later);
+        }
+        assert_eq!(notifications.queue.len(), NOTIFICATIONS_LEN);
+        assert_eq!(notifications.queue[0].text, "2");
+        notifications.clear();
+        assert!(notifications.latest().is_none());
+    }
+
+    #[test]
+    fn test_sticky_stays_until_cleared() {
+        let mut notifications = Notifications::default();
+        let start = Instant::now();
+        notifications.push_at(Level::Error, "Failed to delete api", start);
+        notifications.pin_latest();
+        notifications.push_at(Level::Info, "Theme: dark", start);
+
+        notifications.clear_transient();
+        assert_eq!(notifications.latest().unwrap().text, "Failed to delete api");
+        assert!(!notifications.expire(start + Level::Error.ttl() * 10));
+        notifications.clear();
+        assert!(notifications.latest().is_none());
+    }
+}
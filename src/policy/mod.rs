@@ -0,0 +1,247 @@
+//! Idle policies: flag sessions nobody has touched in a while, or archive
+//! their scrollback and close them.
+//!
+//! A session is dormant when its agent sits idle at the prompt or no agent
+//! runs in it at all. Its quiet time comes from tmux's activity timestamp, so
+//! it carries over restarts of the dashboard. Evaluation is pure; the
+//! dashboard runs it on a slow cadence and enforces the verdicts.
+
+use anyhow::{Context, Result};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+
+use crate::tmux::{AgentKind, AgentStatus, TmuxClient, TmuxSession};
+
+/// Scrollback lines kept in an archive
+pub const ARCHIVE_HISTORY_LINES: usize = 100_000;
+
+/// What happens to a session past the threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PolicyAction {
+    /// Mark it stale and suggest cleaning it up
+    #[default]
+    Flag,
+    /// Archive its scrollback, then close it
+    Kill,
+}
+
+/// `[idle_policy]` section of the config
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct IdlePolicy {
+    /// Hours without activity before a dormant session is stale; 0 disables the policy
+    pub after_hours: f64,
+    pub action: PolicyAction,
+    /// Where scrollback is archived before a session is closed
+    pub archive_dir: Option<PathBuf>,
+    /// Session names the policy never touches
+    pub exempt: Vec<String>,
+}
+
+/// A session the policy applies to
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Verdict {
+    pub session_id: String,
+    pub name: String,
+    /// Seconds since the session's last activity
+    pub idle_secs: u64,
+    pub action: PolicyAction,
+}
+
+impl IdlePolicy {
+    pub fn enabled(&self) -> bool {
+        self.after_hours > 0.0
+    }
+
+    pub fn threshold_secs(&self) -> u64 {
+        (self.after_hours * 3600.0) as u64
+    }
+
+    pub fn archive_dir(&self) -> PathBuf {
+        self.archive_dir.clone().unwrap_or_else(|| {
+            dirs::home_dir()
+                .unwrap_or_default()
+                .join(".agent-deck")
+                .join("archive")
+        })
+    }
+
+    fn is_exempt(&self, name: &str) -> bool {
+        self.exempt.iter().any(|n| n == name)
+    }
+}
+
+/// Idle at the prompt, or no agent running
+pub fn is_dormant(session: &TmuxSession) -> bool {
+    session.status == AgentStatus::Idle
+        || matches!(session.agent_kind(), AgentKind::Shell | AgentKind::Other)
+}
+
+/// Sessions that have been dormant and quiet for longer than the policy allows
+///
+/// Sessions whose activity tmux didn't report are left alone.
+pub fn evaluate(policy: &IdlePolicy, sessions: &[TmuxSession], now: u64) -> Vec<Verdict> {
+    if !policy.enabled() {
+        return Vec::new();
+    }
+    let threshold = policy.threshold_secs();
+    sessions
+        .iter()
+        .filter(|s| s.activity_at != 0 && is_dormant(s) && !policy.is_exempt(&s.name))
+        .filter_map(|s| {
+            let idle_secs = now.checked_sub(s.activity_at)?;
+            (idle_secs >= threshold).then(|| Verdict {
+                session_id: s.id.clone(),
+                name: s.name.clone(),
+                idle_secs,
+                action: policy.action,
+            })
+        })
+        .collect()
+}
+
+/// Archive file for `name` taken at `now`; names are made safe for a file name
+pub fn archive_path(dir: &Path, name: &str, now: u64) -> PathBuf {
+    let safe: String = name
+        .chars()
+        .map(|c| {
+            if c.is_alphanumeric() || matches!(c, '-' | '_' | '.') {
+                c
+            } else {
+                '_'
+            }
+        })
+        .collect();
+    dir.join(format!("{}-{}.log", safe, now))
+}
+
+/// Write the session's scrollback to `dir`, then close it
+///
+/// The session is only closed once the archive is on disk.
+pub async fn archive_and_kill(
+    client: &TmuxClient,
+    dir: &Path,
+    session_id: &str,
+    name: &str,
+    now: u64,
+) -> Result<PathBuf> {
+    let content = client
+        .capture_pane(session_id, ARCHIVE_HISTORY_LINES)
+        .await
+        .context("Failed to capture the scrollback")?;
+    std::fs::create_dir_all(dir).with_context(|| format!("Failed to create {}", dir.display()))?;
+    let path = archive_path(dir, name, now);
+    std::fs::write(&path, content)
+        .with_context(|| format!("Failed to write {}", path.display()))?;
+    client
+        .kill_session(session_id)
+        .await
+        .context("Failed to close the session")?;
+    Ok(path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const HOUR: u64 = 3600;
+    const NOW: u64 = 1_700_000_000;
+
+    fn session(name: &str, command: &str, status: AgentStatus, quiet_for: u64) -> TmuxSession {
+        let mut session = TmuxSession::new(format!("${}", name), name.to_string());
+        session.current_command = command.to_string();
+        session.status = status;
+        session.activity_at = NOW - quiet_for;
+        session
+    }
+
+    fn policy(after_hours: f64, action: PolicyAction) -> IdlePolicy {
+        IdlePolicy {
+            after_hours,
+            action,
+            ..IdlePolicy::default()
+        }
+    }
+
+    fn names(verdicts: &[Verdict]) -> Vec<&str> {
+        verdicts.iter().map(|v| v.name.as_str()).collect()
+    }
+
+    #[test]
+    fn test_disabled_by_default() {
+        let sessions = vec![session("old", "zsh", AgentStatus::Unknown, 100 * HOUR)];
+        assert!(evaluate(&IdlePolicy::default(), &sessions, NOW).is_empty());
+    }
+
+    #[test]
+    fn test_only_dormant_sessions_past_the_threshold() {
+        let sessions = vec![
+            session("idle", "claude", AgentStatus::Idle, 5 * HOUR),
+            session("shell", "zsh", AgentStatus::Unknown, 5 * HOUR),
+            session("recent", "claude", AgentStatus::Idle, HOUR),
+            // Waiting on the user isn't abandoned, however long it waits
+            session("waiting", "claude", AgentStatus::WaitingForInput, 9 * HOUR),
+            session("busy", "aider", AgentStatus::Busy, 9 * HOUR),
+        ];
+        let verdicts = evaluate(&policy(4.0, PolicyAction::Flag), &sessions, NOW);
+        assert_eq!(names(&verdicts), ["idle", "shell"]);
+        assert_eq!(verdicts[0].idle_secs, 5 * HOUR);
+        assert_eq!(verdicts[0].action, PolicyAction::Flag);
+    }
+
+    #[test]
+    fn test_threshold_is_inclusive_and_fractional() {
+        let sessions = vec![
+            session("exact", "zsh", AgentStatus::Unknown, HOUR / 2),
+            session("short", "zsh", AgentStatus::Unknown, HOUR / 2 - 1),
+        ];
+        let verdicts = evaluate(&policy(0.5, PolicyAction::Kill), &sessions, NOW);
+        assert_eq!(names(&verdicts), ["exact"]);
+        assert_eq!(verdicts[0].action, PolicyAction::Kill);
+    }
+
+    #[test]
+    fn test_history_over_several_evaluations() {
+        let policy = policy(2.0, PolicyAction::Flag);
+        let mut api = session("api", "claude", AgentStatus::Busy, 0);
+        // Busy work keeps it out, however long it runs
+        assert!(evaluate(&policy, &[api.clone()], NOW + 3 * HOUR).is_empty());
+
+        // Finishes and goes quiet at the prompt
+        api.status = AgentStatus::Idle;
+        api.activity_at = NOW + 3 * HOUR;
+        assert!(evaluate(&policy, &[api.clone()], NOW + 4 * HOUR).is_empty());
+        assert_eq!(
+            names(&evaluate(&policy, &[api.clone()], NOW + 5 * HOUR)),
+            ["api"]
+        );
+
+        // Any output resets the clock
+        api.activity_at = NOW + 5 * HOUR;
+        assert!(evaluate(&policy, &[api], NOW + 6 * HOUR).is_empty());
+    }
+
+    #[test]
+    fn test_exempt_and_unknown_activity() {
+        let mut unreported = session("unreported", "zsh", AgentStatus::Unknown, 0);
+        unreported.activity_at = 0;
+        let sessions = vec![
+            session("pinned", "zsh", AgentStatus::Unknown, 9 * HOUR),
+            unreported,
+            // Activity after `now` (clock skew) isn't idle time
+            session("future", "zsh", AgentStatus::Unknown, 0),
+        ];
+        let policy = IdlePolicy {
+            exempt: vec!["pinned".to_string()],
+            ..policy(1.0, PolicyAction::Kill)
+        };
+        assert!(evaluate(&policy, &sessions, NOW - 60).is_empty());
+    }
+
+    #[test]
+    fn test_archive_path() {
+        let path = archive_path(Path::new("/archive"), "feat/login x", 42);
+        assert_eq!(path, Path::new("/archive/feat_login_x-42.log"));
+    }
+}
@@ -0,0 +1,666 @@
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap};
+use std::time::{Duration, Instant};
+use tokio::sync::mpsc::UnboundedReceiver;
+
+use crate::rules::glob_match;
+use crate::tmux::{AgentStatus, CapturePlan, TmuxSession};
+
+/// Quiet polls in a row before the adaptive schedule slows down
+const QUIET_POLLS_BEFORE_BACKOFF: usize = 3;
+/// Requested polls come no closer together than this
+pub const POLL_NOW_DEBOUNCE: Duration = Duration::from_millis(250);
+
+/// Messages from the main loop to the poller task
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PollerControl {
+    /// Stop polling (the TUI is suspended)
+    Pause,
+    /// Poll immediately and carry on
+    Resume,
+    /// Poll immediately unless paused
+    PollNow,
+    /// The terminal gained (`true`) or lost focus
+    Focus(bool),
+}
+
+/// Decides how long the poller sleeps between polls
+#[derive(Debug)]
+pub struct PollSchedule {
+    /// Interval while sessions are active (or always, when not adaptive)
+    fast: Duration,
+    /// Interval once nothing has happened for a while
+    slow: Duration,
+    /// Back off to `slow` when sessions are quiet
+    adaptive: bool,
+    /// Consecutive polls with no busy session and no new activity
+    quiet_polls: usize,
+    /// Latest `session_activity` seen, to spot new output
+    last_activity: u64,
+    paused: bool,
+    /// When the last poll started
+    last_poll: Option<Instant>,
+    /// Interval while the terminal is unfocused
+    background: Duration,
+    /// Focus as last reported; `None` if the terminal never said
+    focused: Option<bool>,
+    /// When the soonest session capture is due, if sooner than the next poll
+    next_capture: Option<Instant>,
+}
+
+impl PollSchedule {
+    pub fn new(fast: Duration, slow: Duration, adaptive: bool) -> Self {
+        Self {
+            fast,
+            slow: slow.max(fast),
+            adaptive,
+            quiet_polls: 0,
+            last_activity: 0,
+            paused: false,
+            last_poll: None,
+            background: slow.max(fast),
+            focused: None,
+            next_capture: None,
+        }
+    }
+
+    /// Poll every `interval` (or slower) while the terminal is unfocused
+    pub fn with_background(mut self, interval: Duration) -> Self {
+        self.background = interval;
+        self
+    }
+
+    pub fn set_focused(&mut self, focused: bool) {
+        self.focused = Some(focused);
+    }
+
+    /// The terminal reported that it has focus
+    pub fn is_focused(&self) -> bool {
+        self.focused == Some(true)
+    }
+
+    /// The terminal reported that it lost focus
+    pub fn is_unfocused(&self) -> bool {
+        self.focused == Some(false)
+    }
+
+    /// Record a poll result; any busy session or new output resets the backoff
+    pub fn observe(&mut self, sessions: &[TmuxSession]) {
+        let activity = sessions.iter().map(|s| s.activity_at).max().unwrap_or(0);
+        let busy = sessions.iter().any(|s| s.status == AgentStatus::Busy);
+        if busy || activity > self.last_activity {
+            self.quiet_polls = 0;
+        } else {
+            self.quiet_polls += 1;
+        }
+        self.last_activity = self.last_activity.max(activity);
+    }
+
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+
+    /// Resume polling at the fast rate
+    pub fn resume(&mut self) {
+        self.paused = false;
+        self.quiet_polls = 0;
+    }
+
+    /// How long a requested poll must wait to keep polls `POLL_NOW_DEBOUNCE` apart
+    fn debounce_delay(&self) -> Duration {
+        self.last_poll.map_or(Duration::ZERO, |at| {
+            POLL_NOW_DEBOUNCE.saturating_sub(at.elapsed())
+        })
+    }
+
+    /// Wake for a session capture due at `at`, even if polls are further apart
+    pub fn set_next_capture(&mut self, at: Option<Instant>) {
+        self.next_capture = at;
+    }
+
+    /// Interval between polls, ignoring per-session captures; `None` while paused
+    pub fn base_delay(&self) -> Option<Duration> {
+        let delay = if self.adaptive && self.quiet_polls >= QUIET_POLLS_BEFORE_BACKOFF {
+            self.slow
+        } else {
+            self.fast
+        };
+        match (self.paused, self.focused) {
+            (true, _) => None,
+            (false, Some(false)) => Some(delay.max(self.background)),
+            (false, _) => Some(delay),
+        }
+    }
+
+    /// Delay before the next poll; `None` while paused
+    pub fn next_delay(&self) -> Option<Duration> {
+        let delay = self.base_delay()?;
+        Some(match self.next_capture {
+            Some(at) => delay.min(at.saturating_duration_since(Instant::now())),
+            None => delay,
+        })
+    }
+}
+
+/// How often a session's pane is captured
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum PollRate {
+    Slow,
+    Normal,
+    Fast,
+}
+
+impl PollRate {
+    /// Next rate in the cycle offered from the detail pane
+    pub fn next(self) -> Self {
+        match self {
+            PollRate::Slow => PollRate::Normal,
+            PollRate::Normal => PollRate::Fast,
+            PollRate::Fast => PollRate::Slow,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            PollRate::Slow => "slow",
+            PollRate::Normal => "normal",
+            PollRate::Fast => "fast",
+        }
+    }
+}
+
+/// `[[poll_rates]]` entry of the config
+#[derive(Debug, Clone, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PollRateRule {
+    /// Session name pattern; `*` matches any run of characters
+    pub when: String,
+    pub rate: PollRate,
+}
+
+/// Capture interval of each rate, and the rate of each session
+#[derive(Debug, Clone)]
+pub struct PollRates {
+    pub fast: Duration,
+    pub slow: Duration,
+    rules: Vec<PollRateRule>,
+}
+
+impl PollRates {
+    pub fn new(fast: Duration, slow: Duration, rules: Vec<PollRateRule>) -> Self {
+        Self { fast, slow, rules }
+    }
+
+    /// A session's rate: its override, else the first matching rule, else normal
+    pub fn rate(&self, name: &str, overrides: &BTreeMap<String, PollRate>) -> PollRate {
+        overrides
+            .get(name)
+            .or_else(|| {
+                self.rules
+                    .iter()
+                    .find(|rule| glob_match(&rule.when, name))
+                    .map(|rule| &rule.rate)
+            })
+            .copied()
+            .unwrap_or(PollRate::Normal)
+    }
+
+    /// Interval for `rate`, where normal follows the poll schedule's `normal`
+    pub fn interval(&self, rate: PollRate, normal: Duration) -> Duration {
+        match rate {
+            PollRate::Slow => self.slow.max(normal),
+            PollRate::Normal => normal,
+            PollRate::Fast => self.fast.min(normal),
+        }
+    }
+}
+
+/// When a session's pane was last considered for capture, and how often it is
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CaptureTiming {
+    pub interval: Duration,
+    /// Captured whenever due, even without new activity
+    pub forced: bool,
+    pub last: Option<Instant>,
+}
+
+impl CaptureTiming {
+    fn due_at(&self) -> Option<Instant> {
+        self.last.map(|last| last + self.interval)
+    }
+}
+
+/// Which sessions a poll at `now` captures: ones not yet due keep their last
+/// status, forced ones that are due are captured whatever their activity, and
+/// the rest follow the status cache
+pub fn plan_captures(timings: &HashMap<String, CaptureTiming>, now: Instant) -> CapturePlan {
+    let mut plan = CapturePlan::default();
+    for (id, timing) in timings {
+        match timing.due_at() {
+            Some(at) if at > now => {
+                plan.skip.insert(id.clone());
+            }
+            _ if timing.forced => {
+                plan.force.insert(id.clone());
+            }
+            _ => {}
+        }
+    }
+    plan
+}
+
+/// Per-session capture times kept by the poller across polls
+#[derive(Debug, Default)]
+pub struct CaptureSchedule {
+    timings: HashMap<String, CaptureTiming>,
+}
+
+impl CaptureSchedule {
+    pub fn plan(&self, now: Instant) -> CapturePlan {
+        plan_captures(&self.timings, now)
+    }
+
+    /// Record a poll at `now` that followed `plan`, with each listed session's
+    /// interval and whether it's forced; sessions that are gone are forgotten
+    pub fn update(
+        &mut self,
+        plan: &CapturePlan,
+        sessions: impl IntoIterator<Item = (String, Duration, bool)>,
+        now: Instant,
+    ) {
+        let mut timings = HashMap::new();
+        for (id, interval, forced) in sessions {
+            let last = match self.timings.get(&id) {
+                Some(timing) if plan.skip.contains(&id) => timing.last,
+                _ => Some(now),
+            };
+            timings.insert(
+                id,
+                CaptureTiming {
+                    interval,
+                    forced,
+                    last,
+                },
+            );
+        }
+        self.timings = timings;
+    }
+
+    /// When the soonest session capture is due
+    pub fn next_due(&self) -> Option<Instant> {
+        self.timings
+            .values()
+            .filter_map(CaptureTiming::due_at)
+            .min()
+    }
+}
+
+/// A session's status changed between two polls
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Transition {
+    pub session_id: String,
+    pub session: String,
+    pub previous: AgentStatus,
+    pub status: AgentStatus,
+}
+
+/// Remembers each session's last status to report changes
+#[derive(Debug, Default)]
+pub struct TransitionTracker {
+    /// Status and creation time by session id
+    statuses: HashMap<String, (AgentStatus, u64)>,
+}
+
+impl TransitionTracker {
+    /// Status changes since the previous call; new sessions don't count as changes
+    ///
+    /// A session created again under an id tmux reused (after a server
+    /// restart) is new too, not a change from the old one's status.
+    pub fn observe(&mut self, sessions: &[TmuxSession]) -> Vec<Transition> {
+        let mut transitions = Vec::new();
+        let mut statuses = HashMap::with_capacity(sessions.len());
+        for session in sessions {
+            if let Some(&(previous, created_at)) = self.statuses.get(&session.id)
+                && created_at == session.created_at
+                && previous != session.status
+            {
+                transitions.push(Transition {
+                    session_id: session.id.clone(),
+                    session: session.name.clone(),
+                    previous,
+                    status: session.status,
+                });
+            }
+            statuses.insert(session.id.clone(), (session.status, session.created_at));
+        }
+        self.statuses = statuses;
+        transitions
+    }
+}
+
+/// Wait until the next poll is due; `false` once the main loop has gone away
+///
+/// A burst of `PollNow` requests yields one poll, no sooner than
+/// `POLL_NOW_DEBOUNCE` after the previous one.
+pub async fn wait_for_next_poll(
+    schedule: &mut PollSchedule,
+    control: &mut UnboundedReceiver<PollerControl>,
+) -> bool {
+    let due = wait_until_due(schedule, control).await;
+    if due {
+        schedule.last_poll = Some(Instant::now());
+    }
+    due
+}
+
+async fn wait_until_due(
+    schedule: &mut PollSchedule,
+    control: &mut UnboundedReceiver<PollerControl>,
+) -> bool {
+    let mut requested = false;
+    loop {
+        let delay = match schedule.next_delay() {
+            Some(delay) if requested => Some(delay.min(schedule.debounce_delay())),
+            delay => delay,
+        };
+        let message = match delay {
+            Some(delay) => tokio::select! {
+                message = control.recv() => message,
+                _ = tokio::time::sleep(delay) => return true,
+            },
+            None => control.recv().await,
+        };
+        match message {
+            Some(PollerControl::Pause) => {
+                schedule.pause();
+                requested = false;
+            }
+            Some(PollerControl::Resume) => {
+                schedule.resume();
+                return true;
+            }
+            Some(PollerControl::PollNow) if schedule.next_delay().is_some() => requested = true,
+            Some(PollerControl::PollNow) => {}
+            Some(PollerControl::Focus(focused)) => schedule.set_focused(focused),
+            None => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    const FAST: Duration = Duration::from_secs(1);
+    const SLOW: Duration = Duration::from_secs(5);
+
+    fn session(status: AgentStatus, activity_at: u64) -> TmuxSession {
+        let mut s = TmuxSession::new("$1".to_string(), "api".to_string());
+        s.status = status;
+        s.activity_at = activity_at;
+        s
+    }
+
+    #[test]
+    fn test_backs_off_when_quiet_and_recovers_on_activity() {
+        let mut schedule = PollSchedule::new(FAST, SLOW, true);
+        let idle = [session(AgentStatus::Idle, 100)];
+
+        // The first sighting of the activity timestamp counts as activity
+        schedule.observe(&idle);
+        for _ in 0..QUIET_POLLS_BEFORE_BACKOFF - 1 {
+            schedule.observe(&idle);
+            assert_eq!(schedule.next_delay(), Some(FAST));
+        }
+        schedule.observe(&idle);
+        assert_eq!(schedule.next_delay(), Some(SLOW));
+
+        // New output in any session speeds back up
+        schedule.observe(&[session(AgentStatus::Idle, 101)]);
+        assert_eq!(schedule.next_delay(), Some(FAST));
+
+        // As does a busy agent, even without new activity
+        for _ in 0..QUIET_POLLS_BEFORE_BACKOFF {
+            schedule.observe(&[session(AgentStatus::Idle, 101)]);
+        }
+        assert_eq!(schedule.next_delay(), Some(SLOW));
+        schedule.observe(&[session(AgentStatus::Busy, 101)]);
+        assert_eq!(schedule.next_delay(), Some(FAST));
+    }
+
+    #[test]
+    fn test_fixed_schedule_never_backs_off() {
+        let mut schedule = PollSchedule::new(FAST, SLOW, false);
+        for _ in 0..10 {
+            schedule.observe(&[]);
+        }
+        assert_eq!(schedule.next_delay(), Some(FAST));
+    }
+
+    #[test]
+    fn test_pause_and_resume() {
+        let mut schedule = PollSchedule::new(FAST, SLOW, true);
+        for _ in 0..5 {
+            schedule.observe(&[]);
+        }
+        schedule.pause();
+        assert_eq!(schedule.next_delay(), None);
+
+        schedule.resume();
+        assert_eq!(schedule.next_delay(), Some(FAST));
+    }
+
+    #[test]
+    fn test_transition_tracker() {
+        let mut tracker = TransitionTracker::default();
+        assert!(tracker.observe(&[session(AgentStatus::Busy, 0)]).is_empty());
+        assert!(tracker.observe(&[session(AgentStatus::Busy, 1)]).is_empty());
+        assert_eq!(
+            tracker.observe(&[session(AgentStatus::WaitingForInput, 2)]),
+            [Transition {
+                session_id: "$1".to_string(),
+                session: "api".to_string(),
+                previous: AgentStatus::Busy,
+                status: AgentStatus::WaitingForInput,
+            }]
+        );
+
+        // A session that disappears and comes back starts fresh
+        assert!(tracker.observe(&[]).is_empty());
+        assert!(
+            tracker
+                .observe(&[session(AgentStatus::Error, 3)])
+                .is_empty()
+        );
+
+        // So does one recreated under the same id without a poll in between
+        let mut recreated = session(AgentStatus::Idle, 4);
+        recreated.created_at = 1_700_000_000;
+        assert!(tracker.observe(&[recreated]).is_empty());
+    }
+
+    #[test]
+    fn test_background_interval_while_unfocused() {
+        let background = Duration::from_secs(10);
+        let mut schedule = PollSchedule::new(FAST, SLOW, false).with_background(background);
+        // Terminals that never report focus poll as usual
+        assert_eq!(schedule.next_delay(), Some(FAST));
+        assert!(!schedule.is_focused());
+
+        schedule.set_focused(false);
+        assert_eq!(schedule.next_delay(), Some(background));
+        schedule.pause();
+        assert_eq!(schedule.next_delay(), None);
+        schedule.resume();
+
+        schedule.set_focused(true);
+        assert_eq!(schedule.next_delay(), Some(FAST));
+        assert!(schedule.is_focused());
+    }
+
+    #[test]
+    fn test_poll_rates() {
+        let rules = vec![
+            PollRateRule {
+                when: "batch-*".to_string(),
+                rate: PollRate::Slow,
+            },
+            PollRateRule {
+                when: "*".to_string(),
+                rate: PollRate::Fast,
+            },
+        ];
+        let rates = PollRates::new(Duration::from_millis(250), Duration::from_secs(30), rules);
+        let mut overrides = BTreeMap::new();
+        assert_eq!(rates.rate("batch-nightly", &overrides), PollRate::Slow);
+        assert_eq!(rates.rate("api", &overrides), PollRate::Fast);
+        overrides.insert("batch-nightly".to_string(), PollRate::Normal);
+        assert_eq!(rates.rate("batch-nightly", &overrides), PollRate::Normal);
+        assert_eq!(
+            PollRates::new(FAST, SLOW, Vec::new()).rate("api", &BTreeMap::new()),
+            PollRate::Normal
+        );
+
+        assert_eq!(rates.interval(PollRate::Normal, FAST), FAST);
+        assert_eq!(
+            rates.interval(PollRate::Fast, FAST),
+            Duration::from_millis(250)
+        );
+        assert_eq!(
+            rates.interval(PollRate::Slow, FAST),
+            Duration::from_secs(30)
+        );
+        // A backed-off schedule never makes slow sessions faster or fast ones slower
+        let backed_off = Duration::from_secs(60);
+        assert_eq!(rates.interval(PollRate::Slow, backed_off), backed_off);
+        assert_eq!(
+            rates.interval(PollRate::Fast, backed_off),
+            Duration::from_millis(250)
+        );
+
+        assert_eq!(PollRate::Slow.next(), PollRate::Normal);
+        assert_eq!(PollRate::Fast.next(), PollRate::Slow);
+    }
+
+    #[test]
+    fn test_plan_captures() {
+        let now = Instant::now();
+        let timing = |interval: u64, forced, last: Option<u64>| CaptureTiming {
+            interval: Duration::from_secs(interval),
+            forced,
+            last: last.map(|ago| now - Duration::from_secs(ago)),
+        };
+        let timings = HashMap::from([
+            ("$slow".to_string(), timing(30, false, Some(10))),
+            ("$normal".to_string(), timing(1, false, Some(1))),
+            ("$fast".to_string(), timing(1, true, Some(2))),
+            ("$fresh".to_string(), timing(1, true, Some(0))),
+            ("$new".to_string(), timing(30, true, None)),
+        ]);
+        let plan = plan_captures(&timings, now);
+        // Not due yet: keep the last status
+        assert_eq!(
+            plan.skip,
+            ["$slow", "$fresh"].map(String::from).into_iter().collect()
+        );
+        // Due and forced: captured whatever the activity
+        assert_eq!(
+            plan.force,
+            ["$fast", "$new"].map(String::from).into_iter().collect()
+        );
+        assert!(plan_captures(&HashMap::new(), now) == CapturePlan::default());
+    }
+
+    #[test]
+    fn test_capture_schedule() {
+        let start = Instant::now();
+        let at = |millis| start + Duration::from_millis(millis);
+        let sessions = |selected: &str| {
+            [("$batch", 30_000), ("$api", 1000), ("$web", 1000)].map(|(id, interval)| {
+                let forced = id == selected;
+                let interval = if forced { 250 } else { interval };
+                (id.to_string(), Duration::from_millis(interval), forced)
+            })
+        };
+
+        let mut captures = CaptureSchedule::default();
+        let plan = captures.plan(at(0));
+        assert_eq!(plan, CapturePlan::default());
+        captures.update(&plan, sessions("$api"), at(0));
+        assert_eq!(captures.next_due(), Some(at(250)));
+
+        // Only the selected session is due a quarter second later
+        let plan = captures.plan(at(250));
+        assert_eq!(plan.force, HashSet::from(["$api".to_string()]));
+        assert!(plan.skip.contains("$web") && plan.skip.contains("$batch"));
+        captures.update(&plan, sessions("$api"), at(250));
+        assert_eq!(captures.next_due(), Some(at(500)));
+
+        // Normal sessions come due at the poll interval, the slow one much later
+        let plan = captures.plan(at(1000));
+        assert!(!plan.skip.contains("$web") && plan.skip.contains("$batch"));
+        captures.update(&plan, sessions("$api"), at(1000));
+
+        // Selecting the slow session makes it due from its last capture
+        let plan = captures.plan(at(1100));
+        captures.update(&plan, sessions("$batch"), at(1100));
+        let plan = captures.plan(at(1100));
+        assert!(plan.force.contains("$batch"));
+
+        // Gone sessions are forgotten
+        captures.update(&plan, [("$api".to_string(), FAST, false)], at(1100));
+        assert_eq!(captures.next_due(), Some(at(1000) + FAST));
+    }
+
+    #[test]
+    fn test_next_capture_shortens_delay() {
+        let mut schedule = PollSchedule::new(SLOW, SLOW, false);
+        schedule.set_next_capture(Some(Instant::now() + FAST));
+        assert!(schedule.next_delay().unwrap() <= FAST);
+        assert_eq!(schedule.base_delay(), Some(SLOW));
+        // A capture due later than the next poll changes nothing
+        schedule.set_next_capture(Some(Instant::now() + 2 * SLOW));
+        assert_eq!(schedule.next_delay(), Some(SLOW));
+        schedule.pause();
+        assert_eq!(schedule.next_delay(), None);
+    }
+
+    #[tokio::test]
+    async fn test_poll_now() {
+        let mut schedule = PollSchedule::new(SLOW, SLOW, false);
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        tx.send(PollerControl::PollNow).unwrap();
+        let started = std::time::Instant::now();
+        assert!(wait_for_next_poll(&mut schedule, &mut rx).await);
+        assert!(started.elapsed() < SLOW);
+
+        // Ignored while paused; only Resume wakes the poller
+        tx.send(PollerControl::Pause).unwrap();
+        tx.send(PollerControl::PollNow).unwrap();
+        tx.send(PollerControl::Resume).unwrap();
+        assert!(wait_for_next_poll(&mut schedule, &mut rx).await);
+        assert!(rx.try_recv().is_err());
+        assert_eq!(schedule.next_delay(), Some(SLOW));
+    }
+
+    #[tokio::test]
+    async fn test_poll_now_is_debounced() {
+        let mut schedule = PollSchedule::new(SLOW, SLOW, false);
+        let (tx, mut rx) = tokio::sync::mpsc::unbounded_channel();
+        tx.send(PollerControl::PollNow).unwrap();
+        assert!(wait_for_next_poll(&mut schedule, &mut rx).await);
+        let polled = std::time::Instant::now();
+
+        // Holding the refresh key: one poll for the lot, after the debounce
+        for _ in 0..10 {
+            tx.send(PollerControl::PollNow).unwrap();
+        }
+        assert!(wait_for_next_poll(&mut schedule, &mut rx).await);
+        let elapsed = polled.elapsed();
+        assert!(elapsed >= POLL_NOW_DEBOUNCE - Duration::from_millis(20));
+        assert!(elapsed < SLOW);
+        assert!(rx.try_recv().is_err());
+    }
+}
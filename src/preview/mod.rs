@@ -0,0 +1,257 @@
+mod search;
+
+pub use search::PaneSearch;
+
+/// Number of scrollback lines captured for the full-screen preview
+pub const PREVIEW_HISTORY_LINES: usize = 5000;
+
+/// Scrollable window over a captured pane buffer
+///
+/// Only the slice returned by `visible_lines` is rendered, so the buffer can
+/// hold the full scrollback without slowing down each frame.
+#[derive(Debug, Default)]
+pub struct PaneViewport {
+    /// Captured pane content, one entry per line
+    lines: Vec<String>,
+    /// Index of the first visible line
+    offset: usize,
+    /// Number of lines that fit in the rendered area
+    height: usize,
+    /// Whether the view follows new output (like `less +F`)
+    follow: bool,
+}
+
+impl PaneViewport {
+    pub fn new() -> Self {
+        Self {
+            follow: true,
+            ..Self::default()
+        }
+    }
+
+    /// Replace the buffer with fresh capture output
+    pub fn set_content(&mut self, content: &str) {
+        self.lines = content.lines().map(str::to_string).collect();
+        // tmux pads the visible screen with blank lines below the cursor
+        while self.lines.last().is_some_and(|l| l.trim().is_empty()) {
+            self.lines.pop();
+        }
+
+        if self.follow {
+            self.offset = self.max_offset();
+        } else {
+            self.offset = self.offset.min(self.max_offset());
+        }
+    }
+
+    /// Update the number of visible rows (called on every render)
+    pub fn set_height(&mut self, height: usize) {
+        self.height = height;
+        if self.follow {
+            self.offset = self.max_offset();
+        } else {
+            self.offset = self.offset.min(self.max_offset());
+        }
+    }
+
+    pub fn lines(&self) -> &[String] {
+        &self.lines
+    }
+
+    pub fn offset(&self) -> usize {
+        self.offset
+    }
+
+    pub fn is_following(&self) -> bool {
+        self.follow
+    }
+
+    /// Largest valid offset, keeping the last line at the bottom of the view
+    pub fn max_offset(&self) -> usize {
+        self.lines.len().saturating_sub(self.height)
+    }
+
+    /// Lines currently inside the viewport
+    pub fn visible_lines(&self) -> &[String] {
+        let start = self.offset.min(self.lines.len());
+        let end = (start + self.height).min(self.lines.len());
+        &self.lines[start..end]
+    }
+
+    pub fn scroll_up(&mut self, n: usize) {
+        self.offset = self.offset.saturating_sub(n);
+        self.follow = self.offset >= self.max_offset();
+    }
+
+    pub fn scroll_down(&mut self, n: usize) {
+        self.offset = (self.offset + n).min(self.max_offset());
+        self.follow = self.offset >= self.max_offset();
+    }
+
+    pub fn page_up(&mut self) {
+        self.scroll_up(self.height.saturating_sub(1).max(1));
+    }
+
+    pub fn page_down(&mut self) {
+        self.scroll_down(self.height.saturating_sub(1).max(1));
+    }
+
+    pub fn scroll_to_top(&mut self) {
+        self.offset = 0;
+        self.follow = self.max_offset() == 0;
+    }
+
+    /// Jump to the end of the buffer and resume following new output
+    pub fn scroll_to_bottom(&mut self) {
+        self.offset = self.max_offset();
+        self.follow = true;
+    }
+
+    /// Bring `line` into view, centering it if it is currently off-screen
+    pub fn jump_to(&mut self, line: usize) {
+        let visible = line >= self.offset && line < self.offset + self.height;
+        if !visible {
+            self.offset = line.saturating_sub(self.height / 2).min(self.max_offset());
+        }
+        self.follow = self.offset >= self.max_offset();
+    }
+}
+
+/// Full-screen read-only view of a session's pane
+#[derive(Debug)]
+pub struct PanePreview {
+    /// Session being viewed
+    pub session_id: String,
+    /// Session name (for the title)
+    pub session_name: String,
+    /// Scroll state over the captured buffer
+    pub viewport: PaneViewport,
+    /// Search state over the same buffer
+    pub search: PaneSearch,
+}
+
+impl PanePreview {
+    pub fn new(session_id: String, session_name: String) -> Self {
+        Self {
+            session_id,
+            session_name,
+            viewport: PaneViewport::new(),
+            search: PaneSearch::default(),
+        }
+    }
+
+    /// Replace the captured content, keeping search results in sync
+    pub fn set_content(&mut self, content: &str) {
+        self.viewport.set_content(content);
+        self.search.update_matches(self.viewport.lines());
+    }
+
+    /// Run the typed query and jump to the first match below the top of the view
+    pub fn commit_search(&mut self) {
+        self.search.editing = false;
+        self.search.update_matches(self.viewport.lines());
+        if let Some(m) = self.search.select_from(self.viewport.offset()) {
+            self.viewport.jump_to(m.line);
+        }
+    }
+
+    pub fn next_match(&mut self) {
+        if let Some(m) = self.search.next_match() {
+            self.viewport.jump_to(m.line);
+        }
+    }
+
+    pub fn prev_match(&mut self) {
+        if let Some(m) = self.search.prev_match() {
+            self.viewport.jump_to(m.line);
+        }
+    }
+
+    pub fn toggle_smart_case(&mut self) {
+        self.search.smart_case = !self.search.smart_case;
+        self.search.update_matches(self.viewport.lines());
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn viewport(lines: usize, height: usize) -> PaneViewport {
+        let content = (0..lines)
+            .map(|i| format!("line {}", i))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let mut vp = PaneViewport::new();
+        vp.set_height(height);
+        vp.set_content(&content);
+        vp
+    }
+
+    #[test]
+    fn test_follows_output_until_scrolled_up() {
+        let mut vp = viewport(100, 10);
+        assert_eq!(vp.offset(), 90);
+        assert!(vp.is_following());
+
+        vp.scroll_up(5);
+        assert_eq!(vp.offset(), 85);
+        assert!(!vp.is_following());
+
+        // New output must not move a frozen view
+        let more = (0..120).map(|i| format!("line {}", i)).collect::<Vec<_>>();
+        vp.set_content(&more.join("\n"));
+        assert_eq!(vp.offset(), 85);
+
+        vp.scroll_to_bottom();
+        assert_eq!(vp.offset(), 110);
+        assert!(vp.is_following());
+    }
+
+    #[test]
+    fn test_scroll_bounds() {
+        let mut vp = viewport(30, 10);
+        vp.scroll_to_top();
+        vp.scroll_up(3);
+        assert_eq!(vp.offset(), 0);
+
+        vp.page_down();
+        assert_eq!(vp.offset(), 9);
+        vp.scroll_down(100);
+        assert_eq!(vp.offset(), 20);
+        assert!(vp.is_following());
+        assert_eq!(vp.visible_lines().len(), 10);
+        assert_eq!(vp.visible_lines()[9], "line 29");
+    }
+
+    #[test]
+    fn test_short_buffer_and_trailing_blanks() {
+        let mut vp = PaneViewport::new();
+        vp.set_height(10);
+        vp.set_content("a\nb\n\n   \n");
+        assert_eq!(vp.lines().len(), 2);
+        assert_eq!(vp.max_offset(), 0);
+        assert_eq!(vp.visible_lines().len(), 2);
+    }
+
+    #[test]
+    fn test_jump_to_centers_offscreen_lines() {
+        let mut vp = viewport(100, 10);
+        vp.scroll_to_top();
+
+        // Already visible: no movement
+        vp.jump_to(4);
+        assert_eq!(vp.offset(), 0);
+
+        vp.jump_to(50);
+        assert_eq!(vp.offset(), 45);
+        assert!(!vp.is_following());
+
+        // Clamped at both ends of the buffer
+        vp.jump_to(2);
+        assert_eq!(vp.offset(), 0);
+        vp.jump_to(98);
+        assert_eq!(vp.offset(), 90);
+        assert!(vp.is_following());
+    }
+}
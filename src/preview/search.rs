@@ -0,0 +1,245 @@
+use regex::{Regex, RegexBuilder};
+
+/// A single search hit inside the captured buffer
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SearchMatch {
+    /// Line index in the buffer
+    pub line: usize,
+    /// Byte offset of the match start within the line
+    pub start: usize,
+    /// Byte offset one past the match end within the line
+    pub end: usize,
+}
+
+/// Search state for the pane preview
+#[derive(Debug, Default)]
+pub struct PaneSearch {
+    /// Query text (literal, not a regex)
+    pub query: String,
+    /// Whether the query input line is focused
+    pub editing: bool,
+    /// Match case-sensitively when the query contains an uppercase letter
+    pub smart_case: bool,
+    /// All matches in buffer order
+    matches: Vec<SearchMatch>,
+    /// Index into `matches` of the current match
+    current: Option<usize>,
+}
+
+impl PaneSearch {
+    /// Start typing a new query
+    pub fn begin(&mut self) {
+        self.editing = true;
+        self.query.clear();
+        self.matches.clear();
+        self.current = None;
+    }
+
+    /// Drop the query and all matches
+    pub fn clear(&mut self) {
+        self.editing = false;
+        self.query.clear();
+        self.matches.clear();
+        self.current = None;
+    }
+
+    pub fn is_case_sensitive(&self) -> bool {
+        self.smart_case && self.query.chars().any(char::is_uppercase)
+    }
+
+    fn matcher(&self) -> Option<Regex> {
+        if self.query.is_empty() {
+            return None;
+        }
+        RegexBuilder::new(&regex::escape(&self.query))
+            .case_insensitive(!self.is_case_sensitive())
+            .build()
+            .ok()
+    }
+
+    /// Recompute matches against `lines`, keeping the current match index in range
+    pub fn update_matches(&mut self, lines: &[String]) {
+        self.matches = match self.matcher() {
+            Some(re) => find_matches(lines, &re),
+            None => Vec::new(),
+        };
+        self.current = match self.current {
+            _ if self.matches.is_empty() => None,
+            Some(i) => Some(i.min(self.matches.len() - 1)),
+            None => None,
+        };
+    }
+
+    pub fn matches(&self) -> &[SearchMatch] {
+        &self.matches
+    }
+
+    /// Position of the current match (zero-based) within all matches
+    pub fn current_index(&self) -> Option<usize> {
+        self.current
+    }
+
+    pub fn current(&self) -> Option<SearchMatch> {
+        self.current.map(|i| self.matches[i])
+    }
+
+    /// Select the first match at or after `line`, wrapping to the top
+    pub fn select_from(&mut self, line: usize) -> Option<SearchMatch> {
+        if self.matches.is_empty() {
+            self.current = None;
+            return None;
+        }
+        let i = self.matches.partition_point(|m| m.line < line);
+        self.current = Some(if i < self.matches.len() { i } else { 0 });
+        self.current()
+    }
+
+    /// Advance to the next match, wrapping to the first
+    pub fn next_match(&mut self) -> Option<SearchMatch> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        self.current = Some(match self.current {
+            Some(i) => (i + 1) % self.matches.len(),
+            None => 0,
+        });
+        self.current()
+    }
+
+    /// Step back to the previous match, wrapping to the last
+    pub fn prev_match(&mut self) -> Option<SearchMatch> {
+        if self.matches.is_empty() {
+            return None;
+        }
+        let len = self.matches.len();
+        self.current = Some(match self.current {
+            Some(i) => (i + len - 1) % len,
+            None => len - 1,
+        });
+        self.current()
+    }
+
+    /// Matches that fall on a single line
+    pub fn matches_on_line(&self, line: usize) -> &[SearchMatch] {
+        let start = self.matches.partition_point(|m| m.line < line);
+        let end = self.matches.partition_point(|m| m.line <= line);
+        &self.matches[start..end]
+    }
+}
+
+/// Find every non-overlapping match of `re` in `lines`, in buffer order
+pub fn find_matches(lines: &[String], re: &Regex) -> Vec<SearchMatch> {
+    lines
+        .iter()
+        .enumerate()
+        .flat_map(|(line, text)| {
+            re.find_iter(text).map(move |m| SearchMatch {
+                line,
+                start: m.start(),
+                end: m.end(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn lines(text: &[&str]) -> Vec<String> {
+        text.iter().map(|s| s.to_string()).collect()
+    }
+
+    fn search(query: &str, smart_case: bool, buffer: &[String]) -> PaneSearch {
+        let mut search = PaneSearch {
+            query: query.to_string(),
+            smart_case,
+            ..PaneSearch::default()
+        };
+        search.update_matches(buffer);
+        search
+    }
+
+    #[test]
+    fn test_case_insensitive_by_default() {
+        let buffer = lines(&["Error: one", "no hit", "an error and an ERROR"]);
+        let s = search("error", false, &buffer);
+        let found: Vec<_> = s.matches().iter().map(|m| (m.line, m.start)).collect();
+        assert_eq!(found, vec![(0, 0), (2, 3), (2, 16)]);
+
+        // Without smart case an uppercase query still ignores case
+        let s = search("ERROR", false, &buffer);
+        assert_eq!(s.matches().len(), 3);
+    }
+
+    #[test]
+    fn test_smart_case() {
+        let buffer = lines(&["Error: one", "an error and an ERROR"]);
+        assert_eq!(search("error", true, &buffer).matches().len(), 3);
+        assert_eq!(search("Error", true, &buffer).matches().len(), 1);
+        assert_eq!(search("ERROR", true, &buffer).matches().len(), 1);
+    }
+
+    #[test]
+    fn test_query_is_literal() {
+        let buffer = lines(&["src/main.rs:12", "srcXmainYrs"]);
+        let s = search("main.rs", false, &buffer);
+        assert_eq!(s.matches().len(), 1);
+        assert_eq!(
+            s.matches()[0],
+            SearchMatch {
+                line: 0,
+                start: 4,
+                end: 11
+            }
+        );
+    }
+
+    #[test]
+    fn test_non_ascii_offsets_are_char_boundaries() {
+        let buffer = lines(&["ñandú Straße straße"]);
+        let s = search("STRASSE", false, &buffer);
+        assert!(s.matches().is_empty());
+        let s = search("straße", false, &buffer);
+        assert_eq!(s.matches().len(), 2);
+        for m in s.matches() {
+            assert!(buffer[0].is_char_boundary(m.start));
+            assert!(buffer[0].is_char_boundary(m.end));
+        }
+    }
+
+    #[test]
+    fn test_navigation_wraps() {
+        let buffer = lines(&["x", "foo", "x", "foo foo", "x"]);
+        let mut s = search("foo", false, &buffer);
+        assert_eq!(s.current(), None);
+
+        assert_eq!(s.select_from(2).map(|m| m.line), Some(3));
+        assert_eq!(s.current_index(), Some(1));
+        assert_eq!(s.next_match().map(|m| (m.line, m.start)), Some((3, 4)));
+        assert_eq!(s.next_match().map(|m| m.line), Some(1));
+        assert_eq!(s.prev_match().map(|m| (m.line, m.start)), Some((3, 4)));
+
+        // Past the last match wraps back to the first
+        assert_eq!(s.select_from(4).map(|m| m.line), Some(1));
+    }
+
+    #[test]
+    fn test_matches_on_line_and_refresh() {
+        let buffer = lines(&["foo", "bar", "foo foo"]);
+        let mut s = search("foo", false, &buffer);
+        assert_eq!(s.matches_on_line(0).len(), 1);
+        assert_eq!(s.matches_on_line(1).len(), 0);
+        assert_eq!(s.matches_on_line(2).len(), 2);
+
+        s.select_from(2);
+        s.next_match();
+        assert_eq!(s.current_index(), Some(2));
+
+        // Fewer matches after a refresh clamps the current index
+        s.update_matches(&lines(&["foo"]));
+        assert_eq!(s.current_index(), Some(0));
+        s.update_matches(&lines(&["nothing"]));
+        assert_eq!(s.current(), None);
+    }
+}
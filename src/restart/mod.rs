@@ -0,0 +1,144 @@
+use anyhow::{Result, bail};
+use std::time::Duration;
+
+use crate::tmux::{AgentStatus, StateInferenceEngine, TmuxClient};
+
+/// Interrupts sent before waiting for the shell prompt
+const INTERRUPT_COUNT: usize = 2;
+/// Pause between interrupts and between prompt polls
+const POLL_INTERVAL: Duration = Duration::from_millis(250);
+/// Polls before giving up on the prompt (about five seconds)
+const MAX_PROMPT_POLLS: usize = 20;
+/// Scrollback captured when looking for the prompt
+const PROMPT_CAPTURE_LINES: usize = 20;
+
+/// Result of looking at one pane capture while waiting for the prompt
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum WaitState {
+    Waiting,
+    Ready,
+    TimedOut,
+}
+
+/// Waits for a shell prompt to appear after the agent was interrupted
+#[derive(Debug)]
+pub struct PromptWait {
+    polls: usize,
+    max_polls: usize,
+}
+
+impl PromptWait {
+    pub fn new(max_polls: usize) -> Self {
+        Self {
+            polls: 0,
+            max_polls,
+        }
+    }
+
+    /// Feed the next capture; `Ready` once the pane reads as idle
+    pub fn observe(&mut self, capture: &str) -> WaitState {
+        self.polls += 1;
+        if is_prompt(capture) {
+            WaitState::Ready
+        } else if self.polls >= self.max_polls {
+            WaitState::TimedOut
+        } else {
+            WaitState::Waiting
+        }
+    }
+}
+
+/// Whether the pane shows a prompt ready for a new command
+fn is_prompt(capture: &str) -> bool {
+    if StateInferenceEngine::analyze(capture) == AgentStatus::Idle {
+        return true;
+    }
+    // Decorated prompts like `user@host:~/src$ ` end with the prompt character
+    capture
+        .lines()
+        .rev()
+        .find(|l| !l.trim().is_empty())
+        .map(str::trim_end)
+        .is_some_and(|l| l.ends_with(['$', '%', '#', '❯']))
+}
+
+/// Interrupt the agent in `session_id`, wait for the shell, then run `command`
+///
+/// Each completed step is passed to `log`.
+pub async fn restart_agent(
+    client: &TmuxClient,
+    session_id: &str,
+    command: &str,
+    mut log: impl FnMut(String),
+) -> Result<()> {
+    for _ in 0..INTERRUPT_COUNT {
+        client.send_key_names(session_id, &["C-c"]).await?;
+        tokio::time::sleep(POLL_INTERVAL).await;
+    }
+    log(format!("Sent {} interrupts", INTERRUPT_COUNT));
+
+    let mut wait = PromptWait::new(MAX_PROMPT_POLLS);
+    loop {
+        let capture = client
+            .capture_pane(session_id, PROMPT_CAPTURE_LINES)
+            .await?;
+        match wait.observe(&capture) {
+            WaitState::Ready => break,
+            WaitState::TimedOut => bail!("Shell prompt did not appear after interrupting"),
+            WaitState::Waiting => tokio::time::sleep(POLL_INTERVAL).await,
+        }
+    }
+    log("Shell prompt is back".to_string());
+
+    client.send_keys(session_id, command).await?;
+    log(format!("Ran `{}`", command));
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn run(wait: &mut PromptWait, captures: &[&str]) -> Vec<WaitState> {
+        captures.iter().map(|c| wait.observe(c)).collect()
+    }
+
+    #[test]
+    fn test_ready_once_prompt_appears() {
+        let mut wait = PromptWait::new(10);
+        let states = run(
+            &mut wait,
+            &[
+                "Thinking...",
+                "Interrupted by user\n",
+                "Interrupted by user\nme@box:~/api$ \n\n",
+            ],
+        );
+        assert_eq!(
+            states,
+            vec![WaitState::Waiting, WaitState::Waiting, WaitState::Ready]
+        );
+    }
+
+    #[test]
+    fn test_bare_idle_prompts() {
+        assert_eq!(PromptWait::new(1).observe("done\n$ "), WaitState::Ready);
+        assert_eq!(PromptWait::new(1).observe("~/api\n❯ "), WaitState::Ready);
+        assert_eq!(PromptWait::new(1).observe("host% "), WaitState::Ready);
+    }
+
+    #[test]
+    fn test_times_out_without_prompt() {
+        let mut wait = PromptWait::new(3);
+        let states = run(&mut wait, &["Working", "Working", "Working", "$ "]);
+        assert_eq!(
+            states,
+            vec![
+                WaitState::Waiting,
+                WaitState::Waiting,
+                WaitState::TimedOut,
+                WaitState::Ready
+            ]
+        );
+    }
+}
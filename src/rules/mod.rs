@@ -0,0 +1,584 @@
+//! Automation rules chaining sessions together: when a session matching a
+//! pattern changes status, send a prompt to another session, run a command
+//! or show a notification.
+//!
+//! Rules come from `[[rules]]` in the config or are added in the dashboard,
+//! which keeps them in the state file along with which rules are switched
+//! off and which one-shot rules have fired. Sessions are matched by name
+//! when a transition happens, so a session recreated under the same name is
+//! watched (and sent to) like the old one, while a one-shot rule that
+//! already fired stays spent.
+
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+
+use crate::hooks::Trigger;
+use crate::poller::Transition;
+
+/// Firings kept for the rules popup
+pub const HISTORY_LEN: usize = 50;
+
+/// A rule as written in the config or saved from the dashboard
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct RuleSpec {
+    /// Identifies the rule in the state file; unnamed config rules go by position
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    /// Session names to watch; `*` and `?` are wildcards
+    #[serde(default = "any_session")]
+    pub when: String,
+    /// Status (`idle`) or transition (`busy->idle`) that fires the rule
+    pub on: Trigger,
+    /// Session to type `prompt` into
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub send_to: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub prompt: Option<String>,
+    /// Shell command; `{session}`, `{status}` and `{previous}` are replaced as for hooks
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub run: Option<String>,
+    /// Desktop notification text
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notify: Option<String>,
+    /// Fire once, then stay spent until switched back on
+    #[serde(default)]
+    pub once: bool,
+    /// Start switched off
+    #[serde(default = "enabled_by_default")]
+    pub enabled: bool,
+}
+
+fn any_session() -> String {
+    "*".to_string()
+}
+
+fn enabled_by_default() -> bool {
+    true
+}
+
+/// What a rule does when it fires
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum RuleAction {
+    Send { session: String, prompt: String },
+    Run(String),
+    Notify(String),
+}
+
+impl RuleAction {
+    pub fn describe(&self) -> String {
+        match self {
+            RuleAction::Send { session, prompt } => format!("send '{}' to {}", prompt, session),
+            RuleAction::Run(command) => format!("run `{}`", command),
+            RuleAction::Notify(text) => format!("notify '{}'", text),
+        }
+    }
+}
+
+impl RuleSpec {
+    /// The one action the rule takes
+    pub fn action(&self) -> Result<RuleAction, String> {
+        match (&self.send_to, &self.prompt, &self.run, &self.notify) {
+            (Some(session), Some(prompt), None, None) => Ok(RuleAction::Send {
+                session: session.clone(),
+                prompt: prompt.clone(),
+            }),
+            (Some(_), None, None, None) => Err("`send_to` needs a `prompt`".to_string()),
+            (None, Some(_), None, None) => Err("`prompt` needs `send_to`".to_string()),
+            (None, None, Some(command), None) => Ok(RuleAction::Run(command.clone())),
+            (None, None, None, Some(text)) => Ok(RuleAction::Notify(text.clone())),
+            _ => Err("needs exactly one of `send_to`, `run` or `notify`".to_string()),
+        }
+    }
+}
+
+/// A validated rule
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Rule {
+    /// Name, or `rules[N]` for an unnamed config rule
+    pub key: String,
+    pub spec: RuleSpec,
+    pub action: RuleAction,
+    /// Added in the dashboard rather than the config
+    pub local: bool,
+}
+
+impl Rule {
+    /// `when api* goes busy->idle, send 'run the tests' to tests (once)`
+    pub fn describe(&self) -> String {
+        format!(
+            "when {} goes {}, {}{}",
+            self.spec.when,
+            self.spec.on,
+            self.action.describe(),
+            if self.spec.once { " (once)" } else { "" }
+        )
+    }
+}
+
+/// A rule fired by a transition
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Firing {
+    pub rule: String,
+    pub transition: Transition,
+    pub action: RuleAction,
+}
+
+/// A firing as the rules popup lists it
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RuleRun {
+    pub id: u64,
+    /// Unix time it fired
+    pub at: u64,
+    pub rule: String,
+    /// Session whose transition fired it
+    pub session: String,
+    pub action: String,
+    /// `None` while it runs
+    pub outcome: Option<Result<(), String>>,
+}
+
+/// Which rules are switched off and which one-shot rules have fired, by key
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct RuleFlags {
+    /// Overrides of each rule's `enabled`
+    #[serde(default)]
+    pub toggles: BTreeMap<String, bool>,
+    #[serde(default)]
+    pub spent: BTreeSet<String>,
+}
+
+impl RuleFlags {
+    pub fn is_enabled(&self, rule: &Rule) -> bool {
+        self.toggles
+            .get(&rule.key)
+            .copied()
+            .unwrap_or(rule.spec.enabled)
+    }
+
+    pub fn is_spent(&self, rule: &Rule) -> bool {
+        rule.spec.once && self.spent.contains(&rule.key)
+    }
+
+    /// Switch a rule on or off; switching it on re-arms a spent one-shot rule
+    pub fn toggle(&mut self, rule: &Rule) -> bool {
+        let enabled = !self.is_enabled(rule) || self.is_spent(rule);
+        self.spent.remove(&rule.key);
+        if enabled == rule.spec.enabled {
+            self.toggles.remove(&rule.key);
+        } else {
+            self.toggles.insert(rule.key.clone(), enabled);
+        }
+        enabled
+    }
+
+    /// Forget a deleted rule
+    pub fn forget(&mut self, key: &str) {
+        self.toggles.remove(key);
+        self.spent.remove(key);
+    }
+}
+
+/// Every rule, config rules first
+#[derive(Debug, Clone, Default)]
+pub struct RuleBook {
+    pub rules: Vec<Rule>,
+}
+
+impl RuleBook {
+    /// Build from the config's rules and those saved from the dashboard;
+    /// invalid saved rules are skipped (the config is validated when loaded)
+    pub fn new(config: &[RuleSpec], local: &[RuleSpec]) -> Self {
+        let config = config
+            .iter()
+            .enumerate()
+            .map(|(index, spec)| (index, spec, false));
+        let local = local
+            .iter()
+            .enumerate()
+            .map(|(index, spec)| (index, spec, true));
+        let rules = config
+            .chain(local)
+            .filter_map(|(index, spec, local)| {
+                let action = spec
+                    .action()
+                    .map_err(|e| tracing::warn!("Skipping rule {:?}: {}", spec.name, e))
+                    .ok()?;
+                let key = spec.name.clone().unwrap_or_else(|| {
+                    format!("{}[{}]", if local { "local" } else { "rules" }, index)
+                });
+                Some(Rule {
+                    key,
+                    spec: spec.clone(),
+                    action,
+                    local,
+                })
+            })
+            .collect();
+        Self { rules }
+    }
+
+    /// The saved rules, for the state file
+    pub fn local_specs(&self) -> Vec<RuleSpec> {
+        self.rules
+            .iter()
+            .filter(|r| r.local)
+            .map(|r| r.spec.clone())
+            .collect()
+    }
+
+    /// `base`, or `base 2`, `base 3`... if a rule already has that name
+    pub fn unique_name(&self, base: &str) -> String {
+        let taken = |name: &str| self.rules.iter().any(|r| r.key == name);
+        if !taken(base) {
+            return base.to_string();
+        }
+        (2..)
+            .map(|n| format!("{} {}", base, n))
+            .find(|name| !taken(name))
+            .unwrap_or_default()
+    }
+
+    /// Rules watching the session named `name`
+    pub fn watching<'a>(&'a self, name: &'a str) -> impl Iterator<Item = &'a Rule> + 'a {
+        self.rules
+            .iter()
+            .filter(move |r| glob_match(&r.spec.when, name))
+    }
+
+    /// Rules fired by `transitions`, in order
+    ///
+    /// One-shot rules fire at most once, even for several transitions in one
+    /// poll, and are marked spent in `flags` as they fire.
+    pub fn evaluate(&self, transitions: &[Transition], flags: &mut RuleFlags) -> Vec<Firing> {
+        let mut firings = Vec::new();
+        for transition in transitions {
+            for rule in &self.rules {
+                if !flags.is_enabled(rule)
+                    || flags.is_spent(rule)
+                    || !rule.spec.on.matches(transition)
+                    || !glob_match(&rule.spec.when, &transition.session)
+                {
+                    continue;
+                }
+                if rule.spec.once {
+                    flags.spent.insert(rule.key.clone());
+                }
+                firings.push(Firing {
+                    rule: rule.key.clone(),
+                    transition: transition.clone(),
+                    action: rule.action.clone(),
+                });
+            }
+        }
+        firings
+    }
+}
+
+/// Whether `name` matches `pattern`, where `*` is any run of characters and `?` any one
+pub fn glob_match(pattern: &str, name: &str) -> bool {
+    let pattern: Vec<char> = pattern.chars().collect();
+    let name: Vec<char> = name.chars().collect();
+    let (mut p, mut n) = (0, 0);
+    // Where the last `*` was, and how much of the name it has taken
+    let mut star: Option<(usize, usize)> = None;
+    while n < name.len() {
+        match pattern.get(p) {
+            Some('*') => {
+                star = Some((p, n));
+                p += 1;
+            }
+            Some(&c) if c == '?' || c == name[n] => {
+                p += 1;
+                n += 1;
+            }
+            _ => match star {
+                Some((star_p, star_n)) => {
+                    p = star_p + 1;
+                    n = star_n + 1;
+                    star = Some((star_p, star_n + 1));
+                }
+                None => return false,
+            },
+        }
+    }
+    pattern[p..].iter().all(|&c| c == '*')
+}
+
+/// Parse a rule typed in the dashboard for the session `session`
+///
+/// `[once] <status or from->to> send <session> <prompt>`, `... run <command>`
+/// or `... notify <text>`.
+pub fn parse_line(session: &str, line: &str) -> Result<RuleSpec, String> {
+    const USAGE: &str =
+        "expected [once] <status> send <session> <prompt> | run <command> | notify <text>";
+    let mut rest = line.trim();
+    let mut word = || {
+        let (head, tail) = rest.split_once(char::is_whitespace).unwrap_or((rest, ""));
+        rest = tail.trim_start();
+        head
+    };
+    let mut first = word();
+    let once = first == "once";
+    if once {
+        first = word();
+    }
+    if first.is_empty() {
+        return Err(USAGE.to_string());
+    }
+    let on = Trigger::try_from(first.to_string())?;
+    let verb = word();
+    let mut spec = RuleSpec {
+        name: None,
+        when: session.to_string(),
+        on,
+        send_to: None,
+        prompt: None,
+        run: None,
+        notify: None,
+        once,
+        enabled: true,
+    };
+    match verb {
+        "send" => {
+            let target = word();
+            spec.send_to = Some(target.to_string()).filter(|t| !t.is_empty());
+            spec.prompt = Some(rest.to_string()).filter(|p| !p.is_empty());
+        }
+        "run" => spec.run = Some(rest.to_string()).filter(|c| !c.is_empty()),
+        "notify" => spec.notify = Some(rest.to_string()).filter(|t| !t.is_empty()),
+        _ => return Err(USAGE.to_string()),
+    }
+    spec.action().map_err(|_| USAGE.to_string())?;
+    Ok(spec)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tmux::AgentStatus;
+
+    fn transition(id: &str, name: &str, from: AgentStatus, to: AgentStatus) -> Transition {
+        Transition {
+            session_id: id.to_string(),
+            session: name.to_string(),
+            previous: from,
+            status: to,
+        }
+    }
+
+    fn spec(when: &str, on: &str, once: bool) -> RuleSpec {
+        RuleSpec {
+            name: None,
+            when: when.to_string(),
+            on: Trigger::try_from(on.to_string()).unwrap(),
+            send_to: Some("tests".to_string()),
+            prompt: Some("run the suite".to_string()),
+            run: None,
+            notify: None,
+            once,
+            enabled: true,
+        }
+    }
+
+    fn fired(firings: &[Firing]) -> Vec<(&str, &str)> {
+        firings
+            .iter()
+            .map(|f| (f.rule.as_str(), f.transition.session_id.as_str()))
+            .collect()
+    }
+
+    #[test]
+    fn test_glob_match() {
+        assert!(glob_match("*", ""));
+        assert!(glob_match("refactor", "refactor"));
+        assert!(!glob_match("refactor", "refactor-2"));
+        assert!(glob_match("refactor*", "refactor-2"));
+        assert!(glob_match("*-api", "payments-api"));
+        assert!(glob_match("a*b*c", "a-b-b-c"));
+        assert!(!glob_match("a*b*c", "a-b-b-d"));
+        assert!(glob_match("agent-?", "agent-3"));
+        assert!(!glob_match("agent-?", "agent-10"));
+        assert!(glob_match("ü*", "über"));
+    }
+
+    #[test]
+    fn test_spec_action() {
+        assert!(matches!(
+            spec("*", "idle", false).action(),
+            Ok(RuleAction::Send { .. })
+        ));
+        let mut both = spec("*", "idle", false);
+        both.run = Some("make test".to_string());
+        assert!(both.action().is_err());
+        let mut no_prompt = spec("*", "idle", false);
+        no_prompt.prompt = None;
+        assert!(no_prompt.action().unwrap_err().contains("prompt"));
+    }
+
+    #[test]
+    fn test_matching_and_keys() {
+        let mut named = spec("refactor*", "busy->idle", false);
+        named.name = Some("tests after refactor".to_string());
+        let book = RuleBook::new(&[named, spec("*", "error", false)], &[]);
+        assert_eq!(book.rules[0].key, "tests after refactor");
+        assert_eq!(book.rules[1].key, "rules[1]");
+
+        let mut flags = RuleFlags::default();
+        let firings = book.evaluate(
+            &[
+                transition("$1", "refactor-ui", AgentStatus::Busy, AgentStatus::Idle),
+                // Idle, but not from Busy
+                transition("$2", "refactor-db", AgentStatus::Unknown, AgentStatus::Idle),
+                transition("$3", "docs", AgentStatus::Busy, AgentStatus::Error),
+                transition("$4", "docs", AgentStatus::Busy, AgentStatus::Idle),
+            ],
+            &mut flags,
+        );
+        assert_eq!(
+            fired(&firings),
+            [("tests after refactor", "$1"), ("rules[1]", "$3")]
+        );
+        assert_eq!(
+            firings[0].action,
+            RuleAction::Send {
+                session: "tests".to_string(),
+                prompt: "run the suite".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn test_one_shot_fires_once_across_recreated_sessions() {
+        let book = RuleBook::new(&[spec("refactor", "idle", true)], &[]);
+        let mut flags = RuleFlags::default();
+        let idle = transition("$1", "refactor", AgentStatus::Busy, AgentStatus::Idle);
+
+        // Two matching transitions in one poll still fire once
+        let again = transition("$9", "refactor", AgentStatus::Error, AgentStatus::Idle);
+        let firings = book.evaluate(&[idle.clone(), again], &mut flags);
+        assert_eq!(fired(&firings), [("rules[0]", "$1")]);
+        assert!(flags.spent.contains("rules[0]"));
+
+        // The session is killed and started again under the same name: still spent
+        let recreated = transition("$2", "refactor", AgentStatus::Busy, AgentStatus::Idle);
+        assert!(
+            book.evaluate(std::slice::from_ref(&recreated), &mut flags)
+                .is_empty()
+        );
+
+        // Switching it back on re-arms it
+        assert!(book.rules[0].spec.once);
+        assert!(flags.toggle(&book.rules[0]));
+        assert!(flags.toggles.is_empty());
+        assert_eq!(
+            fired(&book.evaluate(&[recreated], &mut flags)),
+            [("rules[0]", "$2")]
+        );
+    }
+
+    #[test]
+    fn test_repeating_rules_follow_the_name() {
+        let book = RuleBook::new(&[spec("refactor", "idle", false)], &[]);
+        let mut flags = RuleFlags::default();
+        let old = transition("$1", "refactor", AgentStatus::Busy, AgentStatus::Idle);
+        let new = transition("$2", "refactor", AgentStatus::Busy, AgentStatus::Idle);
+        assert_eq!(
+            fired(&book.evaluate(&[old.clone(), new], &mut flags)),
+            [("rules[0]", "$1"), ("rules[0]", "$2")]
+        );
+        assert!(flags.spent.is_empty());
+        assert_eq!(book.evaluate(&[old], &mut flags).len(), 1);
+    }
+
+    #[test]
+    fn test_toggles() {
+        let mut off = spec("*", "idle", false);
+        off.enabled = false;
+        let book = RuleBook::new(&[spec("*", "idle", false), off], &[]);
+        let mut flags = RuleFlags::default();
+        let idle = [transition(
+            "$1",
+            "api",
+            AgentStatus::Busy,
+            AgentStatus::Idle,
+        )];
+        assert_eq!(
+            fired(&book.evaluate(&idle, &mut flags)),
+            [("rules[0]", "$1")]
+        );
+
+        assert!(!flags.toggle(&book.rules[0]));
+        assert!(flags.toggle(&book.rules[1]));
+        assert_eq!(
+            fired(&book.evaluate(&idle, &mut flags)),
+            [("rules[1]", "$1")]
+        );
+
+        // Back to how the config has them, so nothing is stored
+        flags.toggle(&book.rules[0]);
+        flags.toggle(&book.rules[1]);
+        assert_eq!(flags, RuleFlags::default());
+    }
+
+    #[test]
+    fn test_local_rules_and_names() {
+        let mut local = spec("api", "idle", false);
+        local.name = Some("api idle".to_string());
+        let book = RuleBook::new(&[spec("*", "error", false)], &[local.clone()]);
+        assert!(book.rules[1].local);
+        assert_eq!(book.local_specs(), [local]);
+        assert_eq!(book.unique_name("api idle"), "api idle 2");
+        assert_eq!(book.unique_name("web idle"), "web idle");
+        assert_eq!(book.watching("api").count(), 2);
+        assert_eq!(book.watching("web").count(), 1);
+
+        // A broken saved rule is skipped rather than failing startup
+        let mut broken = spec("api", "idle", false);
+        broken.prompt = None;
+        assert_eq!(RuleBook::new(&[], &[broken]).rules.len(), 0);
+    }
+
+    #[test]
+    fn test_parse_line() {
+        let spec = parse_line("refactor", "idle send tests run the whole suite").unwrap();
+        assert_eq!(spec.when, "refactor");
+        assert_eq!(spec.on, Trigger::Enter(AgentStatus::Idle));
+        assert_eq!(spec.send_to.as_deref(), Some("tests"));
+        assert_eq!(spec.prompt.as_deref(), Some("run the whole suite"));
+        assert!(!spec.once);
+
+        let spec = parse_line("api", " once busy->idle  run make test ").unwrap();
+        assert!(spec.once);
+        assert_eq!(
+            spec.on,
+            Trigger::Change(AgentStatus::Busy, AgentStatus::Idle)
+        );
+        assert_eq!(spec.run.as_deref(), Some("make test"));
+
+        let spec = parse_line("api", "error notify api broke").unwrap();
+        assert_eq!(spec.notify.as_deref(), Some("api broke"));
+
+        for bad in [
+            "",
+            "once",
+            "idle",
+            "idle send tests",
+            "idle run",
+            "asleep notify hi",
+            "idle email me",
+        ] {
+            assert!(parse_line("api", bad).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_spec_round_trip() {
+        let mut spec = spec("refactor*", "busy->idle", true);
+        spec.name = Some("chain".to_string());
+        let json = serde_json::to_string(&spec).unwrap();
+        assert!(json.contains("\"on\":\"busy->idle\""), "{}", json);
+        assert!(!json.contains("notify"), "{}", json);
+        assert_eq!(serde_json::from_str::<RuleSpec>(&json).unwrap(), spec);
+    }
+}
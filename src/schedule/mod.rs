@@ -0,0 +1,210 @@
+//! Prompts queued for delivery to a session at a set time.
+//!
+//! Schedules are kept in the state file, which is the source of truth: the
+//! CLI edits it directly and the dashboard re-reads it before touching them.
+//! A due prompt is removed from the file before it is sent, so it fires at
+//! most once, even if the dashboard was closed when it fell due.
+
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeDelta, TimeZone};
+use serde::{Deserialize, Serialize};
+
+use crate::text;
+
+/// A prompt waiting to be typed into a session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ScheduledPrompt {
+    pub id: u64,
+    /// Target session name; looked up when the prompt fires
+    pub session: String,
+    pub prompt: String,
+    /// Unix timestamp to send at
+    pub at: u64,
+}
+
+/// Parse when to send: `+30m`, `+1h30m`, `14:00`, or `2026-10-17 14:00`
+///
+/// A time of day that has already passed today means tomorrow.
+pub fn parse_when<Tz: TimeZone>(input: &str, now: &DateTime<Tz>) -> Result<DateTime<Tz>, String> {
+    let input = input.trim();
+    if let Some(relative) = input.strip_prefix('+') {
+        return parse_relative(relative)
+            .map(|delta| now.clone() + delta)
+            .ok_or_else(|| format!("'{}' isn't a delay like +30m or +1h30m", input));
+    }
+
+    let zone = now.timezone();
+    if let Some(time) = parse_time(input) {
+        let today = now.date_naive().and_time(time);
+        let at = zone
+            .from_local_datetime(&today)
+            .earliest()
+            .ok_or_else(|| format!("{} doesn't exist today", input))?;
+        return Ok(if at <= *now {
+            at + TimeDelta::days(1)
+        } else {
+            at
+        });
+    }
+
+    let datetime = ["%Y-%m-%d %H:%M", "%Y-%m-%d %H:%M:%S", "%Y-%m-%dT%H:%M"]
+        .iter()
+        .find_map(|format| NaiveDateTime::parse_from_str(input, format).ok())
+        .ok_or_else(|| {
+            format!(
+                "'{}' isn't a time (try +30m, 14:00 or 2026-10-17 14:00)",
+                input
+            )
+        })?;
+    let at = zone
+        .from_local_datetime(&datetime)
+        .earliest()
+        .ok_or_else(|| format!("{} doesn't exist in the local time zone", input))?;
+    if at <= *now {
+        return Err(format!("{} is in the past", input));
+    }
+    Ok(at)
+}
+
+/// `30m`, `1h30m`, `90s` or `2d`; at least one unit, no sign
+fn parse_relative(input: &str) -> Option<TimeDelta> {
+    let mut total = TimeDelta::zero();
+    let mut digits = String::new();
+    for c in input.chars() {
+        if c.is_ascii_digit() {
+            digits.push(c);
+            continue;
+        }
+        let count: i64 = std::mem::take(&mut digits).parse().ok()?;
+        total += match c {
+            's' => TimeDelta::try_seconds(count)?,
+            'm' => TimeDelta::try_minutes(count)?,
+            'h' => TimeDelta::try_hours(count)?,
+            'd' => TimeDelta::try_days(count)?,
+            _ => return None,
+        };
+    }
+    (digits.is_empty() && total > TimeDelta::zero()).then_some(total)
+}
+
+fn parse_time(input: &str) -> Option<NaiveTime> {
+    NaiveTime::parse_from_str(input, "%H:%M")
+        .or_else(|_| NaiveTime::parse_from_str(input, "%H:%M:%S"))
+        .ok()
+}
+
+/// Parse `input` against the local clock, as a Unix timestamp
+pub fn parse_when_local(input: &str) -> Result<u64, String> {
+    let at = parse_when(input, &Local::now())?;
+    Ok(at.timestamp().max(0) as u64)
+}
+
+/// When a prompt fires, for display: `14:00 (in 25m 0s)`, with the date if not today
+pub fn format_at(at: u64, now: u64) -> String {
+    let Some(local) = Local.timestamp_opt(at as i64, 0).single() else {
+        return at.to_string();
+    };
+    let today = Local
+        .timestamp_opt(now as i64, 0)
+        .single()
+        .map(|n| n.date_naive());
+    let clock = if today == Some(local.date_naive()) {
+        local.format("%H:%M").to_string()
+    } else {
+        local.format("%Y-%m-%d %H:%M").to_string()
+    };
+    if at <= now {
+        format!("{} (due)", clock)
+    } else {
+        format!("{} (in {})", clock, text::format_duration_long(at - now))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::{FixedOffset, Utc};
+
+    fn at(s: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(s).unwrap().with_timezone(&Utc)
+    }
+
+    #[test]
+    fn test_relative() {
+        let now = at("2026-10-16T10:00:00Z");
+        assert_eq!(
+            parse_when("+30m", &now).unwrap(),
+            at("2026-10-16T10:30:00Z")
+        );
+        assert_eq!(
+            parse_when(" +1h30m ", &now).unwrap(),
+            at("2026-10-16T11:30:00Z")
+        );
+        assert_eq!(
+            parse_when("+90s", &now).unwrap(),
+            at("2026-10-16T10:01:30Z")
+        );
+        assert_eq!(parse_when("+2d", &now).unwrap(), at("2026-10-18T10:00:00Z"));
+        for bad in ["+30", "+", "+0m", "+m", "+30x", "+-5m", "+1h30"] {
+            assert!(parse_when(bad, &now).is_err(), "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_time_of_day() {
+        let now = at("2026-10-16T10:00:00Z");
+        assert_eq!(
+            parse_when("14:00", &now).unwrap(),
+            at("2026-10-16T14:00:00Z")
+        );
+        assert_eq!(
+            parse_when("14:00:30", &now).unwrap(),
+            at("2026-10-16T14:00:30Z")
+        );
+        // Already past today, so tomorrow; the current minute counts as past
+        assert_eq!(
+            parse_when("09:15", &now).unwrap(),
+            at("2026-10-17T09:15:00Z")
+        );
+        assert_eq!(
+            parse_when("10:00", &now).unwrap(),
+            at("2026-10-17T10:00:00Z")
+        );
+        assert!(parse_when("25:00", &now).is_err());
+    }
+
+    #[test]
+    fn test_time_of_day_uses_the_local_zone() {
+        let zone = FixedOffset::east_opt(2 * 3600).unwrap();
+        let now = at("2026-10-16T10:00:00Z").with_timezone(&zone);
+        // 12:00 local is now, so 14:00 local is two hours away
+        assert_eq!(
+            parse_when("14:00", &now).unwrap(),
+            at("2026-10-16T12:00:00Z")
+        );
+    }
+
+    #[test]
+    fn test_absolute() {
+        let now = at("2026-10-16T10:00:00Z");
+        assert_eq!(
+            parse_when("2026-10-17 14:00", &now).unwrap(),
+            at("2026-10-17T14:00:00Z")
+        );
+        assert_eq!(
+            parse_when("2026-10-17T14:00", &now).unwrap(),
+            at("2026-10-17T14:00:00Z")
+        );
+        let error = parse_when("2026-10-15 14:00", &now).unwrap_err();
+        assert!(error.contains("in the past"), "{}", error);
+        assert!(parse_when("tomorrow", &now).is_err());
+    }
+
+    #[test]
+    fn test_format_at() {
+        let now = text::unix_now();
+        assert!(format_at(now + 1500, now).ends_with("(in 25m 0s)"));
+        assert!(format_at(now, now).ends_with("(due)"));
+        // Far enough out to be another day in any zone
+        assert!(format_at(now + 3 * 86400, now).contains('-'));
+    }
+}
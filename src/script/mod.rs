@@ -0,0 +1,103 @@
+//! Shell scripts built from user templates with `{placeholder}`s.
+//!
+//! Custom commands and hooks both fill values into a template the user wrote
+//! for `sh -c`. Each placeholder is replaced with a reference to a shell
+//! variable the script sets first, quoted for where it appears: as a word of
+//! its own, inside single or double quotes, or in a `$(...)` substitution.
+//! Values never become part of the script's text, so they can't be read as
+//! shell syntax wherever the placeholder is. A placeholder preceded by a
+//! backslash outside single quotes is left as written.
+
+/// Quote `value` as a single POSIX shell word
+pub fn shell_quote(value: &str) -> String {
+    format!("'{}'", value.replace('\'', r"'\''"))
+}
+
+/// Quoting in effect at a point of the template
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Quoting {
+    Unquoted,
+    Single,
+    Double,
+}
+
+/// Turn `template` into a script that sets each `(placeholder, variable,
+/// value)` variable and refers to it where the placeholder was
+pub fn expand(template: &str, placeholders: &[(&str, &str, &str)]) -> String {
+    let mut expanded = String::with_capacity(template.len() + 128);
+    for (_, variable, value) in placeholders {
+        expanded.push_str(&format!("{}={}\n", variable, shell_quote(value)));
+    }
+    let mut quoting = Quoting::Unquoted;
+    // Quoting outside each `$(` the template is in
+    let mut outer = Vec::new();
+    let mut chars = template.char_indices().peekable();
+    while let Some((at, c)) = chars.next() {
+        if c == '{'
+            && let Some((variable, len)) = placeholder(&template[at..], placeholders)
+        {
+            expanded.push_str(&reference(variable, quoting));
+            while chars.next_if(|&(next, _)| next < at + len).is_some() {}
+            continue;
+        }
+        expanded.push(c);
+        match (quoting, c) {
+            (Quoting::Unquoted, '\'') => quoting = Quoting::Single,
+            (Quoting::Single, '\'') => quoting = Quoting::Unquoted,
+            (Quoting::Unquoted, '"') => quoting = Quoting::Double,
+            (Quoting::Double, '"') => quoting = Quoting::Unquoted,
+            // A substitution starts afresh, even inside double quotes
+            (Quoting::Unquoted | Quoting::Double, '$')
+                if chars.next_if(|&(_, next)| next == '(').is_some() =>
+            {
+                expanded.push('(');
+                outer.push(quoting);
+                quoting = Quoting::Unquoted;
+            }
+            (Quoting::Unquoted, '(') if !outer.is_empty() => outer.push(Quoting::Unquoted),
+            (Quoting::Unquoted, ')') => {
+                if let Some(previous) = outer.pop() {
+                    quoting = previous;
+                }
+            }
+            // The escaped character is taken as written, even a quote or a brace
+            (Quoting::Unquoted | Quoting::Double, '\\') => {
+                if let Some((_, escaped)) = chars.next() {
+                    expanded.push(escaped);
+                }
+            }
+            _ => {}
+        }
+    }
+    expanded
+}
+
+/// Variable of the placeholder `text` starts with, and the placeholder's length
+fn placeholder<'a>(text: &str, placeholders: &[(&str, &'a str, &str)]) -> Option<(&'a str, usize)> {
+    let end = text.find('}')?;
+    let (_, variable, _) = placeholders
+        .iter()
+        .find(|(name, _, _)| *name == &text[1..end])?;
+    Some((variable, end + 1))
+}
+
+/// Expansion of `variable` as one word, whatever the quoting around it
+fn reference(variable: &str, quoting: Quoting) -> String {
+    match quoting {
+        Quoting::Unquoted => format!("\"${}\"", variable),
+        Quoting::Double => format!("${{{}}}", variable),
+        // Close the quote, expand in double quotes, and reopen it
+        Quoting::Single => format!("'\"${}\"'", variable),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_shell_quote() {
+        assert_eq!(shell_quote(""), "''");
+        assert_eq!(shell_quote("it's"), r"'it'\''s'");
+    }
+}
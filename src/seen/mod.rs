@@ -0,0 +1,125 @@
+//! Records kept by session name until the name has been gone for 30 days.
+//!
+//! Attach history, descriptions and tags outlive restarts this way. Each
+//! record carries when its name was last listed, to a day's resolution, so
+//! refreshing it on every poll doesn't mean saving the state on every poll.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+/// A name not seen for this long has its record forgotten
+const FORGET_AFTER_SECS: u64 = 30 * 24 * 3600;
+/// How stale a recorded sighting may get before it is refreshed (and saved)
+const SEEN_RESOLUTION_SECS: u64 = 24 * 3600;
+
+/// A record that knows when its name was last listed
+pub trait Sighted {
+    /// Unix time the name was last listed
+    fn seen_at(&mut self) -> &mut u64;
+}
+
+/// Records by session name, forgotten 30 days after the name was last listed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct SeenByName<R> {
+    entries: BTreeMap<String, R>,
+}
+
+impl<R> Default for SeenByName<R> {
+    fn default() -> Self {
+        Self {
+            entries: BTreeMap::new(),
+        }
+    }
+}
+
+impl<R: Sighted> SeenByName<R> {
+    pub fn get(&self, name: &str) -> Option<&R> {
+        self.entries.get(name)
+    }
+
+    pub fn insert(&mut self, name: &str, record: R) {
+        self.entries.insert(name.to_string(), record);
+    }
+
+    pub fn remove(&mut self, name: &str) -> Option<R> {
+        self.entries.remove(name)
+    }
+
+    /// Move a session's record to its new name
+    pub fn rename(&mut self, old: &str, new: &str) {
+        if let Some(record) = self.entries.remove(old) {
+            self.entries.insert(new.to_string(), record);
+        }
+    }
+
+    /// Note which names are still listed and forget those unseen for 30 days
+    ///
+    /// Returns whether anything changed enough to be worth saving.
+    pub fn refresh<'a>(&mut self, listed: impl IntoIterator<Item = &'a str>, now: u64) -> bool {
+        let mut changed = false;
+        for name in listed {
+            if let Some(record) = self.entries.get_mut(name) {
+                let seen_at = record.seen_at();
+                if now.saturating_sub(*seen_at) >= SEEN_RESOLUTION_SECS {
+                    *seen_at = now;
+                    changed = true;
+                }
+            }
+        }
+        let before = self.entries.len();
+        self.entries
+            .retain(|_, record| now.saturating_sub(*record.seen_at()) < FORGET_AFTER_SECS);
+        changed || self.entries.len() != before
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const DAY: u64 = 24 * 3600;
+
+    #[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+    struct Record {
+        seen_at: u64,
+    }
+
+    impl Sighted for Record {
+        fn seen_at(&mut self) -> &mut u64 {
+            &mut self.seen_at
+        }
+    }
+
+    #[test]
+    fn test_refresh_forgets_names_gone_for_30_days() {
+        let mut seen = SeenByName::default();
+        seen.insert("api", Record { seen_at: 0 });
+        seen.insert("gone", Record { seen_at: 0 });
+        // Seen again within the day: nothing worth saving
+        assert!(!seen.refresh(["api"], DAY - 1));
+
+        assert!(seen.refresh(["api"], 20 * DAY));
+        assert!(!seen.refresh(["api"], 20 * DAY + 5));
+        assert!(seen.refresh(["api"], 30 * DAY));
+        assert_eq!(seen.get("gone"), None);
+        assert_eq!(seen.get("api"), Some(&Record { seen_at: 30 * DAY }));
+        assert!(!seen.refresh([], 59 * DAY));
+        assert!(seen.refresh([], 60 * DAY));
+        assert_eq!(seen, SeenByName::default());
+    }
+
+    #[test]
+    fn test_rename_keeps_the_record() {
+        let mut seen = SeenByName::default();
+        seen.insert("api", Record { seen_at: 5 });
+        seen.rename("api", "api-v2");
+        seen.rename("missing", "other");
+        assert_eq!(seen.get("api"), None);
+        assert_eq!(seen.get("api-v2"), Some(&Record { seen_at: 5 }));
+        assert_eq!(
+            serde_json::to_string(&seen).unwrap(),
+            r#"{"api-v2":{"seen_at":5}}"#
+        );
+    }
+}
@@ -0,0 +1,920 @@
+use serde::{Deserialize, Serialize};
+use std::cmp::Ordering;
+use std::collections::BTreeSet;
+
+use crate::activity;
+use crate::jump_list::AttachHistory;
+use crate::tmux::{AgentStatus, TmuxSession};
+
+/// How the session list is laid out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+pub enum ListDisplay {
+    /// Sessions in tmux order
+    #[default]
+    Flat,
+    /// Sessions bucketed under status headers
+    Grouped,
+}
+
+impl ListDisplay {
+    /// Next mode in the display cycle
+    pub fn next(self) -> Self {
+        match self {
+            ListDisplay::Flat => ListDisplay::Grouped,
+            ListDisplay::Grouped => ListDisplay::Flat,
+        }
+    }
+}
+
+/// Renderer used for the session list
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ListLayout {
+    /// One status icon and name per line
+    #[default]
+    Compact,
+    /// Aligned columns with per-session details
+    Table,
+}
+
+impl ListLayout {
+    pub fn toggle(self) -> Self {
+        match self {
+            ListLayout::Compact => ListLayout::Table,
+            ListLayout::Table => ListLayout::Compact,
+        }
+    }
+}
+
+/// Columns of the table layout
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Column {
+    Status,
+    Name,
+    Agent,
+    Age,
+    Activity,
+    /// Sparkline of recent output
+    Output,
+    Clients,
+    /// When you last attached
+    Used,
+}
+
+impl Column {
+    pub const ALL: [Column; 8] = [
+        Column::Status,
+        Column::Name,
+        Column::Agent,
+        Column::Age,
+        Column::Activity,
+        Column::Output,
+        Column::Clients,
+        Column::Used,
+    ];
+
+    /// Order in which columns are dropped as the pane narrows
+    const DROP_ORDER: [Column; 6] = [
+        Column::Used,
+        Column::Clients,
+        Column::Output,
+        Column::Activity,
+        Column::Age,
+        Column::Agent,
+    ];
+
+    pub fn title(self) -> &'static str {
+        match self {
+            Column::Status => "",
+            Column::Name => "Name",
+            Column::Agent => "Agent",
+            Column::Age => "Age",
+            Column::Activity => "Active",
+            Column::Output => "Output",
+            Column::Clients => "Clients",
+            Column::Used => "Used",
+        }
+    }
+
+    /// Lowercase name used when describing the sort order
+    pub fn label(self) -> &'static str {
+        match self {
+            Column::Status => "status",
+            Column::Name => "name",
+            Column::Agent => "agent",
+            Column::Age => "age",
+            Column::Activity => "activity",
+            Column::Output => "output",
+            Column::Clients => "clients",
+            Column::Used => "recently used",
+        }
+    }
+
+    /// Narrowest width the column is rendered at
+    pub fn min_width(self) -> u16 {
+        match self {
+            Column::Status => 1,
+            Column::Name => 10,
+            Column::Agent => 6,
+            Column::Age => 4,
+            Column::Activity => 6,
+            Column::Output => activity::LIST_CELLS as u16,
+            Column::Clients => 7,
+            Column::Used => 5,
+        }
+    }
+
+    /// Sorted by even when not on screen; the compact list has no columns to show it in
+    fn always_sortable(self) -> bool {
+        self == Column::Used
+    }
+
+    /// Compare two sessions by this column
+    pub fn compare(self, a: &TmuxSession, b: &TmuxSession, attached: &AttachHistory) -> Ordering {
+        match self {
+            Column::Status => StatusGroup::of(a.status).cmp(&StatusGroup::of(b.status)),
+            Column::Name => a.name.cmp(&b.name),
+            Column::Agent => a.agent_kind().cmp(&b.agent_kind()),
+            // Youngest first
+            Column::Age => b.created_at.cmp(&a.created_at),
+            // Most recently active first
+            Column::Activity => b.activity_at.cmp(&a.activity_at),
+            // Most output over the window first
+            Column::Output => recent_output(b).cmp(&recent_output(a)),
+            Column::Clients => b.attached_clients.cmp(&a.attached_clients),
+            Column::Used => attached.compare(&a.name, &b.name),
+        }
+        .then_with(|| a.name.cmp(&b.name))
+    }
+}
+
+fn recent_output(session: &TmuxSession) -> u32 {
+    session.activity_trend.iter().sum()
+}
+
+/// Columns that fit in `width` cells, dropping the least important first
+pub fn visible_columns(width: u16) -> Vec<Column> {
+    let mut columns = Column::ALL.to_vec();
+    let needed = |columns: &[Column]| -> u16 {
+        let spacing = columns.len().saturating_sub(1) as u16;
+        columns.iter().map(|c| c.min_width()).sum::<u16>() + spacing
+    };
+    for drop in Column::DROP_ORDER {
+        if needed(&columns) <= width {
+            break;
+        }
+        columns.retain(|&c| c != drop);
+    }
+    columns
+}
+
+/// Width left for the name column once the other `columns` are laid out
+pub fn name_width(columns: &[Column], width: u16) -> u16 {
+    let spacing = columns.len().saturating_sub(1) as u16;
+    let others: u16 = columns
+        .iter()
+        .filter(|&&c| c != Column::Name)
+        .map(|c| c.min_width())
+        .sum();
+    width.saturating_sub(others + spacing)
+}
+
+/// Next sort column in the cycle, skipping columns not in `visible`;
+/// `None` keeps tmux's order
+pub fn next_sort(current: Option<Column>, visible: &[Column]) -> Option<Column> {
+    let start = current.map_or(0, |c| {
+        Column::ALL.iter().position(|&x| x == c).unwrap_or(0) + 1
+    });
+    Column::ALL[start.min(Column::ALL.len())..]
+        .iter()
+        .copied()
+        .find(|c| visible.contains(c) || c.always_sortable())
+}
+
+/// Sessions arranged by hand with `J`/`K`, by name
+///
+/// Empty until the first move, so the list keeps tmux's order until then.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct ManualOrder(Vec<String>);
+
+impl ManualOrder {
+    pub fn is_empty(&self) -> bool {
+        self.0.is_empty()
+    }
+
+    /// Names in the order first; others after them, keeping their order
+    pub fn compare(&self, a: &str, b: &str) -> Ordering {
+        let at = |name: &str| self.0.iter().position(|n| n == name).unwrap_or(usize::MAX);
+        at(a).cmp(&at(b))
+    }
+
+    /// Drop names no longer listed and add new ones at the bottom; whether
+    /// anything changed. An empty listing (no server) keeps the order.
+    pub fn refresh<'a>(&mut self, listed: impl IntoIterator<Item = &'a str>) -> bool {
+        let listed: Vec<&str> = listed.into_iter().collect();
+        if self.0.is_empty() || listed.is_empty() {
+            return false;
+        }
+        let before = self.0.clone();
+        self.0.retain(|name| listed.contains(&name.as_str()));
+        for name in listed {
+            if !self.0.iter().any(|n| n == name) {
+                self.0.push(name.to_string());
+            }
+        }
+        self.0 != before
+    }
+
+    /// Put `name` right before `neighbor`, or right after it with `after`;
+    /// the first move takes over the order the list shows, `shown`
+    pub fn place<'a>(
+        &mut self,
+        shown: impl IntoIterator<Item = &'a str>,
+        name: &str,
+        neighbor: &str,
+        after: bool,
+    ) {
+        for listed in shown {
+            if !self.0.iter().any(|n| n == listed) {
+                self.0.push(listed.to_string());
+            }
+        }
+        self.0.retain(|n| n != name);
+        let at = self
+            .0
+            .iter()
+            .position(|n| n == neighbor)
+            .map_or(self.0.len(), |i| i + usize::from(after));
+        self.0.insert(at, name.to_string());
+    }
+
+    pub fn rename(&mut self, old: &str, new: &str) {
+        for name in self.0.iter_mut().filter(|n| *n == old) {
+            *name = new.to_string();
+        }
+    }
+}
+
+/// Status buckets used by the grouped view, in display order
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum StatusGroup {
+    NeedsAttention,
+    Working,
+    Idle,
+    Other,
+}
+
+impl StatusGroup {
+    pub const ALL: [StatusGroup; 4] = [
+        StatusGroup::NeedsAttention,
+        StatusGroup::Working,
+        StatusGroup::Idle,
+        StatusGroup::Other,
+    ];
+
+    pub fn of(status: AgentStatus) -> Self {
+        match status {
+            AgentStatus::WaitingForInput | AgentStatus::Error => StatusGroup::NeedsAttention,
+            AgentStatus::Busy => StatusGroup::Working,
+            AgentStatus::Idle => StatusGroup::Idle,
+            AgentStatus::Unknown => StatusGroup::Other,
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            StatusGroup::NeedsAttention => "Needs attention",
+            StatusGroup::Working => "Working",
+            StatusGroup::Idle => "Idle",
+            StatusGroup::Other => "Other",
+        }
+    }
+}
+
+/// One row of the rendered session list
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ListRow {
+    /// Section header in the grouped view
+    Header {
+        group: StatusGroup,
+        count: usize,
+        collapsed: bool,
+    },
+    /// A session, by index into the session vector
+    Session(usize),
+    /// A window of an expanded session: the session's index and the window's
+    /// position among those listed for it
+    Window(usize, usize),
+    /// Header naming the tmux server of the sessions below it, by index of
+    /// the first of them; only when several servers are watched
+    Server(usize),
+}
+
+impl ListRow {
+    /// Expanded headers are skipped by navigation; collapsed ones are the only
+    /// way back into their section, so they stay selectable
+    pub fn is_selectable(&self) -> bool {
+        !matches!(
+            self,
+            ListRow::Header {
+                collapsed: false,
+                ..
+            } | ListRow::Server(_)
+        )
+    }
+}
+
+/// Build the list rows for `sessions` in the given display mode, ordered by
+/// `sort`, else by `manual`
+///
+/// Sessions of several servers are listed server by server, each under a
+/// header, in the order the servers were polled. Only sessions matching
+/// `filter` are listed, and headers are left out when none of theirs are.
+pub fn build_rows(
+    sessions: &[TmuxSession],
+    filter: &str,
+    display: ListDisplay,
+    collapsed: &BTreeSet<StatusGroup>,
+    sort: Option<Column>,
+    attached: &AttachHistory,
+    manual: &ManualOrder,
+) -> Vec<ListRow> {
+    // Without a hand-made order groups fall back to name order, and the flat
+    // list keeps tmux's order
+    let compare = |a: &usize, b: &usize| match (sort, display) {
+        (Some(column), _) => column.compare(&sessions[*a], &sessions[*b], attached),
+        (None, ListDisplay::Grouped) if manual.is_empty() => {
+            sessions[*a].name.cmp(&sessions[*b].name)
+        }
+        (None, _) => manual.compare(&sessions[*a].name, &sessions[*b].name),
+    };
+
+    let shown: Vec<usize> = (0..sessions.len())
+        .filter(|&i| matches_filter(&sessions[i], filter))
+        .collect();
+    if sessions.iter().all(|s| s.socket.is_empty()) {
+        return build_section(sessions, shown, display, collapsed, compare);
+    }
+    let mut rows = Vec::new();
+    let mut done: Vec<&str> = Vec::new();
+    for (first, session) in sessions.iter().enumerate() {
+        if done.contains(&session.socket.as_str()) {
+            continue;
+        }
+        done.push(&session.socket);
+        let members: Vec<usize> = shown
+            .iter()
+            .copied()
+            .filter(|&i| sessions[i].socket == session.socket)
+            .collect();
+        if members.is_empty() {
+            continue;
+        }
+        rows.push(ListRow::Server(first));
+        rows.extend(build_section(
+            sessions, members, display, collapsed, compare,
+        ));
+    }
+    rows
+}
+
+/// Whether `session`'s name contains `filter`, ignoring case; `#tag` instead
+/// matches sessions with a tag starting with `tag`
+pub fn matches_filter(session: &TmuxSession, filter: &str) -> bool {
+    let filter = filter.to_lowercase();
+    match filter.strip_prefix('#') {
+        Some(tag) => session
+            .tags
+            .iter()
+            .any(|t| t.to_lowercase().starts_with(tag)),
+        None => session.name.to_lowercase().contains(&filter),
+    }
+}
+
+/// Rows for the sessions at `members`
+fn build_section(
+    sessions: &[TmuxSession],
+    members: Vec<usize>,
+    display: ListDisplay,
+    collapsed: &BTreeSet<StatusGroup>,
+    compare: impl Fn(&usize, &usize) -> Ordering,
+) -> Vec<ListRow> {
+    match display {
+        ListDisplay::Flat => {
+            let mut order = members;
+            order.sort_by(compare);
+            order.into_iter().map(ListRow::Session).collect()
+        }
+        ListDisplay::Grouped => {
+            let mut rows = Vec::new();
+            for group in StatusGroup::ALL {
+                let mut members: Vec<usize> = members
+                    .iter()
+                    .copied()
+                    .filter(|&i| StatusGroup::of(sessions[i].status) == group)
+                    .collect();
+                if members.is_empty() {
+                    continue;
+                }
+                members.sort_by(&compare);
+
+                let is_collapsed = collapsed.contains(&group);
+                rows.push(ListRow::Header {
+                    group,
+                    count: members.len(),
+                    collapsed: is_collapsed,
+                });
+                if !is_collapsed {
+                    rows.extend(members.into_iter().map(ListRow::Session));
+                }
+            }
+            rows
+        }
+    }
+}
+
+/// Next selectable row after `from`, wrapping around the ends
+pub fn step_selection(rows: &[ListRow], from: Option<usize>, forward: bool) -> Option<usize> {
+    let len = rows.len();
+    if len == 0 {
+        return None;
+    }
+    let start = match from {
+        Some(i) => i.min(len - 1),
+        None if forward => len - 1,
+        None => 0,
+    };
+    (1..=len)
+        .map(|step| {
+            if forward {
+                (start + step) % len
+            } else {
+                (start + len - step) % len
+            }
+        })
+        .find(|&i| rows[i].is_selectable())
+}
+
+/// First selectable row at or after `from`, falling back to the nearest one before it
+pub fn nearest_selectable(rows: &[ListRow], from: usize) -> Option<usize> {
+    let from = from.min(rows.len().checked_sub(1)?);
+    (from..rows.len())
+        .find(|&i| rows[i].is_selectable())
+        .or_else(|| (0..from).rev().find(|&i| rows[i].is_selectable()))
+}
+
+/// Group a row belongs to: its own for headers, the enclosing header's for sessions
+pub fn group_of_row(rows: &[ListRow], index: usize) -> Option<StatusGroup> {
+    rows.get(..=index)?.iter().rev().find_map(|row| match row {
+        ListRow::Header { group, .. } => Some(*group),
+        ListRow::Session(_) | ListRow::Window(..) | ListRow::Server(_) => None,
+    })
+}
+
+/// `rows` with each session's window rows under it; `windows(i)` is how many
+/// are listed for session `i`, none unless it is expanded
+pub fn with_windows(rows: Vec<ListRow>, windows: impl Fn(usize) -> usize) -> Vec<ListRow> {
+    let mut tree = Vec::with_capacity(rows.len());
+    for row in rows {
+        tree.push(row);
+        if let ListRow::Session(index) = row {
+            tree.extend((0..windows(index)).map(|window| ListRow::Window(index, window)));
+        }
+    }
+    tree
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn session(name: &str, status: AgentStatus) -> TmuxSession {
+        let mut s = TmuxSession::new(format!("${}", name), name.to_string());
+        s.status = status;
+        s
+    }
+
+    fn sample() -> Vec<TmuxSession> {
+        vec![
+            session("zeta", AgentStatus::Idle),
+            session("beta", AgentStatus::Busy),
+            session("alpha", AgentStatus::Idle),
+            session("gamma", AgentStatus::Error),
+        ]
+    }
+
+    #[test]
+    fn test_grouped_rows_sorted_and_skip_empty() {
+        let rows = build_rows(
+            &sample(),
+            "",
+            ListDisplay::Grouped,
+            &BTreeSet::new(),
+            None,
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        assert_eq!(
+            rows,
+            vec![
+                ListRow::Header {
+                    group: StatusGroup::NeedsAttention,
+                    count: 1,
+                    collapsed: false
+                },
+                ListRow::Session(3),
+                ListRow::Header {
+                    group: StatusGroup::Working,
+                    count: 1,
+                    collapsed: false
+                },
+                ListRow::Session(1),
+                ListRow::Header {
+                    group: StatusGroup::Idle,
+                    count: 2,
+                    collapsed: false
+                },
+                ListRow::Session(2),
+                ListRow::Session(0),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_filter_rows() {
+        let mut sessions = sample();
+        for (session, socket) in sessions.iter_mut().zip(["work", "home", "work", "home"]) {
+            session.socket = socket.to_string();
+        }
+        // Case is ignored, and servers without a match are left out
+        let rows = build_rows(
+            &sessions,
+            "ETA",
+            ListDisplay::Flat,
+            &BTreeSet::new(),
+            Some(Column::Name),
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        assert_eq!(
+            rows,
+            vec![
+                ListRow::Server(0),
+                ListRow::Session(0),
+                ListRow::Server(1),
+                ListRow::Session(1),
+            ]
+        );
+
+        // So are groups without one
+        let rows = build_rows(
+            &sample(),
+            "ta",
+            ListDisplay::Grouped,
+            &BTreeSet::new(),
+            None,
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        let counts: Vec<usize> = rows
+            .iter()
+            .filter_map(|row| match row {
+                ListRow::Header { count, .. } => Some(*count),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(counts, [1, 1]);
+        assert!(
+            build_rows(
+                &sample(),
+                "nothing",
+                ListDisplay::Flat,
+                &BTreeSet::new(),
+                None,
+                &AttachHistory::default(),
+                &ManualOrder::default(),
+            )
+            .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_window_rows() {
+        let rows = build_rows(
+            &sample(),
+            "",
+            ListDisplay::Grouped,
+            &BTreeSet::new(),
+            None,
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        // alpha (2) has two windows listed
+        let tree = with_windows(rows, |i| if i == 2 { 2 } else { 0 });
+        let at = tree
+            .iter()
+            .position(|row| *row == ListRow::Session(2))
+            .unwrap();
+        assert_eq!(
+            tree[at..at + 3],
+            [
+                ListRow::Session(2),
+                ListRow::Window(2, 0),
+                ListRow::Window(2, 1)
+            ]
+        );
+        assert!(tree[at + 1].is_selectable());
+        assert_eq!(group_of_row(&tree, at + 2), Some(StatusGroup::Idle));
+    }
+
+    #[test]
+    fn test_filter_by_tag() {
+        let mut sessions = sample();
+        sessions[1].tags = vec!["Backend".to_string(), "acme".to_string()];
+        sessions[3].tags = vec!["backend".to_string()];
+        let shown = |filter: &str| -> Vec<&str> {
+            sessions
+                .iter()
+                .filter(|s| matches_filter(s, filter))
+                .map(|s| s.name.as_str())
+                .collect()
+        };
+        assert_eq!(shown("#back"), ["beta", "gamma"]);
+        assert_eq!(shown("#acme"), ["beta"]);
+        // Any tag at all
+        assert_eq!(shown("#"), ["beta", "gamma"]);
+        // Names don't match a tag filter
+        assert!(shown("#zeta").is_empty());
+    }
+
+    #[test]
+    fn test_rows_by_server() {
+        let mut sessions = sample();
+        for (session, socket) in sessions.iter_mut().zip(["work", "home", "work", "home"]) {
+            session.socket = socket.to_string();
+        }
+        let rows = build_rows(
+            &sessions,
+            "",
+            ListDisplay::Flat,
+            &BTreeSet::new(),
+            Some(Column::Name),
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        // Servers in the order they were polled, sorting within each
+        assert_eq!(
+            rows,
+            vec![
+                ListRow::Server(0),
+                ListRow::Session(2),
+                ListRow::Session(0),
+                ListRow::Server(1),
+                ListRow::Session(1),
+                ListRow::Session(3),
+            ]
+        );
+        assert_eq!(step_selection(&rows, Some(0), false), Some(5));
+        assert_eq!(step_selection(&rows, Some(2), true), Some(4));
+
+        let rows = build_rows(
+            &sessions,
+            "",
+            ListDisplay::Grouped,
+            &BTreeSet::new(),
+            None,
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        // Each server's sessions grouped by status under their own headers
+        assert_eq!(rows.len(), 9);
+        assert_eq!(rows[4], ListRow::Server(1));
+        assert_eq!(group_of_row(&rows, 3), Some(StatusGroup::Idle));
+        assert_eq!(group_of_row(&rows, 6), Some(StatusGroup::NeedsAttention));
+    }
+
+    #[test]
+    fn test_collapsed_group_hides_sessions() {
+        let collapsed = BTreeSet::from([StatusGroup::Idle]);
+        let rows = build_rows(
+            &sample(),
+            "",
+            ListDisplay::Grouped,
+            &collapsed,
+            None,
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        assert_eq!(rows.len(), 5);
+        assert_eq!(
+            rows[4],
+            ListRow::Header {
+                group: StatusGroup::Idle,
+                count: 2,
+                collapsed: true
+            }
+        );
+    }
+
+    #[test]
+    fn test_navigation_skips_expanded_headers() {
+        let collapsed = BTreeSet::from([StatusGroup::Working]);
+        let rows = build_rows(
+            &sample(),
+            "",
+            ListDisplay::Grouped,
+            &collapsed,
+            None,
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        // 0: header, 1: gamma, 2: collapsed Working header, 3: header, 4: alpha, 5: zeta
+        assert_eq!(step_selection(&rows, Some(1), true), Some(2));
+        assert_eq!(step_selection(&rows, Some(2), true), Some(4));
+        assert_eq!(step_selection(&rows, Some(5), true), Some(1));
+        assert_eq!(step_selection(&rows, Some(1), false), Some(5));
+        assert_eq!(step_selection(&rows, Some(4), false), Some(2));
+        assert_eq!(nearest_selectable(&rows, 0), Some(1));
+        assert_eq!(nearest_selectable(&rows, 3), Some(4));
+    }
+
+    #[test]
+    fn test_group_of_row() {
+        let rows = build_rows(
+            &sample(),
+            "",
+            ListDisplay::Grouped,
+            &BTreeSet::new(),
+            None,
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        assert_eq!(group_of_row(&rows, 1), Some(StatusGroup::NeedsAttention));
+        assert_eq!(group_of_row(&rows, 6), Some(StatusGroup::Idle));
+
+        let flat = build_rows(
+            &sample(),
+            "",
+            ListDisplay::Flat,
+            &BTreeSet::new(),
+            None,
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        assert_eq!(group_of_row(&flat, 2), None);
+    }
+
+    #[test]
+    fn test_sorted_flat_rows() {
+        let mut sessions = sample();
+        sessions[0].attached_clients = 2;
+        sessions[2].attached_clients = 1;
+
+        let rows = build_rows(
+            &sessions,
+            "",
+            ListDisplay::Flat,
+            &BTreeSet::new(),
+            None,
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        assert_eq!(rows[0], ListRow::Session(0));
+
+        let rows = build_rows(
+            &sessions,
+            "",
+            ListDisplay::Flat,
+            &BTreeSet::new(),
+            Some(Column::Name),
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        assert_eq!(rows[0], ListRow::Session(2));
+
+        let rows = build_rows(
+            &sessions,
+            "",
+            ListDisplay::Flat,
+            &BTreeSet::new(),
+            Some(Column::Clients),
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        let order: Vec<_> = rows.iter().collect();
+        assert_eq!(
+            order,
+            vec![
+                &ListRow::Session(0),
+                &ListRow::Session(2),
+                &ListRow::Session(1),
+                &ListRow::Session(3)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_sort_by_output() {
+        let mut quiet = TmuxSession::new("$1".to_string(), "quiet".to_string());
+        quiet.activity_trend = vec![0, 1, 0];
+        let mut busy = TmuxSession::new("$2".to_string(), "busy".to_string());
+        busy.activity_trend = vec![5, 0, 5];
+        let never = TmuxSession::new("$3".to_string(), "never".to_string());
+        let rows = build_rows(
+            &[quiet, never, busy],
+            "",
+            ListDisplay::Flat,
+            &BTreeSet::new(),
+            Some(Column::Output),
+            &AttachHistory::default(),
+            &ManualOrder::default(),
+        );
+        assert_eq!(
+            rows,
+            [
+                ListRow::Session(2),
+                ListRow::Session(0),
+                ListRow::Session(1)
+            ]
+        );
+    }
+
+    #[test]
+    fn test_column_drop_order() {
+        assert_eq!(visible_columns(80), Column::ALL.to_vec());
+        assert_eq!(visible_columns(56), Column::ALL.to_vec());
+        assert!(!visible_columns(55).contains(&Column::Used));
+        assert!(visible_columns(50).contains(&Column::Clients));
+        assert!(!visible_columns(49).contains(&Column::Clients));
+        assert!(visible_columns(42).contains(&Column::Output));
+        assert!(!visible_columns(41).contains(&Column::Output));
+        assert!(visible_columns(31).contains(&Column::Activity));
+        assert_eq!(
+            visible_columns(30),
+            vec![Column::Status, Column::Name, Column::Agent, Column::Age]
+        );
+        assert_eq!(
+            visible_columns(23),
+            vec![Column::Status, Column::Name, Column::Agent]
+        );
+        assert_eq!(visible_columns(18), vec![Column::Status, Column::Name]);
+        assert_eq!(visible_columns(5), vec![Column::Status, Column::Name]);
+    }
+
+    #[test]
+    fn test_sort_cycle_skips_hidden_columns() {
+        let visible = [Column::Status, Column::Name, Column::Age];
+        assert_eq!(next_sort(None, &visible), Some(Column::Status));
+        assert_eq!(next_sort(Some(Column::Name), &visible), Some(Column::Age));
+        // Recently used needs no column on screen
+        assert_eq!(next_sort(Some(Column::Age), &visible), Some(Column::Used));
+        assert_eq!(next_sort(Some(Column::Used), &visible), None);
+        // A sort column that has since been hidden still advances
+        assert_eq!(next_sort(Some(Column::Agent), &visible), Some(Column::Age));
+    }
+
+    #[test]
+    fn test_manual_order() {
+        let mut order = ManualOrder::default();
+        // Nothing arranged yet: tmux's order stands and nothing is recorded
+        assert!(!order.refresh(["web", "api"]));
+        assert_eq!(order.compare("web", "api"), Ordering::Equal);
+
+        order.place(["web", "api", "db"], "db", "web", false);
+        assert_eq!(
+            order,
+            ManualOrder(vec!["db".into(), "web".into(), "api".into()])
+        );
+        order.place([], "db", "api", true);
+        assert_eq!(
+            order,
+            ManualOrder(vec!["web".into(), "api".into(), "db".into()])
+        );
+
+        // New sessions go last, gone ones are pruned, and no server keeps it all
+        assert!(order.refresh(["new", "db", "web"]));
+        assert_eq!(
+            order,
+            ManualOrder(vec!["web".into(), "db".into(), "new".into()])
+        );
+        assert!(!order.refresh(["new", "db", "web"]));
+        assert!(!order.refresh([]));
+        assert_eq!(order.compare("new", "unlisted"), Ordering::Less);
+
+        let rows = build_rows(
+            &sample(),
+            "",
+            ListDisplay::Flat,
+            &BTreeSet::new(),
+            None,
+            &AttachHistory::default(),
+            &ManualOrder(vec!["gamma".into(), "alpha".into()]),
+        );
+        assert_eq!(
+            rows,
+            [3, 2, 0, 1].map(ListRow::Session),
+            "ordered ones first, the rest in tmux's order"
+        );
+    }
+}
@@ -0,0 +1,71 @@
+//! Shut down cleanly when we're told to stop or lose the terminal.
+//!
+//! SIGTERM, SIGHUP and SIGINT become an [`Action::Quit`], so the main loop
+//! leaves the alternate screen and raw mode the same way as pressing `q`.
+//! If that takes longer than a deadline (e.g. the loop is blocked behind a
+//! foreground attach), the terminal is restored and the process exits.
+
+use std::time::Duration;
+use tokio::signal::unix::{Signal, SignalKind, signal};
+use tokio::sync::mpsc;
+
+use crate::actions::Action;
+
+/// How long a graceful shutdown gets before we exit anyway
+pub const SHUTDOWN_DEADLINE: Duration = Duration::from_secs(3);
+
+/// Start turning termination signals into [`Action::Quit`]
+///
+/// After the first signal, `restore` runs and the process exits with
+/// 128 + the signal number once `deadline` passes or a second signal arrives.
+/// `restore` must be safe to call when the terminal is already restored.
+pub fn spawn(
+    tx: mpsc::UnboundedSender<Action>,
+    deadline: Duration,
+    restore: fn(),
+) -> std::io::Result<()> {
+    // Register everything up front so a failure is reported, not logged from a task
+    let mut term = signal(SignalKind::terminate())?;
+    let mut hangup = signal(SignalKind::hangup())?;
+    let mut interrupt = signal(SignalKind::interrupt())?;
+    tokio::spawn(async move {
+        let signo = next(&mut term, &mut hangup, &mut interrupt).await;
+        tracing::info!(signal = signo, "Signal received, shutting down");
+        let _ = tx.send(Action::Quit);
+
+        let again = next(&mut term, &mut hangup, &mut interrupt);
+        if tokio::time::timeout(deadline, again).await.is_err() {
+            tracing::warn!("Shutdown took longer than {:?}, exiting", deadline);
+        }
+        restore();
+        std::process::exit(128 + signo);
+    });
+    Ok(())
+}
+
+/// Wait for any of the signals, returning its number
+async fn next(term: &mut Signal, hangup: &mut Signal, interrupt: &mut Signal) -> i32 {
+    tokio::select! {
+        _ = term.recv() => libc::SIGTERM,
+        _ = hangup.recv() => libc::SIGHUP,
+        _ = interrupt.recv() => libc::SIGINT,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[tokio::test]
+    async fn test_signal_requests_quit() {
+        let (tx, mut rx) = mpsc::unbounded_channel();
+        // Long enough that the forced exit never fires while tests run
+        spawn(tx, Duration::from_secs(3600), || {}).unwrap();
+
+        unsafe { libc::raise(libc::SIGHUP) };
+        let action = tokio::time::timeout(Duration::from_secs(5), rx.recv())
+            .await
+            .unwrap();
+        assert!(matches!(action, Some(Action::Quit)));
+    }
+}
@@ -1,9 +1,31 @@
-use anyhow::Result;
 use ignore::WalkBuilder;
 use std::path::Path;
 
+use crate::error::Result;
+
 /// Generate a tree-like skeleton map of the project structure
+///
+/// Fails with [`AgentRustyError::Io`](crate::error::AgentRustyError::Io) if `root` can't be read.
 pub async fn generate_skeleton(root: &str) -> Result<String> {
+    generate_skeleton_blocking(root)
+}
+
+/// [`generate_skeleton`] without a runtime; walks the tree on the calling thread
+pub fn generate_skeleton_blocking(root: &str) -> Result<String> {
+    Ok(render_tree(&walk(root)?))
+}
+
+/// Files and directories under a root, as walked for a skeleton
+#[derive(Debug, Clone)]
+pub struct Listing {
+    /// Name of the root directory itself
+    pub root_name: String,
+    /// Paths relative to the root, sorted by component, with whether each is a directory
+    pub entries: Vec<(String, bool)>,
+}
+
+/// Walk `root`, respecting `.gitignore`
+pub fn walk(root: &str) -> Result<Listing> {
     let root_path = Path::new(root).canonicalize()?;
     let root_name = root_path
         .file_name()
@@ -36,98 +58,96 @@ pub async fn generate_skeleton(root: &str) -> Result<String> {
         }
     }
 
-    // Sort entries for consistent output
-    entries.sort_by(|a, b| a.0.cmp(&b.0));
+    // Sort by component so each directory's contents follow it directly
+    // (plain string order puts `src-old` between `src` and `src/lib.rs`)
+    entries.sort_by(|a, b| components(&a.0).cmp(components(&b.0)));
 
-    // Build tree structure
-    let mut result = format!("{}/\n", root_name);
+    Ok(Listing { root_name, entries })
+}
+
+/// Draw a walked tree with box-drawing connectors
+///
+/// Entries must be in [`walk`] order, each directory before its contents.
+pub fn render_tree(listing: &Listing) -> String {
+    let entries = &listing.entries;
+    let mut result = format!("{}/\n", listing.root_name);
 
     for (i, (path, is_dir)) in entries.iter().enumerate() {
-        let depth = path.matches(['/', '\\']).count();
-        let is_last = is_last_at_depth(&entries, i, depth);
+        let depth = path_depth(path);
+        let is_last = !has_later_sibling(entries, i, path);
 
-        let prefix = build_prefix(&entries, i, depth);
+        let prefix = build_prefix(entries, i, depth);
         let connector = if is_last { "└── " } else { "├── " };
 
-        let name = Path::new(path)
-            .file_name()
-            .map(|s| s.to_string_lossy().to_string())
-            .unwrap_or_default();
+        let name = components(path).next_back().unwrap_or_default();
 
         let suffix = if *is_dir { "/" } else { "" };
 
         result.push_str(&format!("{}{}{}{}\n", prefix, connector, name, suffix));
     }
 
-    Ok(result)
+    result
 }
 
-fn is_last_at_depth(entries: &[(String, bool)], current_idx: usize, depth: usize) -> bool {
-    let current_parent = get_parent(&entries[current_idx].0);
+/// Path components, splitting on either separator
+fn components(path: &str) -> std::str::Split<'_, [char; 2]> {
+    path.split(['/', '\\'])
+}
 
-    for (path, _) in entries.iter().skip(current_idx + 1) {
-        let entry_depth = path.matches(['/', '\\']).count();
-        let entry_parent = get_parent(path);
+/// Number of directories above `path` (0 for a top-level entry)
+fn path_depth(path: &str) -> usize {
+    path.matches(['/', '\\']).count()
+}
 
-        if entry_depth == depth && entry_parent == current_parent {
-            return false;
+/// Whether anything after `entries[current_idx]` shares a parent with `path`,
+/// which is that entry or one of its ancestors
+fn has_later_sibling(entries: &[(String, bool)], current_idx: usize, path: &str) -> bool {
+    let depth = path_depth(path);
+    let parent = get_parent(path);
+    for (other, _) in entries.iter().skip(current_idx + 1) {
+        let other_depth = path_depth(other);
+        if other_depth == depth && get_parent(other) == parent {
+            return true;
         }
-        if entry_depth < depth {
+        // Past the end of the parent's contents
+        if other_depth < depth {
             break;
         }
     }
-    true
+    false
 }
 
-fn get_parent(path: &str) -> String {
-    Path::new(path)
-        .parent()
-        .map(|p| p.to_string_lossy().to_string())
-        .unwrap_or_default()
+/// Everything before the last separator; empty for a top-level entry
+fn get_parent(path: &str) -> &str {
+    path.rfind(['/', '\\']).map_or("", |at| &path[..at])
 }
 
+/// Guide lines for an entry: a bar under each ancestor that has siblings still to come
 fn build_prefix(entries: &[(String, bool)], current_idx: usize, depth: usize) -> String {
-    let mut prefix = String::new();
-
-    for d in 0..depth {
-        // Check if there are more siblings at this depth level
-        let ancestor_path = get_ancestor(&entries[current_idx].0, d);
-        let has_more_siblings = entries
-            .iter()
-            .skip(current_idx + 1)
-            .any(|(path, _)| {
-                let path_depth = path.matches(['/', '\\']).count();
-                path_depth >= d && get_ancestor(path, d) == ancestor_path && {
-                    // Check if there's a sibling at exactly depth d
-                    entries.iter().skip(current_idx + 1).any(|(p, _)| {
-                        let pd = p.matches(['/', '\\']).count();
-                        pd == d && get_parent(p) == get_parent(&ancestor_path)
-                    })
-                }
-            });
-
-        if has_more_siblings {
-            prefix.push_str("│   ");
-        } else {
-            prefix.push_str("    ");
-        }
-    }
-
-    prefix
+    let path = &entries[current_idx].0;
+    (0..depth)
+        .map(|d| {
+            if has_later_sibling(entries, current_idx, get_ancestor(path, d)) {
+                "│   "
+            } else {
+                "    "
+            }
+        })
+        .collect()
 }
 
-fn get_ancestor(path: &str, depth: usize) -> String {
-    let parts: Vec<&str> = path.split(['/', '\\']).collect();
-    if depth < parts.len() {
-        parts[..=depth].join("/")
-    } else {
-        path.to_string()
+/// The ancestor of `path` at `depth`, keeping the original separators
+fn get_ancestor(path: &str, depth: usize) -> &str {
+    match path.match_indices(['/', '\\']).nth(depth) {
+        Some((at, _)) => &path[..at],
+        None => path,
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use proptest::prelude::*;
 
     #[tokio::test]
     async fn test_generate_skeleton() {
@@ -136,4 +156,157 @@ mod tests {
         let result = generate_skeleton(".").await;
         assert!(result.is_ok());
     }
+
+    #[test]
+    fn test_render_tree() {
+        let listing = Listing {
+            root_name: "api".to_string(),
+            entries: [
+                ("Cargo.toml", false),
+                ("src", true),
+                ("src/lib.rs", false),
+                ("src/tmux", true),
+                ("src/tmux/mod.rs", false),
+                ("tests", true),
+            ]
+            .into_iter()
+            .map(|(path, is_dir)| (path.to_string(), is_dir))
+            .collect(),
+        };
+        assert_eq!(
+            render_tree(&listing),
+            concat!(
+                "api/\n",
+                "├── Cargo.toml\n",
+                "├── src/\n",
+                "│   ├── lib.rs\n",
+                "│   └── tmux/\n",
+                "│       └── mod.rs\n",
+                "└── tests/\n",
+            )
+        );
+    }
+
+    #[test]
+    fn test_walk_keeps_contents_under_their_directory() {
+        let root = std::env::temp_dir().join(format!("agent-rusty-walk-{}", std::process::id()));
+        std::fs::create_dir_all(root.join("src")).unwrap();
+        std::fs::create_dir_all(root.join("src-old")).unwrap();
+        std::fs::write(root.join("src/lib.rs"), "").unwrap();
+        let listing = walk(&root.to_string_lossy()).unwrap();
+        std::fs::remove_dir_all(&root).unwrap();
+
+        let paths: Vec<_> = listing.entries.iter().map(|(p, _)| p.as_str()).collect();
+        assert_eq!(paths, ["src", "src/lib.rs", "src-old"]);
+    }
+
+    /// One rendered line, split into its parts
+    struct Line<'a> {
+        /// Whether each guide column is a bar (`│`) rather than blank
+        bars: Vec<bool>,
+        /// `└──` rather than `├──`
+        last: bool,
+        name: &'a str,
+    }
+
+    fn parse_line(mut line: &str) -> Line<'_> {
+        let mut bars = Vec::new();
+        loop {
+            if let Some(rest) = line.strip_prefix("│   ") {
+                bars.push(true);
+                line = rest;
+            } else if let Some(rest) = line.strip_prefix("    ") {
+                bars.push(false);
+                line = rest;
+            } else {
+                break;
+            }
+        }
+        match line.strip_prefix("└── ") {
+            Some(name) => Line {
+                bars,
+                last: true,
+                name,
+            },
+            None => Line {
+                bars,
+                last: false,
+                name: line.strip_prefix("├── ").expect("a connector"),
+            },
+        }
+    }
+
+    /// Path components, including `-` and `.` which sort before the separator
+    fn name() -> impl Strategy<Value = String> {
+        "[ab0-9 ._\\-éß漢😀]{1,4}".prop_filter("not a relative component", |name| {
+            name != "." && name != ".."
+        })
+    }
+
+    /// Listings of up to 40 paths, at most four deep, with every ancestor
+    /// directory present and in walk order
+    fn listing() -> impl Strategy<Value = Listing> {
+        prop::collection::vec(prop::collection::vec(name(), 1..=4), 1..40).prop_map(|paths| {
+            let mut tree = std::collections::BTreeMap::new();
+            for path in paths {
+                for end in 1..path.len() {
+                    tree.insert(path[..end].to_vec(), true);
+                }
+                tree.entry(path).or_insert(false);
+            }
+            Listing {
+                root_name: "root".to_string(),
+                entries: tree
+                    .into_iter()
+                    .map(|(components, is_dir)| (components.join("/"), is_dir))
+                    .collect(),
+            }
+        })
+    }
+
+    proptest! {
+        #[test]
+        fn prop_render_tree_invariants(listing in listing()) {
+            let rendered = render_tree(&listing);
+            let mut lines = rendered.lines();
+            prop_assert_eq!(lines.next(), Some("root/"));
+            let lines: Vec<_> = lines.map(parse_line).collect();
+            let paths: Vec<Vec<&str>> = listing
+                .entries
+                .iter()
+                .map(|(path, _)| path.split('/').collect())
+                .collect();
+
+            // Every entry exactly once, in order
+            prop_assert_eq!(lines.len(), paths.len());
+            for (i, (line, path)) in lines.iter().zip(&paths).enumerate() {
+                let suffix = if listing.entries[i].1 { "/" } else { "" };
+                prop_assert_eq!(line.name, format!("{}{}", path[path.len() - 1], suffix));
+
+                // Indentation matches depth
+                let depth = path.len() - 1;
+                prop_assert_eq!(line.bars.len(), depth);
+
+                // `└──` iff no sibling follows
+                let sibling_follows = paths[i + 1..]
+                    .iter()
+                    .any(|other| other.len() == path.len() && other[..depth] == path[..depth]);
+                prop_assert_eq!(line.last, !sibling_follows, "entry {}", listing.entries[i].0);
+
+                // A bar iff that ancestor has a following sibling
+                for d in 0..depth {
+                    let ancestor_sibling_follows = paths[i + 1..]
+                        .iter()
+                        .any(|other| other.len() == d + 1 && other[..d] == path[..d]);
+                    prop_assert_eq!(
+                        line.bars[d],
+                        ancestor_sibling_follows,
+                        "column {} of {}",
+                        d,
+                        listing.entries[i].0
+                    );
+                }
+            }
+        }
+    }
 }
@@ -0,0 +1,396 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+
+use crate::tmux::{AgentKind, TmuxClient, TmuxSession};
+
+/// Format version written to new snapshots
+pub const SNAPSHOT_VERSION: u32 = 1;
+
+/// Field separator for `list-panes` output; can't appear in names or paths
+const SEPARATOR: char = '\u{1f}';
+
+/// Sessions, windows and panes of the watched tmux servers at one point in time
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Snapshot {
+    pub version: u32,
+    /// Unix timestamp when the snapshot was taken
+    pub taken_at: u64,
+    pub sessions: Vec<SessionSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SessionSnapshot {
+    pub name: String,
+    /// Label of the server it ran on when several were watched (see
+    /// [`TmuxSession::socket`])
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub socket: String,
+    pub windows: Vec<WindowSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct WindowSnapshot {
+    pub name: String,
+    /// tmux `window_layout` string
+    pub layout: String,
+    pub panes: Vec<PaneSnapshot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct PaneSnapshot {
+    /// Working directory
+    pub path: String,
+    /// Command typed into the restored pane's shell; `None` leaves a plain shell
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+impl Snapshot {
+    /// Default location of the snapshot file
+    pub fn default_path() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_default()
+            .join(".agent-deck")
+            .join("snapshot.json")
+    }
+
+    /// Read a snapshot; `None` if none was taken yet
+    pub fn load_from(path: &Path) -> Result<Option<Self>> {
+        if !path.exists() {
+            return Ok(None);
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        Self::parse(&text)
+            .map(Some)
+            .with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    fn parse(text: &str) -> Result<Self> {
+        let value: serde_json::Value = serde_json::from_str(text)?;
+        let version = value.get("version").and_then(|v| v.as_u64()).unwrap_or(0);
+        if version == 0 || version > u64::from(SNAPSHOT_VERSION) {
+            anyhow::bail!(
+                "unsupported snapshot version {} (this build reads version {})",
+                version,
+                SNAPSHOT_VERSION
+            );
+        }
+        Ok(serde_json::from_value(value)?)
+    }
+
+    /// Write the snapshot, replacing the old file only once the new one is complete
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = serde_json::to_string_pretty(self)?;
+        let partial = path.with_extension("json.tmp");
+        std::fs::write(&partial, text)
+            .with_context(|| format!("Failed to write {}", partial.display()))?;
+        std::fs::rename(&partial, path)
+            .with_context(|| format!("Failed to write {}", path.display()))
+    }
+
+    /// Snapshotted sessions with no live session of the same name on their server
+    ///
+    /// Sessions snapshotted while a single server was watched match by name alone.
+    pub fn missing(&self, sessions: &[TmuxSession]) -> Vec<SessionSnapshot> {
+        self.sessions
+            .iter()
+            .filter(|s| {
+                !sessions.iter().any(|live| {
+                    live.name == s.name && (s.socket.is_empty() || live.socket == s.socket)
+                })
+            })
+            .cloned()
+            .collect()
+    }
+}
+
+/// `list-panes -a` format read by [`parse_panes`]
+fn pane_format() -> String {
+    [
+        "#{session_name}",
+        "#{window_index}",
+        "#{window_name}",
+        "#{window_layout}",
+        "#{pane_current_path}",
+        "#{pane_start_command}",
+        "#{pane_current_command}",
+    ]
+    .join(&SEPARATOR.to_string())
+}
+
+/// Group `list-panes -a` lines of the server labeled `socket` into sessions
+/// and windows, in tmux's order
+fn parse_panes(output: &str, socket: &str) -> Vec<SessionSnapshot> {
+    let mut sessions: Vec<SessionSnapshot> = Vec::new();
+    let mut last_window: Option<(String, String)> = None;
+    for line in output.lines() {
+        let fields: Vec<&str> = line.split(SEPARATOR).collect();
+        let [
+            session,
+            window,
+            window_name,
+            layout,
+            path,
+            start_command,
+            current_command,
+        ] = fields[..]
+        else {
+            continue;
+        };
+        let pane = PaneSnapshot {
+            path: path.to_string(),
+            command: restore_command(start_command, current_command),
+        };
+
+        if sessions.last().is_none_or(|s| s.name != session) {
+            sessions.push(SessionSnapshot {
+                name: session.to_string(),
+                socket: socket.to_string(),
+                windows: Vec::new(),
+            });
+            last_window = None;
+        }
+        let windows = &mut sessions.last_mut().expect("pushed above").windows;
+        let key = (session.to_string(), window.to_string());
+        if last_window.as_ref() != Some(&key) {
+            windows.push(WindowSnapshot {
+                name: window_name.to_string(),
+                layout: layout.to_string(),
+                panes: Vec::new(),
+            });
+            last_window = Some(key);
+        }
+        windows.last_mut().expect("pushed above").panes.push(pane);
+    }
+    sessions
+}
+
+/// What to run in a restored pane: its start command, or the agent in its foreground
+fn restore_command(start_command: &str, current_command: &str) -> Option<String> {
+    let start_command = start_command.trim().trim_matches('"');
+    if !start_command.is_empty() {
+        return Some(start_command.to_string());
+    }
+    match AgentKind::detect(current_command) {
+        AgentKind::Shell | AgentKind::Other => None,
+        _ => Some(current_command.trim().to_string()),
+    }
+}
+
+/// Record every session on the watched servers
+pub async fn capture(client: &TmuxClient) -> Result<Snapshot> {
+    let servers = client.list_all_panes(&pane_format()).await?;
+    let taken_at = std::time::SystemTime::now()
+        .duration_since(std::time::UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    Ok(Snapshot {
+        version: SNAPSHOT_VERSION,
+        taken_at,
+        sessions: servers
+            .iter()
+            .flat_map(|(socket, output)| parse_panes(output, socket))
+            .collect(),
+    })
+}
+
+/// Snapshot the servers to `path`; no sessions at all leaves the old snapshot alone
+pub async fn save(client: &TmuxClient, path: &Path) -> Result<usize> {
+    let snapshot = capture(client).await?;
+    let count = snapshot.sessions.len();
+    if count > 0 {
+        snapshot.save_to(path)?;
+    }
+    Ok(count)
+}
+
+/// Restore `sessions` one by one; fails with the names that couldn't be restored
+pub async fn restore_all(client: &TmuxClient, sessions: &[SessionSnapshot]) -> Result<usize> {
+    let mut failures = Vec::new();
+    for session in sessions {
+        if let Err(e) = restore(client, session).await {
+            tracing::warn!("Failed to restore {}: {:#}", session.name, e);
+            failures.push(format!("{}: {:#}", session.name, e));
+        }
+    }
+    if failures.is_empty() {
+        return Ok(sessions.len());
+    }
+    Err(anyhow::anyhow!(failures.join("\n")).context(format!(
+        "Restored {} of {} sessions",
+        sessions.len() - failures.len(),
+        sessions.len()
+    )))
+}
+
+/// Recreate one session's windows and panes, then start each pane's command
+pub async fn restore(client: &TmuxClient, snapshot: &SessionSnapshot) -> Result<()> {
+    let Some(first) = snapshot.windows.first().and_then(|w| w.panes.first()) else {
+        anyhow::bail!("Snapshot of {} has no panes", snapshot.name);
+    };
+    let dir = Some(Path::new(&first.path));
+    let session = client
+        .create_session_in(&snapshot.socket, &snapshot.name, dir, None)
+        .await?;
+
+    for (index, window) in snapshot.windows.iter().enumerate() {
+        let mut pane_ids: Vec<String> = Vec::with_capacity(window.panes.len());
+        for (pane_index, pane) in window.panes.iter().enumerate() {
+            let pane_id = match (index, pane_index) {
+                (0, 0) => {
+                    client.rename_window(&session.id, &window.name).await?;
+                    session.id.clone()
+                }
+                (_, 0) => {
+                    client
+                        .new_window(&session.id, &window.name, &pane.path)
+                        .await?
+                }
+                _ => client.split_window(&pane_ids[0], &pane.path).await?,
+            };
+            pane_ids.push(pane_id);
+        }
+        if window.panes.len() > 1
+            && let Err(e) = client.select_layout(&pane_ids[0], &window.layout).await
+        {
+            tracing::warn!("Keeping default layout for {}: {:#}", snapshot.name, e);
+        }
+        for (pane, pane_id) in window.panes.iter().zip(&pane_ids) {
+            if let Some(command) = &pane.command {
+                client.send_keys(pane_id, command).await?;
+            }
+        }
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn line(fields: &[&str]) -> String {
+        fields.join(&SEPARATOR.to_string())
+    }
+
+    fn sample() -> Snapshot {
+        Snapshot {
+            version: SNAPSHOT_VERSION,
+            taken_at: 1_700_000_000,
+            sessions: vec![SessionSnapshot {
+                name: "api".to_string(),
+                socket: String::new(),
+                windows: vec![WindowSnapshot {
+                    name: "agent".to_string(),
+                    layout: "c195,80x24,0,0[80x12,0,0,0,80x11,0,13,1]".to_string(),
+                    panes: vec![
+                        PaneSnapshot {
+                            path: "/work/api".to_string(),
+                            command: Some("claude".to_string()),
+                        },
+                        PaneSnapshot {
+                            path: "/work/api/web".to_string(),
+                            command: None,
+                        },
+                    ],
+                }],
+            }],
+        }
+    }
+
+    #[test]
+    fn test_parse_panes() {
+        let output = [
+            line(&["api", "0", "agent", "L1", "/work/api", "", "claude"]),
+            line(&["api", "0", "agent", "L1", "/work/api|web", "", "zsh"]),
+            line(&[
+                "api",
+                "1",
+                "logs",
+                "L2",
+                "/var/log",
+                "\"tail -f app.log\"",
+                "tail",
+            ]),
+            line(&["web ui", "0", "zsh", "L3", "/work/web", "", "aider"]),
+            "garbage".to_string(),
+        ]
+        .join("\n");
+        let sessions = parse_panes(&output, "work");
+        assert_eq!(sessions.len(), 2);
+        assert!(sessions.iter().all(|s| s.socket == "work"));
+
+        let api = &sessions[0];
+        assert_eq!(api.windows.len(), 2);
+        assert_eq!(api.windows[0].layout, "L1");
+        assert_eq!(
+            api.windows[0].panes,
+            [
+                PaneSnapshot {
+                    path: "/work/api".to_string(),
+                    command: Some("claude".to_string())
+                },
+                PaneSnapshot {
+                    path: "/work/api|web".to_string(),
+                    command: None
+                },
+            ]
+        );
+        assert_eq!(
+            api.windows[1].panes[0].command.as_deref(),
+            Some("tail -f app.log")
+        );
+        assert_eq!(sessions[1].name, "web ui");
+        assert_eq!(
+            sessions[1].windows[0].panes[0].command.as_deref(),
+            Some("aider")
+        );
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("agent-rusty-snapshot-{}.json", std::process::id()));
+        let snapshot = sample();
+        snapshot.save_to(&path).unwrap();
+        assert_eq!(Snapshot::load_from(&path).unwrap(), Some(snapshot));
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(Snapshot::load_from(&path).unwrap(), None);
+    }
+
+    #[test]
+    fn test_version_check() {
+        let mut value = serde_json::to_value(sample()).unwrap();
+        assert!(Snapshot::parse(&value.to_string()).is_ok());
+
+        value["version"] = serde_json::json!(SNAPSHOT_VERSION + 1);
+        let error = Snapshot::parse(&value.to_string()).unwrap_err();
+        assert!(error.to_string().contains("unsupported snapshot version"));
+
+        value.as_object_mut().unwrap().remove("version");
+        assert!(Snapshot::parse(&value.to_string()).is_err());
+    }
+
+    #[test]
+    fn test_missing_sessions() {
+        let snapshot = sample();
+        assert_eq!(snapshot.missing(&[]).len(), 1);
+        let mut live = TmuxSession::new("$0".to_string(), "api".to_string());
+        live.socket = "personal".to_string();
+        assert!(snapshot.missing(std::slice::from_ref(&live)).is_empty());
+
+        // Taken across servers, a session of the same name elsewhere doesn't count
+        let mut snapshot = sample();
+        snapshot.sessions[0].socket = "work".to_string();
+        assert_eq!(snapshot.missing(std::slice::from_ref(&live)).len(), 1);
+        live.socket = "work".to_string();
+        assert!(snapshot.missing(&[live]).is_empty());
+
+        // The label is left out of single-server snapshots
+        assert!(!serde_json::to_string(&sample()).unwrap().contains("socket"));
+    }
+}
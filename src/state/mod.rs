@@ -0,0 +1,384 @@
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::{Path, PathBuf};
+
+use crate::description::Descriptions;
+use crate::jump_list::AttachHistory;
+use crate::launcher::Launcher;
+use crate::poller::PollRate;
+use crate::rules::{RuleFlags, RuleSpec};
+use crate::schedule::ScheduledPrompt;
+use crate::session_list::{Column, ListDisplay, ManualOrder, StatusGroup};
+use crate::tags::Tags;
+use crate::usage::UsageLedger;
+use crate::worktree::Worktree;
+
+/// UI state persisted between runs in `~/.agent-deck/state.json`
+///
+/// Per-session entries are keyed by session name alone, so sessions of the
+/// same name on different watched servers share their notes, tags and the rest.
+#[derive(Debug, Default, Serialize, Deserialize)]
+pub struct PersistedState {
+    /// How the session list is displayed
+    #[serde(default)]
+    pub list_display: ListDisplay,
+    /// Column the session list is sorted by; `None` keeps tmux's order
+    #[serde(default)]
+    pub sort_by: Option<Column>,
+    /// Order of sessions arranged by hand, by session name
+    #[serde(default)]
+    pub session_order: ManualOrder,
+    /// Status groups collapsed in the grouped view
+    #[serde(default)]
+    pub collapsed_groups: BTreeSet<StatusGroup>,
+    /// Free-form notes keyed by session name, so they outlive the session
+    #[serde(default)]
+    pub notes: BTreeMap<String, String>,
+    /// What each session is for, by session name; derived when first seen, editable
+    #[serde(default)]
+    pub descriptions: Descriptions,
+    /// Tags grouping sessions, by session name
+    #[serde(default)]
+    pub tags: Tags,
+    /// Launcher each session was created with, by session name, for restarting it
+    #[serde(default)]
+    pub launchers: BTreeMap<String, Launcher>,
+    /// Worktrees made along with sessions, by session name, offered for removal with them
+    #[serde(default)]
+    pub worktrees: BTreeMap<String, Worktree>,
+    /// When each session name was last attached to, for the recently-used sort
+    #[serde(default)]
+    pub attach_history: AttachHistory,
+    /// Poll rates chosen in the dashboard, by session name
+    #[serde(default)]
+    pub poll_rates: BTreeMap<String, PollRate>,
+    /// Prompts waiting to be sent, soonest first
+    #[serde(default)]
+    pub schedules: Vec<ScheduledPrompt>,
+    /// Automation rules added in the dashboard
+    #[serde(default)]
+    pub rules: Vec<RuleSpec>,
+    /// Rules switched on or off, and one-shot rules that have fired
+    #[serde(default)]
+    pub rule_flags: RuleFlags,
+    /// Token and cost samples for the usage rollup
+    #[serde(default)]
+    pub usage: UsageLedger,
+    /// Directory last chosen in the directory picker, where it opens next
+    #[serde(default)]
+    pub last_dir: Option<PathBuf>,
+    /// Where this state is saved; `None` keeps it in memory only
+    #[serde(skip)]
+    path: Option<PathBuf>,
+}
+
+impl PersistedState {
+    /// Default location of the state file
+    pub fn default_path() -> PathBuf {
+        dirs::home_dir()
+            .unwrap_or_default()
+            .join(".agent-deck")
+            .join("state.json")
+    }
+
+    /// Load state from the default location, falling back to defaults
+    pub fn load() -> Self {
+        let path = Self::default_path();
+        let mut state = Self::load_from(&path).unwrap_or_else(|e| {
+            tracing::warn!("Ignoring unreadable state file: {:#}", e);
+            Self::default()
+        });
+        state.path = Some(path);
+        state
+    }
+
+    /// Read state from `path`; a missing file yields defaults
+    pub fn load_from(path: &Path) -> Result<Self> {
+        if !path.exists() {
+            return Ok(Self::default());
+        }
+        let text = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read {}", path.display()))?;
+        serde_json::from_str(&text).with_context(|| format!("Failed to parse {}", path.display()))
+    }
+
+    /// Write state back to where it was loaded from
+    pub fn save(&self) -> Result<()> {
+        match &self.path {
+            Some(path) => self.save_to(path),
+            None => Ok(()),
+        }
+    }
+
+    /// Note attached to a session name, if any
+    pub fn note(&self, session_name: &str) -> Option<&str> {
+        self.notes.get(session_name).map(String::as_str)
+    }
+
+    /// Set or, when `text` is blank, remove a session's note
+    pub fn set_note(&mut self, session_name: &str, text: &str) {
+        let text = text.trim_end();
+        if text.trim().is_empty() {
+            self.notes.remove(session_name);
+        } else {
+            self.notes
+                .insert(session_name.to_string(), text.to_string());
+        }
+    }
+
+    /// Carry what is kept by session name over to a session's new name
+    pub fn rename_session(&mut self, old: &str, new: &str) {
+        if let Some(note) = self.notes.remove(old) {
+            self.notes.insert(new.to_string(), note);
+        }
+        self.descriptions.rename(old, new);
+        self.tags.rename(old, new);
+        self.attach_history.rename(old, new);
+        self.session_order.rename(old, new);
+        if let Some(launcher) = self.launchers.remove(old) {
+            self.launchers.insert(new.to_string(), launcher);
+        }
+        if let Some(worktree) = self.worktrees.remove(old) {
+            self.worktrees.insert(new.to_string(), worktree);
+        }
+        if let Some(rate) = self.poll_rates.remove(old) {
+            self.poll_rates.insert(new.to_string(), rate);
+        }
+        for schedule in self.schedules.iter_mut().filter(|s| s.session == old) {
+            schedule.session = new.to_string();
+        }
+    }
+
+    /// Launcher a session was created with, if it was created here
+    pub fn launcher(&self, session_name: &str) -> Option<&Launcher> {
+        self.launchers.get(session_name)
+    }
+
+    /// Replace the schedules with those on disk, which another process may have changed
+    pub fn reload_schedules(&mut self) {
+        let Some(path) = &self.path else {
+            return;
+        };
+        match Self::load_from(path) {
+            Ok(saved) => self.schedules = saved.schedules,
+            Err(e) => tracing::warn!("Keeping schedules in memory: {:#}", e),
+        }
+    }
+
+    /// Queue `prompt` for `session` at Unix time `at`, returning its ID
+    pub fn add_schedule(&mut self, session: &str, prompt: &str, at: u64) -> u64 {
+        let id = self.schedules.iter().map(|s| s.id).max().unwrap_or(0) + 1;
+        let index = self.schedules.partition_point(|s| s.at <= at);
+        self.schedules.insert(
+            index,
+            ScheduledPrompt {
+                id,
+                session: session.to_string(),
+                prompt: prompt.to_string(),
+                at,
+            },
+        );
+        id
+    }
+
+    pub fn cancel_schedule(&mut self, id: u64) -> Option<ScheduledPrompt> {
+        let index = self.schedules.iter().position(|s| s.id == id)?;
+        Some(self.schedules.remove(index))
+    }
+
+    /// Remove and return the schedules due at `now`, soonest first
+    pub fn take_due(&mut self, now: u64) -> Vec<ScheduledPrompt> {
+        let (due, pending) = std::mem::take(&mut self.schedules)
+            .into_iter()
+            .partition(|s| s.at <= now);
+        self.schedules = pending;
+        due
+    }
+
+    /// Pending schedules for one session, soonest first
+    pub fn schedules_for<'a>(
+        &'a self,
+        session_name: &'a str,
+    ) -> impl Iterator<Item = &'a ScheduledPrompt> {
+        self.schedules
+            .iter()
+            .filter(move |s| s.session == session_name)
+    }
+
+    pub fn save_to(&self, path: &Path) -> Result<()> {
+        if let Some(parent) = path.parent() {
+            std::fs::create_dir_all(parent)?;
+        }
+        let text = serde_json::to_string_pretty(self)?;
+        std::fs::write(path, text).with_context(|| format!("Failed to write {}", path.display()))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_round_trip() {
+        let path =
+            std::env::temp_dir().join(format!("agent-rusty-state-{}.json", std::process::id()));
+
+        let mut state = PersistedState {
+            list_display: ListDisplay::Grouped,
+            ..PersistedState::default()
+        };
+        state.collapsed_groups.insert(StatusGroup::Idle);
+        state.notes.insert(
+            "payments".to_string(),
+            "waiting on review of PR #412\nresume after".to_string(),
+        );
+        state.launchers.insert(
+            "payments".to_string(),
+            Launcher {
+                name: "Aider".to_string(),
+                command: Some("aider --model sonnet".to_string()),
+                ask_dir: true,
+                ask_command: false,
+                resume: false,
+            },
+        );
+        state.attach_history.record("payments", 1_700_000_000);
+        state
+            .poll_rates
+            .insert("nightly".to_string(), PollRate::Slow);
+        state.save_to(&path).unwrap();
+
+        let loaded = PersistedState::load_from(&path).unwrap();
+        assert_eq!(loaded.list_display, ListDisplay::Grouped);
+        assert_eq!(
+            loaded
+                .launcher("payments")
+                .and_then(|l| l.command.as_deref()),
+            Some("aider --model sonnet")
+        );
+        assert!(loaded.collapsed_groups.contains(&StatusGroup::Idle));
+        assert_eq!(loaded.collapsed_groups.len(), 1);
+        assert_eq!(
+            loaded.attach_history.last_attached("payments"),
+            Some(1_700_000_000)
+        );
+        assert_eq!(loaded.poll_rates.get("nightly"), Some(&PollRate::Slow));
+        assert_eq!(
+            loaded.notes.get("payments").map(String::as_str),
+            Some("waiting on review of PR #412\nresume after")
+        );
+
+        std::fs::remove_file(&path).unwrap();
+        assert!(
+            PersistedState::load_from(&path)
+                .unwrap()
+                .collapsed_groups
+                .is_empty()
+        );
+    }
+
+    #[test]
+    fn test_schedules() {
+        let mut state = PersistedState::default();
+        let late = state.add_schedule("api", "summarize", 300);
+        let early = state.add_schedule("web", "run tests", 100);
+        let middle = state.add_schedule("api", "commit", 200);
+        assert_eq!((late, early, middle), (1, 2, 3));
+        let order: Vec<u64> = state.schedules.iter().map(|s| s.id).collect();
+        assert_eq!(order, [early, middle, late]);
+        let api: Vec<&str> = state
+            .schedules_for("api")
+            .map(|s| s.prompt.as_str())
+            .collect();
+        assert_eq!(api, ["commit", "summarize"]);
+
+        assert_eq!(state.cancel_schedule(middle).unwrap().prompt, "commit");
+        assert!(state.cancel_schedule(middle).is_none());
+
+        assert!(state.take_due(99).is_empty());
+        let due = state.take_due(100);
+        assert_eq!(due.len(), 1);
+        assert_eq!(due[0].prompt, "run tests");
+        assert_eq!(state.schedules.len(), 1);
+        // IDs only need to be unique among pending schedules
+        assert_eq!(state.add_schedule("web", "again", 400), 2);
+    }
+
+    #[test]
+    fn test_due_schedules_fire_once_across_restarts() {
+        let path = std::env::temp_dir().join(format!(
+            "agent-rusty-state-schedules-{}.json",
+            std::process::id()
+        ));
+        let mut state = PersistedState {
+            path: Some(path.clone()),
+            ..PersistedState::default()
+        };
+        state.add_schedule("api", "summarize", 100);
+        state.add_schedule("api", "later", 1_000);
+        state.save().unwrap();
+
+        // The dashboard was closed at 100; the next one starts at 500
+        let mut restarted = PersistedState::load_from(&path).unwrap();
+        restarted.path = Some(path.clone());
+        let due = restarted.take_due(500);
+        assert_eq!(due.len(), 1);
+        restarted.save().unwrap();
+
+        // Another restart finds nothing left to send
+        let mut again = PersistedState::load_from(&path).unwrap();
+        assert!(again.take_due(500).is_empty());
+        assert_eq!(again.schedules.len(), 1);
+
+        // A schedule added by another process shows up on reload
+        let mut other = PersistedState::load_from(&path).unwrap();
+        other.add_schedule("web", "from the cli", 2_000);
+        other.save_to(&path).unwrap();
+        restarted.reload_schedules();
+        assert_eq!(restarted.schedules.len(), 2);
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_rename_session() {
+        let mut state = PersistedState::default();
+        state.set_note("api", "flaky tests");
+        state.descriptions.set("api", "backend", 100);
+        state.tags.set("api", vec!["acme".to_string()], 100);
+        state.poll_rates.insert("api".to_string(), PollRate::Fast);
+        state.add_schedule("api", "continue", 200);
+        state.add_schedule("web", "continue", 300);
+        state
+            .session_order
+            .place(["web", "api"], "api", "web", false);
+
+        state.rename_session("api", "backend");
+        assert_eq!(state.note("api"), None);
+        assert_eq!(state.note("backend"), Some("flaky tests"));
+        assert_eq!(state.descriptions.get("backend"), Some("backend"));
+        assert_eq!(state.tags.get("backend"), ["acme"]);
+        assert_eq!(
+            state.poll_rates,
+            BTreeMap::from([("backend".to_string(), PollRate::Fast)])
+        );
+        let targets: Vec<&str> = state.schedules.iter().map(|s| s.session.as_str()).collect();
+        assert_eq!(targets, ["backend", "web"]);
+        assert_eq!(
+            state.session_order.compare("backend", "web"),
+            std::cmp::Ordering::Less
+        );
+    }
+
+    #[test]
+    fn test_blank_note_removes_entry() {
+        let mut state = PersistedState::default();
+        state.set_note("api", "line one\nline two\n\n");
+        assert_eq!(state.note("api"), Some("line one\nline two"));
+
+        state.set_note("api", "  \n ");
+        assert_eq!(state.note("api"), None);
+        assert!(state.notes.is_empty());
+    }
+}
@@ -0,0 +1,121 @@
+//! Tags that group sessions, such as the repo or client they work for.
+//!
+//! Tags are kept by session name so they outlive restarts, until the name
+//! has been gone for 30 days (see [`crate::seen`]). The poller copies them
+//! onto each [`TmuxSession`], which is how they reach `--json` output and the
+//! control socket.
+
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
+
+use crate::seen::{SeenByName, Sighted};
+use crate::tmux::TmuxSession;
+
+/// A session's tags and when its name was last listed
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct TagRecord {
+    /// Sorted, without repeats
+    pub tags: Vec<String>,
+    /// Unix time the session was last listed, to a day's resolution
+    pub seen_at: u64,
+}
+
+impl Sighted for TagRecord {
+    fn seen_at(&mut self) -> &mut u64 {
+        &mut self.seen_at
+    }
+}
+
+/// Tags by session name
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct Tags {
+    entries: SeenByName<TagRecord>,
+}
+
+impl Tags {
+    pub fn get(&self, name: &str) -> &[String] {
+        self.entries
+            .get(name)
+            .map(|r| r.tags.as_slice())
+            .unwrap_or_default()
+    }
+
+    /// Replace a session's tags; none removes its entry
+    pub fn set(&mut self, name: &str, tags: Vec<String>, now: u64) {
+        if tags.is_empty() {
+            self.entries.remove(name);
+        } else {
+            self.entries.insert(name, TagRecord { tags, seen_at: now });
+        }
+    }
+
+    /// Move a session's tags to its new name
+    pub fn rename(&mut self, old: &str, new: &str) {
+        self.entries.rename(old, new);
+    }
+
+    /// Note which names are still listed; see [`SeenByName::refresh`]
+    pub fn refresh<'a>(&mut self, listed: impl IntoIterator<Item = &'a str>, now: u64) -> bool {
+        self.entries.refresh(listed, now)
+    }
+
+    /// Copy each session's tags onto it
+    pub fn apply(&self, sessions: &mut [TmuxSession]) {
+        for session in sessions {
+            session.tags = self.get(&session.name).to_vec();
+        }
+    }
+}
+
+/// Tags typed as a list separated by spaces or commas; a leading `#` is
+/// dropped
+pub fn parse(input: &str) -> Result<Vec<String>, String> {
+    let mut tags = BTreeSet::new();
+    for word in input.split(|c: char| c.is_whitespace() || c == ',') {
+        let tag = word.strip_prefix('#').unwrap_or(word);
+        if tag.is_empty() {
+            continue;
+        }
+        if !tag
+            .chars()
+            .all(|c| c.is_alphanumeric() || matches!(c, '-' | '_' | '/' | '.'))
+        {
+            return Err(format!(
+                "'{}' isn't a tag: use letters, digits and - _ / .",
+                word
+            ));
+        }
+        tags.insert(tag.to_string());
+    }
+    Ok(tags.into_iter().collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_parse() {
+        assert_eq!(
+            parse("backend, #infra  backend"),
+            Ok(vec!["backend".to_string(), "infra".to_string()])
+        );
+        assert_eq!(parse(" , "), Ok(Vec::new()));
+        assert!(parse("client:acme").is_err());
+    }
+
+    #[test]
+    fn test_set_rename_and_apply() {
+        let mut tags = Tags::default();
+        tags.set("api", vec!["backend".to_string()], 0);
+        tags.set("web", vec!["frontend".to_string()], 0);
+        tags.set("web", Vec::new(), 0);
+        assert!(tags.get("web").is_empty());
+
+        tags.rename("api", "api-v2");
+        let mut sessions = vec![TmuxSession::new("$0".to_string(), "api-v2".to_string())];
+        tags.apply(&mut sessions);
+        assert_eq!(sessions[0].tags, ["backend"]);
+    }
+}
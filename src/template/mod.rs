@@ -0,0 +1,176 @@
+//! Sets of sessions created together for a project: an agent, its tests and
+//! a server, say.
+//!
+//! `{project}` in a session's name is the project directory's name, so one
+//! template serves every project.
+
+use serde::Deserialize;
+use std::path::Path;
+
+use crate::launcher::{Launcher, NewSession};
+use crate::worktree;
+
+/// `[[template]]` entry of the config
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct Template {
+    /// Shown in the templates menu
+    pub name: String,
+    /// `[[template.session]]` tables, created in order
+    #[serde(rename = "session")]
+    pub sessions: Vec<TemplateSession>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct TemplateSession {
+    /// Session name; `{project}` is replaced
+    pub name: String,
+    /// Where it starts, relative to the project directory; the project
+    /// directory itself when unset
+    #[serde(default)]
+    pub dir: Option<String>,
+    /// What it runs; a plain shell when unset
+    #[serde(default)]
+    pub command: Option<String>,
+}
+
+/// Why a list of templates can't be used, if it can't
+pub fn validate(templates: &[Template]) -> Result<(), String> {
+    for (index, template) in templates.iter().enumerate() {
+        if template.name.trim().is_empty() {
+            return Err(format!("[{}] needs a `name`", index));
+        }
+        if templates[..index].iter().any(|t| t.name == template.name) {
+            return Err(format!("[{}] repeats the name '{}'", index, template.name));
+        }
+        if template.sessions.is_empty() {
+            return Err(format!("[{}] needs a [[template.session]]", index));
+        }
+        for (i, session) in template.sessions.iter().enumerate() {
+            if template.sessions[..i]
+                .iter()
+                .any(|s| s.name == session.name)
+            {
+                return Err(format!(
+                    "[{}] repeats the session '{}'",
+                    index, session.name
+                ));
+            }
+        }
+    }
+    Ok(())
+}
+
+/// `{project}` for `dir`: its name, with what tmux doesn't allow in session
+/// names replaced
+pub fn project_name(dir: &Path) -> String {
+    let name = dir
+        .file_name()
+        .map(|name| name.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    name.chars()
+        .map(|c| if matches!(c, '.' | ':') { '_' } else { c })
+        .collect()
+}
+
+/// The sessions `template` creates for the project in `project`
+pub fn plan(template: &Template, project: &Path) -> Vec<NewSession> {
+    let project_name = project_name(project);
+    template
+        .sessions
+        .iter()
+        .map(|session| NewSession {
+            name: session.name.replace("{project}", &project_name),
+            launcher: Some(Launcher {
+                name: template.name.clone(),
+                command: session.command.clone(),
+                ask_dir: false,
+                ask_command: false,
+                resume: false,
+            }),
+            cwd: Some(match &session.dir {
+                Some(dir) => project.join(worktree::expand_home(Path::new(dir))),
+                None => project.to_path_buf(),
+            }),
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn template() -> Template {
+        Template {
+            name: "web".to_string(),
+            sessions: vec![
+                TemplateSession {
+                    name: "{project}-agent".to_string(),
+                    dir: None,
+                    command: Some("claude".to_string()),
+                },
+                TemplateSession {
+                    name: "{project}-server".to_string(),
+                    dir: Some("frontend".to_string()),
+                    command: Some("npm run dev".to_string()),
+                },
+                TemplateSession {
+                    name: "scratch".to_string(),
+                    dir: Some("/tmp".to_string()),
+                    command: None,
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_plan() {
+        let sessions = plan(&template(), Path::new("/work/shop.io"));
+        let planned: Vec<(&str, Option<&str>, Option<&Path>)> = sessions
+            .iter()
+            .map(|s| {
+                (
+                    s.name.as_str(),
+                    s.launcher.as_ref().and_then(|l| l.command.as_deref()),
+                    s.cwd.as_deref(),
+                )
+            })
+            .collect();
+        assert_eq!(
+            planned,
+            [
+                (
+                    "shop_io-agent",
+                    Some("claude"),
+                    Some(Path::new("/work/shop.io"))
+                ),
+                (
+                    "shop_io-server",
+                    Some("npm run dev"),
+                    Some(Path::new("/work/shop.io/frontend"))
+                ),
+                ("scratch", None, Some(Path::new("/tmp"))),
+            ]
+        );
+        assert_eq!(sessions[0].launcher.as_ref().unwrap().name, "web");
+    }
+
+    #[test]
+    fn test_validate() {
+        assert!(validate(&[template()]).is_ok());
+        assert_eq!(
+            validate(&[template(), template()]),
+            Err("[1] repeats the name 'web'".to_string())
+        );
+        let mut empty = template();
+        empty.sessions.clear();
+        assert!(validate(&[empty]).is_err());
+        let mut repeated = template();
+        repeated.sessions.push(repeated.sessions[0].clone());
+        assert_eq!(
+            validate(&[repeated]),
+            Err("[0] repeats the session '{project}-agent'".to_string())
+        );
+    }
+}
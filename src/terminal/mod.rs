@@ -0,0 +1,150 @@
+use std::process::{Command, Stdio};
+
+/// Install a panic hook that runs `restore` before the default hook prints
+///
+/// Without this the message is drawn on the alternate screen and lost, and
+/// the shell is left in raw mode. The panic and a backtrace also go to the
+/// log. `restore` must be safe to call when the terminal is already restored
+/// (e.g. while suspended for an attach).
+pub fn install_panic_hook(restore: fn()) {
+    let default_hook = std::panic::take_hook();
+    std::panic::set_hook(Box::new(move |info| {
+        restore();
+        tracing::error!("{}\n{}", info, std::backtrace::Backtrace::force_capture());
+        default_hook(info);
+    }));
+}
+
+/// Guess a new-window command for the terminal we are running in
+///
+/// `env` looks up an environment variable, so tests can supply their own.
+pub fn detect(env: impl Fn(&str) -> Option<String>) -> Option<&'static str> {
+    let term_program = env("TERM_PROGRAM").unwrap_or_default().to_lowercase();
+    if env("KITTY_WINDOW_ID").is_some() || term_program == "kitty" {
+        Some("kitty {cmd}")
+    } else if env("WEZTERM_PANE").is_some() || term_program == "wezterm" {
+        Some("wezterm start -- {cmd}")
+    } else if env("ALACRITTY_SOCKET").is_some() || term_program == "alacritty" {
+        Some("alacritty -e {cmd}")
+    } else if term_program == "ghostty" {
+        Some("ghostty -e {cmd}")
+    } else if env("GNOME_TERMINAL_SCREEN").is_some() {
+        Some("gnome-terminal -- {cmd}")
+    } else if env("KONSOLE_VERSION").is_some() {
+        Some("konsole -e {cmd}")
+    } else {
+        None
+    }
+}
+
+/// Build the argv for `template`, putting `cmd` where `{cmd}` appears
+///
+/// Without a placeholder the command is appended.
+pub fn command_argv(template: &str, cmd: &[String]) -> Vec<String> {
+    let mut argv = Vec::new();
+    let mut placed = false;
+    for part in template.split_whitespace() {
+        if part == "{cmd}" {
+            argv.extend(cmd.iter().cloned());
+            placed = true;
+        } else {
+            argv.push(part.to_string());
+        }
+    }
+    if !placed {
+        argv.extend(cmd.iter().cloned());
+    }
+    argv
+}
+
+/// Start `argv` detached from our terminal and process group
+pub fn spawn_detached(argv: &[String]) -> std::io::Result<()> {
+    let Some((program, args)) = argv.split_first() else {
+        return Err(std::io::Error::other("empty terminal command"));
+    };
+
+    let mut command = Command::new(program);
+    command
+        .args(args)
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null());
+    #[cfg(unix)]
+    {
+        use std::os::unix::process::CommandExt;
+        // Keep Ctrl-C in the TUI from reaching the new window
+        command.process_group(0);
+    }
+    let mut child = command.spawn()?;
+
+    // Reap the child when it exits so it doesn't linger as a zombie
+    std::thread::spawn(move || child.wait());
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn attach() -> Vec<String> {
+        ["tmux", "attach-session", "-t", "$3"]
+            .iter()
+            .map(|s| s.to_string())
+            .collect()
+    }
+
+    #[test]
+    fn test_command_argv() {
+        assert_eq!(
+            command_argv("wezterm start -- {cmd}", &attach()),
+            vec![
+                "wezterm",
+                "start",
+                "--",
+                "tmux",
+                "attach-session",
+                "-t",
+                "$3"
+            ]
+        );
+        assert_eq!(
+            command_argv("foot", &attach()),
+            vec!["foot", "tmux", "attach-session", "-t", "$3"]
+        );
+    }
+
+    #[test]
+    fn test_panic_hook_restores_first() {
+        use std::sync::atomic::{AtomicBool, Ordering};
+        static RESTORED: AtomicBool = AtomicBool::new(false);
+
+        install_panic_hook(|| RESTORED.store(true, Ordering::SeqCst));
+        let result = std::panic::catch_unwind(|| panic!("deliberate test panic"));
+        // Put the default hook back for the other tests
+        let _ = std::panic::take_hook();
+
+        assert!(result.is_err());
+        assert!(RESTORED.load(Ordering::SeqCst));
+    }
+
+    #[test]
+    fn test_detect() {
+        let env = |vars: &'static [(&'static str, &'static str)]| {
+            move |key: &str| {
+                vars.iter()
+                    .find(|(k, _)| *k == key)
+                    .map(|(_, v)| v.to_string())
+            }
+        };
+        assert_eq!(
+            detect(env(&[("KITTY_WINDOW_ID", "1")])),
+            Some("kitty {cmd}")
+        );
+        assert_eq!(
+            detect(env(&[("TERM_PROGRAM", "WezTerm")])),
+            Some("wezterm start -- {cmd}")
+        );
+        assert_eq!(detect(env(&[("TERM_PROGRAM", "Apple_Terminal")])), None);
+        assert_eq!(detect(env(&[])), None);
+    }
+}
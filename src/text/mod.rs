@@ -0,0 +1,282 @@
+use once_cell::sync::Lazy;
+use regex::Regex;
+use std::borrow::Cow;
+use std::time::{SystemTime, UNIX_EPOCH};
+use unicode_width::{UnicodeWidthChar, UnicodeWidthStr};
+
+/// Current Unix time in seconds
+pub fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+/// CSI sequences (colors, cursor movement) and OSC sequences (titles, links)
+static RE_ANSI: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\x1b\[[0-?]*[ -/]*[@-~]|\x1b\][^\x07\x1b]*(\x07|\x1b\\)").unwrap());
+
+/// Remove terminal escape sequences from captured output
+pub fn strip_ansi(s: &str) -> Cow<'_, str> {
+    RE_ANSI.replace_all(s, "")
+}
+
+/// Human-readable byte count (`512 B`, `4.2 KB`, `1.3 MB`)
+pub fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        0..1024 => format!("{} B", bytes),
+        1024..1_048_576 => format!("{:.1} KB", bytes as f64 / 1024.0),
+        _ => format!("{:.1} MB", bytes as f64 / 1_048_576.0),
+    }
+}
+
+/// Compact duration using the largest whole unit (`45s`, `12m`, `6h`, `3d`)
+pub fn format_duration_short(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        3600..86400 => format!("{}h", secs / 3600),
+        _ => format!("{}d", secs / 86400),
+    }
+}
+
+/// Two-unit duration for longer spans (`45s`, `12m 5s`, `6h 42m`, `3d 4h`)
+pub fn format_duration_long(secs: u64) -> String {
+    match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m {}s", secs / 60, secs % 60),
+        3600..86400 => format!("{}h {}m", secs / 3600, secs % 3600 / 60),
+        _ => format!("{}d {}h", secs / 86400, secs % 86400 / 3600),
+    }
+}
+
+/// How long before `now` the Unix time `at` was (`45s ago`, `12m ago`,
+/// `2h 14m ago`, `3d 4h ago`); tmux reports 0 for times it couldn't give
+pub fn format_ago(at: u64, now: u64) -> String {
+    if at == 0 {
+        return "unknown".to_string();
+    }
+    let Some(secs) = now.checked_sub(at) else {
+        return "just now".to_string();
+    };
+    let span = match secs {
+        0..60 => format!("{}s", secs),
+        60..3600 => format!("{}m", secs / 60),
+        _ => format_duration_long(secs),
+    };
+    format!("{} ago", span)
+}
+
+/// Unix time `at` as local date and time, or `unknown` for 0
+pub fn format_local_time(at: u64) -> String {
+    if at == 0 {
+        return "unknown".to_string();
+    }
+    chrono::DateTime::from_timestamp(at as i64, 0).map_or_else(
+        || "unknown".to_string(),
+        |time| {
+            time.with_timezone(&chrono::Local)
+                .format("%Y-%m-%d %H:%M")
+                .to_string()
+        },
+    )
+}
+
+/// Shorten `s` to at most `max_width` terminal cells by replacing its middle
+/// with an ellipsis (`agent-paym…new-prompt`)
+pub fn truncate_middle(s: &str, max_width: usize) -> Cow<'_, str> {
+    if s.width() <= max_width {
+        return Cow::Borrowed(s);
+    }
+    if max_width == 0 {
+        return Cow::Borrowed("");
+    }
+
+    // Favour the head when the budget is odd
+    let budget = max_width - 1;
+    let tail_budget = budget / 2;
+    let head_budget = budget - tail_budget;
+
+    let mut head = String::new();
+    let mut used = 0;
+    for c in s.chars() {
+        let w = c.width().unwrap_or(0);
+        if used + w > head_budget {
+            break;
+        }
+        used += w;
+        head.push(c);
+    }
+
+    let mut tail = Vec::new();
+    let mut used = 0;
+    for c in s.chars().rev() {
+        let w = c.width().unwrap_or(0);
+        if used + w > tail_budget {
+            break;
+        }
+        used += w;
+        tail.push(c);
+    }
+
+    head.push('…');
+    head.extend(tail.into_iter().rev());
+    Cow::Owned(head)
+}
+
+/// Break `s` into lines of at most `width` cells, at spaces where possible;
+/// a word longer than a line is split
+pub fn wrap(s: &str, width: usize) -> Vec<String> {
+    let width = width.max(1);
+    let mut lines = Vec::new();
+    let mut line = String::new();
+    let mut used = 0;
+    for word in s.split(' ') {
+        let w = word.width();
+        if used > 0 && used + 1 + w <= width {
+            line.push(' ');
+            line.push_str(word);
+            used += 1 + w;
+            continue;
+        }
+        if used > 0 || !line.is_empty() {
+            lines.push(std::mem::take(&mut line));
+            used = 0;
+        }
+        for c in word.chars() {
+            let cw = c.width().unwrap_or(0);
+            if used + cw > width {
+                lines.push(std::mem::take(&mut line));
+                used = 0;
+            }
+            line.push(c);
+            used += cw;
+        }
+    }
+    lines.push(line);
+    lines
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_strip_ansi() {
+        assert_eq!(
+            strip_ansi("\x1b[1;32m✓\x1b[0m passed \x1b[38;2;80;200;120mok\x1b[m"),
+            "✓ passed ok"
+        );
+        assert_eq!(
+            strip_ansi("\x1b]0;title\x07see \x1b]8;;https://x.dev\x1b\\link\x1b]8;;\x1b\\"),
+            "see link"
+        );
+        assert!(matches!(strip_ansi("plain"), Cow::Borrowed("plain")));
+    }
+
+    #[test]
+    fn test_format_bytes() {
+        assert_eq!(format_bytes(0), "0 B");
+        assert_eq!(format_bytes(1023), "1023 B");
+        assert_eq!(format_bytes(4300), "4.2 KB");
+        assert_eq!(format_bytes(3 * 1_048_576 / 2), "1.5 MB");
+    }
+
+    #[test]
+    fn test_format_duration_short() {
+        assert_eq!(format_duration_short(0), "0s");
+        assert_eq!(format_duration_short(59), "59s");
+        assert_eq!(format_duration_short(60), "1m");
+        assert_eq!(format_duration_short(3599), "59m");
+        assert_eq!(format_duration_short(3600), "1h");
+        assert_eq!(format_duration_short(86400 * 3 + 5), "3d");
+    }
+
+    #[test]
+    fn test_format_duration_long() {
+        assert_eq!(format_duration_long(7), "7s");
+        assert_eq!(format_duration_long(725), "12m 5s");
+        assert_eq!(format_duration_long(6 * 3600 + 42 * 60 + 59), "6h 42m");
+        assert_eq!(format_duration_long(3 * 86400 + 4 * 3600 + 120), "3d 4h");
+    }
+
+    #[test]
+    fn test_format_ago() {
+        let now = 1_700_000_000;
+        assert_eq!(format_ago(now, now), "0s ago");
+        assert_eq!(format_ago(now - 59, now), "59s ago");
+        assert_eq!(format_ago(now - 60, now), "1m ago");
+        assert_eq!(format_ago(now - 3599, now), "59m ago");
+        assert_eq!(format_ago(now - 3600, now), "1h 0m ago");
+        assert_eq!(
+            format_ago(now - (2 * 3600 + 14 * 60 + 30), now),
+            "2h 14m ago"
+        );
+        assert_eq!(format_ago(now - 86399, now), "23h 59m ago");
+        assert_eq!(format_ago(now - 86400, now), "1d 0h ago");
+        assert_eq!(format_ago(now - (3 * 86400 + 4 * 3600), now), "3d 4h ago");
+
+        // Not 1970, and a clock running behind tmux's isn't the future
+        assert_eq!(format_ago(0, now), "unknown");
+        assert_eq!(format_ago(now + 5, now), "just now");
+        assert_eq!(format_local_time(0), "unknown");
+        assert_eq!(format_local_time(now).len(), "2023-11-14 22:13".len());
+    }
+
+    #[test]
+    fn test_truncate_middle_ascii() {
+        let name = "agent-payments-refactor-attempt-3-with-new-prompt";
+        let short = truncate_middle(name, 21);
+        assert_eq!(short, "agent-paym…new-prompt");
+        assert_eq!(short.width(), 21);
+
+        assert_eq!(truncate_middle("short", 10), "short");
+        assert_eq!(truncate_middle("abcdef", 4), "ab…f");
+        assert_eq!(truncate_middle("abcdef", 1), "…");
+        assert_eq!(truncate_middle("abcdef", 0), "");
+    }
+
+    #[test]
+    fn test_truncate_middle_exact_fit() {
+        assert!(matches!(
+            truncate_middle("abcde", 5),
+            Cow::Borrowed("abcde")
+        ));
+        assert_eq!(truncate_middle("abcdef", 5), "ab…ef");
+        assert_eq!(truncate_middle("", 0), "");
+    }
+
+    #[test]
+    fn test_truncate_middle_wide_chars() {
+        // Each CJK character takes two cells
+        let cjk = "日本語のセッション";
+        assert_eq!(cjk.width(), 18);
+        let short = truncate_middle(cjk, 8);
+        assert_eq!(short, "日本…ン");
+        assert!(short.width() <= 8);
+
+        // An odd budget cannot be filled exactly with double-width characters
+        let short = truncate_middle(cjk, 7);
+        assert_eq!(short, "日…ン");
+        assert!(short.width() <= 7);
+    }
+
+    #[test]
+    fn test_truncate_middle_emoji() {
+        let name = "🚀deploy-the-rocket🚀";
+        let short = truncate_middle(name, 9);
+        assert_eq!(short, "🚀de…et🚀");
+        assert_eq!(short.width(), 9);
+    }
+
+    #[test]
+    fn test_wrap() {
+        assert_eq!(
+            wrap("waiting on the PR review", 10),
+            ["waiting on", "the PR", "review"]
+        );
+        assert_eq!(wrap("abcdefghij klm", 4), ["abcd", "efgh", "ij", "klm"]);
+        assert_eq!(wrap("short", 10), ["short"]);
+        assert_eq!(wrap("", 10), [""]);
+    }
+}
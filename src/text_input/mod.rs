@@ -0,0 +1,217 @@
+//! A line of text being typed into a dialog, with a cursor that can move
+//! through it.
+//!
+//! The cursor is a byte offset that always sits on a character boundary, so
+//! names with multi-byte letters edit a character at a time.
+
+use crossterm::event::{KeyCode, KeyEvent, KeyModifiers};
+use std::fmt;
+use unicode_width::UnicodeWidthStr;
+
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct TextInput {
+    text: String,
+    cursor: usize,
+}
+
+impl TextInput {
+    pub fn as_str(&self) -> &str {
+        &self.text
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.text.is_empty()
+    }
+
+    /// Byte offset of the cursor
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Display column of the cursor, counting wide characters as two
+    pub fn cursor_column(&self) -> usize {
+        self.text[..self.cursor].width()
+    }
+
+    /// Replace the text, with the cursor at its end
+    pub fn set(&mut self, text: impl Into<String>) {
+        self.text = text.into();
+        self.cursor = self.text.len();
+    }
+
+    pub fn clear(&mut self) {
+        self.text.clear();
+        self.cursor = 0;
+    }
+
+    /// The text, leaving the input empty
+    pub fn take(&mut self) -> String {
+        self.cursor = 0;
+        std::mem::take(&mut self.text)
+    }
+
+    pub fn insert(&mut self, c: char) {
+        self.text.insert(self.cursor, c);
+        self.cursor += c.len_utf8();
+    }
+
+    pub fn insert_str(&mut self, s: &str) {
+        self.text.insert_str(self.cursor, s);
+        self.cursor += s.len();
+    }
+
+    /// Delete the character before the cursor
+    pub fn backspace(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+            self.text.remove(self.cursor);
+        }
+    }
+
+    /// Delete the character under the cursor
+    pub fn delete(&mut self) {
+        if self.cursor < self.text.len() {
+            self.text.remove(self.cursor);
+        }
+    }
+
+    pub fn left(&mut self) {
+        if let Some(c) = self.text[..self.cursor].chars().next_back() {
+            self.cursor -= c.len_utf8();
+        }
+    }
+
+    pub fn right(&mut self) {
+        if let Some(c) = self.text[self.cursor..].chars().next() {
+            self.cursor += c.len_utf8();
+        }
+    }
+
+    pub fn home(&mut self) {
+        self.cursor = 0;
+    }
+
+    pub fn end(&mut self) {
+        self.cursor = self.text.len();
+    }
+
+    /// Delete the word before the cursor, and any separators after it
+    pub fn delete_word(&mut self) {
+        let before = &self.text[..self.cursor];
+        let word_end = before
+            .trim_end_matches(|c: char| !c.is_alphanumeric())
+            .len();
+        let start = before[..word_end]
+            .trim_end_matches(char::is_alphanumeric)
+            .len();
+        self.text.replace_range(start..self.cursor, "");
+        self.cursor = start;
+    }
+
+    /// Apply an editing or cursor key; `false` if `key` is neither, such as
+    /// a character to insert
+    pub fn edit(&mut self, key: &KeyEvent) -> bool {
+        if key.modifiers.contains(KeyModifiers::CONTROL) {
+            match key.code {
+                KeyCode::Char('w') => self.delete_word(),
+                KeyCode::Char('u') => self.clear(),
+                _ => return false,
+            }
+            return true;
+        }
+        match key.code {
+            KeyCode::Left => self.left(),
+            KeyCode::Right => self.right(),
+            KeyCode::Home => self.home(),
+            KeyCode::End => self.end(),
+            KeyCode::Backspace => self.backspace(),
+            KeyCode::Delete => self.delete(),
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl fmt::Display for TextInput {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn input(text: &str) -> TextInput {
+        let mut input = TextInput::default();
+        input.set(text);
+        input
+    }
+
+    #[test]
+    fn test_multibyte_cursor() {
+        let mut name = input("café_日本");
+        assert_eq!(name.cursor(), "café_日本".len());
+        assert_eq!(name.cursor_column(), 9);
+
+        name.left();
+        name.left();
+        assert_eq!(name.cursor_column(), 5);
+        name.backspace();
+        assert_eq!(name.as_str(), "café日本");
+        name.left();
+        name.delete();
+        assert_eq!(name.as_str(), "caf日本");
+        assert_eq!(name.cursor_column(), 3);
+        name.insert('é');
+        name.insert('-');
+        assert_eq!(name.as_str(), "café-日本");
+
+        name.home();
+        name.left();
+        name.backspace();
+        name.insert_str("my_");
+        assert_eq!(name.as_str(), "my_café-日本");
+        name.end();
+        name.right();
+        name.delete();
+        assert_eq!(name.cursor_column(), 12);
+        assert_eq!(name.take(), "my_café-日本");
+        assert!(name.is_empty());
+        assert_eq!(name.cursor(), 0);
+    }
+
+    #[test]
+    fn test_delete_word() {
+        let mut name = input("api-naïve_fix");
+        name.delete_word();
+        assert_eq!(name.as_str(), "api-naïve_");
+        name.delete_word();
+        assert_eq!(name.as_str(), "api-");
+        name.delete_word();
+        name.delete_word();
+        assert!(name.is_empty());
+
+        // Only what is before the cursor
+        let mut name = input("über-api");
+        name.left();
+        name.left();
+        name.left();
+        name.delete_word();
+        assert_eq!(name.as_str(), "api");
+        assert_eq!(name.cursor(), 0);
+    }
+
+    #[test]
+    fn test_edit_keys() {
+        let mut name = input("alpha");
+        let ctrl = |c| KeyEvent::new(KeyCode::Char(c), KeyModifiers::CONTROL);
+        assert!(name.edit(&KeyEvent::from(KeyCode::Home)));
+        assert!(name.edit(&KeyEvent::from(KeyCode::Delete)));
+        assert_eq!(name.as_str(), "lpha");
+        assert!(!name.edit(&KeyEvent::from(KeyCode::Char('w'))));
+        assert!(!name.edit(&ctrl('x')));
+        assert!(name.edit(&ctrl('u')));
+        assert!(name.is_empty());
+    }
+}
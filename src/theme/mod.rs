@@ -0,0 +1,602 @@
+//! Dashboard colors and how they degrade on limited terminals.
+//!
+//! Render code picks colors by role (`theme.accent`, `theme.dim`, ...). The
+//! roles come from a built-in palette, `dark` or `light`, with any the
+//! `[theme]` section sets as `#rrggbb` on top. On a truecolor terminal the
+//! roles are RGB; on 256-color terminals they map to the nearest indexed
+//! color, 16-color terminals get a fixed palette, and under `NO_COLOR` the
+//! finished frame has its colors swapped for bold, dim and reverse.
+
+use ratatui::{
+    buffer::Buffer,
+    style::{Color, Modifier, Style},
+    text::Span,
+};
+use serde::Deserialize;
+use unicode_width::UnicodeWidthStr;
+
+use crate::tmux::AgentStatus;
+
+/// Frames of the spinner busy sessions show in the list
+const BUSY_SPINNER: [&str; 10] = ["⠋", "⠙", "⠹", "⠸", "⠼", "⠴", "⠦", "⠧", "⠇", "⠏"];
+
+/// `[status_icons]` section of the config: the glyph shown for each status
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct StatusIcons {
+    pub busy: String,
+    /// Animate busy sessions in the list with a braille spinner; `false`
+    /// shows `busy` there too, for terminals without braille glyphs
+    pub spinner: bool,
+    pub idle: String,
+    pub waiting_for_input: String,
+    pub error: String,
+    pub unknown: String,
+}
+
+impl Default for StatusIcons {
+    fn default() -> Self {
+        Self {
+            busy: "●".to_string(),
+            spinner: true,
+            idle: "●".to_string(),
+            waiting_for_input: "?".to_string(),
+            error: "✗".to_string(),
+            unknown: "○".to_string(),
+        }
+    }
+}
+
+impl StatusIcons {
+    pub fn get(&self, status: AgentStatus) -> &str {
+        match status {
+            AgentStatus::Busy => &self.busy,
+            AgentStatus::Idle => &self.idle,
+            AgentStatus::WaitingForInput => &self.waiting_for_input,
+            AgentStatus::Error => &self.error,
+            AgentStatus::Unknown => &self.unknown,
+        }
+    }
+
+    /// Icons that don't take exactly one cell, which misaligns the list columns
+    pub fn warnings(&self) -> Vec<String> {
+        [
+            ("busy", &self.busy),
+            ("idle", &self.idle),
+            ("waiting_for_input", &self.waiting_for_input),
+            ("error", &self.error),
+            ("unknown", &self.unknown),
+        ]
+        .into_iter()
+        .filter_map(|(name, icon)| match icon.width() {
+            1 => None,
+            width => Some(format!(
+                "status_icons.{} {:?} is {} cells wide; columns expect 1",
+                name, icon, width
+            )),
+        })
+        .collect()
+    }
+}
+
+/// Colors a terminal can show
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum ColorSupport {
+    #[serde(rename = "truecolor")]
+    TrueColor,
+    #[serde(rename = "256")]
+    Ansi256,
+    #[serde(rename = "16")]
+    Ansi16,
+    /// No colors at all; emphasis comes from modifiers
+    #[serde(rename = "none")]
+    Monochrome,
+}
+
+impl ColorSupport {
+    /// Detect from `NO_COLOR`, `COLORTERM` and `TERM`
+    pub fn detect() -> Self {
+        let var = |name| std::env::var(name).ok();
+        Self::from_env(
+            var("NO_COLOR").as_deref(),
+            var("COLORTERM").as_deref(),
+            var("TERM").as_deref(),
+        )
+    }
+
+    pub fn from_env(no_color: Option<&str>, colorterm: Option<&str>, term: Option<&str>) -> Self {
+        // https://no-color.org: set and not empty
+        if no_color.is_some_and(|value| !value.is_empty()) {
+            return ColorSupport::Monochrome;
+        }
+        if colorterm.is_some_and(|value| matches!(value, "truecolor" | "24bit")) {
+            return ColorSupport::TrueColor;
+        }
+        let term = term.unwrap_or_default();
+        if term.is_empty() || term == "dumb" {
+            ColorSupport::Monochrome
+        } else if term.ends_with("-direct") || term.contains("truecolor") {
+            ColorSupport::TrueColor
+        } else if term.contains("256color") {
+            ColorSupport::Ansi256
+        } else {
+            ColorSupport::Ansi16
+        }
+    }
+}
+
+/// Color of each role on a truecolor terminal
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Palette {
+    pub bg: Color,
+    pub fg: Color,
+    pub accent: Color,
+    pub dim: Color,
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+    pub highlight: Color,
+}
+
+/// Names of the built-in palettes, in the order `c` cycles through them
+pub const PALETTES: [&str; 2] = ["dark", "light"];
+
+impl Palette {
+    /// Built-in palette by name
+    pub fn named(name: &str) -> Option<Self> {
+        match name {
+            // Inspired by Claude Code
+            "dark" => Some(Self {
+                bg: Color::Rgb(30, 30, 30),
+                fg: Color::Rgb(220, 220, 220),
+                accent: Color::Rgb(217, 119, 87), // Claude orange
+                dim: Color::Rgb(100, 100, 100),
+                success: Color::Rgb(80, 200, 120),
+                warning: Color::Rgb(255, 193, 7),
+                error: Color::Rgb(220, 53, 69),
+                highlight: Color::Rgb(50, 50, 50),
+            }),
+            // Darker tones that keep their contrast on a white background
+            "light" => Some(Self {
+                bg: Color::Rgb(250, 250, 250),
+                fg: Color::Rgb(40, 40, 40),
+                accent: Color::Rgb(184, 84, 50),
+                dim: Color::Rgb(120, 120, 120),
+                success: Color::Rgb(30, 130, 70),
+                warning: Color::Rgb(160, 105, 0),
+                error: Color::Rgb(190, 30, 45),
+                highlight: Color::Rgb(222, 222, 222),
+            }),
+            _ => None,
+        }
+    }
+}
+
+/// `[theme]` section of the config: a built-in palette, and `#rrggbb`
+/// colors replacing some of its roles
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ThemeConfig {
+    pub name: String,
+    pub bg: Option<String>,
+    pub fg: Option<String>,
+    pub accent: Option<String>,
+    pub dim: Option<String>,
+    pub success: Option<String>,
+    pub warning: Option<String>,
+    pub error: Option<String>,
+    pub highlight: Option<String>,
+}
+
+impl Default for ThemeConfig {
+    fn default() -> Self {
+        Self {
+            name: PALETTES[0].to_string(),
+            bg: None,
+            fg: None,
+            accent: None,
+            dim: None,
+            success: None,
+            warning: None,
+            error: None,
+            highlight: None,
+        }
+    }
+}
+
+impl ThemeConfig {
+    fn overrides(&self) -> [(&'static str, &Option<String>); 8] {
+        [
+            ("bg", &self.bg),
+            ("fg", &self.fg),
+            ("accent", &self.accent),
+            ("dim", &self.dim),
+            ("success", &self.success),
+            ("warning", &self.warning),
+            ("error", &self.error),
+            ("highlight", &self.highlight),
+        ]
+    }
+
+    /// The palette named `name` with this section's colors on top; an
+    /// unknown name gives the dark palette, and a color that doesn't parse
+    /// leaves its role as it was
+    pub fn palette(&self, name: &str) -> Palette {
+        let mut palette = Palette::named(name)
+            .or_else(|| Palette::named(PALETTES[0]))
+            .expect("the first palette is built in");
+        for (role, value) in self.overrides() {
+            let Some(color) = value.as_deref().and_then(parse_hex) else {
+                continue;
+            };
+            let slot = match role {
+                "bg" => &mut palette.bg,
+                "fg" => &mut palette.fg,
+                "accent" => &mut palette.accent,
+                "dim" => &mut palette.dim,
+                "success" => &mut palette.success,
+                "warning" => &mut palette.warning,
+                "error" => &mut palette.error,
+                _ => &mut palette.highlight,
+            };
+            *slot = color;
+        }
+        palette
+    }
+
+    /// Settings that were ignored
+    pub fn warnings(&self) -> Vec<String> {
+        let mut warnings = Vec::new();
+        if Palette::named(&self.name).is_none() {
+            warnings.push(format!(
+                "theme.name {:?} is not one of {}",
+                self.name,
+                PALETTES.join(", ")
+            ));
+        }
+        for (role, value) in self.overrides() {
+            if let Some(value) = value
+                && parse_hex(value).is_none()
+            {
+                warnings.push(format!("theme.{} {:?} is not #rrggbb", role, value));
+            }
+        }
+        warnings
+    }
+}
+
+/// Parse `#rrggbb` or `#rgb`
+pub fn parse_hex(text: &str) -> Option<Color> {
+    let hex = text.trim().strip_prefix('#')?;
+    if !hex.chars().all(|c| c.is_ascii_hexdigit()) {
+        return None;
+    }
+    let channel = |digits: &str| u8::from_str_radix(digits, 16).ok();
+    match hex.len() {
+        6 => Some(Color::Rgb(
+            channel(&hex[0..2])?,
+            channel(&hex[2..4])?,
+            channel(&hex[4..6])?,
+        )),
+        3 => {
+            let short = |i: usize| channel(&hex[i..=i]).map(|v| v * 17);
+            Some(Color::Rgb(short(0)?, short(1)?, short(2)?))
+        }
+        _ => None,
+    }
+}
+
+/// Theme colors by role, adapted to the terminal
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Theme {
+    pub bg: Color,
+    pub fg: Color,
+    pub accent: Color,
+    pub dim: Color,
+    pub success: Color,
+    pub warning: Color,
+    pub error: Color,
+    /// Background of the selected row
+    pub highlight: Color,
+    pub icons: StatusIcons,
+    support: ColorSupport,
+}
+
+impl Theme {
+    /// The dark palette
+    pub fn new(support: ColorSupport, icons: StatusIcons) -> Self {
+        Self::with_palette(&ThemeConfig::default().palette(PALETTES[0]), support, icons)
+    }
+
+    pub fn with_palette(palette: &Palette, support: ColorSupport, icons: StatusIcons) -> Self {
+        let truecolor = Self {
+            bg: palette.bg,
+            fg: palette.fg,
+            accent: palette.accent,
+            dim: palette.dim,
+            success: palette.success,
+            warning: palette.warning,
+            error: palette.error,
+            highlight: palette.highlight,
+            icons,
+            support,
+        };
+        match support {
+            // Roles keep distinct colors so `finish` can tell them apart
+            ColorSupport::TrueColor | ColorSupport::Monochrome => truecolor,
+            ColorSupport::Ansi256 => Self {
+                bg: ansi256(truecolor.bg),
+                fg: ansi256(truecolor.fg),
+                accent: ansi256(truecolor.accent),
+                dim: ansi256(truecolor.dim),
+                success: ansi256(truecolor.success),
+                warning: ansi256(truecolor.warning),
+                error: ansi256(truecolor.error),
+                highlight: ansi256(truecolor.highlight),
+                icons: truecolor.icons,
+                support,
+            },
+            ColorSupport::Ansi16 => Self {
+                bg: Color::Black,
+                fg: Color::Reset,
+                accent: Color::LightRed,
+                dim: Color::DarkGray,
+                success: Color::Green,
+                warning: Color::Yellow,
+                error: Color::Red,
+                highlight: Color::Blue,
+                icons: truecolor.icons,
+                support,
+            },
+        }
+    }
+
+    pub fn support(&self) -> ColorSupport {
+        self.support
+    }
+
+    /// Color of a status wherever it's shown
+    pub fn status_color(&self, status: AgentStatus) -> Color {
+        match status {
+            AgentStatus::Busy => self.warning,
+            AgentStatus::Idle => self.success,
+            AgentStatus::WaitingForInput => self.accent,
+            AgentStatus::Error => self.error,
+            AgentStatus::Unknown => self.dim,
+        }
+    }
+
+    /// Status glyph shown in front of a session name
+    pub fn status_icon(&self, status: AgentStatus) -> Span<'static> {
+        Span::styled(
+            format!("{} ", self.icons.get(status)),
+            Style::default().fg(self.status_color(status)),
+        )
+    }
+
+    /// Like [`status_icon`](Self::status_icon), but busy sessions get the
+    /// spinner's `frame`th glyph unless it is turned off
+    pub fn animated_status_icon(&self, status: AgentStatus, frame: u64) -> Span<'static> {
+        if status != AgentStatus::Busy || !self.icons.spinner {
+            return self.status_icon(status);
+        }
+        Span::styled(
+            format!(
+                "{} ",
+                BUSY_SPINNER[(frame % BUSY_SPINNER.len() as u64) as usize]
+            ),
+            Style::default().fg(self.status_color(status)),
+        )
+    }
+
+    /// Adapt a drawn frame to the terminal; in monochrome every color is
+    /// replaced: backgrounds become reverse video, dim text dim and the
+    /// accent, warning and error roles bold
+    pub fn finish(&self, buffer: &mut Buffer) {
+        if self.support != ColorSupport::Monochrome {
+            return;
+        }
+        for cell in buffer.content.iter_mut() {
+            let mut modifier = cell.modifier;
+            if cell.bg != Color::Reset {
+                modifier |= Modifier::REVERSED;
+            }
+            if cell.fg == self.dim {
+                modifier |= Modifier::DIM;
+            } else if [self.accent, self.warning, self.error].contains(&cell.fg) {
+                modifier |= Modifier::BOLD;
+            }
+            cell.set_style(Style::reset().add_modifier(modifier));
+        }
+    }
+}
+
+impl Default for Theme {
+    fn default() -> Self {
+        Self::new(ColorSupport::TrueColor, StatusIcons::default())
+    }
+}
+
+/// Levels of the xterm 6x6x6 color cube
+const CUBE: [u8; 6] = [0, 95, 135, 175, 215, 255];
+
+/// Nearest xterm-256 color to an RGB color, from the cube or the gray ramp
+fn ansi256(color: Color) -> Color {
+    let Color::Rgb(r, g, b) = color else {
+        return color;
+    };
+    let nearest_level = |value: u8| {
+        (0..CUBE.len())
+            .min_by_key(|&i| CUBE[i].abs_diff(value))
+            .unwrap_or_default()
+    };
+    let (ri, gi, bi) = (nearest_level(r), nearest_level(g), nearest_level(b));
+    let cube = (CUBE[ri], CUBE[gi], CUBE[bi]);
+    // Gray ramp 232..=255 runs from 8 to 238 in steps of 10
+    let average = (r as u32 + g as u32 + b as u32) / 3;
+    let step = (average.saturating_sub(3) / 10).min(23) as u8;
+    let gray = 8 + 10 * step;
+    let distance = |(cr, cg, cb): (u8, u8, u8)| {
+        [(cr, r), (cg, g), (cb, b)]
+            .iter()
+            .map(|&(a, b)| (a.abs_diff(b) as u32).pow(2))
+            .sum::<u32>()
+    };
+    if distance((gray, gray, gray)) < distance(cube) {
+        Color::Indexed(232 + step)
+    } else {
+        Color::Indexed(16 + 36 * ri as u8 + 6 * gi as u8 + bi as u8)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use ratatui::layout::Rect;
+
+    #[test]
+    fn test_detect() {
+        let detect = ColorSupport::from_env;
+        assert_eq!(
+            detect(Some("1"), Some("truecolor"), Some("xterm-256color")),
+            ColorSupport::Monochrome
+        );
+        // An empty NO_COLOR doesn't count
+        assert_eq!(
+            detect(Some(""), Some("truecolor"), Some("xterm")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            detect(None, Some("24bit"), Some("xterm")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            detect(None, None, Some("xterm-direct")),
+            ColorSupport::TrueColor
+        );
+        assert_eq!(
+            detect(None, None, Some("tmux-256color")),
+            ColorSupport::Ansi256
+        );
+        assert_eq!(detect(None, None, Some("xterm")), ColorSupport::Ansi16);
+        assert_eq!(detect(None, None, Some("linux")), ColorSupport::Ansi16);
+        assert_eq!(detect(None, None, Some("dumb")), ColorSupport::Monochrome);
+        assert_eq!(detect(None, None, None), ColorSupport::Monochrome);
+    }
+
+    #[test]
+    fn test_ansi256() {
+        // Claude orange lands on its nearest cube color, grays on the ramp
+        assert_eq!(ansi256(Color::Rgb(217, 119, 87)), Color::Indexed(173));
+        assert_eq!(ansi256(Color::Rgb(30, 30, 30)), Color::Indexed(234));
+        assert_eq!(ansi256(Color::Rgb(0, 0, 0)), Color::Indexed(16));
+        assert_eq!(ansi256(Color::Rgb(255, 255, 255)), Color::Indexed(231));
+        assert_eq!(ansi256(Color::Green), Color::Green);
+
+        let theme = Theme::new(ColorSupport::Ansi256, StatusIcons::default());
+        assert!(
+            [theme.accent, theme.dim, theme.success, theme.warning]
+                .iter()
+                .all(|color| matches!(color, Color::Indexed(_)))
+        );
+        assert_ne!(theme.accent, theme.error);
+    }
+
+    #[test]
+    fn test_icon_widths() {
+        assert!(StatusIcons::default().warnings().is_empty());
+
+        // Nerd Font glyphs live in the private use area and take one cell
+        let icons = StatusIcons {
+            busy: "\u{f110}".to_string(),
+            error: "E".to_string(),
+            ..StatusIcons::default()
+        };
+        assert!(icons.warnings().is_empty());
+        assert_eq!(icons.get(AgentStatus::Busy), "\u{f110}");
+
+        let icons = StatusIcons {
+            busy: "🔥".to_string(),
+            idle: "忙".to_string(),
+            waiting_for_input: "!!".to_string(),
+            error: String::new(),
+            ..StatusIcons::default()
+        };
+        assert_eq!(
+            icons.warnings(),
+            [
+                "status_icons.busy \"🔥\" is 2 cells wide; columns expect 1",
+                "status_icons.idle \"忙\" is 2 cells wide; columns expect 1",
+                "status_icons.waiting_for_input \"!!\" is 2 cells wide; columns expect 1",
+                "status_icons.error \"\" is 0 cells wide; columns expect 1",
+            ]
+        );
+    }
+
+    #[test]
+    fn test_parse_hex() {
+        assert_eq!(parse_hex("#d97757"), Some(Color::Rgb(217, 119, 87)));
+        assert_eq!(parse_hex(" #FFF "), Some(Color::Rgb(255, 255, 255)));
+        for bad in ["d97757", "#d9775", "#gg0000", "#+1+2+3", "", "#ééé"] {
+            assert_eq!(parse_hex(bad), None, "{}", bad);
+        }
+    }
+
+    #[test]
+    fn test_theme_config() {
+        let dark = Palette::named("dark").unwrap();
+        assert_eq!(ThemeConfig::default().palette("dark"), dark);
+        assert!(ThemeConfig::default().warnings().is_empty());
+
+        // Roles set in the config replace the palette's, whichever palette it is
+        let config = ThemeConfig {
+            accent: Some("#0000ff".to_string()),
+            error: Some("red".to_string()),
+            ..ThemeConfig::default()
+        };
+        let light = config.palette("light");
+        assert_eq!(light.accent, Color::Rgb(0, 0, 255));
+        assert_eq!(light.error, Palette::named("light").unwrap().error);
+        assert_eq!(light.fg, Palette::named("light").unwrap().fg);
+        assert_eq!(config.warnings(), ["theme.error \"red\" is not #rrggbb"]);
+
+        let config = ThemeConfig {
+            name: "solarized".to_string(),
+            ..ThemeConfig::default()
+        };
+        assert_eq!(config.palette(&config.name), dark);
+        assert_eq!(config.warnings().len(), 1);
+
+        let theme = Theme::with_palette(&light, ColorSupport::TrueColor, StatusIcons::default());
+        assert_eq!(theme.fg, Color::Rgb(40, 40, 40));
+        assert_eq!(theme.support(), ColorSupport::TrueColor);
+    }
+
+    #[test]
+    fn test_monochrome_finish() {
+        let theme = Theme::new(ColorSupport::Monochrome, StatusIcons::default());
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 4, 1));
+        buffer[(0, 0)].set_style(Style::default().fg(theme.dim));
+        buffer[(1, 0)].set_style(Style::default().fg(theme.accent));
+        buffer[(2, 0)].set_style(Style::default().fg(theme.fg).bg(theme.highlight));
+        buffer[(3, 0)].set_style(Style::default().fg(theme.success));
+        theme.finish(&mut buffer);
+
+        let cells = &buffer.content;
+        assert!(
+            cells
+                .iter()
+                .all(|cell| cell.fg == Color::Reset && cell.bg == Color::Reset)
+        );
+        assert_eq!(cells[0].modifier, Modifier::DIM);
+        assert_eq!(cells[1].modifier, Modifier::BOLD);
+        assert_eq!(cells[2].modifier, Modifier::REVERSED);
+        assert_eq!(cells[3].modifier, Modifier::empty());
+
+        // Other modes leave the frame alone
+        let mut buffer = Buffer::empty(Rect::new(0, 0, 1, 1));
+        buffer[(0, 0)].set_style(Style::default().fg(Color::Red));
+        Theme::new(ColorSupport::Ansi16, StatusIcons::default()).finish(&mut buffer);
+        assert_eq!(buffer.content[0].fg, Color::Red);
+    }
+}
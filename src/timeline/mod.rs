@@ -0,0 +1,105 @@
+//! Recent status changes of each session, for the single-session dashboard.
+//!
+//! Kept in memory only: the timeline starts when the dashboard does, and a
+//! session's entries go with it.
+
+use std::collections::{HashMap, VecDeque};
+
+use crate::poller::Transition;
+use crate::tmux::AgentStatus;
+
+/// Changes kept per session; older ones drop off
+pub const TIMELINE_LEN: usize = 50;
+
+/// A session entering a status
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct StatusChange {
+    /// Unix time the change was seen
+    pub at: u64,
+    pub status: AgentStatus,
+}
+
+/// Status changes by session id, oldest first
+#[derive(Debug, Default)]
+pub struct StatusTimeline {
+    sessions: HashMap<String, VecDeque<StatusChange>>,
+}
+
+impl StatusTimeline {
+    pub fn record(&mut self, transitions: &[Transition], now: u64) {
+        for transition in transitions {
+            let changes = self
+                .sessions
+                .entry(transition.session_id.clone())
+                .or_default();
+            if changes.len() == TIMELINE_LEN {
+                changes.pop_front();
+            }
+            changes.push_back(StatusChange {
+                at: now,
+                status: transition.status,
+            });
+        }
+    }
+
+    /// A session's changes, newest first
+    pub fn changes(&self, session_id: &str) -> impl Iterator<Item = &StatusChange> {
+        self.sessions
+            .get(session_id)
+            .into_iter()
+            .flat_map(|changes| changes.iter().rev())
+    }
+
+    /// Forget sessions `keep` rejects
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.sessions.retain(|id, _| keep(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn transition(id: &str, status: AgentStatus) -> Transition {
+        Transition {
+            session_id: id.to_string(),
+            session: id.trim_start_matches('$').to_string(),
+            previous: AgentStatus::Unknown,
+            status,
+        }
+    }
+
+    #[test]
+    fn test_record_newest_first() {
+        let mut timeline = StatusTimeline::default();
+        timeline.record(&[transition("$1", AgentStatus::Busy)], 10);
+        timeline.record(
+            &[
+                transition("$1", AgentStatus::WaitingForInput),
+                transition("$2", AgentStatus::Idle),
+            ],
+            20,
+        );
+        let statuses: Vec<(u64, AgentStatus)> =
+            timeline.changes("$1").map(|c| (c.at, c.status)).collect();
+        assert_eq!(
+            statuses,
+            [(20, AgentStatus::WaitingForInput), (10, AgentStatus::Busy)]
+        );
+        assert_eq!(timeline.changes("$3").count(), 0);
+
+        timeline.retain(|id| id != "$1");
+        assert_eq!(timeline.changes("$1").count(), 0);
+        assert_eq!(timeline.changes("$2").count(), 1);
+    }
+
+    #[test]
+    fn test_record_is_capped() {
+        let mut timeline = StatusTimeline::default();
+        for at in 0..TIMELINE_LEN as u64 + 5 {
+            timeline.record(&[transition("$1", AgentStatus::Busy)], at);
+        }
+        assert_eq!(timeline.changes("$1").count(), TIMELINE_LEN);
+        assert_eq!(timeline.changes("$1").last().unwrap().at, 5);
+    }
+}
@@ -0,0 +1,63 @@
+use serde::{Deserialize, Serialize};
+
+/// Kind of program running in a session's active pane
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize, Default)]
+pub enum AgentKind {
+    Claude,
+    Aider,
+    Codex,
+    Gemini,
+    /// A plain shell with no agent in the foreground
+    Shell,
+    /// Something else, or not yet known
+    #[default]
+    Other,
+}
+
+impl AgentKind {
+    /// Infer the agent from tmux's `pane_current_command`
+    pub fn detect(command: &str) -> Self {
+        let command = command.trim().to_lowercase();
+        if command.contains("claude") {
+            AgentKind::Claude
+        } else if command.contains("aider") {
+            AgentKind::Aider
+        } else if command.contains("codex") {
+            AgentKind::Codex
+        } else if command.contains("gemini") {
+            AgentKind::Gemini
+        } else if matches!(
+            command.as_str(),
+            "bash" | "zsh" | "fish" | "sh" | "dash" | "ksh" | "nu" | "pwsh"
+        ) {
+            AgentKind::Shell
+        } else {
+            AgentKind::Other
+        }
+    }
+
+    pub fn label(self) -> &'static str {
+        match self {
+            AgentKind::Claude => "claude",
+            AgentKind::Aider => "aider",
+            AgentKind::Codex => "codex",
+            AgentKind::Gemini => "gemini",
+            AgentKind::Shell => "shell",
+            AgentKind::Other => "-",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_detect_agent_kind() {
+        assert_eq!(AgentKind::detect("claude"), AgentKind::Claude);
+        assert_eq!(AgentKind::detect("aider"), AgentKind::Aider);
+        assert_eq!(AgentKind::detect("zsh"), AgentKind::Shell);
+        assert_eq!(AgentKind::detect("vim"), AgentKind::Other);
+        assert_eq!(AgentKind::detect(""), AgentKind::Other);
+    }
+}
@@ -0,0 +1,275 @@
+//! Aider's status and details, read from its pane.
+//!
+//! Aider asks multi-choice questions (`Commit the changes? (Y)es/(N)o
+//! [Yes]:`), waits at a `> ` or `architect> ` prompt, and announces its model
+//! and running cost, none of which the generic patterns understand. The
+//! latest non-blank line decides: a question means it needs an answer, a
+//! prompt means it's done, and a spinner or half-written edit means it's busy.
+
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+
+use super::{AgentStatus, TmuxClient, TmuxSession};
+
+/// Scrollback searched for the startup banner and the latest cost
+pub const AIDER_HISTORY_LINES: usize = 2000;
+/// Scrollback searched for a newer cost once the banner has been read
+const AIDER_RECENT_LINES: usize = 100;
+
+/// Startup banner lines, and lines only Aider prints
+static RE_SIGNATURE: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?m)^(Aider v\d|Main model: |Repo-map: |Tokens: .* sent, .* received\.)").unwrap()
+});
+
+/// `Add src/foo.py to the chat? (Y)es/(N)o/(A)ll/(S)kip all/(D)on't ask again [Yes]:`
+static RE_QUESTION: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"\(Y\)es/\(N\)o.*\[[^\]]*\]:\s*$").unwrap());
+
+/// `> `, `architect> `, `ask> `, `multi> `
+static RE_PROMPT: Lazy<Regex> = Lazy::new(|| Regex::new(r"^(?:[a-z-]+)?>\s*$").unwrap());
+
+/// The waiting spinner, and phases between sending and the next prompt
+static RE_BUSY: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"^(?:[░▒▓█]+\s+)?(?:Waiting for |Applying edits|Updating repo map|Scanning repo|Summarizing)").unwrap()
+});
+
+/// `Main model: anthropic/claude-sonnet-4 with diff edit format`, or `Model: gpt-4o with ...` before v0.40
+static RE_MODEL: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)^(?:Main model|Model): (\S+)").unwrap());
+
+/// `Tokens: 8.2k sent, 1.1k received. Cost: $0.04 message, $0.11 session.`
+static RE_COST: Lazy<Regex> = Lazy::new(|| Regex::new(r"(?m)^Tokens: .*Cost: .*$").unwrap());
+
+/// Lines Aider prints that nothing else does
+pub fn is_aider(content: &str) -> bool {
+    RE_SIGNATURE.is_match(content) || content.lines().any(|l| RE_QUESTION.is_match(l))
+}
+
+/// Status from the latest non-blank line; `None` leaves it to the generic patterns
+pub fn analyze(content: &str) -> Option<AgentStatus> {
+    let last = content.lines().rev().find(|l| !l.trim().is_empty())?;
+    let last = last.trim_start();
+    if RE_QUESTION.is_match(last) {
+        return Some(AgentStatus::WaitingForInput);
+    }
+    if RE_PROMPT.is_match(last) {
+        return Some(AgentStatus::Idle);
+    }
+    if RE_BUSY.is_match(last) {
+        return Some(AgentStatus::Busy);
+    }
+    // Still streaming a SEARCH/REPLACE block
+    let opened = content.rfind("<<<<<<< SEARCH");
+    let closed = content.rfind(">>>>>>> REPLACE");
+    if opened.is_some() && opened > closed {
+        return Some(AgentStatus::Busy);
+    }
+    None
+}
+
+/// Model and spend Aider has announced
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct AiderInfo {
+    /// Main model, e.g. `anthropic/claude-sonnet-4-20250514`
+    pub model: Option<String>,
+    /// The latest `Tokens: ... Cost: ...` line
+    pub cost: Option<String>,
+}
+
+impl AiderInfo {
+    /// Read from a capture; the latest banner and cost line win
+    pub fn parse(content: &str) -> Self {
+        let last = |re: &Regex, group: usize| {
+            re.captures_iter(content)
+                .last()
+                .map(|c| c[group].trim().to_string())
+        };
+        Self {
+            model: last(&RE_MODEL, 1),
+            cost: last(&RE_COST, 0),
+        }
+    }
+
+    /// Fill in what this capture lacks (the banner scrolled away) from an earlier one
+    fn or(self, earlier: Option<&AiderInfo>) -> Self {
+        let Some(earlier) = earlier else {
+            return self;
+        };
+        Self {
+            model: self.model.or_else(|| earlier.model.clone()),
+            cost: self.cost.or_else(|| earlier.cost.clone()),
+        }
+    }
+}
+
+/// Aider details by session, read again only after new output
+#[derive(Debug, Default)]
+pub struct AiderCache {
+    /// Session id to `session_activity` at capture and what was read
+    entries: HashMap<String, (u64, AiderInfo)>,
+}
+
+impl AiderCache {
+    pub fn get(&self, session: &TmuxSession) -> Option<&AiderInfo> {
+        self.entries
+            .get(&session.id)
+            .filter(|(at, _)| *at == session.activity_at)
+            .map(|(_, info)| info)
+    }
+
+    /// Record what a fresh capture showed, keeping details it no longer has
+    pub fn update(&mut self, session: &TmuxSession, content: &str) -> AiderInfo {
+        let earlier = self.entries.get(&session.id).map(|(_, info)| info);
+        let info = AiderInfo::parse(content).or(earlier);
+        self.entries
+            .insert(session.id.clone(), (session.activity_at, info.clone()));
+        info
+    }
+
+    /// How far back to capture: the whole history the first time, then only
+    /// what's recent since earlier details are kept
+    fn capture_lines(&self, session: &TmuxSession) -> usize {
+        if self.entries.contains_key(&session.id) {
+            AIDER_RECENT_LINES
+        } else {
+            AIDER_HISTORY_LINES
+        }
+    }
+
+    /// Details for an Aider session, capturing its pane if there's new output
+    pub async fn lookup(
+        &mut self,
+        client: &TmuxClient,
+        session: &TmuxSession,
+    ) -> Option<AiderInfo> {
+        if let Some(info) = self.get(session) {
+            return Some(info.clone());
+        }
+        let content = client
+            .capture_pane(&session.id, self.capture_lines(session))
+            .await
+            .ok()?;
+        Some(self.update(session, &content))
+    }
+
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.entries.retain(|id, _| keep(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tmux::{AgentKind, StateInferenceEngine};
+
+    fn fixture(name: &str) -> String {
+        let path = std::path::Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("tests/fixtures/aider")
+            .join(name);
+        std::fs::read_to_string(&path).unwrap_or_else(|e| panic!("{}: {}", path.display(), e))
+    }
+
+    #[test]
+    fn test_fixture_statuses() {
+        let cases = [
+            ("startup.txt", AgentStatus::Idle),
+            ("add_file.txt", AgentStatus::WaitingForInput),
+            ("commit.txt", AgentStatus::WaitingForInput),
+            ("create_file.txt", AgentStatus::WaitingForInput),
+            ("waiting.txt", AgentStatus::Busy),
+            ("streaming_edit.txt", AgentStatus::Busy),
+            ("applying.txt", AgentStatus::Busy),
+            ("done.txt", AgentStatus::Idle),
+            ("architect.txt", AgentStatus::Idle),
+        ];
+        for (name, expected) in cases {
+            let capture = fixture(name);
+            assert_eq!(
+                StateInferenceEngine::analyze_as(&capture, AgentKind::Aider),
+                expected,
+                "{}",
+                name
+            );
+            // Recognized by its banner or questions even when run as `python -m aider`
+            if is_aider(&capture) {
+                assert_eq!(
+                    StateInferenceEngine::analyze(&capture),
+                    expected,
+                    "{}",
+                    name
+                );
+            }
+        }
+        for name in [
+            "startup.txt",
+            "add_file.txt",
+            "commit.txt",
+            "create_file.txt",
+        ] {
+            assert!(is_aider(&fixture(name)), "{}", name);
+        }
+    }
+
+    #[test]
+    fn test_answered_question_is_not_waiting() {
+        let capture = "Commit the changes? (Y)es/(N)o [Yes]: y\nCommit 3f2a1bc fix: login\n> ";
+        assert_eq!(analyze(capture), Some(AgentStatus::Idle));
+        assert_eq!(
+            analyze("Commit the changes? (Y)es/(N)o [Yes]: y\nCommitting..."),
+            None
+        );
+    }
+
+    #[test]
+    fn test_not_aider() {
+        assert!(!is_aider("$ cargo build\n   Compiling agent-rusty\n$ "));
+        assert!(!is_aider("Do you want to continue? [y/n]"));
+    }
+
+    #[test]
+    fn test_info_from_fixtures() {
+        let info = AiderInfo::parse(&fixture("done.txt"));
+        assert_eq!(
+            info.model.as_deref(),
+            Some("anthropic/claude-sonnet-4-20250514")
+        );
+        assert_eq!(
+            info.cost.as_deref(),
+            Some("Tokens: 9.6k sent, 734 received. Cost: $0.04 message, $0.09 session.")
+        );
+
+        // Versions before 0.40 said `Model:`
+        let info = AiderInfo::parse("Aider v0.35.0\nModel: gpt-4o with diff edit format\n> ");
+        assert_eq!(info.model.as_deref(), Some("gpt-4o"));
+        assert_eq!(info.cost, None);
+    }
+
+    #[test]
+    fn test_cache_keeps_scrolled_away_details() {
+        let mut session = TmuxSession::new("$1".to_string(), "api".to_string());
+        session.activity_at = 100;
+        let mut cache = AiderCache::default();
+        assert_eq!(cache.capture_lines(&session), AIDER_HISTORY_LINES);
+        cache.update(&session, &fixture("startup.txt"));
+        assert!(cache.get(&session).is_some());
+        assert_eq!(cache.capture_lines(&session), AIDER_RECENT_LINES);
+
+        // New output; the banner is gone but a cost line appeared
+        session.activity_at = 200;
+        assert_eq!(cache.get(&session), None);
+        let info = cache.update(
+            &session,
+            "Tokens: 2.1k sent, 90 received. Cost: $0.01 message, $0.01 session.\n> ",
+        );
+        assert_eq!(
+            info.model.as_deref(),
+            Some("anthropic/claude-sonnet-4-20250514")
+        );
+        assert!(info.cost.unwrap().ends_with("$0.01 session."));
+
+        cache.retain(|_| false);
+        assert_eq!(cache.get(&session), None);
+    }
+}
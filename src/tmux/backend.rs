@@ -0,0 +1,149 @@
+use std::path::Path;
+
+use super::{TmuxClient, TmuxSession};
+use crate::error::Result;
+
+/// The tmux operations used by headless tools, so they can run against a fake in tests
+pub trait Sessions {
+    fn list_sessions(&self) -> impl Future<Output = Result<Vec<TmuxSession>>> + Send;
+    fn capture_pane(
+        &self,
+        session_id: &str,
+        history: usize,
+    ) -> impl Future<Output = Result<String>> + Send;
+    fn send_keys(&self, session_id: &str, text: &str) -> impl Future<Output = Result<()>> + Send;
+    fn send_text(&self, session_id: &str, text: &str) -> impl Future<Output = Result<()>> + Send;
+    fn create_session(
+        &self,
+        name: &str,
+        dir: Option<&Path>,
+        command: Option<&str>,
+    ) -> impl Future<Output = Result<TmuxSession>> + Send;
+    fn kill_session(&self, session_id: &str) -> impl Future<Output = Result<()>> + Send;
+}
+
+impl Sessions for TmuxClient {
+    async fn list_sessions(&self) -> Result<Vec<TmuxSession>> {
+        TmuxClient::list_sessions(self).await
+    }
+
+    async fn capture_pane(&self, session_id: &str, history: usize) -> Result<String> {
+        TmuxClient::capture_pane(self, session_id, history).await
+    }
+
+    async fn send_keys(&self, session_id: &str, text: &str) -> Result<()> {
+        TmuxClient::send_keys(self, session_id, text).await
+    }
+
+    async fn send_text(&self, session_id: &str, text: &str) -> Result<()> {
+        TmuxClient::send_text(self, session_id, text).await
+    }
+
+    async fn create_session(
+        &self,
+        name: &str,
+        dir: Option<&Path>,
+        command: Option<&str>,
+    ) -> Result<TmuxSession> {
+        self.create_session_with(name, dir, command).await
+    }
+
+    async fn kill_session(&self, session_id: &str) -> Result<()> {
+        TmuxClient::kill_session(self, session_id).await
+    }
+}
+
+/// In-memory [`Sessions`] for tests
+#[cfg(test)]
+pub mod fake {
+    use super::*;
+    use crate::error::AgentRustyError;
+    use crate::tmux::AgentStatus;
+    use std::sync::Mutex;
+
+    /// In-memory sessions; each `list_sessions` call pops the next queued status
+    #[derive(Default)]
+    pub struct FakeSessions {
+        pub sessions: Mutex<Vec<TmuxSession>>,
+        pub statuses: Mutex<Vec<AgentStatus>>,
+        pub sent: Mutex<Vec<(String, String)>>,
+        /// Text typed without pressing Enter
+        pub typed: Mutex<Vec<(String, String)>>,
+    }
+
+    impl FakeSessions {
+        pub fn with(names: &[&str]) -> Self {
+            let sessions = names
+                .iter()
+                .enumerate()
+                .map(|(i, name)| {
+                    let mut session = TmuxSession::new(format!("${}", i), name.to_string());
+                    session.current_command = "claude".to_string();
+                    session.current_path = format!("/work/{}", name);
+                    session.status = AgentStatus::Idle;
+                    session
+                })
+                .collect();
+            Self {
+                sessions: Mutex::new(sessions),
+                ..Default::default()
+            }
+        }
+    }
+
+    impl Sessions for FakeSessions {
+        async fn list_sessions(&self) -> Result<Vec<TmuxSession>> {
+            let mut sessions = self.sessions.lock().unwrap();
+            let mut statuses = self.statuses.lock().unwrap();
+            if !statuses.is_empty() {
+                let status = statuses.remove(0);
+                for session in sessions.iter_mut() {
+                    session.status = status;
+                }
+            }
+            Ok(sessions.clone())
+        }
+
+        async fn capture_pane(&self, session_id: &str, _history: usize) -> Result<String> {
+            Ok(format!("one\ntwo\nthree from {}\n\n\n", session_id))
+        }
+
+        async fn send_keys(&self, session_id: &str, text: &str) -> Result<()> {
+            self.sent
+                .lock()
+                .unwrap()
+                .push((session_id.to_string(), text.to_string()));
+            Ok(())
+        }
+
+        async fn send_text(&self, session_id: &str, text: &str) -> Result<()> {
+            self.typed
+                .lock()
+                .unwrap()
+                .push((session_id.to_string(), text.to_string()));
+            Ok(())
+        }
+
+        async fn create_session(
+            &self,
+            name: &str,
+            dir: Option<&Path>,
+            command: Option<&str>,
+        ) -> Result<TmuxSession> {
+            let mut sessions = self.sessions.lock().unwrap();
+            if sessions.iter().any(|s| s.name == name) {
+                return Err(AgentRustyError::DuplicateSession(name.to_string()));
+            }
+            let mut session = TmuxSession::new(format!("${}", sessions.len()), name.to_string());
+            session.current_command = command.unwrap_or("zsh").to_string();
+            session.current_path = dir.map(|d| d.display().to_string()).unwrap_or_default();
+            sessions.push(session.clone());
+            Ok(session)
+        }
+
+        async fn kill_session(&self, session_id: &str) -> Result<()> {
+            self.sessions.lock().unwrap().retain(|s| s.id != session_id);
+            Ok(())
+        }
+    }
+}
@@ -1,164 +1,997 @@
-use anyhow::{Context, Result};
-use std::process::Stdio;
+use std::collections::BTreeSet;
+use std::ffi::OsString;
+use std::path::Path;
+use std::process::{Output, Stdio};
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::io::AsyncWriteExt;
 use tokio::process::Command;
 
 use super::heuristics::{AgentStatus, StateInferenceEngine};
-use super::TmuxSession;
+use super::{
+    AgentKind, AttachedClient, CapturePlan, ServerInfo, Socket, StatusCache, TitleRule, TmuxServer,
+    TmuxSession, TmuxWindow, Wsl, is_default_title,
+};
+use crate::error::{AgentRustyError, Result};
 
 /// Client for interacting with tmux via CLI
+#[derive(Clone)]
 pub struct TmuxClient {
     /// Path to tmux binary
     tmux_path: String,
+    /// Servers to watch; the default one when empty. With several, session
+    /// ids carry their server's label and commands go to the server a target names.
+    sockets: Arc<[Socket]>,
+    /// How long a single tmux invocation may take
+    timeout: Duration,
+    /// Run tmux inside WSL through `wsl.exe`
+    wsl: Option<Wsl>,
+    /// Pane titles that give a session's status without capturing its pane
+    title_rules: Arc<[TitleRule]>,
 }
 
+/// Default for [`TmuxClient::with_timeout`]
+pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(10);
+
 impl TmuxClient {
     pub fn new() -> Self {
+        Self::with_options("tmux", None)
+    }
+
+    pub fn with_options(tmux_path: &str, socket_name: Option<&str>) -> Self {
         Self {
-            tmux_path: "tmux".to_string(),
+            tmux_path: tmux_path.to_string(),
+            sockets: socket_name
+                .map(|name| Socket::Name(name.to_string()))
+                .into_iter()
+                .collect(),
+            timeout: DEFAULT_TIMEOUT,
+            wsl: None,
+            title_rules: Arc::new([]),
         }
     }
 
-    /// Check if tmux server is running
-    pub async fn is_server_running(&self) -> bool {
-        Command::new(&self.tmux_path)
-            .arg("list-sessions")
-            .stdout(Stdio::null())
-            .stderr(Stdio::null())
-            .status()
+    /// Give up on (and kill) tmux invocations that take longer than `timeout`
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Take statuses from pane titles matching `rules` instead of capturing those panes
+    pub fn with_title_rules(mut self, rules: Vec<TitleRule>) -> Self {
+        self.title_rules = rules.into();
+        self
+    }
+
+    /// Watch these servers instead; the first is where new sessions go
+    pub fn with_sockets(mut self, sockets: Vec<Socket>) -> Self {
+        self.sockets = sockets.into();
+        self
+    }
+
+    /// Whether several servers are watched, so session ids carry their server's label
+    fn is_multi(&self) -> bool {
+        self.sockets.len() > 1
+    }
+
+    /// Every watched server; `None` stands for the default one
+    fn servers(&self) -> Vec<Option<&Socket>> {
+        if self.sockets.is_empty() {
+            vec![None]
+        } else {
+            self.sockets.iter().map(Some).collect()
+        }
+    }
+
+    /// `id` as `socket`'s server reports it, prefixed with the server's label
+    /// when several are watched
+    fn labeled(&self, socket: Option<&Socket>, id: &str) -> String {
+        match socket {
+            Some(socket) if self.is_multi() => format!("{}/{}", socket.label(), id),
+            _ => id.to_string(),
+        }
+    }
+
+    /// Watched server labeled `label`; the first for an empty or unknown label
+    fn labeled_socket(&self, label: &str) -> Option<&Socket> {
+        self.sockets
+            .iter()
+            .find(|s| s.label() == label)
+            .or(self.sockets.first())
+    }
+
+    /// Server `target` is on, and the target as that server knows it
+    fn route<'a>(&self, target: &'a str) -> (Option<&Socket>, &'a str) {
+        if self.is_multi()
+            && let Some((label, rest)) = target.split_once('/')
+            && let Some(socket) = self.sockets.iter().find(|s| s.label() == label)
+        {
+            return (Some(socket), rest);
+        }
+        (self.sockets.first(), target)
+    }
+
+    /// Run tmux inside a WSL distribution, translating paths on the way
+    pub fn with_wsl(mut self, wsl: Wsl) -> Self {
+        self.wsl = Some(wsl);
+        self
+    }
+
+    /// Program and leading arguments of every tmux invocation on `socket`'s server
+    fn base_argv_on(&self, socket: Option<&Socket>) -> Vec<String> {
+        let mut argv = match &self.wsl {
+            Some(wsl) => wsl.argv(&self.tmux_path),
+            None => vec![self.tmux_path.clone()],
+        };
+        argv.extend(socket.into_iter().flat_map(Socket::args));
+        argv
+    }
+
+    fn base_argv(&self) -> Vec<String> {
+        self.base_argv_on(self.sockets.first())
+    }
+
+    fn command_on(&self, socket: Option<&Socket>) -> Command {
+        let argv = self.base_argv_on(socket);
+        let mut command = Command::new(&argv[0]);
+        command.args(&argv[1..]).kill_on_drop(true);
+        command
+    }
+
+    /// A command for the first (or only) server
+    fn command(&self) -> Command {
+        self.command_on(self.sockets.first())
+    }
+
+    /// Run a prepared tmux command to completion, within the timeout
+    async fn output(&self, command: &mut Command) -> Result<Output> {
+        tokio::time::timeout(self.timeout, command.output())
             .await
-            .map(|s| s.success())
-            .unwrap_or(false)
+            .map_err(|_| self.timed_out(command))?
+            .map_err(|e| {
+                let program = command.as_std().get_program().to_string_lossy();
+                AgentRustyError::spawn(&program, e)
+            })
+    }
+
+    fn timed_out(&self, command: &Command) -> AgentRustyError {
+        let subcommand = command
+            .as_std()
+            .get_args()
+            .nth(self.base_argv().len() - 1)
+            .map(|arg| arg.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        AgentRustyError::TmuxTimedOut {
+            command: subcommand,
+            after: self.timeout,
+        }
+    }
+
+    /// A local path as tmux sees it, which differs inside WSL
+    fn tmux_side_path(&self, path: &Path) -> OsString {
+        match &self.wsl {
+            Some(_) => Wsl::to_wsl_path(&path.to_string_lossy()).into(),
+            None => path.into(),
+        }
+    }
+
+    /// A path reported by tmux (e.g. a pane's directory) as seen from here
+    pub fn local_path(&self, path: &str) -> String {
+        match &self.wsl {
+            Some(wsl) => wsl.to_windows_path(path),
+            None => path.to_string(),
+        }
+    }
+
+    /// Names of all sessions, without capturing panes (e.g. for shell completion)
+    pub async fn session_names(&self) -> Result<Vec<String>> {
+        let mut names = Vec::new();
+        for socket in self.servers() {
+            let output = self
+                .output(
+                    self.command_on(socket)
+                        .args(["list-sessions", "-F", "#{session_name}"]),
+                )
+                .await?;
+            if !output.status.success() {
+                if no_server(&output.stderr) {
+                    continue;
+                }
+                return Err(AgentRustyError::tmux("list-sessions", &output.stderr));
+            }
+            names.extend(
+                String::from_utf8_lossy(&output.stdout)
+                    .lines()
+                    .filter(|name| !name.is_empty())
+                    .map(str::to_string),
+            );
+        }
+        Ok(names)
+    }
+
+    /// The tmux version string, e.g. `tmux 3.4`
+    ///
+    /// `-V` doesn't talk to a server, so the first socket stands for all of them.
+    pub async fn version(&self) -> Result<String> {
+        let output = self.output(self.command().arg("-V")).await?;
+        if !output.status.success() {
+            return Err(AgentRustyError::tmux("-V", &output.stderr));
+        }
+        Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+    }
+
+    /// The tmux version, and for each watched server its socket and how many
+    /// sessions and windows run on it
+    pub async fn server_info(&self) -> Result<ServerInfo> {
+        let version = self.version().await?;
+        let mut servers = Vec::new();
+        for socket in self.servers() {
+            let output = self
+                .output(self.command_on(socket).args([
+                    "list-windows",
+                    "-a",
+                    "-F",
+                    "#{socket_path}\t#{session_id}",
+                ]))
+                .await?;
+            if !output.status.success() {
+                if no_server(&output.stderr) {
+                    servers.push(TmuxServer {
+                        socket_path: socket_in_error(&output.stderr),
+                        running: false,
+                        sessions: 0,
+                        windows: 0,
+                    });
+                    continue;
+                }
+                return Err(AgentRustyError::tmux("list-windows", &output.stderr));
+            }
+            let mut server = TmuxServer {
+                socket_path: String::new(),
+                running: true,
+                sessions: 0,
+                windows: 0,
+            };
+            let mut sessions = BTreeSet::new();
+            for (path, session) in String::from_utf8_lossy(&output.stdout)
+                .lines()
+                .filter_map(|line| line.split_once('\t'))
+            {
+                server.socket_path = path.to_string();
+                sessions.insert(session.to_string());
+                server.windows += 1;
+            }
+            server.sessions = sessions.len();
+            servers.push(server);
+        }
+        Ok(ServerInfo { version, servers })
+    }
+
+    /// Check if any watched tmux server is running
+    #[allow(dead_code)]
+    pub async fn is_server_running(&self) -> bool {
+        for socket in self.servers() {
+            let running = self
+                .command_on(socket)
+                .arg("list-sessions")
+                .stdout(Stdio::null())
+                .stderr(Stdio::null())
+                .status()
+                .await
+                .is_ok_and(|s| s.success());
+            if running {
+                return true;
+            }
+        }
+        false
     }
 
     /// List all tmux sessions
     pub async fn list_sessions(&self) -> Result<Vec<TmuxSession>> {
-        // Format: session_id|session_name|session_created|session_attached
-        let output = Command::new(&self.tmux_path)
+        self.list_sessions_cached(None, &CapturePlan::default())
+            .await
+    }
+
+    /// List sessions, reusing statuses from `cache` for sessions with no new activity
+    ///
+    /// Panes in `plan.force` (e.g. the selected session) are captured regardless,
+    /// and those in `plan.skip` keep their cached status while it lasts.
+    #[tracing::instrument(level = "debug", skip(self, cache, plan))]
+    pub async fn list_sessions_cached(
+        &self,
+        mut cache: Option<&mut StatusCache>,
+        plan: &CapturePlan,
+    ) -> Result<Vec<TmuxSession>> {
+        let mut sessions = Vec::new();
+        for socket in self.servers() {
+            let mut listed = self.list_server(socket).await?;
+            if self.is_multi()
+                && let Some(socket) = socket
+            {
+                for session in &mut listed {
+                    session.id = self.labeled(Some(socket), &session.id);
+                    session.socket = socket.label();
+                }
+            }
+            sessions.extend(listed);
+        }
+
+        let (mut captured, mut titled) = (0, 0);
+        for session in &mut sessions {
+            // A title the rules agree on needs no capture at all
+            if let Some(status) =
+                StateInferenceEngine::analyze_title(&session.pane_title, &self.title_rules)
+            {
+                titled += 1;
+                session.status = status;
+                if let Some(cache) = cache.as_deref_mut() {
+                    let now = crate::text::unix_now();
+                    cache.insert_uncaptured(&session.id, session.activity_at, now, status);
+                }
+                continue;
+            }
+            let cached = match &cache {
+                Some(cache) if plan.skip.contains(&session.id) => cache.last(&session.id),
+                Some(cache) if !plan.force.contains(&session.id) => {
+                    cache.get(&session.id, session.activity_at)
+                }
+                _ => None,
+            };
+            session.status = match cached {
+                Some(status) => status,
+                None => {
+                    captured += 1;
+                    let now = crate::text::unix_now();
+                    let (status, content) = self
+                        .get_session_status(&session.id, session.agent_kind())
+                        .await
+                        .unwrap_or((AgentStatus::Unknown, String::new()));
+                    if let Some(cache) = cache.as_deref_mut() {
+                        cache.insert(&session.id, session.activity_at, now, status, &content);
+                    }
+                    status
+                }
+            };
+        }
+        if let Some(cache) = cache {
+            cache.retain(|id| sessions.iter().any(|s| s.id == id));
+        }
+        tracing::debug!(
+            sessions = sessions.len(),
+            captured,
+            titled,
+            "Listed sessions"
+        );
+
+        Ok(sessions)
+    }
+
+    /// Sessions of one server as it names them, statuses left unknown
+    async fn list_server(&self, socket: Option<&Socket>) -> Result<Vec<TmuxSession>> {
+        // Format: session_id|session_name|session_created|session_attached|session_activity|pane_current_command|pane_current_path<TAB>session_windows<TAB>session_path<TAB>pane_start_command<TAB>host<TAB>pane_title
+        let output = self.output(self.command_on(socket)
             .args([
                 "list-sessions",
                 "-F",
-                "#{session_id}|#{session_name}|#{session_created}|#{session_attached}",
-            ])
-            .output()
-            .await
-            .context("Failed to execute tmux list-sessions")?;
+                "#{session_id}|#{session_name}|#{session_created}|#{session_attached}|#{session_activity}|#{pane_current_command}|#{pane_current_path}\t#{session_windows}\t#{session_path}\t#{pane_start_command}\t#{host}\t#{pane_title}",
+            ])).await?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            if stderr.contains("no server running") || stderr.contains("no sessions") {
+            if no_server(&output.stderr) {
                 return Ok(Vec::new());
             }
-            anyhow::bail!("tmux list-sessions failed: {}", stderr);
+            return Err(AgentRustyError::tmux("list-sessions", &output.stderr));
         }
 
         let stdout = String::from_utf8_lossy(&output.stdout);
-        let mut sessions = Vec::new();
+        Ok(stdout.lines().filter_map(parse_session_line).collect())
+    }
 
-        for line in stdout.lines() {
-            if let Some(session) = self.parse_session_line(line).await {
-                sessions.push(session);
-            }
+    /// Get the status of a session by analyzing pane content, along with the content
+    #[tracing::instrument(level = "debug", skip(self))]
+    async fn get_session_status(
+        &self,
+        session_id: &str,
+        kind: AgentKind,
+    ) -> Result<(AgentStatus, String)> {
+        let (socket, target) = self.route(session_id);
+        let output = self
+            .output(
+                self.command_on(socket)
+                    .args(["capture-pane", "-p", "-t", target]),
+            )
+            .await?;
+
+        if !output.status.success() {
+            return Ok((AgentStatus::Unknown, String::new()));
         }
 
-        Ok(sessions)
+        let content = String::from_utf8_lossy(&output.stdout).into_owned();
+        Ok((StateInferenceEngine::analyze_as(&content, kind), content))
+    }
+
+    /// Capture a session's active pane including `history` lines of scrollback
+    pub async fn capture_pane(&self, session_id: &str, history: usize) -> Result<String> {
+        self.capture_pane_with(session_id, history, false).await
     }
 
-    async fn parse_session_line(&self, line: &str) -> Option<TmuxSession> {
-        let parts: Vec<&str> = line.split('|').collect();
-        if parts.len() < 4 {
-            return None;
+    /// Capture pane content, optionally keeping color and attribute escapes
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn capture_pane_with(
+        &self,
+        session_id: &str,
+        history: usize,
+        escapes: bool,
+    ) -> Result<String> {
+        let (socket, target) = self.route(session_id);
+        let start = format!("-{}", history);
+        let mut args = vec!["capture-pane", "-p", "-J", "-S", &start, "-t", target];
+        if escapes {
+            args.push("-e");
         }
+        let output = self.output(self.command_on(socket).args(args)).await?;
 
-        let id = parts[0].to_string();
-        let name = parts[1].to_string();
-        let created_at = parts[2].parse().unwrap_or(0);
-        let attached_clients = parts[3].parse().unwrap_or(0);
+        if !output.status.success() {
+            return Err(AgentRustyError::tmux("capture-pane", &output.stderr));
+        }
 
-        // Get pane content for status detection
-        let status = self.get_session_status(&id).await.unwrap_or(AgentStatus::Unknown);
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
 
-        Some(TmuxSession {
-            id,
-            name,
-            created_at,
-            attached_clients,
-            status,
-        })
+    /// Store `text` in the paste buffer of every watched server, so it can be
+    /// pasted in any session; fails only if no server took it
+    #[tracing::instrument(level = "debug", skip_all)]
+    pub async fn set_buffer(&self, text: &str) -> Result<()> {
+        let mut result = Ok(());
+        let mut loaded = false;
+        for socket in self.servers() {
+            match self.load_buffer_on(socket, text).await {
+                Ok(()) => loaded = true,
+                Err(e) => result = Err(e),
+            }
+        }
+        if loaded { Ok(()) } else { result }
     }
 
-    /// Get the status of a session by analyzing pane content
-    async fn get_session_status(&self, session_id: &str) -> Result<AgentStatus> {
-        let output = Command::new(&self.tmux_path)
-            .args(["capture-pane", "-p", "-t", session_id])
-            .output()
+    async fn load_buffer_on(&self, socket: Option<&Socket>, text: &str) -> Result<()> {
+        let mut command = self.command_on(socket);
+        command
+            .args(["load-buffer", "-"])
+            .stdin(Stdio::piped())
+            .stderr(Stdio::piped());
+        let mut child = command
+            .spawn()
+            .map_err(|e| AgentRustyError::spawn(&self.tmux_path, e))?;
+
+        let load = async {
+            if let Some(mut stdin) = child.stdin.take() {
+                stdin.write_all(text.as_bytes()).await?;
+            }
+            child.wait_with_output().await
+        };
+        let output = tokio::time::timeout(self.timeout, load)
             .await
-            .context("Failed to capture pane")?;
+            .map_err(|_| self.timed_out(&command))??;
 
         if !output.status.success() {
-            return Ok(AgentStatus::Unknown);
+            return Err(AgentRustyError::tmux("load-buffer", &output.stderr));
         }
 
-        let content = String::from_utf8_lossy(&output.stdout);
-        Ok(StateInferenceEngine::analyze(&content))
+        Ok(())
+    }
+
+    /// Type `text` literally into a session's active pane, then press Enter
+    #[tracing::instrument(level = "debug", skip(self, text))]
+    pub async fn send_keys(&self, session_id: &str, text: &str) -> Result<()> {
+        self.send_text(session_id, text).await?;
+        self.send_key_names(session_id, &["Enter"]).await
+    }
+
+    /// Type `text` literally into a session's active pane, without pressing Enter
+    #[tracing::instrument(level = "debug", skip(self, text))]
+    pub async fn send_text(&self, session_id: &str, text: &str) -> Result<()> {
+        let (socket, target) = self.route(session_id);
+        let output = self
+            .output(
+                self.command_on(socket)
+                    // `--` so text like `-h` isn't read as a flag
+                    .args(["send-keys", "-t", target, "-l", "--", text]),
+            )
+            .await?;
+
+        if !output.status.success() {
+            return Err(AgentRustyError::tmux("send-keys", &output.stderr));
+        }
+        Ok(())
+    }
+
+    /// Send named keys (e.g. `Enter`, `C-c`) to a session's active pane
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn send_key_names(&self, session_id: &str, keys: &[&str]) -> Result<()> {
+        let (socket, target) = self.route(session_id);
+        let output = self
+            .output(
+                self.command_on(socket)
+                    .args(["send-keys", "-t", target])
+                    .args(keys),
+            )
+            .await?;
+
+        if !output.status.success() {
+            return Err(AgentRustyError::tmux("send-keys", &output.stderr));
+        }
+
+        Ok(())
     }
 
     /// Create a new session with isolated history
     pub async fn create_session(&self, name: &str) -> Result<TmuxSession> {
-        let history_dir = dirs::home_dir()
-            .unwrap_or_default()
-            .join(".agent-deck")
-            .join("history");
+        self.create_session_with(name, None, None).await
+    }
+
+    /// Create a new session starting in `dir` and running `command` instead of a shell
+    pub async fn create_session_with(
+        &self,
+        name: &str,
+        dir: Option<&Path>,
+        command: Option<&str>,
+    ) -> Result<TmuxSession> {
+        self.create_session_on(self.sockets.first(), name, dir, command)
+            .await
+    }
+
+    /// Like [`create_session_with`](Self::create_session_with), on the server
+    /// session `near` is on rather than the first
+    pub async fn create_session_beside(
+        &self,
+        near: &str,
+        name: &str,
+        dir: Option<&Path>,
+        command: Option<&str>,
+    ) -> Result<TmuxSession> {
+        let (socket, _) = self.route(near);
+        self.create_session_on(socket, name, dir, command).await
+    }
+
+    /// Like [`create_session_with`](Self::create_session_with), on the server
+    /// labeled `server` (see [`TmuxSession::socket`]); the first if there is none
+    pub async fn create_session_in(
+        &self,
+        server: &str,
+        name: &str,
+        dir: Option<&Path>,
+        command: Option<&str>,
+    ) -> Result<TmuxSession> {
+        let socket = self.labeled_socket(server);
+        self.create_session_on(socket, name, dir, command).await
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
+    async fn create_session_on(
+        &self,
+        socket: Option<&Socket>,
+        name: &str,
+        dir: Option<&Path>,
+        command: Option<&str>,
+    ) -> Result<TmuxSession> {
+        let history_dir = super::history_dir();
 
         // Ensure history directory exists
         tokio::fs::create_dir_all(&history_dir).await?;
 
         let history_file = history_dir.join(format!("{}.hist", name));
 
-        let output = Command::new(&self.tmux_path)
-            .args(["new-session", "-d", "-s", name])
-            .env("HISTFILE", &history_file)
-            .output()
-            .await
-            .context("Failed to create tmux session")?;
+        let mut new_session = self.command_on(socket);
+        new_session.args(["new-session", "-d", "-s", name]);
+        if let Some(dir) = dir {
+            new_session.arg("-c").arg(self.tmux_side_path(dir));
+        }
+        if let Some(command) = command {
+            new_session.arg(command);
+        }
+        new_session.env("HISTFILE", self.tmux_side_path(&history_file));
+        if self.wsl.is_some() {
+            // wsl.exe only passes on variables listed in WSLENV
+            let existing = std::env::var("WSLENV").ok();
+            new_session.env("WSLENV", Wsl::share_env(existing.as_deref(), "HISTFILE"));
+        }
+        let output = self.output(&mut new_session).await?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Failed to create session: {}", stderr);
+            if String::from_utf8_lossy(&output.stderr).contains("duplicate session") {
+                return Err(AgentRustyError::DuplicateSession(name.to_string()));
+            }
+            return Err(AgentRustyError::tmux("new-session", &output.stderr));
         }
 
-        // Get the session info
+        // Get the session info; another server may have one of the same name
+        let server = match socket {
+            Some(socket) if self.is_multi() => socket.label(),
+            _ => String::new(),
+        };
         let sessions = self.list_sessions().await?;
         sessions
             .into_iter()
-            .find(|s| s.name == name)
-            .ok_or_else(|| anyhow::anyhow!("Session created but not found"))
+            .find(|s| s.name == name && s.socket == server)
+            .ok_or_else(|| AgentRustyError::SessionNotFound(name.to_string()))
+    }
+
+    /// Run a tmux command on `socket`'s server and return its stdout
+    async fn run_on(&self, socket: Option<&Socket>, args: &[&str]) -> Result<String> {
+        let output = self.output(self.command_on(socket).args(args)).await?;
+
+        if !output.status.success() {
+            return Err(AgentRustyError::tmux(args[0], &output.stderr));
+        }
+
+        Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+    }
+
+    /// One line per pane across all sessions, formatted with `format`, for
+    /// each running server along with its label (empty unless several are watched)
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn list_all_panes(&self, format: &str) -> Result<Vec<(String, String)>> {
+        let mut servers = Vec::new();
+        for socket in self.servers() {
+            let output = self
+                .output(
+                    self.command_on(socket)
+                        .args(["list-panes", "-a", "-F", format]),
+                )
+                .await?;
+            if !output.status.success() {
+                if no_server(&output.stderr) {
+                    continue;
+                }
+                return Err(AgentRustyError::tmux("list-panes", &output.stderr));
+            }
+            let label = match socket {
+                Some(socket) if self.is_multi() => socket.label(),
+                _ => String::new(),
+            };
+            servers.push((label, String::from_utf8_lossy(&output.stdout).into_owned()));
+        }
+        Ok(servers)
+    }
+
+    /// Add a window to `session` starting in `dir`; returns the new pane's ID
+    pub async fn new_window(&self, session: &str, name: &str, dir: &str) -> Result<String> {
+        self.new_window_with(session, name, dir, &[]).await
+    }
+
+    /// Add a window to `session` running `command` (argv; the default shell
+    /// when empty), starting in `dir`; returns the new pane's ID
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn new_window_with(
+        &self,
+        session: &str,
+        name: &str,
+        dir: &str,
+        command: &[&str],
+    ) -> Result<String> {
+        let (socket, session) = self.route(session);
+        let target = format!("{}:", session);
+        let mut args = vec![
+            "new-window",
+            "-d",
+            "-P",
+            "-F",
+            "#{pane_id}",
+            "-t",
+            &target,
+            "-n",
+            name,
+            "-c",
+            dir,
+        ];
+        args.extend(command);
+        let pane = self.run_on(socket, &args).await?;
+        Ok(self.labeled(socket, pane.trim()))
+    }
+
+    /// Split the window containing `target`; returns the new pane's ID
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn split_window(&self, target: &str, dir: &str) -> Result<String> {
+        let (socket, target) = self.route(target);
+        let args = [
+            "split-window",
+            "-d",
+            "-P",
+            "-F",
+            "#{pane_id}",
+            "-t",
+            target,
+            "-c",
+            dir,
+        ];
+        let pane = self.run_on(socket, &args).await?;
+        Ok(self.labeled(socket, pane.trim()))
+    }
+
+    /// Apply a `window_layout` string to the window containing `target`
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn select_layout(&self, target: &str, layout: &str) -> Result<()> {
+        let (socket, target) = self.route(target);
+        self.run_on(socket, &["select-layout", "-t", target, layout])
+            .await
+            .map(drop)
+    }
+
+    /// Windows of a session, in index order
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn list_windows(&self, session_id: &str) -> Result<Vec<TmuxWindow>> {
+        let (socket, target) = self.route(session_id);
+        let output = self
+            .run_on(
+                socket,
+                &[
+                    "list-windows",
+                    "-t",
+                    target,
+                    "-F",
+                    "#{window_index}\t#{window_active}\t#{window_panes}\t#{pane_current_command}\t#{window_name}",
+                ],
+            )
+            .await?;
+        Ok(output.lines().filter_map(parse_window_line).collect())
+    }
+
+    /// Directory a session's active pane is in now
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn get_session_cwd(&self, session_id: &str) -> Result<String> {
+        let (socket, target) = self.route(session_id);
+        let output = self
+            .run_on(
+                socket,
+                &[
+                    "display-message",
+                    "-p",
+                    "-t",
+                    target,
+                    "#{pane_current_path}",
+                ],
+            )
+            .await?;
+        Ok(output.trim_end_matches('\n').to_string())
+    }
+
+    /// Terminals attached to a session; clients of other sessions aren't listed
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn list_clients(&self, session_id: &str) -> Result<Vec<AttachedClient>> {
+        let (socket, target) = self.route(session_id);
+        let output = self
+            .run_on(
+                socket,
+                &[
+                    "list-clients",
+                    "-t",
+                    target,
+                    "-F",
+                    "#{client_name}|#{client_width}x#{client_height}|#{client_termname}",
+                ],
+            )
+            .await?;
+        Ok(output.lines().filter_map(parse_client_line).collect())
+    }
+
+    /// Detach a client (named as in [`list_clients`](Self::list_clients)) on
+    /// the server of `session_id`
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn detach_client(&self, session_id: &str, client: &str) -> Result<()> {
+        let (socket, _) = self.route(session_id);
+        self.run_on(socket, &["detach-client", "-t", client])
+            .await
+            .map(drop)
+    }
+
+    /// Make `target` (see [`window_target`]) the window its session shows
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn select_window(&self, target: &str) -> Result<()> {
+        let (socket, target) = self.route(target);
+        self.run_on(socket, &["select-window", "-t", target])
+            .await
+            .map(drop)
+    }
+
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn rename_window(&self, target: &str, name: &str) -> Result<()> {
+        let (socket, target) = self.route(target);
+        self.run_on(socket, &["rename-window", "-t", target, name])
+            .await
+            .map(drop)
+    }
+
+    /// Rename a session; a name already taken fails with [`AgentRustyError::DuplicateSession`]
+    #[tracing::instrument(level = "debug", skip(self))]
+    pub async fn rename_session(&self, session_id: &str, new_name: &str) -> Result<()> {
+        let (socket, target) = self.route(session_id);
+        let output = self
+            .output(
+                self.command_on(socket)
+                    .args(["rename-session", "-t", target, new_name]),
+            )
+            .await?;
+
+        if !output.status.success() {
+            if String::from_utf8_lossy(&output.stderr).contains("duplicate session") {
+                return Err(AgentRustyError::DuplicateSession(new_name.to_string()));
+            }
+            return Err(AgentRustyError::tmux("rename-session", &output.stderr));
+        }
+
+        Ok(())
     }
 
     /// Kill a session
+    #[tracing::instrument(level = "debug", skip(self))]
     pub async fn kill_session(&self, session_id: &str) -> Result<()> {
-        let output = Command::new(&self.tmux_path)
-            .args(["kill-session", "-t", session_id])
-            .output()
-            .await
-            .context("Failed to kill tmux session")?;
+        let (socket, target) = self.route(session_id);
+        let output = self
+            .output(self.command_on(socket).args(["kill-session", "-t", target]))
+            .await?;
 
         if !output.status.success() {
-            let stderr = String::from_utf8_lossy(&output.stderr);
-            anyhow::bail!("Failed to kill session: {}", stderr);
+            return Err(AgentRustyError::tmux("kill-session", &output.stderr));
         }
 
         Ok(())
     }
 
-    /// Get the command to attach to a session (for external execution)
-    pub fn attach_command(&self, session_id: &str) -> Vec<String> {
-        vec![
-            self.tmux_path.clone(),
-            "attach-session".to_string(),
-            "-t".to_string(),
-            session_id.to_string(),
-        ]
+    /// Get the command to attach to a session (for external execution);
+    /// `read_only` attaches with `-r`, so keys typed there don't reach the panes
+    ///
+    /// ```
+    /// use agent_rusty::tmux::{TmuxClient, Wsl};
+    ///
+    /// let client = TmuxClient::with_options("tmux", Some("agents"));
+    /// assert_eq!(
+    ///     client.attach_command("$3", false).join(" "),
+    ///     "tmux -L agents attach-session -t $3"
+    /// );
+    /// assert_eq!(
+    ///     client.attach_command("$3", true).join(" "),
+    ///     "tmux -L agents attach-session -r -t $3"
+    /// );
+    ///
+    /// let client = client.with_wsl(Wsl::new(Some("Ubuntu".to_string())));
+    /// assert_eq!(
+    ///     client.attach_command("$3", false).join(" "),
+    ///     "wsl.exe -d Ubuntu -- tmux -L agents attach-session -t $3"
+    /// );
+    /// ```
+    pub fn attach_command(&self, session_id: &str, read_only: bool) -> Vec<String> {
+        let (socket, target) = self.route(session_id);
+        let mut cmd = self.base_argv_on(socket);
+        cmd.push("attach-session".to_string());
+        if read_only {
+            cmd.push("-r".to_string());
+        }
+        cmd.extend(["-t".to_string(), target.to_string()]);
+        cmd
+    }
+}
+
+/// Target naming window `index` of a session, for `select-window`
+///
+/// ```
+/// use agent_rusty::tmux::window_target;
+///
+/// assert_eq!(window_target("$3", 2), "$3:2");
+/// ```
+pub fn window_target(session_id: &str, index: u32) -> String {
+    format!("{}:{}", session_id, index)
+}
+
+/// Parse one `list-windows` line; the name comes last so a tab inside it survives
+fn parse_window_line(line: &str) -> Option<TmuxWindow> {
+    let mut fields = line.splitn(5, '\t');
+    Some(TmuxWindow {
+        index: fields.next()?.parse().ok()?,
+        active: fields.next()? == "1",
+        panes: fields.next()?.parse().unwrap_or(1),
+        command: fields.next()?.to_string(),
+        name: fields.next().unwrap_or_default().to_string(),
+    })
+}
+
+/// Parse one `list-clients` line, `name|WxH|termname`
+fn parse_client_line(line: &str) -> Option<AttachedClient> {
+    let mut fields = line.splitn(3, '|');
+    let name = fields.next()?;
+    let (width, height) = fields.next()?.split_once('x')?;
+    Some(AttachedClient {
+        name: name.to_string(),
+        width: width.parse().ok()?,
+        height: height.parse().ok()?,
+        termname: fields.next()?.to_string(),
+    })
+}
+
+/// Whether `list-sessions` failed only because there is nothing to list
+///
+/// A socket that's missing, or left behind by a server that died, means no
+/// server; one we can't connect to for another reason (say it belongs to
+/// another user) is an error.
+fn no_server(stderr: &[u8]) -> bool {
+    let stderr = String::from_utf8_lossy(stderr);
+    stderr.contains("no server running")
+        || stderr.contains("no sessions")
+        || (stderr.contains("error connecting")
+            && (stderr.contains("No such file or directory")
+                || stderr.contains("Connection refused")))
+}
+
+/// The socket a "no server running on <path>" or "error connecting to <path>
+/// (<reason>)" error names; empty for other errors
+fn socket_in_error(stderr: &[u8]) -> String {
+    let stderr = String::from_utf8_lossy(stderr);
+    let line = stderr.lines().next().unwrap_or_default();
+    if let Some((_, path)) = line.split_once("no server running on ") {
+        return path.trim().to_string();
+    }
+    if let Some((_, rest)) = line.split_once("error connecting to ") {
+        return rest.split(" (").next().unwrap_or_default().to_string();
+    }
+    String::new()
+}
+
+/// Parse one `list-sessions` line; the status is left for the caller to fill in
+fn parse_session_line(line: &str) -> Option<TmuxSession> {
+    // Host and title follow tabs, so a '|' in the title can't shift the other fields
+    let (line, pane_title) = match line.rsplit_once('\t') {
+        Some((rest, title)) => match rest.rsplit_once('\t') {
+            Some((line, host)) if !is_default_title(title, host) => (line, title.to_string()),
+            Some((line, _)) => (line, String::new()),
+            None => (rest, String::new()),
+        },
+        None => (line, String::new()),
+    };
+    let mut fields = line.splitn(4, '\t');
+    let line = fields.next().unwrap_or_default();
+    let windows = fields.next().and_then(|w| w.parse().ok()).unwrap_or(0);
+    let start_path = fields.next().unwrap_or_default().to_string();
+    // tmux quotes the start command when it has arguments
+    let start_command = fields.next().unwrap_or_default();
+    let start_command = start_command
+        .strip_prefix('"')
+        .and_then(|c| c.strip_suffix('"'))
+        .unwrap_or(start_command)
+        .to_string();
+    let parts: Vec<&str> = line.split('|').collect();
+    if parts.len() < 4 {
+        return None;
     }
+
+    let id = parts[0].to_string();
+    let name = parts[1].to_string();
+    let created_at = parts[2].parse().unwrap_or(0);
+    let attached_clients = parts[3].parse().unwrap_or(0);
+    let activity_at = parts.get(4).and_then(|s| s.parse().ok()).unwrap_or(0);
+    let current_command = parts.get(5).unwrap_or(&"").to_string();
+    // The path comes last so a '|' inside it survives
+    let current_path = parts.get(6..).map(|p| p.join("|")).unwrap_or_default();
+
+    Some(TmuxSession {
+        id,
+        name,
+        created_at,
+        attached_clients,
+        activity_at,
+        current_command,
+        current_path,
+        pane_title,
+        windows,
+        start_command,
+        start_path,
+        socket: String::new(),
+        git: None,
+        claude: None,
+        aider: None,
+        activity_trend: Vec::new(),
+        tags: Vec::new(),
+        window_tree: None,
+        status: AgentStatus::Unknown,
+    })
 }
 
 impl Default for TmuxClient {
@@ -166,3 +999,68 @@ impl Default for TmuxClient {
         Self::new()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_no_server() {
+        assert!(no_server(b"no server running on /tmp/tmux-1000/default\n"));
+        assert!(no_server(
+            b"error connecting to /tmp/tmux-1000/gone (No such file or directory)\n"
+        ));
+        assert!(no_server(
+            b"error connecting to /tmp/tmux-1000/dead (Connection refused)\n"
+        ));
+        assert!(!no_server(
+            b"error connecting to /tmp/tmux-0/default (Permission denied)\n"
+        ));
+        assert!(!no_server(b"can't find session: api\n"));
+    }
+
+    #[test]
+    fn test_parse_window_line() {
+        let window = parse_window_line("1\t1\t2\ttail\tlogs").unwrap();
+        assert_eq!((window.index, window.active, window.panes), (1, true, 2));
+        assert_eq!(
+            (window.command.as_str(), window.name.as_str()),
+            ("tail", "logs")
+        );
+        // The name comes last, so tabs and pipes in it are kept
+        let window = parse_window_line("3\t0\t1\tnode\tclaude\t| api").unwrap();
+        assert_eq!((window.index, window.active), (3, false));
+        assert_eq!(window.name, "claude\t| api");
+        assert_eq!(parse_window_line("2\t0\t1\tzsh\t").unwrap().name, "");
+        assert_eq!(parse_window_line("2\t0\t1\tzsh").unwrap().name, "");
+        // tmux always reports the pane count, but one is a safe guess
+        assert_eq!(parse_window_line("0\t0\t\tzsh\tx").unwrap().panes, 1);
+
+        for malformed in ["", "0", "0\t1\t1", "first\t1\t1\tzsh\tx"] {
+            assert_eq!(parse_window_line(malformed), None, "{:?}", malformed);
+        }
+    }
+
+    #[test]
+    fn test_parse_client_line() {
+        let client = parse_client_line("/dev/pts/3|212x50|xterm-256color").unwrap();
+        assert_eq!(
+            (client.name.as_str(), client.width, client.height),
+            ("/dev/pts/3", 212, 50)
+        );
+        assert_eq!(client.termname, "xterm-256color");
+        // tmux leaves an unknown terminal type empty
+        assert_eq!(parse_client_line("/dev/pts/4|80x24|").unwrap().termname, "");
+
+        for malformed in [
+            "",
+            "/dev/pts/9",
+            "/dev/pts/9|80",
+            "/dev/pts/9|80x24",
+            "/dev/pts/9|wide x tall|xterm",
+            "/dev/pts/9|80x-1|xterm",
+        ] {
+            assert_eq!(parse_client_line(malformed), None, "{:?}", malformed);
+        }
+    }
+}
@@ -2,6 +2,8 @@ use once_cell::sync::Lazy;
 use regex::Regex;
 use serde::{Deserialize, Serialize};
 
+use super::{AgentKind, aider};
+
 /// Status of an AI agent session
 #[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize, Default)]
 pub enum AgentStatus {
@@ -18,33 +20,138 @@ pub enum AgentStatus {
     Unknown,
 }
 
+impl std::str::FromStr for AgentStatus {
+    type Err = String;
+
+    /// Accepts `WaitingForInput`, `waiting-for-input` or `waiting_for_input`, in any case
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let normalized: String = s
+            .chars()
+            .filter(|c| !matches!(c, '-' | '_' | ' '))
+            .collect::<String>()
+            .to_lowercase();
+        match normalized.as_str() {
+            "busy" => Ok(AgentStatus::Busy),
+            "idle" => Ok(AgentStatus::Idle),
+            "waitingforinput" | "waiting" => Ok(AgentStatus::WaitingForInput),
+            "error" => Ok(AgentStatus::Error),
+            "unknown" => Ok(AgentStatus::Unknown),
+            _ => Err(format!(
+                "unknown status '{}' (expected busy, idle, waiting-for-input, error or unknown)",
+                s
+            )),
+        }
+    }
+}
+
 /// Compiled regex patterns for status detection
 static RE_WAITING_INPUT: Lazy<Regex> = Lazy::new(|| {
     Regex::new(r"(?mi)(^\s*>\s*$|Type a message|Press Enter|waiting for input|\? $|\[y/n\]|\(y/N\)|\(Y/n\))").unwrap()
 });
 
 static RE_BUSY: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(?mi)(Thinking\.{3}|Processing|Loading|Working|⠋|⠙|⠹|⠸|⠼|⠴|⠦|⠧|⠇|⠏|\.\.\.$)").unwrap()
+    Regex::new(r"(?mi)(Thinking\.{3}|Processing|Loading|Working|⠋|⠙|⠹|⠸|⠼|⠴|⠦|⠧|⠇|⠏|\.\.\.$)")
+        .unwrap()
 });
 
-static RE_ERROR: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(?mi)(^Error:|^error:|Exception|FAILED|panic|fatal|crash)").unwrap()
-});
+static RE_ERROR: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?mi)(^Error:|^error:|Exception|FAILED|panic|fatal|crash)").unwrap());
 
-static RE_IDLE: Lazy<Regex> = Lazy::new(|| {
-    Regex::new(r"(?m)(^\$\s*$|^❯\s*$|^>\s*$|claude>)").unwrap()
-});
+static RE_IDLE: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?m)(^\$\s*$|^❯\s*$|^>\s*$|claude>)").unwrap());
+
+/// Maps pane titles matching `pattern` to `status`
+///
+/// ```
+/// use agent_rusty::tmux::{AgentStatus, StateInferenceEngine, TitleRule};
+///
+/// let rules = [TitleRule::new(r"^⠂", AgentStatus::Busy).unwrap()];
+/// assert_eq!(
+///     StateInferenceEngine::analyze_title("⠂ Refactor parser", &rules),
+///     Some(AgentStatus::Busy)
+/// );
+/// ```
+#[derive(Debug, Clone, Deserialize)]
+#[serde(try_from = "TitleRuleSpec")]
+pub struct TitleRule {
+    pub pattern: Regex,
+    pub status: AgentStatus,
+}
+
+/// A `[[title_rules]]` table as written in the config
+#[derive(Deserialize)]
+#[serde(deny_unknown_fields)]
+struct TitleRuleSpec {
+    title: String,
+    status: String,
+}
+
+impl TryFrom<TitleRuleSpec> for TitleRule {
+    type Error = String;
+
+    fn try_from(spec: TitleRuleSpec) -> Result<Self, Self::Error> {
+        Self::new(&spec.title, spec.status.parse()?).map_err(|e| e.to_string())
+    }
+}
+
+impl TitleRule {
+    pub fn new(pattern: &str, status: AgentStatus) -> Result<Self, regex::Error> {
+        Ok(Self {
+            pattern: Regex::new(pattern)?,
+            status,
+        })
+    }
+}
+
+/// Whether `title` is just tmux's default, the host name, rather than one a program set
+pub fn is_default_title(title: &str, host: &str) -> bool {
+    let short = |name: &str| name.split('.').next().unwrap_or_default().to_string();
+    let title = title.trim();
+    title.is_empty() || (!host.is_empty() && (title == host || short(title) == short(host)))
+}
 
 /// Engine for inferring agent status from pane content
 pub struct StateInferenceEngine;
 
 impl StateInferenceEngine {
     /// Analyze pane content and determine agent status
+    ///
+    /// ```
+    /// use agent_rusty::tmux::{AgentStatus, StateInferenceEngine};
+    ///
+    /// let pane = "Do you want to continue? [y/n]";
+    /// assert_eq!(StateInferenceEngine::analyze(pane), AgentStatus::WaitingForInput);
+    /// ```
     pub fn analyze(content: &str) -> AgentStatus {
+        Self::analyze_as(content, AgentKind::Other)
+    }
+
+    /// Status from the pane title, if the rules that match it agree
+    ///
+    /// `None` when no rule matches or matching rules disagree, leaving it to the content.
+    pub fn analyze_title(title: &str, rules: &[TitleRule]) -> Option<AgentStatus> {
+        let mut statuses = rules
+            .iter()
+            .filter(|rule| rule.pattern.is_match(title))
+            .map(|rule| rule.status);
+        let status = statuses.next()?;
+        statuses.all(|other| other == status).then_some(status)
+    }
+
+    /// Analyze pane content from an agent we know, using its own patterns first
+    ///
+    /// Aider's patterns also apply to any pane showing its banner or questions.
+    pub fn analyze_as(content: &str, kind: AgentKind) -> AgentStatus {
         // Check last ~20 lines for most recent status
         let lines: Vec<&str> = content.lines().rev().take(20).collect();
         let recent_content = lines.into_iter().rev().collect::<Vec<_>>().join("\n");
 
+        if (kind == AgentKind::Aider || aider::is_aider(content))
+            && let Some(status) = aider::analyze(&recent_content)
+        {
+            return status;
+        }
+
         // Priority order: Error > WaitingForInput > Busy > Idle > Unknown
         if RE_ERROR.is_match(&recent_content) {
             return AgentStatus::Error;
@@ -70,13 +177,34 @@ impl StateInferenceEngine {
 mod tests {
     use super::*;
 
+    #[test]
+    fn test_parse_status_names() {
+        for name in [
+            "WaitingForInput",
+            "waiting-for-input",
+            "WAITING_FOR_INPUT",
+            "waiting",
+        ] {
+            assert_eq!(name.parse(), Ok(AgentStatus::WaitingForInput));
+        }
+        assert_eq!("idle".parse(), Ok(AgentStatus::Idle));
+        assert_eq!("Error".parse(), Ok(AgentStatus::Error));
+        assert!("sleeping".parse::<AgentStatus>().is_err());
+    }
+
     #[test]
     fn test_detect_waiting_for_input() {
         let content = "Some output\n\n> ";
-        assert_eq!(StateInferenceEngine::analyze(content), AgentStatus::WaitingForInput);
+        assert_eq!(
+            StateInferenceEngine::analyze(content),
+            AgentStatus::WaitingForInput
+        );
 
         let content = "Do you want to continue? [y/n]";
-        assert_eq!(StateInferenceEngine::analyze(content), AgentStatus::WaitingForInput);
+        assert_eq!(
+            StateInferenceEngine::analyze(content),
+            AgentStatus::WaitingForInput
+        );
     }
 
     #[test]
@@ -91,6 +219,46 @@ mod tests {
         assert_eq!(StateInferenceEngine::analyze(content), AgentStatus::Error);
     }
 
+    #[test]
+    fn test_analyze_title() {
+        let rules = [
+            TitleRule::new(r"^✳", AgentStatus::Idle).unwrap(),
+            TitleRule::new(r"^[⠁-⣿]", AgentStatus::Busy).unwrap(),
+            TitleRule::new(r"(?i)approve", AgentStatus::WaitingForInput).unwrap(),
+        ];
+        let analyze = |title| StateInferenceEngine::analyze_title(title, &rules);
+        assert_eq!(analyze("✳ Fix login bug"), Some(AgentStatus::Idle));
+        assert_eq!(analyze("⠐ Fix login bug"), Some(AgentStatus::Busy));
+        assert_eq!(analyze("vim"), None);
+        assert_eq!(analyze(""), None);
+        // Rules that disagree leave it to the pane content
+        assert_eq!(analyze("⠐ Approve edit?"), None);
+        assert_eq!(StateInferenceEngine::analyze_title("✳", &[]), None);
+
+        let twice = [
+            TitleRule::new(r"^✳", AgentStatus::Idle).unwrap(),
+            TitleRule::new(r"Fix", AgentStatus::Idle).unwrap(),
+        ];
+        assert_eq!(
+            StateInferenceEngine::analyze_title("✳ Fix", &twice),
+            Some(AgentStatus::Idle)
+        );
+        assert!(TitleRule::new(r"(", AgentStatus::Idle).is_err());
+    }
+
+    #[test]
+    fn test_default_titles() {
+        assert!(is_default_title("", "build01"));
+        assert!(is_default_title("  ", "build01"));
+        assert!(is_default_title("build01", "build01"));
+        assert!(is_default_title("build01.example.com", "build01"));
+        assert!(is_default_title("build01", "build01.example.com"));
+        assert!(!is_default_title("✳ Fix login bug", "build01"));
+        assert!(!is_default_title("vim", "build01"));
+        // Without a host name only an empty title is the default
+        assert!(!is_default_title("vim", ""));
+    }
+
     #[test]
     fn test_detect_idle() {
         let content = "Previous output\n$ ";
@@ -1,15 +1,41 @@
+mod agent;
+mod aider;
+mod backend;
 mod client;
 mod heuristics;
+mod socket;
+mod status_cache;
+mod wsl;
 
-pub use client::TmuxClient;
-pub use heuristics::{AgentStatus, StateInferenceEngine};
+pub use agent::AgentKind;
+pub use aider::{AIDER_HISTORY_LINES, AiderCache, AiderInfo};
+pub use backend::Sessions;
+#[cfg(test)]
+pub use backend::fake;
+pub use client::{TmuxClient, window_target};
+pub use heuristics::{AgentStatus, StateInferenceEngine, TitleRule, is_default_title};
+pub use socket::Socket;
+pub use status_cache::{CapturePlan, STATUS_CACHE_TTL, StatusCache};
+pub use wsl::Wsl;
+
+/// Where sessions we create keep their shell history
+pub fn history_dir() -> std::path::PathBuf {
+    dirs::home_dir()
+        .unwrap_or_default()
+        .join(".agent-deck")
+        .join("history")
+}
 
 use serde::{Deserialize, Serialize};
 
+use crate::claude::ClaudeMeta;
+use crate::git::GitInfo;
+
 /// Represents a tmux session
-#[derive(Debug, Clone, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct TmuxSession {
-    /// Session ID (e.g., "$0")
+    /// Session ID (e.g., "$0"); prefixed with the server's label (`work/$0`)
+    /// when several servers are watched
     pub id: String,
     /// Session name
     pub name: String,
@@ -17,18 +43,126 @@ pub struct TmuxSession {
     pub created_at: u64,
     /// Number of attached clients
     pub attached_clients: usize,
+    /// Unix timestamp of the last activity in the session
+    #[serde(default)]
+    pub activity_at: u64,
+    /// Command running in the active pane
+    #[serde(default)]
+    pub current_command: String,
+    /// Working directory of the active pane
+    #[serde(default)]
+    pub current_path: String,
+    /// Title the active pane's program set; empty if it's still tmux's default
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub pane_title: String,
+    /// Number of windows in the session; 0 if unknown
+    #[serde(default, skip_serializing_if = "is_zero")]
+    pub windows: usize,
+    /// Command the active pane was started with; empty for the default shell
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub start_command: String,
+    /// Directory the session was started in
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub start_path: String,
+    /// Label of the tmux server the session is on; empty when only one is watched
+    #[serde(default, skip_serializing_if = "String::is_empty")]
+    pub socket: String,
+    /// Git state of `current_path`, filled in by the poller
+    #[serde(default)]
+    pub git: Option<GitInfo>,
+    /// Latest Claude Code conversation in `current_path`, filled in by the poller
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub claude: Option<ClaudeMeta>,
+    /// Model and cost an Aider session announced, filled in by the poller
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub aider: Option<AiderInfo>,
+    /// New output lines per `activity::ACTIVITY_BUCKETS` bucket, oldest first, filled in by the poller
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub activity_trend: Vec<u32>,
+    /// Tags given in the dashboard, filled in by the poller
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tags: Vec<String>,
+    /// Windows shown under the session once it's expanded in the list; `None`
+    /// while collapsed, empty while they load
+    #[serde(skip)]
+    pub window_tree: Option<Vec<TmuxWindow>>,
     /// Detected agent status
     pub status: AgentStatus,
 }
 
 impl TmuxSession {
+    #[allow(dead_code)]
     pub fn new(id: String, name: String) -> Self {
         Self {
             id,
             name,
             created_at: 0,
             attached_clients: 0,
+            activity_at: 0,
+            current_command: String::new(),
+            current_path: String::new(),
+            pane_title: String::new(),
+            windows: 0,
+            start_command: String::new(),
+            start_path: String::new(),
+            socket: String::new(),
+            git: None,
+            claude: None,
+            aider: None,
+            activity_trend: Vec::new(),
+            tags: Vec::new(),
+            window_tree: None,
             status: AgentStatus::Unknown,
         }
     }
+
+    /// Agent detected from the active pane's command
+    pub fn agent_kind(&self) -> AgentKind {
+        AgentKind::detect(&self.current_command)
+    }
+}
+
+fn is_zero(n: &usize) -> bool {
+    *n == 0
+}
+
+/// A window of a session, as offered before attaching
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmuxWindow {
+    pub index: u32,
+    pub name: String,
+    /// Whether it's the window the session shows when attached
+    pub active: bool,
+    pub panes: usize,
+    /// Command running in its active pane
+    pub command: String,
+}
+
+/// The tmux version and how each watched server is doing
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ServerInfo {
+    /// `tmux -V`, e.g. `tmux 3.4`
+    pub version: String,
+    pub servers: Vec<TmuxServer>,
+}
+
+/// One watched tmux server
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TmuxServer {
+    /// Socket it listens on; empty if tmux didn't say
+    pub socket_path: String,
+    pub running: bool,
+    pub sessions: usize,
+    pub windows: usize,
+}
+
+/// A terminal attached to a session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AttachedClient {
+    /// tmux's name for it, usually its tty, e.g. `/dev/pts/3`
+    pub name: String,
+    pub width: u16,
+    pub height: u16,
+    /// The client's `TERM`, e.g. `xterm-256color`
+    pub termname: String,
 }
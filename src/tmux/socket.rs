@@ -0,0 +1,47 @@
+use std::path::PathBuf;
+
+/// A tmux server to talk to, other than the default one
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Socket {
+    /// Socket name in tmux's socket directory (`tmux -L`)
+    Name(String),
+    /// Socket path (`tmux -S`)
+    Path(PathBuf),
+}
+
+impl Socket {
+    /// A socket from the config: a path if it contains a `/`, else a name
+    ///
+    /// ```
+    /// use agent_rusty::tmux::Socket;
+    ///
+    /// assert_eq!(Socket::parse("work").label(), "work");
+    /// assert_eq!(Socket::parse("/tmp/tmux-1000/personal").label(), "personal");
+    /// ```
+    pub fn parse(socket: &str) -> Self {
+        if socket.contains('/') {
+            Socket::Path(PathBuf::from(socket))
+        } else {
+            Socket::Name(socket.to_string())
+        }
+    }
+
+    /// Short name shown in the list, and the prefix of its sessions' ids
+    pub fn label(&self) -> String {
+        match self {
+            Socket::Name(name) => name.clone(),
+            Socket::Path(path) => path
+                .file_name()
+                .map(|name| name.to_string_lossy().into_owned())
+                .unwrap_or_else(|| path.display().to_string()),
+        }
+    }
+
+    /// Arguments selecting this server, ahead of the tmux subcommand
+    pub(crate) fn args(&self) -> [String; 2] {
+        match self {
+            Socket::Name(name) => ["-L".to_string(), name.clone()],
+            Socket::Path(path) => ["-S".to_string(), path.display().to_string()],
+        }
+    }
+}
@@ -0,0 +1,199 @@
+use std::collections::{HashMap, HashSet};
+use std::time::{Duration, Instant};
+
+use super::AgentStatus;
+use crate::activity;
+
+/// How long a cached status is trusted without new activity
+pub const STATUS_CACHE_TTL: Duration = Duration::from_secs(15);
+
+/// A session's status as of its last capture
+#[derive(Debug, Clone)]
+struct Entry {
+    /// `session_activity` when the pane was captured
+    activity_at: u64,
+    /// Unix time of the capture
+    captured_unix: u64,
+    captured_at: Instant,
+    status: AgentStatus,
+    /// `activity::line_digests` of the latest capture; `None` until there is one
+    lines: Option<Vec<u64>>,
+}
+
+/// Session ids whose panes a poll captures regardless of the cache, or skips
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct CapturePlan {
+    /// Captured even without new activity
+    pub force: HashSet<String>,
+    /// Not due yet: keep the last status even if there is new activity
+    pub skip: HashSet<String>,
+}
+
+/// Remembers inferred statuses so quiet sessions aren't captured on every poll
+#[derive(Debug)]
+pub struct StatusCache {
+    entries: HashMap<String, Entry>,
+    ttl: Duration,
+    /// New lines seen per session since the last `take_changes`
+    changes: HashMap<String, u32>,
+}
+
+impl StatusCache {
+    pub fn new(ttl: Duration) -> Self {
+        Self {
+            entries: HashMap::new(),
+            ttl,
+            changes: HashMap::new(),
+        }
+    }
+
+    /// Cached status, if the session has shown no activity since it was captured
+    ///
+    /// Activity is only reported to the second, so a capture taken in the same
+    /// second as the last activity may have missed some output and isn't reused.
+    pub fn get(&self, session_id: &str, activity_at: u64) -> Option<AgentStatus> {
+        let entry = self.entries.get(session_id)?;
+        let quiet = entry.activity_at == activity_at && activity_at < entry.captured_unix;
+        (quiet && entry.captured_at.elapsed() < self.ttl).then_some(entry.status)
+    }
+
+    /// Status as of the last capture, however old
+    pub fn last(&self, session_id: &str) -> Option<AgentStatus> {
+        self.entries.get(session_id).map(|entry| entry.status)
+    }
+
+    pub fn insert(
+        &mut self,
+        session_id: &str,
+        activity_at: u64,
+        now_unix: u64,
+        status: AgentStatus,
+        content: &str,
+    ) {
+        let lines = activity::line_digests(content);
+        // A first capture is where the session stands, not new output
+        if let Some(previous) = self
+            .entries
+            .get(session_id)
+            .and_then(|entry| entry.lines.as_ref())
+        {
+            *self.changes.entry(session_id.to_string()).or_default() +=
+                activity::count_new(previous, &lines);
+        }
+        self.entries.insert(
+            session_id.to_string(),
+            Entry {
+                activity_at,
+                captured_unix: now_unix,
+                captured_at: Instant::now(),
+                status,
+                lines: Some(lines),
+            },
+        );
+    }
+
+    /// Record a status decided without capturing, e.g. from the pane title
+    ///
+    /// The lines of the last capture are kept, so output in between is
+    /// counted once the pane is captured again rather than lost.
+    pub fn insert_uncaptured(
+        &mut self,
+        session_id: &str,
+        activity_at: u64,
+        now_unix: u64,
+        status: AgentStatus,
+    ) {
+        let lines = self
+            .entries
+            .remove(session_id)
+            .and_then(|entry| entry.lines);
+        self.entries.insert(
+            session_id.to_string(),
+            Entry {
+                activity_at,
+                captured_unix: now_unix,
+                captured_at: Instant::now(),
+                status,
+                lines,
+            },
+        );
+    }
+
+    /// New lines per session captured since the last call
+    pub fn take_changes(&mut self) -> HashMap<String, u32> {
+        std::mem::take(&mut self.changes)
+    }
+
+    /// Forget sessions that no longer exist
+    pub fn retain(&mut self, mut keep: impl FnMut(&str) -> bool) {
+        self.entries.retain(|id, _| keep(id));
+        self.changes.retain(|id, _| keep(id));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_reuse_until_activity() {
+        let mut cache = StatusCache::new(Duration::from_secs(60));
+        assert_eq!(cache.get("$1", 100), None);
+
+        cache.insert("$1", 100, 101, AgentStatus::Idle, "");
+        assert_eq!(cache.get("$1", 100), Some(AgentStatus::Idle));
+        assert_eq!(cache.get("$1", 102), None);
+
+        // Captured within the second of the last activity: not trusted
+        cache.insert("$1", 102, 102, AgentStatus::Busy, "");
+        assert_eq!(cache.get("$1", 102), None);
+
+        cache.retain(|id| id != "$1");
+        cache.insert("$2", 100, 101, AgentStatus::Idle, "");
+        assert_eq!(cache.get("$1", 102), None);
+        assert_eq!(cache.get("$2", 100), Some(AgentStatus::Idle));
+
+        // Expired entries force a fresh capture
+        let mut cache = StatusCache::new(Duration::ZERO);
+        cache.insert("$1", 100, 101, AgentStatus::Idle, "");
+        assert_eq!(cache.get("$1", 100), None);
+        // Though sessions that aren't due yet keep it
+        assert_eq!(cache.last("$1"), Some(AgentStatus::Idle));
+        assert_eq!(cache.last("$2"), None);
+    }
+
+    #[test]
+    fn test_changes_between_captures() {
+        let mut cache = StatusCache::new(Duration::from_secs(60));
+        cache.insert("$1", 100, 101, AgentStatus::Busy, "a\nb\nc");
+        assert!(cache.take_changes().is_empty());
+
+        cache.insert("$1", 102, 103, AgentStatus::Busy, "b\nc\nd");
+        cache.insert("$1", 104, 105, AgentStatus::Busy, "d\ne\nf");
+        cache.insert("$2", 104, 105, AgentStatus::Idle, "x");
+        assert_eq!(cache.take_changes(), HashMap::from([("$1".to_string(), 3)]));
+        assert!(cache.take_changes().is_empty());
+
+        cache.insert("$1", 106, 107, AgentStatus::Idle, "f\n> ");
+        cache.retain(|id| id != "$1");
+        assert!(cache.take_changes().is_empty());
+    }
+
+    #[test]
+    fn test_uncaptured_status_keeps_lines() {
+        let mut cache = StatusCache::new(Duration::from_secs(60));
+        cache.insert("$1", 100, 101, AgentStatus::Busy, "a\nb");
+        cache.insert_uncaptured("$1", 102, 103, AgentStatus::Idle);
+        assert_eq!(cache.get("$1", 102), Some(AgentStatus::Idle));
+        assert!(cache.take_changes().is_empty());
+
+        // The next capture counts everything since the last one
+        cache.insert("$1", 104, 105, AgentStatus::Busy, "a\nb\nc\nd");
+        assert_eq!(cache.take_changes(), HashMap::from([("$1".to_string(), 2)]));
+
+        // Nothing captured yet: a first capture still counts as none
+        cache.insert_uncaptured("$2", 100, 101, AgentStatus::Idle);
+        cache.insert("$2", 102, 103, AgentStatus::Busy, "x");
+        assert!(cache.take_changes().is_empty());
+    }
+}
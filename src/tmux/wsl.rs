@@ -0,0 +1,197 @@
+use std::process::Command;
+
+/// The launcher that runs Linux programs inside WSL
+pub const WSL_EXE: &str = "wsl.exe";
+
+/// Runs tmux inside a WSL distribution from the Windows side
+///
+/// tmux and its sessions live in Linux, so paths crossing the boundary are
+/// translated: `C:\src\api` is `/mnt/c/src/api` inside the distribution,
+/// and `/home/me` is `\\wsl.localhost\<distro>\home\me` outside it.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Wsl {
+    /// Distribution to run in; WSL's default when `None`
+    pub distro: Option<String>,
+}
+
+impl Wsl {
+    pub fn new(distro: Option<String>) -> Self {
+        Self { distro }
+    }
+
+    /// Whether WSL can be driven from here: on Windows, with `wsl.exe` on `PATH`
+    pub fn available() -> bool {
+        cfg!(windows)
+            && std::env::var_os("PATH").is_some_and(|path| {
+                std::env::split_paths(&path).any(|dir| dir.join(WSL_EXE).is_file())
+            })
+    }
+
+    /// Name of the default distribution, asked from inside it
+    pub fn default_distro() -> Option<String> {
+        let output = Command::new(WSL_EXE)
+            .args(["-e", "printenv", "WSL_DISTRO_NAME"])
+            .output()
+            .ok()?;
+        let name = String::from_utf8_lossy(&output.stdout).trim().to_string();
+        (output.status.success() && !name.is_empty()).then_some(name)
+    }
+
+    /// Program and arguments that run `program` inside the distribution
+    ///
+    /// ```
+    /// use agent_rusty::tmux::Wsl;
+    ///
+    /// let wsl = Wsl::new(Some("Ubuntu".to_string()));
+    /// assert_eq!(wsl.argv("tmux").join(" "), "wsl.exe -d Ubuntu -- tmux");
+    /// ```
+    pub fn argv(&self, program: &str) -> Vec<String> {
+        let mut argv = vec![WSL_EXE.to_string()];
+        if let Some(distro) = &self.distro {
+            argv.extend(["-d".to_string(), distro.clone()]);
+        }
+        argv.extend(["--".to_string(), program.to_string()]);
+        argv
+    }
+
+    /// A Windows path as seen from inside the distribution
+    ///
+    /// Drive paths map to `/mnt/<drive>`, `\\wsl$` and `\\wsl.localhost`
+    /// shares to the distribution's root; Linux paths are returned unchanged.
+    pub fn to_wsl_path(path: &str) -> String {
+        let slashed = path.replace('\\', "/");
+        let lower = slashed.to_ascii_lowercase();
+        for share in ["//wsl$/", "//wsl.localhost/"] {
+            if lower.starts_with(share) {
+                // Drop the share and the distribution name
+                let rest = &slashed[share.len()..];
+                return match rest.find('/') {
+                    Some(at) => rest[at..].to_string(),
+                    None => "/".to_string(),
+                };
+            }
+        }
+        let mut chars = slashed.chars();
+        match (chars.next(), chars.next()) {
+            (Some(drive), Some(':')) if drive.is_ascii_alphabetic() => {
+                let rest = chars.as_str().trim_start_matches('/');
+                let mut wsl_path = format!("/mnt/{}", drive.to_ascii_lowercase());
+                if !rest.is_empty() {
+                    wsl_path.push('/');
+                    wsl_path.push_str(rest);
+                }
+                wsl_path
+            }
+            _ => path.to_string(),
+        }
+    }
+
+    /// A path from inside the distribution as seen from Windows
+    ///
+    /// Relative paths, and Linux paths when the distribution is unknown,
+    /// are returned unchanged.
+    pub fn to_windows_path(&self, path: &str) -> String {
+        if let Some(rest) = path.strip_prefix("/mnt/") {
+            let (drive, rest) = rest.split_once('/').unwrap_or((rest, ""));
+            if drive.len() == 1 && drive.chars().all(|c| c.is_ascii_alphabetic()) {
+                return format!(
+                    "{}:\\{}",
+                    drive.to_ascii_uppercase(),
+                    rest.replace('/', "\\")
+                );
+            }
+        }
+        match &self.distro {
+            Some(distro) if path.starts_with('/') => {
+                format!("\\\\wsl.localhost\\{}{}", distro, path.replace('/', "\\"))
+            }
+            _ => path.to_string(),
+        }
+    }
+
+    /// `WSLENV` value that also shares `name` with Linux programs
+    ///
+    /// The value is passed through untouched, so translate paths first; an
+    /// existing entry for `name` (e.g. `name/p`) is replaced.
+    pub fn share_env(existing: Option<&str>, name: &str) -> String {
+        existing
+            .unwrap_or_default()
+            .split(':')
+            .filter(|entry| !entry.is_empty() && entry.split('/').next() != Some(name))
+            .chain([name])
+            .collect::<Vec<_>>()
+            .join(":")
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_argv() {
+        assert_eq!(Wsl::default().argv("tmux"), ["wsl.exe", "--", "tmux"]);
+        assert_eq!(
+            Wsl::new(Some("Debian".to_string())).argv("/usr/bin/tmux"),
+            ["wsl.exe", "-d", "Debian", "--", "/usr/bin/tmux"]
+        );
+    }
+
+    #[test]
+    fn test_to_wsl_path() {
+        assert_eq!(
+            Wsl::to_wsl_path(r"C:\Users\me\.agent-deck\history\api.hist"),
+            "/mnt/c/Users/me/.agent-deck/history/api.hist"
+        );
+        assert_eq!(Wsl::to_wsl_path("d:/src"), "/mnt/d/src");
+        assert_eq!(Wsl::to_wsl_path(r"E:\"), "/mnt/e");
+        assert_eq!(
+            Wsl::to_wsl_path(r"\\wsl$\Ubuntu\home\me\api"),
+            "/home/me/api"
+        );
+        assert_eq!(Wsl::to_wsl_path(r"\\WSL.localhost\Ubuntu\home"), "/home");
+        assert_eq!(Wsl::to_wsl_path(r"\\wsl$\Ubuntu"), "/");
+        assert_eq!(Wsl::to_wsl_path("/home/me"), "/home/me");
+        assert_eq!(Wsl::to_wsl_path("api"), "api");
+    }
+
+    #[test]
+    fn test_to_windows_path() {
+        let wsl = Wsl::new(Some("Ubuntu".to_string()));
+        assert_eq!(wsl.to_windows_path("/mnt/c/src/api"), r"C:\src\api");
+        assert_eq!(wsl.to_windows_path("/mnt/d"), r"D:\");
+        assert_eq!(
+            wsl.to_windows_path("/home/me/api"),
+            r"\\wsl.localhost\Ubuntu\home\me\api"
+        );
+        // Not a drive mount
+        assert_eq!(
+            wsl.to_windows_path("/mnt/data/x"),
+            r"\\wsl.localhost\Ubuntu\mnt\data\x"
+        );
+        assert_eq!(Wsl::default().to_windows_path("/home/me"), "/home/me");
+        assert_eq!(Wsl::default().to_windows_path("/mnt/c/x"), r"C:\x");
+    }
+
+    #[test]
+    fn test_round_trip() {
+        let wsl = Wsl::new(Some("Ubuntu".to_string()));
+        for path in [r"C:\src\api", r"\\wsl.localhost\Ubuntu\home\me"] {
+            assert_eq!(wsl.to_windows_path(&Wsl::to_wsl_path(path)), path);
+        }
+    }
+
+    #[test]
+    fn test_share_env() {
+        assert_eq!(Wsl::share_env(None, "HISTFILE"), "HISTFILE");
+        assert_eq!(Wsl::share_env(Some(""), "HISTFILE"), "HISTFILE");
+        assert_eq!(
+            Wsl::share_env(Some("USERPROFILE/p"), "HISTFILE"),
+            "USERPROFILE/p:HISTFILE"
+        );
+        assert_eq!(
+            Wsl::share_env(Some("HISTFILE/p:X"), "HISTFILE"),
+            "X:HISTFILE"
+        );
+    }
+}
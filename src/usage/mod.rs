@@ -0,0 +1,463 @@
+//! Token and cost usage rolled up across sessions.
+//!
+//! Agents print running totals for their own run: Claude Code's `/cost`
+//! report and aider's `Cost: $0.02 message, $0.13 session.` line. The
+//! dashboard reads them from each pane now and then and records how much
+//! they grew since the previous reading. Keeping growth rather than totals
+//! means an agent restarting with fresh counters loses nothing, and the
+//! samples are kept in the state file so the rollup outlives the dashboard.
+
+use chrono::{DateTime, Datelike, TimeZone};
+use once_cell::sync::Lazy;
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+
+use crate::tmux::AgentKind;
+
+/// Scrollback searched for the latest totals
+pub const USAGE_HISTORY_LINES: usize = 500;
+/// Samples older than this are dropped
+pub const KEEP_SECS: u64 = 14 * 86_400;
+
+/// `Total cost: $0.2151` (Claude Code) or `$0.13 session` (aider)
+static RE_COST: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"(?i)total cost:\s*\$([0-9]+(?:\.[0-9]+)?)|\$([0-9]+(?:\.[0-9]+)?) session")
+        .unwrap()
+});
+
+/// `Total tokens: 12.3k`
+static RE_TOTAL_TOKENS: Lazy<Regex> =
+    Lazy::new(|| Regex::new(r"(?i)total tokens:\s*([0-9][0-9.,]*[kKmM]?)").unwrap());
+
+/// A count in a `/cost` model line: `1.2k input`, `150.3k cache read`
+static RE_MODEL_TOKENS: Lazy<Regex> = Lazy::new(|| {
+    Regex::new(r"([0-9][0-9.,]*[kKmM]?) (?:input|output|cache read|cache write)\b").unwrap()
+});
+
+/// Running totals an agent shows for its current run
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Reading {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tokens: Option<u64>,
+    /// US dollars
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cost: Option<f64>,
+}
+
+impl Reading {
+    pub fn is_empty(&self) -> bool {
+        self.tokens.is_none() && self.cost.is_none()
+    }
+}
+
+/// `12,345`, `1.2k` or `2.1M`
+fn parse_count(text: &str) -> Option<u64> {
+    let text = text.replace(',', "");
+    let (number, scale) = match text.chars().last()? {
+        'k' | 'K' => (&text[..text.len() - 1], 1e3),
+        'm' | 'M' => (&text[..text.len() - 1], 1e6),
+        _ => (text.as_str(), 1.0),
+    };
+    let value: f64 = number.parse().ok()?;
+    Some((value * scale).round() as u64)
+}
+
+/// The latest totals in a pane capture
+///
+/// Tokens come from a `Total tokens:` line, or else from summing the model
+/// lines of the last `/cost` report.
+pub fn parse(content: &str) -> Reading {
+    let cost = RE_COST
+        .captures_iter(content)
+        .last()
+        .and_then(|c| c.get(1).or(c.get(2)))
+        .and_then(|m| m.as_str().parse().ok());
+
+    let total = RE_TOTAL_TOKENS
+        .captures_iter(content)
+        .last()
+        .and_then(|c| parse_count(&c[1]));
+    let report = content
+        .rfind("Total cost:")
+        .map(|start| &content[start..])
+        .and_then(|report| {
+            let counts: Vec<u64> = RE_MODEL_TOKENS
+                .captures_iter(report)
+                .filter_map(|c| parse_count(&c[1]))
+                .collect();
+            (!counts.is_empty()).then(|| counts.iter().sum())
+        });
+
+    Reading {
+        tokens: total.or(report),
+        cost,
+    }
+}
+
+/// Growth of one session's totals, observed at `at`
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Sample {
+    pub at: u64,
+    pub session: String,
+    pub kind: AgentKind,
+    #[serde(default)]
+    pub tokens: u64,
+    #[serde(default)]
+    pub cost: f64,
+}
+
+/// A session's previous reading, to measure growth from
+#[derive(Debug, Clone, Copy, Default, PartialEq, Serialize, Deserialize)]
+pub struct Baseline {
+    pub at: u64,
+    #[serde(flatten)]
+    pub reading: Reading,
+}
+
+/// Usage samples kept in the state file
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct UsageLedger {
+    /// Latest reading by session name
+    #[serde(default)]
+    pub baselines: BTreeMap<String, Baseline>,
+    /// Oldest first
+    #[serde(default)]
+    pub samples: Vec<Sample>,
+}
+
+/// Growth from `last` to `now`; a smaller value means the counter restarted
+fn growth<T: Copy + PartialOrd + std::ops::Sub<Output = T>>(
+    last: Option<T>,
+    now: Option<T>,
+) -> Option<T> {
+    match (last, now) {
+        (Some(last), Some(now)) if now >= last => Some(now - last),
+        (_, now) => now,
+    }
+}
+
+impl UsageLedger {
+    /// Record a reading of `session`'s totals; `true` if it grew
+    ///
+    /// The first reading of a session counts in full. Figures missing from a
+    /// reading (scrolled away, say) keep their previous value.
+    pub fn record(&mut self, session: &str, kind: AgentKind, reading: Reading, at: u64) -> bool {
+        if reading.is_empty() {
+            return false;
+        }
+        let last = self
+            .baselines
+            .get(session)
+            .map(|b| b.reading)
+            .unwrap_or_default();
+        let tokens = growth(last.tokens, reading.tokens).unwrap_or(0);
+        let cost = growth(last.cost, reading.cost).unwrap_or(0.0);
+        self.baselines.insert(
+            session.to_string(),
+            Baseline {
+                at,
+                reading: Reading {
+                    tokens: reading.tokens.or(last.tokens),
+                    cost: reading.cost.or(last.cost),
+                },
+            },
+        );
+        if tokens == 0 && cost <= 0.0 {
+            return false;
+        }
+        self.samples.push(Sample {
+            at,
+            session: session.to_string(),
+            kind,
+            tokens,
+            cost,
+        });
+        true
+    }
+
+    /// Drop samples and baselines older than `KEEP_SECS`
+    pub fn prune(&mut self, now: u64) {
+        let cutoff = now.saturating_sub(KEEP_SECS);
+        self.samples.retain(|s| s.at >= cutoff);
+        self.baselines.retain(|_, b| b.at >= cutoff);
+    }
+
+    pub fn rollup(&self, day_start: u64, week_start: u64) -> Rollup {
+        rollup(&self.samples, day_start, week_start)
+    }
+}
+
+/// Tokens and cost added up
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct Totals {
+    pub tokens: u64,
+    pub cost: f64,
+}
+
+impl Totals {
+    fn add(&mut self, sample: &Sample) {
+        self.tokens += sample.tokens;
+        self.cost += sample.cost;
+    }
+}
+
+/// One session's or agent kind's usage
+#[derive(Debug, Clone, PartialEq)]
+pub struct RollupRow {
+    pub label: String,
+    pub today: Totals,
+    pub week: Totals,
+}
+
+/// Usage for today and this week, overall and broken down
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Rollup {
+    pub today: Totals,
+    pub week: Totals,
+    /// Biggest spender this week first
+    pub sessions: Vec<RollupRow>,
+    /// Biggest spender this week first
+    pub kinds: Vec<RollupRow>,
+}
+
+impl Rollup {
+    /// Session that spent the most this week, by cost, then tokens
+    pub fn biggest_spender(&self) -> Option<&str> {
+        self.sessions.first().map(|r| r.label.as_str())
+    }
+}
+
+fn rows(groups: BTreeMap<String, (Totals, Totals)>) -> Vec<RollupRow> {
+    let mut rows: Vec<RollupRow> = groups
+        .into_iter()
+        .map(|(label, (today, week))| RollupRow { label, today, week })
+        .collect();
+    rows.sort_by(|a, b| {
+        b.week
+            .cost
+            .total_cmp(&a.week.cost)
+            .then(b.week.tokens.cmp(&a.week.tokens))
+            .then_with(|| a.label.cmp(&b.label))
+    });
+    rows
+}
+
+/// Add up the samples since `week_start`, and those since `day_start` separately
+pub fn rollup(samples: &[Sample], day_start: u64, week_start: u64) -> Rollup {
+    let mut rollup = Rollup::default();
+    let mut sessions: BTreeMap<String, (Totals, Totals)> = BTreeMap::new();
+    let mut kinds: BTreeMap<String, (Totals, Totals)> = BTreeMap::new();
+    for sample in samples.iter().filter(|s| s.at >= week_start) {
+        let today = sample.at >= day_start;
+        let groups = [
+            (&mut sessions, sample.session.clone()),
+            (&mut kinds, sample.kind.label().to_string()),
+        ];
+        for (group, label) in groups {
+            let (day, week) = group.entry(label).or_default();
+            week.add(sample);
+            if today {
+                day.add(sample);
+            }
+        }
+        rollup.week.add(sample);
+        if today {
+            rollup.today.add(sample);
+        }
+    }
+    rollup.sessions = rows(sessions);
+    rollup.kinds = rows(kinds);
+    rollup
+}
+
+/// Unix times of the start of `now`'s day and of its week (Monday)
+pub fn period_starts<Tz: TimeZone>(now: &DateTime<Tz>) -> (u64, u64) {
+    let zone = now.timezone();
+    let midnight = |date: chrono::NaiveDate| {
+        zone.from_local_datetime(&date.and_time(chrono::NaiveTime::MIN))
+            .earliest()
+            .map_or(0, |at| at.timestamp().max(0) as u64)
+    };
+    let today = now.date_naive();
+    let monday = today - chrono::Days::new(today.weekday().num_days_from_monday() as u64);
+    (midnight(today), midnight(monday))
+}
+
+/// `12.3k` tokens, `1.2M`
+pub fn format_tokens(tokens: u64) -> String {
+    match tokens {
+        0..1_000 => tokens.to_string(),
+        1_000..1_000_000 => format!("{:.1}k", tokens as f64 / 1e3),
+        _ => format!("{:.1}M", tokens as f64 / 1e6),
+    }
+}
+
+pub fn format_cost(cost: f64) -> String {
+    format!("${:.2}", cost)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::FixedOffset;
+
+    const DAY: u64 = 86_400;
+
+    fn reading(tokens: u64, cost: f64) -> Reading {
+        Reading {
+            tokens: Some(tokens),
+            cost: Some(cost),
+        }
+    }
+
+    fn sample(at: u64, session: &str, kind: AgentKind, tokens: u64, cost: f64) -> Sample {
+        Sample {
+            at,
+            session: session.to_string(),
+            kind,
+            tokens,
+            cost,
+        }
+    }
+
+    #[test]
+    fn test_parse_claude_cost_report() {
+        let pane = "\
+> /cost
+  ⎿  Total cost:            $0.2151
+     Total duration (API):  1m 2.3s
+     Total code changes:    12 lines added, 3 lines removed
+     Usage by model:
+         claude-haiku:  1.2k input, 80 output, 0 cache read, 0 cache write
+        claude-sonnet:  12 input, 2.1k output, 150.3k cache read, 20.1k cache write
+";
+        assert_eq!(
+            parse(pane),
+            reading(1_200 + 80 + 12 + 2_100 + 150_300 + 20_100, 0.2151)
+        );
+    }
+
+    #[test]
+    fn test_parse_aider_and_latest_wins() {
+        let pane = "\
+Tokens: 4.5k sent, 312 received. Cost: $0.02 message, $0.02 session.
+Tokens: 5.1k sent, 250 received. Cost: $0.03 message, $0.05 session.
+";
+        let parsed = parse(pane);
+        assert_eq!(parsed.cost, Some(0.05));
+        // Aider only shows tokens per message
+        assert_eq!(parsed.tokens, None);
+
+        assert_eq!(parse("Total tokens: 12,345").tokens, Some(12_345));
+        assert_eq!(parse("Total tokens: 2.5M").tokens, Some(2_500_000));
+        assert!(parse("$ ls\nsrc  Cargo.toml").is_empty());
+    }
+
+    #[test]
+    fn test_record_keeps_growth_across_restarts() {
+        let mut ledger = UsageLedger::default();
+        let claude = AgentKind::Claude;
+        assert!(ledger.record("api", claude, reading(1_000, 0.10), 100));
+        // Nothing new
+        assert!(!ledger.record("api", claude, reading(1_000, 0.10), 200));
+        assert!(ledger.record("api", claude, reading(1_500, 0.25), 300));
+        // The agent restarts and its counters start over
+        assert!(ledger.record("api", claude, reading(200, 0.02), 400));
+        assert!(ledger.record("api", claude, reading(700, 0.07), 500));
+        // The totals scrolled away
+        assert!(!ledger.record("api", claude, Reading::default(), 600));
+
+        let tokens: Vec<u64> = ledger.samples.iter().map(|s| s.tokens).collect();
+        assert_eq!(tokens, [1_000, 500, 200, 500]);
+        let total = ledger.rollup(0, 0).week;
+        assert_eq!(total.tokens, 2_200);
+        assert!((total.cost - 0.32).abs() < 1e-9, "{}", total.cost);
+    }
+
+    #[test]
+    fn test_record_partial_readings() {
+        let mut ledger = UsageLedger::default();
+        ledger.record("api", AgentKind::Claude, reading(1_000, 0.10), 100);
+        // Only the cost is on screen: the token count carries over
+        let cost_only = Reading {
+            tokens: None,
+            cost: Some(0.30),
+        };
+        assert!(ledger.record("api", AgentKind::Claude, cost_only, 200));
+        assert_eq!(ledger.samples[1].tokens, 0);
+        assert!(!ledger.record("api", AgentKind::Claude, reading(1_000, 0.30), 300));
+        assert_eq!(ledger.baselines["api"].at, 300);
+    }
+
+    #[test]
+    fn test_rollup_today_and_week() {
+        let week_start = 10 * DAY;
+        let day_start = 13 * DAY;
+        let samples = vec![
+            // Last week
+            sample(week_start - 1, "api", AgentKind::Claude, 9_000, 9.0),
+            sample(week_start, "api", AgentKind::Claude, 1_000, 0.50),
+            sample(day_start + 10, "api", AgentKind::Claude, 2_000, 1.00),
+            sample(day_start - 10, "web", AgentKind::Aider, 0, 2.00),
+            sample(day_start + 20, "docs", AgentKind::Claude, 500, 0.25),
+        ];
+        let rollup = rollup(&samples, day_start, week_start);
+        assert_eq!(rollup.today.tokens, 2_500);
+        assert!((rollup.today.cost - 1.25).abs() < 1e-9);
+        assert_eq!(rollup.week.tokens, 3_500);
+        assert!((rollup.week.cost - 3.75).abs() < 1e-9);
+
+        let sessions: Vec<&str> = rollup.sessions.iter().map(|r| r.label.as_str()).collect();
+        assert_eq!(sessions, ["web", "api", "docs"]);
+        assert_eq!(rollup.biggest_spender(), Some("web"));
+        assert_eq!(rollup.sessions[0].today, Totals::default());
+        assert_eq!(rollup.sessions[1].today.tokens, 2_000);
+
+        let kinds: Vec<(&str, u64)> = rollup
+            .kinds
+            .iter()
+            .map(|r| (r.label.as_str(), r.week.tokens))
+            .collect();
+        assert_eq!(kinds, [("aider", 0), ("claude", 3_500)]);
+
+        assert_eq!(super::rollup(&[], 0, 0).biggest_spender(), None);
+    }
+
+    #[test]
+    fn test_prune() {
+        let mut ledger = UsageLedger::default();
+        ledger.record("old", AgentKind::Claude, reading(10, 0.01), 100);
+        ledger.record("new", AgentKind::Claude, reading(10, 0.01), 100 + KEEP_SECS);
+        ledger.prune(101 + KEEP_SECS);
+        assert_eq!(ledger.samples.len(), 1);
+        assert_eq!(ledger.baselines.keys().collect::<Vec<_>>(), ["new"]);
+
+        let json = serde_json::to_string(&ledger).unwrap();
+        assert_eq!(serde_json::from_str::<UsageLedger>(&json).unwrap(), ledger);
+    }
+
+    #[test]
+    fn test_period_starts() {
+        let zone = FixedOffset::east_opt(2 * 3600).unwrap();
+        // Thursday 2026-10-15 09:30 at +02:00
+        let now = zone.with_ymd_and_hms(2026, 10, 15, 9, 30, 0).unwrap();
+        let (day, week) = period_starts(&now);
+        let monday = zone.with_ymd_and_hms(2026, 10, 12, 0, 0, 0).unwrap();
+        let midnight = zone.with_ymd_and_hms(2026, 10, 15, 0, 0, 0).unwrap();
+        assert_eq!(day, midnight.timestamp() as u64);
+        assert_eq!(week, monday.timestamp() as u64);
+
+        // On a Monday both start at midnight
+        let (day, week) = period_starts(&monday);
+        assert_eq!(day, week);
+    }
+
+    #[test]
+    fn test_format() {
+        assert_eq!(format_tokens(999), "999");
+        assert_eq!(format_tokens(12_345), "12.3k");
+        assert_eq!(format_tokens(2_500_000), "2.5M");
+        assert_eq!(format_cost(1.234), "$1.23");
+    }
+}
@@ -0,0 +1,114 @@
+use anyhow::Result;
+use std::time::Duration;
+
+use crate::commands::find_session;
+use crate::tmux::{AgentStatus, Sessions, TmuxSession};
+
+/// How a wait ended
+#[derive(Debug, Clone)]
+pub enum WaitOutcome {
+    /// The session reached one of the statuses
+    Reached(TmuxSession),
+    /// Time ran out; the session as last seen
+    TimedOut(TmuxSession),
+    /// The session doesn't exist (or stopped existing)
+    Gone,
+}
+
+/// Poll `name` every `interval` until its status is one of `targets`;
+/// `timeout` of `None` waits indefinitely
+pub async fn wait_for_status<S: Sessions>(
+    sessions: &S,
+    name: &str,
+    targets: &[AgentStatus],
+    timeout: Option<Duration>,
+    interval: Duration,
+) -> Result<WaitOutcome> {
+    let deadline = timeout.map(|t| tokio::time::Instant::now() + t);
+    loop {
+        let listed = sessions.list_sessions().await?;
+        let Some(session) = find_session(&listed, name) else {
+            return Ok(WaitOutcome::Gone);
+        };
+        if targets.contains(&session.status) {
+            return Ok(WaitOutcome::Reached(session.clone()));
+        }
+        let next = tokio::time::Instant::now() + interval;
+        match deadline {
+            Some(deadline) if deadline <= tokio::time::Instant::now() => {
+                return Ok(WaitOutcome::TimedOut(session.clone()));
+            }
+            Some(deadline) => tokio::time::sleep_until(next.min(deadline)).await,
+            None => tokio::time::sleep_until(next).await,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tmux::fake::FakeSessions;
+
+    const TICK: Duration = Duration::from_millis(1);
+
+    #[tokio::test]
+    async fn test_reaches_status() {
+        let sessions = FakeSessions::with(&["api"]);
+        *sessions.statuses.lock().unwrap() =
+            vec![AgentStatus::Busy, AgentStatus::Busy, AgentStatus::Idle];
+        let outcome = wait_for_status(&sessions, "api", &[AgentStatus::Idle], None, TICK)
+            .await
+            .unwrap();
+        assert!(matches!(outcome, WaitOutcome::Reached(s) if s.status == AgentStatus::Idle));
+        assert!(sessions.statuses.lock().unwrap().is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_times_out() {
+        let sessions = FakeSessions::with(&["api"]);
+        *sessions.statuses.lock().unwrap() = vec![AgentStatus::Busy];
+        let started = std::time::Instant::now();
+        let outcome = wait_for_status(
+            &sessions,
+            "api",
+            &[AgentStatus::Error],
+            Some(Duration::from_millis(50)),
+            Duration::from_millis(10),
+        )
+        .await
+        .unwrap();
+        assert!(matches!(outcome, WaitOutcome::TimedOut(s) if s.status == AgentStatus::Busy));
+        assert!(started.elapsed() >= Duration::from_millis(50));
+
+        // A zero timeout checks exactly once
+        let outcome = wait_for_status(
+            &sessions,
+            "api",
+            &[AgentStatus::Busy],
+            Some(Duration::ZERO),
+            TICK,
+        )
+        .await
+        .unwrap();
+        assert!(matches!(outcome, WaitOutcome::Reached(_)));
+    }
+
+    #[tokio::test]
+    async fn test_session_gone() {
+        let sessions = FakeSessions::with(&["api"]);
+        let outcome = wait_for_status(&sessions, "web", &[AgentStatus::Idle], None, TICK)
+            .await
+            .unwrap();
+        assert!(matches!(outcome, WaitOutcome::Gone));
+
+        // Killed while waiting
+        *sessions.statuses.lock().unwrap() = vec![AgentStatus::Busy];
+        let wait = wait_for_status(&sessions, "api", &[AgentStatus::Error], None, TICK);
+        let kill = async {
+            tokio::time::sleep(Duration::from_millis(20)).await;
+            sessions.sessions.lock().unwrap().clear();
+        };
+        let (outcome, ()) = tokio::join!(wait, kill);
+        assert!(matches!(outcome.unwrap(), WaitOutcome::Gone));
+    }
+}
@@ -0,0 +1,310 @@
+//! Git worktrees created together with the session that works in them.
+//!
+//! The worktree comes first: the session is only created once git has
+//! succeeded, and a session that then fails to start takes its new worktree
+//! (and the branch, if it was new) with it.
+
+use anyhow::{Context, Result, bail};
+use serde::{Deserialize, Serialize};
+use std::path::{Path, PathBuf};
+use tokio::process::Command;
+
+use crate::tmux::{TmuxClient, TmuxSession};
+
+/// `[worktrees]` section of the config
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct WorktreeConfig {
+    /// Directory new worktrees go in; `<repo>-worktrees` next to the repository when unset
+    pub base: Option<PathBuf>,
+    /// Repository to branch from when the selected session isn't in one
+    pub repo: Option<PathBuf>,
+}
+
+/// A worktree made for a session
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Worktree {
+    /// Top level of the repository it was added to
+    pub repo: PathBuf,
+    pub path: PathBuf,
+    pub branch: String,
+    /// The branch was created along with the worktree
+    #[serde(default)]
+    pub new_branch: bool,
+}
+
+/// What the dashboard asks for: a branch, where to branch from, and the session
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct WorktreeRequest {
+    pub branch: String,
+    /// Any directory inside the repository
+    pub repo: PathBuf,
+    pub base: Option<PathBuf>,
+    pub session: String,
+}
+
+/// Session name for a branch; tmux doesn't allow `.` or `:` and `/` reads badly
+pub fn session_name(branch: &str) -> String {
+    branch
+        .trim()
+        .chars()
+        .map(|c| if matches!(c, '/' | '.' | ':') { '-' } else { c })
+        .collect()
+}
+
+/// `~/src` with the home directory filled in
+pub fn expand_home(path: &Path) -> PathBuf {
+    match path.strip_prefix("~") {
+        Ok(rest) => dirs::home_dir().unwrap_or_default().join(rest),
+        Err(_) => path.to_path_buf(),
+    }
+}
+
+/// `~/src/api` gets its worktrees in `~/src/api-worktrees`
+pub fn default_base(root: &Path) -> PathBuf {
+    let name = root
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .unwrap_or_else(|| "repo".to_string());
+    root.with_file_name(format!("{}-worktrees", name))
+}
+
+async fn git(dir: &Path, args: &[&str]) -> Result<String> {
+    let output = Command::new("git")
+        .arg("-C")
+        .arg(dir)
+        .args(args)
+        .output()
+        .await
+        .context("Failed to run git")?;
+    if !output.status.success() {
+        bail!(
+            "git {} failed: {}",
+            args.first().unwrap_or(&""),
+            String::from_utf8_lossy(&output.stderr).trim()
+        );
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).trim().to_string())
+}
+
+/// Top level of the repository (or worktree) containing `dir`
+pub async fn repo_root(dir: &Path) -> Result<PathBuf> {
+    let root = git(dir, &["rev-parse", "--show-toplevel"])
+        .await
+        .with_context(|| format!("{} isn't in a git repository", dir.display()))?;
+    Ok(PathBuf::from(root))
+}
+
+async fn branch_exists(root: &Path, branch: &str) -> bool {
+    let reference = format!("refs/heads/{}", branch);
+    git(root, &["show-ref", "--verify", "--quiet", &reference])
+        .await
+        .is_ok()
+}
+
+/// Add a worktree for `branch` under `base`, creating the branch if it doesn't exist
+pub async fn add(repo: &Path, base: Option<&Path>, branch: &str) -> Result<Worktree> {
+    let branch = branch.trim();
+    git(repo, &["check-ref-format", "--branch", branch])
+        .await
+        .with_context(|| format!("'{}' isn't a valid branch name", branch))?;
+    let root = repo_root(repo).await?;
+    let base = base.map_or_else(|| default_base(&root), expand_home);
+    let path = base.join(branch);
+    if path.exists() {
+        bail!("{} already exists", path.display());
+    }
+    let target = path.to_string_lossy();
+    let new_branch = !branch_exists(&root, branch).await;
+    if new_branch {
+        git(&root, &["worktree", "add", &target, "-b", branch]).await?;
+    } else {
+        git(&root, &["worktree", "add", &target, branch]).await?;
+    }
+    Ok(Worktree {
+        repo: root,
+        path,
+        branch: branch.to_string(),
+        new_branch,
+    })
+}
+
+/// Remove the worktree; git refuses if it has uncommitted changes
+pub async fn remove(worktree: &Worktree) -> Result<()> {
+    git(
+        &worktree.repo,
+        &["worktree", "remove", &worktree.path.to_string_lossy()],
+    )
+    .await
+    .with_context(|| format!("Failed to remove worktree {}", worktree.path.display()))?;
+    Ok(())
+}
+
+/// Take back a worktree whose session never started, with its new branch
+async fn undo(worktree: &Worktree) -> Result<()> {
+    remove(worktree).await?;
+    if worktree.new_branch {
+        git(&worktree.repo, &["branch", "-D", &worktree.branch]).await?;
+    }
+    Ok(())
+}
+
+/// Add the worktree, then start the session in it running `command`
+pub async fn create_session(
+    client: &TmuxClient,
+    request: &WorktreeRequest,
+    command: Option<&str>,
+) -> Result<(TmuxSession, Worktree)> {
+    // Checked up front so a taken name doesn't cost a worktree
+    if client
+        .session_names()
+        .await
+        .is_ok_and(|names| names.contains(&request.session))
+    {
+        bail!("A session named '{}' already exists", request.session);
+    }
+    let worktree = add(&request.repo, request.base.as_deref(), &request.branch).await?;
+    match client
+        .create_session_with(&request.session, Some(&worktree.path), command)
+        .await
+    {
+        Ok(session) => Ok((session, worktree)),
+        Err(e) => {
+            if let Err(undo_error) = undo(&worktree).await {
+                tracing::warn!(
+                    "Left worktree {} behind: {:#}",
+                    worktree.path.display(),
+                    undo_error
+                );
+            }
+            Err(anyhow::Error::from(e).context(format!(
+                "Failed to create session '{}' in the new worktree",
+                request.session
+            )))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    /// A repository with one commit in a fresh temp directory
+    async fn temp_repo(name: &str) -> PathBuf {
+        let dir = std::env::temp_dir().join(format!(
+            "agent-rusty-worktree-{}-{}",
+            name,
+            std::process::id()
+        ));
+        let _ = std::fs::remove_dir_all(&dir);
+        let repo = dir.join("api");
+        std::fs::create_dir_all(repo.join("src")).unwrap();
+        git(&repo, &["init", "-q"]).await.unwrap();
+        git(
+            &repo,
+            &[
+                "-c",
+                "user.name=Test",
+                "-c",
+                "user.email=test@example.com",
+                "commit",
+                "-q",
+                "--allow-empty",
+                "-m",
+                "init",
+            ],
+        )
+        .await
+        .unwrap();
+        repo
+    }
+
+    #[test]
+    fn test_names_and_paths() {
+        assert_eq!(session_name(" feat/login.v2 "), "feat-login-v2");
+        assert_eq!(
+            default_base(Path::new("/home/dev/src/api")),
+            Path::new("/home/dev/src/api-worktrees")
+        );
+        if let Some(home) = dirs::home_dir() {
+            assert_eq!(expand_home(Path::new("~/wt")), home.join("wt"));
+        }
+        assert_eq!(expand_home(Path::new("/wt")), Path::new("/wt"));
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove() {
+        let repo = temp_repo("add").await;
+        // From a subdirectory, with the default base
+        let worktree = add(&repo.join("src"), None, "feat/login").await.unwrap();
+        let root = repo_root(&repo).await.unwrap();
+        assert_eq!(worktree.repo, root);
+        assert_eq!(
+            worktree.path,
+            root.with_file_name("api-worktrees").join("feat/login")
+        );
+        assert!(worktree.new_branch);
+        assert_eq!(
+            git(&worktree.path, &["branch", "--show-current"])
+                .await
+                .unwrap(),
+            "feat/login"
+        );
+
+        // The same branch can't be checked out twice
+        let error = add(&repo, None, "feat/login").await.unwrap_err();
+        assert!(
+            format!("{:#}", error).contains("already exists"),
+            "{:#}",
+            error
+        );
+
+        // An existing branch is checked out rather than created
+        git(&repo, &["branch", "fix/typo"]).await.unwrap();
+        let base = repo.parent().unwrap().join("elsewhere");
+        let existing = add(&repo, Some(&base), "fix/typo").await.unwrap();
+        assert!(!existing.new_branch);
+        assert_eq!(existing.path, base.join("fix/typo"));
+
+        remove(&worktree).await.unwrap();
+        assert!(!worktree.path.exists());
+        // The branch stays for the work on it
+        assert!(branch_exists(&root, "feat/login").await);
+
+        // Uncommitted changes make git refuse
+        std::fs::write(existing.path.join("notes.txt"), "wip").unwrap();
+        git(&existing.path, &["add", "notes.txt"]).await.unwrap();
+        assert!(remove(&existing).await.is_err());
+        assert!(existing.path.exists());
+
+        std::fs::remove_dir_all(repo.parent().unwrap()).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_git_failures() {
+        let repo = temp_repo("fail").await;
+        let error = add(&repo, None, "bad name").await.unwrap_err();
+        assert!(
+            format!("{:#}", error).contains("valid branch name"),
+            "{:#}",
+            error
+        );
+
+        let outside = repo.parent().unwrap();
+        let error = add(outside, None, "feat/x").await.unwrap_err();
+        assert!(
+            format!("{:#}", error).contains("isn't in a git repository"),
+            "{:#}",
+            error
+        );
+        assert!(!outside.join("api-worktrees").exists());
+
+        // Undoing a worktree also drops the branch it created
+        let worktree = add(&repo, None, "feat/undo").await.unwrap();
+        undo(&worktree).await.unwrap();
+        assert!(!worktree.path.exists());
+        assert!(!branch_exists(&worktree.repo, "feat/undo").await);
+
+        std::fs::remove_dir_all(outside).unwrap();
+    }
+}
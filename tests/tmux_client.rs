@@ -0,0 +1,659 @@
+//! `TmuxClient` against a fake tmux that replays canned scenarios
+#![cfg(unix)]
+
+use agent_rusty::doctor;
+use agent_rusty::error::AgentRustyError;
+use agent_rusty::snapshot;
+use agent_rusty::tmux::{
+    AgentStatus, CapturePlan, Socket, StatusCache, TitleRule, TmuxClient, window_target,
+};
+use std::os::unix::fs::PermissionsExt;
+use std::path::PathBuf;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
+
+/// Canned replies, keyed by `$SCENARIO:<tmux subcommand>`; `$SERVER` is the
+/// socket's name, for scenarios where each server answers differently
+const FAKE_TMUX: &str = r#"
+printf '%s\n' "$*" >> "$LOG"
+SERVER=default
+case "$1" in -L|-S) SERVER=${2##*/}; shift 2 ;; esac
+case "$SCENARIO:$1" in
+list:list-sessions)
+    printf '%s\n' '$0|api|1700000000|1|1700000100|claude|/work/api' '$1|web|1700000001|0|1700000002|zsh|/work/a|b'
+    ;;
+list:capture-pane|titles:capture-pane)
+    [ "$4" = '$0' ] && printf 'Thinking...\n' || printf '$ \n'
+    ;;
+titles:list-sessions)
+    printf '$0|api|1700000000|1|1700000100|claude|/work/api\t3\t/work/api\t"claude --model opus"\tbuild01\t✳ Fix | login\n'
+    printf '$1|web|1700000001|0|1700000002|zsh|/work/web/src\t1\t/work/web\t\tbuild01\tbuild01.example.com\n'
+    ;;
+windows:list-windows)
+    printf '0\t0\t1\tnode\tclaude\n1\t1\t2\ttail\tlogs\tmain\n2\t0\t1\tzsh\t\n'
+    ;;
+servers:list-sessions)
+    # Both servers have a session '$0' named api
+    case "$SERVER" in
+    work) printf '%s\n' '$0|api|1700000000|1|1700000100|claude|/work/api' '$1|web|1700000001|0|1700000002|zsh|/work/web' ;;
+    personal) printf '%s\n' '$0|api|1700000000|0|1700000100|claude|/home/api' ;;
+    esac
+    ;;
+servers:capture-pane)
+    [ "$SERVER" = work ] && printf 'Thinking...\n' || printf '$ \n'
+    ;;
+servers:list-panes)
+    # Snapshot format, fields split by the unit separator
+    case "$SERVER" in
+    work) printf 'api\0370\037agent\037L1\037/work/api\037\037claude\n' ;;
+    personal)
+        printf 'api\0370\037agent\037L1\037/home/api\037\037claude\n'
+        printf 'api\0371\037logs\037L2\037/var/log\037\037zsh\n'
+        printf 'api\0371\037logs\037L2\037/tmp\037\037zsh\n'
+        ;;
+    esac
+    ;;
+servers:new-window|servers:split-window)
+    echo '%5'
+    ;;
+servers:load-buffer)
+    cat > /dev/null
+    ;;
+version:-V|info:-V)
+    echo 'tmux 3.4'
+    ;;
+info:list-windows)
+    case "$SERVER" in
+    work) printf '/tmp/tmux-1000/work\t$0\n/tmp/tmux-1000/work\t$0\n/tmp/tmux-1000/work\t$3\n' ;;
+    personal) echo 'no server running on /tmp/sockets/personal' >&2; exit 1 ;;
+    *) echo 'error connecting to /tmp/tmux-1000/gone (No such file or directory)' >&2; exit 1 ;;
+    esac
+    ;;
+clients:list-clients)
+    # Only '$0' has anyone attached; the last line is cut off
+    if [ "$3" = '$0' ]; then
+        printf '%s\n' '/dev/pts/3|212x50|xterm-256color' '/dev/pts/7|80x24|screen' '/dev/pts/9|80'
+    fi
+    ;;
+cwd:display-message)
+    echo '/work/api/src'
+    ;;
+names:list-sessions)
+    printf '%s\n' api 'my agent'
+    ;;
+no-server:*)
+    echo 'no server running on /tmp/tmux-1000/default' >&2
+    exit 1
+    ;;
+denied:*)
+    echo 'error connecting to /tmp/tmux-0/default (Permission denied)' >&2
+    exit 1
+    ;;
+malformed:list-sessions)
+    printf '%s\n' 'garbage' '' '$2|ok|not-a-number|1'
+    ;;
+duplicate:new-session)
+    echo 'duplicate session: api' >&2
+    exit 1
+    ;;
+duplicate:rename-session)
+    echo 'duplicate session: web' >&2
+    exit 1
+    ;;
+missing:kill-session)
+    echo "can't find session: nope" >&2
+    exit 1
+    ;;
+capture:capture-pane)
+    printf 'first line\n\033[1msecond\033[0m line\n'
+    ;;
+slow:*)
+    sleep 5
+    ;;
+esac
+"#;
+
+/// A fake tmux binary in its own directory, logging each invocation's argv
+struct FakeTmux {
+    dir: PathBuf,
+}
+
+impl FakeTmux {
+    fn new(scenario: &str) -> Self {
+        static NEXT: AtomicUsize = AtomicUsize::new(0);
+        let dir = std::env::temp_dir().join(format!(
+            "agent-rusty-fake-tmux-{}-{}",
+            std::process::id(),
+            NEXT.fetch_add(1, Ordering::Relaxed)
+        ));
+        std::fs::create_dir_all(&dir).unwrap();
+        let script = format!(
+            "#!/bin/sh\nSCENARIO='{}'\nLOG='{}'\n{}",
+            scenario,
+            dir.join("argv").display(),
+            FAKE_TMUX
+        );
+        let path = dir.join("tmux");
+        std::fs::write(&path, script).unwrap();
+        std::fs::set_permissions(&path, std::fs::Permissions::from_mode(0o755)).unwrap();
+        Self { dir }
+    }
+
+    fn client(&self) -> TmuxClient {
+        TmuxClient::with_options(&self.dir.join("tmux").to_string_lossy(), None)
+    }
+
+    /// Arguments of every invocation so far, one string per call
+    fn calls(&self) -> Vec<String> {
+        std::fs::read_to_string(self.dir.join("argv"))
+            .unwrap_or_default()
+            .lines()
+            .map(str::to_string)
+            .collect()
+    }
+}
+
+impl Drop for FakeTmux {
+    fn drop(&mut self) {
+        let _ = std::fs::remove_dir_all(&self.dir);
+    }
+}
+
+#[tokio::test]
+async fn test_list_sessions() {
+    let tmux = FakeTmux::new("list");
+    let sessions = tmux.client().list_sessions().await.unwrap();
+
+    assert_eq!(sessions.len(), 2);
+    assert_eq!(sessions[0].id, "$0");
+    assert_eq!(sessions[0].name, "api");
+    assert_eq!(sessions[0].created_at, 1700000000);
+    assert_eq!(sessions[0].attached_clients, 1);
+    assert_eq!(sessions[0].activity_at, 1700000100);
+    assert_eq!(sessions[0].current_command, "claude");
+    assert_eq!(sessions[0].status, AgentStatus::Busy);
+    // A '|' in the path survives
+    assert_eq!(sessions[1].current_path, "/work/a|b");
+    assert_eq!(sessions[1].status, AgentStatus::Idle);
+
+    let calls = tmux.calls();
+    assert!(calls[0].starts_with("list-sessions -F #{session_id}|#{session_name}|"));
+    assert_eq!(
+        calls[1..],
+        ["capture-pane -p -t $0", "capture-pane -p -t $1"]
+    );
+}
+
+#[tokio::test]
+async fn test_quiet_sessions_are_not_recaptured() {
+    let tmux = FakeTmux::new("list");
+    let client = tmux.client();
+    let mut cache = StatusCache::new(Duration::from_secs(60));
+    let mut polls = Vec::new();
+    for selected in [None, None, None, Some("$1")] {
+        let before = tmux.calls().len();
+        let plan = CapturePlan {
+            force: selected.into_iter().map(String::from).collect(),
+            ..CapturePlan::default()
+        };
+        let sessions = client
+            .list_sessions_cached(Some(&mut cache), &plan)
+            .await
+            .unwrap();
+        assert_eq!(sessions[0].status, AgentStatus::Busy);
+        assert_eq!(sessions[1].status, AgentStatus::Idle);
+        polls.push(tmux.calls().len() - before);
+    }
+    // Only the first poll and the selected session need capture-pane
+    assert_eq!(polls, [3, 1, 1, 2]);
+    assert_eq!(tmux.calls().last().unwrap(), "capture-pane -p -t $1");
+
+    // Without a cache every poll captures every pane
+    let before = tmux.calls().len();
+    client.list_sessions().await.unwrap();
+    assert_eq!(tmux.calls().len() - before, 3);
+
+    // Sessions that aren't due keep their last status even once it expires
+    let mut cache = StatusCache::new(Duration::ZERO);
+    let plan = CapturePlan {
+        skip: ["$0".to_string()].into(),
+        ..CapturePlan::default()
+    };
+    client
+        .list_sessions_cached(Some(&mut cache), &CapturePlan::default())
+        .await
+        .unwrap();
+    let before = tmux.calls().len();
+    let sessions = client
+        .list_sessions_cached(Some(&mut cache), &plan)
+        .await
+        .unwrap();
+    assert_eq!(sessions[0].status, AgentStatus::Busy);
+    assert_eq!(tmux.calls()[before + 1..], ["capture-pane -p -t $1"]);
+}
+
+#[tokio::test]
+async fn test_titled_sessions_skip_capture() {
+    let tmux = FakeTmux::new("titles");
+    let rules = vec![TitleRule::new(r"^✳", AgentStatus::Idle).unwrap()];
+    let sessions = tmux
+        .client()
+        .with_title_rules(rules)
+        .list_sessions()
+        .await
+        .unwrap();
+
+    // The title decides api's status even though its pane reads as busy
+    assert_eq!(sessions[0].pane_title, "✳ Fix | login");
+    assert_eq!(sessions[0].current_path, "/work/api");
+    assert_eq!(sessions[0].status, AgentStatus::Idle);
+    // Start command and directory come unquoted; a default shell has no start command
+    assert_eq!(sessions[0].start_command, "claude --model opus");
+    assert_eq!(sessions[1].start_command, "");
+    assert_eq!(sessions[1].start_path, "/work/web");
+    assert_eq!(sessions[1].current_path, "/work/web/src");
+    assert_eq!((sessions[0].windows, sessions[1].windows), (3, 1));
+    // A title that is just the host name is dropped
+    assert_eq!(sessions[1].pane_title, "");
+    assert_eq!(sessions[1].status, AgentStatus::Idle);
+    assert_eq!(tmux.calls()[1..], ["capture-pane -p -t $1"]);
+
+    // Title-decided statuses are cached like captured ones
+    let tmux = FakeTmux::new("titles");
+    let mut cache = StatusCache::new(Duration::from_secs(60));
+    let client = tmux
+        .client()
+        .with_title_rules(vec![TitleRule::new(r"^✳", AgentStatus::Idle).unwrap()]);
+    client
+        .list_sessions_cached(Some(&mut cache), &CapturePlan::default())
+        .await
+        .unwrap();
+    assert_eq!(cache.last("$0"), Some(AgentStatus::Idle));
+
+    // Without rules the title is shown but every pane is captured
+    let tmux = FakeTmux::new("titles");
+    let sessions = tmux.client().list_sessions().await.unwrap();
+    assert_eq!(sessions[0].status, AgentStatus::Busy);
+    assert_eq!(tmux.calls().len(), 3);
+}
+
+#[tokio::test]
+async fn test_windows() {
+    let tmux = FakeTmux::new("windows");
+    let client = tmux.client();
+    let windows = client.list_windows("$0").await.unwrap();
+
+    assert_eq!(windows.len(), 3);
+    assert_eq!(windows[0].name, "claude");
+    assert_eq!(windows[0].command, "node");
+    assert!(!windows[0].active);
+    // A tab in the name survives
+    assert_eq!(windows[1].name, "logs\tmain");
+    assert_eq!((windows[1].index, windows[1].panes), (1, 2));
+    assert_eq!(windows[1].command, "tail");
+    assert!(windows[1].active);
+    // So does an empty name
+    assert_eq!(
+        (windows[2].name.as_str(), windows[2].command.as_str()),
+        ("", "zsh")
+    );
+
+    client
+        .select_window(&window_target("$0", windows[0].index))
+        .await
+        .unwrap();
+    assert_eq!(
+        tmux.calls(),
+        [
+            "list-windows -t $0 -F #{window_index}\t#{window_active}\t#{window_panes}\t#{pane_current_command}\t#{window_name}",
+            "select-window -t $0:0",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_socket_name_selects_server() {
+    let tmux = FakeTmux::new("list");
+    let client = TmuxClient::with_options(&tmux.dir.join("tmux").to_string_lossy(), Some("work"));
+    assert_eq!(client.list_sessions().await.unwrap().len(), 2);
+    assert!(tmux.calls().iter().all(|call| call.starts_with("-L work ")));
+}
+
+#[tokio::test]
+async fn test_sockets_route_to_their_server() {
+    let tmux = FakeTmux::new("servers");
+    let client = tmux.client().with_sockets(vec![
+        Socket::parse("work"),
+        Socket::parse("/tmp/sockets/personal"),
+    ]);
+    let sessions = client.list_sessions().await.unwrap();
+
+    // Ids are told apart by server even where tmux gave both the same
+    let ids: Vec<(&str, &str, &str)> = sessions
+        .iter()
+        .map(|s| (s.id.as_str(), s.name.as_str(), s.socket.as_str()))
+        .collect();
+    assert_eq!(
+        ids,
+        [
+            ("work/$0", "api", "work"),
+            ("work/$1", "web", "work"),
+            ("personal/$0", "api", "personal"),
+        ]
+    );
+    // Each pane was captured on its own server
+    assert_eq!(sessions[0].status, AgentStatus::Busy);
+    assert_ne!(sessions[2].status, AgentStatus::Busy);
+    let captures: Vec<String> = tmux
+        .calls()
+        .into_iter()
+        .filter(|call| call.contains("capture-pane"))
+        .collect();
+    assert_eq!(
+        captures,
+        [
+            "-L work capture-pane -p -t $0",
+            "-L work capture-pane -p -t $1",
+            "-S /tmp/sockets/personal capture-pane -p -t $0",
+        ]
+    );
+
+    client.send_keys("personal/$0", "hi").await.unwrap();
+    client.kill_session("work/$1").await.unwrap();
+    client
+        .select_window(&window_target("personal/$0", 2))
+        .await
+        .unwrap();
+    let calls = tmux.calls();
+    assert_eq!(
+        calls[calls.len() - 4..],
+        [
+            "-S /tmp/sockets/personal send-keys -t $0 -l -- hi",
+            "-S /tmp/sockets/personal send-keys -t $0 Enter",
+            "-L work kill-session -t $1",
+            "-S /tmp/sockets/personal select-window -t $0:2",
+        ]
+    );
+    assert_eq!(
+        client.attach_command("personal/$0", false)[1..],
+        ["-S", "/tmp/sockets/personal", "attach-session", "-t", "$0"]
+    );
+
+    // New sessions go to the first server, found there despite the same name elsewhere
+    let created = client.create_session("api").await.unwrap();
+    assert_eq!(created.id, "work/$0");
+    assert!(
+        tmux.calls()
+            .contains(&"-L work new-session -d -s api".to_string())
+    );
+
+    // A copy goes next to the session it copies
+    let copy = client
+        .create_session_beside("personal/$0", "api", None, None)
+        .await
+        .unwrap();
+    assert_eq!(copy.id, "personal/$0");
+    assert!(
+        tmux.calls()
+            .contains(&"-S /tmp/sockets/personal new-session -d -s api".to_string())
+    );
+}
+
+#[tokio::test]
+async fn test_snapshots_span_servers() {
+    let tmux = FakeTmux::new("servers");
+    let client = tmux.client().with_sockets(vec![
+        Socket::parse("work"),
+        Socket::parse("/tmp/sockets/personal"),
+    ]);
+    let snapshot = snapshot::capture(&client).await.unwrap();
+    let sessions: Vec<(&str, &str, usize)> = snapshot
+        .sessions
+        .iter()
+        .map(|s| (s.name.as_str(), s.socket.as_str(), s.windows.len()))
+        .collect();
+    assert_eq!(sessions, [("api", "work", 1), ("api", "personal", 2)]);
+
+    // Restored on its own server, down to panes named by id
+    let before = tmux.calls().len();
+    snapshot::restore(&client, &snapshot.sessions[1])
+        .await
+        .unwrap();
+    let changes: Vec<String> = tmux.calls()[before..]
+        .iter()
+        .filter(|call| !call.contains("list-sessions") && !call.contains("capture-pane"))
+        .cloned()
+        .collect();
+    assert_eq!(
+        changes,
+        [
+            "-S /tmp/sockets/personal new-session -d -s api -c /home/api",
+            "-S /tmp/sockets/personal rename-window -t $0 agent",
+            "-S /tmp/sockets/personal send-keys -t $0 -l -- claude",
+            "-S /tmp/sockets/personal send-keys -t $0 Enter",
+            "-S /tmp/sockets/personal new-window -d -P -F #{pane_id} -t $0: -n logs -c /var/log",
+            "-S /tmp/sockets/personal split-window -d -P -F #{pane_id} -t %5 -c /tmp",
+            "-S /tmp/sockets/personal select-layout -t %5 L2",
+        ]
+    );
+
+    // Copies can be pasted on either server
+    let before = tmux.calls().len();
+    client.set_buffer("hello").await.unwrap();
+    assert_eq!(
+        tmux.calls()[before..],
+        [
+            "-L work load-buffer -",
+            "-S /tmp/sockets/personal load-buffer -"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_no_server_means_no_sessions() {
+    let tmux = FakeTmux::new("no-server");
+    assert!(tmux.client().list_sessions().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_denied_socket_is_not_a_missing_server() {
+    let tmux = FakeTmux::new("denied");
+    let client = tmux.client();
+    assert!(client.list_sessions().await.is_err());
+    let check = doctor::check_server(client.session_names().await);
+    assert_eq!(check.level, doctor::Level::Fail);
+    assert!(
+        check.detail.contains("Permission denied"),
+        "{}",
+        check.detail
+    );
+}
+
+#[tokio::test]
+async fn test_session_names_skip_capture() {
+    let tmux = FakeTmux::new("names");
+    assert_eq!(
+        tmux.client().session_names().await.unwrap(),
+        ["api", "my agent"]
+    );
+    assert_eq!(tmux.calls(), ["list-sessions -F #{session_name}"]);
+
+    let tmux = FakeTmux::new("no-server");
+    assert!(tmux.client().session_names().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn test_server_info() {
+    let tmux = FakeTmux::new("info");
+    let client = tmux.client().with_sockets(vec![
+        Socket::parse("work"),
+        Socket::parse("/tmp/sockets/personal"),
+        Socket::parse("gone"),
+    ]);
+    let info = client.server_info().await.unwrap();
+    assert_eq!(info.version, "tmux 3.4");
+    let servers: Vec<(&str, bool, usize, usize)> = info
+        .servers
+        .iter()
+        .map(|s| (s.socket_path.as_str(), s.running, s.sessions, s.windows))
+        .collect();
+    assert_eq!(
+        servers,
+        [
+            ("/tmp/tmux-1000/work", true, 2, 3),
+            ("/tmp/sockets/personal", false, 0, 0),
+            ("/tmp/tmux-1000/gone", false, 0, 0),
+        ]
+    );
+    assert_eq!(
+        tmux.calls()[..2],
+        [
+            "-L work -V",
+            "-L work list-windows -a -F #{socket_path}\t#{session_id}"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_session_cwd() {
+    let tmux = FakeTmux::new("cwd");
+    assert_eq!(
+        tmux.client().get_session_cwd("$0").await.unwrap(),
+        "/work/api/src"
+    );
+    assert_eq!(
+        tmux.calls(),
+        ["display-message -p -t $0 #{pane_current_path}"]
+    );
+}
+
+#[tokio::test]
+async fn test_clients() {
+    let tmux = FakeTmux::new("clients");
+    let client = tmux.client();
+    let clients = client.list_clients("$0").await.unwrap();
+    let listed: Vec<(&str, u16, u16, &str)> = clients
+        .iter()
+        .map(|c| (c.name.as_str(), c.width, c.height, c.termname.as_str()))
+        .collect();
+    assert_eq!(
+        listed,
+        [
+            ("/dev/pts/3", 212, 50, "xterm-256color"),
+            ("/dev/pts/7", 80, 24, "screen"),
+        ]
+    );
+    assert!(client.list_clients("$1").await.unwrap().is_empty());
+
+    client.detach_client("$0", "/dev/pts/7").await.unwrap();
+    assert_eq!(
+        tmux.calls(),
+        [
+            "list-clients -t $0 -F #{client_name}|#{client_width}x#{client_height}|#{client_termname}",
+            "list-clients -t $1 -F #{client_name}|#{client_width}x#{client_height}|#{client_termname}",
+            "detach-client -t /dev/pts/7",
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_version() {
+    let tmux = FakeTmux::new("version");
+    assert_eq!(tmux.client().version().await.unwrap(), "tmux 3.4");
+    assert_eq!(tmux.calls(), ["-V"]);
+}
+
+#[tokio::test]
+async fn test_malformed_lines_are_skipped() {
+    let tmux = FakeTmux::new("malformed");
+    let sessions = tmux.client().list_sessions().await.unwrap();
+    assert_eq!(sessions.len(), 1);
+    assert_eq!(sessions[0].name, "ok");
+    assert_eq!(sessions[0].created_at, 0);
+    assert_eq!(sessions[0].status, AgentStatus::Unknown);
+}
+
+#[tokio::test]
+async fn test_create_duplicate_session() {
+    let tmux = FakeTmux::new("duplicate");
+    let error = tmux
+        .client()
+        .create_session_with("api", Some("/work/api".as_ref()), Some("claude"))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, AgentRustyError::DuplicateSession(name) if name == "api"));
+    assert_eq!(tmux.calls(), ["new-session -d -s api -c /work/api claude"]);
+}
+
+#[tokio::test]
+async fn test_rename_session() {
+    let tmux = FakeTmux::new("list");
+    tmux.client().rename_session("$0", "backend").await.unwrap();
+    assert_eq!(tmux.calls(), ["rename-session -t $0 backend"]);
+
+    let tmux = FakeTmux::new("duplicate");
+    let error = tmux.client().rename_session("$0", "web").await.unwrap_err();
+    assert!(matches!(error, AgentRustyError::DuplicateSession(name) if name == "web"));
+}
+
+#[tokio::test]
+async fn test_send_text_that_looks_like_a_flag() {
+    let tmux = FakeTmux::new("list");
+    tmux.client().send_text("$0", "-h").await.unwrap();
+    assert_eq!(tmux.calls(), ["send-keys -t $0 -l -- -h"]);
+}
+
+#[tokio::test]
+async fn test_kill_missing_session() {
+    let tmux = FakeTmux::new("missing");
+    let error = tmux.client().kill_session("nope").await.unwrap_err();
+    assert!(matches!(
+        &error,
+        AgentRustyError::TmuxCommandFailed { command, stderr }
+            if command == "kill-session" && stderr == "can't find session: nope"
+    ));
+    assert_eq!(
+        error.to_string(),
+        "tmux kill-session failed: can't find session: nope"
+    );
+}
+
+#[tokio::test]
+async fn test_capture_pane() {
+    let tmux = FakeTmux::new("capture");
+    let client = tmux.client();
+    assert_eq!(
+        client.capture_pane("$3", 500).await.unwrap(),
+        "first line\n\x1b[1msecond\x1b[0m line\n"
+    );
+    client.capture_pane_with("$3", 0, true).await.unwrap();
+    assert_eq!(
+        tmux.calls(),
+        [
+            "capture-pane -p -J -S -500 -t $3",
+            "capture-pane -p -J -S -0 -t $3 -e"
+        ]
+    );
+}
+
+#[tokio::test]
+async fn test_slow_tmux_times_out() {
+    let tmux = FakeTmux::new("slow");
+    let client = tmux.client().with_timeout(Duration::from_millis(200));
+    let started = Instant::now();
+    let error = client.kill_session("$0").await.unwrap_err();
+    assert!(started.elapsed() < Duration::from_secs(2));
+    assert!(matches!(
+        error,
+        AgentRustyError::TmuxTimedOut { command, .. } if command == "kill-session"
+    ));
+}
+
+#[tokio::test]
+async fn test_missing_binary() {
+    let client = TmuxClient::with_options("/nonexistent/tmux", None);
+    assert!(matches!(
+        client.kill_session("$0").await.unwrap_err(),
+        AgentRustyError::TmuxNotFound { path } if path == "/nonexistent/tmux"
+    ));
+}